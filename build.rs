@@ -0,0 +1,46 @@
+use std::{env, path::Path, process::Command};
+
+fn main() {
+    // Embed the output of git describe, if available, so that simulation
+    // results can be traced back to the exact source that produced them. The
+    // value can be given in the LDPC_TOOLBOX_GIT_DESCRIBE environment variable
+    // (for instance by packaging scripts). Otherwise, git is only used if the
+    // crate is the root of its own git checkout, so that a crate vendored
+    // inside another repository does not record the commit of that repository.
+    println!("cargo:rerun-if-env-changed=LDPC_TOOLBOX_GIT_DESCRIBE");
+    if let Ok(describe) = env::var("LDPC_TOOLBOX_GIT_DESCRIBE") {
+        println!(
+            "cargo:rustc-env=LDPC_TOOLBOX_GIT_DESCRIBE={}",
+            describe.trim()
+        );
+        return;
+    }
+    let Ok(manifest_dir) = env::var("CARGO_MANIFEST_DIR") else {
+        return;
+    };
+    let git_dir = Path::new(&manifest_dir).join(".git");
+    if !git_dir.is_dir() {
+        return;
+    }
+    if let Ok(output) = Command::new("git")
+        .args(["describe", "--always", "--dirty", "--tags"])
+        .current_dir(&manifest_dir)
+        .env_remove("GIT_DIR")
+        .env_remove("GIT_WORK_TREE")
+        .output()
+    {
+        if output.status.success() {
+            let describe = String::from_utf8_lossy(&output.stdout);
+            println!(
+                "cargo:rustc-env=LDPC_TOOLBOX_GIT_DESCRIBE={}",
+                describe.trim()
+            );
+        }
+    }
+    for file in ["HEAD", "index"] {
+        let path = git_dir.join(file);
+        if path.exists() {
+            println!("cargo:rerun-if-changed={}", path.display());
+        }
+    }
+}
@@ -24,6 +24,8 @@ impl LdpcDecoder for ExampleDecoder {
         Err(DecoderOutput {
             codeword,
             iterations: 0,
+            corrected_bits: 0,
+            counters: None,
         })
     }
 }
@@ -13,6 +13,7 @@ void *ldpc_toolbox_decoder_ctor(const char *alist_file_path, const char *impleme
 void *ldpc_toolbox_decoder_ctor_alist_string(const char *alist, const char *implementation,
                                              const char *puncturing);  
 void ldpc_toolbox_decoder_dtor(void *decoder);
+int32_t ldpc_toolbox_decoder_set_llr_transform(void *decoder, const char *transform);
 int32_t ldpc_toolbox_decoder_decode_f64(void *decoder,
                                         uint8_t *output, size_t output_len,
                                         const double *llrs, size_t llrs_len,
@@ -22,6 +23,35 @@ int32_t ldpc_toolbox_decoder_decode_f32(void *decoder,
                                         const float *llrs, size_t llrs_len,
                                         uint32_t max_iterations);
 
+/*
+ * The decoder implementation and the maximum number of iterations can be
+ * changed without recompiling the application. The following sources are
+ * used, in order of decreasing precedence:
+ *
+ * 1. The environment variables LDPC_TOOLBOX_DECODER and
+ *    LDPC_TOOLBOX_MAX_ITERATIONS.
+ * 2. The configuration file given by the environment variable
+ *    LDPC_TOOLBOX_CONFIG, which contains lines of the form "key = value" with
+ *    the keys "decoder" and "max_iterations". Empty lines and lines starting
+ *    with '#' are ignored.
+ * 3. The implementation given to the constructor and the max_iterations given
+ *    to each decode call.
+ * 4. If the implementation given to the constructor is an empty string,
+ *    "Phif64".
+ *
+ * The configuration is read by the constructors, which return NULL if it is
+ * invalid.
+ *
+ * ldpc_toolbox_decoder_get_config() writes the NUL-terminated name of the
+ * effective decoder implementation to the implementation buffer and the
+ * maximum number of iterations set by the environment or the configuration
+ * file to max_iterations, or 0 if the max_iterations of each decode call is
+ * used. It returns 0 on success and -1 if the buffer is too small.
+ */
+int32_t ldpc_toolbox_decoder_get_config(const void *decoder,
+                                        char *implementation, size_t implementation_len,
+                                        uint32_t *max_iterations);
+
 void *ldpc_toolbox_encoder_ctor(const char *alist_file_path, const char *puncturing);
 void *ldpc_toolbox_encoder_ctor_alist_string(const char *alist, const char *puncturing);
 void ldpc_toolbox_encoder_dtor(void *encoder);
@@ -4,6 +4,7 @@ use std::{
     ffi::{c_char, CStr},
 };
 
+mod config;
 mod decoder;
 mod encoder;
 
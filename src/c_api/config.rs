@@ -0,0 +1,181 @@
+//! Decoder configuration of the C API.
+//!
+//! The decoder implementation and the maximum number of iterations used by the
+//! C API decoders can be changed through the environment, so that the
+//! implementation used by a deployed binary can be switched without
+//! recompiling the host application. The following sources are used, in order
+//! of decreasing precedence:
+//!
+//! 1. The environment variables `LDPC_TOOLBOX_DECODER` and
+//!    `LDPC_TOOLBOX_MAX_ITERATIONS`.
+//! 2. The configuration file given by the environment variable
+//!    `LDPC_TOOLBOX_CONFIG`. This contains lines of the form `key = value`,
+//!    with the keys `decoder` and `max_iterations`. Empty lines and lines
+//!    starting with `#` are ignored.
+//! 3. The implementation given to the constructor and the maximum number of
+//!    iterations given to each decode call.
+//! 4. If the implementation given to the constructor is an empty string,
+//!    [`DEFAULT_IMPLEMENTATION`].
+//!
+//! Empty environment variables are treated as if they were not set. The
+//! configuration is read when the decoder is constructed. Invalid values make
+//! the constructor fail.
+
+use crate::decoder::factory::DecoderImplementation;
+use std::error::Error;
+
+/// Decoder implementation used when none is given.
+pub const DEFAULT_IMPLEMENTATION: DecoderImplementation = DecoderImplementation::Phif64;
+
+/// Effective configuration of a C API decoder.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecoderConfig {
+    /// Decoder implementation.
+    pub implementation: DecoderImplementation,
+    /// Maximum number of iterations, if it overrides the one given to each
+    /// decode call.
+    pub max_iterations: Option<usize>,
+}
+
+// Settings given by the environment or the configuration file
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Settings {
+    decoder: Option<DecoderImplementation>,
+    max_iterations: Option<usize>,
+}
+
+impl Settings {
+    fn set(&mut self, key: &str, value: &str) -> Result<(), Box<dyn Error>> {
+        match key {
+            "decoder" => self.decoder = Some(value.parse()?),
+            "max_iterations" => {
+                let max_iterations = value.parse()?;
+                if max_iterations == 0 {
+                    return Err("the maximum number of iterations must be positive".into());
+                }
+                self.max_iterations = Some(max_iterations);
+            }
+            _ => return Err(format!("unknown configuration key {key}").into()),
+        }
+        Ok(())
+    }
+
+    fn from_file(contents: &str) -> Result<Settings, Box<dyn Error>> {
+        let mut settings = Settings::default();
+        for line in contents.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| format!("invalid configuration line {line}"))?;
+            settings.set(key.trim(), value.trim())?;
+        }
+        Ok(settings)
+    }
+
+    fn from_env(var: impl Fn(&str) -> Option<String>) -> Result<Settings, Box<dyn Error>> {
+        let mut settings = match var("LDPC_TOOLBOX_CONFIG") {
+            Some(path) => Settings::from_file(&std::fs::read_to_string(path)?)?,
+            None => Settings::default(),
+        };
+        if let Some(decoder) = var("LDPC_TOOLBOX_DECODER") {
+            settings.set("decoder", &decoder)?;
+        }
+        if let Some(max_iterations) = var("LDPC_TOOLBOX_MAX_ITERATIONS") {
+            settings.set("max_iterations", &max_iterations)?;
+        }
+        Ok(settings)
+    }
+}
+
+impl DecoderConfig {
+    /// Returns the effective configuration for a decoder constructed with a
+    /// given implementation.
+    pub fn new(implementation: &str) -> Result<DecoderConfig, Box<dyn Error>> {
+        DecoderConfig::with_env(implementation, |name| {
+            std::env::var(name).ok().filter(|v| !v.is_empty())
+        })
+    }
+
+    fn with_env(
+        implementation: &str,
+        var: impl Fn(&str) -> Option<String>,
+    ) -> Result<DecoderConfig, Box<dyn Error>> {
+        let settings = Settings::from_env(var)?;
+        let implementation = match settings.decoder {
+            Some(decoder) => decoder,
+            None if implementation.is_empty() => DEFAULT_IMPLEMENTATION,
+            None => implementation.parse()?,
+        };
+        Ok(DecoderConfig {
+            implementation,
+            max_iterations: settings.max_iterations,
+        })
+    }
+
+    /// Returns the maximum number of iterations for a decode call with a given
+    /// maximum number of iterations.
+    pub fn max_iterations(&self, max_iterations: usize) -> usize {
+        self.max_iterations.unwrap_or(max_iterations)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn config(implementation: &str, vars: &[(&str, &str)]) -> Result<DecoderConfig, String> {
+        let vars: HashMap<_, _> = vars.iter().copied().collect();
+        DecoderConfig::with_env(implementation, |name| vars.get(name).map(|v| v.to_string()))
+            .map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn precedence() {
+        assert_eq!(
+            config("", &[]).unwrap(),
+            DecoderConfig {
+                implementation: DEFAULT_IMPLEMENTATION,
+                max_iterations: None,
+            }
+        );
+        let conf = config("Aminstarf32", &[]).unwrap();
+        assert_eq!(conf.implementation, DecoderImplementation::Aminstarf32);
+        assert_eq!(conf.max_iterations(25), 25);
+
+        let dir = std::env::temp_dir().join(format!("ldpc-toolbox-c-api-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("config");
+        std::fs::write(
+            &file,
+            "# defaults\ndecoder = Phif32\n\nmax_iterations = 50\n",
+        )
+        .unwrap();
+        let file = file.to_str().unwrap();
+        let conf = config("Aminstarf32", &[("LDPC_TOOLBOX_CONFIG", file)]).unwrap();
+        assert_eq!(conf.implementation, DecoderImplementation::Phif32);
+        assert_eq!(conf.max_iterations(25), 50);
+        let conf = config(
+            "Aminstarf32",
+            &[
+                ("LDPC_TOOLBOX_CONFIG", file),
+                ("LDPC_TOOLBOX_DECODER", "Aminstari8"),
+            ],
+        )
+        .unwrap();
+        assert_eq!(conf.implementation, DecoderImplementation::Aminstari8);
+        assert_eq!(conf.max_iterations, Some(50));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(config("Aminstarf32", &[("LDPC_TOOLBOX_DECODER", "foo")]).is_err());
+        assert!(config("", &[("LDPC_TOOLBOX_MAX_ITERATIONS", "0")]).is_err());
+        assert!(config("", &[("LDPC_TOOLBOX_CONFIG", "/nonexistent/config")]).is_err());
+        assert!(Settings::from_file("iterations = 10").is_err());
+        assert!(Settings::from_file("decoder").is_err());
+    }
+}
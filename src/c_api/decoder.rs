@@ -1,11 +1,10 @@
-use super::{c_to_string, size_t_to_usize};
+use super::{c_to_string, config::DecoderConfig, size_t_to_usize};
 use crate::{
-    cli::ber::parse_puncturing_pattern,
-    decoder::{
-        factory::{DecoderFactory, DecoderImplementation},
-        LdpcDecoder,
+    decoder::{factory::DecoderFactory, LdpcDecoder},
+    simulation::{
+        llr_transform::LlrTransform,
+        puncturing::{parse_puncturing_pattern, Puncturer},
     },
-    simulation::puncturing::Puncturer,
     sparse::SparseMatrix,
 };
 use libc::size_t;
@@ -18,21 +17,31 @@ use std::{
 #[derive(Debug)]
 struct Decoder {
     decoder: Box<dyn LdpcDecoder>,
+    config: DecoderConfig,
     puncturer: Option<Puncturer>,
+    llr_transform: Option<LlrTransform>,
+    codeword: Vec<u8>,
 }
 
 impl Decoder {
     fn new(alist: &str, implementation: &str, puncturing: &str) -> Result<Decoder, Box<dyn Error>> {
         let h = SparseMatrix::from_alist(alist)?;
-        let implementation: DecoderImplementation = implementation.parse()?;
+        let config = DecoderConfig::new(implementation)?;
         let puncturing_pattern = if !puncturing.is_empty() {
             Some(parse_puncturing_pattern(puncturing)?)
         } else {
             None
         };
         let puncturer = puncturing_pattern.map(|v| Puncturer::new(&v));
-        let decoder = implementation.build_decoder(h);
-        Ok(Decoder { decoder, puncturer })
+        let codeword = vec![0; h.num_cols()];
+        let decoder = config.implementation.build_decoder(h);
+        Ok(Decoder {
+            decoder,
+            config,
+            puncturer,
+            llr_transform: None,
+            codeword,
+        })
     }
 
     fn from_alist_file(
@@ -47,25 +56,52 @@ impl Decoder {
         )
     }
 
+    fn set_llr_transform(&mut self, transform: &str) -> Result<(), Box<dyn Error>> {
+        self.llr_transform = if transform.is_empty() {
+            None
+        } else {
+            Some(transform.parse()?)
+        };
+        Ok(())
+    }
+
     fn decode_f64(&mut self, output: &mut [u8], llrs: &[f64], max_iterations: u32) -> i32 {
+        let transformed = self.llr_transform.as_ref().map(|t| {
+            let mut llrs = llrs.to_vec();
+            t.apply(&mut llrs);
+            llrs
+        });
+        let llrs = if let Some(t) = &transformed { t } else { llrs };
         let depunctured = self.puncturer.as_ref().map(|p| p.depuncture(llrs).unwrap());
         let llrs = if let Some(d) = &depunctured { d } else { llrs };
-        let res = self
-            .decoder
-            .decode(llrs, usize::try_from(max_iterations).unwrap());
-        let success = res.is_ok();
-        let decoded = match res {
-            Ok(o) => o,
-            Err(o) => o,
-        };
-        output.copy_from_slice(&decoded.codeword[..output.len()]);
-        if success {
-            i32::try_from(decoded.iterations).unwrap()
-        } else {
-            -1
+        let res = self.decoder.decode_into(
+            llrs,
+            &mut self.codeword,
+            self.config
+                .max_iterations(usize::try_from(max_iterations).unwrap()),
+        );
+        output.copy_from_slice(&self.codeword[..output.len()]);
+        match res {
+            Ok(iterations) => i32::try_from(iterations).unwrap(),
+            Err(_) => -1,
         }
     }
 
+    fn write_config(&self, implementation: &mut [u8], max_iterations: &mut u32) -> i32 {
+        let name = self.config.implementation.to_string();
+        // The name is written with a NUL terminator
+        if name.len() >= implementation.len() {
+            return -1;
+        }
+        implementation[..name.len()].copy_from_slice(name.as_bytes());
+        implementation[name.len()] = 0;
+        *max_iterations = self
+            .config
+            .max_iterations
+            .map_or(0, |n| u32::try_from(n).unwrap_or(u32::MAX));
+        0
+    }
+
     fn decode_f32(&mut self, output: &mut [u8], llrs: &[f32], max_iterations: u32) -> i32 {
         let llrs_f64 = llrs.iter().copied().map(f64::from).collect::<Vec<f64>>();
         self.decode_f64(output, &llrs_f64, max_iterations)
@@ -109,6 +145,19 @@ unsafe extern "C" fn ldpc_toolbox_decoder_dtor(decoder: *mut c_void) {
     drop(Box::from_raw(decoder as *mut Decoder));
 }
 
+#[no_mangle]
+unsafe extern "C" fn ldpc_toolbox_decoder_set_llr_transform(
+    decoder: *mut c_void,
+    transform: *const c_char,
+) -> i32 {
+    let transform = c_to_string(transform);
+    let decoder = &mut *(decoder as *mut Decoder);
+    match decoder.set_llr_transform(&transform) {
+        Ok(()) => 0,
+        Err(_) => -1,
+    }
+}
+
 #[no_mangle]
 unsafe extern "C" fn ldpc_toolbox_decoder_decode_f64(
     decoder: *mut c_void,
@@ -138,3 +187,18 @@ unsafe extern "C" fn ldpc_toolbox_decoder_decode_f32(
     let decoder = &mut *(decoder as *mut Decoder);
     decoder.decode_f32(output, llrs, max_iterations)
 }
+
+#[no_mangle]
+unsafe extern "C" fn ldpc_toolbox_decoder_get_config(
+    decoder: *const c_void,
+    implementation: *mut c_char,
+    implementation_len: size_t,
+    max_iterations: *mut u32,
+) -> i32 {
+    let implementation = std::slice::from_raw_parts_mut(
+        implementation as *mut u8,
+        size_t_to_usize(implementation_len),
+    );
+    let decoder = &*(decoder as *const Decoder);
+    decoder.write_config(implementation, &mut *max_iterations)
+}
@@ -1,7 +1,9 @@
 use super::{c_to_string, size_t_to_usize};
 use crate::{
-    cli::ber::parse_puncturing_pattern, encoder::Encoder as LdpcEncoder, gf2::GF2,
-    simulation::puncturing::Puncturer, sparse::SparseMatrix,
+    encoder::Encoder as LdpcEncoder,
+    gf2::GF2,
+    simulation::puncturing::{parse_puncturing_pattern, Puncturer},
+    sparse::SparseMatrix,
 };
 use libc::size_t;
 use ndarray::Array1;
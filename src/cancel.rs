@@ -0,0 +1,65 @@
+//! # Cooperative cancellation
+//!
+//! This module contains [`CancellationToken`], which is used to stop
+//! long-running operations, such as BER tests, code constructions and
+//! searches, from another thread. This is useful for applications that embed
+//! ldpc-toolbox, such as GUIs or servers, which need to stop an operation
+//! cleanly without killing the process.
+//!
+//! Cancellation is cooperative: the operation checks the token periodically
+//! and stops as soon as possible after it has been cancelled. Operations that
+//! can produce partial results, such as the BER test, return the results
+//! obtained up to the cancellation. Operations that cannot, such as code
+//! constructions, return an error.
+//!
+//! # Examples
+//! ```
+//! # use ldpc_toolbox::cancel::CancellationToken;
+//! let token = CancellationToken::new();
+//! let handle = std::thread::spawn({
+//!     let token = token.clone();
+//!     move || {
+//!         let mut count = 0_u64;
+//!         while !token.is_cancelled() {
+//!             count += 1;
+//!         }
+//!         count
+//!     }
+//! });
+//! token.cancel();
+//! handle.join().unwrap();
+//! assert!(token.is_cancelled());
+//! ```
+
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+/// Cancellation token.
+///
+/// The token can be cloned and shared between threads. All the clones refer
+/// to the same cancellation state, so that calling
+/// [`CancellationToken::cancel`] on any clone cancels the operations that use
+/// any of the clones.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    /// Creates a new token that has not been cancelled.
+    pub fn new() -> CancellationToken {
+        CancellationToken::default()
+    }
+
+    /// Cancels the operations that use this token.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns `true` if the token has been cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
@@ -5,17 +5,42 @@
 //! See the modules below for examples and more information about
 //! how to use each subcommand.
 
+use crate::{
+    codes::Code,
+    progress::{Progress, Reporter},
+    sparse::{
+        AlistOptions, AlistOrientation, AlistSections, AlistValidation, IndexBase, SparseMatrix,
+    },
+};
 use clap::Parser;
-use std::error::Error;
+use console::Term;
+use std::{error::Error, path::Path, sync::mpsc, time::Duration};
 
+pub mod analyze;
 pub mod ber;
 pub mod ccsds;
 pub mod ccsds_c2;
+pub mod ccsds_tc;
+pub mod convert;
 pub mod dvbs2;
+pub mod dvbs2x;
 pub mod encode;
+pub mod fa_tables;
+pub mod fetch_codes;
+pub mod gen_matrix;
 pub mod mackay_neal;
+pub mod min_sum;
 pub mod peg;
+pub mod protograph;
+pub mod quant_sweep;
+pub mod rate_compatible;
+pub mod report;
+pub mod schedule;
+pub mod serve;
 pub mod systematic;
+pub mod trace;
+pub mod verify_tables;
+pub mod wimax;
 
 /// Trait to run a CLI subcommand
 pub trait Run {
@@ -27,36 +52,226 @@ pub trait Run {
 #[derive(Debug, Parser)]
 #[command(author, version, name = "ldpc-toolbox", about = "LDPC toolbox")]
 pub enum Args {
+    /// analyze subcommand
+    Analyze(analyze::Args),
     /// ber subcommand
-    BER(ber::Args),
+    BER(Box<ber::Args>),
     /// ccsds subcommand
     CCSDS(ccsds::Args),
     /// ccsds-c2 subcommand
     #[allow(non_camel_case_types)]
     CCSDS_C2(ccsds_c2::Args),
+    /// ccsds-tc subcommand
+    #[allow(non_camel_case_types)]
+    CCSDS_TC(ccsds_tc::Args),
+    /// convert subcommand
+    Convert(convert::Args),
     /// encode subcommand
     Encode(encode::Args),
+    /// fa-tables subcommand
+    FaTables(fa_tables::Args),
+    /// fetch-codes subcommand
+    FetchCodes(fetch_codes::Args),
+    /// gen-matrix subcommand
+    GenMatrix(gen_matrix::Args),
     /// dvbs2 subcommand
     DVBS2(dvbs2::Args),
+    /// dvbs2x subcommand
+    DVBS2X(dvbs2x::Args),
     /// mackay-neal subcommand
     MackayNeal(mackay_neal::Args),
+    /// min-sum subcommand
+    MinSum(min_sum::Args),
     /// peg subcommand
     PEG(peg::Args),
+    /// protograph subcommand
+    Protograph(protograph::Args),
+    /// quant-sweep subcommand
+    QuantSweep(quant_sweep::Args),
+    /// rate-compatible subcommand
+    RateCompatible(rate_compatible::Args),
+    /// report subcommand
+    Report(report::Args),
+    /// schedule subcommand
+    Schedule(schedule::Args),
+    /// serve subcommand
+    Serve(serve::Args),
     /// systematic subcommand
     Systematic(systematic::Args),
+    /// trace subcommand
+    Trace(trace::Args),
+    /// verify-tables subcommand
+    VerifyTables(verify_tables::Args),
+    /// wimax subcommand
+    Wimax(wimax::Args),
 }
 
 impl Run for Args {
     fn run(&self) -> Result<(), Box<dyn Error>> {
         match self {
+            Args::Analyze(x) => x.run(),
             Args::BER(x) => x.run(),
             Args::CCSDS(x) => x.run(),
             Args::CCSDS_C2(x) => x.run(),
+            Args::CCSDS_TC(x) => x.run(),
+            Args::Convert(x) => x.run(),
             Args::DVBS2(x) => x.run(),
+            Args::DVBS2X(x) => x.run(),
             Args::Encode(x) => x.run(),
+            Args::FaTables(x) => x.run(),
+            Args::FetchCodes(x) => x.run(),
+            Args::GenMatrix(x) => x.run(),
             Args::MackayNeal(x) => x.run(),
+            Args::MinSum(x) => x.run(),
             Args::PEG(x) => x.run(),
+            Args::Protograph(x) => x.run(),
+            Args::QuantSweep(x) => x.run(),
+            Args::RateCompatible(x) => x.run(),
+            Args::Report(x) => x.run(),
+            Args::Schedule(x) => x.run(),
+            Args::Serve(x) => x.run(),
             Args::Systematic(x) => x.run(),
+            Args::Trace(x) => x.run(),
+            Args::VerifyTables(x) => x.run(),
+            Args::Wimax(x) => x.run(),
         }
     }
 }
+
+/// Alist output options.
+///
+/// These options are shared by the subcommands that print an alist.
+#[derive(Debug, clap::Args)]
+pub struct AlistArgs {
+    /// Omit the zeros used as padding for irregular codes
+    #[arg(long)]
+    alist_no_padding: bool,
+    /// Base of the indices in the alist ("1" or "0")
+    #[arg(long, default_value = "1")]
+    alist_index_base: IndexBase,
+    /// Order of the alist sections ("columns-first", "rows-first" or
+    /// "columns-only")
+    #[arg(long, default_value = "columns-first")]
+    alist_sections: AlistSections,
+}
+
+impl AlistArgs {
+    /// Returns the alist options given by the arguments.
+    pub fn options(&self) -> AlistOptions {
+        AlistOptions {
+            padding: !self.alist_no_padding,
+            index_base: self.alist_index_base,
+            sections: self.alist_sections,
+        }
+    }
+}
+
+/// Alist input options.
+///
+/// These options are shared by the subcommands that read a parity check matrix
+/// from an alist.
+#[derive(Debug, clap::Args)]
+pub struct AlistInputArgs {
+    /// Orientation of the matrix in the input alist ("auto", "as-is" or
+    /// "transposed")
+    #[arg(long, default_value = "auto")]
+    alist_orientation: AlistOrientation,
+    /// Handling of inconsistent weights in the input alist ("lenient" to warn or
+    /// "strict" to fail)
+    #[arg(long, default_value = "lenient")]
+    alist_validation: AlistValidation,
+}
+
+impl AlistInputArgs {
+    /// Reads a parity check matrix from an alist file.
+    ///
+    /// The matrix is transposed according to the alist orientation and the
+    /// weights are checked according to the alist validation (see
+    /// [`SparseMatrix::from_alist_with`]). Warnings about the orientation and
+    /// the weights are printed to `stderr`.
+    pub fn read(&self, path: &str) -> Result<SparseMatrix, Box<dyn Error>> {
+        let (h, warnings) = SparseMatrix::from_alist_with(
+            &std::fs::read_to_string(path)?,
+            self.alist_orientation,
+            self.alist_validation,
+        )?;
+        for warning in warnings {
+            eprintln!("warning: {path}: {warning}");
+        }
+        Ok(h)
+    }
+}
+
+/// Writes the alists of a family of codes to a directory.
+///
+/// The codes are given as pairs of code specification (see
+/// [`crate::codes::cache`]) and code. The alist of each code is written to a
+/// file whose name is obtained from the code specification by replacing `:` by
+/// `_` and `/` by `-`, so for instance the alist of `ar4ja:1/2:1024` is written
+/// to `ar4ja_1-2_1024.alist`. The directory is created if it does not exist. An
+/// `index.json` file listing the codes that have been written is also created
+/// in the directory.
+pub(crate) fn write_code_family(
+    dir: &str,
+    codes: impl IntoIterator<Item = (String, Box<dyn Code>)>,
+    options: &AlistOptions,
+) -> Result<(), Box<dyn Error>> {
+    let dir = Path::new(dir);
+    std::fs::create_dir_all(dir)?;
+    let mut index = String::from("[");
+    for (j, (spec, code)) in codes.into_iter().enumerate() {
+        let file = format!("{}.alist", spec.replace(':', "_").replace('/', "-"));
+        let h = code.h();
+        std::fs::write(dir.join(&file), h.alist_with(options))?;
+        println!("{spec}: {}", dir.join(&file).display());
+        if j != 0 {
+            index.push(',');
+        }
+        index.push_str(&format!(
+            "\n  {{\"spec\": \"{spec}\", \"name\": \"{}\", \"file\": \"{file}\", \"n\": {}, \"k\": {}, \"num_rows\": {}, \"num_cols\": {}}}",
+            code.name(),
+            code.n(),
+            code.k(),
+            h.num_rows(),
+            h.num_cols()
+        ));
+    }
+    index.push_str("\n]\n");
+    std::fs::write(dir.join("index.json"), index)?;
+    Ok(())
+}
+
+/// Runs an operation showing its progress in `stderr`.
+///
+/// The function `f` is called with a [`Reporter`] whose [`Progress`] reports
+/// are shown in `stderr` as a status line prefixed by `label`. If `stderr` is
+/// not a terminal, the progress is not shown and `f` is called with `None`.
+pub(crate) fn with_progress<R>(label: &str, f: impl FnOnce(Option<&Reporter<Progress>>) -> R) -> R {
+    let term = Term::stderr();
+    if !term.is_term() {
+        return f(None);
+    }
+    let (tx, rx) = mpsc::channel();
+    let reporter = Reporter {
+        tx,
+        interval: Duration::from_millis(200),
+    };
+    let display = std::thread::spawn({
+        let label = label.to_string();
+        move || {
+            // The loop ends when the operation finishes or the reporter is
+            // dropped.
+            while let Ok(Progress::Update { done, total, best }) = rx.recv() {
+                let percent = 100.0 * done as f64 / total.max(1) as f64;
+                let best = best.map_or(String::new(), |b| format!(", best {b}"));
+                let _ = term.clear_line();
+                let _ = term.write_str(&format!("{label}: {done}/{total} ({percent:.1}%){best}"));
+            }
+            let _ = term.clear_line();
+        }
+    });
+    let ret = f(Some(&reporter));
+    drop(reporter);
+    let _ = display.join();
+    ret
+}
@@ -12,13 +12,287 @@
 //!
 //! The alist file must have been generated previoulsy with the
 //! [ccsds](super::ccsds) subcommand.
+//!
+//! Besides the BER and FER, the results table contains their 95% confidence
+//! intervals, which can be used as error bars when plotting the results. The
+//! FER interval is the exact Clopper-Pearson interval, and the BER interval is
+//! a normal approximation (see [`crate::simulation::frame_errors`]).
+//!
+//! Besides the patterns defined by blocks, `--puncturing` accepts patterns
+//! defined per bit, such as `bits:1,1,0`, which punctures every third bit, and
+//! the standard patterns `nr:<Zc>`, which punctures the first `2 * Zc` bits as
+//! in 5G NR, and `ar4ja`, which punctures the last M bits of an AR4JA code (see
+//! [`PuncturingPattern`]). The previous simulation can also be run with
+//! ```shell
+//! $ ldpc-toolbox ber --min-ebn0 0.0 --max-ebn0 2.05 --step-ebn0 0.1 \
+//!       --puncturing ar4ja ar4ja_1_2_1024.alist
+//! ```
+//!
+//! Instead of a uniform grid of Eb/N0's, an explicit list can be given, which
+//! is useful to sample the waterfall region densely:
+//! ```shell
+//! $ ldpc-toolbox ber --ebn0 0.0,1.0,1.5,1.6,1.7,1.8,2.0 \
+//!       --puncturing 1,1,1,1,0 ar4ja_1_2_1024.alist
+//! ```
+//! The list can also be read from a file with `--ebn0-file`.
+//!
+//! A traffic mix of several codes can be simulated with `--mixture`, which
+//! takes a list of code specifications (see [`crate::codes::cache`]) with
+//! their weights. The standard puncturing of each code is used. For instance,
+//! a mix of 30% DVB-S2 short FECFRAMEs and 70% normal FECFRAMEs is simulated
+//! with
+//! ```shell
+//! $ ldpc-toolbox ber --ebn0 1.0,1.2,1.4 \
+//!       --mixture dvbs2:1/2:short=0.3,dvbs2:1/2=0.7
+//! ```
+//! All the frames go through the same channel, and the results table contains
+//! the aggregate statistics. The statistics of each code are listed after the
+//! simulation finishes. See [`crate::simulation::mixture`] for more details.
+//!
+//! Before committing to a long simulation, the configuration can be checked
+//! with a quick-look run using `--quick`. This collects at most 10 frame
+//! errors and simulates at most 1000 frames per Eb/N0, and thins the Eb/N0's so
+//! that they are at least 0.5 dB apart, producing a rough curve in a few
+//! seconds:
+//! ```shell
+//! $ ldpc-toolbox ber --quick --min-ebn0 0.0 --max-ebn0 2.05 --step-ebn0 0.1 \
+//!       --puncturing 1,1,1,1,0 ar4ja_1_2_1024.alist
+//! ```
+//! The number of frames per Eb/N0 can also be limited in a regular run with
+//! `--max-frames`.
+//!
+//! Pressing ctrl-c stops the simulation cleanly. The statistics obtained so
+//! far, including those of the Eb/N0 that was being simulated, are written to
+//! the output files. Pressing ctrl-c a second time exits immediately.
+//!
+//! The results can be compared with a reference curve bundled with
+//! ldpc-toolbox (see [`crate::simulation::reference_curves`]) using
+//! `--compare-reference`, which takes the code specification of the reference.
+//! When the simulation finishes, a table with the BER and FER of the reference
+//! and the Eb/N0 gap between the simulation and the reference at the same FER
+//! is printed. For instance,
+//! ```shell
+//! $ ldpc-toolbox ber --ebn0 1.0,1.5,2.0 --puncturing 1,1,1,1,0 \
+//!       --compare-reference ar4ja:1/2:1024 ar4ja_1_2_1024.alist
+//! ```
+//! Gaps larger than about 0.1 dB indicate a deviation from the known-good
+//! results.
+//!
+//! The modulation is selected with `--modulation`, which supports BPSK, QPSK,
+//! 8PSK and 16APSK with the DVB-S2 constellations. The ring ratio of 16APSK is
+//! given with `--apsk16-ring-ratio` (see
+//! [`crate::simulation::modulation::dvbs2_apsk16_ring_ratio`] for the ring
+//! ratios used in DVB-S2). For instance, the DVB-S2 16APSK 3/4 MODCOD with short
+//! FECFRAMEs is simulated with
+//! ```shell
+//! $ ldpc-toolbox ber --ebn0 5.0,5.5,6.0 --modulation 16APSK \
+//!       --apsk16-ring-ratio 2.85 dvbs2:3/4:short
+//! ```
+//!
+//! The channel is AWGN by default. A Rayleigh flat fading channel is selected
+//! with `--channel rayleigh`, which uses an independent fading gain for each
+//! symbol. Block fading and fading with a Jakes Doppler spectrum are selected
+//! with `--channel rayleigh-block-<symbols>` and
+//! `--channel rayleigh-doppler-<frequency>`, where the Doppler frequency is
+//! normalized by the symbol rate. The demodulator assumes that the fading
+//! gains are perfectly known (see [`crate::simulation::channel`]). For
+//! instance,
+//! ```shell
+//! $ ldpc-toolbox ber --min-ebn0 2.0 --max-ebn0 6.0 --step-ebn0 0.5 \
+//!       --modulation QPSK --channel rayleigh-block-64 ar4ja:1/2:1024
+//! ```
+//!
+//! The performance of a hard-decision receiver is simulated with `--channel
+//! bsc`, which is a binary symmetric channel. By default, its crossover
+//! probability is that of hard decisions on BPSK symbols at each Eb/N0. A
+//! fixed crossover probability is given with `--crossover`, in which case the
+//! channel does not depend on the Eb/N0, which is only used to label the
+//! results. The decoder receives LLRs of constant magnitude (see
+//! [`crate::simulation::modulation::HardDecisionDemodulator`]). The BSC can
+//! only be used with BPSK. For instance,
+//! ```shell
+//! $ ldpc-toolbox ber --ebn0 0.0 --channel bsc --crossover 0.02 ar4ja:1/2:1024
+//! ```
+//!
+//! An imperfect normalization of the symbols by the receiver front-end is
+//! simulated with `--impairments`, which gives an amplitude scaling, a DC
+//! offset and a per-symbol gain jitter that are not compensated by the
+//! demodulator (see [`crate::simulation::channel::Impairments`]). For instance,
+//! ```shell
+//! $ ldpc-toolbox ber --ebn0 1.0,1.5,2.0 --decoder Aminstari8 \
+//!       --impairments amplitude=0.7,gain-jitter=0.05 ar4ja:1/2:1024
+//! ```
+//!
+//! A shortened code is simulated with `--shortening`, which gives the
+//! information bits that are fixed to zero and not transmitted (see
+//! [`crate::simulation::shortening`]). The code rate and the Eb/N0 take into
+//! account the shortening. For instance, a system that only uses 900 of the
+//! 1024 information bits of the CCSDS r=1/2, k=1024 code is simulated with
+//! ```shell
+//! $ ldpc-toolbox ber --ebn0 1.0,1.5,2.0 --shortening 900-1023 ar4ja:1/2:1024
+//! ```
+//!
+//! The maximum number of iterations can depend on the Eb/N0 by means of
+//! `--max-iter-schedule` (see [`crate::simulation::iterations`]). It accepts a
+//! table of Eb/N0's and maximum iterations, which apply from each Eb/N0 up to
+//! the next one:
+//! ```shell
+//! $ ldpc-toolbox ber --min-ebn0 0.0 --max-ebn0 2.05 --step-ebn0 0.1 \
+//!       --max-iter-schedule 0.0:200,1.0:100,1.5:50 \
+//!       --puncturing 1,1,1,1,0 ar4ja_1_2_1024.alist
+//! ```
+//! With `--max-iter-schedule auto`, the maximum number of iterations of each
+//! Eb/N0 is a multiple (4 by default, or the factor given as in `auto:3`) of
+//! the average iterations of the correct frames in the previous Eb/N0, limited
+//! to `--max-iter`. The maximum number of iterations used in each Eb/N0 is
+//! shown in the results table.
+//!
+//! Similarly, the number of frame errors collected in each Eb/N0 can depend
+//! on the measured FER by means of `--frame-errors-policy` (see
+//! [`crate::simulation::frame_errors`]), which accepts a table of FERs and
+//! frame errors, limited to `--frame-errors`. With `--frame-errors-policy
+//! auto`, 100 frame errors are collected above a FER of 1e-3 and 30 frame
+//! errors at a FER of 1e-7 and below.
+//! ```shell
+//! $ ldpc-toolbox ber --min-ebn0 0.0 --max-ebn0 2.05 --step-ebn0 0.1 \
+//!       --frame-errors-policy 1e-3:100,1e-6:20 ar4ja:1/2:1024
+//! ```
+//!
+//! Adaptive coding and modulation studies need the performance under SNR
+//! dynamics. With `--snr-profile`, the Eb/N0 changes from frame to frame
+//! according to a ramp, a sinusoid or a trace file with the Eb/N0 of each
+//! frame (see [`crate::simulation::snr_profile`]). All the frames of the
+//! profile are simulated in a single run, and the results are reported for
+//! Eb/N0 bins of width `--snr-bin` dB. For instance, a sinusoidal fade of
+//! ±1.5 dB around 1.5 dB with a period of 2000 frames is simulated with
+//! ```shell
+//! $ ldpc-toolbox ber --snr-profile sine:1.5:1.5:2000:100000 --snr-bin 0.25 \
+//!       ar4ja:1/2:1024
+//! ```
+//!
+//! The LLRs computed by the demodulator can be modified by an affine transform
+//! with `--llr-transform` before decoding (see
+//! [`crate::simulation::llr_transform`]). This can be used to study the
+//! sensitivity of the decoder to errors in the estimation of the noise
+//! variance. For instance, the LLRs are scaled by 0.7 with
+//! ```shell
+//! $ ldpc-toolbox ber --ebn0 1.0,1.5,2.0 --llr-transform 0.7 \
+//!       --puncturing 1,1,1,1,0 ar4ja_1_2_1024.alist
+//! ```
+//!
+//! The frames simulated in the BER test can be written to a file with
+//! `--training-data`, to train neural decoders and other learned decoding
+//! algorithms. Each record contains the transmitted codeword, the channel LLRs
+//! and the LLRs at the end of each decoder iteration, as flat tensors in the
+//! format given by `--training-format` (see
+//! [`crate::simulation::training_data`]). The frames written can be limited
+//! with `--training-probability`, `--training-errors-only` and
+//! `--training-max-frames`. For instance, 10000 frames with errors are
+//! collected with 8-bit LLRs in steps of 1/4 with
+//! ```shell
+//! $ ldpc-toolbox ber --ebn0 1.0,1.5 --decoder Phif32 --training-data train.bin \
+//!       --training-format i8:4 --training-errors-only --training-max-frames 10000 \
+//!       ar4ja:1/2:1024
+//! ```
+//!
+//! The error patterns of the failed frames (the bits in which the decoded
+//! codeword differs from the transmitted one) can be captured with
+//! `--error-patterns`, which gives the maximum number of patterns to capture.
+//! After the simulation finishes, the patterns are clustered by their overlap
+//! (see [`crate::simulation::error_patterns`]), and the most frequent clusters
+//! are listed with their variable nodes, which are often trapping sets of the
+//! Tanner graph. For instance
+//! ```shell
+//! $ ldpc-toolbox ber --ebn0 3.0 --error-patterns 1000 --error-overlap 0.5 ar4ja:1/2:1024
+//! ```
+//!
+//! When there are false decodes (frames for which the decoder converges to a
+//! codeword different from the transmitted one), the distribution of the
+//! Hamming distance between the decoded and the transmitted codewords is
+//! listed after the simulation finishes.
+//!
+//! The throughput measured in short simulations is affected by the start up
+//! of the worker threads. With `--warm-up-frames`, the first frames of each
+//! Eb/N0 are excluded from the throughput and the elapsed time (but not from
+//! the error counts). With `--worker-utilization`, the fraction of the time
+//! that each worker thread has spent simulating frames is listed after the
+//! simulation finishes, which can reveal load imbalance. For instance
+//! ```shell
+//! $ ldpc-toolbox ber --warm-up-frames 1000 --worker-utilization --min-ebn0 0.0 --max-ebn0 2.05 --step-ebn0 0.1 ar4ja:1/2:1024
+//! ```
+//!
+//! With `--batch`, each worker thread simulates several frames at once and
+//! decodes them with a batch decoder (see [`crate::decoder::batch`]). The
+//! `NMSi8` and `OMSi8` decoders decode 32 frames in lockstep using SIMD
+//! instructions, which increases the throughput considerably. For instance
+//! ```shell
+//! $ ldpc-toolbox ber --batch --decoder NMSi8-0.75 --min-ebn0 0.0 --max-ebn0 2.05 --step-ebn0 0.1 ar4ja:1/2:1024
+//! ```
+//!
+//! A long simulation can be distributed between several machines (see
+//! [`crate::simulation::distributed`]). A coordinator started with
+//! `--coordinator` listens on a TCP address and assigns jobs of
+//! `--job-frames` frames of each Eb/N0 to the workers, which are started with
+//! `--worker` and the address of the coordinator. The coordinator writes the
+//! results. The workers must be given the same code, decoder and channel
+//! options as the coordinator, but not the Eb/N0's or the number of frames.
+//! For instance
+//! ```shell
+//! $ ldpc-toolbox ber --coordinator 0.0.0.0:7878 --job-frames 5000 \
+//!       --min-ebn0 0.0 --max-ebn0 2.05 --step-ebn0 0.1 ar4ja:1/2:1024
+//! ```
+//! and in each of the machines
+//! ```shell
+//! $ ldpc-toolbox ber --worker coordinator-host:7878 ar4ja:1/2:1024
+//! ```
+//!
+//! Instead of an alist file, a code specification (see
+//! [`crate::codes::cache`]) can be given. In this case, the standard puncturing
+//! of the code and its [decoder preset](crate::codes::DecoderPreset) are used
+//! unless `--puncturing`, `--decoder` or `--max-iter` are given. For instance,
+//! the CCSDS r=1/2, k=1024 LDPC code can also be simulated with
+//! ```shell
+//! $ ldpc-toolbox ber --min-ebn0 0.0 --max-ebn0 2.05 --step-ebn0 0.1 ar4ja:1/2:1024
+//! ```
+//!
+//! With `--decoder auto`, the decoder implementation is chosen automatically
+//! according to the degree distributions of the code, its size and the maximum
+//! Eb/N0 simulated (see [`DecoderImplementation::recommend`]).
+//!
+//! The min-sum decoders take their scaling factor or offset as part of the
+//! decoder name. For instance, a normalized min-sum decoder with a scaling
+//! factor of 0.75 and a layered schedule is used with
+//! ```shell
+//! $ ldpc-toolbox ber --decoder HLNMS-0.75 --min-ebn0 0.0 --max-ebn0 2.05 --step-ebn0 0.1 ar4ja:1/2:1024
+//! ```
 
+pub use crate::simulation::puncturing::parse_puncturing_pattern;
 use crate::{
+    cancel::CancellationToken,
     cli::*,
+    codes::{
+        cache::{code_from_spec, CodeCache},
+        Code, MatrixCode,
+    },
     decoder::factory::{DecoderFactory, DecoderImplementation},
     simulation::{
         ber::{Report, Reporter, Statistics},
-        factory::{Ber, BerTestBuilder, Modulation},
+        channel::{ChannelModel, Impairments},
+        distributed::{self, Coordinator, TestIdentity},
+        error_patterns::{cluster_patterns, ErrorPatternCapture},
+        factory::{ebn0_range, parse_ebn0_list, Ber, BerTestBuilder, Modulation},
+        frame_errors::FrameErrorPolicy,
+        iterations::IterationSchedule,
+        known_bits::{parse_known_bit_positions, KnownBits},
+        llr_transform::LlrTransform,
+        mixture::{MixtureComponent, MixtureStatistics, MixtureTest},
+        modulation::{self, Apsk16, Apsk16Modulator, Bpsk, Psk8, Qpsk, APSK16_DEFAULT_RING_RATIO},
+        puncturing::PuncturingPattern,
+        reference_curves::{self, ReferenceCurve},
+        scrambling::Scrambler,
+        shortening::Shortening,
+        snr_profile::SnrProfile,
+        training_data::{TensorFormat, TrainingDataSink},
     },
     sparse::SparseMatrix,
 };
@@ -28,8 +302,13 @@ use std::{
     error::Error,
     fs::File,
     io::Write,
+    net::{TcpListener, TcpStream},
+    path::Path,
     str::FromStr,
-    sync::mpsc::{self, Receiver},
+    sync::{
+        mpsc::{self, Receiver},
+        Arc,
+    },
     time::Duration,
 };
 
@@ -38,60 +317,264 @@ use std::{
 #[command(about = "Performs a BER simulation")]
 pub struct Args<
     Dec: DecoderFactory + FromStr<Err = E> = DecoderImplementation,
-    E: Into<Box<(dyn std::error::Error + Send + Sync + 'static)>> = &'static str,
+    E: Into<Box<dyn std::error::Error + Send + Sync + 'static>> = &'static str,
 > {
-    /// alist file for the code
-    alist: String,
+    /// alist file or code specification (for instance "ar4ja:1/2:1024")
+    #[structopt(required_unless_present = "mixture")]
+    alist: Option<String>,
+    /// Code mixture (format "dvbs2:1/2:short=0.3,dvbs2:1/2=0.7")
+    #[structopt(long, conflicts_with_all = [
+        "alist",
+        "output_file_ldpc",
+        "puncturing",
+        "interleaving",
+        "scrambler_polynomial",
+        "known_bits",
+        "shortening",
+        "llr_transform",
+        "max_llr",
+        "compare_reference",
+        "max_iter_schedule",
+        "frame_errors_policy",
+        "warm_up_frames",
+        "worker_utilization",
+        "batch",
+        "channel",
+        "crossover",
+        "impairments",
+        "snr_profile",
+        "training_data",
+        "error_patterns",
+        "coordinator",
+        "worker",
+    ])]
+    mixture: Option<String>,
     /// Output file for simulation results
     #[structopt(long)]
     output_file: Option<String>,
     /// Output file for LDPC-only results (only useful when using BCH)
     #[structopt(long)]
     output_file_ldpc: Option<String>,
-    /// Decoder implementation
-    #[structopt(long, default_value = "Phif64")]
-    decoder: Dec,
-    /// Modulation
+    /// Decoder implementation ("auto" to choose one based on the code) [default:
+    /// Phif64 or the preset of the code]
+    #[structopt(long)]
+    decoder: Option<DecoderArg<Dec>>,
+    /// Modulation ("BPSK", "QPSK", "8PSK" or "16APSK")
     #[structopt(long, default_value = "BPSK")]
     modulation: Modulation,
-    /// Puncturing pattern (format "1,1,1,0")
+    /// Ring ratio of the 16APSK constellation [default: 2.85]
+    #[structopt(long)]
+    apsk16_ring_ratio: Option<f64>,
+    /// Channel model ("awgn", "rayleigh", "rayleigh-block-<symbols>",
+    /// "rayleigh-doppler-<normalized frequency>" or "bsc")
+    #[structopt(long, default_value = "awgn")]
+    channel: ChannelModel,
+    /// Fixed crossover probability of the BSC channel
+    #[structopt(long)]
+    crossover: Option<f64>,
+    /// Receiver front-end impairments (format
+    /// "amplitude=0.9,dc-offset=0.05,gain-jitter=0.02")
+    #[structopt(long)]
+    impairments: Option<Impairments>,
+    /// Puncturing pattern (format "1,1,1,0" or "3*1,0" by blocks, "bits:1,1,0"
+    /// per bit, "nr:<Zc>" for 5G NR or "ar4ja")
     #[structopt(long)]
-    puncturing: Option<String>,
+    puncturing: Option<PuncturingPattern>,
     /// Interleaving columns (negative for backwards read)
     #[structopt(long)]
     interleaving: Option<isize>,
-    /// Minimum Eb/N0 (dB)
+    /// Scrambler polynomial (for instance "0x1a9" for x^8 + x^7 + x^5 + x^3 + 1)
+    #[structopt(long, value_parser = parse_hex, requires = "scrambler_seed")]
+    scrambler_polynomial: Option<u64>,
+    /// Scrambler seed (for instance "0xff")
+    #[structopt(long, value_parser = parse_hex, requires = "scrambler_polynomial")]
+    scrambler_seed: Option<u64>,
+    /// Information bits known by the decoder, set to zero (format "0-63,100")
     #[structopt(long)]
-    min_ebn0: f64,
-    /// Maximum Eb/N0 (dB)
+    known_bits: Option<String>,
+    /// Information bits fixed to zero and not transmitted (format "900-1023")
+    #[structopt(long)]
+    shortening: Option<String>,
+    /// Affine transform applied to the LLRs before decoding (format "scale",
+    /// "scale:offset" or comma-separated patterns such as "1,1,0.5:0")
     #[structopt(long)]
-    max_ebn0: f64,
+    llr_transform: Option<LlrTransform>,
+    /// Minimum Eb/N0 (dB)
+    #[structopt(long, required_unless_present_any = ["ebn0", "ebn0_file", "snr_profile", "worker"])]
+    min_ebn0: Option<f64>,
+    /// Maximum Eb/N0 (dB)
+    #[structopt(long, required_unless_present_any = ["ebn0", "ebn0_file", "snr_profile", "worker"])]
+    max_ebn0: Option<f64>,
     /// Eb/N0 step (dB)
+    #[structopt(long, required_unless_present_any = ["ebn0", "ebn0_file", "snr_profile", "worker"])]
+    step_ebn0: Option<f64>,
+    /// List of Eb/N0's (dB) (format "0.0,0.5,1.0,1.25")
+    #[structopt(long, conflicts_with_all = ["min_ebn0", "max_ebn0", "step_ebn0", "ebn0_file"])]
+    ebn0: Option<String>,
+    /// File containing a list of Eb/N0's (dB), separated by commas or newlines
+    #[structopt(long, conflicts_with_all = ["min_ebn0", "max_ebn0", "step_ebn0"])]
+    ebn0_file: Option<String>,
+    /// Time-varying Eb/N0 profile ("ramp:<start>:<stop>:<frames>",
+    /// "sine:<mean>:<amplitude>:<period>:<frames>" or "trace:<file>")
+    #[structopt(long, conflicts_with_all = [
+        "min_ebn0",
+        "max_ebn0",
+        "step_ebn0",
+        "ebn0",
+        "ebn0_file",
+        "frame_errors_policy",
+        "max_frames",
+        "warm_up_frames",
+        "quick",
+        "compare_reference",
+    ])]
+    snr_profile: Option<SnrProfile>,
+    /// Width of the Eb/N0 bins of --snr-profile (dB) [default: 0.5]
+    #[structopt(long, requires = "snr_profile")]
+    snr_bin: Option<f32>,
+    /// Maximum number of iterations [default: 100 or the preset of the code]
     #[structopt(long)]
-    step_ebn0: f64,
-    /// Maximum number of iterations
-    #[structopt(long, default_value = "100")]
-    max_iter: usize,
+    max_iter: Option<usize>,
+    /// Maximum iterations per Eb/N0 ("auto", "auto:<factor>" or a table with
+    /// format "0.0:200,1.0:100")
+    #[structopt(long)]
+    max_iter_schedule: Option<IterationSchedule>,
     /// Number of frame errors to collect
     #[structopt(long, default_value = "100")]
     frame_errors: u64,
+    /// Frame errors as a function of the measured FER ("fixed", "auto" or a
+    /// table with format "1e-3:100,1e-7:30"), limited by --frame-errors
+    #[structopt(long)]
+    frame_errors_policy: Option<FrameErrorPolicy>,
+    /// Maximum number of frames to simulate per Eb/N0
+    #[structopt(long)]
+    max_frames: Option<u64>,
+    /// Number of frames per Eb/N0 excluded from the throughput measurement
+    #[structopt(long, default_value = "0")]
+    warm_up_frames: u64,
+    /// Report the utilization of each worker thread
+    #[structopt(long)]
+    worker_utilization: bool,
+    /// Decode the frames in batches (faster with the NMSi8 and OMSi8 decoders)
+    #[structopt(long, conflicts_with_all = ["snr_profile", "training_data", "max_llr"])]
+    batch: bool,
+    /// Quick-look mode (few frames and a coarse Eb/N0 grid)
+    #[structopt(long)]
+    quick: bool,
     /// Maximum number of bit errors that the BCH decoder can correct (0 means no BCH decoder)
     #[structopt(long, default_value = "0")]
     bch_max_errors: u64,
+    /// Report the maximum magnitude of the internal LLRs of the decoder
+    #[structopt(long)]
+    max_llr: bool,
+    /// Master seed for the random number generators (random if not given)
+    #[structopt(long)]
+    seed: Option<u64>,
+    /// Compare the results with a reference curve (for instance "ar4ja:1/2:1024")
+    #[structopt(long)]
+    compare_reference: Option<String>,
+    /// Output file for training data (codewords and LLRs of each iteration)
+    #[structopt(long)]
+    training_data: Option<String>,
+    /// Format of the LLRs in the training data ("f16" or "i8:<scale>")
+    #[structopt(long, default_value = "f16", requires = "training_data")]
+    training_format: TensorFormat,
+    /// Probability with which each frame is written to the training data
+    #[structopt(long, default_value = "1", requires = "training_data")]
+    training_probability: f64,
+    /// Write only the frames with bit errors to the training data
+    #[structopt(long, requires = "training_data")]
+    training_errors_only: bool,
+    /// Maximum number of frames written to the training data
+    #[structopt(long, requires = "training_data")]
+    training_max_frames: Option<u64>,
+    /// Maximum number of error patterns of failed frames to capture and cluster
+    #[structopt(long)]
+    error_patterns: Option<usize>,
+    /// Minimum overlap between the error patterns of a cluster
+    #[structopt(long, default_value = "0.5", requires = "error_patterns")]
+    error_overlap: f64,
+    /// Run as the coordinator of a distributed simulation, listening on an
+    /// address (for instance "0.0.0.0:7878")
+    #[structopt(long, conflicts_with_all = [
+        "worker",
+        "snr_profile",
+        "training_data",
+        "error_patterns",
+        "frame_errors_policy",
+        "max_iter_schedule",
+    ])]
+    coordinator: Option<String>,
+    /// Number of frames per job of the distributed simulation
+    #[structopt(long, default_value = "10000", requires = "coordinator")]
+    job_frames: u64,
+    /// Run as a worker of a distributed simulation, connecting to the address
+    /// of the coordinator
+    #[structopt(long, conflicts_with_all = [
+        "snr_profile",
+        "error_patterns",
+        "frame_errors_policy",
+        "max_iter_schedule",
+        "max_frames",
+        "quick",
+        "compare_reference",
+        "output_file",
+        "output_file_ldpc",
+    ])]
+    worker: Option<String>,
 }
 
 impl<
         Dec: DecoderFactory + FromStr<Err = E>,
-        E: Into<Box<(dyn std::error::Error + Send + Sync + 'static)>>,
+        E: Into<Box<dyn std::error::Error + Send + Sync + 'static>>,
     > Run for Args<Dec, E>
 {
     fn run(&self) -> Result<(), Box<dyn Error>> {
-        let puncturing_pattern = if let Some(p) = self.puncturing.as_ref() {
-            Some(parse_puncturing_pattern(p)?)
-        } else {
-            None
+        if self.apsk16_ring_ratio.is_some() && self.modulation != Modulation::Apsk16 {
+            return Err("--apsk16-ring-ratio can only be used with 16APSK".into());
+        }
+        if self.crossover.is_some() && self.channel != ChannelModel::Bsc(None) {
+            return Err("--crossover can only be used with --channel bsc".into());
+        }
+        if let Some(mixture) = &self.mixture {
+            return match self.modulation {
+                Modulation::Bpsk => self.run_mixture::<Bpsk>(mixture, Default::default()),
+                Modulation::Qpsk => self.run_mixture::<Qpsk>(mixture, Default::default()),
+                Modulation::Psk8 => self.run_mixture::<Psk8>(mixture, Default::default()),
+                Modulation::Apsk16 => {
+                    let ring_ratio = self.apsk16_ring_ratio();
+                    if ring_ratio.is_nan() || ring_ratio <= 1.0 {
+                        return Err(format!("invalid 16APSK ring ratio {ring_ratio}").into());
+                    }
+                    self.run_mixture::<Apsk16>(mixture, Apsk16Modulator::new(ring_ratio))
+                }
+            };
+        }
+        let code = self.code()?;
+        let settings = self.settings(Some(&*code))?;
+        let scrambler = match (self.scrambler_polynomial, self.scrambler_seed) {
+            (Some(polynomial), Some(seed)) => Some(Scrambler::new(polynomial, seed)?),
+            _ => None,
+        };
+        let known_bits = match self.known_bits.as_ref() {
+            Some(k) => Some(KnownBits::zeros(&parse_known_bit_positions(k)?)?),
+            None => None,
+        };
+        let shortening = match self.shortening.as_ref() {
+            Some(s) => Some(Shortening::new(&parse_known_bit_positions(s)?)?),
+            None => None,
         };
-        let h = SparseMatrix::from_alist(&std::fs::read_to_string(&self.alist)?)?;
+        let reference = self.reference_curve()?;
+        if !(self.error_overlap > 0.0 && self.error_overlap <= 1.0) {
+            return Err(format!("invalid error pattern overlap {}", self.error_overlap).into());
+        }
+        if self.job_frames == 0 {
+            return Err("the number of frames per job must be positive".into());
+        }
+        let error_patterns = self
+            .error_patterns
+            .map(|max| Arc::new(ErrorPatternCapture::new(max)));
+        let h = Arc::new(code.h());
         let mut output_file = if let Some(f) = &self.output_file {
             Some(File::create(f)?)
         } else {
@@ -101,31 +584,72 @@ impl<
             (true, Some(f)) => Some(File::create(f)?),
             _ => None,
         };
-        let num_ebn0s = ((self.max_ebn0 - self.min_ebn0) / self.step_ebn0).floor() as usize + 1;
-        let ebn0s = (0..num_ebn0s)
-            .map(|k| (self.min_ebn0 + k as f64 * self.step_ebn0) as f32)
-            .collect::<Vec<_>>();
+        let ebn0s = self.ebn0s()?;
+        let decoder = settings.decoder.resolve(&h, &ebn0s)?;
+        let cancellation = CancellationToken::new();
         let (report_tx, report_rx) = mpsc::channel();
         let reporter = Reporter {
             tx: report_tx,
             interval: Duration::from_millis(500),
         };
-        let test = BerTestBuilder {
-            h,
-            decoder_implementation: self.decoder.clone(),
+        let builder = BerTestBuilder {
+            h: Arc::clone(&h),
+            decoder_implementation: decoder.clone(),
             modulation: self.modulation,
-            puncturing_pattern: puncturing_pattern.as_ref().map(|v| &v[..]),
+            apsk16_ring_ratio: self.apsk16_ring_ratio,
+            channel: self.channel(),
+            impairments: self.impairments.unwrap_or_default(),
+            snr_profile: self
+                .snr_profile
+                .clone()
+                .map(|profile| (profile, self.snr_bin())),
+            puncturing: settings.puncturing.clone(),
             interleaving_columns: self.interleaving,
-            max_frame_errors: self.frame_errors,
-            max_iterations: self.max_iter,
+            scrambler,
+            known_bits,
+            shortening,
+            llr_transform: self.llr_transform.clone(),
+            max_frame_errors: self.frame_errors(),
+            frame_error_policy: self.frame_errors_policy.clone().unwrap_or_default(),
+            max_frames: self.max_frames(),
+            warm_up_frames: self.warm_up_frames,
+            batch_decoding: self.batch,
+            max_iterations: settings.max_iter,
+            iteration_schedule: self.max_iter_schedule.clone().unwrap_or_default(),
             ebn0s_db: &ebn0s,
-            reporter: Some(reporter),
+            reporter: Some(reporter.clone()),
             bch_max_errors: self.bch_max_errors,
+            llr_magnitude_tracking: self.max_llr,
+            training_data: self.training_data_sink()?,
+            error_patterns: error_patterns.clone(),
+            seed: self.seed,
+            cancellation: Some(cancellation.clone()),
+        };
+        if let Some(coordinator) = &self.worker {
+            return Self::run_worker(coordinator, &builder);
         }
-        .build()?;
-        self.write_details(std::io::stdout(), &*test)?;
+        let test = builder.build()?;
+        if let Some(reference) = reference {
+            if self.modulation != Modulation::Bpsk
+                || self.channel != ChannelModel::Awgn
+                || self.impairments.is_some_and(|i| !i.is_ideal())
+                || test.k() != reference.k
+                || test.n() != reference.n
+            {
+                return Err(format!(
+                    "the simulated code does not match the reference {} ({})",
+                    reference.spec, reference.description
+                )
+                .into());
+            }
+        }
+        let details = CodeDetails::Single {
+            test: &*test,
+            name: code.name(),
+        };
+        self.write_details(std::io::stdout(), &details, &decoder, &settings, &ebn0s)?;
         if let Some(f) = &mut output_file {
-            self.write_details(&*f, &*test)?;
+            self.write_details(&*f, &details, &decoder, &settings, &ebn0s)?;
             if self.bch_max_errors > 0 {
                 writeln!(f)?;
                 writeln!(f, "LDPC+BCH results")?;
@@ -133,53 +657,482 @@ impl<
             }
         }
         if let Some(f) = &mut output_file_ldpc {
-            self.write_details(&*f, &*test)?;
+            self.write_details(&*f, &details, &decoder, &settings, &ebn0s)?;
             writeln!(f)?;
             writeln!(f, "LDPC-only results")?;
             writeln!(f)?;
         }
-        let mut progress = Progress::new(report_rx, output_file, output_file_ldpc);
+        let mut progress = Progress::new(
+            report_rx,
+            output_file.as_ref().map(File::try_clone).transpose()?,
+            output_file_ldpc,
+            Columns {
+                max_iter: self.max_iter_schedule.is_some(),
+                max_llr: self.max_llr,
+            },
+            cancellation.clone(),
+        );
         let progress = std::thread::spawn(move || progress.run());
-        test.run()?;
+        let statistics = match &self.coordinator {
+            Some(address) => {
+                Coordinator::new(&*test, &ebn0s, self.frame_errors(), self.max_frames())
+                    .with_frames_per_job(self.job_frames)
+                    .with_reporter(reporter)
+                    .with_cancellation(cancellation)
+                    .run(TcpListener::bind(address)?)?
+            }
+            None => test.run()?,
+        };
         // This block cannot actually be written with the ? operator
         #[allow(clippy::question_mark)]
         if let Err(e) = progress.join().unwrap() {
             return Err(e);
         }
+        write_false_decodes(std::io::stdout(), &statistics)?;
+        if let Some(f) = &mut output_file {
+            write_false_decodes(&*f, &statistics)?;
+        }
+        if let Some(capture) = &error_patterns {
+            write_error_clusters(std::io::stdout(), &h, capture, self.error_overlap)?;
+            if let Some(f) = &mut output_file {
+                write_error_clusters(&*f, &h, capture, self.error_overlap)?;
+            }
+        }
+        if self.worker_utilization {
+            write_worker_utilization(std::io::stdout(), &statistics)?;
+            if let Some(f) = &mut output_file {
+                write_worker_utilization(&*f, &statistics)?;
+            }
+        }
+        if let Some(reference) = reference {
+            write_comparison(std::io::stdout(), reference, &statistics)?;
+            if let Some(f) = &mut output_file {
+                writeln!(f)?;
+                write_comparison(&*f, reference, &statistics)?;
+            }
+        }
         Ok(())
     }
 }
 
+// Limits used in quick-look mode
+const QUICK_FRAME_ERRORS: u64 = 10;
+const QUICK_MAX_FRAMES: u64 = 1000;
+const QUICK_MIN_STEP: f64 = 0.5;
+
+// Default width of the Eb/N0 bins of the SNR profile
+const DEFAULT_SNR_BIN: f32 = 0.5;
+
+/// Parses an integer given in hexadecimal (with a `0x` prefix) or decimal.
+fn parse_hex(s: &str) -> Result<u64, std::num::ParseIntError> {
+    match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => s.parse(),
+    }
+}
+
 impl<
         Dec: DecoderFactory + FromStr<Err = E>,
-        E: Into<Box<(dyn std::error::Error + Send + Sync + 'static)>>,
+        E: Into<Box<dyn std::error::Error + Send + Sync + 'static>>,
     > Args<Dec, E>
 {
-    fn write_details<W: Write>(&self, mut f: W, test: &dyn Ber) -> std::io::Result<()> {
+    fn ebn0s(&self) -> Result<Vec<f32>, Box<dyn Error>> {
+        if let Some(profile) = &self.snr_profile {
+            return Ok(profile.bins(self.snr_bin()));
+        }
+        let ebn0s = match (&self.ebn0, &self.ebn0_file) {
+            (Some(list), _) => parse_ebn0_list(list)?,
+            (None, Some(file)) => parse_ebn0_list(&std::fs::read_to_string(file)?)?,
+            (None, None) => match (self.min_ebn0, self.max_ebn0, self.step_ebn0) {
+                (Some(min), Some(max), Some(step)) => ebn0_range(min, max, step),
+                // Workers of a distributed simulation receive the Eb/N0's
+                // from the coordinator
+                _ => Vec::new(),
+            },
+        };
+        if !self.quick {
+            return Ok(ebn0s);
+        }
+        // Thin the Eb/N0's so that they are at least QUICK_MIN_STEP apart
+        let mut thinned: Vec<f32> = Vec::with_capacity(ebn0s.len());
+        for ebn0 in ebn0s {
+            if thinned
+                .last()
+                .is_none_or(|&last| f64::from((ebn0 - last).abs()) >= QUICK_MIN_STEP - 1e-6)
+            {
+                thinned.push(ebn0);
+            }
+        }
+        Ok(thinned)
+    }
+
+    // Returns the code given either as an alist file or as a code
+    // specification.
+    fn code(&self) -> Result<Box<dyn Code>, Box<dyn Error>> {
+        let code = self.alist.as_ref().unwrap();
+        Ok(if Path::new(code).is_file() {
+            let h = SparseMatrix::from_alist(&std::fs::read_to_string(code)?)?;
+            Box::new(MatrixCode::new(code.clone(), h))
+        } else {
+            code_from_spec(code)?
+        })
+    }
+
+    // Resolves the decoder settings from the command line arguments and the
+    // preset and standard puncturing of the code.
+    fn settings(&self, code: Option<&dyn Code>) -> Result<Settings<Dec>, Box<dyn Error>> {
+        let preset = code.and_then(|c| c.decoder_preset());
+        let decoder = match (&self.decoder, &preset) {
+            (Some(decoder), _) => decoder.clone(),
+            (None, Some(preset)) => preset
+                .implementation
+                .to_string()
+                .parse()
+                .map_err(|e: E| e.into() as Box<dyn Error>)?,
+            (None, None) => DEFAULT_DECODER
+                .parse()
+                .map_err(|e: E| e.into() as Box<dyn Error>)?,
+        };
+        let max_iter = self
+            .max_iter
+            .or(preset.map(|p| p.max_iterations))
+            .unwrap_or(DEFAULT_MAX_ITER);
+        let puncturing = self.puncturing.clone().or_else(|| {
+            code.and_then(|c| c.standard_puncturing())
+                .map(PuncturingPattern::Blocks)
+        });
+        Ok(Settings {
+            decoder,
+            max_iter,
+            puncturing,
+            preset: preset.is_some() && (self.decoder.is_none() || self.max_iter.is_none()),
+        })
+    }
+
+    fn reference_curve(&self) -> Result<Option<&'static ReferenceCurve>, Box<dyn Error>> {
+        let Some(spec) = &self.compare_reference else {
+            return Ok(None);
+        };
+        match reference_curves::find(spec) {
+            Some(curve) => Ok(Some(curve)),
+            None => {
+                let available = reference_curves::CURVES
+                    .iter()
+                    .map(|curve| curve.spec)
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                Err(format!("no reference curve for {spec} (available: {available})").into())
+            }
+        }
+    }
+
+    fn channel(&self) -> ChannelModel {
+        match (self.channel, self.crossover) {
+            (ChannelModel::Bsc(_), Some(crossover)) => ChannelModel::Bsc(Some(crossover)),
+            (channel, _) => channel,
+        }
+    }
+
+    fn snr_bin(&self) -> f32 {
+        self.snr_bin.unwrap_or(DEFAULT_SNR_BIN)
+    }
+
+    fn training_data_sink(&self) -> Result<Option<Arc<TrainingDataSink>>, Box<dyn Error>> {
+        let Some(path) = &self.training_data else {
+            return Ok(None);
+        };
+        if !(0.0..=1.0).contains(&self.training_probability) {
+            return Err(format!(
+                "invalid training data probability {}",
+                self.training_probability
+            )
+            .into());
+        }
+        let mut sink = TrainingDataSink::create(path, self.training_format)?
+            .with_sampling_probability(self.training_probability)
+            .with_errors_only(self.training_errors_only);
+        if let Some(max) = self.training_max_frames {
+            sink = sink.with_max_records(max);
+        }
+        Ok(Some(Arc::new(sink)))
+    }
+
+    fn frame_errors(&self) -> u64 {
+        if self.quick {
+            self.frame_errors.min(QUICK_FRAME_ERRORS)
+        } else {
+            self.frame_errors
+        }
+    }
+
+    fn max_frames(&self) -> Option<u64> {
+        if self.quick {
+            Some(
+                self.max_frames
+                    .map_or(QUICK_MAX_FRAMES, |m| m.min(QUICK_MAX_FRAMES)),
+            )
+        } else {
+            self.max_frames
+        }
+    }
+
+    // Runs a worker of a distributed simulation, which runs the jobs assigned
+    // by the coordinator with BER tests created from the builder.
+    fn run_worker(coordinator: &str, builder: &BerTestBuilder<Dec>) -> Result<(), Box<dyn Error>> {
+        let identity = TestIdentity::new(&*builder.clone().build()?);
+        let stream = TcpStream::connect(coordinator)?;
+        println!("Connected to coordinator {coordinator}");
+        let jobs = distributed::run_worker(stream, &identity, |job| {
+            let test = BerTestBuilder {
+                ebn0s_db: &[job.ebn0_db],
+                max_frame_errors: job.max_frame_errors,
+                max_frames: Some(job.max_frames),
+                reporter: None,
+                seed: Some(job.seed),
+                cancellation: None,
+                ..builder.clone()
+            }
+            .build()?;
+            let statistics = test.run()?.remove(0);
+            println!(
+                "Job {}: Eb/N0 = {:.2} dB, {} frames, {} frame errors, {:.3} Mbps",
+                job.id,
+                job.ebn0_db,
+                statistics.num_frames,
+                statistics.ldpc.frame_errors,
+                statistics.throughput_mbps
+            );
+            Ok(statistics)
+        })?;
+        println!("Finished after {jobs} jobs");
+        Ok(())
+    }
+
+    fn apsk16_ring_ratio(&self) -> f64 {
+        self.apsk16_ring_ratio.unwrap_or(APSK16_DEFAULT_RING_RATIO)
+    }
+
+    fn run_mixture<Mod: modulation::Modulation>(
+        &self,
+        mixture: &str,
+        modulator: Mod::Modulator,
+    ) -> Result<(), Box<dyn Error>> {
+        if self.bch_max_errors > 0 {
+            return Err("BCH decoding is not supported with --mixture".into());
+        }
+        let components = parse_mixture(mixture)?;
+        let mut output_file = if let Some(f) = &self.output_file {
+            Some(File::create(f)?)
+        } else {
+            None
+        };
+        let ebn0s = self.ebn0s()?;
+        let settings = self.settings(None)?;
+        let decoder = match &settings.decoder {
+            DecoderArg::Auto => return Err("--decoder auto is not supported with --mixture".into()),
+            DecoderArg::Implementation(decoder) => decoder.clone(),
+        };
+        let cancellation = CancellationToken::new();
+        let (report_tx, report_rx) = mpsc::channel();
+        let reporter = Reporter {
+            tx: report_tx,
+            interval: Duration::from_millis(500),
+        };
+        let test = MixtureTest::<Mod, Dec>::new(
+            &components,
+            decoder.clone(),
+            self.frame_errors(),
+            self.max_frames(),
+            settings.max_iter,
+            &ebn0s,
+            Some(reporter),
+            self.seed,
+            Some(cancellation.clone()),
+        )?
+        .with_modulator(modulator);
+        let code = CodeDetails::Mixture {
+            components: &components,
+            rate: test.rate(),
+            seed: test.seed(),
+        };
+        self.write_details(std::io::stdout(), &code, &decoder, &settings, &ebn0s)?;
+        if let Some(f) = &mut output_file {
+            self.write_details(&*f, &code, &decoder, &settings, &ebn0s)?;
+        }
+        let mut progress = Progress::new(
+            report_rx,
+            output_file.as_ref().map(File::try_clone).transpose()?,
+            None,
+            Columns::default(),
+            cancellation,
+        );
+        let progress = std::thread::spawn(move || progress.run());
+        let statistics = test.run()?;
+        // This block cannot actually be written with the ? operator
+        #[allow(clippy::question_mark)]
+        if let Err(e) = progress.join().unwrap() {
+            return Err(e);
+        }
+        write_components(std::io::stdout(), &components, &statistics)?;
+        let aggregate = statistics
+            .iter()
+            .map(|stats| stats.aggregate.clone())
+            .collect::<Vec<_>>();
+        write_false_decodes(std::io::stdout(), &aggregate)?;
+        if let Some(f) = &mut output_file {
+            writeln!(f)?;
+            write_components(&*f, &components, &statistics)?;
+            write_false_decodes(&*f, &aggregate)?;
+        }
+        Ok(())
+    }
+
+    fn write_details<W: Write>(
+        &self,
+        mut f: W,
+        code: &CodeDetails,
+        decoder: &Dec,
+        settings: &Settings<Dec>,
+        ebn0s: &[f32],
+    ) -> std::io::Result<()> {
         writeln!(f, "BER TEST PARAMETERS")?;
         writeln!(f, "-------------------")?;
         writeln!(f, "Simulation:")?;
-        writeln!(f, " - Minimum Eb/N0: {:.2} dB", self.min_ebn0)?;
-        writeln!(f, " - Maximum Eb/N0: {:.2} dB", self.max_ebn0)?;
-        writeln!(f, " - Eb/N0 step: {:.2} dB", self.step_ebn0)?;
-        writeln!(f, " - Number of frame errors: {}", self.frame_errors)?;
+        match (
+            self.min_ebn0,
+            self.max_ebn0,
+            self.step_ebn0,
+            &self.snr_profile,
+        ) {
+            (_, _, _, Some(profile)) => {
+                writeln!(f, " - Eb/N0 profile: {profile}")?;
+                writeln!(f, " - Number of frames: {}", profile.num_frames())?;
+                writeln!(f, " - Eb/N0 bin width: {:.2} dB", self.snr_bin())?;
+            }
+            (Some(min), Some(max), Some(step), None) if !self.quick => {
+                writeln!(f, " - Minimum Eb/N0: {min:.2} dB")?;
+                writeln!(f, " - Maximum Eb/N0: {max:.2} dB")?;
+                writeln!(f, " - Eb/N0 step: {step:.2} dB")?;
+            }
+            _ => {
+                let list = ebn0s
+                    .iter()
+                    .map(|ebn0| format!("{ebn0:.2}"))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                writeln!(f, " - Eb/N0s: {list} dB")?;
+            }
+        }
+        if self.snr_profile.is_none() {
+            writeln!(f, " - Number of frame errors: {}", self.frame_errors())?;
+        }
+        if let Some(policy) = &self.frame_errors_policy {
+            writeln!(f, " - Frame errors policy: {policy}")?;
+        }
+        if let Some(max_frames) = self.max_frames() {
+            writeln!(f, " - Maximum number of frames: {max_frames}")?;
+        }
+        if self.quick {
+            writeln!(f, " - Quick-look mode (rough results)")?;
+        }
+        if let Some(address) = &self.coordinator {
+            writeln!(
+                f,
+                " - Distributed: coordinator on {address}, {} frames per job",
+                self.job_frames
+            )?;
+        }
+        if let Some(max) = self.error_patterns {
+            writeln!(f, " - Error patterns captured: up to {max}")?;
+        }
+        if let Some(path) = &self.training_data {
+            writeln!(
+                f,
+                " - Training data: {path} ({} LLRs)",
+                self.training_format
+            )?;
+        }
         writeln!(f, "Channel:")?;
         writeln!(f, " - Modulation: {}", self.modulation)?;
-        writeln!(f, "LDPC code:")?;
-        writeln!(f, " - alist: {}", self.alist)?;
-        if let Some(puncturing) = self.puncturing.as_ref() {
-            writeln!(f, " - Puncturing pattern: {puncturing}")?;
-        }
-        if let Some(interleaving) = self.interleaving.as_ref() {
-            writeln!(f, " - Interleaving columns: {interleaving}")?;
-        }
-        writeln!(f, " - Information bits (k): {}", test.k())?;
-        writeln!(f, " - Codeword size (N_cw): {}", test.n_cw())?;
-        writeln!(f, " - Frame size (N): {}", test.n())?;
-        writeln!(f, " - Code rate: {:.3}", test.rate())?;
+        if self.modulation == Modulation::Apsk16 {
+            writeln!(f, " - Ring ratio: {}", self.apsk16_ring_ratio())?;
+        }
+        if self.mixture.is_none() {
+            writeln!(f, " - Channel model: {}", self.channel())?;
+            if let Some(impairments) = &self.impairments {
+                writeln!(f, " - Impairments: {impairments}")?;
+            }
+        }
+        match code {
+            CodeDetails::Single { test, name } => {
+                writeln!(f, "LDPC code:")?;
+                let code = self.alist.as_deref().unwrap_or_default();
+                if name == code {
+                    writeln!(f, " - alist: {code}")?;
+                } else {
+                    writeln!(f, " - Code: {name} ({code})")?;
+                }
+                if let Some(pattern) = settings.puncturing.as_ref() {
+                    writeln!(f, " - Puncturing pattern: {pattern}")?;
+                }
+                if let Some(interleaving) = self.interleaving.as_ref() {
+                    writeln!(f, " - Interleaving columns: {interleaving}")?;
+                }
+                if let (Some(polynomial), Some(seed)) =
+                    (self.scrambler_polynomial, self.scrambler_seed)
+                {
+                    writeln!(
+                        f,
+                        " - Scrambler: polynomial {polynomial:#x}, seed {seed:#x}"
+                    )?;
+                }
+                if let Some(known_bits) = self.known_bits.as_ref() {
+                    writeln!(f, " - Known bits: {known_bits}")?;
+                }
+                if let Some(shortening) = self.shortening.as_ref() {
+                    writeln!(f, " - Shortened bits: {shortening}")?;
+                }
+                if let Some(transform) = self.llr_transform.as_ref() {
+                    writeln!(f, " - LLR transform: {transform}")?;
+                }
+                writeln!(f, " - Information bits (k): {}", test.k())?;
+                writeln!(f, " - Codeword size (N_cw): {}", test.n_cw())?;
+                writeln!(f, " - Frame size (N): {}", test.n())?;
+                writeln!(f, " - Code rate: {:.3}", test.rate())?;
+                if let Some(reference) = self.compare_reference.as_ref() {
+                    writeln!(f, " - Reference curve: {reference}")?;
+                }
+            }
+            CodeDetails::Mixture {
+                components, rate, ..
+            } => {
+                writeln!(f, "Code mixture:")?;
+                let total_weight = components.iter().map(|c| c.weight).sum::<f64>();
+                for c in components.iter() {
+                    writeln!(
+                        f,
+                        " - {}: {:.1}% (k = {}, N_cw = {})",
+                        c.name,
+                        100.0 * c.weight / total_weight,
+                        c.h.num_cols() - c.h.num_rows(),
+                        c.h.num_cols()
+                    )?;
+                }
+                writeln!(f, " - Average code rate: {rate:.3}")?;
+            }
+        }
         writeln!(f, "LDPC decoder:")?;
-        writeln!(f, " - Implementation: {}", self.decoder)?;
-        writeln!(f, " - Maximum iterations: {}", self.max_iter)?;
+        writeln!(f, " - Implementation: {decoder}")?;
+        if let DecoderArg::Auto = settings.decoder {
+            writeln!(f, " - Implementation selected automatically")?;
+        }
+        writeln!(f, " - Maximum iterations: {}", settings.max_iter)?;
+        if settings.preset {
+            writeln!(f, " - Decoder preset of the code used")?;
+        }
+        if let Some(schedule) = &self.max_iter_schedule {
+            writeln!(f, " - Maximum iterations schedule: {schedule}")?;
+        }
         if self.bch_max_errors > 0 {
             writeln!(f, "BCH decoder:")?;
             writeln!(
@@ -188,26 +1141,249 @@ impl<
                 self.bch_max_errors
             )?;
         }
+        writeln!(f, "Metadata:")?;
+        match code {
+            CodeDetails::Single { test, .. } => {
+                for line in test.metadata().to_string().lines() {
+                    writeln!(f, " - {line}")?;
+                }
+            }
+            CodeDetails::Mixture { seed, .. } => {
+                writeln!(f, " - ldpc-toolbox version: {}", env!("CARGO_PKG_VERSION"))?;
+                writeln!(f, " - Seed: {seed}")?;
+            }
+        }
         writeln!(f)?;
         Ok(())
     }
 }
 
-/// Parses a puncturing pattern.
+/// Decoder given in the command line.
 ///
-/// This function parses a punturing pattern given as a string, converting it
-/// into a vector of bools. The format for the puncturing pattern should be
-/// like `"1,1,1,0"`.
-pub fn parse_puncturing_pattern(s: &str) -> Result<Vec<bool>, &'static str> {
-    let mut v = Vec::new();
-    for a in s.split(',') {
-        v.push(match a {
-            "0" => false,
-            "1" => true,
-            _ => return Err("invalid puncturing pattern"),
-        });
+/// This is either a decoder implementation or `auto`, which selects the
+/// decoder recommended by [`DecoderFactory::recommended`].
+#[derive(Debug, Clone)]
+enum DecoderArg<Dec> {
+    Auto,
+    Implementation(Dec),
+}
+
+impl<Dec: DecoderFactory> DecoderArg<Dec> {
+    fn resolve(&self, h: &SparseMatrix, ebn0s: &[f32]) -> Result<Dec, Box<dyn Error>> {
+        match self {
+            DecoderArg::Auto => Dec::recommended(h, ebn0s)
+                .ok_or_else(|| "the decoder cannot be selected automatically".into()),
+            DecoderArg::Implementation(decoder) => Ok(decoder.clone()),
+        }
+    }
+}
+
+impl<Dec: FromStr<Err = E>, E: Into<Box<dyn std::error::Error + Send + Sync + 'static>>> FromStr
+    for DecoderArg<Dec>
+{
+    type Err = E;
+
+    fn from_str(s: &str) -> Result<Self, E> {
+        if s == "auto" {
+            Ok(DecoderArg::Auto)
+        } else {
+            s.parse().map(DecoderArg::Implementation)
+        }
+    }
+}
+
+/// Parses a code mixture.
+///
+/// The mixture is given as a comma-separated list of elements of the form
+/// `spec=weight`, where `spec` is a code specification (see
+/// [`crate::codes::cache`]). The parity check matrices are obtained from the
+/// global [`CodeCache`].
+fn parse_mixture(s: &str) -> Result<Vec<MixtureComponent>, Box<dyn Error>> {
+    s.split(',')
+        .map(|element| {
+            let (spec, weight) = element
+                .rsplit_once('=')
+                .ok_or_else(|| format!("invalid mixture element {element}"))?;
+            let weight = weight
+                .parse()
+                .map_err(|_| format!("invalid mixture weight {weight}"))?;
+            let code = code_from_spec(spec)?;
+            Ok(MixtureComponent {
+                name: code.name(),
+                h: CodeCache::global().get(spec)?,
+                puncturing_pattern: code.standard_puncturing(),
+                weight,
+            })
+        })
+        .collect()
+}
+
+fn write_components<W: Write>(
+    mut f: W,
+    components: &[MixtureComponent],
+    statistics: &[MixtureStatistics],
+) -> std::io::Result<()> {
+    writeln!(f, "RESULTS PER CODE")?;
+    writeln!(f, "----------------")?;
+    for (j, c) in components.iter().enumerate() {
+        writeln!(f, "{}:", c.name)?;
+        writeln!(f, "{}", Progress::format_header(Columns::default()))?;
+        for stats in statistics.iter() {
+            writeln!(
+                f,
+                "{}",
+                Progress::format_progress(&stats.components[j], false, Columns::default())
+            )?;
+        }
+        writeln!(f)?;
+    }
+    Ok(())
+}
+
+// Writes the distribution of the distances of the false decodes, if there are
+// any false decodes.
+fn write_false_decodes<W: Write>(mut f: W, statistics: &[Statistics]) -> std::io::Result<()> {
+    if statistics.iter().all(|stats| stats.false_decodes == 0) {
+        return Ok(());
     }
-    Ok(v)
+    writeln!(f, "FALSE DECODE DISTANCES")?;
+    writeln!(f, "----------------------")?;
+    writeln!(f, "  Eb/N0 | Distance (number of false decodes)")?;
+    writeln!(f, "--------|-----------------------------------")?;
+    for stats in statistics.iter().filter(|stats| stats.false_decodes > 0) {
+        let distances = stats
+            .false_decode_distances
+            .iter()
+            .map(|(distance, count)| format!("{distance} ({count})"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        writeln!(f, "{:7.2} | {distances}", stats.ebn0_db)?;
+    }
+    writeln!(f)?;
+    Ok(())
+}
+
+// Number of error pattern clusters listed
+const ERROR_CLUSTERS: usize = 10;
+
+fn write_error_clusters<W: Write>(
+    mut f: W,
+    h: &SparseMatrix,
+    capture: &ErrorPatternCapture,
+    min_overlap: f64,
+) -> std::io::Result<()> {
+    let patterns = capture.patterns();
+    let clusters = cluster_patterns(h, &patterns, min_overlap);
+    writeln!(f, "ERROR PATTERN CLUSTERS")?;
+    writeln!(f, "----------------------")?;
+    writeln!(
+        f,
+        "{} error patterns in {} clusters (overlap >= {min_overlap})",
+        patterns.len(),
+        clusters.len()
+    )?;
+    if clusters.is_empty() {
+        writeln!(f)?;
+        return Ok(());
+    }
+    writeln!(f, "Frames | False decodes |   (a, b) | Variable nodes")?;
+    writeln!(f, "-------|---------------|----------|---------------")?;
+    for cluster in clusters.iter().take(ERROR_CLUSTERS) {
+        let (a, b) = cluster.trapping_set_parameters();
+        let variables = cluster
+            .variables
+            .iter()
+            .map(|v| v.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        writeln!(
+            f,
+            "{:6} | {:13} | {:>8} | {variables}",
+            cluster.num_patterns,
+            cluster.false_decodes,
+            format!("({a}, {b})")
+        )?;
+    }
+    writeln!(f)?;
+    Ok(())
+}
+
+fn write_worker_utilization<W: Write>(mut f: W, statistics: &[Statistics]) -> std::io::Result<()> {
+    writeln!(f, "WORKER UTILIZATION")?;
+    writeln!(f, "------------------")?;
+    writeln!(f, "  Eb/N0 | Utilization of each worker (%)")?;
+    writeln!(f, "--------|-------------------------------")?;
+    for stats in statistics {
+        let utilization = stats
+            .worker_utilization
+            .iter()
+            .map(|u| format!("{:.1}", 100.0 * u))
+            .collect::<Vec<_>>()
+            .join(", ");
+        writeln!(f, "{:7.2} | {utilization}", stats.ebn0_db)?;
+    }
+    writeln!(f)?;
+    Ok(())
+}
+
+fn write_comparison<W: Write>(
+    mut f: W,
+    reference: &ReferenceCurve,
+    statistics: &[Statistics],
+) -> std::io::Result<()> {
+    let rate = |x: Option<f64>| x.map_or_else(|| format!("{:>7}", "N/A"), |x| format!("{x:7.2e}"));
+    writeln!(f, "COMPARISON WITH REFERENCE")?;
+    writeln!(f, "-------------------------")?;
+    writeln!(f, "Reference: {}", reference.description)?;
+    writeln!(f, "Source: {}", reference.source)?;
+    writeln!(
+        f,
+        "  Eb/N0 |     BER | Ref BER |     FER | Ref FER | Eb/N0 gap (dB)\n\
+         --------|---------|---------|---------|---------|---------------"
+    )?;
+    for stats in statistics {
+        let c = reference.compare(stats);
+        let delta = c
+            .delta_ebn0_db
+            .map_or_else(|| "N/A".to_string(), |d| format!("{d:+.2}"));
+        writeln!(
+            f,
+            "{:7.2} | {:7.2e} | {} | {:7.2e} | {} | {delta:>14}",
+            c.ebn0_db,
+            c.ber,
+            rate(c.reference_ber),
+            c.fer,
+            rate(c.reference_fer),
+        )?;
+    }
+    Ok(())
+}
+
+// Decoder settings, resolved from the command line arguments and the decoder
+// preset of the code.
+struct Settings<Dec> {
+    decoder: DecoderArg<Dec>,
+    max_iter: usize,
+    puncturing: Option<PuncturingPattern>,
+    // The decoder preset of the code has been used for some setting
+    preset: bool,
+}
+
+// Default settings used when the code has no decoder preset
+const DEFAULT_DECODER: &str = "Phif64";
+const DEFAULT_MAX_ITER: usize = 100;
+
+// Code details printed in the BER test parameters.
+enum CodeDetails<'a> {
+    Single {
+        test: &'a dyn Ber,
+        name: String,
+    },
+    Mixture {
+        components: &'a [MixtureComponent],
+        rate: f64,
+        seed: u64,
+    },
 }
 
 #[derive(Debug)]
@@ -216,6 +1392,15 @@ struct Progress {
     term: Term,
     output_file: Option<File>,
     output_file_ldpc: Option<File>,
+    columns: Columns,
+    cancellation: CancellationToken,
+}
+
+// Optional columns of the results table.
+#[derive(Debug, Copy, Clone, Default)]
+struct Columns {
+    max_iter: bool,
+    max_llr: bool,
 }
 
 impl Progress {
@@ -223,19 +1408,30 @@ impl Progress {
         rx: Receiver<Report>,
         output_file: Option<File>,
         output_file_ldpc: Option<File>,
+        columns: Columns,
+        cancellation: CancellationToken,
     ) -> Progress {
         Progress {
             rx,
             term: Term::stdout(),
             output_file,
             output_file_ldpc,
+            columns,
+            cancellation,
         }
     }
 
     fn run(&mut self) -> Result<(), Box<dyn Error + Send + Sync + 'static>> {
+        // The first ctrl-c stops the BER test, which then reports the
+        // statistics obtained so far. A second ctrl-c exits immediately.
         ctrlc::set_handler({
             let term = self.term.clone();
+            let cancellation = self.cancellation.clone();
             move || {
+                if !cancellation.is_cancelled() {
+                    cancellation.cancel();
+                    return;
+                }
                 let _ = term.write_line("");
                 let _ = term.show_cursor();
                 std::process::exit(0);
@@ -251,33 +1447,45 @@ impl Progress {
     fn work(&mut self) -> Result<(), Box<dyn Error + Send + Sync + 'static>> {
         self.term.set_title("ldpc-toolbox ber");
         self.term.hide_cursor()?;
-        self.term.write_line(Self::format_header())?;
+        let header = Self::format_header(self.columns);
+        self.term.write_line(&header)?;
         if let Some(f) = &mut self.output_file {
-            writeln!(f, "{}", Self::format_header())?;
+            writeln!(f, "{header}")?;
         }
         if let Some(f) = &mut self.output_file_ldpc {
-            writeln!(f, "{}", Self::format_header())?;
+            writeln!(f, "{header}")?;
         }
         let mut last_stats = None;
         loop {
             let Report::Statistics(stats) = self.rx.recv().unwrap() else {
                 // BER test has finished
-                let last_stats = last_stats.unwrap();
+                let Some(last_stats) = last_stats else {
+                    // The BER test was cancelled before reporting any statistics
+                    return Ok(());
+                };
                 if let Some(f) = &mut self.output_file {
-                    writeln!(f, "{}", &Self::format_progress(&last_stats, false))?;
+                    writeln!(
+                        f,
+                        "{}",
+                        &Self::format_progress(&last_stats, false, self.columns)
+                    )?;
                 }
                 if let Some(f) = &mut self.output_file_ldpc {
-                    writeln!(f, "{}", &Self::format_progress(&last_stats, true))?;
+                    writeln!(
+                        f,
+                        "{}",
+                        &Self::format_progress(&last_stats, true, self.columns)
+                    )?;
                 }
                 return Ok(());
             };
             if let Some(s) = &last_stats {
                 if s.ebn0_db != stats.ebn0_db {
                     if let Some(f) = &mut self.output_file {
-                        writeln!(f, "{}", &Self::format_progress(s, false))?;
+                        writeln!(f, "{}", &Self::format_progress(s, false, self.columns))?;
                     }
                     if let Some(f) = &mut self.output_file_ldpc {
-                        writeln!(f, "{}", &Self::format_progress(s, true))?;
+                        writeln!(f, "{}", &Self::format_progress(s, true, self.columns))?;
                     }
                 }
             }
@@ -289,24 +1497,47 @@ impl Progress {
                 _ => (),
             };
             self.term
-                .write_line(&Self::format_progress(&stats, false))?;
+                .write_line(&Self::format_progress(&stats, false, self.columns))?;
             last_stats = Some(stats);
         }
     }
 
-    fn format_header() -> &'static str {
-        "  Eb/N0 |   Frames | Bit errs | Frame er | False de |     BER |     FER | Avg iter | Avg corr | Throughp | Elapsed\n\
-         --------|----------|----------|----------|----------|---------|---------|----------|----------|----------|----------"
+    fn format_header(columns: Columns) -> String {
+        let (max_iter, max_iter_line) = if columns.max_iter {
+            (" Max iter |", "----------|")
+        } else {
+            ("", "")
+        };
+        let (max_llr, max_llr_line) = if columns.max_llr {
+            (" Max LLR |", "---------|")
+        } else {
+            ("", "")
+        };
+        format!(
+            "  Eb/N0 |   Frames | Bit errs | Frame er | False de |     BER |     FER | BER 95% conf. int. | FER 95% conf. int. | Avg iter | Avg corr |{max_iter} Throughp |{max_llr} Elapsed\n\
+             --------|----------|----------|----------|----------|---------|---------|--------------------|--------------------|----------|----------|{max_iter_line}----------|{max_llr_line}----------"
+        )
     }
 
-    fn format_progress(stats: &Statistics, force_ldpc: bool) -> String {
+    fn format_progress(stats: &Statistics, force_ldpc: bool, columns: Columns) -> String {
         let code_stats = match (force_ldpc, &stats.bch) {
             (true, _) => &stats.ldpc,
             (false, Some(bch)) => bch,
             (false, None) => &stats.ldpc,
         };
+        let max_iter = if columns.max_iter {
+            format!(" {:8} |", stats.max_iterations)
+        } else {
+            String::new()
+        };
+        let max_llr = match (columns.max_llr, stats.max_llr_magnitude) {
+            (false, _) => String::new(),
+            (true, Some(m)) => format!(" {m:7.1} |"),
+            (true, None) => format!(" {:>7} |", "N/A"),
+        };
+        let interval = |(low, high): (f64, f64)| format!("[{low:7.2e}, {high:7.2e}]");
         format!(
-            "{:7.2} | {:8} | {:8} | {:8} | {:8} | {:7.2e} | {:7.2e} | {:8.1} | {:8.1} | {:8.3} | {}",
+            "{:7.2} | {:8} | {:8} | {:8} | {:8} | {:7.2e} | {:7.2e} | {} | {} | {:8.1} | {:8.1} |{} {:8.3} |{} {}",
             stats.ebn0_db,
             stats.num_frames,
             code_stats.bit_errors,
@@ -314,9 +1545,13 @@ impl Progress {
             stats.false_decodes,
             code_stats.ber,
             code_stats.fer,
+            interval(code_stats.ber_confidence_interval),
+            interval(code_stats.fer_confidence_interval),
             stats.average_iterations,
             code_stats.average_iterations_correct,
+            max_iter,
             stats.throughput_mbps,
+            max_llr,
             humantime::format_duration(Duration::from_secs(stats.elapsed.as_secs()))
         )
     }
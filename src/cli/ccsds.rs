@@ -16,9 +16,20 @@
 //! $ ldpc-toolbox ccsds --rate 1/2 --block-size 1024 --girth
 //! Code girth = 6
 //! ```
+//! The alists of all the AR4JA codes are written to the directory `codes`,
+//! together with an `index.json` file describing them, with
+//! ```shell
+//! $ ldpc-toolbox ccsds --all codes
+//! ```
+//! The files are named after the code specifications (see
+//! [`crate::codes::cache`]), so for instance the r=1/2, k=1024 code is written
+//! to `codes/ar4ja_1-2_1024.alist`.
 
 use crate::cli::*;
-use crate::codes::ccsds::{AR4JACode, AR4JAInfoSize, AR4JARate};
+use crate::codes::{
+    ccsds::{AR4JACode, AR4JAInfoSize, AR4JARate},
+    Code,
+};
 use clap::Parser;
 
 type Error = String;
@@ -29,38 +40,57 @@ type Result<T> = std::result::Result<T, Error>;
 #[command(about = "Generates the alist of CCSDS LDPCs")]
 pub struct Args {
     /// Coding rate
-    #[arg(short, long)]
-    rate: String,
+    #[arg(short, long, required_unless_present = "all")]
+    rate: Option<String>,
 
     /// Information block size (k)
-    #[arg(long)]
-    block_size: usize,
+    #[arg(long, required_unless_present = "all")]
+    block_size: Option<usize>,
 
     /// Performs girth calculation
     #[arg(long)]
     girth: bool,
+
+    /// Writes the alists of all the codes to a directory
+    #[arg(
+        long,
+        value_name = "DIR",
+        conflicts_with_all = ["rate", "block_size", "girth"]
+    )]
+    all: Option<String>,
+
+    #[command(flatten)]
+    alist: AlistArgs,
 }
 
 impl Args {
     fn code(&self) -> Result<AR4JACode> {
-        let rate = match &*self.rate {
-            "1/2" => AR4JARate::R1_2,
-            "2/3" => AR4JARate::R2_3,
-            "4/5" => AR4JARate::R4_5,
-            r => return Err(format!("Invalid code rate {}", r)),
-        };
-        let info_size = match self.block_size {
-            1024 => AR4JAInfoSize::K1024,
-            4096 => AR4JAInfoSize::K4096,
-            16384 => AR4JAInfoSize::K16384,
-            s => return Err(format!("Invalid information block size k = {}", s)),
-        };
+        // clap ensures that these are present when --all is not used
+        let rate = self.rate.as_deref().unwrap();
+        let block_size = self.block_size.unwrap();
+        let rate =
+            AR4JARate::from_rate(rate).ok_or_else(|| format!("Invalid code rate {}", rate))?;
+        let info_size = AR4JAInfoSize::from_k(block_size)
+            .ok_or_else(|| format!("Invalid information block size k = {}", block_size))?;
         Ok(AR4JACode::new(rate, info_size))
     }
+
+    fn all_codes() -> impl Iterator<Item = (String, Box<dyn Code>)> {
+        enum_iterator::all::<AR4JARate>().flat_map(|rate| {
+            enum_iterator::all::<AR4JAInfoSize>().map(move |info_size| {
+                let spec = format!("ar4ja:{rate}:{}", info_size.k());
+                let code: Box<dyn Code> = Box::new(AR4JACode::new(rate, info_size));
+                (spec, code)
+            })
+        })
+    }
 }
 
 impl Run for Args {
     fn run(&self) -> std::result::Result<(), Box<dyn std::error::Error>> {
+        if let Some(dir) = &self.all {
+            return write_code_family(dir, Self::all_codes(), &self.alist.options());
+        }
         let h = self.code()?.h();
         if self.girth {
             if let Some(g) = h.girth() {
@@ -69,7 +99,7 @@ impl Run for Args {
                 println!("Code girth is infinite");
             }
         } else {
-            print!("{}", h.alist());
+            print!("{}", h.alist_with(&self.alist.options()));
         }
         Ok(())
     }
@@ -19,12 +19,15 @@ use clap::Parser;
 /// CCSDS CLI arguments.
 #[derive(Debug, Parser)]
 #[command(about = "Generates the alist of CCSDS C2 LDPC")]
-pub struct Args {}
+pub struct Args {
+    #[command(flatten)]
+    alist: AlistArgs,
+}
 
 impl Run for Args {
     fn run(&self) -> std::result::Result<(), Box<dyn std::error::Error>> {
         let h = C2Code::new().h();
-        print!("{}", h.alist());
+        print!("{}", h.alist_with(&self.alist.options()));
         Ok(())
     }
 }
@@ -0,0 +1,79 @@
+//! CCSDS TC CLI subcommand
+//!
+//! This subcommand can be used to generate the short block length LDPC codes
+//! described in the CCSDS TC Synchronization and Channel Coding Blue Book
+//! standard. It will print the alist of the parity check matrix to `stdout`
+//! and optionally compute and print the girth of the Tanner graph. See
+//! [`crate::codes::ccsds_tc`] for more information about these codes.
+//!
+//! # Examples
+//! The (128, 64) parity check matrix can be generated with
+//! ```shell
+//! $ ldpc-toolbox ccsds-tc --block-size 64
+//! ```
+//! The alists of all the TC codes are written to the directory `codes`,
+//! together with an `index.json` file describing them, with
+//! ```shell
+//! $ ldpc-toolbox ccsds-tc --all codes
+//! ```
+
+use crate::cli::*;
+use crate::codes::{ccsds_tc::TcCode, Code};
+use clap::Parser;
+
+/// CCSDS TC CLI arguments.
+#[derive(Debug, Parser)]
+#[command(about = "Generates the alist of CCSDS TC LDPCs")]
+pub struct Args {
+    /// Information block size (k)
+    #[arg(long, required_unless_present = "all")]
+    block_size: Option<usize>,
+
+    /// Performs girth calculation
+    #[arg(long)]
+    girth: bool,
+
+    /// Writes the alists of all the codes to a directory
+    #[arg(
+        long,
+        value_name = "DIR",
+        conflicts_with_all = ["block_size", "girth"]
+    )]
+    all: Option<String>,
+
+    #[command(flatten)]
+    alist: AlistArgs,
+}
+
+impl Args {
+    fn all_codes() -> impl Iterator<Item = (String, Box<dyn Code>)> {
+        enum_iterator::all::<TcCode>().map(|code| {
+            let spec = format!("ccsds-tc:{}", code.k());
+            let code: Box<dyn Code> = Box::new(code);
+            (spec, code)
+        })
+    }
+}
+
+impl Run for Args {
+    fn run(&self) -> std::result::Result<(), Box<dyn std::error::Error>> {
+        if let Some(dir) = &self.all {
+            return write_code_family(dir, Self::all_codes(), &self.alist.options());
+        }
+        // clap ensures that the block size is present when --all is not used
+        let block_size = self.block_size.unwrap();
+        let h = TcCode::from_k(block_size)
+            .ok_or_else(|| format!("Invalid information block size k = {}", block_size))?
+            .h();
+        if self.girth {
+            if let Some(g) = h.girth() {
+                println!("Code girth = {}", g);
+            } else {
+                println!("Code girth is infinite");
+            }
+        } else {
+            print!("{}", h.alist_with(&self.alist.options()));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,166 @@
+//! Convert CLI subcommand.
+//!
+//! This command converts a parity check matrix given as an alist or
+//! MatrixMarket file to other formats and prints the result to `stdout`. The
+//! format of the input file is given by `--input-format`. By default, files
+//! with the `.mtx` extension are read as MatrixMarket files and any other
+//! files are read as alists. The supported output formats are:
+//!
+//! - `alist`. The matrix is printed as an alist. Together with the alist
+//!   options, this can also be used to change the formatting of an alist file.
+//!   An alist that holds the transpose of the parity check matrix can be fixed
+//!   with `--alist-orientation transposed`. Since the weights in the header of
+//!   the output alist are computed from the matrix, this also fixes alists
+//!   whose header gives wrong weights, which are accepted with a warning
+//!   unless `--alist-validation strict` is used.
+//!
+//! - `mtx`. The MatrixMarket coordinate format, which is used by tools such as
+//!   MATLAB, SciPy and aff3ct.
+//!
+//! - `dvbs2-tables`. The table format used in Annexes B and C of the DVB-S2
+//!   standard, in which each line lists the parity bit addresses of a group of
+//!   360 information bits. This format can be used with decoders that take
+//!   DVB-S2 style tables. The matrix must have the structure of the DVB-S2
+//!   codes (see [`SparseMatrix::to_dvbs2_tables`]), or an error is returned.
+//!
+//! # Examples
+//! The DVB-S2 tables for a code are printed with
+//! ```shell
+//! $ ldpc-toolbox convert --format dvbs2-tables code.alist
+//! ```
+//! An alist file is converted to MatrixMarket and back with
+//! ```shell
+//! $ ldpc-toolbox convert --format mtx code.alist > code.mtx
+//! $ ldpc-toolbox convert code.mtx > code.alist
+//! ```
+
+use crate::{
+    cli::{AlistArgs, AlistInputArgs, Run},
+    sparse::SparseMatrix,
+};
+use clap::Parser;
+use std::error::Error;
+
+/// Convert CLI arguments.
+#[derive(Debug, Parser)]
+#[command(about = "Converts a parity check matrix to other formats")]
+pub struct Args {
+    /// alist or MatrixMarket file for the code
+    input: String,
+    /// Input format ("alist" or "mtx") [default: based on the file extension]
+    #[arg(long)]
+    input_format: Option<InputFormat>,
+    /// Output format ("alist", "mtx" or "dvbs2-tables")
+    #[arg(long, default_value = "alist")]
+    format: Format,
+    #[command(flatten)]
+    alist_input: AlistInputArgs,
+    #[command(flatten)]
+    output: AlistArgs,
+}
+
+/// Output format.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Format {
+    /// alist format.
+    Alist,
+    /// MatrixMarket format.
+    Mtx,
+    /// DVB-S2 table format.
+    Dvbs2Tables,
+}
+
+impl std::str::FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Format, String> {
+        Ok(match s {
+            "alist" => Format::Alist,
+            "mtx" => Format::Mtx,
+            "dvbs2-tables" => Format::Dvbs2Tables,
+            _ => return Err(format!("invalid format {s}")),
+        })
+    }
+}
+
+impl std::fmt::Display for Format {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Format::Alist => "alist",
+                Format::Mtx => "mtx",
+                Format::Dvbs2Tables => "dvbs2-tables",
+            }
+        )
+    }
+}
+
+/// Input format.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum InputFormat {
+    /// alist format.
+    Alist,
+    /// MatrixMarket format.
+    Mtx,
+}
+
+impl std::str::FromStr for InputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<InputFormat, String> {
+        Ok(match s {
+            "alist" => InputFormat::Alist,
+            "mtx" => InputFormat::Mtx,
+            _ => return Err(format!("invalid input format {s}")),
+        })
+    }
+}
+
+impl std::fmt::Display for InputFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                InputFormat::Alist => "alist",
+                InputFormat::Mtx => "mtx",
+            }
+        )
+    }
+}
+
+impl Args {
+    fn input_format(&self) -> InputFormat {
+        self.input_format.unwrap_or_else(|| {
+            match std::path::Path::new(&self.input)
+                .extension()
+                .and_then(|ext| ext.to_str())
+            {
+                Some("mtx") => InputFormat::Mtx,
+                _ => InputFormat::Alist,
+            }
+        })
+    }
+}
+
+impl Run for Args {
+    fn run(&self) -> Result<(), Box<dyn Error>> {
+        let h = match self.input_format() {
+            InputFormat::Alist => self.alist_input.read(&self.input)?,
+            InputFormat::Mtx => SparseMatrix::from_mtx(&std::fs::read_to_string(&self.input)?)?,
+        };
+        match self.format {
+            Format::Alist => print!("{}", h.alist_with(&self.output.options())),
+            Format::Mtx => print!("{}", h.mtx()),
+            Format::Dvbs2Tables => {
+                for row in h.to_dvbs2_tables()? {
+                    let row = row.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+                    println!("{}", row.join(" "));
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -21,9 +21,19 @@
 //! ```shell
 //! $ ldpc-toolbox dvbs2 --rate 1/2 --short
 //! ```
+//!
+//! The alists of all the codes, for both normal and short FECFRAMEs, are
+//! written to the directory `codes`, together with an `index.json` file
+//! describing them, with
+//! ```shell
+//! $ ldpc-toolbox dvbs2 --all codes
+//! ```
+//! The files are named after the code specifications (see
+//! [`crate::codes::cache`]), so for instance the r=1/2 short FECFRAME code is
+//! written to `codes/dvbs2_1-2_short.alist`.
 
 use crate::cli::*;
-use crate::codes::dvbs2::Code;
+use crate::codes::{self, dvbs2::Code};
 use clap::Parser;
 
 type Error = String;
@@ -34,8 +44,8 @@ type Result<T> = std::result::Result<T, Error>;
 #[command(about = "Generates the alist of DVB-S2 LDPCs")]
 pub struct Args {
     /// Coding rate
-    #[arg(short, long)]
-    rate: String,
+    #[arg(short, long, required_unless_present = "all")]
+    rate: Option<String>,
 
     /// Enables short FECFRAME
     #[arg(long)]
@@ -44,44 +54,57 @@ pub struct Args {
     /// Performs girth calculation
     #[arg(long)]
     girth: bool,
+
+    /// Writes the alists of all the codes to a directory
+    #[arg(
+        long,
+        value_name = "DIR",
+        conflicts_with_all = ["rate", "short", "girth"]
+    )]
+    all: Option<String>,
+
+    #[command(flatten)]
+    alist: AlistArgs,
 }
 
 impl Args {
     fn code(&self) -> Result<Code> {
-        match (&*self.rate, self.short) {
-            ("1/4", false) => Ok(Code::R1_4),
-            ("1/3", false) => Ok(Code::R1_3),
-            ("2/5", false) => Ok(Code::R2_5),
-            ("1/2", false) => Ok(Code::R1_2),
-            ("3/5", false) => Ok(Code::R3_5),
-            ("2/3", false) => Ok(Code::R2_3),
-            ("3/4", false) => Ok(Code::R3_4),
-            ("4/5", false) => Ok(Code::R4_5),
-            ("5/6", false) => Ok(Code::R5_6),
-            ("8/9", false) => Ok(Code::R8_9),
-            ("9/10", false) => Ok(Code::R9_10),
-            ("1/4", true) => Ok(Code::R1_4short),
-            ("1/3", true) => Ok(Code::R1_3short),
-            ("2/5", true) => Ok(Code::R2_5short),
-            ("1/2", true) => Ok(Code::R1_2short),
-            ("3/5", true) => Ok(Code::R3_5short),
-            ("2/3", true) => Ok(Code::R2_3short),
-            ("3/4", true) => Ok(Code::R3_4short),
-            ("4/5", true) => Ok(Code::R4_5short),
-            ("5/6", true) => Ok(Code::R5_6short),
-            ("8/9", true) => Ok(Code::R8_9short),
-            _ => Err(self.code_error()),
-        }
+        // clap ensures that the rate is present when --all is not used
+        Code::from_rate(self.rate.as_deref().unwrap(), self.short).ok_or_else(|| self.code_error())
     }
 
     fn code_error(&self) -> String {
         let fecframe = if self.short { "short" } else { "normal" };
-        format!("Invalid rate {} for {} FECFRAME", self.rate, fecframe)
+        format!(
+            "Invalid rate {} for {} FECFRAME",
+            self.rate.as_deref().unwrap(),
+            fecframe
+        )
+    }
+
+    fn all_codes() -> impl Iterator<Item = (String, Box<dyn codes::Code>)> {
+        const RATES: [&str; 11] = [
+            "1/4", "1/3", "2/5", "1/2", "3/5", "2/3", "3/4", "4/5", "5/6", "8/9", "9/10",
+        ];
+        [false, true].into_iter().flat_map(|short| {
+            RATES.into_iter().filter_map(move |rate| {
+                let code: Box<dyn codes::Code> = Box::new(Code::from_rate(rate, short)?);
+                let spec = if short {
+                    format!("dvbs2:{rate}:short")
+                } else {
+                    format!("dvbs2:{rate}")
+                };
+                Some((spec, code))
+            })
+        })
     }
 }
 
 impl Run for Args {
     fn run(&self) -> std::result::Result<(), Box<dyn std::error::Error>> {
+        if let Some(dir) = &self.all {
+            return write_code_family(dir, Self::all_codes(), &self.alist.options());
+        }
         let h = self.code()?.h();
         if self.girth {
             if let Some(g) = h.girth() {
@@ -90,7 +113,7 @@ impl Run for Args {
                 println!("Code girth is infinite");
             }
         } else {
-            print!("{}", h.alist());
+            print!("{}", h.alist_with(&self.alist.options()));
         }
         Ok(())
     }
@@ -0,0 +1,70 @@
+//! DVB-S2X CLI subcommand
+//!
+//! This subcommand can be used to generate the LDPC codes used in the DVB-S2X
+//! standard from their tables. It will print the alist of the parity check
+//! matrix to `stdout` and optionally compute and print the girth of the Tanner
+//! graph. The tables are not included in this crate, so they must be given in
+//! a text file. See [`crate::codes::dvbs2x`] for the format of the file.
+//!
+//! # Examples
+//! The parity check matrix of the rate 1/3 code for the medium FECFRAMEs,
+//! whose table is in `r1_3.txt`, can be generated with
+//! ```shell
+//! $ ldpc-toolbox dvbs2x --rate 1/3 --frame medium r1_3.txt
+//! ```
+//! Its girth is computed with
+//! ```shell
+//! $ ldpc-toolbox dvbs2x --rate 1/3 --frame medium --girth r1_3.txt
+//! ```
+//!
+//! The code can also be used in other subcommands with the code specification
+//! `dvbs2x:1/3:medium:r1_3.txt` (see [`crate::codes::cache`]).
+
+use crate::cli::*;
+use crate::codes::dvbs2x::{Code, FrameLen, Rate};
+use clap::Parser;
+
+/// DVB-S2X CLI arguments.
+#[derive(Debug, Parser)]
+#[command(about = "Generates the alist of DVB-S2X LDPCs from their tables")]
+pub struct Args {
+    /// Text file with the table of the code
+    table: String,
+
+    /// Code rate (for example "2/9" or "132/180")
+    #[arg(long)]
+    rate: String,
+
+    /// FECFRAME length ("normal", "medium" or "short")
+    #[arg(long, default_value = "normal")]
+    frame: FrameLen,
+
+    /// Performs girth calculation
+    #[arg(long)]
+    girth: bool,
+
+    #[command(flatten)]
+    alist: AlistArgs,
+}
+
+impl Run for Args {
+    fn run(&self) -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let rate = Rate::from_rate(&self.rate, self.frame).ok_or_else(|| {
+            format!(
+                "there is no DVB-S2X code of rate {} for {} FECFRAMEs",
+                self.rate, self.frame
+            )
+        })?;
+        let h = Code::from_file(rate, &self.table)?.h();
+        if self.girth {
+            if let Some(g) = h.girth() {
+                println!("Code girth = {}", g);
+            } else {
+                println!("Code girth is infinite");
+            }
+        } else {
+            print!("{}", h.alist_with(&self.alist.options()));
+        }
+        Ok(())
+    }
+}
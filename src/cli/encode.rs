@@ -1,10 +1,40 @@
 //! Encode CLI subcommand.
 //!
 //! This command can be used to encode using a systematic LDPC code.
+//!
+//! By default, the codewords are written in the order given by the columns of
+//! the parity check matrix. A different transmission order can be selected
+//! with `--output-order` (`parity-first` or a comma-separated permutation) or
+//! with `--output-permutation`, which reads the permutation from a file. The
+//! bit in position `j` of each output word is the bit in position
+//! `permutation[j]` of the codeword. The puncturing pattern, if any, is applied
+//! to the reordered codeword.
+//!
+//! Alists whose matrix has more rows than columns are transposed, with a
+//! warning, since they probably hold the transpose of the parity check matrix.
+//! This can be controlled with `--alist-orientation`.
+//!
+//! With `--qc-generator <size>`, the code file is not an alist, but the
+//! systematic quasi-cyclic generator matrix of the code, given by the first
+//! rows of its circulants of size `<size>` in hexadecimal (see
+//! [`QcGenerator::from_hex`]). The parity bits are then computed directly with
+//! the circulants.
+//!
+//! # Examples
+//! The parity bits are transmitted first with
+//! ```shell
+//! $ ldpc-toolbox encode --output-order parity-first code.alist info.u8 codewords.u8
+//! ```
+//! A code whose generator has circulants of size 511 is encoded with
+//! ```shell
+//! $ ldpc-toolbox encode --qc-generator 511 generator.txt info.u8 codewords.u8
+//! ```
 
-use super::ber::parse_puncturing_pattern;
 use crate::{
-    cli::Run, encoder::Encoder, gf2::GF2, simulation::puncturing::Puncturer, sparse::SparseMatrix,
+    cli::{AlistInputArgs, Run},
+    encoder::{erasure, qc_generator::QcGenerator, Encoder, OutputOrder},
+    gf2::GF2,
+    simulation::puncturing::PuncturingPattern,
 };
 use clap::Parser;
 use ndarray::Array1;
@@ -19,30 +49,77 @@ use std::{
 #[derive(Debug, Parser)]
 #[command(about = "Performs LDPC encoding")]
 pub struct Args {
-    /// alist file for the code
+    /// alist file for the code (or generator file with --qc-generator)
     alist: String,
     /// input file (information words as unpacked bits)
     input: String,
     /// input file (punctured words as unpacked bits)
     output: String,
-    /// Puncturing pattern (format "1,1,1,0")
+    /// Puncturing pattern (format "1,1,1,0" or "3*1,0" by blocks, "bits:1,1,0"
+    /// per bit, "nr:<Zc>" for 5G NR or "ar4ja")
     #[structopt(long)]
-    puncturing: Option<String>,
+    puncturing: Option<PuncturingPattern>,
+    /// Use encoding by erasure decoding (supports parity check matrices whose
+    /// last columns are not invertible)
+    #[structopt(long)]
+    erasure: bool,
+    /// Read the code file as a systematic quasi-cyclic generator matrix with
+    /// circulants of this size given in hexadecimal
+    #[structopt(long, conflicts_with_all = ["erasure", "alist_orientation"])]
+    qc_generator: Option<usize>,
+    /// Order of the output bits ("natural", "parity-first" or a comma-separated
+    /// permutation)
+    #[structopt(long, default_value = "natural", conflicts_with = "output_permutation")]
+    output_order: OutputOrder,
+    /// File with the permutation of the output bits (positions separated by
+    /// whitespace or commas)
+    #[structopt(long)]
+    output_permutation: Option<String>,
+    #[command(flatten)]
+    alist_input: AlistInputArgs,
+}
+
+enum AnyEncoder {
+    Systematic(Encoder),
+    Erasure(erasure::Encoder),
 }
 
 impl Run for Args {
     fn run(&self) -> Result<(), Box<dyn Error>> {
-        let puncturer = if let Some(p) = self.puncturing.as_ref() {
-            Some(Puncturer::new(&parse_puncturing_pattern(p)?))
-        } else {
-            None
-        };
-        let h = SparseMatrix::from_alist(&std::fs::read_to_string(&self.alist)?)?;
         let mut input = File::open(&self.input)?;
         let mut output = File::create(&self.output)?;
-        let encoder = Encoder::from_h(&h)?;
-        let n = h.num_cols();
-        let k = n - h.num_rows();
+        let order = match &self.output_permutation {
+            Some(file) => OutputOrder::Permutation(
+                std::fs::read_to_string(file)?
+                    .split(|c: char| c.is_whitespace() || c == ',')
+                    .filter(|s| !s.is_empty())
+                    .map(|s| s.parse())
+                    .collect::<Result<_, _>>()?,
+            ),
+            None => self.output_order.clone(),
+        };
+        let (encoder, k, n) = if let Some(size) = self.qc_generator {
+            let generator = QcGenerator::from_hex(size, &std::fs::read_to_string(&self.alist)?)?;
+            let (k, n) = (generator.message_length(), generator.codeword_length());
+            let encoder = Encoder::from_qc_generator(generator).with_output_order(order)?;
+            (AnyEncoder::Systematic(encoder), k, n)
+        } else {
+            let h = self.alist_input.read(&self.alist)?;
+            let n = h.num_cols();
+            if self.erasure {
+                let encoder = erasure::Encoder::from_h(&h).with_output_order(order)?;
+                let k = encoder.message_length();
+                (AnyEncoder::Erasure(encoder), k, n)
+            } else {
+                let encoder = Encoder::from_h(&h)?.with_output_order(order)?;
+                (AnyEncoder::Systematic(encoder), n - h.num_rows(), n)
+            }
+        };
+        let puncturer = self
+            .puncturing
+            .as_ref()
+            .map(|pattern| pattern.puncturer(n))
+            .transpose()?;
         let mut information_word = vec![0; k];
         let mut codeword_buf = vec![0; n];
         loop {
@@ -57,7 +134,10 @@ impl Run for Args {
                     GF2::zero()
                 }
             }));
-            let codeword = encoder.encode(&word);
+            let codeword = match &encoder {
+                AnyEncoder::Systematic(encoder) => encoder.encode(&word),
+                AnyEncoder::Erasure(encoder) => encoder.encode(&word),
+            };
             let codeword = match &puncturer {
                 Some(p) => p.puncture(&codeword)?,
                 None => codeword,
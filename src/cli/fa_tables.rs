@@ -0,0 +1,57 @@
+//! Finite alphabet decoder tables CLI subcommand
+//!
+//! This subcommand designs the lookup tables for a finite alphabet decoder
+//! using density evolution and the information bottleneck method. It prints
+//! the tables to `stdout` in the text format used by
+//! [`Tables`](crate::decoder::finite_alphabet::Tables). For more details, see
+//! [`crate::decoder::finite_alphabet`].
+//!
+//! # Examples
+//! The tables for a 4-bit decoder for the CCSDS r=1/2, k=1024 code designed
+//! for an Eb/N0 of 1.5 dB can be generated with
+//! ```shell
+//! $ ldpc-toolbox fa-tables --ebn0 1.5 --bits 4 ar4ja_1_2_1024.alist > tables.txt
+//! ```
+
+use crate::cli::*;
+use crate::decoder::finite_alphabet::Config;
+use crate::sparse::SparseMatrix;
+use clap::Parser;
+use std::error::Error;
+
+/// Finite alphabet decoder tables CLI arguments.
+#[derive(Debug, Parser)]
+#[command(about = "Designs lookup tables for finite alphabet decoders")]
+pub struct Args {
+    /// alist file for the code
+    alist: String,
+    /// Eb/N0 (dB) for which to design the tables
+    #[arg(long)]
+    ebn0: f64,
+    /// Number of bits per message
+    #[arg(long, default_value = "4")]
+    bits: u32,
+    /// Number of iterations
+    #[arg(long, default_value = "50")]
+    iterations: usize,
+    /// Number of density evolution samples
+    #[arg(long, default_value = "100000")]
+    samples: usize,
+    /// Seed
+    #[arg(long, default_value = "0")]
+    seed: u64,
+}
+
+impl Run for Args {
+    fn run(&self) -> Result<(), Box<dyn Error>> {
+        let h = SparseMatrix::from_alist(&std::fs::read_to_string(&self.alist)?)?;
+        let config = Config {
+            ebn0_db: self.ebn0,
+            bits: self.bits,
+            iterations: self.iterations,
+            samples: self.samples,
+        };
+        print!("{}", config.run(&h, self.seed)?);
+        Ok(())
+    }
+}
@@ -0,0 +1,102 @@
+//! Fetch codes CLI subcommand
+//!
+//! This subcommand manages the local cache of the corpus of public parity
+//! check matrices (see [`crate::codes::corpus`]). Without `--download`, it
+//! only reports which matrices are cached and verifies them, without accessing
+//! the network. With `--download`, the matrices that are not cached are
+//! downloaded using `curl`. The cached matrices can be used in other
+//! subcommands with the code specification `corpus:<name>`.
+//!
+//! # Examples
+//! The catalog and the status of each matrix are listed with
+//! ```shell
+//! $ ldpc-toolbox fetch-codes
+//! ```
+//! A matrix is downloaded with
+//! ```shell
+//! $ ldpc-toolbox fetch-codes --download mackay-96.3.963
+//! ```
+//! and then it can be used, for instance, in a report
+//! ```shell
+//! $ ldpc-toolbox report corpus:mackay-96.3.963
+//! ```
+//! All the matrices in the catalog are downloaded with
+//! ```shell
+//! $ ldpc-toolbox fetch-codes --download --all
+//! ```
+
+use crate::{
+    cli::*,
+    codes::corpus::{Corpus, CATALOG},
+};
+use clap::Parser;
+
+/// Fetch codes CLI arguments.
+#[derive(Debug, Parser)]
+#[command(about = "Downloads and manages public parity check matrices")]
+pub struct Args {
+    /// Names of the matrices
+    names: Vec<String>,
+    /// Select all the matrices in the catalog
+    #[arg(long)]
+    all: bool,
+    /// Download the selected matrices that are not cached
+    #[arg(long)]
+    download: bool,
+    /// Cache directory (by default, $LDPC_TOOLBOX_CORPUS or
+    /// $XDG_CACHE_HOME/ldpc-toolbox)
+    #[arg(long)]
+    cache_dir: Option<String>,
+}
+
+impl Run for Args {
+    fn run(&self) -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let corpus = match &self.cache_dir {
+            Some(dir) => Corpus::new(dir),
+            None => Corpus::with_default_dir()?,
+        };
+        if self.names.is_empty() && !self.all {
+            println!("Cache directory: {}", corpus.dir().display());
+            for entry in CATALOG {
+                let status = if corpus.is_cached(entry.name) {
+                    "cached"
+                } else {
+                    "not cached"
+                };
+                println!(
+                    "{} ({}x{}, {}): {status}",
+                    entry.name, entry.num_rows, entry.num_cols, entry.collection
+                );
+            }
+            return Ok(());
+        }
+        let names = if self.all {
+            CATALOG.iter().map(|e| e.name.to_string()).collect()
+        } else {
+            self.names.clone()
+        };
+        let mut failed = 0;
+        for name in &names {
+            let result = if self.download {
+                corpus.fetch(name)
+            } else {
+                corpus.load(name)
+            };
+            match result {
+                Ok(h) => println!(
+                    "{name}: ok, fingerprint {:016x} ({})",
+                    h.fingerprint(),
+                    corpus.path(name).display()
+                ),
+                Err(e) => {
+                    eprintln!("{name}: {e}");
+                    failed += 1;
+                }
+            }
+        }
+        if failed > 0 {
+            return Err(format!("{failed} of {} codes are not available", names.len()).into());
+        }
+        Ok(())
+    }
+}
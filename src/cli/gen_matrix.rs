@@ -0,0 +1,115 @@
+//! Generator matrix CLI subcommand.
+//!
+//! This subcommand computes the generator matrix G of the code given by the
+//! alist of its parity check matrix H and prints it to `stdout`. The generator
+//! matrix has size k x n, and the codeword corresponding to a message m (as a
+//! row vector) is m G (see [`Encoder::generator_matrix`]). This can be used
+//! with external encoders that take the generator matrix of the code.
+//!
+//! The encoder uses the first k columns of H as systematic, so G has the form
+//! [I | P]. If the last n - k columns of H are not invertible, the columns of H
+//! are permuted to find a systematic set (see
+//! [`Encoder::from_h_with_permutation`]). In this case, the columns of G are
+//! still given in the order of the columns of H, so the identity part of G is
+//! spread over the systematic columns.
+//!
+//! The supported output formats are:
+//!
+//! - `alist`. The matrix is printed as an alist, using the alist output
+//!   options. Note that the generator matrix of an LDPC code is usually dense.
+//!
+//! - `dense`. Each row of the matrix is printed in a line, as the values `0`
+//!   and `1` separated by spaces. This format can be read by tools such as
+//!   MATLAB and NumPy.
+//!
+//! # Examples
+//! The generator matrix of a code is printed in the dense format with
+//! ```shell
+//! $ ldpc-toolbox gen-matrix --format dense code.alist > generator.txt
+//! ```
+
+use crate::{
+    cli::{AlistArgs, AlistInputArgs, Run},
+    encoder::Encoder,
+    gf2::GF2,
+};
+use clap::Parser;
+use num_traits::One;
+use std::{error::Error, io::Write};
+
+/// Generator matrix CLI arguments.
+#[derive(Debug, Parser)]
+#[command(about = "Computes the generator matrix of a code")]
+pub struct Args {
+    /// alist file for the code
+    alist: String,
+    /// Output format ("alist" or "dense")
+    #[arg(long, default_value = "alist")]
+    format: Format,
+    #[command(flatten)]
+    input: AlistInputArgs,
+    #[command(flatten)]
+    output: AlistArgs,
+}
+
+/// Output format.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Format {
+    /// alist format.
+    Alist,
+    /// Dense text format.
+    Dense,
+}
+
+impl std::str::FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Format, String> {
+        Ok(match s {
+            "alist" => Format::Alist,
+            "dense" => Format::Dense,
+            _ => return Err(format!("invalid format {s}")),
+        })
+    }
+}
+
+impl std::fmt::Display for Format {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Format::Alist => "alist",
+                Format::Dense => "dense",
+            }
+        )
+    }
+}
+
+impl Run for Args {
+    fn run(&self) -> Result<(), Box<dyn Error>> {
+        let h = self.input.read(&self.alist)?;
+        let encoder = Encoder::from_h_with_permutation(&h)?;
+        match self.format {
+            Format::Alist => print!(
+                "{}",
+                encoder
+                    .generator_matrix_sparse()
+                    .alist_with(&self.output.options())
+            ),
+            Format::Dense => {
+                let mut stdout = std::io::BufWriter::new(std::io::stdout().lock());
+                for row in encoder.generator_matrix().rows() {
+                    let line = row
+                        .iter()
+                        .map(|x: &GF2| if x.is_one() { "1" } else { "0" })
+                        .collect::<Vec<_>>()
+                        .join(" ");
+                    writeln!(stdout, "{line}")?;
+                }
+                stdout.flush()?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -3,7 +3,8 @@
 //! This subcommand uses the MacKay-Neal pseudorandom construction to build
 //! an LDPC parity check matrix. It runs the MacKay-Neal algorithm and,
 //! if the construction is successful, prints to `stdout` the alist of the
-//! parity check matrix. For more details about this construction, see
+//! parity check matrix. If `stderr` is a terminal, the progress of the
+//! construction is shown in it. For more details about this construction, see
 //! [`crate::mackay_neal`].
 //!
 //! # Examples
@@ -75,6 +76,8 @@ pub struct Args {
     /// Try several seeds in parallel
     #[structopt(long)]
     search: bool,
+    #[command(flatten)]
+    alist: AlistArgs,
 }
 
 impl Args {
@@ -100,15 +103,20 @@ impl Run for Args {
     fn run(&self) -> Result<(), Box<dyn Error>> {
         let conf = self.config();
         let h = if self.search {
-            let (seed, hh) = conf
-                .search(self.seed, self.seed_trials)
-                .ok_or("no solution found")?;
+            let (seed, hh) = with_progress("Trying seeds", |reporter| match reporter {
+                Some(reporter) => conf.search_with_progress(self.seed, self.seed_trials, reporter),
+                None => conf.search(self.seed, self.seed_trials),
+            })
+            .ok_or("no solution found")?;
             eprintln!("seed = {}", seed);
             hh
         } else {
-            conf.run(self.seed)?
+            with_progress("Placing columns", |reporter| match reporter {
+                Some(reporter) => conf.run_with_progress(self.seed, reporter),
+                None => conf.run(self.seed),
+            })?
         };
-        println!("{}", h.alist());
+        println!("{}", h.alist_with(&self.alist.options()));
         Ok(())
     }
 }
@@ -0,0 +1,77 @@
+//! Min-sum CLI subcommand
+//!
+//! This subcommand computes the min-sum check node reduction of sets of
+//! variable messages read from a text file, in order to generate test vectors
+//! for hardware implementations of check node units. See
+//! [`crate::decoder::min_sum`] for more information.
+//!
+//! Each line of the input file contains the messages received by a check node,
+//! separated by whitespace or commas. Empty lines and everything after a `#`
+//! are ignored. For each set of messages, a line with `min1`, `min2`,
+//! `argmin` and `sign` (`0` for positive and `1` for negative) is printed. The
+//! outgoing check messages can be printed after these values with
+//! `--messages-out`.
+//!
+//! # Examples
+//! The reduction of the message sets in `messages.txt` is computed with
+//! ```shell
+//! $ ldpc-toolbox min-sum messages.txt
+//! ```
+//! The messages are read from `stdin` if the file name is `-`.
+
+use crate::{cli::*, decoder::min_sum::reduce};
+use clap::Parser;
+use std::io::{Read, Write};
+
+/// Min-sum CLI arguments.
+#[derive(Debug, Parser)]
+#[command(about = "Computes min-sum check node reductions")]
+pub struct Args {
+    /// Text file with one set of messages per line ("-" for stdin)
+    messages: String,
+    /// Also print the outgoing check messages
+    #[arg(long)]
+    messages_out: bool,
+}
+
+impl Run for Args {
+    fn run(&self) -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let input = if self.messages == "-" {
+            let mut s = String::new();
+            std::io::stdin().read_to_string(&mut s)?;
+            s
+        } else {
+            std::fs::read_to_string(&self.messages)?
+        };
+        let mut stdout = std::io::stdout().lock();
+        for (num, line) in input.lines().enumerate() {
+            let line = line.split_once('#').map_or(line, |(data, _)| data);
+            let messages = line
+                .split(|c: char| c.is_whitespace() || c == ',')
+                .filter(|s| !s.is_empty())
+                .map(|s| s.parse::<f64>())
+                .collect::<Result<Vec<f64>, _>>()
+                .map_err(|e| format!("line {}: {e}", num + 1))?;
+            if messages.is_empty() {
+                continue;
+            }
+            let reduction = reduce(&messages)
+                .ok_or_else(|| format!("line {}: at least two messages are needed", num + 1))?;
+            write!(
+                stdout,
+                "{} {} {} {}",
+                reduction.min1,
+                reduction.min2,
+                reduction.argmin,
+                u8::from(reduction.sign)
+            )?;
+            if self.messages_out {
+                for (j, &x) in messages.iter().enumerate() {
+                    write!(stdout, " {}", reduction.message(j, x))?;
+                }
+            }
+            writeln!(stdout)?;
+        }
+        Ok(())
+    }
+}
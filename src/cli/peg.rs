@@ -4,7 +4,9 @@
 //! build and LDPC parity check matrix. It runs the PEG algorithm and, if the
 //! construction is successful, prints to `stout` the alist of the parity check
 //! matrix. Optionally, it can also print to `stderr` the girth of the generated
-//! code. For more details about this construction, see [`crate::peg`].
+//! code. If `stderr` is a terminal, the progress of the construction and of the
+//! girth computation is shown in it. For more details about this construction,
+//! see [`crate::peg`].
 //!
 //! # Examples
 //! An r=1/2, n=16800 regular code with column weight 3 can be generated
@@ -16,9 +18,29 @@
 //! ```shell
 //! $ ldpc-toolbox peg 8100 16200 3 0 --girth > /dev/null
 //! ```
+//! A deterministic construction, which does not depend on the random number
+//! generator, can be obtained with
+//! ```shell
+//! $ ldpc-toolbox peg 8100 16200 3 0 --tie-breaking lowest-index
+//! ```
+//! The cycles with a low ACE can be avoided by means of an ACE constraint (see
+//! [`crate::peg`]). For instance, the edges that close cycles of length up
+//! to 12 with an ACE smaller than 4 are rejected with
+//! ```shell
+//! $ ldpc-toolbox peg 8100 16200 3 0 --ace-depth 6 --ace-min 4
+//! ```
+//! The minimum ACE of the cycles of each length up to 12 in the generated code
+//! is shown with `--ace-spectrum 12`.
+//!
+//! The `--alist-*` options control the variant of the alist format that is
+//! printed. For instance, an alist with zero-based indices and only the column
+//! section is obtained with
+//! ```shell
+//! $ ldpc-toolbox peg 8100 16200 3 0 --alist-index-base 0 --alist-sections columns-only
+//! ```
 
 use crate::cli::*;
-use crate::peg::Config;
+use crate::peg::{Config, TieBreaking};
 use clap::Parser;
 use std::error::Error;
 
@@ -34,9 +56,27 @@ pub struct Args {
     wc: usize,
     /// Seed
     seed: u64,
+    /// Tie-breaking rule ("random" or "lowest-index")
+    #[structopt(long, default_value = "random")]
+    tie_breaking: TieBreaking,
+    /// Prefer the edges that close the fewest shortest cycles
+    #[structopt(long)]
+    fewest_cycles: bool,
+    /// Depth of the ACE constraint (cycles of length up to 2 * depth)
+    #[structopt(long, requires = "ace_min")]
+    ace_depth: Option<usize>,
+    /// Minimum ACE of the cycles covered by the ACE constraint
+    #[structopt(long, requires = "ace_depth")]
+    ace_min: Option<usize>,
     /// Performs girth calculation
     #[structopt(long)]
     girth: bool,
+    /// Computes the ACE spectrum up to a maximum cycle length
+    #[structopt(long)]
+    ace_spectrum: Option<usize>,
+
+    #[command(flatten)]
+    alist: AlistArgs,
 }
 
 impl Args {
@@ -45,6 +85,10 @@ impl Args {
             nrows: self.num_rows,
             ncols: self.num_columns,
             wc: self.wc,
+            tie_breaking: self.tie_breaking,
+            fewest_cycles: self.fewest_cycles,
+            ace_depth: self.ace_depth.unwrap_or(0),
+            ace_min: self.ace_min.unwrap_or(0),
         }
     }
 }
@@ -52,14 +96,26 @@ impl Args {
 impl Run for Args {
     fn run(&self) -> Result<(), Box<dyn Error>> {
         let conf = self.config();
-        let h = conf.run(self.seed)?;
-        println!("{}", h.alist());
+        let h = with_progress("Placing columns", |reporter| match reporter {
+            Some(reporter) => conf.run_with_progress(self.seed, reporter),
+            None => conf.run(self.seed),
+        })?;
+        println!("{}", h.alist_with(&self.alist.options()));
         if self.girth {
-            match h.girth() {
+            let girth = with_progress("Computing girth", |reporter| match reporter {
+                Some(reporter) => h.girth_with_progress(usize::MAX, reporter),
+                None => h.girth(),
+            });
+            match girth {
                 Some(g) => eprintln!("Code girth = {}", g),
                 None => eprintln!("Code girth = infinity (there are no cycles)"),
             };
         }
+        if let Some(max_length) = self.ace_spectrum {
+            for (length, ace) in h.ace_spectrum(max_length) {
+                eprintln!("Minimum ACE of {length}-cycles = {ace}");
+            }
+        }
         Ok(())
     }
 }
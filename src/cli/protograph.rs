@@ -0,0 +1,112 @@
+//! Protograph CLI subcommand
+//!
+//! This constructs an LDPC code by lifting a protograph, selecting the
+//! permutations with a PEG-style construction that avoids short cycles, and
+//! prints to `stdout` the alist of the parity check matrix. Optionally, it can
+//! also print to `stderr` the girth of the generated code. For more details
+//! about this construction, see [`crate::protograph`].
+//!
+//! The protograph is given either as the path of a file containing the
+//! protomatrix, with one line per row and the number of edges of each entry
+//! separated by whitespace, or as `ar4ja:<rate>`, which selects the protograph
+//! of the CCSDS AR4JA code of that rate.
+//!
+//! # Examples
+//! A quasi-cyclic code with the AR4JA r=1/2 protograph and a circulant size of
+//! 128 can be generated with
+//! ```shell
+//! $ ldpc-toolbox protograph ar4ja:1/2 128 0
+//! ```
+//! The exponents of the base matrix of the quasi-cyclic code can be printed
+//! instead of the alist with
+//! ```shell
+//! $ ldpc-toolbox protograph ar4ja:1/2 128 0 --exponents
+//! ```
+//! A custom protograph can be lifted with random permutations with
+//! ```shell
+//! $ ldpc-toolbox protograph protomatrix.txt 100 0 --lifting random
+//! ```
+
+use crate::cli::*;
+use crate::codes::ccsds::AR4JARate;
+use crate::peg::TieBreaking;
+use crate::protograph::{Config, Lifting, Protograph};
+use clap::Parser;
+use std::error::Error;
+
+/// Protograph CLI arguments.
+#[derive(Debug, Parser)]
+#[command(about = "Generates LDPC codes by lifting a protograph")]
+pub struct Args {
+    /// Protomatrix file or "ar4ja:<rate>" (for instance "ar4ja:1/2")
+    protograph: String,
+    /// Lifting size
+    lifting_size: usize,
+    /// Seed
+    seed: u64,
+    /// Lifting ("circulant" or "random")
+    #[arg(long, default_value = "circulant")]
+    lifting: Lifting,
+    /// Tie-breaking rule ("random" or "lowest-index")
+    #[arg(long, default_value = "random")]
+    tie_breaking: TieBreaking,
+    /// Print the exponents of the base matrix instead of the alist (requires
+    /// circulant lifting)
+    #[arg(long)]
+    exponents: bool,
+    /// Performs girth calculation
+    #[arg(long)]
+    girth: bool,
+
+    #[command(flatten)]
+    alist: AlistArgs,
+}
+
+impl Args {
+    fn config(&self) -> Config {
+        Config {
+            lifting_size: self.lifting_size,
+            lifting: self.lifting,
+            tie_breaking: self.tie_breaking,
+        }
+    }
+
+    fn protograph(&self) -> Result<Protograph, Box<dyn Error>> {
+        Ok(match self.protograph.strip_prefix("ar4ja:") {
+            Some(rate) => Protograph::ar4ja(
+                AR4JARate::from_rate(rate).ok_or(format!("invalid AR4JA rate {rate}"))?,
+            ),
+            None => std::fs::read_to_string(&self.protograph)?.parse()?,
+        })
+    }
+}
+
+impl Run for Args {
+    fn run(&self) -> Result<(), Box<dyn Error>> {
+        if self.exponents && self.lifting != Lifting::Circulant {
+            return Err("--exponents requires circulant lifting".into());
+        }
+        let protograph = self.protograph()?;
+        let conf = self.config();
+        let h = if self.exponents {
+            let qc = conf.run_circulant(&protograph, self.seed)?;
+            print!("{}", qc.exponents());
+            qc.lift(self.lifting_size)
+        } else {
+            let h = conf.run(&protograph, self.seed)?;
+            println!("{}", h.alist_with(&self.alist.options()));
+            h
+        };
+        if self.girth {
+            let girth = with_progress("Computing girth", |reporter| match reporter {
+                Some(reporter) => h.girth_with_progress(usize::MAX, reporter),
+                None => h.girth(),
+            });
+            match girth {
+                Some(g) => eprintln!("Code girth = {}", g),
+                None => eprintln!("Code girth = infinity (there are no cycles)"),
+            };
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,222 @@
+//! Quantization sweep CLI subcommand.
+//!
+//! This subcommand runs a BER test for each of a family of quantized decoder
+//! configurations on a short grid of Eb/N0's, together with a BER test of a
+//! floating point reference decoder, and prints a table with the FER of each
+//! configuration and its Eb/N0 degradation with respect to the reference. See
+//! [`crate::simulation::quantization_sweep`] for the definition of the
+//! configurations and of the degradation.
+//!
+//! The configurations are formed by all the combinations of the decoders given
+//! in `--decoders` and the LLR scales given in `--llr-scales`. All the BER
+//! tests use the same seed, so all the decoders see the same noise
+//! realizations.
+//!
+//! # Examples
+//! The 8-bit decoders with and without partial hard limiting, and with LLR
+//! scales of 0.5, 0.75 and 1, are compared with the `Phif64` decoder for the
+//! CCSDS r=1/2, k=1024 LDPC code with
+//! ```shell
+//! $ ldpc-toolbox quant-sweep --ebn0 1.0,1.5,2.0 \
+//!       --decoders Aminstari8Deg1Clip,Aminstari8PartialHardLimitDeg1Clip \
+//!       --llr-scales 0.5,0.75,1 ar4ja:1/2:1024
+//! ```
+//! As in the [ber](super::ber) subcommand, the code can be given as an alist
+//! file or as a code specification, in which case its standard puncturing is
+//! used unless `--puncturing` is given.
+
+use crate::{
+    cli::*,
+    codes::{cache::code_from_spec, Code, MatrixCode},
+    decoder::factory::DecoderImplementation,
+    simulation::{
+        ber::Statistics,
+        factory::{parse_ebn0_list, BerTestBuilder, Modulation},
+        puncturing::PuncturingPattern,
+        quantization_sweep::{self, SweepConfig},
+    },
+    sparse::SparseMatrix,
+};
+use clap::Parser;
+use std::{error::Error, fs::File, io::Write, path::Path, sync::Arc};
+
+/// Quantization sweep CLI arguments.
+#[derive(Debug, Parser)]
+#[command(about = "Compares quantized decoders with a floating point reference")]
+pub struct Args {
+    /// alist file or code specification (for instance "ar4ja:1/2:1024")
+    alist: String,
+    /// Quantized decoder implementations (format "Aminstari8,HLNMSi8-0.75")
+    #[arg(long)]
+    decoders: String,
+    /// Scales applied to the LLRs before decoding (format "0.5,0.75,1")
+    #[arg(long, default_value = "1")]
+    llr_scales: String,
+    /// Reference decoder implementation
+    #[arg(long, default_value = "Phif64")]
+    reference_decoder: DecoderImplementation,
+    /// List of Eb/N0's (dB) (format "0.0,0.5,1.0,1.25")
+    #[arg(long)]
+    ebn0: String,
+    /// Puncturing pattern (format "1,1,1,0" or "3*1,0" by blocks, "bits:1,1,0"
+    /// per bit, "nr:<Zc>" for 5G NR or "ar4ja")
+    #[arg(long)]
+    puncturing: Option<PuncturingPattern>,
+    /// Maximum number of iterations
+    #[arg(long, default_value = "100")]
+    max_iter: usize,
+    /// Number of frame errors to collect
+    #[arg(long, default_value = "100")]
+    frame_errors: u64,
+    /// Maximum number of frames to simulate per Eb/N0
+    #[arg(long)]
+    max_frames: Option<u64>,
+    /// Master seed for the random number generators (random if not given)
+    #[arg(long)]
+    seed: Option<u64>,
+    /// Output file for the results table
+    #[arg(long)]
+    output_file: Option<String>,
+}
+
+impl Run for Args {
+    fn run(&self) -> Result<(), Box<dyn Error>> {
+        let code = self.code()?;
+        let h = Arc::new(code.h());
+        let puncturing = self
+            .puncturing
+            .clone()
+            .or_else(|| code.standard_puncturing().map(PuncturingPattern::Blocks));
+        let ebn0s = parse_ebn0_list(&self.ebn0)?;
+        let decoders = self
+            .decoders
+            .split(',')
+            .map(|d| d.parse::<DecoderImplementation>())
+            .collect::<Result<Vec<_>, _>>()?;
+        let llr_scales = self
+            .llr_scales
+            .split(',')
+            .map(|s| match s.parse::<f64>() {
+                Ok(scale) if scale.is_finite() && scale > 0.0 => Ok(scale),
+                _ => Err(format!("invalid LLR scale {s}")),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let seed = self.seed.unwrap_or_else(rand::random);
+        let reference = SweepConfig {
+            decoder: self.reference_decoder,
+            llr_scale: 1.0,
+        };
+        let run = |config: &SweepConfig| -> Result<Vec<Statistics>, Box<dyn Error>> {
+            let statistics = BerTestBuilder {
+                h: Arc::clone(&h),
+                decoder_implementation: config.decoder,
+                modulation: Modulation::Bpsk,
+                apsk16_ring_ratio: None,
+                channel: Default::default(),
+                impairments: Default::default(),
+                snr_profile: None,
+                puncturing: puncturing.clone(),
+                interleaving_columns: None,
+                scrambler: None,
+                known_bits: None,
+                shortening: None,
+                llr_transform: config.llr_transform(),
+                max_frame_errors: self.frame_errors,
+                frame_error_policy: Default::default(),
+                max_frames: self.max_frames,
+                warm_up_frames: 0,
+                batch_decoding: false,
+                max_iterations: self.max_iter,
+                iteration_schedule: Default::default(),
+                ebn0s_db: &ebn0s,
+                reporter: None,
+                bch_max_errors: 0,
+                llr_magnitude_tracking: false,
+                training_data: None,
+                error_patterns: None,
+                seed: Some(seed),
+                cancellation: None,
+            }
+            .build()?
+            .run()?;
+            eprintln!("{config}: done");
+            Ok(statistics)
+        };
+        let reference_curve = quantization_sweep::fer_curve(&run(&reference)?);
+        let mut results = vec![(reference, reference_curve.clone())];
+        for config in quantization_sweep::configs(&decoders, &llr_scales) {
+            results.push((config, quantization_sweep::fer_curve(&run(&config)?)));
+        }
+        writeln!(std::io::stdout())?;
+        write_results(
+            std::io::stdout(),
+            &code.name(),
+            seed,
+            &reference_curve,
+            &results,
+        )?;
+        if let Some(f) = &self.output_file {
+            write_results(
+                File::create(f)?,
+                &code.name(),
+                seed,
+                &reference_curve,
+                &results,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl Args {
+    // Returns the code given either as an alist file or as a code
+    // specification.
+    fn code(&self) -> Result<Box<dyn Code>, Box<dyn Error>> {
+        Ok(if Path::new(&self.alist).is_file() {
+            let h = SparseMatrix::from_alist(&std::fs::read_to_string(&self.alist)?)?;
+            Box::new(MatrixCode::new(self.alist.clone(), h))
+        } else {
+            code_from_spec(&self.alist)?
+        })
+    }
+}
+
+fn write_results<W: Write>(
+    mut f: W,
+    code: &str,
+    seed: u64,
+    reference: &[(f64, f64)],
+    results: &[(SweepConfig, Vec<(f64, f64)>)],
+) -> std::io::Result<()> {
+    writeln!(f, "QUANTIZATION SWEEP")?;
+    writeln!(f, "------------------")?;
+    writeln!(f, "Code: {code}")?;
+    writeln!(f, "Reference: {}", results[0].0.decoder)?;
+    writeln!(f, "Seed: {seed}")?;
+    let width = results
+        .iter()
+        .map(|(config, _)| config.to_string().len())
+        .max()
+        .unwrap_or(0)
+        .max("Configuration".len());
+    let mut header = format!("{:width$} |", "Configuration");
+    let mut line = format!("{:-<width$}-|", "");
+    for &(ebn0, _) in reference {
+        header.push_str(&format!(" FER {ebn0:5.2} |"));
+        line.push_str("-----------|");
+    }
+    header.push_str(" Gap (dB)");
+    line.push_str("---------");
+    writeln!(f, "{header}\n{line}")?;
+    for (config, curve) in results {
+        let mut row = format!("{:width$} |", config.to_string());
+        for &(_, fer) in curve {
+            row.push_str(&format!("   {fer:7.2e} |"));
+        }
+        let gap = quantization_sweep::ebn0_gap_db(reference, curve)
+            .map_or_else(|| "N/A".to_string(), |gap| format!("{gap:+.2}"));
+        row.push_str(&format!(" {gap:>8}"));
+        writeln!(f, "{row}")?;
+    }
+    Ok(())
+}
@@ -0,0 +1,242 @@
+//! Rate-compatible family CLI subcommand.
+//!
+//! This subcommand generates a nested family of codes of different rates from
+//! a mother code given as an alist file, using row merging to increase the rate
+//! and shortening to decrease it (see [`crate::rate_compatible`]). The alist of
+//! each code of the family is written to a directory, in a file named after its
+//! rate (for instance `rate_2-3.alist` for the rate 2/3). A summary of the
+//! family is printed to `stdout` and written to the file `family.txt` in the
+//! same directory.
+//!
+//! For each code, the summary lists the puncturing and shortening of the mother
+//! codeword that give the code, in the formats used by the `--puncturing` and
+//! `--shortening` arguments of the [ber](super::ber) subcommand. These allow
+//! encoding all the codes of the family with the encoder of the mother code.
+//!
+//! With `--method puncturing`, the family is obtained only by puncturing the
+//! parity bits of the mother code, using nested puncturing patterns searched
+//! with the k-step recoverable criterion (see
+//! [`crate::rate_compatible::nested_puncturing`]). This is suitable for
+//! incremental redundancy, since all the rates are decoded with the parity
+//! check matrix of the mother code. Instead of an alist, the puncturing pattern
+//! of each rate is written to a file named after its rate (for instance
+//! `rate_2-3.puncturing`), in the format used by the `--puncturing` argument of
+//! the [ber](super::ber) subcommand.
+//!
+//! # Examples
+//! The codes of rates 1/3, 2/3 and 3/4 are derived from the rate 1/2 mother code
+//! in `mother.alist` with
+//! ```shell
+//! $ ldpc-toolbox rate-compatible mother.alist --rates 1/3,2/3,3/4 --output-dir family
+//! ```
+//! The rate 2/3 code can then be simulated either with its own parity check
+//! matrix, or with the parity check matrix of the mother code and the
+//! puncturing given in the summary (which gives a different decoder
+//! performance, since the punctured bits are kept in the graph)
+//! ```shell
+//! $ ldpc-toolbox ber --min-ebn0 1.0 --max-ebn0 3.0 --step-ebn0 0.2 family/rate_2-3.alist
+//! $ ldpc-toolbox ber --min-ebn0 1.0 --max-ebn0 3.0 --step-ebn0 0.2 \
+//!       --puncturing bits:... mother.alist
+//! ```
+//! Nested puncturing patterns for incremental redundancy are obtained with
+//! ```shell
+//! $ ldpc-toolbox rate-compatible mother.alist --rates 2/3,3/4 --method puncturing \
+//!       --output-dir family
+//! $ ldpc-toolbox ber --min-ebn0 1.0 --max-ebn0 3.0 --step-ebn0 0.2 \
+//!       --puncturing "$(cat family/rate_3-4.puncturing)" mother.alist
+//! ```
+
+use crate::{
+    cli::*,
+    rate_compatible::{nested_family, nested_puncturing, FamilyMember},
+    simulation::puncturing::PuncturingPattern,
+};
+use clap::Parser;
+use std::{error::Error, io::Write, path::Path};
+
+/// Rate-compatible family CLI arguments.
+#[derive(Debug, Parser)]
+#[command(about = "Generates a rate-compatible family of codes from a mother code")]
+pub struct Args {
+    /// alist file for the mother code
+    alist: String,
+    /// Rates of the codes of the family (format "1/3,2/3,3/4")
+    #[arg(long)]
+    rates: String,
+    /// Output directory
+    #[arg(long)]
+    output_dir: String,
+    /// Construction method ("row-merging" or "puncturing")
+    #[arg(long, default_value = "row-merging")]
+    method: Method,
+    #[command(flatten)]
+    input: AlistInputArgs,
+    #[command(flatten)]
+    output: AlistArgs,
+}
+
+/// Family construction method.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Method {
+    /// Row merging and shortening.
+    RowMerging,
+    /// Nested puncturing of the mother code.
+    Puncturing,
+}
+
+impl std::str::FromStr for Method {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Method, String> {
+        Ok(match s {
+            "row-merging" => Method::RowMerging,
+            "puncturing" => Method::Puncturing,
+            _ => return Err(format!("invalid method {s}")),
+        })
+    }
+}
+
+impl std::fmt::Display for Method {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Method::RowMerging => "row-merging",
+                Method::Puncturing => "puncturing",
+            }
+        )
+    }
+}
+
+impl Run for Args {
+    fn run(&self) -> Result<(), Box<dyn Error>> {
+        let h = self.input.read(&self.alist)?;
+        let rates = self
+            .rates
+            .split(',')
+            .map(|r| parse_rate(r.trim()).map(|rate| (r.trim(), rate)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let dir = Path::new(&self.output_dir);
+        std::fs::create_dir_all(dir)?;
+        let mut summary = Vec::new();
+        writeln!(summary, "Mother code: {}", self.alist)?;
+        writeln!(
+            summary,
+            " - n = {}, k = {}",
+            h.num_cols(),
+            h.num_cols() - h.num_rows()
+        )?;
+        match self.method {
+            Method::RowMerging => self.row_merging(&h, &rates, dir, &mut summary)?,
+            Method::Puncturing => puncturing(&h, &rates, dir, &mut summary)?,
+        }
+        std::fs::write(dir.join("family.txt"), &summary)?;
+        std::io::stdout().write_all(&summary)?;
+        Ok(())
+    }
+}
+
+impl Args {
+    fn row_merging(
+        &self,
+        h: &SparseMatrix,
+        rates: &[(&str, f64)],
+        dir: &Path,
+        summary: &mut Vec<u8>,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut values = rates.iter().map(|&(_, rate)| rate).collect::<Vec<_>>();
+        let family = nested_family(h, &values)?;
+        // The family is returned in order of increasing rate
+        values.sort_by(f64::total_cmp);
+        for (code, rate) in family.iter().zip(&values) {
+            let label = rates
+                .iter()
+                .find(|(_, r)| r == rate)
+                .map(|&(label, _)| label)
+                .unwrap();
+            let file = format!("rate_{}.alist", label.replace('/', "-"));
+            std::fs::write(dir.join(&file), code.h().alist_with(&self.output.options()))?;
+            write_member(&mut *summary, label, &file, code, h.num_cols())?;
+        }
+        Ok(())
+    }
+}
+
+fn puncturing(
+    h: &SparseMatrix,
+    rates: &[(&str, f64)],
+    dir: &Path,
+    summary: &mut Vec<u8>,
+) -> Result<(), Box<dyn Error>> {
+    let puncturing = nested_puncturing(h)?;
+    writeln!(
+        summary,
+        " - Maximum rate by puncturing: {:.4}",
+        puncturing.max_rate()
+    )?;
+    let mut rates = rates.to_vec();
+    rates.sort_by(|a, b| a.1.total_cmp(&b.1));
+    for (label, rate) in rates {
+        let punctured = puncturing.punctured(rate)?;
+        let pattern = PuncturingPattern::Bits(puncturing.pattern(rate)?);
+        let file = format!("rate_{}.puncturing", label.replace('/', "-"));
+        std::fs::write(dir.join(&file), format!("{pattern}\n"))?;
+        let n = h.num_cols() - punctured.len();
+        let k = h.num_cols() - h.num_rows();
+        writeln!(summary, "Rate {label}: {file}")?;
+        writeln!(
+            summary,
+            " - n = {n}, k = {k}, rate = {:.4}",
+            k as f64 / n as f64
+        )?;
+        writeln!(summary, " - Punctured bits: {}", punctured.len())?;
+        let levels = &puncturing.recovery_levels()[..punctured.len()];
+        if let Some(level) = levels.last() {
+            writeln!(summary, " - Maximum recovery level: {level}")?;
+        }
+    }
+    Ok(())
+}
+
+fn write_member<W: Write>(
+    mut f: W,
+    label: &str,
+    file: &str,
+    code: &FamilyMember,
+    mother_columns: usize,
+) -> std::io::Result<()> {
+    let h = code.h();
+    writeln!(f, "Rate {label}: {file}")?;
+    writeln!(
+        f,
+        " - n = {}, k = {}, rate = {:.4}",
+        h.num_cols(),
+        h.num_cols() - h.num_rows(),
+        code.rate()
+    )?;
+    let merged = code.rows().iter().filter(|rows| rows.len() > 1).count();
+    if merged > 0 {
+        writeln!(f, " - Merged rows: {merged}")?;
+    }
+    if !code.punctured().is_empty() {
+        let pattern = PuncturingPattern::Bits(code.puncturing_pattern(mother_columns));
+        writeln!(f, " - Puncturing: {pattern}")?;
+    }
+    if let (Some(first), Some(last)) = (code.shortened().first(), code.shortened().last()) {
+        writeln!(f, " - Shortening: {first}-{last}")?;
+    }
+    Ok(())
+}
+
+// Parses a rate given as a fraction ("2/3") or as a decimal number ("0.6").
+fn parse_rate(s: &str) -> Result<f64, String> {
+    let rate = match s.split_once('/') {
+        Some((num, den)) => match (num.parse::<u32>(), den.parse::<u32>()) {
+            (Ok(num), Ok(den)) if den != 0 => f64::from(num) / f64::from(den),
+            _ => return Err(format!("invalid rate {s}")),
+        },
+        None => s.parse().map_err(|_| format!("invalid rate {s}"))?,
+    };
+    Ok(rate)
+}
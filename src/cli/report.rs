@@ -0,0 +1,142 @@
+//! Code report CLI subcommand
+//!
+//! This subcommand analyzes a code and prints to `stdout` a report of its
+//! properties in Markdown or HTML format. The code can be given either as the
+//! path of an alist file or as a code specification (see
+//! [`crate::codes::cache`]). Optionally, the report can include a quick BER
+//! estimate, obtained with a short BER simulation using BPSK. For more details
+//! about the contents of the report, see [`crate::report`].
+//!
+//! # Examples
+//! A Markdown report of the code in `code.alist` can be generated with
+//! ```shell
+//! $ ldpc-toolbox report code.alist > report.md
+//! ```
+//! An HTML report of the CCSDS AR4JA r=1/2, k=1024 code including a BER
+//! estimate at 1.5 and 2.0 dB can be generated with
+//! ```shell
+//! $ ldpc-toolbox report ar4ja:1/2:1024 --html --ber-ebn0 1.5,2.0 > report.html
+//! ```
+
+use crate::{
+    cli::*,
+    codes::{cache::code_from_spec, Code, MatrixCode},
+    decoder::factory::DecoderImplementation,
+    report::{BerEstimate, CodeReport},
+    simulation::{
+        channel::ChannelModel,
+        factory::{parse_ebn0_list, BerTestBuilder, Modulation},
+        frame_errors::FrameErrorPolicy,
+        iterations::IterationSchedule,
+        puncturing::PuncturingPattern,
+    },
+    sparse::SparseMatrix,
+};
+use clap::Parser;
+use std::path::Path;
+
+/// Code report CLI arguments.
+#[derive(Debug, Parser)]
+#[command(about = "Generates a report of the properties of a code")]
+pub struct Args {
+    /// alist file or code specification (for instance "dvbs2:1/2:short")
+    code: String,
+    /// Write the report in HTML format instead of Markdown
+    #[arg(long)]
+    html: bool,
+    /// Maximum cycle length in the analysis of short cycles
+    #[arg(long, default_value = "8")]
+    max_cycle: usize,
+    /// Eb/N0's (dB) for the BER estimate (format "1.0,1.5")
+    #[arg(long)]
+    ber_ebn0: Option<String>,
+    /// Decoder implementation for the BER estimate
+    #[arg(long, default_value = "HLAminstarf32")]
+    decoder: DecoderImplementation,
+    /// Maximum number of iterations for the BER estimate
+    #[arg(long, default_value = "50")]
+    max_iter: usize,
+    /// Number of frame errors to collect for the BER estimate
+    #[arg(long, default_value = "20")]
+    frame_errors: u64,
+}
+
+impl Args {
+    fn code(&self) -> Result<Box<dyn Code>, Box<dyn std::error::Error>> {
+        Ok(if Path::new(&self.code).is_file() {
+            let h = SparseMatrix::from_alist(&std::fs::read_to_string(&self.code)?)?;
+            Box::new(MatrixCode::new(self.code.clone(), h))
+        } else {
+            code_from_spec(&self.code)?
+        })
+    }
+
+    fn ber_estimate(
+        &self,
+        code: &dyn Code,
+        ebn0s: &[f32],
+    ) -> Result<Vec<BerEstimate>, Box<dyn std::error::Error>> {
+        let test = BerTestBuilder {
+            h: code.h().into(),
+            decoder_implementation: self.decoder,
+            modulation: Modulation::Bpsk,
+            apsk16_ring_ratio: None,
+            channel: ChannelModel::Awgn,
+            impairments: Default::default(),
+            snr_profile: None,
+            puncturing: code.standard_puncturing().map(PuncturingPattern::Blocks),
+            interleaving_columns: None,
+            scrambler: None,
+            known_bits: None,
+            shortening: None,
+            llr_transform: None,
+            max_frame_errors: self.frame_errors,
+            frame_error_policy: FrameErrorPolicy::Fixed,
+            max_frames: None,
+            warm_up_frames: 0,
+            batch_decoding: false,
+            max_iterations: self.max_iter,
+            iteration_schedule: IterationSchedule::Fixed,
+            ebn0s_db: ebn0s,
+            reporter: None,
+            bch_max_errors: 0,
+            llr_magnitude_tracking: false,
+            training_data: None,
+            error_patterns: None,
+            seed: Some(0),
+            cancellation: None,
+        }
+        .build()?;
+        Ok(test
+            .run()?
+            .into_iter()
+            .map(|stats| BerEstimate {
+                ebn0_db: f64::from(stats.ebn0_db),
+                frames: stats.num_frames,
+                ber: stats.ldpc.ber,
+                fer: stats.ldpc.fer,
+                ber_confidence_interval: stats.ldpc.ber_confidence_interval,
+                fer_confidence_interval: stats.ldpc.fer_confidence_interval,
+            })
+            .collect())
+    }
+}
+
+impl Run for Args {
+    fn run(&self) -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let code = self.code()?;
+        let mut report = CodeReport::new(&*code, self.max_cycle);
+        if let Some(ebn0s) = &self.ber_ebn0 {
+            let ebn0s = parse_ebn0_list(ebn0s)?;
+            report.ber = self.ber_estimate(&*code, &ebn0s)?;
+        }
+        let mut output = String::new();
+        if self.html {
+            report.write_html(&mut output)?;
+        } else {
+            report.write_markdown(&mut output)?;
+        }
+        print!("{output}");
+        Ok(())
+    }
+}
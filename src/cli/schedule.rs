@@ -0,0 +1,83 @@
+//! Layered schedule CLI subcommand
+//!
+//! This subcommand writes the processing schedule of the horizontal layered
+//! decoder for a code, including the layer order, the edges processed by each
+//! layer and the LLR memory accesses, in CSV or JSON format. It is intended for
+//! hardware architects studying the memory conflicts of layered decoders. See
+//! [`crate::decoder::schedule`] for more information.
+//!
+//! The code can be given either as the path of an alist file or as a code
+//! specification (see [`crate::codes::cache`]).
+//!
+//! # Examples
+//! The schedule of the DVB-S2 r=1/2 short FECFRAME code, with the LLRs stored in
+//! one memory bank per column block of 360 variable nodes, can be written to
+//! `schedule.csv` with
+//! ```shell
+//! $ ldpc-toolbox schedule dvbs2:1/2:short --circulant-size 360 > schedule.csv
+//! ```
+//! The JSON format is selected with `--json`.
+
+use crate::{
+    cli::*,
+    codes::{cache::code_from_spec, Code, MatrixCode},
+    decoder::{graph::CompiledGraph, schedule::LayeredSchedule},
+    sparse::SparseMatrix,
+};
+use clap::Parser;
+use std::{io::Write, path::Path};
+
+/// Layered schedule CLI arguments.
+#[derive(Debug, Parser)]
+#[command(about = "Writes the schedule of the layered decoder")]
+pub struct Args {
+    /// alist file or code specification (for instance "dvbs2:1/2:short")
+    code: String,
+    /// Circulant size used to assign the LLR memory banks
+    #[arg(long)]
+    circulant_size: Option<usize>,
+    /// Write the schedule in JSON format instead of CSV
+    #[arg(long)]
+    json: bool,
+}
+
+impl Run for Args {
+    fn run(&self) -> std::result::Result<(), Box<dyn std::error::Error>> {
+        if self.circulant_size == Some(0) {
+            return Err("the circulant size must be positive".into());
+        }
+        let code = self.code()?;
+        let graph = CompiledGraph::new(code.h());
+        let schedule = LayeredSchedule::new(&graph, self.circulant_size);
+        let mut stdout = std::io::stdout().lock();
+        if self.json {
+            schedule.write_json(&mut stdout)?;
+        } else {
+            schedule.write_csv(&mut stdout)?;
+        }
+        stdout.flush()?;
+        let hazards = schedule
+            .layers()
+            .iter()
+            .map(|layer| layer.hazards.len())
+            .sum::<usize>();
+        eprintln!(
+            "{} layers, {} edges, {} hazards",
+            schedule.layers().len(),
+            graph.num_edges(),
+            hazards
+        );
+        Ok(())
+    }
+}
+
+impl Args {
+    fn code(&self) -> Result<Box<dyn Code>, Box<dyn std::error::Error>> {
+        Ok(if Path::new(&self.code).is_file() {
+            let h = SparseMatrix::from_alist(&std::fs::read_to_string(&self.code)?)?;
+            Box::new(MatrixCode::new(self.code.clone(), h))
+        } else {
+            code_from_spec(&self.code)?
+        })
+    }
+}
@@ -0,0 +1,117 @@
+//! Decoder server CLI subcommand
+//!
+//! This subcommand runs a [`DecoderServer`] that reads frames of LLRs and
+//! writes the decoded frames. The frames are read from `stdin` and written to
+//! `stdout`, or exchanged through the connections of a UNIX socket with
+//! `--socket`. See [`crate::decoder::server`] for the format of the frames.
+//!
+//! The decoders are kept between frames and connections, so this can be used
+//! to decode with this crate from other applications without paying for the
+//! construction of the decoder for each frame, or as a stress test for the
+//! decoders. The statistics of each stream are printed to `stderr` when it
+//! ends.
+//!
+//! # Examples
+//! The LLRs in `llrs.bin` can be decoded with 4 threads and the `HLPhif32`
+//! decoder with
+//! ```shell
+//! $ ldpc-toolbox serve --decoder HLPhif32 --threads 4 code.alist < llrs.bin > decoded.bin
+//! ```
+//! A server that accepts connections on the socket `/tmp/ldpc.sock` is run
+//! with
+//! ```shell
+//! $ ldpc-toolbox serve --socket /tmp/ldpc.sock code.alist
+//! ```
+//! The connections are served one at a time.
+
+use crate::{
+    cli::*,
+    decoder::{
+        factory::DecoderImplementation,
+        server::{DecoderServer, ServerStatistics},
+    },
+    sparse::SparseMatrix,
+};
+use clap::Parser;
+use std::time::Instant;
+
+/// Decoder server CLI arguments.
+#[derive(Debug, Parser)]
+#[command(about = "Decodes LLR frames from stdin or a UNIX socket")]
+pub struct Args {
+    /// alist file for the code
+    alist: String,
+    /// Decoder implementation
+    #[arg(long, default_value = "Phif64")]
+    decoder: DecoderImplementation,
+    /// Maximum number of iterations
+    #[arg(long, default_value = "100")]
+    max_iter: usize,
+    /// Number of decoder threads (defaults to the number of CPUs)
+    #[arg(long)]
+    threads: Option<usize>,
+    /// Maximum number of frames decoded as a batch
+    #[arg(long, default_value = "64")]
+    batch_size: usize,
+    /// Path of a UNIX socket to listen on instead of using stdin and stdout
+    #[arg(long)]
+    socket: Option<String>,
+}
+
+impl Args {
+    fn report(statistics: &ServerStatistics, start: Instant) {
+        let elapsed = start.elapsed().as_secs_f64();
+        eprintln!(
+            "Decoded {} frames ({} failures), {:.2} iterations/frame, {:.1} frames/s",
+            statistics.frames,
+            statistics.failures,
+            statistics.average_iterations(),
+            statistics.frames as f64 / elapsed
+        );
+    }
+
+    #[cfg(unix)]
+    fn serve_socket(
+        server: &mut DecoderServer<DecoderImplementation>,
+        path: &str,
+    ) -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let listener = std::os::unix::net::UnixListener::bind(path)?;
+        for stream in listener.incoming() {
+            let stream = stream?;
+            let start = Instant::now();
+            // A failed connection does not stop the server
+            match server.serve(stream.try_clone()?, std::io::BufWriter::new(stream)) {
+                Ok(statistics) => Self::report(&statistics, start),
+                Err(e) => eprintln!("Connection error: {e}"),
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(not(unix))]
+    fn serve_socket(
+        _server: &mut DecoderServer<DecoderImplementation>,
+        _path: &str,
+    ) -> std::result::Result<(), Box<dyn std::error::Error>> {
+        Err("UNIX sockets are not supported on this platform".into())
+    }
+}
+
+impl Run for Args {
+    fn run(&self) -> std::result::Result<(), Box<dyn std::error::Error>> {
+        if self.batch_size == 0 || self.threads == Some(0) {
+            return Err("the batch size and the number of threads must be positive".into());
+        }
+        let h = SparseMatrix::from_alist(&std::fs::read_to_string(&self.alist)?)?;
+        let mut server = DecoderServer::new(self.decoder, h, self.max_iter)
+            .with_threads(self.threads.unwrap_or_else(num_cpus::get))
+            .with_batch_size(self.batch_size);
+        if let Some(path) = &self.socket {
+            return Self::serve_socket(&mut server, path);
+        }
+        let start = Instant::now();
+        let statistics = server.serve(std::io::stdin(), std::io::stdout().lock())?;
+        Self::report(&statistics, start);
+        Ok(())
+    }
+}
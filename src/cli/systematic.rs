@@ -4,8 +4,23 @@
 //! that supports systematic encoding using the first m - n columns by permuting
 //! columns in such a way that the n x n submatrix formed by the last n columns
 //! is invertible.
+//!
+//! With `--column-metadata`, the output alist includes a column metadata
+//! section that records the original index of each column and whether it is a
+//! systematic or a parity column (see
+//! [`SparseMatrix::write_alist_with`]). If the input alist already has column
+//! metadata, it is always preserved.
+//!
+//! Input alists whose matrix has more rows than columns are assumed to hold the
+//! transpose of the parity check matrix and are transposed, with a warning.
+//! This can be controlled with `--alist-orientation`.
+//!
+//! [`SparseMatrix::write_alist_with`]: crate::sparse::SparseMatrix::write_alist_with
 
-use crate::{cli::Run, sparse::SparseMatrix, systematic::parity_to_systematic};
+use crate::{
+    cli::{AlistArgs, AlistInputArgs, Run},
+    systematic::parity_to_systematic,
+};
 use clap::Parser;
 use std::error::Error;
 
@@ -15,13 +30,23 @@ use std::error::Error;
 pub struct Args {
     /// alist file for the code
     alist: String,
+    /// Write the original index and role of each column in the alist
+    #[arg(long)]
+    column_metadata: bool,
+    #[command(flatten)]
+    input: AlistInputArgs,
+    #[command(flatten)]
+    output: AlistArgs,
 }
 
 impl Run for Args {
     fn run(&self) -> Result<(), Box<dyn Error>> {
-        let h = SparseMatrix::from_alist(&std::fs::read_to_string(&self.alist)?)?;
+        let mut h = self.input.read(&self.alist)?;
+        if self.column_metadata {
+            h.init_column_metadata();
+        }
         let h_sys = parity_to_systematic(&h)?;
-        println!("{}", h_sys.alist());
+        println!("{}", h_sys.alist_with(&self.output.options()));
         Ok(())
     }
 }
@@ -0,0 +1,102 @@
+//! Message trace CLI subcommand
+//!
+//! This subcommand decodes a single codeword and writes every message
+//! exchanged by the decoder in every iteration, in CSV or JSON format. It is
+//! intended to be used with small codes, to visualize the evolution of the
+//! messages of belief propagation or to debug decoder arithmetics. See
+//! [`crate::decoder::trace`] for more information.
+//!
+//! The LLRs of the codeword are read from a text file, separated by whitespace
+//! or commas. Only the decoders with a flooding schedule support tracing.
+//!
+//! # Examples
+//! The messages of the decoding of the LLRs in `llrs.txt` with the `Phif64`
+//! decoder can be written to `trace.csv` with
+//! ```shell
+//! $ ldpc-toolbox trace code.alist llrs.txt > trace.csv
+//! ```
+//! The JSON format is selected with `--json`.
+//!
+//! With `--iterations`, the statistics of each iteration (number of unsatisfied
+//! checks, flipped bits and saturated LLRs) are written in CSV format instead
+//! of the messages. This is also supported by the decoders with a horizontal
+//! layered schedule, and it can be used with long codes. See
+//! [`IterationTrace`](crate::decoder::trace::IterationTrace).
+//! ```shell
+//! $ ldpc-toolbox trace --iterations --decoder HLPhif64 code.alist llrs.txt
+//! ```
+
+use crate::{
+    cli::*,
+    decoder::factory::{DecoderFactory, DecoderImplementation},
+    sparse::SparseMatrix,
+};
+use clap::Parser;
+use std::io::Write;
+
+/// Message trace CLI arguments.
+#[derive(Debug, Parser)]
+#[command(about = "Writes the messages exchanged by the decoder")]
+pub struct Args {
+    /// alist file for the code
+    alist: String,
+    /// Text file with the LLRs of the codeword
+    llrs: String,
+    /// Decoder implementation
+    #[arg(long, default_value = "Phif64")]
+    decoder: DecoderImplementation,
+    /// Maximum number of iterations
+    #[arg(long, default_value = "100")]
+    max_iter: usize,
+    /// Write the trace in JSON format instead of CSV
+    #[arg(long, conflicts_with = "iterations")]
+    json: bool,
+    /// Write the statistics of each iteration instead of the messages
+    #[arg(long)]
+    iterations: bool,
+}
+
+impl Run for Args {
+    fn run(&self) -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let h = SparseMatrix::from_alist(&std::fs::read_to_string(&self.alist)?)?;
+        let llrs = std::fs::read_to_string(&self.llrs)?
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|s| !s.is_empty())
+            .map(|s| s.parse::<f64>())
+            .collect::<Result<Vec<f64>, _>>()?;
+        if llrs.len() != h.num_cols() {
+            return Err(format!(
+                "the number of LLRs ({}) does not match the codeword length ({})",
+                llrs.len(),
+                h.num_cols()
+            )
+            .into());
+        }
+        let mut decoder = self.decoder.build_decoder(h);
+        if self.iterations {
+            decoder.set_iteration_tracing(true);
+        } else {
+            decoder.set_message_tracing(true);
+        }
+        let result = decoder.decode(&llrs, self.max_iter);
+        let unsupported = || format!("decoder {} does not support tracing", self.decoder);
+        let mut stdout = std::io::stdout().lock();
+        if self.iterations {
+            let trace = decoder.iteration_trace().ok_or_else(unsupported)?;
+            trace.write_csv(&mut stdout)?;
+        } else {
+            let trace = decoder.message_trace().ok_or_else(unsupported)?;
+            if self.json {
+                trace.write_json(&mut stdout)?;
+            } else {
+                trace.write_csv(&mut stdout)?;
+            }
+        }
+        stdout.flush()?;
+        match result {
+            Ok(output) => eprintln!("decoding succeeded in {} iterations", output.iterations),
+            Err(output) => eprintln!("decoding failed after {} iterations", output.iterations),
+        }
+        Ok(())
+    }
+}
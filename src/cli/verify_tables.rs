@@ -0,0 +1,44 @@
+//! Verify tables CLI subcommand
+//!
+//! This subcommand runs the internal consistency checks of the tables of the
+//! standards and prints a report with the result of each check. It returns an
+//! error if some check fails. See [`crate::codes::verify`] for more
+//! information about the checks.
+//!
+//! # Examples
+//! The checks are run with
+//! ```shell
+//! $ ldpc-toolbox verify-tables
+//! ```
+//! Only the failed checks are printed with
+//! ```shell
+//! $ ldpc-toolbox verify-tables --failed
+//! ```
+
+use crate::cli::*;
+use crate::codes::verify::verify_tables;
+use clap::Parser;
+
+/// Verify tables CLI arguments.
+#[derive(Debug, Parser)]
+#[command(about = "Verifies the consistency of the tables of the standards")]
+pub struct Args {
+    /// Only print the checks that fail
+    #[arg(long)]
+    failed: bool,
+}
+
+impl Run for Args {
+    fn run(&self) -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let checks = verify_tables();
+        for check in checks.iter().filter(|c| !self.failed || !c.passed()) {
+            println!("{check}");
+        }
+        let failed = checks.iter().filter(|c| !c.passed()).count();
+        if failed > 0 {
+            return Err(format!("{failed} of {} checks failed", checks.len()).into());
+        }
+        println!("All {} checks passed", checks.len());
+        Ok(())
+    }
+}
@@ -0,0 +1,93 @@
+//! WiMAX CLI subcommand
+//!
+//! This subcommand can be used to generate the LDPC codes used in the IEEE
+//! 802.16e (WiMAX) standard. It will print the alist of the parity check matrix
+//! to `stdout` and optionally compute and print the girth of the Tanner
+//! graph. See [`crate::codes::wimax`] for more information about these codes.
+//!
+//! # Examples
+//! The r=2/3A, n=1152 parity check matrix can be generated with
+//! ```shell
+//! $ ldpc-toolbox wimax --rate 2/3A --length 1152
+//! ```
+//! The alists of all the codes are written to the directory `codes`, together
+//! with an `index.json` file describing them, with
+//! ```shell
+//! $ ldpc-toolbox wimax --all codes
+//! ```
+//! The files are named after the code specifications (see
+//! [`crate::codes::cache`]), so for instance the r=1/2, n=576 code is written
+//! to `codes/wimax_1-2_576.alist`.
+
+use crate::cli::*;
+use crate::codes::{
+    self,
+    wimax::{WimaxCode, WimaxRate},
+};
+use clap::Parser;
+
+/// WiMAX CLI arguments.
+#[derive(Debug, Parser)]
+#[command(about = "Generates the alist of WiMAX (IEEE 802.16e) LDPCs")]
+pub struct Args {
+    /// Coding rate ("1/2", "2/3A", "2/3B", "3/4A", "3/4B" or "5/6")
+    #[arg(short, long, required_unless_present = "all")]
+    rate: Option<String>,
+
+    /// Codeword length (n = 576, 672, ..., 2304)
+    #[arg(long, required_unless_present = "all")]
+    length: Option<usize>,
+
+    /// Performs girth calculation
+    #[arg(long)]
+    girth: bool,
+
+    /// Writes the alists of all the codes to a directory
+    #[arg(
+        long,
+        value_name = "DIR",
+        conflicts_with_all = ["rate", "length", "girth"]
+    )]
+    all: Option<String>,
+
+    #[command(flatten)]
+    alist: AlistArgs,
+}
+
+impl Args {
+    fn code(&self) -> Result<WimaxCode, String> {
+        // clap ensures that the rate and length are present when --all is not
+        // used
+        let rate = self.rate.as_deref().unwrap();
+        let rate = WimaxRate::from_rate(rate).ok_or_else(|| format!("Invalid rate {rate}"))?;
+        let n = self.length.unwrap();
+        WimaxCode::new(rate, n).ok_or_else(|| format!("Invalid codeword length n = {n}"))
+    }
+
+    fn all_codes() -> impl Iterator<Item = (String, Box<dyn codes::Code>)> {
+        WimaxCode::all().map(|code| {
+            let spec = format!("wimax:{}:{}", code.rate(), codes::Code::n(&code));
+            let code: Box<dyn codes::Code> = Box::new(code);
+            (spec, code)
+        })
+    }
+}
+
+impl Run for Args {
+    fn run(&self) -> std::result::Result<(), Box<dyn std::error::Error>> {
+        if let Some(dir) = &self.all {
+            return write_code_family(dir, Self::all_codes(), &self.alist.options());
+        }
+        let h = self.code()?.h();
+        if self.girth {
+            if let Some(g) = h.girth() {
+                println!("Code girth = {}", g);
+            } else {
+                println!("Code girth is infinite");
+            }
+        } else {
+            print!("{}", h.alist_with(&self.alist.options()));
+        }
+        Ok(())
+    }
+}
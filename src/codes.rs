@@ -1,4 +1,128 @@
 //! # LDPC codes used in standards
+//!
+//! This module also contains some short classical codes that are useful as
+//! references for testing, and some small [example codes](examples) that are
+//! used in the documentation.
+//!
+//! All the codes implement the [`Code`] trait, which gives access to their
+//! parity check matrix and metadata in a uniform way. A code can be obtained
+//! as a `Box<dyn Code>` from a code specification string with
+//! [`cache::code_from_spec`].
+//!
+//! The codes of the standards also give a [`DecoderPreset`] with the decoder
+//! settings that should be used to simulate them, so that the simulation
+//! results are comparable with the published performance of the code.
 
+use crate::{decoder::factory::DecoderImplementation, sparse::SparseMatrix};
+
+pub mod cache;
 pub mod ccsds;
+pub mod ccsds_tc;
+pub mod corpus;
 pub mod dvbs2;
+pub mod dvbs2x;
+pub mod examples;
+pub mod reference;
+pub mod verify;
+pub mod wimax;
+
+/// LDPC code.
+///
+/// This trait gives the parity check matrix and the metadata of a code.
+pub trait Code: std::fmt::Debug + Send + Sync {
+    /// Constructs the parity check matrix for the code.
+    fn h(&self) -> SparseMatrix;
+
+    /// Returns the codeword length `n`.
+    ///
+    /// This is the number of columns of the parity check matrix, which
+    /// includes the bits that are punctured by the
+    /// [standard puncturing](Code::standard_puncturing).
+    fn n(&self) -> usize;
+
+    /// Returns the number of information bits `k`.
+    fn k(&self) -> usize;
+
+    /// Returns the puncturing pattern defined by the standard, if any.
+    ///
+    /// The pattern is given in the format used by
+    /// [`Puncturer::new`](crate::simulation::puncturing::Puncturer::new).
+    fn standard_puncturing(&self) -> Option<Vec<bool>> {
+        None
+    }
+
+    /// Returns a human readable name for the code.
+    fn name(&self) -> String;
+
+    /// Returns the recommended decoder settings for the code, if any.
+    ///
+    /// The default implementation returns `None`.
+    fn decoder_preset(&self) -> Option<DecoderPreset> {
+        None
+    }
+
+    /// Returns the code rate.
+    ///
+    /// The rate is computed as `k` divided by the number of bits that remain
+    /// after applying the [standard puncturing](Code::standard_puncturing).
+    fn rate(&self) -> f64 {
+        let transmitted = match self.standard_puncturing() {
+            Some(pattern) => self.n() / pattern.len() * pattern.iter().filter(|&&b| b).count(),
+            None => self.n(),
+        };
+        self.k() as f64 / transmitted as f64
+    }
+}
+
+/// Recommended decoder settings for a code.
+///
+/// These are the settings with which the performance of a code is usually
+/// reported. The puncturing is given separately by
+/// [`Code::standard_puncturing`].
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct DecoderPreset {
+    /// Decoder implementation.
+    pub implementation: DecoderImplementation,
+    /// Maximum number of iterations.
+    pub max_iterations: usize,
+}
+
+/// Code defined by an arbitrary parity check matrix.
+///
+/// This is used for codes that do not belong to any standard, such as codes
+/// read from an alist file. The parity check matrix is assumed to have
+/// maximum rank, so that `k` is the number of columns minus the number of
+/// rows.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MatrixCode {
+    name: String,
+    h: SparseMatrix,
+}
+
+impl MatrixCode {
+    /// Creates a code from its name and parity check matrix.
+    pub fn new(name: impl Into<String>, h: SparseMatrix) -> MatrixCode {
+        MatrixCode {
+            name: name.into(),
+            h,
+        }
+    }
+}
+
+impl Code for MatrixCode {
+    fn h(&self) -> SparseMatrix {
+        self.h.clone()
+    }
+
+    fn n(&self) -> usize {
+        self.h.num_cols()
+    }
+
+    fn k(&self) -> usize {
+        self.h.num_cols() - self.h.num_rows()
+    }
+
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+}
@@ -0,0 +1,334 @@
+//! Cache of parity check matrices.
+//!
+//! Constructing the parity check matrix of some codes requires a nontrivial
+//! amount of work, such as expanding the tables of a standard or parsing a
+//! large alist file. This module contains a [`CodeCache`] that stores parity
+//! check matrices keyed by a code specification string, so that they are only
+//! constructed once and can be shared between users by means of an
+//! [`Arc`]. A process-wide cache is available by calling [`CodeCache::global`].
+//!
+//! # Code specifications
+//!
+//! A code specification is a string formed by fields separated by colons. The
+//! following specifications are supported:
+//!
+//! - `dvbs2:<rate>` and `dvbs2:<rate>:short`: DVB-S2 code with normal or short
+//!   FECFRAMEs. For example, `dvbs2:3/5:short`.
+//!
+//! - `dvbs2x:<rate>:<frame>:<path>`: DVB-S2X code with `normal`, `medium` or
+//!   `short` FECFRAMEs, constructed from the table in a file (see
+//!   [`dvbs2x`](super::dvbs2x)). For example, `dvbs2x:1/3:medium:r1_3.txt`.
+//!
+//! - `ar4ja:<rate>:<k>`: CCSDS AR4JA code. For example, `ar4ja:1/2:1024`.
+//!
+//! - `c2`: CCSDS C2 code.
+//!
+//! - `ccsds-tc:<k>`: CCSDS TC short block code. For example, `ccsds-tc:64`.
+//!
+//! - `wimax:<rate>:<n>`: WiMAX (IEEE 802.16e) code. For example,
+//!   `wimax:2/3A:1152`.
+//!
+//! - `reference:<name>`: reference code. The names are `hamming7_4`,
+//!   `ext_hamming8_4`, `bch15_7`, `golay23_12` and `ext_golay24_12`.
+//!
+//! - `example:<name>`: [example code](super::examples). The names are
+//!   `johnson2_5`, `gallager20_8` and `qc42_21`.
+//!
+//! - `alist:<path>`: code read from an alist file.
+//!
+//! - `corpus:<name>`: code from the [`corpus`](super::corpus), read from the
+//!   default corpus cache directory. The code must have been downloaded
+//!   previously, since the network is not accessed.
+//!
+//! # Examples
+//! ```
+//! # use ldpc_toolbox::codes::cache::CodeCache;
+//! # use std::sync::Arc;
+//! let cache = CodeCache::new();
+//! let h = cache.get("ar4ja:1/2:1024")?;
+//! assert_eq!(h.num_cols(), 2560);
+//! // The second call returns the same matrix without constructing it again
+//! assert!(Arc::ptr_eq(&h, &cache.get("ar4ja:1/2:1024")?));
+//! # Ok::<(), ldpc_toolbox::codes::cache::Error>(())
+//! ```
+
+use super::{
+    ccsds::{AR4JACode, AR4JAInfoSize, AR4JARate, C2Code},
+    ccsds_tc::TcCode,
+    corpus::{self, Corpus},
+    dvbs2, dvbs2x,
+    examples::ExampleCode,
+    reference::ReferenceCode,
+    wimax::{WimaxCode, WimaxRate},
+    Code, MatrixCode,
+};
+use crate::sparse::SparseMatrix;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, OnceLock},
+};
+use thiserror::Error;
+
+/// Code cache error.
+#[derive(Debug, Error)]
+pub enum Error {
+    /// The code specification is not valid.
+    #[error("invalid code specification: {0}")]
+    InvalidSpec(String),
+    /// Error reading the alist file.
+    #[error("could not read alist file: {0}")]
+    Io(#[from] std::io::Error),
+    /// Error parsing the alist file.
+    #[error("could not parse alist file: {0}")]
+    Alist(String),
+    /// Error loading a DVB-S2X code.
+    #[error(transparent)]
+    Dvbs2x(#[from] dvbs2x::Error),
+    /// Error loading a code from the corpus.
+    #[error(transparent)]
+    Corpus(#[from] corpus::Error),
+}
+
+/// Code cache result.
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Cache of parity check matrices.
+///
+/// The cache stores the parity check matrices keyed by their code
+/// specification string. See the [module documentation](self) for the format
+/// of the code specifications.
+#[derive(Debug, Default)]
+pub struct CodeCache {
+    codes: Mutex<HashMap<String, Arc<SparseMatrix>>>,
+}
+
+impl CodeCache {
+    /// Creates a new empty cache.
+    pub fn new() -> CodeCache {
+        CodeCache::default()
+    }
+
+    /// Returns the process-wide cache.
+    pub fn global() -> &'static CodeCache {
+        static GLOBAL: OnceLock<CodeCache> = OnceLock::new();
+        GLOBAL.get_or_init(CodeCache::new)
+    }
+
+    /// Returns the parity check matrix for a code specification.
+    ///
+    /// If the matrix is not in the cache, it is constructed and inserted in the
+    /// cache. The construction is done without holding the cache lock, so two
+    /// threads that request the same code simultaneously can both construct
+    /// it, but only one of the matrices is kept.
+    pub fn get(&self, spec: &str) -> Result<Arc<SparseMatrix>> {
+        if let Some(h) = self.codes.lock().unwrap().get(spec) {
+            return Ok(Arc::clone(h));
+        }
+        let h = Arc::new(build_code(spec)?);
+        Ok(Arc::clone(
+            self.codes
+                .lock()
+                .unwrap()
+                .entry(spec.to_string())
+                .or_insert(h),
+        ))
+    }
+
+    /// Inserts a parity check matrix in the cache.
+    ///
+    /// This can be used to make codes constructed by other means available
+    /// under an arbitrary key. If the key was already present, its matrix is
+    /// replaced.
+    pub fn insert(&self, spec: &str, h: Arc<SparseMatrix>) {
+        self.codes.lock().unwrap().insert(spec.to_string(), h);
+    }
+
+    /// Removes all the matrices from the cache.
+    pub fn clear(&self) {
+        self.codes.lock().unwrap().clear();
+    }
+
+    /// Returns the number of matrices in the cache.
+    pub fn len(&self) -> usize {
+        self.codes.lock().unwrap().len()
+    }
+
+    /// Returns `true` if the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Constructs the parity check matrix for a code specification.
+///
+/// This function does not use any cache. See the [module
+/// documentation](self) for the format of the code specifications.
+pub fn build_code(spec: &str) -> Result<SparseMatrix> {
+    Ok(code_from_spec(spec)?.h())
+}
+
+/// Returns the code for a code specification.
+///
+/// The code gives access to the parity check matrix and the metadata through
+/// the [`Code`] trait. See the [module documentation](self) for the format of
+/// the code specifications. For `alist:<path>` specifications, the alist file
+/// is read by this function, and the name of the code is the path.
+///
+/// # Examples
+/// ```
+/// # use ldpc_toolbox::codes::cache::code_from_spec;
+/// let code = code_from_spec("ar4ja:1/2:1024")?;
+/// assert_eq!(code.name(), "CCSDS AR4JA r=1/2 k=1024");
+/// assert_eq!((code.n(), code.k()), (2560, 1024));
+/// assert_eq!(code.rate(), 0.5);
+/// # Ok::<(), ldpc_toolbox::codes::cache::Error>(())
+/// ```
+pub fn code_from_spec(spec: &str) -> Result<Box<dyn Code>> {
+    let invalid = || Error::InvalidSpec(spec.to_string());
+    let (kind, params) = spec.split_once(':').unwrap_or((spec, ""));
+    match kind {
+        "dvbs2" => {
+            let (rate, short) = match params.split_once(':') {
+                Some((rate, "short")) => (rate, true),
+                Some(_) => return Err(invalid()),
+                None => (params, false),
+            };
+            Ok(Box::new(
+                dvbs2::Code::from_rate(rate, short).ok_or_else(invalid)?,
+            ))
+        }
+        "dvbs2x" => {
+            let (rate, params) = params.split_once(':').ok_or_else(invalid)?;
+            let (frame, path) = params.split_once(':').ok_or_else(invalid)?;
+            let frame = frame.parse().map_err(|_| invalid())?;
+            let rate = dvbs2x::Rate::from_rate(rate, frame).ok_or_else(invalid)?;
+            Ok(Box::new(dvbs2x::Code::from_file(rate, path)?))
+        }
+        "ar4ja" => {
+            let (rate, k) = params.split_once(':').ok_or_else(invalid)?;
+            let rate = AR4JARate::from_rate(rate).ok_or_else(invalid)?;
+            let k = k.parse().ok().and_then(AR4JAInfoSize::from_k);
+            Ok(Box::new(AR4JACode::new(rate, k.ok_or_else(invalid)?)))
+        }
+        "c2" if params.is_empty() => Ok(Box::new(C2Code::new())),
+        "ccsds-tc" => {
+            let k = params.parse().ok().and_then(TcCode::from_k);
+            Ok(Box::new(k.ok_or_else(invalid)?))
+        }
+        "wimax" => {
+            let (rate, n) = params.split_once(':').ok_or_else(invalid)?;
+            let rate = WimaxRate::from_rate(rate).ok_or_else(invalid)?;
+            let code = n.parse().ok().and_then(|n| WimaxCode::new(rate, n));
+            Ok(Box::new(code.ok_or_else(invalid)?))
+        }
+        "reference" => {
+            let code = match params {
+                "hamming7_4" => ReferenceCode::Hamming7_4,
+                "ext_hamming8_4" => ReferenceCode::ExtendedHamming8_4,
+                "bch15_7" => ReferenceCode::Bch15_7,
+                "golay23_12" => ReferenceCode::Golay23_12,
+                "ext_golay24_12" => ReferenceCode::ExtendedGolay24_12,
+                _ => return Err(invalid()),
+            };
+            Ok(Box::new(code))
+        }
+        "example" => Ok(Box::new(
+            ExampleCode::from_name(params).ok_or_else(invalid)?,
+        )),
+        "alist" if !params.is_empty() => {
+            let alist = std::fs::read_to_string(params)?;
+            let h = SparseMatrix::from_alist(&alist).map_err(Error::Alist)?;
+            Ok(Box::new(MatrixCode::new(params, h)))
+        }
+        "corpus" if !params.is_empty() => {
+            let h = Corpus::with_default_dir()?.load(params)?;
+            Ok(Box::new(MatrixCode::new(params, h)))
+        }
+        _ => Err(invalid()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn specs() {
+        assert_eq!(build_code("dvbs2:1/2").unwrap(), dvbs2::Code::R1_2.h());
+        assert_eq!(
+            build_code("dvbs2:2/3:short").unwrap(),
+            dvbs2::Code::R2_3short.h()
+        );
+        assert_eq!(build_code("c2").unwrap(), C2Code::new().h());
+        assert_eq!(build_code("ccsds-tc:128").unwrap(), TcCode::N256.h());
+        assert_eq!(
+            build_code("wimax:3/4B:960").unwrap(),
+            WimaxCode::new(WimaxRate::R3_4B, 960).unwrap().h()
+        );
+        assert_eq!(
+            build_code("reference:golay23_12").unwrap(),
+            ReferenceCode::Golay23_12.h()
+        );
+        assert_eq!(
+            build_code("example:qc42_21").unwrap(),
+            ExampleCode::Qc42_21.h()
+        );
+        for spec in [
+            "",
+            "dvbs2",
+            "dvbs2:9/10:short",
+            "dvbs2:1/2:long",
+            "ar4ja:1/2",
+            "ar4ja:1/2:1000",
+            "c2:1/2",
+            "ccsds-tc",
+            "ccsds-tc:32",
+            "wimax:1/2",
+            "wimax:3/4:576",
+            "wimax:5/6:600",
+            "reference:hamming",
+            "example:johnson",
+            "alist:",
+            "dvbs2x:medium",
+            "dvbs2x:1/3:medium",
+            "dvbs2x:1/3:long:table.txt",
+            "dvbs2x:1/2:medium:table.txt",
+            "turbo:1/3",
+        ] {
+            assert!(matches!(build_code(spec), Err(Error::InvalidSpec(_))));
+        }
+        let code = code_from_spec("dvbs2:1/4:short").unwrap();
+        assert_eq!(code.name(), "DVB-S2 r=1/4 short FECFRAME");
+        assert_eq!(code.k(), 3240);
+        let code = code_from_spec("reference:ext_golay24_12").unwrap();
+        assert_eq!(code.name(), "extended Golay (24, 12)");
+        assert!(code.standard_puncturing().is_none());
+    }
+
+    #[test]
+    fn presets() {
+        for spec in ["dvbs2:1/2", "ar4ja:4/5:4096", "c2", "reference:bch15_7"] {
+            let preset = code_from_spec(spec).unwrap().decoder_preset().unwrap();
+            assert!(preset.max_iterations > 0);
+        }
+        let h = ReferenceCode::Hamming7_4.h();
+        assert!(MatrixCode::new("hamming".to_string(), h)
+            .decoder_preset()
+            .is_none());
+    }
+
+    #[test]
+    fn cache() {
+        let cache = CodeCache::new();
+        assert!(cache.is_empty());
+        let h = cache.get("reference:hamming7_4").unwrap();
+        assert!(Arc::ptr_eq(&h, &cache.get("reference:hamming7_4").unwrap()));
+        assert!(cache.get("reference:hamming").is_err());
+        assert_eq!(cache.len(), 1);
+        let h2 = Arc::new(ReferenceCode::Bch15_7.h());
+        cache.insert("bch", Arc::clone(&h2));
+        assert!(Arc::ptr_eq(&h2, &cache.get("bch").unwrap()));
+        cache.clear();
+        assert!(cache.is_empty());
+    }
+}
@@ -6,7 +6,14 @@
 //! ## References
 //! \[1\] [CCSDS 131.0-B-5 TM Synchronization and Channel Coding Blue Book](https://public.ccsds.org/Pubs/131x0b5.pdf).
 
-use crate::sparse::SparseMatrix;
+use super::{
+    verify::{check_col_weights, check_row_weights, TableCheck},
+    DecoderPreset,
+};
+use crate::{
+    decoder::factory::DecoderImplementation,
+    sparse::{Col, QcMatrix, Row, SparseMatrix},
+};
 use enum_iterator::Sequence;
 
 /// AR4JA code definition.
@@ -38,6 +45,55 @@ pub enum AR4JAInfoSize {
     K16384,
 }
 
+impl AR4JARate {
+    /// Returns the AR4JA rate corresponding to a string such as `"1/2"`.
+    ///
+    /// Returns `None` if there is no AR4JA code with this rate.
+    pub fn from_rate(rate: &str) -> Option<AR4JARate> {
+        Some(match rate {
+            "1/2" => AR4JARate::R1_2,
+            "2/3" => AR4JARate::R2_3,
+            "4/5" => AR4JARate::R4_5,
+            _ => return None,
+        })
+    }
+}
+
+impl std::fmt::Display for AR4JARate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let rate = match self {
+            AR4JARate::R1_2 => "1/2",
+            AR4JARate::R2_3 => "2/3",
+            AR4JARate::R4_5 => "4/5",
+        };
+        write!(f, "{rate}")
+    }
+}
+
+impl AR4JAInfoSize {
+    /// Returns the AR4JA information block size corresponding to `k`.
+    ///
+    /// Returns `None` if there is no AR4JA code with this information block
+    /// size.
+    pub fn from_k(k: usize) -> Option<AR4JAInfoSize> {
+        Some(match k {
+            1024 => AR4JAInfoSize::K1024,
+            4096 => AR4JAInfoSize::K4096,
+            16384 => AR4JAInfoSize::K16384,
+            _ => return None,
+        })
+    }
+
+    /// Returns the information block size `k`.
+    pub fn k(&self) -> usize {
+        match self {
+            AR4JAInfoSize::K1024 => 1024,
+            AR4JAInfoSize::K4096 => 4096,
+            AR4JAInfoSize::K16384 => 16384,
+        }
+    }
+}
+
 impl AR4JACode {
     /// Creates an AR4JA code definition.
     pub fn new(rate: AR4JARate, information_block_size: AR4JAInfoSize) -> AR4JACode {
@@ -47,44 +103,47 @@ impl AR4JACode {
         }
     }
 
+    /// Returns the size M of the submatrices of the parity check matrix.
+    ///
+    /// The parity check matrix has 3M rows, and its last 3M columns contain
+    /// the parity bits. The last M columns are punctured.
+    pub fn submatrix_size(&self) -> usize {
+        1 << self.m().log2()
+    }
+
     /// Constructs the parity check matrix for the code.
     pub fn h(&self) -> SparseMatrix {
-        let m = 1 << self.m().log2();
-        let extra_column_blocks = match self.rate {
-            AR4JARate::R1_2 => 0,
-            AR4JARate::R2_3 => 2,
-            AR4JARate::R4_5 => 6,
-        };
-        let extra_columns = m * extra_column_blocks;
+        let m = self.submatrix_size();
+        let extra_columns = m * self.extra_column_blocks();
         let mut h = SparseMatrix::new(3 * m, extra_columns + 5 * m);
 
         // fill common part (H_1/2)
         for i in 0..m {
             // block(0,2) = I_M
-            h.insert(i, extra_columns + 2 * m + i);
+            h.insert(Row(i), Col(extra_columns + 2 * m + i));
             // block(0,4) = I_M + Pi_1
-            h.insert(i, extra_columns + 4 * m + i);
-            h.toggle(i, extra_columns + 4 * m + self.pi(1, i));
+            h.insert(Row(i), Col(extra_columns + 4 * m + i));
+            h.toggle(Row(i), Col(extra_columns + 4 * m + self.pi(1, i)));
             // block(1,0) = I_M
-            h.insert(m + i, extra_columns + i);
+            h.insert(Row(m + i), Col(extra_columns + i));
             // block(1,1) = I_M
-            h.insert(m + i, extra_columns + m + i);
+            h.insert(Row(m + i), Col(extra_columns + m + i));
             // block(1,3) = I_M
-            h.insert(m + i, extra_columns + 3 * m + i);
+            h.insert(Row(m + i), Col(extra_columns + 3 * m + i));
             // block(1,4) = Pi_2 + Pi_3 + Pi_4
-            h.insert(m + i, extra_columns + 4 * m + self.pi(2, i));
-            h.toggle(m + i, extra_columns + 4 * m + self.pi(3, i));
-            h.toggle(m + i, extra_columns + 4 * m + self.pi(4, i));
+            h.insert(Row(m + i), Col(extra_columns + 4 * m + self.pi(2, i)));
+            h.toggle(Row(m + i), Col(extra_columns + 4 * m + self.pi(3, i)));
+            h.toggle(Row(m + i), Col(extra_columns + 4 * m + self.pi(4, i)));
             // block(2,0) = I_M
-            h.insert(2 * m + i, extra_columns + i);
+            h.insert(Row(2 * m + i), Col(extra_columns + i));
             // block(2,1) = Pi_5 + Pi_6
-            h.insert(2 * m + i, extra_columns + m + self.pi(5, i));
-            h.toggle(2 * m + i, extra_columns + m + self.pi(6, i));
+            h.insert(Row(2 * m + i), Col(extra_columns + m + self.pi(5, i)));
+            h.toggle(Row(2 * m + i), Col(extra_columns + m + self.pi(6, i)));
             // block(2,3) = Pi_7 + Pi_8
-            h.insert(2 * m + i, extra_columns + 3 * m + self.pi(7, i));
-            h.toggle(2 * m + i, extra_columns + 3 * m + self.pi(8, i));
+            h.insert(Row(2 * m + i), Col(extra_columns + 3 * m + self.pi(7, i)));
+            h.toggle(Row(2 * m + i), Col(extra_columns + 3 * m + self.pi(8, i)));
             // block(2,4) = I_M
-            h.insert(2 * m + i, extra_columns + 4 * m + i);
+            h.insert(Row(2 * m + i), Col(extra_columns + 4 * m + i));
         }
 
         if !matches!(self.rate, AR4JARate::R1_2) {
@@ -96,17 +155,17 @@ impl AR4JACode {
             };
             for i in 0..m {
                 // block(1,0) = Pi_9 + Pi_10 + Pi_11
-                h.insert(m + i, extra_columns + self.pi(9, i));
-                h.toggle(m + i, extra_columns + self.pi(10, i));
-                h.toggle(m + i, extra_columns + self.pi(11, i));
+                h.insert(Row(m + i), Col(extra_columns + self.pi(9, i)));
+                h.toggle(Row(m + i), Col(extra_columns + self.pi(10, i)));
+                h.toggle(Row(m + i), Col(extra_columns + self.pi(11, i)));
                 // block(1,1) = I_M
-                h.insert(m + i, extra_columns + m + i);
+                h.insert(Row(m + i), Col(extra_columns + m + i));
                 // block(2,0) = I_M
-                h.insert(2 * m + i, extra_columns + i);
+                h.insert(Row(2 * m + i), Col(extra_columns + i));
                 // block(2,1) = Pi_12 + Pi_13 + Pi_14
-                h.insert(2 * m + i, extra_columns + m + self.pi(12, i));
-                h.toggle(2 * m + i, extra_columns + m + self.pi(13, i));
-                h.toggle(2 * m + i, extra_columns + m + self.pi(14, i));
+                h.insert(Row(2 * m + i), Col(extra_columns + m + self.pi(12, i)));
+                h.toggle(Row(2 * m + i), Col(extra_columns + m + self.pi(13, i)));
+                h.toggle(Row(2 * m + i), Col(extra_columns + m + self.pi(14, i)));
             }
         }
 
@@ -114,35 +173,44 @@ impl AR4JACode {
             // fill specific H_4/5 part
             for i in 0..m {
                 // block(1,0) = Pi_21 + Pi_22 + Pi_23
-                h.insert(m + i, self.pi(21, i));
-                h.toggle(m + i, self.pi(22, i));
-                h.toggle(m + i, self.pi(23, i));
+                h.insert(Row(m + i), Col(self.pi(21, i)));
+                h.toggle(Row(m + i), Col(self.pi(22, i)));
+                h.toggle(Row(m + i), Col(self.pi(23, i)));
                 // block(1,1) = I_M
-                h.insert(m + i, m + i);
+                h.insert(Row(m + i), Col(m + i));
                 // block(1,2) = Pi_15 + Pi_16 + Pi_17
-                h.insert(m + i, 2 * m + self.pi(15, i));
-                h.toggle(m + i, 2 * m + self.pi(16, i));
-                h.toggle(m + i, 2 * m + self.pi(17, i));
+                h.insert(Row(m + i), Col(2 * m + self.pi(15, i)));
+                h.toggle(Row(m + i), Col(2 * m + self.pi(16, i)));
+                h.toggle(Row(m + i), Col(2 * m + self.pi(17, i)));
                 // block(1,3) = I_M
-                h.insert(m + i, 3 * m + i);
+                h.insert(Row(m + i), Col(3 * m + i));
                 // block(2,0) = I_M
-                h.insert(2 * m + i, i);
+                h.insert(Row(2 * m + i), Col(i));
                 // block(2,1) = Pi_24 + Pi_25 + Pi_26
-                h.insert(2 * m + i, m + self.pi(24, i));
-                h.toggle(2 * m + i, m + self.pi(25, i));
-                h.toggle(2 * m + i, m + self.pi(26, i));
+                h.insert(Row(2 * m + i), Col(m + self.pi(24, i)));
+                h.toggle(Row(2 * m + i), Col(m + self.pi(25, i)));
+                h.toggle(Row(2 * m + i), Col(m + self.pi(26, i)));
                 // block(2,2) = I_M
-                h.insert(2 * m + i, 2 * m + i);
+                h.insert(Row(2 * m + i), Col(2 * m + i));
                 // block(2,3) = Pi_18 + Pi_19 + Pi_20
-                h.insert(2 * m + i, 3 * m + self.pi(18, i));
-                h.toggle(2 * m + i, 3 * m + self.pi(19, i));
-                h.toggle(2 * m + i, 3 * m + self.pi(20, i));
+                h.insert(Row(2 * m + i), Col(3 * m + self.pi(18, i)));
+                h.toggle(Row(2 * m + i), Col(3 * m + self.pi(19, i)));
+                h.toggle(Row(2 * m + i), Col(3 * m + self.pi(20, i)));
             }
         }
 
         h
     }
 
+    // Number of blocks of M columns added to the rate 1/2 code
+    fn extra_column_blocks(&self) -> usize {
+        match self.rate {
+            AR4JARate::R1_2 => 0,
+            AR4JARate::R2_3 => 2,
+            AR4JARate::R4_5 => 6,
+        }
+    }
+
     // Table 7.2 in [1]
     fn m(&self) -> M {
         match (self.rate, self.k) {
@@ -187,6 +255,148 @@ impl AR4JACode {
     }
 }
 
+impl super::Code for AR4JACode {
+    fn h(&self) -> SparseMatrix {
+        AR4JACode::h(self)
+    }
+
+    fn n(&self) -> usize {
+        (self.extra_column_blocks() + 5) * self.submatrix_size()
+    }
+
+    fn k(&self) -> usize {
+        self.k.k()
+    }
+
+    fn standard_puncturing(&self) -> Option<Vec<bool>> {
+        // The last block of M columns is punctured
+        let mut pattern = vec![true; self.extra_column_blocks() + 5];
+        *pattern.last_mut().unwrap() = false;
+        Some(pattern)
+    }
+
+    fn name(&self) -> String {
+        format!("CCSDS AR4JA r={} k={}", self.rate, self.k.k())
+    }
+
+    fn decoder_preset(&self) -> Option<DecoderPreset> {
+        Some(DecoderPreset {
+            implementation: DecoderImplementation::Phif64,
+            max_iterations: 100,
+        })
+    }
+}
+
+impl super::Code for C2Code {
+    fn h(&self) -> SparseMatrix {
+        C2Code::h(self)
+    }
+
+    fn n(&self) -> usize {
+        Self::COL_BLOCKS * 511
+    }
+
+    fn k(&self) -> usize {
+        // The rows of H are not linearly independent: H has rank 1020
+        7156
+    }
+
+    fn name(&self) -> String {
+        "CCSDS C2 (8176, 7156)".to_string()
+    }
+
+    fn decoder_preset(&self) -> Option<DecoderPreset> {
+        Some(DecoderPreset {
+            implementation: DecoderImplementation::Phif64,
+            max_iterations: 50,
+        })
+    }
+}
+
+// Adds the checks of the CCSDS tables to a report.
+pub(crate) fn verify_tables(checks: &mut Vec<TableCheck>) {
+    checks.push(TableCheck::run(
+        "AR4JA theta_k",
+        "entries in 0..4",
+        || match THETA_K.iter().position(|&theta| theta >= 4) {
+            Some(k) => Err(format!("theta_{} = {}", k + 1, THETA_K[k])),
+            None => Ok(()),
+        },
+    ));
+    checks.push(TableCheck::run("AR4JA phi_k", "entries in 0..M/4", || {
+        for (j, table) in PHI_K.iter().enumerate() {
+            for (k, row) in table.iter().enumerate() {
+                for (m_index, &phi) in row.iter().enumerate() {
+                    let m = 1 << (M::M128.log2() + m_index);
+                    if phi >= m / 4 {
+                        return Err(format!("phi_{}({j}) = {phi} for M = {m}", k + 1));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }));
+    for rate in enum_iterator::all::<AR4JARate>() {
+        for info_size in enum_iterator::all::<AR4JAInfoSize>() {
+            let code = AR4JACode::new(rate, info_size);
+            let name = super::Code::name(&code);
+            let m = code.submatrix_size();
+            checks.push(TableCheck::run(
+                name.as_str(),
+                "pi_k are permutations",
+                || {
+                    for k in 1..=26 {
+                        let mut seen = vec![false; m];
+                        for i in 0..m {
+                            let p = code.pi(k, i);
+                            if seen[p] {
+                                return Err(format!("pi_{k} is not a permutation"));
+                            }
+                            seen[p] = true;
+                        }
+                    }
+                    Ok(())
+                },
+            ));
+            checks.push(TableCheck::run(name.as_str(), "number of ones", || {
+                // Number of ones in each group of M rows given by the sums of
+                // permutations in the definition of H
+                let per_row = match rate {
+                    AR4JARate::R1_2 => 15,
+                    AR4JARate::R2_3 => 23,
+                    AR4JARate::R4_5 => 39,
+                };
+                let ones = code.h().iter_all().count();
+                if ones != per_row * m {
+                    return Err(format!("found {ones}, expected {}", per_row * m));
+                }
+                Ok(())
+            }));
+        }
+    }
+    checks.push(TableCheck::run(
+        "C2 circulants",
+        "entries in 0..511",
+        || {
+            for (row, circs) in C2_CIRCULANTS.iter().enumerate() {
+                for (col, circs) in circs.iter().enumerate() {
+                    if circs.iter().any(|&c| c >= 511) || circs[0] == circs[1] {
+                        return Err(format!("invalid circulants in block ({row}, {col})"));
+                    }
+                }
+            }
+            Ok(())
+        },
+    ));
+    let h = C2Code::new().h();
+    checks.push(TableCheck::run("C2", "row weights", || {
+        check_row_weights(&h, &[C2Code::BLOCK_WEIGHT * C2Code::COL_BLOCKS])
+    }));
+    checks.push(TableCheck::run("C2", "column weights", || {
+        check_col_weights(&h, &[C2Code::BLOCK_WEIGHT * C2Code::ROW_BLOCKS])
+    }));
+}
+
 enum M {
     M128,
     M256,
@@ -352,18 +562,13 @@ impl C2Code {
     /// Constructs the parity check matrix for the code.
     pub fn h(&self) -> SparseMatrix {
         const N: usize = 511;
-        let mut h = SparseMatrix::new(Self::ROW_BLOCKS * N, Self::COL_BLOCKS * N);
+        let mut qc = QcMatrix::new(Self::ROW_BLOCKS, Self::COL_BLOCKS);
         for (row, circs) in C2_CIRCULANTS.iter().enumerate() {
             for (col, circs) in circs.iter().enumerate() {
-                for &circ in circs.iter() {
-                    let circ = usize::from(circ);
-                    for j in 0..N {
-                        h.insert(row * N + j, col * N + (j + circ) % N);
-                    }
-                }
+                qc.set_shifts(row, col, circs.iter().map(|&c| usize::from(c)));
             }
         }
-        h
+        qc.lift(N)
     }
 
     const ROW_BLOCKS: usize = 2;
@@ -422,6 +627,26 @@ mod test {
         m / 4 * ((theta_k + (4 * i / m)) % 4) + (phi_k + i) % (m / 4)
     }
 
+    #[test]
+    fn code_trait() {
+        use crate::codes::Code;
+
+        for rate in enum_iterator::all() {
+            for info_k in enum_iterator::all() {
+                let code = AR4JACode::new(rate, info_k);
+                let h = code.h();
+                assert_eq!(Code::n(&code), h.num_cols());
+                assert_eq!(Code::k(&code), h.num_cols() - h.num_rows());
+            }
+        }
+        let code = AR4JACode::new(AR4JARate::R2_3, AR4JAInfoSize::K4096);
+        assert_eq!(code.name(), "CCSDS AR4JA r=2/3 k=4096");
+        assert!((code.rate() - 2.0 / 3.0).abs() < 1e-12);
+        let code = C2Code::new();
+        assert_eq!(code.k(), 7156);
+        assert_eq!(code.h().num_cols(), code.n());
+    }
+
     // Checks that AR4JACode::pi matches the simpler (but less efficient)
     // implementation given in pi_k_model.
     #[test]
@@ -0,0 +1,193 @@
+//! CCSDS TC Synchronization and Channel Coding LDPC codes.
+//!
+//! This module contains the short block length LDPC codes described in the TC
+//! Synchronization and Channel Coding Blue Book for the telecommand uplink.
+//! These are three rate 1/2 codes with information block sizes `k = 64`, `128`
+//! and `256` (codeword lengths `n = 128`, `256` and `512`).
+//!
+//! The parity check matrix of each code is formed by 4 x 8 blocks of size M x
+//! M, where M = k / 4. Each block is either zero or a sum of at most two
+//! circulant permutation matrices. The first `k` columns of the parity check
+//! matrix correspond to the information bits.
+//!
+//! ## References
+//! \[1\] [CCSDS 231.0-B-4 TC Synchronization and Channel Coding Blue Book](https://public.ccsds.org/Pubs/231x0b4e1.pdf).
+
+use super::{
+    verify::{check_col_weights, check_row_weights, TableCheck},
+    DecoderPreset,
+};
+use crate::{
+    decoder::factory::DecoderImplementation,
+    sparse::{Col, QcMatrix, SparseMatrix},
+};
+use enum_iterator::Sequence;
+
+/// CCSDS TC LDPC code definition.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Sequence)]
+pub enum TcCode {
+    /// (128, 64) code.
+    N128,
+    /// (256, 128) code.
+    N256,
+    /// (512, 256) code.
+    N512,
+}
+
+impl TcCode {
+    /// Returns the TC code with information block size `k`.
+    ///
+    /// Returns `None` if there is no TC code with this information block size.
+    pub fn from_k(k: usize) -> Option<TcCode> {
+        Some(match k {
+            64 => TcCode::N128,
+            128 => TcCode::N256,
+            256 => TcCode::N512,
+            _ => return None,
+        })
+    }
+
+    /// Returns the size M of the submatrices of the parity check matrix.
+    pub fn submatrix_size(&self) -> usize {
+        match self {
+            TcCode::N128 => 16,
+            TcCode::N256 => 32,
+            TcCode::N512 => 64,
+        }
+    }
+
+    /// Constructs the parity check matrix for the code.
+    pub fn h(&self) -> SparseMatrix {
+        let mut qc = QcMatrix::new(Self::ROW_BLOCKS, Self::COL_BLOCKS);
+        for (row, circs) in self.circulants().iter().enumerate() {
+            for (col, circs) in circs.iter().enumerate() {
+                qc.set_shifts(row, col, circs.iter().map(|&c| usize::from(c)));
+            }
+        }
+        qc.lift(self.submatrix_size())
+    }
+
+    // Section 3.2 in [1]
+    fn circulants(&self) -> &'static TcCirculants {
+        match self {
+            TcCode::N128 => &TC_128_CIRCULANTS,
+            TcCode::N256 => &TC_256_CIRCULANTS,
+            TcCode::N512 => &TC_512_CIRCULANTS,
+        }
+    }
+
+    const ROW_BLOCKS: usize = 4;
+    const COL_BLOCKS: usize = 8;
+}
+
+impl super::Code for TcCode {
+    fn h(&self) -> SparseMatrix {
+        TcCode::h(self)
+    }
+
+    fn n(&self) -> usize {
+        Self::COL_BLOCKS * self.submatrix_size()
+    }
+
+    fn k(&self) -> usize {
+        Self::ROW_BLOCKS * self.submatrix_size()
+    }
+
+    fn name(&self) -> String {
+        format!(
+            "CCSDS TC ({}, {})",
+            super::Code::n(self),
+            super::Code::k(self)
+        )
+    }
+
+    fn decoder_preset(&self) -> Option<DecoderPreset> {
+        Some(DecoderPreset {
+            implementation: DecoderImplementation::Phif64,
+            max_iterations: 100,
+        })
+    }
+}
+
+// Adds the checks of the CCSDS TC tables to a report.
+pub(crate) fn verify_tables(checks: &mut Vec<TableCheck>) {
+    for code in enum_iterator::all::<TcCode>() {
+        let name = super::Code::name(&code);
+        let m = code.submatrix_size();
+        checks.push(TableCheck::run(name.as_str(), "circulants in 0..M", || {
+            for (row, circs) in code.circulants().iter().enumerate() {
+                for (col, circs) in circs.iter().enumerate() {
+                    if circs.iter().any(|&c| usize::from(c) >= m)
+                        || (circs.len() == 2 && circs[0] == circs[1])
+                    {
+                        return Err(format!("invalid circulants in block ({row}, {col})"));
+                    }
+                }
+            }
+            Ok(())
+        }));
+        let h = code.h();
+        checks.push(TableCheck::run(name.as_str(), "row weights", || {
+            check_row_weights(&h, &[8])
+        }));
+        checks.push(TableCheck::run(name.as_str(), "column weights", || {
+            // The information columns have weight 5 and the parity columns
+            // have weight 3
+            check_col_weights(&h, &[3, 5])?;
+            match (0..h.num_cols()).find(|&c| (h.col_weight(Col(c)) == 5) != (c < 4 * m)) {
+                Some(c) => Err(format!("column {c} has weight {}", h.col_weight(Col(c)))),
+                None => Ok(()),
+            }
+        }));
+    }
+}
+
+type TcCirculants = [[&'static [u8]; TcCode::COL_BLOCKS]; TcCode::ROW_BLOCKS];
+
+// The blocks I_M + Phi^s are written as [0, s], the blocks Phi^s as [s], and
+// the zero blocks as [].
+
+// (128, 64) code, M = 16
+static TC_128_CIRCULANTS: TcCirculants = [
+    [&[0, 7], &[2], &[14], &[6], &[], &[0], &[13], &[0]],
+    [&[6], &[0, 15], &[0], &[1], &[0], &[], &[0], &[7]],
+    [&[4], &[1], &[0, 15], &[14], &[11], &[0], &[], &[3]],
+    [&[0], &[1], &[9], &[0, 13], &[14], &[1], &[0], &[]],
+];
+
+// (256, 128) code, M = 32
+static TC_256_CIRCULANTS: TcCirculants = [
+    [&[0, 31], &[15], &[25], &[0], &[], &[20], &[12], &[0]],
+    [&[28], &[0, 30], &[29], &[24], &[0], &[], &[1], &[20]],
+    [&[8], &[0], &[0, 28], &[1], &[29], &[0], &[], &[21]],
+    [&[18], &[30], &[0], &[0, 30], &[25], &[26], &[0], &[]],
+];
+
+// (512, 256) code, M = 64
+static TC_512_CIRCULANTS: TcCirculants = [
+    [&[0, 63], &[30], &[50], &[25], &[], &[43], &[62], &[0]],
+    [&[56], &[0, 61], &[50], &[23], &[0], &[], &[37], &[26]],
+    [&[16], &[0], &[0, 55], &[27], &[56], &[0], &[], &[43]],
+    [&[35], &[56], &[62], &[0, 11], &[58], &[3], &[0], &[]],
+];
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{codes::Code, linalg::SparseSolver};
+
+    #[test]
+    fn code_trait() {
+        for code in enum_iterator::all::<TcCode>() {
+            let h = code.h();
+            assert_eq!(h.num_cols(), code.n());
+            // H has full rank
+            assert_eq!(SparseSolver::new(&h).rank(), code.n() - code.k());
+            assert_eq!(code.rate(), 0.5);
+            assert_eq!(TcCode::from_k(code.k()), Some(code));
+            assert_eq!(h.girth(), Some(6));
+        }
+        assert_eq!(TcCode::N256.name(), "CCSDS TC (256, 128)");
+        assert_eq!(TcCode::from_k(1024), None);
+    }
+}
@@ -0,0 +1,436 @@
+//! Corpus of public parity check matrices.
+//!
+//! This module contains a catalog of well-known parity check matrices that are
+//! publicly available online, such as entries of David MacKay's Encyclopedia
+//! of Sparse Graph Codes and matrices distributed with the AFF3CT toolbox. The
+//! matrices are downloaded on request into a local cache directory, so that
+//! examples, benchmarks and experiments can refer to them by name, for
+//! instance with the code specification `corpus:<name>` (see
+//! [`cache`](super::cache)).
+//!
+//! Network access only happens in [`Corpus::fetch`], which downloads the alist
+//! file using the `curl` command. All the other functions only read the cache
+//! directory. The size of each downloaded matrix is checked against the
+//! catalog, as well as its [fingerprint](SparseMatrix::fingerprint) for the
+//! entries that give one. Currently no entry of the catalog pins a fingerprint,
+//! so the contents of a download are not verified. The fingerprint of the
+//! downloaded matrix is recorded next to it in the cache, so that later
+//! modifications or corruption of the cached file are detected when it is
+//! loaded.
+//!
+//! # Examples
+//! ```
+//! # use ldpc_toolbox::codes::corpus::{Corpus, CATALOG};
+//! let corpus = Corpus::new(std::env::temp_dir().join("ldpc-toolbox-doctest-corpus"));
+//! let entry = &CATALOG[0];
+//! if !corpus.is_cached(entry.name) {
+//!     // corpus.fetch(entry.name) would download the matrix
+//!     assert!(corpus.load(entry.name).is_err());
+//! }
+//! ```
+
+use crate::sparse::SparseMatrix;
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+use thiserror::Error;
+
+/// Entry of the corpus catalog.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Entry {
+    /// Name used to refer to the matrix.
+    pub name: &'static str,
+    /// Collection to which the matrix belongs.
+    pub collection: &'static str,
+    /// URL of the alist file.
+    pub url: &'static str,
+    /// Number of rows of the parity check matrix.
+    pub num_rows: usize,
+    /// Number of columns of the parity check matrix.
+    pub num_cols: usize,
+    /// Expected fingerprint of the parity check matrix.
+    ///
+    /// This is `None` if the fingerprint of the matrix has not been pinned in
+    /// the catalog, in which case only its size is checked.
+    pub fingerprint: Option<u64>,
+}
+
+const MACKAY: &str = "MacKay's Encyclopedia of Sparse Graph Codes";
+const AFF3CT: &str = "AFF3CT configuration files";
+
+/// Catalog of the matrices in the corpus.
+pub const CATALOG: &[Entry] = &[
+    Entry {
+        name: "mackay-96.3.963",
+        collection: MACKAY,
+        url: "https://www.inference.org.uk/mackay/codes/EN/C/96.3.963",
+        num_rows: 48,
+        num_cols: 96,
+        fingerprint: None,
+    },
+    Entry {
+        name: "mackay-204.33.484",
+        collection: MACKAY,
+        url: "https://www.inference.org.uk/mackay/codes/EN/C/204.33.484",
+        num_rows: 102,
+        num_cols: 204,
+        fingerprint: None,
+    },
+    Entry {
+        name: "mackay-408.33.844",
+        collection: MACKAY,
+        url: "https://www.inference.org.uk/mackay/codes/EN/C/408.33.844",
+        num_rows: 204,
+        num_cols: 408,
+        fingerprint: None,
+    },
+    Entry {
+        name: "mackay-816.55.178",
+        collection: MACKAY,
+        url: "https://www.inference.org.uk/mackay/codes/EN/C/816.55.178",
+        num_rows: 408,
+        num_cols: 816,
+        fingerprint: None,
+    },
+    Entry {
+        name: "aff3ct-ccsds-64-128",
+        collection: AFF3CT,
+        url: "https://raw.githubusercontent.com/aff3ct/configuration_files/master/dec/LDPC/CCSDS_64_128.alist",
+        num_rows: 64,
+        num_cols: 128,
+        fingerprint: None,
+    },
+    Entry {
+        name: "aff3ct-wimax-288-576",
+        collection: AFF3CT,
+        url: "https://raw.githubusercontent.com/aff3ct/configuration_files/master/dec/LDPC/WIMAX_288_576.alist",
+        num_rows: 288,
+        num_cols: 576,
+        fingerprint: None,
+    },
+    Entry {
+        name: "aff3ct-wifi-648-1296",
+        collection: AFF3CT,
+        url: "https://raw.githubusercontent.com/aff3ct/configuration_files/master/dec/LDPC/WIFI_648_1296.alist",
+        num_rows: 648,
+        num_cols: 1296,
+        fingerprint: None,
+    },
+    Entry {
+        name: "aff3ct-10gbps-ethernet-1723-2048",
+        collection: AFF3CT,
+        url: "https://raw.githubusercontent.com/aff3ct/configuration_files/master/dec/LDPC/10GBPS-ETHERNET_1723_2048.alist",
+        num_rows: 384,
+        num_cols: 2048,
+        fingerprint: None,
+    },
+];
+
+/// Returns the catalog entry with a given name.
+pub fn find(name: &str) -> Option<&'static Entry> {
+    CATALOG.iter().find(|entry| entry.name == name)
+}
+
+/// Corpus error.
+#[derive(Debug, Error)]
+pub enum Error {
+    /// The name is not in the catalog.
+    #[error("unknown corpus code: {0}")]
+    UnknownCode(String),
+    /// The matrix has not been downloaded.
+    #[error("corpus code {0} is not cached (run `ldpc-toolbox fetch-codes --download {0}`)")]
+    NotCached(String),
+    /// No cache directory could be determined.
+    #[error("could not determine the corpus cache directory")]
+    NoCacheDir,
+    /// The download failed.
+    #[error("could not download {name}: {reason}")]
+    Download {
+        /// Name of the matrix.
+        name: String,
+        /// Description of the failure.
+        reason: String,
+    },
+    /// Error accessing the cache directory.
+    #[error("corpus cache error: {0}")]
+    Io(#[from] std::io::Error),
+    /// Error parsing the alist file.
+    #[error("could not parse alist of {name}: {reason}")]
+    Alist {
+        /// Name of the matrix.
+        name: String,
+        /// Description of the parsing error.
+        reason: String,
+    },
+    /// The matrix does not have the size given in the catalog.
+    #[error("{name} has size {num_rows}x{num_cols}, which does not match the catalog")]
+    WrongSize {
+        /// Name of the matrix.
+        name: String,
+        /// Number of rows of the matrix.
+        num_rows: usize,
+        /// Number of columns of the matrix.
+        num_cols: usize,
+    },
+    /// The fingerprint of the matrix does not match the one given in the
+    /// catalog.
+    #[error("{name} has fingerprint {found:016x}, but the catalog expects {expected:016x}")]
+    WrongFingerprint {
+        /// Name of the matrix.
+        name: String,
+        /// Fingerprint given in the catalog.
+        expected: u64,
+        /// Fingerprint of the matrix.
+        found: u64,
+    },
+    /// The fingerprint of the cached matrix does not match the one recorded
+    /// when it was downloaded.
+    #[error("cached {name} has fingerprint {found:016x}, but {expected:016x} was recorded")]
+    ChecksumMismatch {
+        /// Name of the matrix.
+        name: String,
+        /// Recorded fingerprint.
+        expected: u64,
+        /// Fingerprint of the cached matrix.
+        found: u64,
+    },
+}
+
+/// Corpus result.
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Local cache of the corpus.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Corpus {
+    dir: PathBuf,
+}
+
+impl Corpus {
+    /// Creates a corpus that uses a given cache directory.
+    ///
+    /// The directory is created when the first matrix is downloaded.
+    pub fn new(dir: impl Into<PathBuf>) -> Corpus {
+        Corpus { dir: dir.into() }
+    }
+
+    /// Creates a corpus that uses the default cache directory.
+    ///
+    /// The default directory is given by the environment variable
+    /// `LDPC_TOOLBOX_CORPUS` if it is set. Otherwise, it is the
+    /// `ldpc-toolbox` subdirectory of `$XDG_CACHE_HOME` or of
+    /// `$HOME/.cache`.
+    pub fn with_default_dir() -> Result<Corpus> {
+        let var = |name| std::env::var_os(name).filter(|v| !v.is_empty());
+        let dir = var("LDPC_TOOLBOX_CORPUS")
+            .map(PathBuf::from)
+            .or_else(|| var("XDG_CACHE_HOME").map(|d| PathBuf::from(d).join("ldpc-toolbox")))
+            .or_else(|| var("HOME").map(|d| PathBuf::from(d).join(".cache/ldpc-toolbox")))
+            .ok_or(Error::NoCacheDir)?;
+        Ok(Corpus::new(dir))
+    }
+
+    /// Returns the cache directory.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Returns the path of the cached alist file of a matrix.
+    pub fn path(&self, name: &str) -> PathBuf {
+        self.dir.join(format!("{name}.alist"))
+    }
+
+    fn fingerprint_path(&self, name: &str) -> PathBuf {
+        self.dir.join(format!("{name}.fingerprint"))
+    }
+
+    /// Returns `true` if a matrix has been downloaded to the cache.
+    pub fn is_cached(&self, name: &str) -> bool {
+        self.path(name).is_file() && self.fingerprint_path(name).is_file()
+    }
+
+    /// Loads a matrix from the cache.
+    ///
+    /// This does not access the network. It returns an error if the matrix
+    /// has not been downloaded or if its fingerprint does not match the one
+    /// recorded when it was downloaded.
+    pub fn load(&self, name: &str) -> Result<SparseMatrix> {
+        let entry = find(name).ok_or_else(|| Error::UnknownCode(name.to_string()))?;
+        if !self.is_cached(name) {
+            return Err(Error::NotCached(name.to_string()));
+        }
+        let h = parse(entry, &std::fs::read_to_string(self.path(name))?)?;
+        let expected = std::fs::read_to_string(self.fingerprint_path(name))?;
+        let expected = u64::from_str_radix(expected.trim(), 16).map_err(|_| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("invalid fingerprint file for {name}"),
+            )
+        })?;
+        let found = h.fingerprint();
+        if found != expected {
+            return Err(Error::ChecksumMismatch {
+                name: name.to_string(),
+                expected,
+                found,
+            });
+        }
+        Ok(h)
+    }
+
+    /// Downloads a matrix to the cache.
+    ///
+    /// If the matrix is already cached, it is loaded from the cache without
+    /// accessing the network. Otherwise, it is downloaded with `curl`, checked
+    /// against the size and fingerprint given in the catalog, and stored in
+    /// the cache together with its fingerprint.
+    pub fn fetch(&self, name: &str) -> Result<SparseMatrix> {
+        let entry = find(name).ok_or_else(|| Error::UnknownCode(name.to_string()))?;
+        if self.is_cached(name) {
+            return self.load(name);
+        }
+        std::fs::create_dir_all(&self.dir)?;
+        let download_path = self.dir.join(format!("{name}.download"));
+        let download_error = |reason| Error::Download {
+            name: name.to_string(),
+            reason,
+        };
+        let output = Command::new("curl")
+            .args([
+                "--fail",
+                "--silent",
+                "--show-error",
+                "--location",
+                "--output",
+            ])
+            .arg(&download_path)
+            .arg(entry.url)
+            .output()
+            .map_err(|e| download_error(format!("could not run curl: {e}")))?;
+        if !output.status.success() {
+            let _ = std::fs::remove_file(&download_path);
+            return Err(download_error(
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            ));
+        }
+        let alist = std::fs::read_to_string(&download_path);
+        let _ = std::fs::remove_file(&download_path);
+        let h = parse(entry, &alist?)?;
+        // The matrix is stored in the format written by this crate, so that
+        // the cache does not depend on the formatting of the original file.
+        std::fs::write(self.path(name), h.alist())?;
+        std::fs::write(
+            self.fingerprint_path(name),
+            format!("{:016x}\n", h.fingerprint()),
+        )?;
+        Ok(h)
+    }
+}
+
+fn parse(entry: &Entry, alist: &str) -> Result<SparseMatrix> {
+    let h = SparseMatrix::from_alist(alist).map_err(|reason| Error::Alist {
+        name: entry.name.to_string(),
+        reason,
+    })?;
+    if (h.num_rows(), h.num_cols()) != (entry.num_rows, entry.num_cols) {
+        return Err(Error::WrongSize {
+            name: entry.name.to_string(),
+            num_rows: h.num_rows(),
+            num_cols: h.num_cols(),
+        });
+    }
+    if let Some(expected) = entry.fingerprint {
+        let found = h.fingerprint();
+        if found != expected {
+            return Err(Error::WrongFingerprint {
+                name: entry.name.to_string(),
+                expected,
+                found,
+            });
+        }
+    }
+    Ok(h)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn catalog_names() {
+        let names = CATALOG.iter().map(|e| e.name).collect::<HashSet<_>>();
+        assert_eq!(names.len(), CATALOG.len());
+        assert_eq!(find("mackay-96.3.963").unwrap().num_cols, 96);
+        assert!(find("mackay").is_none());
+    }
+
+    #[test]
+    fn pinned_fingerprint() {
+        let mut h = SparseMatrix::new(2, 4);
+        for j in 0..2 {
+            h.insert(j, j);
+            h.insert(j, j + 2);
+        }
+        let entry = Entry {
+            name: "test",
+            collection: "test",
+            url: "",
+            num_rows: 2,
+            num_cols: 4,
+            fingerprint: Some(h.fingerprint()),
+        };
+        assert_eq!(parse(&entry, &h.alist()).unwrap(), h);
+        let mut modified = h.clone();
+        modified.insert(0, 1);
+        assert!(matches!(
+            parse(&entry, &modified.alist()),
+            Err(Error::WrongFingerprint { .. })
+        ));
+        let entry = Entry {
+            fingerprint: None,
+            ..entry
+        };
+        assert_eq!(
+            parse(&entry, &modified.alist()).unwrap().fingerprint(),
+            modified.fingerprint()
+        );
+    }
+
+    #[test]
+    fn load_from_cache() {
+        let dir = std::env::temp_dir().join(format!("ldpc-toolbox-corpus-{}", std::process::id()));
+        let corpus = Corpus::new(&dir);
+        let name = "aff3ct-ccsds-64-128";
+        assert!(matches!(corpus.load(name), Err(Error::NotCached(_))));
+        assert!(matches!(corpus.load("unknown"), Err(Error::UnknownCode(_))));
+
+        // Populate the cache as if the matrix had been downloaded
+        let mut h = SparseMatrix::new(64, 128);
+        for j in 0..64 {
+            h.insert(j, j);
+            h.insert(j, j + 64);
+        }
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(corpus.path(name), h.alist()).unwrap();
+        std::fs::write(
+            corpus.fingerprint_path(name),
+            format!("{:016x}\n", h.fingerprint()),
+        )
+        .unwrap();
+        assert!(corpus.is_cached(name));
+        assert_eq!(corpus.load(name).unwrap(), h);
+        // fetch does not download cached matrices
+        assert_eq!(corpus.fetch(name).unwrap(), h);
+
+        let mut modified = h.clone();
+        modified.insert(0, 1);
+        std::fs::write(corpus.path(name), modified.alist()).unwrap();
+        assert!(matches!(
+            corpus.load(name),
+            Err(Error::ChecksumMismatch { .. })
+        ));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
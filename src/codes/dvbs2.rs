@@ -7,7 +7,11 @@
 //!
 //! ## References
 //! \[1\] ETSI EN 302 307-1 V1.4.1 (2014-11)
-use crate::sparse::SparseMatrix;
+use super::verify::TableCheck;
+use crate::{
+    decoder::factory::DecoderImplementation,
+    sparse::{Row, SparseMatrix},
+};
 use enum_iterator::Sequence;
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
@@ -69,6 +73,45 @@ pub enum Code {
 }
 
 impl Code {
+    /// Returns the code with a given rate and FECFRAME length.
+    ///
+    /// The `rate` is given as a string such as `"1/2"`, and `short` indicates
+    /// whether the code is for short FECFRAMEs. Returns `None` if there is no
+    /// such code.
+    ///
+    /// # Examples
+    /// ```
+    /// # use ldpc_toolbox::codes::dvbs2::Code;
+    /// assert_eq!(Code::from_rate("1/2", false), Some(Code::R1_2));
+    /// assert_eq!(Code::from_rate("9/10", true), None);
+    /// ```
+    pub fn from_rate(rate: &str, short: bool) -> Option<Code> {
+        Some(match (rate, short) {
+            ("1/4", false) => Code::R1_4,
+            ("1/3", false) => Code::R1_3,
+            ("2/5", false) => Code::R2_5,
+            ("1/2", false) => Code::R1_2,
+            ("3/5", false) => Code::R3_5,
+            ("2/3", false) => Code::R2_3,
+            ("3/4", false) => Code::R3_4,
+            ("4/5", false) => Code::R4_5,
+            ("5/6", false) => Code::R5_6,
+            ("8/9", false) => Code::R8_9,
+            ("9/10", false) => Code::R9_10,
+            ("1/4", true) => Code::R1_4short,
+            ("1/3", true) => Code::R1_3short,
+            ("2/5", true) => Code::R2_5short,
+            ("1/2", true) => Code::R1_2short,
+            ("3/5", true) => Code::R3_5short,
+            ("2/3", true) => Code::R2_3short,
+            ("3/4", true) => Code::R3_4short,
+            ("4/5", true) => Code::R4_5short,
+            ("5/6", true) => Code::R5_6short,
+            ("8/9", true) => Code::R8_9short,
+            _ => return None,
+        })
+    }
+
     /// Construct parity check matrix for the code
     /// # Examples
     /// ```
@@ -78,23 +121,8 @@ impl Code {
     /// ```
     pub fn h(self) -> SparseMatrix {
         // Algorithm from Section 5.3.2.1 in [1]
-        let mut h = SparseMatrix::new(self.m(), self.n());
-        // systematic bits
-        for j in 0..self.k() {
-            let w = j % 360;
-            let t = j / 360;
-            let a = self.addresses()[t]
-                .iter()
-                .map(|x| (x + w * self.q()) % self.m());
-            h.insert_col(j, a);
-        }
-        // parity bits
-        h.insert(0, self.k());
-        for j in 1..self.m() {
-            h.insert(j, j + self.k());
-            h.insert(j, j + self.k() - 1);
-        }
-        h
+        SparseMatrix::from_dvbs2_tables(self.addresses(), self.n())
+            .expect("the DVB-S2 tables are valid")
     }
 
     const fn len(self) -> FrameLen {
@@ -123,7 +151,8 @@ impl Code {
         }
     }
 
-    const fn n(self) -> usize {
+    /// Returns the codeword length `n`.
+    pub const fn n(self) -> usize {
         match self.len() {
             FrameLen::Normal => 64800,
             FrameLen::Short => 16200,
@@ -149,14 +178,15 @@ impl Code {
             Code::R1_2short => self.n() * 5 / 9, // LDPC r=4/9
             Code::R3_5short => self.n() * 2 / 5,
             Code::R2_3short => self.n() / 3,
-            Code::R3_4short => self.n() * 14 / 15, // LDPC r=11/15
-            Code::R4_5short => self.n() * 2 / 9,   // LDPC r=7/9
-            Code::R5_6short => self.n() * 8 / 45,  // LDPC r=37/45
+            Code::R3_4short => self.n() * 4 / 15, // LDPC r=11/15
+            Code::R4_5short => self.n() * 2 / 9,  // LDPC r=7/9
+            Code::R5_6short => self.n() * 8 / 45, // LDPC r=37/45
             Code::R8_9short => self.n() / 9,
         }
     }
 
-    const fn k(self) -> usize {
+    /// Returns the number of information bits `k`.
+    pub const fn k(self) -> usize {
         self.n() - self.m()
     }
 
@@ -2168,6 +2198,88 @@ impl Code {
     }
 }
 
+// Adds the checks of the DVB-S2 tables to a report.
+pub(crate) fn verify_tables(checks: &mut Vec<TableCheck>) {
+    for code in enum_iterator::all::<Code>() {
+        let name = super::Code::name(&code);
+        checks.push(TableCheck::run(name.as_str(), "q = (n - k) / 360", || {
+            if code.q() * 360 != code.m() {
+                return Err(format!("q = {}, n - k = {}", code.q(), code.m()));
+            }
+            Ok(())
+        }));
+        checks.push(TableCheck::run(
+            name.as_str(),
+            "parity bit addresses",
+            || {
+                let addresses = code.addresses();
+                if addresses.len() * 360 != code.k() {
+                    return Err(format!("table has {} rows", addresses.len()));
+                }
+                for (t, row) in addresses.iter().enumerate() {
+                    if let Some(&x) = row.iter().find(|&&x| x >= code.m()) {
+                        return Err(format!("address {x} in row {t} out of range"));
+                    }
+                    if (1..row.len()).any(|j| row[..j].contains(&row[j])) {
+                        return Err(format!("repeated address in row {t}"));
+                    }
+                }
+                Ok(())
+            },
+        ));
+        checks.push(TableCheck::run(name.as_str(), "row weights", || {
+            // Row 0 has one less element than the other rows, since it only
+            // contains one parity bit. For the short FECFRAMEs in which the
+            // number of addresses is not a multiple of q, the row weights
+            // differ by at most two.
+            let h = code.h();
+            let w = h.row_weight(Row(0));
+            let weights = match code {
+                Code::R1_4short | Code::R4_5short => vec![w, w + 1, w + 2],
+                Code::R1_2short | Code::R3_4short | Code::R5_6short => return Ok(()),
+                _ => vec![w + 1],
+            };
+            match (1..h.num_rows()).find(|&r| !weights.contains(&h.row_weight(Row(r)))) {
+                Some(r) => Err(format!("row {r} has weight {}", h.row_weight(Row(r)))),
+                None => Ok(()),
+            }
+        }));
+    }
+}
+
+impl super::Code for Code {
+    fn h(&self) -> SparseMatrix {
+        Code::h(*self)
+    }
+
+    fn n(&self) -> usize {
+        Code::n(*self)
+    }
+
+    fn k(&self) -> usize {
+        Code::k(*self)
+    }
+
+    fn name(&self) -> String {
+        let short = self.len() == FrameLen::Short;
+        let rate = [
+            "1/4", "1/3", "2/5", "1/2", "3/5", "2/3", "3/4", "4/5", "5/6", "8/9", "9/10",
+        ]
+        .into_iter()
+        .find(|rate| Code::from_rate(rate, short) == Some(*self))
+        .unwrap();
+        let len = if short { "short" } else { "normal" };
+        format!("DVB-S2 r={rate} {len} FECFRAME")
+    }
+
+    fn decoder_preset(&self) -> Option<super::DecoderPreset> {
+        Some(super::DecoderPreset {
+            implementation: DecoderImplementation::Phif64,
+            max_iterations: 50,
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -2181,6 +2293,33 @@ mod tests {
         }
     }
 
+    #[test]
+    fn code_trait() {
+        use crate::codes::Code as _;
+
+        let code: &dyn crate::codes::Code = &Code::R1_4short;
+        assert_eq!(code.name(), "DVB-S2 r=1/4 short FECFRAME");
+        assert_eq!(code.n(), 16200);
+        assert_eq!(code.k(), 3240);
+        assert_eq!(code.rate(), 0.2);
+        assert_eq!(Code::R9_10.name(), "DVB-S2 r=9/10 normal FECFRAME");
+        // Table 5b in [1]
+        assert_eq!(Code::R3_4short.k(), 11880);
+    }
+
+    #[test]
+    fn dvbs2_tables() {
+        for code in enum_iterator::all::<Code>() {
+            let table = code.h().to_dvbs2_tables().unwrap();
+            assert_eq!(table.len(), code.addresses().len());
+            for (row, addresses) in table.iter().zip(code.addresses()) {
+                let mut addresses = addresses.to_vec();
+                addresses.sort_unstable();
+                assert_eq!(row, &addresses);
+            }
+        }
+    }
+
     #[test]
     fn regular_row_weight() {
         let irregular = [Code::R1_4short, Code::R4_5short];
@@ -0,0 +1,468 @@
+//! # DVB-S2X LDPC codes
+//!
+//! DVB-S2X adds many code rates to those of DVB-S2 (see
+//! [`dvbs2`](super::dvbs2)), and medium FECFRAMEs of 32400 bits, which are
+//! used by the very low SNR MODCODs. The parity check matrices of all these
+//! codes have the same structure as those of DVB-S2: the information bits are
+//! divided in groups of 360 bits, which are described by one row of a table in
+//! the annexes of \[1\], and the parity bits form an accumulator (see
+//! [`SparseMatrix::from_dvbs2_tables`]).
+//!
+//! The codes are identified by a [`Rate`], which gives the code rate and the
+//! FECFRAME length, and thus the number of information bits `k` of the code.
+//! The tables of the DVB-S2X codes are not included in this crate. A [`Code`]
+//! is constructed from its rate and its table, given as text in the format of
+//! the annexes of \[1\]: a line for each group of 360 information bits, with
+//! the addresses of the parity check accumulators separated by whitespace or
+//! commas. This is also the format written by the `convert` subcommand of the
+//! CLI with `--format dvbs2-tables`. Empty lines and lines starting with `#`
+//! are ignored. The number of lines of the table must match the `k` of the
+//! rate.
+//!
+//! # Examples
+//! ```
+//! # use ldpc_toolbox::codes::{dvbs2::Code as Dvbs2Code, dvbs2x::{Code, Rate}};
+//! # use ldpc_toolbox::codes::Code as _;
+//! // The table of the DVB-S2 code with the same length and k is used as an
+//! // example
+//! let h = Dvbs2Code::R1_4short.h();
+//! let table = h
+//!     .to_dvbs2_tables()?
+//!     .iter()
+//!     .map(|row| row.iter().map(|x| x.to_string()).collect::<Vec<_>>().join(" "))
+//!     .collect::<Vec<_>>()
+//!     .join("\n");
+//! let code = Code::from_tables(Rate::R1_5short, &table)?;
+//! assert_eq!(code.k(), 3240);
+//! assert_eq!(code.h().fingerprint(), h.fingerprint());
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! ## References
+//! \[1\] ETSI EN 302 307-2 V1.3.1 (2021-07)
+
+use crate::{decoder::factory::DecoderImplementation, sparse::SparseMatrix};
+use enum_iterator::Sequence;
+use thiserror::Error;
+
+/// DVB-S2X code error.
+#[derive(Debug, Error)]
+pub enum Error {
+    /// Error reading the table file.
+    #[error("could not read table file: {0}")]
+    Io(#[from] std::io::Error),
+    /// The table contains an invalid address.
+    #[error("invalid address {0} in table")]
+    InvalidAddress(String),
+    /// The table does not describe a code with the FECFRAME length.
+    #[error("{0}")]
+    InvalidTable(String),
+}
+
+/// DVB-S2X code result.
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// FECFRAME length.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum FrameLen {
+    /// Normal FECFRAME (64800 bits).
+    Normal,
+    /// Medium FECFRAME (32400 bits).
+    Medium,
+    /// Short FECFRAME (16200 bits).
+    Short,
+}
+
+impl FrameLen {
+    /// Returns the codeword length `n`.
+    pub const fn n(self) -> usize {
+        match self {
+            FrameLen::Normal => 64800,
+            FrameLen::Medium => 32400,
+            FrameLen::Short => 16200,
+        }
+    }
+}
+
+impl std::str::FromStr for FrameLen {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<FrameLen, String> {
+        Ok(match s {
+            "normal" => FrameLen::Normal,
+            "medium" => FrameLen::Medium,
+            "short" => FrameLen::Short,
+            _ => return Err(format!("invalid FECFRAME length {s}")),
+        })
+    }
+}
+
+impl std::fmt::Display for FrameLen {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                FrameLen::Normal => "normal",
+                FrameLen::Medium => "medium",
+                FrameLen::Short => "short",
+            }
+        )
+    }
+}
+
+/// DVB-S2X LDPC code rate.
+///
+/// Each variant is the rate of one of the LDPC codes introduced in DVB-S2X for
+/// a FECFRAME length. The DVB-S2X rates of the medium and short FECFRAMEs are
+/// the rates of the LDPC codes.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Sequence)]
+pub enum Rate {
+    /// rate 2/9 normal FECFRAME
+    R2_9,
+    /// rate 13/45 normal FECFRAME
+    R13_45,
+    /// rate 9/20 normal FECFRAME
+    R9_20,
+    /// rate 90/180 normal FECFRAME
+    R90_180,
+    /// rate 96/180 normal FECFRAME
+    R96_180,
+    /// rate 11/20 normal FECFRAME
+    R11_20,
+    /// rate 100/180 normal FECFRAME
+    R100_180,
+    /// rate 104/180 normal FECFRAME
+    R104_180,
+    /// rate 26/45 normal FECFRAME
+    R26_45,
+    /// rate 18/30 normal FECFRAME
+    R18_30,
+    /// rate 28/45 normal FECFRAME
+    R28_45,
+    /// rate 23/36 normal FECFRAME
+    R23_36,
+    /// rate 116/180 normal FECFRAME
+    R116_180,
+    /// rate 20/30 normal FECFRAME
+    R20_30,
+    /// rate 124/180 normal FECFRAME
+    R124_180,
+    /// rate 25/36 normal FECFRAME
+    R25_36,
+    /// rate 128/180 normal FECFRAME
+    R128_180,
+    /// rate 13/18 normal FECFRAME
+    R13_18,
+    /// rate 132/180 normal FECFRAME
+    R132_180,
+    /// rate 22/30 normal FECFRAME
+    R22_30,
+    /// rate 135/180 normal FECFRAME
+    R135_180,
+    /// rate 140/180 normal FECFRAME
+    R140_180,
+    /// rate 7/9 normal FECFRAME
+    R7_9,
+    /// rate 154/180 normal FECFRAME
+    R154_180,
+    /// rate 1/5 medium FECFRAME
+    R1_5medium,
+    /// rate 11/45 medium FECFRAME
+    R11_45medium,
+    /// rate 1/3 medium FECFRAME
+    R1_3medium,
+    /// rate 1/5 short FECFRAME
+    R1_5short,
+    /// rate 11/45 short FECFRAME
+    R11_45short,
+    /// rate 4/15 short FECFRAME
+    R4_15short,
+    /// rate 14/45 short FECFRAME
+    R14_45short,
+    /// rate 1/3 short FECFRAME
+    R1_3short,
+    /// rate 7/15 short FECFRAME
+    R7_15short,
+    /// rate 8/15 short FECFRAME
+    R8_15short,
+    /// rate 26/45 short FECFRAME
+    R26_45short,
+    /// rate 32/45 short FECFRAME
+    R32_45short,
+}
+
+impl Rate {
+    /// Returns the code rate with a given rate and FECFRAME length.
+    ///
+    /// The `rate` is given as a string such as `"2/9"`. Returns `None` if there
+    /// is no such code.
+    ///
+    /// # Examples
+    /// ```
+    /// # use ldpc_toolbox::codes::dvbs2x::{FrameLen, Rate};
+    /// assert_eq!(Rate::from_rate("1/3", FrameLen::Medium), Some(Rate::R1_3medium));
+    /// assert_eq!(Rate::from_rate("1/2", FrameLen::Normal), None);
+    /// ```
+    pub fn from_rate(rate: &str, frame: FrameLen) -> Option<Rate> {
+        enum_iterator::all::<Rate>().find(|r| r.frame() == frame && r.to_string() == rate)
+    }
+
+    /// Returns the numerator and denominator of the code rate.
+    pub const fn fraction(self) -> (usize, usize) {
+        match self {
+            Rate::R2_9 => (2, 9),
+            Rate::R13_45 => (13, 45),
+            Rate::R9_20 => (9, 20),
+            Rate::R90_180 => (90, 180),
+            Rate::R96_180 => (96, 180),
+            Rate::R11_20 => (11, 20),
+            Rate::R100_180 => (100, 180),
+            Rate::R104_180 => (104, 180),
+            Rate::R26_45 => (26, 45),
+            Rate::R18_30 => (18, 30),
+            Rate::R28_45 => (28, 45),
+            Rate::R23_36 => (23, 36),
+            Rate::R116_180 => (116, 180),
+            Rate::R20_30 => (20, 30),
+            Rate::R124_180 => (124, 180),
+            Rate::R25_36 => (25, 36),
+            Rate::R128_180 => (128, 180),
+            Rate::R13_18 => (13, 18),
+            Rate::R132_180 => (132, 180),
+            Rate::R22_30 => (22, 30),
+            Rate::R135_180 => (135, 180),
+            Rate::R140_180 => (140, 180),
+            Rate::R7_9 => (7, 9),
+            Rate::R154_180 => (154, 180),
+            Rate::R1_5medium | Rate::R1_5short => (1, 5),
+            Rate::R11_45medium | Rate::R11_45short => (11, 45),
+            Rate::R1_3medium | Rate::R1_3short => (1, 3),
+            Rate::R4_15short => (4, 15),
+            Rate::R14_45short => (14, 45),
+            Rate::R7_15short => (7, 15),
+            Rate::R8_15short => (8, 15),
+            Rate::R26_45short => (26, 45),
+            Rate::R32_45short => (32, 45),
+        }
+    }
+
+    /// Returns the FECFRAME length.
+    pub const fn frame(self) -> FrameLen {
+        match self {
+            Rate::R1_5medium | Rate::R11_45medium | Rate::R1_3medium => FrameLen::Medium,
+            Rate::R1_5short
+            | Rate::R11_45short
+            | Rate::R4_15short
+            | Rate::R14_45short
+            | Rate::R1_3short
+            | Rate::R7_15short
+            | Rate::R8_15short
+            | Rate::R26_45short
+            | Rate::R32_45short => FrameLen::Short,
+            _ => FrameLen::Normal,
+        }
+    }
+
+    /// Returns the codeword length `n`.
+    pub const fn n(self) -> usize {
+        self.frame().n()
+    }
+
+    /// Returns the number of information bits `k`.
+    pub const fn k(self) -> usize {
+        let (num, den) = self.fraction();
+        self.n() * num / den
+    }
+}
+
+impl std::fmt::Display for Rate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (num, den) = self.fraction();
+        write!(f, "{num}/{den}")
+    }
+}
+
+/// DVB-S2X LDPC code.
+///
+/// The code is defined by its rate and its table. See the [module
+/// documentation](self) for the format of the table.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Code {
+    rate: Rate,
+    table: Vec<Vec<usize>>,
+}
+
+impl Code {
+    /// Creates a code from its table.
+    ///
+    /// # Errors
+    /// An error is returned if the number of rows of the table does not
+    /// correspond to the `k` of the `rate`, or if the table does not describe
+    /// a code with the FECFRAME length of the `rate` (see
+    /// [`SparseMatrix::from_dvbs2_tables`]).
+    pub fn new(rate: Rate, table: Vec<Vec<usize>>) -> Result<Code> {
+        if 360 * table.len() != rate.k() {
+            return Err(Error::InvalidTable(format!(
+                "table has {} rows, but rate {rate} {} FECFRAME has k = {}",
+                table.len(),
+                rate.frame(),
+                rate.k()
+            )));
+        }
+        let code = Code { rate, table };
+        // Check that the table is valid
+        code.try_h()?;
+        Ok(code)
+    }
+
+    /// Creates a code from its table given as text.
+    ///
+    /// See the [module documentation](self) for the format of the table.
+    pub fn from_tables(rate: Rate, tables: &str) -> Result<Code> {
+        let table = tables
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| {
+                line.split(|c: char| c.is_whitespace() || c == ',')
+                    .filter(|x| !x.is_empty())
+                    .map(|x| x.parse().map_err(|_| Error::InvalidAddress(x.to_string())))
+                    .collect::<Result<Vec<usize>>>()
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Code::new(rate, table)
+    }
+
+    /// Creates a code from a file containing its table.
+    pub fn from_file(rate: Rate, path: &str) -> Result<Code> {
+        Code::from_tables(rate, &std::fs::read_to_string(path)?)
+    }
+
+    /// Returns the code rate.
+    pub fn rate(&self) -> Rate {
+        self.rate
+    }
+
+    /// Returns the FECFRAME length.
+    pub fn frame(&self) -> FrameLen {
+        self.rate.frame()
+    }
+
+    /// Returns the table of the code.
+    pub fn table(&self) -> &[Vec<usize>] {
+        &self.table
+    }
+
+    /// Returns the codeword length `n`.
+    pub fn n(&self) -> usize {
+        self.rate.n()
+    }
+
+    /// Returns the number of information bits `k`.
+    pub fn k(&self) -> usize {
+        self.rate.k()
+    }
+
+    /// Constructs the parity check matrix for the code.
+    pub fn h(&self) -> SparseMatrix {
+        self.try_h()
+            .expect("the table was checked when creating the code")
+    }
+
+    fn try_h(&self) -> Result<SparseMatrix> {
+        SparseMatrix::from_dvbs2_tables(&self.table, self.n()).map_err(Error::InvalidTable)
+    }
+}
+
+impl super::Code for Code {
+    fn h(&self) -> SparseMatrix {
+        Code::h(self)
+    }
+
+    fn n(&self) -> usize {
+        Code::n(self)
+    }
+
+    fn k(&self) -> usize {
+        Code::k(self)
+    }
+
+    fn name(&self) -> String {
+        format!("DVB-S2X r={} {} FECFRAME", self.rate, self.frame())
+    }
+
+    fn decoder_preset(&self) -> Option<super::DecoderPreset> {
+        // Same settings as for the DVB-S2 codes
+        Some(super::DecoderPreset {
+            implementation: DecoderImplementation::Phif64,
+            max_iterations: 50,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::codes::Code as _;
+
+    #[test]
+    fn from_tables() {
+        // Medium FECFRAME: k = 6480, m = 32400 - 6480, q = 72
+        let table = format!(
+            "# test table\n1 2 3000\n\n7, 25919, 72\n{}",
+            "5\n".repeat(16)
+        );
+        let code = Code::from_tables(Rate::R1_5medium, &table).unwrap();
+        assert_eq!(code.table()[..2], [vec![1, 2, 3000], vec![7, 25919, 72]]);
+        assert_eq!(code.n(), 32400);
+        assert_eq!(code.k(), 6480);
+        assert_eq!(code.name(), "DVB-S2X r=1/5 medium FECFRAME");
+        let h = code.h();
+        assert_eq!(h.num_rows(), 25920);
+        assert!(h.contains(3000 + 72, 1));
+        assert!(h.contains((72 * 359 + 25919) % 25920, 360 + 359));
+        assert_eq!(h.to_dvbs2_tables().unwrap()[0], [1, 2, 3000]);
+
+        assert!(matches!(
+            Code::from_tables(Rate::R1_5short, "1 2 x"),
+            Err(Error::InvalidAddress(_))
+        ));
+        // Wrong number of rows for k
+        assert!(matches!(
+            Code::from_tables(Rate::R1_5short, "1 2 3"),
+            Err(Error::InvalidTable(_))
+        ));
+        let table = "1 2 16000\n".repeat(9);
+        assert!(matches!(
+            Code::from_tables(Rate::R1_5short, &table),
+            Err(Error::InvalidTable(_))
+        ));
+        assert!(matches!(
+            Code::from_file(Rate::R1_5short, "/nonexistent/table.txt"),
+            Err(Error::Io(_))
+        ));
+    }
+
+    #[test]
+    fn rates() {
+        for rate in enum_iterator::all::<Rate>() {
+            // The information bits are divided in groups of 360
+            assert!(rate.k().is_multiple_of(360));
+            assert_eq!(rate.k() * rate.fraction().1, rate.n() * rate.fraction().0);
+            assert_eq!(Rate::from_rate(&rate.to_string(), rate.frame()), Some(rate));
+        }
+        assert_eq!(Rate::R2_9.k(), 14400);
+        assert_eq!(Rate::R11_45medium.k(), 7920);
+        assert_eq!(Rate::R32_45short.k(), 11520);
+        assert_eq!(Rate::from_rate("1/5", FrameLen::Normal), None);
+    }
+
+    #[test]
+    fn frame_len() {
+        for frame in [FrameLen::Normal, FrameLen::Medium, FrameLen::Short] {
+            assert_eq!(frame.to_string().parse::<FrameLen>(), Ok(frame));
+        }
+        assert!("long".parse::<FrameLen>().is_err());
+    }
+}
@@ -0,0 +1,219 @@
+//! Small example codes.
+//!
+//! This module contains a few small LDPC codes that are used in the
+//! documentation and in the tests of this crate. They are small enough that
+//! their parity check matrices can be printed and checked by hand, which makes
+//! them a good starting point to learn how to use the crate and to write
+//! integration tests against it. The codes can be obtained by name with
+//! [`ExampleCode::from_name`], or with the code specification `example:<name>`
+//! (see [`cache`](super::cache)), which is also accepted by the CLI.
+//!
+//! # Examples
+//! The following encodes a message with the QC example code, flips a bit of
+//! the codeword, and corrects it with a belief propagation decoder.
+//! ```
+//! # use ldpc_toolbox::{
+//! #     codes::examples::ExampleCode,
+//! #     decoder::{arithmetic::Phif64, flooding::Decoder},
+//! #     encoder::Encoder,
+//! #     gf2::GF2,
+//! # };
+//! # use ndarray::Array1;
+//! # use num_traits::{One, Zero};
+//! let code = ExampleCode::Qc42_21;
+//! let h = code.h();
+//! let encoder = Encoder::from_h(&h)?;
+//! let message = Array1::from_iter((0..code.k()).map(|j| {
+//!     if j % 3 == 0 {
+//!         GF2::one()
+//!     } else {
+//!         GF2::zero()
+//!     }
+//! }));
+//! let codeword = encoder.encode(&message);
+//! // BPSK symbols with a bit error in position 5
+//! let mut llrs = codeword
+//!     .iter()
+//!     .map(|x| if x.is_one() { -4.0 } else { 4.0 })
+//!     .collect::<Vec<_>>();
+//! llrs[5] = -llrs[5];
+//! let mut decoder = Decoder::new(h, Phif64::new());
+//! let output = decoder.decode(&llrs, 10).unwrap();
+//! assert!(output
+//!     .codeword
+//!     .iter()
+//!     .zip(codeword.iter())
+//!     .all(|(&a, b)| (a == 1) == b.is_one()));
+//! assert_eq!(output.corrected_bits, 1);
+//! # Ok::<(), ldpc_toolbox::encoder::Error>(())
+//! ```
+
+use crate::{
+    decoder::factory::DecoderImplementation,
+    sparse::{Col, QcMatrix, Row, SparseMatrix},
+};
+use enum_iterator::Sequence;
+
+/// Example code.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Sequence)]
+pub enum ExampleCode {
+    /// (6, 3) code of Example 2.5 in Sarah J. Johnson, Iterative Error
+    /// Correction.
+    ///
+    /// The parity check matrix has size 4 x 6, with columns of weight 2 and
+    /// rows of weight 3. It does not have full rank, since the sum of its rows
+    /// is zero, so it cannot be used with the systematic
+    /// [`Encoder`](crate::encoder::Encoder). The word `001011` is a codeword.
+    Johnson2_5,
+    /// (20, 8) regular code with Gallager's construction.
+    ///
+    /// The parity check matrix has size 15 x 20, with columns of weight 3 and
+    /// rows of weight 4. It is formed by three bands of 5 rows. The first band
+    /// contains the rows with ones in 4 consecutive columns, and the other two
+    /// bands are column permutations of the first one. The permutations are
+    /// chosen so that the girth is 6. The matrix has rank 12.
+    Gallager20_8,
+    /// (42, 21) quasi-cyclic code.
+    ///
+    /// The parity check matrix has size 21 x 42 and it is formed by a 3 x 6
+    /// base matrix of 7 x 7 circulants. The parity part has the dual-diagonal
+    /// structure of the IEEE 802.11n codes, so the code can be encoded with
+    /// the systematic [`Encoder`](crate::encoder::Encoder). The girth is 6.
+    Qc42_21,
+}
+
+impl ExampleCode {
+    /// Returns the example code with a given name.
+    ///
+    /// The names are the ones returned by [`ExampleCode::short_name`]. This
+    /// returns `None` if there is no example code with that name.
+    pub fn from_name(name: &str) -> Option<ExampleCode> {
+        enum_iterator::all::<ExampleCode>().find(|code| code.short_name() == name)
+    }
+
+    /// Returns the name of the code.
+    ///
+    /// This is the name used in the code specifications.
+    pub fn short_name(self) -> &'static str {
+        match self {
+            ExampleCode::Johnson2_5 => "johnson2_5",
+            ExampleCode::Gallager20_8 => "gallager20_8",
+            ExampleCode::Qc42_21 => "qc42_21",
+        }
+    }
+
+    /// Returns the codeword length `n`.
+    pub fn n(self) -> usize {
+        match self {
+            ExampleCode::Johnson2_5 => 6,
+            ExampleCode::Gallager20_8 => 20,
+            ExampleCode::Qc42_21 => 42,
+        }
+    }
+
+    /// Returns the number of information bits `k`.
+    ///
+    /// This is the codeword length minus the rank of the parity check matrix,
+    /// which can be smaller than the number of rows.
+    pub fn k(self) -> usize {
+        match self {
+            ExampleCode::Johnson2_5 => 3,
+            ExampleCode::Gallager20_8 => 8,
+            ExampleCode::Qc42_21 => 21,
+        }
+    }
+
+    /// Constructs the parity check matrix for the code.
+    pub fn h(self) -> SparseMatrix {
+        match self {
+            ExampleCode::Johnson2_5 => {
+                let mut h = SparseMatrix::new(4, 6);
+                h.insert_row(Row(0), [0, 1, 3].iter());
+                h.insert_row(Row(1), [1, 2, 4].iter());
+                h.insert_row(Row(2), [0, 4, 5].iter());
+                h.insert_row(Row(3), [2, 3, 5].iter());
+                h
+            }
+            ExampleCode::Gallager20_8 => {
+                let mut h = SparseMatrix::new(15, 20);
+                for j in 0..5 {
+                    for k in 0..4 {
+                        h.insert(Row(j), Col(4 * j + k));
+                        h.insert(Row(5 + j), Col(j + 5 * k));
+                        h.insert(Row(10 + j), Col((j + 4 * k) % 5 + 5 * k));
+                    }
+                }
+                h
+            }
+            ExampleCode::Qc42_21 => QcMatrix::from_exponents(QC42_21_EXPONENTS).unwrap().lift(7),
+        }
+    }
+}
+
+impl std::fmt::Display for ExampleCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            ExampleCode::Johnson2_5 => "Johnson example 2.5",
+            ExampleCode::Gallager20_8 => "Gallager",
+            ExampleCode::Qc42_21 => "quasi-cyclic",
+        };
+        write!(f, "{} ({}, {})", name, self.n(), self.k())
+    }
+}
+
+impl super::Code for ExampleCode {
+    fn h(&self) -> SparseMatrix {
+        ExampleCode::h(*self)
+    }
+
+    fn n(&self) -> usize {
+        ExampleCode::n(*self)
+    }
+
+    fn k(&self) -> usize {
+        ExampleCode::k(*self)
+    }
+
+    fn name(&self) -> String {
+        self.to_string()
+    }
+
+    fn decoder_preset(&self) -> Option<super::DecoderPreset> {
+        Some(super::DecoderPreset {
+            implementation: DecoderImplementation::HLPhif64,
+            max_iterations: 50,
+        })
+    }
+}
+
+// Base matrix of the (42, 21) QC code, with circulants of size 7
+const QC42_21_EXPONENTS: &str = "\
+0 0 0 1 0 -1
+3 4 5 0 0 0
+5 3 2 1 -1 0
+";
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{encoder::Encoder, linalg::SparseSolver};
+
+    #[test]
+    fn codes() {
+        for code in enum_iterator::all::<ExampleCode>() {
+            let h = code.h();
+            assert_eq!(h.num_cols(), code.n(), "{code}");
+            assert_eq!(SparseSolver::new(&h).rank(), code.n() - code.k(), "{code}");
+            assert_eq!(ExampleCode::from_name(code.short_name()), Some(code));
+        }
+        assert_eq!(ExampleCode::from_name("johnson"), None);
+        assert_eq!(ExampleCode::Gallager20_8.h().girth(), Some(6));
+        let h = ExampleCode::Qc42_21.h();
+        assert_eq!(h.girth(), Some(6));
+        assert!(Encoder::from_h(&h).is_ok());
+        let h = ExampleCode::Johnson2_5.h();
+        assert!(Encoder::from_h(&h).is_err());
+        let codeword = [0, 0, 1, 0, 1, 1];
+        assert!((0..h.num_rows()).all(|r| h.iter_row(r).map(|&c| codeword[c]).sum::<u8>() % 2 == 0));
+    }
+}
@@ -0,0 +1,221 @@
+//! Short classical reference codes.
+//!
+//! This module contains some short classical block codes, such as the
+//! Hamming(7,4) code and the extended Golay code, represented by their parity
+//! check matrices. These codes are not LDPC codes, but since they are very
+//! short, their minimum distance and their maximum likelihood performance are
+//! well known, and they can be decoded by brute force. This makes them useful
+//! as references to check the decoders, modulations and BER simulation
+//! pipeline of this crate.
+//!
+//! The cyclic codes are constructed from their generator polynomials. Their
+//! parity check matrices are formed by shifts of the reciprocal of the parity
+//! check polynomial, so the square submatrix formed by the last columns is
+//! invertible and they can be encoded with the systematic
+//! [`Encoder`](crate::encoder::Encoder).
+
+use crate::{
+    decoder::factory::DecoderImplementation,
+    sparse::{Col, Row, SparseMatrix},
+};
+use enum_iterator::Sequence;
+
+/// Reference code.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Sequence)]
+pub enum ReferenceCode {
+    /// Hamming (7, 4) code.
+    Hamming7_4,
+    /// Extended Hamming (8, 4) code.
+    ExtendedHamming8_4,
+    /// Double error correcting BCH (15, 7) code.
+    Bch15_7,
+    /// Golay (23, 12) code.
+    Golay23_12,
+    /// Extended Golay (24, 12) code.
+    ExtendedGolay24_12,
+}
+
+impl ReferenceCode {
+    /// Returns the codeword length `n`.
+    pub fn n(self) -> usize {
+        match self {
+            ReferenceCode::Hamming7_4 => 7,
+            ReferenceCode::ExtendedHamming8_4 => 8,
+            ReferenceCode::Bch15_7 => 15,
+            ReferenceCode::Golay23_12 => 23,
+            ReferenceCode::ExtendedGolay24_12 => 24,
+        }
+    }
+
+    /// Returns the number of information bits `k`.
+    pub fn k(self) -> usize {
+        match self {
+            ReferenceCode::Hamming7_4 | ReferenceCode::ExtendedHamming8_4 => 4,
+            ReferenceCode::Bch15_7 => 7,
+            ReferenceCode::Golay23_12 | ReferenceCode::ExtendedGolay24_12 => 12,
+        }
+    }
+
+    /// Returns the minimum distance of the code.
+    pub fn minimum_distance(self) -> usize {
+        match self {
+            ReferenceCode::Hamming7_4 => 3,
+            ReferenceCode::ExtendedHamming8_4 => 4,
+            ReferenceCode::Bch15_7 => 5,
+            ReferenceCode::Golay23_12 => 7,
+            ReferenceCode::ExtendedGolay24_12 => 8,
+        }
+    }
+
+    /// Returns the number of bit errors that the code can always correct.
+    ///
+    /// For the perfect codes (Hamming (7, 4) and Golay (23, 12)), maximum
+    /// likelihood decoding in a binary symmetric channel corrects exactly the
+    /// error patterns with at most this number of errors.
+    pub fn error_correction_capability(self) -> usize {
+        (self.minimum_distance() - 1) / 2
+    }
+
+    /// Constructs the parity check matrix for the code.
+    ///
+    /// The matrix has size `(n - k) x n`.
+    pub fn h(self) -> SparseMatrix {
+        match self {
+            ReferenceCode::Hamming7_4 => cyclic_h(7, HAMMING_7_4_GENERATOR),
+            ReferenceCode::ExtendedHamming8_4 => extend(&cyclic_h(7, HAMMING_7_4_GENERATOR)),
+            ReferenceCode::Bch15_7 => cyclic_h(15, BCH_15_7_GENERATOR),
+            ReferenceCode::Golay23_12 => cyclic_h(23, GOLAY_23_12_GENERATOR),
+            ReferenceCode::ExtendedGolay24_12 => extend(&cyclic_h(23, GOLAY_23_12_GENERATOR)),
+        }
+    }
+}
+
+impl std::fmt::Display for ReferenceCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            ReferenceCode::Hamming7_4 => "Hamming",
+            ReferenceCode::ExtendedHamming8_4 => "extended Hamming",
+            ReferenceCode::Bch15_7 => "BCH",
+            ReferenceCode::Golay23_12 => "Golay",
+            ReferenceCode::ExtendedGolay24_12 => "extended Golay",
+        };
+        write!(f, "{} ({}, {})", name, self.n(), self.k())
+    }
+}
+
+impl super::Code for ReferenceCode {
+    fn h(&self) -> SparseMatrix {
+        ReferenceCode::h(*self)
+    }
+
+    fn n(&self) -> usize {
+        ReferenceCode::n(*self)
+    }
+
+    fn k(&self) -> usize {
+        ReferenceCode::k(*self)
+    }
+
+    fn name(&self) -> String {
+        self.to_string()
+    }
+
+    fn decoder_preset(&self) -> Option<super::DecoderPreset> {
+        // Exact belief propagation with a layered schedule, which is the
+        // recommended decoder for small codes
+        Some(super::DecoderPreset {
+            implementation: DecoderImplementation::HLPhif64,
+            max_iterations: 50,
+        })
+    }
+}
+
+// Generator polynomials. Bit j contains the coefficient of x^j.
+
+// x^3 + x + 1
+const HAMMING_7_4_GENERATOR: u32 = 0xb;
+// x^8 + x^7 + x^6 + x^4 + 1
+const BCH_15_7_GENERATOR: u32 = 0x1d1;
+// x^11 + x^10 + x^6 + x^5 + x^4 + x^2 + 1
+const GOLAY_23_12_GENERATOR: u32 = 0xc75;
+
+// Constructs the parity check matrix of a cyclic code of length n with the
+// given generator polynomial.
+fn cyclic_h(n: usize, generator: u32) -> SparseMatrix {
+    let r = 31 - generator.leading_zeros() as usize;
+    let k = n - r;
+    // Compute the parity check polynomial h(x) = (x^n + 1) / g(x) by long
+    // division. The remainder is zero because g(x) divides x^n + 1.
+    let mut dividend = (1u64 << n) | 1;
+    let mut parity_check = 0u64;
+    for j in (0..=k).rev() {
+        if (dividend >> (j + r)) & 1 != 0 {
+            parity_check |= 1 << j;
+            dividend ^= u64::from(generator) << j;
+        }
+    }
+    assert_eq!(dividend, 0);
+    // Row i of H contains the reciprocal of h(x) shifted by i positions.
+    let mut h = SparseMatrix::new(r, n);
+    for i in 0..r {
+        for j in 0..=k {
+            if (parity_check >> (k - j)) & 1 != 0 {
+                h.insert(Row(i), Col(i + j));
+            }
+        }
+    }
+    h
+}
+
+// Extends a code with an overall parity bit, which is appended at the end of
+// the codeword.
+fn extend(h: &SparseMatrix) -> SparseMatrix {
+    let r = h.num_rows();
+    let n = h.num_cols();
+    let mut extended = SparseMatrix::new(r + 1, n + 1);
+    for (j, k) in h.iter_all() {
+        extended.insert(Row(j), Col(k));
+    }
+    extended.insert_row(Row(r), 0..=n);
+    extended
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{encoder::Encoder, gf2::GF2};
+    use ndarray::Array1;
+    use num_traits::{One, Zero};
+
+    #[test]
+    fn minimum_distance() {
+        for code in enum_iterator::all::<ReferenceCode>() {
+            let h = code.h();
+            assert_eq!(h.num_rows(), code.n() - code.k());
+            assert_eq!(h.num_cols(), code.n());
+            let encoder = Encoder::from_h(&h).unwrap();
+            let min_weight = (1..1u32 << code.k())
+                .map(|message| {
+                    let message = Array1::from_iter((0..code.k()).map(|j| {
+                        if (message >> j) & 1 != 0 {
+                            GF2::one()
+                        } else {
+                            GF2::zero()
+                        }
+                    }));
+                    let codeword = encoder.encode(&message);
+                    for check in 0..h.num_rows() {
+                        assert!(h
+                            .iter_row(check)
+                            .map(|&j| codeword[j])
+                            .sum::<GF2>()
+                            .is_zero());
+                    }
+                    codeword.iter().filter(|x| x.is_one()).count()
+                })
+                .min()
+                .unwrap();
+            assert_eq!(min_weight, code.minimum_distance(), "{code}");
+        }
+    }
+}
@@ -0,0 +1,121 @@
+//! Verification of the tables of the standards.
+//!
+//! The codes defined in standards are constructed from large tables that have
+//! been transcribed from the standard documents. This module contains an
+//! internal consistency checker for these tables. It does not compare the
+//! tables against the standards, but it verifies that they satisfy the
+//! structural properties that the standards imply, such as the table shapes,
+//! the range of their entries, and the row and column weights of the
+//! resulting parity check matrices. A transcription error in a table is
+//! likely to break some of these properties.
+//!
+//! The checks are run with [`verify_tables`], which returns a report formed by
+//! a [`TableCheck`] for each check.
+//!
+//! # Examples
+//! ```
+//! # use ldpc_toolbox::codes::verify::verify_tables;
+//! let checks = verify_tables();
+//! assert!(checks.iter().all(|check| check.passed()));
+//! ```
+
+use super::{ccsds, ccsds_tc, dvbs2, wimax};
+use crate::sparse::{Col, Row, SparseMatrix};
+
+/// Result of a table check.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableCheck {
+    /// Table or code that has been checked.
+    pub table: String,
+    /// Description of the check.
+    pub description: String,
+    /// Error found by the check, or `None` if the check passed.
+    pub error: Option<String>,
+}
+
+impl TableCheck {
+    /// Runs a check and returns its result.
+    ///
+    /// The function `check` returns an error describing the problem found if
+    /// the check fails.
+    pub fn run<F>(table: impl Into<String>, description: impl Into<String>, check: F) -> TableCheck
+    where
+        F: FnOnce() -> Result<(), String>,
+    {
+        TableCheck {
+            table: table.into(),
+            description: description.into(),
+            error: check().err(),
+        }
+    }
+
+    /// Returns `true` if the check passed.
+    pub fn passed(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+impl std::fmt::Display for TableCheck {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}: ", self.table, self.description)?;
+        match &self.error {
+            None => write!(f, "OK"),
+            Some(error) => write!(f, "FAILED ({error})"),
+        }
+    }
+}
+
+/// Verifies the tables of all the standards.
+///
+/// Returns the result of each of the checks. See the [module
+/// documentation](self) for more details.
+pub fn verify_tables() -> Vec<TableCheck> {
+    let mut checks = Vec::new();
+    ccsds::verify_tables(&mut checks);
+    ccsds_tc::verify_tables(&mut checks);
+    dvbs2::verify_tables(&mut checks);
+    wimax::verify_tables(&mut checks);
+    checks
+}
+
+/// Checks that all the rows of a matrix have weights in a set.
+pub(crate) fn check_row_weights(h: &SparseMatrix, weights: &[usize]) -> Result<(), String> {
+    match (0..h.num_rows()).find(|&r| !weights.contains(&h.row_weight(Row(r)))) {
+        Some(r) => Err(format!("row {r} has weight {}", h.row_weight(Row(r)))),
+        None => Ok(()),
+    }
+}
+
+/// Checks that all the columns of a matrix have weights in a set.
+pub(crate) fn check_col_weights(h: &SparseMatrix, weights: &[usize]) -> Result<(), String> {
+    match (0..h.num_cols()).find(|&c| !weights.contains(&h.col_weight(Col(c)))) {
+        Some(c) => Err(format!("column {c} has weight {}", h.col_weight(Col(c)))),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn tables() {
+        for check in verify_tables() {
+            assert!(check.passed(), "{check}");
+        }
+    }
+
+    #[test]
+    fn failed_check() {
+        let mut h = SparseMatrix::new(2, 3);
+        h.insert_row(0, [0, 1].iter());
+        h.insert_row(1, [1].iter());
+        let check = TableCheck::run("test", "row weights", || check_row_weights(&h, &[2]));
+        assert!(!check.passed());
+        assert_eq!(
+            check.to_string(),
+            "test: row weights: FAILED (row 1 has weight 1)"
+        );
+        assert!(check_col_weights(&h, &[0, 1, 2]).is_ok());
+    }
+}
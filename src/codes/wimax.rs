@@ -0,0 +1,367 @@
+//! WiMAX (IEEE 802.16e) LDPC codes.
+//!
+//! This module contains the LDPC codes of the OFDMA PHY of IEEE 802.16e. There
+//! are six code rates, 1/2, 2/3A, 2/3B, 3/4A, 3/4B and 5/6, and 19 codeword
+//! lengths `n = 576, 672, ..., 2304` for each rate.
+//!
+//! The parity check matrix of each code is obtained by expanding a base matrix
+//! with 24 columns and `24 * (1 - R)` rows, where `R` is the code rate. Each
+//! entry of the base matrix is replaced by a zero block or by a circulant
+//! permutation matrix of size `z x z`, with `z = n / 24`. The standard gives
+//! the shifts of the circulants for `z = 96`, and the shifts for a smaller
+//! expansion factor are obtained by scaling them by `z / 96` (rounding down),
+//! except for rate 2/3A, for which they are reduced modulo `z`. The last
+//! columns of the base matrix correspond to the parity bits and have a
+//! dual-diagonal structure that allows linear-time encoding.
+//!
+//! The Tanner graphs of the rate 3/4 codes contain cycles of length 4, as do
+//! those of the other rates for some small expansion factors, because the
+//! scaling of the shifts does not preserve the girth of the `z = 96` codes.
+//!
+//! ## References
+//! \[1\] IEEE Std 802.16e-2005, Section 8.4.9.2.5 (Low density parity check
+//! code).
+
+use super::{
+    verify::{check_col_weights, TableCheck},
+    DecoderPreset,
+};
+use crate::{
+    decoder::factory::DecoderImplementation,
+    sparse::{QcMatrix, SparseMatrix},
+};
+use enum_iterator::Sequence;
+
+/// WiMAX LDPC code rate.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Sequence)]
+pub enum WimaxRate {
+    /// Rate 1/2.
+    R1_2,
+    /// Rate 2/3, code A.
+    R2_3A,
+    /// Rate 2/3, code B.
+    R2_3B,
+    /// Rate 3/4, code A.
+    R3_4A,
+    /// Rate 3/4, code B.
+    R3_4B,
+    /// Rate 5/6.
+    R5_6,
+}
+
+impl WimaxRate {
+    /// Returns the rate given by a string such as `"1/2"` or `"2/3A"`.
+    ///
+    /// Returns `None` if there is no WiMAX code with this rate.
+    pub fn from_rate(rate: &str) -> Option<WimaxRate> {
+        Some(match rate {
+            "1/2" => WimaxRate::R1_2,
+            "2/3A" => WimaxRate::R2_3A,
+            "2/3B" => WimaxRate::R2_3B,
+            "3/4A" => WimaxRate::R3_4A,
+            "3/4B" => WimaxRate::R3_4B,
+            "5/6" => WimaxRate::R5_6,
+            _ => return None,
+        })
+    }
+
+    /// Returns the number of rows of the base matrix.
+    pub fn row_blocks(&self) -> usize {
+        self.table().lines().count()
+    }
+
+    /// Returns the base matrix of the standard, with the shifts for `z = 96`.
+    pub fn base_matrix(&self) -> QcMatrix {
+        // The tables are checked by verify_tables, so parsing cannot fail
+        QcMatrix::from_exponents(self.table()).unwrap()
+    }
+
+    // Section 8.4.9.2.5.1 in [1]
+    fn table(&self) -> &'static str {
+        match self {
+            WimaxRate::R1_2 => BASE_1_2,
+            WimaxRate::R2_3A => BASE_2_3A,
+            WimaxRate::R2_3B => BASE_2_3B,
+            WimaxRate::R3_4A => BASE_3_4A,
+            WimaxRate::R3_4B => BASE_3_4B,
+            WimaxRate::R5_6 => BASE_5_6,
+        }
+    }
+}
+
+impl std::fmt::Display for WimaxRate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let rate = match self {
+            WimaxRate::R1_2 => "1/2",
+            WimaxRate::R2_3A => "2/3A",
+            WimaxRate::R2_3B => "2/3B",
+            WimaxRate::R3_4A => "3/4A",
+            WimaxRate::R3_4B => "3/4B",
+            WimaxRate::R5_6 => "5/6",
+        };
+        write!(f, "{}", rate)
+    }
+}
+
+/// WiMAX LDPC code definition.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct WimaxCode {
+    rate: WimaxRate,
+    n: usize,
+}
+
+impl WimaxCode {
+    /// Creates a new WiMAX code with a given rate and codeword length.
+    ///
+    /// Returns `None` if `n` is not one of the codeword lengths of the
+    /// standard.
+    pub fn new(rate: WimaxRate, n: usize) -> Option<WimaxCode> {
+        if Self::lengths().any(|len| len == n) {
+            Some(WimaxCode { rate, n })
+        } else {
+            None
+        }
+    }
+
+    /// Returns an iterator over the codeword lengths of the standard.
+    pub fn lengths() -> impl Iterator<Item = usize> {
+        (Self::MIN_Z..=Self::MAX_Z)
+            .step_by(4)
+            .map(|z| z * Self::COL_BLOCKS)
+    }
+
+    /// Returns an iterator over all the WiMAX codes.
+    pub fn all() -> impl Iterator<Item = WimaxCode> {
+        enum_iterator::all::<WimaxRate>()
+            .flat_map(|rate| Self::lengths().map(move |n| WimaxCode { rate, n }))
+    }
+
+    /// Returns the rate of the code.
+    pub fn rate(&self) -> WimaxRate {
+        self.rate
+    }
+
+    /// Returns the expansion factor `z` of the base matrix.
+    pub fn expansion_factor(&self) -> usize {
+        self.n / Self::COL_BLOCKS
+    }
+
+    /// Returns the base matrix with the shifts for the expansion factor of the
+    /// code.
+    pub fn base_matrix(&self) -> QcMatrix {
+        let z = self.expansion_factor();
+        let mut qc = self.rate.base_matrix();
+        for row in 0..qc.num_row_blocks() {
+            for col in 0..qc.num_col_blocks() {
+                let shifts = qc
+                    .shifts(row, col)
+                    .iter()
+                    .map(|&shift| match self.rate {
+                        WimaxRate::R2_3A => shift % z,
+                        _ => shift * z / Self::MAX_Z,
+                    })
+                    .collect::<Vec<_>>();
+                qc.set_shifts(row, col, shifts.into_iter());
+            }
+        }
+        qc
+    }
+
+    /// Constructs the parity check matrix for the code.
+    pub fn h(&self) -> SparseMatrix {
+        self.base_matrix().lift(self.expansion_factor())
+    }
+
+    const COL_BLOCKS: usize = 24;
+    const MIN_Z: usize = 24;
+    const MAX_Z: usize = 96;
+}
+
+impl super::Code for WimaxCode {
+    fn h(&self) -> SparseMatrix {
+        WimaxCode::h(self)
+    }
+
+    fn n(&self) -> usize {
+        self.n
+    }
+
+    fn k(&self) -> usize {
+        (Self::COL_BLOCKS - self.rate.row_blocks()) * self.expansion_factor()
+    }
+
+    fn name(&self) -> String {
+        format!("WiMAX r={} n={}", self.rate, self.n)
+    }
+
+    fn decoder_preset(&self) -> Option<DecoderPreset> {
+        Some(DecoderPreset {
+            implementation: DecoderImplementation::Phif64,
+            max_iterations: 50,
+        })
+    }
+}
+
+// Adds the checks of the WiMAX tables to a report.
+pub(crate) fn verify_tables(checks: &mut Vec<TableCheck>) {
+    for rate in enum_iterator::all::<WimaxRate>() {
+        let name = format!("WiMAX r={rate} base matrix");
+        let base = match QcMatrix::from_exponents(rate.table()) {
+            Ok(base) => base,
+            Err(err) => {
+                checks.push(TableCheck::run(name, "table format", || Err(err)));
+                continue;
+            }
+        };
+        let m = base.num_row_blocks();
+        checks.push(TableCheck::run(name.as_str(), "table shape", || {
+            if base.num_col_blocks() != WimaxCode::COL_BLOCKS {
+                return Err(format!("table has {} columns", base.num_col_blocks()));
+            }
+            let blocks = (0..m).flat_map(|r| (0..WimaxCode::COL_BLOCKS).map(move |c| (r, c)));
+            for (r, c) in blocks {
+                let shifts = base.shifts(r, c);
+                if shifts.len() > 1 || shifts.iter().any(|&s| s >= WimaxCode::MAX_Z) {
+                    return Err(format!("invalid shifts {shifts:?} in block ({r}, {c})"));
+                }
+            }
+            Ok(())
+        }));
+        checks.push(TableCheck::run(
+            name.as_str(),
+            "dual-diagonal parity",
+            || {
+                // The first parity column has three nonzero entries, in the first,
+                // the last and some middle row. The remaining parity columns form
+                // a dual-diagonal of unshifted identities.
+                let info = WimaxCode::COL_BLOCKS - m;
+                let first = (0..m)
+                    .filter(|&r| !base.shifts(r, info).is_empty())
+                    .collect::<Vec<_>>();
+                if first.len() != 3 || first[0] != 0 || first[2] != m - 1 {
+                    return Err(format!("first parity column has nonzero rows {first:?}"));
+                }
+                for r in 0..m {
+                    for c in info + 1..WimaxCode::COL_BLOCKS {
+                        let diagonal = c == info + r || c == info + r + 1;
+                        let expected: &[usize] = if diagonal { &[0] } else { &[] };
+                        if base.shifts(r, c) != expected {
+                            return Err(format!("invalid shifts in block ({r}, {c})"));
+                        }
+                    }
+                }
+                Ok(())
+            },
+        ));
+        let h = base.lift(WimaxCode::MAX_Z);
+        checks.push(TableCheck::run(name.as_str(), "column weights", || {
+            check_col_weights(&h, &[2, 3, 4, 6])
+        }));
+    }
+}
+
+// The shifts are given for z = 96, and the zero blocks are written as -1.
+
+static BASE_1_2: &str = "\
+-1 94 73 -1 -1 -1 -1 -1 55 83 -1 -1  7  0 -1 -1 -1 -1 -1 -1 -1 -1 -1 -1
+-1 27 -1 -1 -1 22 79  9 -1 -1 -1 12 -1  0  0 -1 -1 -1 -1 -1 -1 -1 -1 -1
+-1 -1 -1 24 22 81 -1 33 -1 -1 -1  0 -1 -1  0  0 -1 -1 -1 -1 -1 -1 -1 -1
+61 -1 47 -1 -1 -1 -1 -1 65 25 -1 -1 -1 -1 -1  0  0 -1 -1 -1 -1 -1 -1 -1
+-1 -1 39 -1 -1 -1 84 -1 -1 41 72 -1 -1 -1 -1 -1  0  0 -1 -1 -1 -1 -1 -1
+-1 -1 -1 -1 46 40 -1 82 -1 -1 -1 79  0 -1 -1 -1 -1  0  0 -1 -1 -1 -1 -1
+-1 -1 95 53 -1 -1 -1 -1 -1 14 18 -1 -1 -1 -1 -1 -1 -1  0  0 -1 -1 -1 -1
+-1 11 73 -1 -1 -1  2 -1 -1 47 -1 -1 -1 -1 -1 -1 -1 -1 -1  0  0 -1 -1 -1
+12 -1 -1 -1 83 24 -1 43 -1 -1 -1 51 -1 -1 -1 -1 -1 -1 -1 -1  0  0 -1 -1
+-1 -1 -1 -1 -1 94 -1 59 -1 -1 70 72 -1 -1 -1 -1 -1 -1 -1 -1 -1  0  0 -1
+-1 -1  7 65 -1 -1 -1 -1 39 49 -1 -1 -1 -1 -1 -1 -1 -1 -1 -1 -1 -1  0  0
+43 -1 -1 -1 -1 66 -1 41 -1 -1 -1 26  7 -1 -1 -1 -1 -1 -1 -1 -1 -1 -1  0
+";
+
+static BASE_2_3A: &str = "\
+ 3  0 -1 -1  2  0 -1  3  7 -1  1  1 -1 -1 -1 -1  1  0 -1 -1 -1 -1 -1 -1
+-1 -1  1 -1 36 -1 -1 34 10 -1 -1 18  2 -1  3  0 -1  0  0 -1 -1 -1 -1 -1
+-1 -1 12  2 -1 15 -1 40 -1  3 -1 15 -1  2 13 -1 -1 -1  0  0 -1 -1 -1 -1
+-1 -1 19 24 -1  3  0 -1  6 -1 17 -1 -1 -1  8 39 -1 -1 -1  0  0 -1 -1 -1
+20 -1  6 -1 -1 10 29 -1 -1 28 -1 14 -1 38 -1 -1  0 -1 -1 -1  0  0 -1 -1
+-1 -1 10 -1 28 20 -1 -1  8 -1 36 -1  9 -1 21 45 -1 -1 -1 -1 -1  0  0 -1
+35 25 -1 37 -1 21 -1 -1  5 -1 -1  0 -1  4 20 -1 -1 -1 -1 -1 -1 -1  0  0
+-1  6  6 -1 -1 -1  4 -1 14 30 -1  3 36 -1 14 -1  1 -1 -1 -1 -1 -1 -1  0
+";
+
+static BASE_2_3B: &str = "\
+ 2 -1 19 -1 47 -1 48 -1 36 -1 82 -1 47 -1 15 -1 95  0 -1 -1 -1 -1 -1 -1
+-1 69 -1 88 -1 33 -1  3 -1 16 -1 37 -1 40 -1 48 -1  0  0 -1 -1 -1 -1 -1
+10 -1 86 -1 62 -1 28 -1 85 -1 16 -1 34 -1 73 -1 -1 -1  0  0 -1 -1 -1 -1
+-1 28 -1 32 -1 81 -1 27 -1 88 -1  5 -1 56 -1 37 -1 -1 -1  0  0 -1 -1 -1
+23 -1 29 -1 15 -1 30 -1 66 -1 24 -1 50 -1 62 -1 -1 -1 -1 -1  0  0 -1 -1
+-1 30 -1 65 -1 54 -1 14 -1  0 -1 30 -1 74 -1  0 -1 -1 -1 -1 -1  0  0 -1
+32 -1  0 -1 15 -1 56 -1 85 -1  5 -1  6 -1 52 -1  0 -1 -1 -1 -1 -1  0  0
+-1  0 -1 47 -1 13 -1 61 -1 84 -1 55 -1 78 -1 41 95 -1 -1 -1 -1 -1 -1  0
+";
+
+static BASE_3_4A: &str = "\
+ 6 38  3 93 -1 -1 -1 30 70 -1 86 -1 37 38  4 11 -1 46 48  0 -1 -1 -1 -1
+62 94 19 84 -1 92 78 -1 15 -1 -1 92 -1 45 24 32 30 -1 -1  0  0 -1 -1 -1
+71 -1 55 -1 12 66 45 79 -1 78 -1 -1 10 -1 22 55 70 82 -1 -1  0  0 -1 -1
+38 61 -1 66  9 73 47 64 -1 39 61 43 -1 -1 -1 -1 95 32  0 -1 -1  0  0 -1
+-1 -1 -1 -1 32 52 55 80 95 22  6 51 24 90 44 20 -1 -1 -1 -1 -1 -1  0  0
+-1 63 31 88 20 -1 -1 -1  6 40 56 16 71 53 -1 -1 27 26 48 -1 -1 -1 -1  0
+";
+
+static BASE_3_4B: &str = "\
+-1 81 -1 28 -1 -1 14 25 17 -1 -1 85 29 52 78 95 22 92  0  0 -1 -1 -1 -1
+42 -1 14 68 32 -1 -1 -1 -1 70 43 11 36 40 33 57 38 24 -1  0  0 -1 -1 -1
+-1 -1 20 -1 -1 63 39 -1 70 67 -1 38  4 72 47 29 60  5 80 -1  0  0 -1 -1
+64  2 -1 -1 63 -1 -1  3 51 -1 81 15 94  9 85 36 14 19 -1 -1 -1  0  0 -1
+-1 53 60 80 -1 26 75 -1 -1 -1 -1 86 77  1  3 72 60 25 -1 -1 -1 -1  0  0
+77 -1 -1 -1 15 28 35 -1 72 30 68 85 84 26 64 11 89  0  0 -1 -1 -1 -1  0
+";
+
+static BASE_5_6: &str = "\
+ 1 25 55 -1 47  4 -1 91 84  8 86 52 82 33  5  0 36 20  4 77 80  0 -1 -1
+-1  6 -1 36 40 47 12 79 47 -1 41 21 12 71 14 72  0 44 49  0  0  0  0 -1
+51 81 83  4 67 -1 21 -1 31 24 91 61 81  9 86 78 60 88 67 15 -1 -1  0  0
+50 -1 50 15 -1 36 13 10 11 20 53 90 29 92 57 30 84 92 11 66 80 -1 -1  0
+";
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{codes::Code, linalg::SparseSolver};
+
+    #[test]
+    fn code_trait() {
+        assert_eq!(WimaxCode::lengths().count(), 19);
+        for code in WimaxCode::all() {
+            let h = code.h();
+            assert_eq!(h.num_cols(), code.n());
+            // H has full rank
+            assert_eq!(
+                SparseSolver::new(&h).rank(),
+                code.n() - code.k(),
+                "{}",
+                code.name()
+            );
+        }
+        for rate in [WimaxRate::R1_2, WimaxRate::R2_3B, WimaxRate::R5_6] {
+            let h = WimaxCode::new(rate, 2304).unwrap().h();
+            assert_eq!(h.girth(), Some(6));
+        }
+        let code = WimaxCode::new(WimaxRate::from_rate("3/4B").unwrap(), 1152).unwrap();
+        assert_eq!(code.name(), "WiMAX r=3/4B n=1152");
+        assert_eq!(code.expansion_factor(), 48);
+        assert_eq!(code.k(), 864);
+        assert_eq!(WimaxCode::new(WimaxRate::R1_2, 600), None);
+        assert_eq!(WimaxRate::from_rate("2/3"), None);
+    }
+
+    #[test]
+    fn shift_scaling() {
+        let code = WimaxCode::new(WimaxRate::R1_2, 576).unwrap();
+        // floor(94 * 24 / 96) = 23
+        assert_eq!(code.base_matrix().shifts(0, 1), &[23]);
+        let code = WimaxCode::new(WimaxRate::R2_3A, 576).unwrap();
+        // 36 mod 24 = 12
+        assert_eq!(code.base_matrix().shifts(1, 4), &[12]);
+    }
+}
@@ -3,14 +3,26 @@
 //! This module provides several implementations of a LDPC decoders using belief
 //! propagation (the sum-product algorithm). The implementations differ in
 //! details about their numerical algorithms, data types and message passing
-//! schedules.
-
-use crate::sparse::SparseMatrix;
+//! schedules. Low-complexity hard-decision decoders are also provided in
+//! [`bit_flipping`].
 
 pub mod arithmetic;
+pub mod batch;
+pub mod bit_flipping;
 pub mod factory;
+pub mod finite_alphabet;
 pub mod flooding;
+pub mod flooding_parallel;
+pub mod graph;
 pub mod horizontal_layered;
+pub mod learned;
+pub mod min_sum;
+pub mod schedule;
+pub mod server;
+pub mod stochastic;
+pub mod trace;
+pub mod vertical_layered;
+pub mod windowed;
 
 /// Generic LDPC decoder.
 ///
@@ -32,6 +44,239 @@ pub trait LdpcDecoder: std::fmt::Debug + Send {
         llrs: &[f64],
         max_iterations: usize,
     ) -> Result<DecoderOutput, DecoderOutput>;
+
+    /// Decodes a codeword, giving the reason of a failure.
+    ///
+    /// This is similar to [`LdpcDecoder::decode`], but if decoding is not
+    /// successful, the function returns a [`DecodeFailure`], which contains
+    /// the output of the decoder together with the [`FailureReason`]. This can
+    /// be used to decide whether to request a retransmission or to
+    /// post-process the failed codeword.
+    ///
+    /// The default implementation enables iteration tracing (see
+    /// [`LdpcDecoder::set_iteration_tracing`]) during the decoding in order to
+    /// detect whether the decoder has stalled, and then restores the previous
+    /// setting. For decoders that do not support iteration tracing, a stalled
+    /// decoding is reported as [`FailureReason::MaxIterations`].
+    fn try_decode(
+        &mut self,
+        llrs: &[f64],
+        max_iterations: usize,
+    ) -> Result<DecoderOutput, DecodeFailure> {
+        let tracing = self.iteration_trace().is_some();
+        self.set_iteration_tracing(true);
+        let result = self
+            .decode(llrs, max_iterations)
+            .map_err(|output| DecodeFailure {
+                reason: failure_reason(&*self, llrs),
+                output,
+            });
+        self.set_iteration_tracing(tracing);
+        result
+    }
+
+    /// Decodes a codeword into a buffer provided by the caller.
+    ///
+    /// This is similar to [`LdpcDecoder::decode`], but the hard decisions on
+    /// the decoded codeword are written to `out_codeword`, which must have the
+    /// same length as `llrs`. If decoding is successful, the function returns
+    /// an `Ok` containing the number of iterations used in decoding. If
+    /// decoding is not successful, the function returns an `Err` containing
+    /// the number of iterations used in decoding (which is equal to
+    /// `max_iterations`), and `out_codeword` contains the hard decision on the
+    /// final decoder LLRs.
+    ///
+    /// The decoders in this crate implement this function without allocating
+    /// any memory. The default implementation calls [`LdpcDecoder::decode`]
+    /// and copies its output.
+    fn decode_into(
+        &mut self,
+        llrs: &[f64],
+        out_codeword: &mut [u8],
+        max_iterations: usize,
+    ) -> Result<usize, usize> {
+        let (output, success) = match self.decode(llrs, max_iterations) {
+            Ok(output) => (output, true),
+            Err(output) => (output, false),
+        };
+        out_codeword.copy_from_slice(&output.codeword);
+        if success {
+            Ok(output.iterations)
+        } else {
+            Err(output.iterations)
+        }
+    }
+
+    /// Decodes a codeword from strided LLRs into a buffer provided by the
+    /// caller.
+    ///
+    /// This is similar to [`LdpcDecoder::decode_into`], but the LLRs are read
+    /// from a [`StridedLlrs`] view, such as one of the channels of a buffer
+    /// that interleaves the LLRs of several channels.
+    ///
+    /// The decoders in this crate implement this function reading the LLRs
+    /// directly from the view. The default implementation gathers the LLRs
+    /// into a vector and calls [`LdpcDecoder::decode_into`].
+    fn decode_strided(
+        &mut self,
+        llrs: StridedLlrs<'_>,
+        out_codeword: &mut [u8],
+        max_iterations: usize,
+    ) -> Result<usize, usize> {
+        let llrs = llrs.iter().collect::<Vec<f64>>();
+        self.decode_into(&llrs, out_codeword, max_iterations)
+    }
+
+    /// Enables or disables tracking of the maximum LLR magnitude.
+    ///
+    /// When tracking is enabled, the decoder records the maximum magnitude of
+    /// its internal LLRs during each call to [`LdpcDecoder::decode`]. Tracking
+    /// is disabled by default, since it has a small runtime cost. The default
+    /// implementation does nothing, for decoders that do not support tracking.
+    fn set_llr_magnitude_tracking(&mut self, _enabled: bool) {}
+
+    /// Returns the maximum LLR magnitude in the last decoding.
+    ///
+    /// The magnitude is given in the internal units of the decoder arithmetic
+    /// (see [`DecoderArithmetic::llr_magnitude`](arithmetic::DecoderArithmetic::llr_magnitude)).
+    /// This returns `None` if tracking is not enabled or not supported by the
+    /// decoder.
+    fn max_llr_magnitude(&self) -> Option<f64> {
+        None
+    }
+
+    /// Enables or disables message tracing.
+    ///
+    /// When tracing is enabled, the decoder records every message exchanged
+    /// during each call to [`LdpcDecoder::decode`] in a
+    /// [`MessageTrace`](trace::MessageTrace). Tracing is disabled by default,
+    /// and it is only practical for small codes, since the trace contains all
+    /// the messages of all the iterations. The default implementation does
+    /// nothing, for decoders that do not support tracing.
+    fn set_message_tracing(&mut self, _enabled: bool) {}
+
+    /// Returns the message trace of the last decoding.
+    ///
+    /// This returns `None` if tracing is not enabled or not supported by the
+    /// decoder.
+    fn message_trace(&self) -> Option<&trace::MessageTrace> {
+        None
+    }
+
+    /// Enables or disables iteration tracing.
+    ///
+    /// When tracing is enabled, the decoder records the
+    /// [`IterationStatistics`](trace::IterationStatistics) of each iteration,
+    /// such as the number of unsatisfied checks, during each call to
+    /// [`LdpcDecoder::decode`] in an [`IterationTrace`](trace::IterationTrace).
+    /// Tracing is disabled by default. The default implementation does nothing,
+    /// for decoders that do not support tracing.
+    fn set_iteration_tracing(&mut self, _enabled: bool) {}
+
+    /// Returns the iteration trace of the last decoding.
+    ///
+    /// This returns `None` if tracing is not enabled or not supported by the
+    /// decoder.
+    fn iteration_trace(&self) -> Option<&trace::IterationTrace> {
+        None
+    }
+
+    /// Enables or disables LLR tracing.
+    ///
+    /// When tracing is enabled, the decoder records the LLR of each variable
+    /// node at the end of each iteration during each call to
+    /// [`LdpcDecoder::decode`] in an [`LlrTrace`](trace::LlrTrace). Tracing is
+    /// disabled by default. The default implementation does nothing, for
+    /// decoders that do not support tracing.
+    fn set_llr_tracing(&mut self, _enabled: bool) {}
+
+    /// Returns the LLR trace of the last decoding.
+    ///
+    /// This returns `None` if tracing is not enabled or not supported by the
+    /// decoder or by its arithmetic.
+    fn llr_trace(&self) -> Option<&trace::LlrTrace> {
+        None
+    }
+
+    /// Returns the reliability of each bit in the last decoding.
+    ///
+    /// The reliability of a bit is the magnitude of its final LLR in the
+    /// decoder, given in the internal units of the decoder arithmetic (see
+    /// [`DecoderArithmetic::llr_magnitude`](arithmetic::DecoderArithmetic::llr_magnitude)).
+    /// If the last decoding succeeded without running any iterations, the
+    /// final LLRs are the input LLRs. This returns `None` if no codeword has
+    /// been decoded yet or if the decoder does not support it. The default
+    /// implementation returns `None`.
+    fn bit_reliabilities(&self) -> Option<Vec<f64>> {
+        None
+    }
+
+    /// Returns the indices of the least reliable bits in the last decoding.
+    ///
+    /// This is intended to be used after a failed decoding, so that an outer
+    /// protocol such as hybrid ARQ can request the retransmission of the
+    /// `count` bits in which the decoder is least confident. The indices are
+    /// sorted by increasing reliability (see [`LdpcDecoder::bit_reliabilities`]
+    /// and [`least_reliable_bits`]). This returns `None` if the decoder does
+    /// not support bit reliabilities.
+    fn least_reliable_bits(&self, count: usize) -> Option<Vec<usize>> {
+        self.bit_reliabilities()
+            .map(|reliabilities| least_reliable_bits(&reliabilities, count))
+    }
+
+    /// Returns the update counters of the last decoding.
+    ///
+    /// The counters are only collected when the `profiling` feature is
+    /// enabled. This returns `None` if the feature is not enabled or if the
+    /// decoder does not support counters. The default implementation returns
+    /// `None`.
+    fn update_counters(&self) -> Option<DecoderCounters> {
+        None
+    }
+}
+
+/// Returns the indices of the least reliable bits.
+///
+/// Given the reliability of each bit, such as the magnitude of its LLR, this
+/// returns the indices of the `count` bits with the lowest reliability, sorted
+/// by increasing reliability. Bits with the same reliability are sorted by
+/// index. If `count` is larger than the number of bits, the indices of all
+/// the bits are returned.
+///
+/// # Examples
+/// ```
+/// # use ldpc_toolbox::decoder::least_reliable_bits;
+/// assert_eq!(least_reliable_bits(&[3.0, 0.5, 7.0, 0.5, 1.0], 3), vec![1, 3, 4]);
+/// ```
+pub fn least_reliable_bits(reliabilities: &[f64], count: usize) -> Vec<usize> {
+    let mut indices = (0..reliabilities.len()).collect::<Vec<_>>();
+    indices.sort_by(|&a, &b| reliabilities[a].total_cmp(&reliabilities[b]));
+    indices.truncate(count);
+    indices
+}
+
+/// Returns the number of bits corrected by a decoder.
+///
+/// This counts the bits in which a decoded codeword differs from the hard
+/// decision on the LLRs given to the decoder, which is a cheap measure of the
+/// quality of the channel. An LLR is decided as a one if it is smaller than or
+/// equal to zero, as done by the decoders.
+///
+/// # Panics
+/// This function panics if the lengths of `llrs` and `codeword` are
+/// different.
+///
+/// # Examples
+/// ```
+/// # use ldpc_toolbox::decoder::corrected_bits;
+/// assert_eq!(corrected_bits(&[1.5, -0.5, 2.0, -3.0], &[0, 0, 1, 1]), 2);
+/// ```
+pub fn corrected_bits(llrs: &[f64], codeword: &[u8]) -> usize {
+    assert_eq!(llrs.len(), codeword.len());
+    llrs.iter()
+        .zip(codeword)
+        .filter(|(&llr, &bit)| (llr <= 0.0) != (bit != 0))
+        .count()
 }
 
 /// LDPC decoder output.
@@ -45,6 +290,224 @@ pub struct DecoderOutput {
     ///
     /// Number of iterations used in decoding.
     pub iterations: usize,
+    /// Number of corrected bits.
+    ///
+    /// Number of bits in which the decoded codeword differs from the hard
+    /// decision on the input LLRs (see [`corrected_bits`]).
+    pub corrected_bits: usize,
+    /// Update counters.
+    ///
+    /// Contains the number of node updates and messages used in decoding, if
+    /// the decoder collects them (see [`LdpcDecoder::update_counters`]).
+    pub counters: Option<DecoderCounters>,
+}
+
+/// LDPC decoding failure.
+///
+/// This is returned by [`LdpcDecoder::try_decode`] when decoding is not
+/// successful.
+#[derive(Debug, Clone, Eq, PartialEq, Hash, thiserror::Error)]
+#[error("decoding failed: {reason}")]
+pub struct DecodeFailure {
+    /// Reason of the failure.
+    pub reason: FailureReason,
+    /// Decoder output.
+    ///
+    /// Contains the hard decision on the final decoder LLRs, which still has
+    /// some bit errors.
+    pub output: DecoderOutput,
+}
+
+/// Reason of an LDPC decoding failure.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum FailureReason {
+    /// The maximum number of iterations was reached while the hard decisions
+    /// were still changing.
+    ///
+    /// Running more iterations might give a codeword.
+    MaxIterations,
+    /// The decoder converged to a state that is not a codeword.
+    ///
+    /// The hard decisions have not changed after iteration `since` (which is 0
+    /// if they have never changed), and at least 5 iterations have been run
+    /// since then, so running more iterations is unlikely to give a codeword.
+    /// This typically happens when the bit errors contain a trapping set of
+    /// the Tanner graph.
+    Stalled {
+        /// Last iteration in which the hard decisions changed.
+        since: usize,
+    },
+    /// The input LLRs or the LLRs computed by the decoder contain NaNs.
+    NumericalFailure,
+}
+
+impl std::fmt::Display for FailureReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FailureReason::MaxIterations => write!(f, "maximum number of iterations reached"),
+            FailureReason::Stalled { since } => {
+                write!(f, "decoder stalled since iteration {since}")
+            }
+            FailureReason::NumericalFailure => write!(f, "numerical failure (NaN LLRs)"),
+        }
+    }
+}
+
+// Number of iterations without changes in the hard decisions after which a
+// decoding failure is considered a stall.
+const STALL_ITERATIONS: usize = 5;
+
+// Determines the reason of a decoding failure from the input LLRs and the
+// state of the decoder after the decoding.
+fn failure_reason<D: LdpcDecoder + ?Sized>(decoder: &D, llrs: &[f64]) -> FailureReason {
+    let nan_reliabilities = decoder
+        .bit_reliabilities()
+        .is_some_and(|reliabilities| reliabilities.iter().any(|x| x.is_nan()));
+    if nan_reliabilities || llrs.iter().any(|x| x.is_nan()) {
+        return FailureReason::NumericalFailure;
+    }
+    if let Some(trace) = decoder.iteration_trace() {
+        let iterations = trace.iterations();
+        let last = iterations.last().map_or(0, |s| s.iteration);
+        let since = iterations
+            .iter()
+            .rev()
+            .find(|s| s.flipped_bits != 0)
+            .map_or(0, |s| s.iteration);
+        if last - since >= STALL_ITERATIONS {
+            return FailureReason::Stalled { since };
+        }
+    }
+    FailureReason::MaxIterations
+}
+
+/// LDPC decoder update counters.
+///
+/// These counters measure the amount of work done by a decoder in a decoding,
+/// so that the complexity of different message passing schedules can be
+/// compared independently of the wall time. A check node update or a variable
+/// node update is counted each time that the decoder processes a node, and a
+/// message is counted each time that a value is passed through an edge of the
+/// Tanner graph, in either direction.
+///
+/// The decoders in this crate only collect the counters when the `profiling`
+/// feature is enabled, since counting has a small runtime cost.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub struct DecoderCounters {
+    /// Number of check node updates.
+    pub check_node_updates: u64,
+    /// Number of variable node updates.
+    pub variable_node_updates: u64,
+    /// Number of messages sent.
+    pub messages: u64,
+}
+
+impl std::ops::AddAssign for DecoderCounters {
+    fn add_assign(&mut self, other: DecoderCounters) {
+        self.check_node_updates += other.check_node_updates;
+        self.variable_node_updates += other.variable_node_updates;
+        self.messages += other.messages;
+    }
+}
+
+impl std::ops::Add for DecoderCounters {
+    type Output = DecoderCounters;
+
+    fn add(mut self, other: DecoderCounters) -> DecoderCounters {
+        self += other;
+        self
+    }
+}
+
+/// Source of LLRs for a decoder.
+///
+/// This trait abstracts the input of the decoders, so that they can read the
+/// LLRs of the received codeword from a buffer that is not a contiguous slice
+/// without copying them first. It is implemented by `[f64]` and by
+/// [`StridedLlrs`].
+pub trait LlrSource {
+    /// Returns the number of LLRs.
+    fn len(&self) -> usize;
+
+    /// Returns `true` if there are no LLRs.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the LLR with a given index.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    fn llr(&self, index: usize) -> f64;
+}
+
+impl LlrSource for [f64] {
+    fn len(&self) -> usize {
+        <[f64]>::len(self)
+    }
+
+    fn llr(&self, index: usize) -> f64 {
+        self[index]
+    }
+}
+
+/// Strided view of LLRs.
+///
+/// This view contains every `stride`-th element of a slice, starting by the
+/// first element. It can be used to decode one of the channels of a buffer in
+/// which the LLRs of several channels are interleaved, by slicing the buffer
+/// so that it starts at the first LLR of the channel.
+///
+/// # Examples
+/// ```
+/// # use ldpc_toolbox::decoder::{LlrSource, StridedLlrs};
+/// // Two channels interleaved
+/// let buffer = [1.0, -1.0, 2.0, -2.0, 3.0, -3.0];
+/// let channel1 = StridedLlrs::new(&buffer[1..], 2);
+/// assert_eq!(channel1.len(), 3);
+/// assert_eq!(channel1.iter().collect::<Vec<_>>(), [-1.0, -2.0, -3.0]);
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct StridedLlrs<'a> {
+    data: &'a [f64],
+    stride: usize,
+}
+
+impl<'a> StridedLlrs<'a> {
+    /// Creates a new strided view.
+    ///
+    /// The view contains the elements of `data` with indices `0`, `stride`,
+    /// `2 * stride`, etc.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `stride` is zero.
+    pub fn new(data: &'a [f64], stride: usize) -> StridedLlrs<'a> {
+        assert!(stride > 0, "stride must be positive");
+        StridedLlrs { data, stride }
+    }
+
+    /// Returns an iterator over the LLRs of the view.
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = f64> + 'a {
+        let len = LlrSource::len(self);
+        self.data.iter().step_by(self.stride).take(len).copied()
+    }
+}
+
+impl LlrSource for StridedLlrs<'_> {
+    fn len(&self) -> usize {
+        self.data.len().div_ceil(self.stride)
+    }
+
+    fn llr(&self, index: usize) -> f64 {
+        self.data[index * self.stride]
+    }
+}
+
+// Returns an iterator over the LLRs of an LLR source.
+fn source_llrs<L: LlrSource + ?Sized>(llrs: &L) -> impl ExactSizeIterator<Item = f64> + '_ {
+    (0..llrs.len()).map(|j| llrs.llr(j))
 }
 
 /// LDPC decoder message.
@@ -154,21 +617,58 @@ impl<T: Default> SentMessages<T> {
     }
 }
 
-fn check_llrs<T, F>(h: &SparseMatrix, llrs: &[T], hard_decision: F) -> bool
+// Updates a maximum LLR magnitude with the magnitudes of some LLRs.
+fn update_max_magnitude<T, I, F>(max: &mut Option<f64>, llrs: I, magnitude: F)
 where
-    T: Copy,
-    F: Fn(T) -> bool,
+    I: IntoIterator<Item = T>,
+    F: Fn(T) -> Option<f64>,
 {
-    // Check if hard decision on LLRs satisfies the parity check equations
-    !(0..h.num_rows()).any(|r| h.iter_row(r).filter(|&&c| hard_decision(llrs[c])).count() % 2 == 1)
+    for llr in llrs {
+        if let Some(m) = magnitude(llr) {
+            *max = Some(max.map_or(m, |x| x.max(m)));
+        }
+    }
 }
 
-fn hard_decisions<T, F>(llrs: &[T], hard_decision: F) -> Vec<u8>
+fn write_hard_decisions<T, I, F>(llrs: I, out_codeword: &mut [u8], hard_decision: F)
 where
-    T: Copy,
+    I: IntoIterator<Item = T>,
+    I::IntoIter: ExactSizeIterator,
     F: Fn(T) -> bool,
 {
-    llrs.iter()
-        .map(|&llr| u8::from(hard_decision(llr)))
-        .collect()
+    let llrs = llrs.into_iter();
+    assert_eq!(llrs.len(), out_codeword.len());
+    for (out, llr) in out_codeword.iter_mut().zip(llrs) {
+        *out = u8::from(hard_decision(llr));
+    }
+}
+
+// Implements decode in terms of decode_into by allocating the output codeword.
+fn decoder_output<D, F>(
+    decoder: &mut D,
+    llrs: &[f64],
+    decode_into: F,
+) -> Result<DecoderOutput, DecoderOutput>
+where
+    D: LdpcDecoder + ?Sized,
+    F: FnOnce(&mut D, &mut [u8]) -> Result<usize, usize>,
+{
+    let mut codeword = vec![0; llrs.len()];
+    let result = decode_into(decoder, &mut codeword);
+    let counters = decoder.update_counters();
+    let corrected_bits = corrected_bits(llrs, &codeword);
+    match result {
+        Ok(iterations) => Ok(DecoderOutput {
+            codeword,
+            iterations,
+            corrected_bits,
+            counters,
+        }),
+        Err(iterations) => Err(DecoderOutput {
+            codeword,
+            iterations,
+            corrected_bits,
+            counters,
+        }),
+    }
 }
@@ -27,7 +27,10 @@
 //! 1-157-1-162. Piscataway, NJ: IEEE, October 2003.
 //!
 
-use super::{Message, SentMessage};
+use super::{
+    min_sum::{reduce, Reduction},
+    Message, SentMessage,
+};
 use std::convert::identity;
 
 /// LDPC decoder arithmetic.
@@ -88,6 +91,60 @@ pub trait DecoderArithmetic: std::fmt::Debug + Send {
     /// Defines how to transform a variable LLR into an LLR.
     fn var_llr_to_llr(&self, var_llr: Self::VarLlr) -> Self::Llr;
 
+    /// Magnitude of an LLR.
+    ///
+    /// Returns the magnitude of an LLR in the internal units of the arithmetic
+    /// (for instance, in quantization steps for fixed-point arithmetics). This
+    /// is used to track the largest LLRs that appear during decoding, which is
+    /// useful to size the datapaths of hardware implementations.
+    ///
+    /// The default implementation returns `None`, which indicates that the
+    /// arithmetic does not support tracking LLR magnitudes.
+    fn llr_magnitude(&self, _llr: Self::Llr) -> Option<f64> {
+        None
+    }
+
+    /// Magnitude of a variable LLR.
+    ///
+    /// This is the same as [`DecoderArithmetic::llr_magnitude`], but for
+    /// variable LLRs.
+    fn var_llr_magnitude(&self, _var_llr: Self::VarLlr) -> Option<f64> {
+        None
+    }
+
+    /// Saturation of an LLR.
+    ///
+    /// Returns `true` if the LLR is at the limit of the range that the
+    /// arithmetic can represent. This is used to count the saturated LLRs in
+    /// each iteration (see
+    /// [`IterationStatistics`](super::trace::IterationStatistics)).
+    ///
+    /// The default implementation returns `false`, which is appropriate for
+    /// arithmetics that do not saturate LLRs.
+    fn llr_saturated(&self, _llr: Self::Llr) -> bool {
+        false
+    }
+
+    /// Value of a check node message.
+    ///
+    /// Returns the value of a check node message as an `f64` in the internal
+    /// units of the arithmetic. This is used to trace the messages exchanged
+    /// during decoding (see [`MessageTrace`](super::trace::MessageTrace)).
+    ///
+    /// The default implementation returns `None`, which indicates that the
+    /// arithmetic does not support message tracing.
+    fn check_message_value(&self, _msg: Self::CheckMessage) -> Option<f64> {
+        None
+    }
+
+    /// Value of a variable node message.
+    ///
+    /// This is the same as [`DecoderArithmetic::check_message_value`], but
+    /// for variable node messages.
+    fn var_message_value(&self, _msg: Self::VarMessage) -> Option<f64> {
+        None
+    }
+
     /// Send check messages from a check node.
     ///
     /// This function is called with the list of variable messages arriving to a
@@ -96,9 +153,14 @@ pub trait DecoderArithmetic: std::fmt::Debug + Send {
     ///
     /// This function should compute the values of the check node messages and
     /// call the `send` closure for each of the variable nodes connected to the
-    /// check node being processed.
-    fn send_check_messages<F>(&mut self, var_messages: &[Message<Self::VarMessage>], send: F)
-    where
+    /// check node being processed. The `context` gives the current iteration
+    /// and the degree of the check node.
+    fn send_check_messages<F>(
+        &mut self,
+        var_messages: &[Message<Self::VarMessage>],
+        context: NodeContext,
+        send: F,
+    ) where
         F: FnMut(SentMessage<Self::CheckMessage>);
 
     /// Send variable messages from a variable node.
@@ -110,13 +172,15 @@ pub trait DecoderArithmetic: std::fmt::Debug + Send {
     ///
     /// This function should compute the values of the variable node messages and
     /// call the `send` closure for each of the check nodes connected to the
-    /// variable node being processed.
+    /// variable node being processed. The `context` gives the current iteration
+    /// and the degree of the variable node.
     ///
     /// Additionally, the function returns the new LLR for this variable node.
     fn send_var_messages<F>(
         &mut self,
         input_llr: Self::Llr,
         check_messages: &[Message<Self::CheckMessage>],
+        context: NodeContext,
         send: F,
     ) -> Self::Llr
     where
@@ -128,16 +192,81 @@ pub trait DecoderArithmetic: std::fmt::Debug + Send {
     /// with the messages sent by a check node and the list of LLRs of all the
     /// variables. The function computes and updates the new check messages and
     /// the new variable LLRs (for the variables directly connected to this
-    /// check messages).
+    /// check messages). The `context` gives the current iteration and the
+    /// degree of the check node.
     fn update_check_messages_and_vars(
         &mut self,
         check_messages: &mut [SentMessage<Self::CheckMessage>],
         vars: &mut [Self::VarLlr],
+        context: NodeContext,
     );
 }
 
+/// Context of a node being processed.
+///
+/// This is passed by the decoders to the methods of [`DecoderArithmetic`] that
+/// process a check node or a variable node, so that arithmetic rules can
+/// depend on the decoding progress and on the node being processed (for
+/// instance, to implement adaptive scaling or degree-dependent offsets).
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub struct NodeContext {
+    /// Current iteration.
+    ///
+    /// Iterations are numbered starting by 1.
+    pub iteration: usize,
+    /// Degree of the node being processed.
+    pub degree: usize,
+    /// Index of the node being processed.
+    ///
+    /// This is the index of the check node or the variable node in the parity
+    /// check matrix. The messages of a check node are always given in the
+    /// order of [`SparseMatrix::iter_row`](crate::sparse::SparseMatrix::iter_row),
+    /// so the node index and the position of a message identify an edge of the
+    /// graph.
+    pub node: usize,
+}
+
+impl NodeContext {
+    /// Creates a new node context.
+    ///
+    /// The node index is set to zero. It can be set with
+    /// [`NodeContext::with_node`].
+    pub fn new(iteration: usize, degree: usize) -> NodeContext {
+        NodeContext {
+            iteration,
+            degree,
+            node: 0,
+        }
+    }
+
+    /// Sets the index of the node being processed.
+    pub fn with_node(self, node: usize) -> NodeContext {
+        NodeContext { node, ..self }
+    }
+}
+
+/// Scaling of check node messages.
+///
+/// This trait is implemented by decoder arithmetics whose check node messages
+/// can be multiplied by a scaling factor. It is used by the flooding decoder to
+/// scale the messages sent through some of the edges of the graph (see
+/// [`Decoder::with_short_cycle_scaling`](super::flooding::Decoder::with_short_cycle_scaling)).
+pub trait CheckMessageScaling: DecoderArithmetic {
+    /// Scales a check node message.
+    ///
+    /// Returns the check node message multiplied by `factor`. This does not
+    /// depend on the state of the arithmetic, so that it can be called while
+    /// the check node messages are being sent.
+    fn scale_check_message(message: Self::CheckMessage, factor: f64) -> Self::CheckMessage;
+}
+
 // The usual variable message update rule, without any clipping.
-fn send_var_messages_no_clip<T, F>(input_llr: T, check_messages: &[Message<T>], mut send: F) -> T
+pub(super) fn send_var_messages_no_clip<T, F>(
+    input_llr: T,
+    check_messages: &[Message<T>],
+    mut send: F,
+) -> T
 where
     T: std::iter::Sum + std::ops::Add<Output = T> + std::ops::Sub<Output = T> + Copy,
     F: FnMut(SentMessage<T>),
@@ -211,8 +340,28 @@ macro_rules! impl_phif {
                 var_llr
             }
 
-            fn send_check_messages<F>(&mut self, var_messages: &[Message<$f>], mut send: F)
-            where
+            fn llr_magnitude(&self, llr: $f) -> Option<f64> {
+                Some(f64::from(llr.abs()))
+            }
+
+            fn var_llr_magnitude(&self, var_llr: $f) -> Option<f64> {
+                Some(f64::from(var_llr.abs()))
+            }
+
+            fn check_message_value(&self, msg: $f) -> Option<f64> {
+                Some(f64::from(msg))
+            }
+
+            fn var_message_value(&self, msg: $f) -> Option<f64> {
+                Some(f64::from(msg))
+            }
+
+            fn send_check_messages<F>(
+                &mut self,
+                var_messages: &[Message<$f>],
+                _context: NodeContext,
+                mut send: F,
+            ) where
                 F: FnMut(SentMessage<$f>),
             {
                 // Compute combination of all variable messages
@@ -249,6 +398,7 @@ macro_rules! impl_phif {
                 &mut self,
                 input_llr: $f,
                 check_messages: &[Message<$f>],
+                _context: NodeContext,
                 send: F,
             ) -> $f
             where
@@ -261,6 +411,7 @@ macro_rules! impl_phif {
                 &mut self,
                 check_messages: &mut [SentMessage<$f>],
                 vars: &mut [$f],
+                _context: NodeContext,
             ) {
                 // Compute combination of all variables messages
                 let mut sign: u32 = 0;
@@ -344,8 +495,28 @@ macro_rules! impl_tanhf {
                 var_llr
             }
 
-            fn send_check_messages<F>(&mut self, var_messages: &[Message<$f>], mut send: F)
-            where
+            fn llr_magnitude(&self, llr: $f) -> Option<f64> {
+                Some(f64::from(llr.abs()))
+            }
+
+            fn var_llr_magnitude(&self, var_llr: $f) -> Option<f64> {
+                Some(f64::from(var_llr.abs()))
+            }
+
+            fn check_message_value(&self, msg: $f) -> Option<f64> {
+                Some(f64::from(msg))
+            }
+
+            fn var_message_value(&self, msg: $f) -> Option<f64> {
+                Some(f64::from(msg))
+            }
+
+            fn send_check_messages<F>(
+                &mut self,
+                var_messages: &[Message<$f>],
+                _context: NodeContext,
+                mut send: F,
+            ) where
                 F: FnMut(SentMessage<$f>),
             {
                 // Compute tanh's of all variable messages
@@ -382,6 +553,7 @@ macro_rules! impl_tanhf {
                 &mut self,
                 input_llr: $f,
                 check_messages: &[Message<$f>],
+                _context: NodeContext,
                 send: F,
             ) -> $f
             where
@@ -394,6 +566,7 @@ macro_rules! impl_tanhf {
                 &mut self,
                 check_messages: &mut [SentMessage<$f>],
                 vars: &mut [$f],
+                _context: NodeContext,
             ) {
                 // Compute tanh's of all variable messages
                 if self.tanhs.len() < check_messages.len() {
@@ -434,6 +607,202 @@ impl_tanhf!(Tanhf64, f64, 18.0);
 // For f32, tanh(10) already gives 1.0.
 impl_tanhf!(Tanhf32, f32, 9.0);
 
+/// Reference LDPC decoder arithmetic with `f64`.
+///
+/// This is a [`DecoderArithmetic`] meant to be used as a numerical reference
+/// to quantify the floating point error of the other arithmetics, rather than
+/// for speed. It uses `f64` and the involution `phi(x) = -log(tanh(x/2))` (as
+/// [`Phif64`]), with the following differences:
+///
+/// - `phi(x)` is evaluated as `log(1 + exp(-x)) - log(1 - exp(-x))` using
+///   `ln_1p()` and `exp_m1()`, which is accurate also for large `x`, where
+///   `tanh(x/2)` rounds to one.
+///
+/// - The messages sent to each node are computed by adding the contributions of
+///   all the other nodes, instead of adding all the contributions and then
+///   subtracting the contribution of the destination, which suffers from
+///   cancellation. This makes the cost of processing a node quadratic in its
+///   degree.
+///
+/// - All the sums use compensated (Kahan-Babuška-Neumaier) summation.
+///
+/// Nothing is saturated, so LLRs and messages can grow without limit
+/// (other than the range of `f64`).
+#[derive(Debug, Clone, Default)]
+pub struct Referencef64 {
+    phis: Vec<f64>,
+}
+
+impl Referencef64 {
+    /// Creates a new [`Referencef64`] decoder arithmetic object.
+    pub fn new() -> Referencef64 {
+        Referencef64::default()
+    }
+
+    fn phi(x: f64) -> f64 {
+        // phi(0) is +inf, which is avoided by taking the smallest positive
+        // normal number instead of zero.
+        let x = x.max(f64::MIN_POSITIVE);
+        let e = (-x).exp();
+        // log(1 - exp(-x)) is computed in the way that avoids cancellation for
+        // each range of x.
+        let log_1_minus_e = if x > std::f64::consts::LN_2 {
+            (-e).ln_1p()
+        } else {
+            (-(-x).exp_m1()).ln()
+        };
+        e.ln_1p() - log_1_minus_e
+    }
+
+    // Computes the sign (0 or 1) and phi values of the inputs of a check node,
+    // storing the phi values in self.phis.
+    fn check_inputs(&mut self, inputs: impl ExactSizeIterator<Item = f64>) -> u32 {
+        self.phis.clear();
+        let mut sign = 0;
+        for x in inputs {
+            self.phis.push(Self::phi(x.abs()));
+            if x < 0.0 {
+                sign ^= 1;
+            }
+        }
+        sign
+    }
+
+    // Computes the magnitude of the check message sent to the node with index
+    // exclude, using the phi values in self.phis.
+    fn check_output(&self, exclude: usize) -> f64 {
+        Self::phi(compensated_sum(
+            self.phis
+                .iter()
+                .enumerate()
+                .filter(|&(j, _)| j != exclude)
+                .map(|(_, &phi)| phi),
+        ))
+    }
+}
+
+// Kahan-Babuška-Neumaier compensated summation.
+fn compensated_sum(values: impl Iterator<Item = f64>) -> f64 {
+    let mut sum = 0.0;
+    let mut compensation = 0.0;
+    for x in values {
+        let t = sum + x;
+        if sum.abs() >= x.abs() {
+            compensation += (sum - t) + x;
+        } else {
+            compensation += (x - t) + sum;
+        }
+        sum = t;
+    }
+    sum + compensation
+}
+
+impl DecoderArithmetic for Referencef64 {
+    type Llr = f64;
+    type CheckMessage = f64;
+    type VarMessage = f64;
+    type VarLlr = f64;
+
+    fn input_llr_quantize(&self, llr: f64) -> f64 {
+        llr
+    }
+
+    fn llr_hard_decision(&self, llr: f64) -> bool {
+        llr <= 0.0
+    }
+
+    fn llr_to_var_message(&self, llr: f64) -> f64 {
+        llr
+    }
+
+    fn llr_to_var_llr(&self, llr: f64) -> f64 {
+        llr
+    }
+
+    fn var_llr_to_llr(&self, var_llr: f64) -> f64 {
+        var_llr
+    }
+
+    fn llr_magnitude(&self, llr: f64) -> Option<f64> {
+        Some(llr.abs())
+    }
+
+    fn var_llr_magnitude(&self, var_llr: f64) -> Option<f64> {
+        Some(var_llr.abs())
+    }
+
+    fn check_message_value(&self, msg: f64) -> Option<f64> {
+        Some(msg)
+    }
+
+    fn var_message_value(&self, msg: f64) -> Option<f64> {
+        Some(msg)
+    }
+
+    fn send_check_messages<F>(
+        &mut self,
+        var_messages: &[Message<f64>],
+        _context: NodeContext,
+        mut send: F,
+    ) where
+        F: FnMut(SentMessage<f64>),
+    {
+        let sign = self.check_inputs(var_messages.iter().map(|msg| msg.value));
+        for (j, msg) in var_messages.iter().enumerate() {
+            let y = self.check_output(j);
+            let s = if msg.value < 0.0 { sign ^ 1 } else { sign };
+            send(SentMessage {
+                dest: msg.source,
+                value: if s == 0 { y } else { -y },
+            });
+        }
+    }
+
+    fn send_var_messages<F>(
+        &mut self,
+        input_llr: f64,
+        check_messages: &[Message<f64>],
+        _context: NodeContext,
+        mut send: F,
+    ) -> f64
+    where
+        F: FnMut(SentMessage<f64>),
+    {
+        for msg in check_messages.iter() {
+            send(SentMessage {
+                dest: msg.source,
+                value: compensated_sum(
+                    std::iter::once(input_llr).chain(
+                        check_messages
+                            .iter()
+                            .filter(|m| m.source != msg.source)
+                            .map(|m| m.value),
+                    ),
+                ),
+            });
+        }
+        compensated_sum(std::iter::once(input_llr).chain(check_messages.iter().map(|m| m.value)))
+    }
+
+    fn update_check_messages_and_vars(
+        &mut self,
+        check_messages: &mut [SentMessage<f64>],
+        vars: &mut [f64],
+        _context: NodeContext,
+    ) {
+        // Subtract the contribution of the previous check node messages
+        let sign = self.check_inputs(check_messages.iter().map(|msg| vars[msg.dest] - msg.value));
+        for (j, msg) in check_messages.iter_mut().enumerate() {
+            let x = vars[msg.dest] - msg.value;
+            let rcv = self.check_output(j);
+            let s = if x < 0.0 { sign ^ 1 } else { sign };
+            let rcv = if s == 0 { rcv } else { -rcv };
+            msg.value = rcv;
+            vars[msg.dest] = x + rcv;
+        }
+    }
+}
+
 macro_rules! impl_minstarapproxf {
     ($ty:ident, $f:ty) => {
         /// LDPC decoder arithmetic with `$f` and the following approximation to
@@ -484,8 +853,28 @@ macro_rules! impl_minstarapproxf {
                 var_llr
             }
 
-            fn send_check_messages<F>(&mut self, var_messages: &[Message<$f>], mut send: F)
-            where
+            fn llr_magnitude(&self, llr: $f) -> Option<f64> {
+                Some(f64::from(llr.abs()))
+            }
+
+            fn var_llr_magnitude(&self, var_llr: $f) -> Option<f64> {
+                Some(f64::from(var_llr.abs()))
+            }
+
+            fn check_message_value(&self, msg: $f) -> Option<f64> {
+                Some(f64::from(msg))
+            }
+
+            fn var_message_value(&self, msg: $f) -> Option<f64> {
+                Some(f64::from(msg))
+            }
+
+            fn send_check_messages<F>(
+                &mut self,
+                var_messages: &[Message<$f>],
+                _context: NodeContext,
+                mut send: F,
+            ) where
                 F: FnMut(SentMessage<$f>),
             {
                 for exclude_msg in var_messages.iter() {
@@ -524,6 +913,7 @@ macro_rules! impl_minstarapproxf {
                 &mut self,
                 input_llr: $f,
                 check_messages: &[Message<$f>],
+                _context: NodeContext,
                 send: F,
             ) -> $f
             where
@@ -536,6 +926,7 @@ macro_rules! impl_minstarapproxf {
                 &mut self,
                 check_messages: &mut [SentMessage<$f>],
                 vars: &mut [$f],
+                _context: NodeContext,
             ) {
                 // Compute all min*'s
                 if self.minstars.len() < check_messages.len() {
@@ -630,6 +1021,7 @@ macro_rules! impl_send_var_messages_i8 {
             &mut self,
             input_llr: i8,
             check_messages: &[Message<i8>],
+            _context: NodeContext,
             mut send: F,
         ) -> i8
         where
@@ -718,9 +1110,33 @@ macro_rules! impl_minstarapproxi8 {
                 Self::clip(var_llr)
             }
 
+            fn llr_saturated(&self, llr: i8) -> bool {
+                llr.unsigned_abs() >= 127
+            }
+
+            fn llr_magnitude(&self, llr: i8) -> Option<f64> {
+                Some(f64::from(llr.unsigned_abs()))
+            }
+
+            fn var_llr_magnitude(&self, var_llr: i16) -> Option<f64> {
+                Some(f64::from(var_llr.unsigned_abs()))
+            }
+
+            fn check_message_value(&self, msg: i8) -> Option<f64> {
+                Some(f64::from(msg))
+            }
+
+            fn var_message_value(&self, msg: i8) -> Option<f64> {
+                Some(f64::from(msg))
+            }
+
             #[allow(clippy::redundant_closure_call)]
-            fn send_check_messages<F>(&mut self, var_messages: &[Message<i8>], mut send: F)
-            where
+            fn send_check_messages<F>(
+                &mut self,
+                var_messages: &[Message<i8>],
+                _context: NodeContext,
+                mut send: F,
+            ) where
                 F: FnMut(SentMessage<i8>),
             {
                 for exclude_msg in var_messages.iter() {
@@ -764,6 +1180,7 @@ macro_rules! impl_minstarapproxi8 {
                 &mut self,
                 check_messages: &mut [SentMessage<i8>],
                 vars: &mut [i16],
+                _context: NodeContext,
             ) {
                 // Compute all min*'s
                 if self._minstars.len() < check_messages.len() {
@@ -943,8 +1360,28 @@ macro_rules! impl_aminstarf {
                 var_llr
             }
 
-            fn send_check_messages<F>(&mut self, var_messages: &[Message<$f>], mut send: F)
-            where
+            fn llr_magnitude(&self, llr: $f) -> Option<f64> {
+                Some(f64::from(llr.abs()))
+            }
+
+            fn var_llr_magnitude(&self, var_llr: $f) -> Option<f64> {
+                Some(f64::from(var_llr.abs()))
+            }
+
+            fn check_message_value(&self, msg: $f) -> Option<f64> {
+                Some(f64::from(msg))
+            }
+
+            fn var_message_value(&self, msg: $f) -> Option<f64> {
+                Some(f64::from(msg))
+            }
+
+            fn send_check_messages<F>(
+                &mut self,
+                var_messages: &[Message<$f>],
+                _context: NodeContext,
+                mut send: F,
+            ) where
                 F: FnMut(SentMessage<$f>),
             {
                 let (argmin, msgmin) = var_messages
@@ -1008,6 +1445,7 @@ macro_rules! impl_aminstarf {
                 &mut self,
                 input_llr: $f,
                 check_messages: &[Message<$f>],
+                _context: NodeContext,
                 send: F,
             ) -> $f
             where
@@ -1020,6 +1458,7 @@ macro_rules! impl_aminstarf {
                 &mut self,
                 check_messages: &mut [SentMessage<$f>],
                 vars: &mut [$f],
+                _context: NodeContext,
             ) {
                 let (argmin, msgmin) = check_messages
                     .iter()
@@ -1126,9 +1565,33 @@ macro_rules! impl_aminstari8 {
                 Self::clip(var_llr)
             }
 
+            fn llr_saturated(&self, llr: i8) -> bool {
+                llr.unsigned_abs() >= 127
+            }
+
+            fn llr_magnitude(&self, llr: i8) -> Option<f64> {
+                Some(f64::from(llr.unsigned_abs()))
+            }
+
+            fn var_llr_magnitude(&self, var_llr: i16) -> Option<f64> {
+                Some(f64::from(var_llr.unsigned_abs()))
+            }
+
+            fn check_message_value(&self, msg: i8) -> Option<f64> {
+                Some(f64::from(msg))
+            }
+
+            fn var_message_value(&self, msg: i8) -> Option<f64> {
+                Some(f64::from(msg))
+            }
+
             #[allow(clippy::redundant_closure_call)]
-            fn send_check_messages<F>(&mut self, var_messages: &[Message<i8>], mut send: F)
-            where
+            fn send_check_messages<F>(
+                &mut self,
+                var_messages: &[Message<i8>],
+                _context: NodeContext,
+                mut send: F,
+            ) where
                 F: FnMut(SentMessage<i8>),
             {
                 let (argmin, msgmin) = var_messages
@@ -1200,6 +1663,7 @@ macro_rules! impl_aminstari8 {
                 &mut self,
                 check_messages: &mut [SentMessage<i8>],
                 vars: &mut [i16],
+                _context: NodeContext,
             ) {
                 let (argmin, msgmin) = check_messages
                     .iter()
@@ -1304,3 +1768,503 @@ impl_aminstari8!(
     partial_hard_limit!(),
     degree_one_clipping!()
 );
+
+// Check node update of the min-sum arithmetics. The magnitudes of the
+// outgoing messages are modified with the `correct` method of the arithmetic,
+// which implements the normalization or offset.
+macro_rules! impl_min_sum_check_nodes {
+    ($t:ty) => {
+        fn send_check_messages<F>(
+            &mut self,
+            var_messages: &[Message<$t>],
+            _context: NodeContext,
+            mut send: F,
+        ) where
+            F: FnMut(SentMessage<$t>),
+        {
+            self.values.clear();
+            self.values.extend(var_messages.iter().map(|msg| msg.value));
+            let reduction = self.corrected_reduction();
+            for (j, msg) in var_messages.iter().enumerate() {
+                send(SentMessage {
+                    dest: msg.source,
+                    value: reduction.message(j, msg.value),
+                })
+            }
+        }
+
+        fn update_check_messages_and_vars(
+            &mut self,
+            check_messages: &mut [SentMessage<$t>],
+            vars: &mut [Self::VarLlr],
+            _context: NodeContext,
+        ) {
+            self.values.clear();
+            self.values.extend(
+                check_messages
+                    .iter()
+                    .map(|msg| Self::var_message(vars[msg.dest], msg.value)),
+            );
+            let reduction = self.corrected_reduction();
+            // Update Rcv's and Qv's
+            for (j, msg) in check_messages.iter_mut().enumerate() {
+                let value = reduction.message(j, self.values[j]);
+                vars[msg.dest] += Self::VarLlr::from(value) - Self::VarLlr::from(msg.value);
+                msg.value = value;
+            }
+        }
+    };
+}
+
+macro_rules! impl_min_sum_reduction {
+    ($t:ty) => {
+        fn corrected_reduction(&self) -> Reduction<$t> {
+            let reduction =
+                reduce(&self.values).expect("only one variable message connected to check node");
+            Reduction {
+                min1: self.correct(reduction.min1),
+                min2: self.correct(reduction.min2),
+                ..reduction
+            }
+        }
+    };
+}
+
+macro_rules! impl_min_sumf {
+    ($ty:ident, $f:ty) => {
+        impl $ty {
+            impl_min_sum_reduction!($f);
+
+            fn var_message(var_llr: $f, check_message: $f) -> $f {
+                var_llr - check_message
+            }
+        }
+
+        impl DecoderArithmetic for $ty {
+            type Llr = $f;
+            type CheckMessage = $f;
+            type VarMessage = $f;
+            type VarLlr = $f;
+
+            fn input_llr_quantize(&self, llr: f64) -> $f {
+                llr as $f
+            }
+
+            fn llr_hard_decision(&self, llr: $f) -> bool {
+                llr <= 0.0
+            }
+
+            fn llr_to_var_message(&self, llr: $f) -> $f {
+                llr
+            }
+
+            fn llr_to_var_llr(&self, llr: $f) -> $f {
+                llr
+            }
+
+            fn var_llr_to_llr(&self, var_llr: $f) -> $f {
+                var_llr
+            }
+
+            fn llr_magnitude(&self, llr: $f) -> Option<f64> {
+                Some(f64::from(llr.abs()))
+            }
+
+            fn var_llr_magnitude(&self, var_llr: $f) -> Option<f64> {
+                Some(f64::from(var_llr.abs()))
+            }
+
+            fn check_message_value(&self, msg: $f) -> Option<f64> {
+                Some(f64::from(msg))
+            }
+
+            fn var_message_value(&self, msg: $f) -> Option<f64> {
+                Some(f64::from(msg))
+            }
+
+            impl_min_sum_check_nodes!($f);
+
+            fn send_var_messages<F>(
+                &mut self,
+                input_llr: $f,
+                check_messages: &[Message<$f>],
+                _context: NodeContext,
+                send: F,
+            ) -> $f
+            where
+                F: FnMut(SentMessage<$f>),
+            {
+                send_var_messages_no_clip(input_llr, check_messages, send)
+            }
+        }
+    };
+}
+
+macro_rules! impl_norm_min_sumf {
+    ($ty:ident, $f:ty) => {
+        #[doc = concat!(
+                                                    "LDPC decoder arithmetic with `",
+                                                    stringify!($f),
+                                                    "` and the normalized min-sum rule."
+                                                )]
+        ///
+        #[doc = concat!(
+                                                    "This is a [`DecoderArithmetic`] that uses `",
+                                                    stringify!($f),
+                                                    "` to represent the LLRs"
+                                                )]
+        /// and messages and computes the check node messages using the min-sum
+        /// rule, multiplying their magnitude by a scaling factor. The factor
+        /// compensates the overestimation of the magnitude of the messages
+        /// caused by the min-sum approximation. Typical values are between 0.7
+        /// and 0.9.
+        #[derive(Debug, Clone)]
+        pub struct $ty {
+            factor: $f,
+            values: Vec<$f>,
+        }
+
+        impl $ty {
+            #[doc = concat!("Creates a new [`", stringify!($ty), "`] decoder arithmetic object.")]
+            ///
+            /// The parameter `factor` is the scaling factor for the check node
+            /// messages.
+            pub fn new(factor: f64) -> $ty {
+                $ty {
+                    factor: factor as $f,
+                    values: Vec::new(),
+                }
+            }
+
+            fn correct(&self, magnitude: $f) -> $f {
+                magnitude * self.factor
+            }
+        }
+
+        impl_min_sumf!($ty, $f);
+    };
+}
+
+impl_norm_min_sumf!(NormMinSumf64, f64);
+impl_norm_min_sumf!(NormMinSumf32, f32);
+
+macro_rules! impl_offset_min_sumf {
+    ($ty:ident, $f:ty) => {
+        #[doc = concat!(
+                                                    "LDPC decoder arithmetic with `",
+                                                    stringify!($f),
+                                                    "` and the offset min-sum rule."
+                                                )]
+        ///
+        #[doc = concat!(
+                                                    "This is a [`DecoderArithmetic`] that uses `",
+                                                    stringify!($f),
+                                                    "` to represent the LLRs"
+                                                )]
+        /// and messages and computes the check node messages using the min-sum
+        /// rule, subtracting an offset from their magnitude (which is clamped
+        /// to zero from below). The offset compensates the overestimation of
+        /// the magnitude of the messages caused by the min-sum approximation.
+        #[derive(Debug, Clone)]
+        pub struct $ty {
+            offset: $f,
+            values: Vec<$f>,
+        }
+
+        impl $ty {
+            #[doc = concat!("Creates a new [`", stringify!($ty), "`] decoder arithmetic object.")]
+            ///
+            /// The parameter `offset` is the offset for the check node messages,
+            /// in LLR units.
+            pub fn new(offset: f64) -> $ty {
+                $ty {
+                    offset: offset as $f,
+                    values: Vec::new(),
+                }
+            }
+
+            fn correct(&self, magnitude: $f) -> $f {
+                (magnitude - self.offset).max(0.0)
+            }
+        }
+
+        impl_min_sumf!($ty, $f);
+    };
+}
+
+impl_offset_min_sumf!(OffsetMinSumf64, f64);
+impl_offset_min_sumf!(OffsetMinSumf32, f32);
+
+macro_rules! impl_min_sumi8 {
+    ($ty:ident) => {
+        impl $ty {
+            const QUANTIZER_C: f64 = 8.0;
+
+            impl_min_sum_reduction!(i8);
+
+            fn var_message(var_llr: i16, check_message: i8) -> i8 {
+                Self::clip(var_llr - i16::from(check_message))
+            }
+
+            fn clip(x: i16) -> i8 {
+                x.clamp(-127, 127) as i8
+            }
+        }
+
+        impl DecoderArithmetic for $ty {
+            type Llr = i8;
+            type CheckMessage = i8;
+            type VarMessage = i8;
+            // See Minstarapproxi8
+            type VarLlr = i16;
+
+            fn input_llr_quantize(&self, llr: f64) -> i8 {
+                (Self::QUANTIZER_C * llr).round().clamp(-127.0, 127.0) as i8
+            }
+
+            fn llr_hard_decision(&self, llr: i8) -> bool {
+                llr <= 0
+            }
+
+            fn llr_to_var_message(&self, llr: i8) -> i8 {
+                llr
+            }
+
+            fn llr_to_var_llr(&self, llr: i8) -> i16 {
+                i16::from(llr)
+            }
+
+            fn var_llr_to_llr(&self, var_llr: i16) -> i8 {
+                Self::clip(var_llr)
+            }
+
+            fn llr_saturated(&self, llr: i8) -> bool {
+                llr.unsigned_abs() >= 127
+            }
+
+            fn llr_magnitude(&self, llr: i8) -> Option<f64> {
+                Some(f64::from(llr.unsigned_abs()))
+            }
+
+            fn var_llr_magnitude(&self, var_llr: i16) -> Option<f64> {
+                Some(f64::from(var_llr.unsigned_abs()))
+            }
+
+            fn check_message_value(&self, msg: i8) -> Option<f64> {
+                Some(f64::from(msg))
+            }
+
+            fn var_message_value(&self, msg: i8) -> Option<f64> {
+                Some(f64::from(msg))
+            }
+
+            impl_min_sum_check_nodes!(i8);
+
+            impl_send_var_messages_i8!(degree_one_no_clipping!(), identity);
+        }
+    };
+}
+
+/// LDPC decoder arithmetic with 8-bit quantization and the normalized min-sum
+/// rule.
+///
+/// This is a [`DecoderArithmetic`] that uses `i8` to represent the LLRs and
+/// messages and computes the check node messages using the min-sum rule,
+/// multiplying their magnitude by a scaling factor, as in [`NormMinSumf64`].
+/// The scaled magnitudes are rounded to the nearest integer.
+#[derive(Debug, Clone)]
+pub struct NormMinSumi8 {
+    factor: f64,
+    values: Vec<i8>,
+}
+
+impl NormMinSumi8 {
+    /// Creates a new [`NormMinSumi8`] decoder arithmetic object.
+    ///
+    /// The parameter `factor` is the scaling factor for the check node
+    /// messages.
+    pub fn new(factor: f64) -> NormMinSumi8 {
+        NormMinSumi8 {
+            factor,
+            values: Vec::new(),
+        }
+    }
+
+    fn correct(&self, magnitude: i8) -> i8 {
+        (f64::from(magnitude) * self.factor)
+            .round()
+            .clamp(0.0, 127.0) as i8
+    }
+}
+
+impl_min_sumi8!(NormMinSumi8);
+
+/// LDPC decoder arithmetic with 8-bit quantization and the offset min-sum
+/// rule.
+///
+/// This is a [`DecoderArithmetic`] that uses `i8` to represent the LLRs and
+/// messages and computes the check node messages using the min-sum rule,
+/// subtracting an offset from their magnitude, as in [`OffsetMinSumf64`]. The
+/// offset is quantized with the same scale as the LLRs.
+#[derive(Debug, Clone)]
+pub struct OffsetMinSumi8 {
+    offset: i8,
+    values: Vec<i8>,
+}
+
+impl OffsetMinSumi8 {
+    /// Creates a new [`OffsetMinSumi8`] decoder arithmetic object.
+    ///
+    /// The parameter `offset` is the offset for the check node messages, in
+    /// LLR units.
+    pub fn new(offset: f64) -> OffsetMinSumi8 {
+        OffsetMinSumi8 {
+            offset: (Self::QUANTIZER_C * offset).round().clamp(0.0, 127.0) as i8,
+            values: Vec::new(),
+        }
+    }
+
+    fn correct(&self, magnitude: i8) -> i8 {
+        (magnitude - self.offset).max(0)
+    }
+}
+
+impl_min_sumi8!(OffsetMinSumi8);
+
+macro_rules! impl_check_message_scaling_float {
+    ($ty:ident, $f:ty) => {
+        impl CheckMessageScaling for $ty {
+            fn scale_check_message(message: $f, factor: f64) -> $f {
+                message * factor as $f
+            }
+        }
+    };
+}
+
+impl_check_message_scaling_float!(Phif64, f64);
+impl_check_message_scaling_float!(Phif32, f32);
+impl_check_message_scaling_float!(Tanhf64, f64);
+impl_check_message_scaling_float!(Tanhf32, f32);
+impl_check_message_scaling_float!(Minstarapproxf64, f64);
+impl_check_message_scaling_float!(Minstarapproxf32, f32);
+impl_check_message_scaling_float!(Aminstarf64, f64);
+impl_check_message_scaling_float!(Aminstarf32, f32);
+impl_check_message_scaling_float!(Referencef64, f64);
+impl_check_message_scaling_float!(NormMinSumf64, f64);
+impl_check_message_scaling_float!(NormMinSumf32, f32);
+impl_check_message_scaling_float!(OffsetMinSumf64, f64);
+impl_check_message_scaling_float!(OffsetMinSumf32, f32);
+
+macro_rules! impl_check_message_scaling_i8 {
+    ($($ty:ident),*) => {
+        $(
+            impl CheckMessageScaling for $ty {
+                fn scale_check_message(message: i8, factor: f64) -> i8 {
+                    (f64::from(message) * factor).round().clamp(-127.0, 127.0) as i8
+                }
+            }
+        )*
+    };
+}
+
+impl_check_message_scaling_i8!(
+    Minstarapproxi8,
+    Minstarapproxi8Jones,
+    Minstarapproxi8PartialHardLimit,
+    Minstarapproxi8JonesPartialHardLimit,
+    Minstarapproxi8Deg1Clip,
+    Minstarapproxi8JonesDeg1Clip,
+    Minstarapproxi8PartialHardLimitDeg1Clip,
+    Minstarapproxi8JonesPartialHardLimitDeg1Clip,
+    Aminstari8,
+    Aminstari8Jones,
+    Aminstari8PartialHardLimit,
+    Aminstari8JonesPartialHardLimit,
+    Aminstari8Deg1Clip,
+    Aminstari8JonesDeg1Clip,
+    Aminstari8PartialHardLimitDeg1Clip,
+    Aminstari8JonesPartialHardLimitDeg1Clip,
+    NormMinSumi8,
+    OffsetMinSumi8
+);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn reference_phi_is_involution() {
+        for &x in &[1e-6, 0.01, 0.5, 1.0, 5.0, 20.0, 30.0, 300.0] {
+            let y = Referencef64::phi(Referencef64::phi(x));
+            assert!((y - x).abs() <= 1e-9 * x, "phi(phi({x})) = {y}");
+        }
+    }
+
+    #[test]
+    fn reference_decodes() {
+        use crate::{
+            codes::ccsds::{AR4JACode, AR4JAInfoSize, AR4JARate},
+            decoder::{flooding, horizontal_layered},
+        };
+        let h = AR4JACode::new(AR4JARate::R1_2, AR4JAInfoSize::K1024).h();
+        // All-zeros codeword with some bit errors
+        let llrs = (0..h.num_cols())
+            .map(|j| if j % 7 == 0 { -0.5 } else { 2.0 })
+            .collect::<Vec<f64>>();
+        let mut decoder = flooding::Decoder::new(h.clone(), Referencef64::new());
+        let output = decoder.decode(&llrs, 100).unwrap();
+        assert!(output.codeword.iter().all(|&b| b == 0));
+        let mut decoder = horizontal_layered::Decoder::new(h, Referencef64::new());
+        let output = decoder.decode(&llrs, 50).unwrap();
+        assert!(output.codeword.iter().all(|&b| b == 0));
+    }
+
+    #[test]
+    fn min_sum_decodes() {
+        use crate::{
+            codes::ccsds::{AR4JACode, AR4JAInfoSize, AR4JARate},
+            decoder::{flooding, horizontal_layered, LdpcDecoder},
+        };
+        let h = std::sync::Arc::new(AR4JACode::new(AR4JARate::R1_2, AR4JAInfoSize::K1024).h());
+        // All-zeros codeword with some bit errors
+        let llrs = (0..h.num_cols())
+            .map(|j| if j % 7 == 0 { -0.5 } else { 2.0 })
+            .collect::<Vec<f64>>();
+        let mut decoders: Vec<Box<dyn LdpcDecoder>> = vec![
+            Box::new(flooding::Decoder::new(h.clone(), NormMinSumf64::new(0.75))),
+            Box::new(flooding::Decoder::new(h.clone(), OffsetMinSumf32::new(0.5))),
+            Box::new(horizontal_layered::Decoder::new(
+                h.clone(),
+                NormMinSumi8::new(0.75),
+            )),
+            Box::new(horizontal_layered::Decoder::new(
+                h,
+                OffsetMinSumi8::new(0.5),
+            )),
+        ];
+        for decoder in decoders.iter_mut() {
+            let output = decoder.decode(&llrs, 100).unwrap();
+            assert!(output.codeword.iter().all(|&b| b == 0));
+        }
+    }
+
+    #[test]
+    fn min_sum_corrections() {
+        let nms = NormMinSumf64::new(0.75);
+        assert_eq!(nms.correct(2.0), 1.5);
+        let oms = OffsetMinSumf64::new(0.5);
+        assert_eq!(oms.correct(2.0), 1.5);
+        assert_eq!(oms.correct(0.25), 0.0);
+        assert_eq!(NormMinSumi8::new(0.75).correct(10), 8);
+        let oms = OffsetMinSumi8::new(0.5);
+        assert_eq!(oms.correct(10), 6);
+        assert_eq!(oms.correct(3), 0);
+    }
+
+    #[test]
+    fn compensated() {
+        let values = [1.0, 1e100, 1.0, -1e100];
+        assert_eq!(values.iter().sum::<f64>(), 0.0);
+        assert_eq!(compensated_sum(values.iter().copied()), 2.0);
+    }
+}
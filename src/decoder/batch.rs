@@ -0,0 +1,530 @@
+//! Batch LDPC decoders.
+//!
+//! This module contains decoders that decode several codewords with a single
+//! call, by means of the [`LdpcBatchDecoder`] trait. The [`MinSumi8`] decoder
+//! processes a number of codewords (called lanes) in lockstep: the LLRs and
+//! messages of each node and edge of the Tanner graph are stored as an array
+//! with one `i8` per codeword, and the check node and variable node updates
+//! are done with element-wise operations on these arrays.
+//!
+//! The element-wise operations are written as loops over the lanes without
+//! branches, so that the compiler can vectorize them. The baseline x86_64
+//! instruction set (SSE2) has no instructions for the absolute value and
+//! minimum of `i8` values, so on x86_64 the decoder is compiled twice: once for
+//! the baseline target and once with AVX2 enabled. The AVX2 version is
+//! selected at runtime if the CPU supports it, so the check node updates are
+//! vectorized without building the crate with `-C target-cpu=native`.
+//!
+//! Any [`LdpcDecoder`] can be used as a batch decoder with the [`Sequential`]
+//! adapter, which decodes the codewords one by one.
+
+use super::{graph::CompiledGraph, LdpcDecoder};
+use std::sync::Arc;
+
+/// Generic LDPC batch decoder.
+///
+/// This trait is used to form LDPC batch decoder trait objects, abstracting
+/// over the internal implementation decoder.
+pub trait LdpcBatchDecoder: std::fmt::Debug + Send {
+    /// Returns the number of codewords that are decoded in lockstep.
+    ///
+    /// Batches whose number of codewords is a multiple of this number use the
+    /// decoder most efficiently.
+    fn lanes(&self) -> usize;
+
+    /// Decodes a batch of codewords.
+    ///
+    /// The parameter `llrs` contains the LLRs of `n_codewords` codewords, one
+    /// codeword after another. The hard decisions on the decoded codewords are
+    /// written to `out_codewords` with the same layout, so it must have the
+    /// same length as `llrs`. The function returns the result of decoding each
+    /// codeword, which is an `Ok` containing the number of iterations if
+    /// decoding is successful, or an `Err` containing the number of iterations
+    /// (which is equal to `max_iterations`) if decoding is not successful, as
+    /// in [`LdpcDecoder::decode_into`].
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the length of `llrs` or `out_codewords` is not
+    /// `n_codewords` times the codeword length.
+    fn decode_batch(
+        &mut self,
+        llrs: &[f32],
+        n_codewords: usize,
+        out_codewords: &mut [u8],
+        max_iterations: usize,
+    ) -> &[Result<usize, usize>];
+}
+
+/// Batch decoder that decodes the codewords one by one.
+///
+/// This adapter implements [`LdpcBatchDecoder`] using an [`LdpcDecoder`],
+/// which decodes each of the codewords of the batch in turn.
+#[derive(Debug)]
+pub struct Sequential {
+    decoder: Box<dyn LdpcDecoder>,
+    llrs: Vec<f64>,
+    results: Vec<Result<usize, usize>>,
+}
+
+impl Sequential {
+    /// Creates a new sequential batch decoder.
+    pub fn new(decoder: Box<dyn LdpcDecoder>) -> Sequential {
+        Sequential {
+            decoder,
+            llrs: Vec::new(),
+            results: Vec::new(),
+        }
+    }
+}
+
+impl LdpcBatchDecoder for Sequential {
+    fn lanes(&self) -> usize {
+        1
+    }
+
+    fn decode_batch(
+        &mut self,
+        llrs: &[f32],
+        n_codewords: usize,
+        out_codewords: &mut [u8],
+        max_iterations: usize,
+    ) -> &[Result<usize, usize>] {
+        assert_eq!(llrs.len() % n_codewords.max(1), 0);
+        assert_eq!(out_codewords.len(), llrs.len());
+        self.results.clear();
+        if n_codewords == 0 {
+            return &self.results;
+        }
+        let n = llrs.len() / n_codewords;
+        for (llrs, out) in llrs.chunks_exact(n).zip(out_codewords.chunks_exact_mut(n)) {
+            self.llrs.clear();
+            self.llrs.extend(llrs.iter().map(|&x| f64::from(x)));
+            self.results
+                .push(self.decoder.decode_into(&self.llrs, out, max_iterations));
+        }
+        &self.results
+    }
+}
+
+/// LDPC batch decoder with 8-bit quantization and the min-sum rule.
+///
+/// This decoder uses a flooding schedule and the same arithmetic as the
+/// [`NormMinSumi8`](super::arithmetic::NormMinSumi8) and
+/// [`OffsetMinSumi8`](super::arithmetic::OffsetMinSumi8) decoder arithmetics.
+/// It decodes `LANES` codewords in lockstep, and for each codeword it gives
+/// the same results as the [flooding decoder](super::flooding::Decoder) with
+/// the corresponding arithmetic.
+///
+/// All the codewords of a group of `LANES` codewords are iterated until all of
+/// them have been decoded or the maximum number of iterations has been
+/// reached. The output of each codeword is recorded in the iteration in which
+/// it is decoded.
+///
+/// The lanes are processed with loops that the compiler vectorizes, using
+/// AVX2 if it is available at runtime (see the [module documentation](self)).
+#[derive(Debug, Clone)]
+pub struct MinSumi8<const LANES: usize = 32> {
+    graph: Arc<CompiledGraph>,
+    // Corrected magnitude of the check node messages for each min-sum
+    // magnitude
+    correction: Box<[i8]>,
+    input_llrs: Box<[[i8; LANES]]>,
+    output_llrs: Box<[[i8; LANES]]>,
+    hard_decisions: Box<[[u8; LANES]]>,
+    // Messages indexed by edge number
+    check_messages: Box<[[i8; LANES]]>,
+    var_messages: Box<[[i8; LANES]]>,
+    results: Vec<Result<usize, usize>>,
+}
+
+// Same LLR quantization as NormMinSumi8 and OffsetMinSumi8
+const QUANTIZER_C: f64 = 8.0;
+
+impl<const LANES: usize> MinSumi8<LANES> {
+    /// Creates a new batch decoder with the normalized min-sum rule.
+    ///
+    /// The parameter `factor` is the scaling factor for the check node
+    /// messages, as in [`NormMinSumi8::new`](super::arithmetic::NormMinSumi8::new).
+    pub fn normalized(graph: Arc<CompiledGraph>, factor: f64) -> Self {
+        Self::with_correction(graph, |magnitude| {
+            (f64::from(magnitude) * factor).round().clamp(0.0, 127.0) as i8
+        })
+    }
+
+    /// Creates a new batch decoder with the offset min-sum rule.
+    ///
+    /// The parameter `offset` is the offset for the check node messages, in
+    /// LLR units, as in
+    /// [`OffsetMinSumi8::new`](super::arithmetic::OffsetMinSumi8::new).
+    pub fn offset(graph: Arc<CompiledGraph>, offset: f64) -> Self {
+        let offset = (QUANTIZER_C * offset).round().clamp(0.0, 127.0) as i8;
+        Self::with_correction(graph, |magnitude| (magnitude - offset).max(0))
+    }
+
+    fn with_correction<F: Fn(i8) -> i8>(graph: Arc<CompiledGraph>, correct: F) -> Self {
+        assert!(LANES > 0);
+        let vars = vec![[0; LANES]; graph.num_vars()].into_boxed_slice();
+        let edges = vec![[0; LANES]; graph.num_edges()].into_boxed_slice();
+        MinSumi8 {
+            correction: (0..=i8::MAX).map(correct).collect(),
+            input_llrs: vars.clone(),
+            output_llrs: vars,
+            hard_decisions: vec![[0; LANES]; graph.num_vars()].into_boxed_slice(),
+            check_messages: edges.clone(),
+            var_messages: edges,
+            results: Vec::new(),
+            graph,
+        }
+    }
+
+    // Decodes up to LANES codewords, using the AVX2 version of the decoder if
+    // the CPU supports it.
+    fn decode_lanes(
+        &mut self,
+        llrs: &[f32],
+        out_codewords: &mut [u8],
+        results: &mut [Result<usize, usize>],
+        max_iterations: usize,
+    ) {
+        #[cfg(target_arch = "x86_64")]
+        if std::is_x86_feature_detected!("avx2") {
+            // SAFETY: the CPU supports AVX2
+            unsafe { self.decode_lanes_avx2(llrs, out_codewords, results, max_iterations) };
+            return;
+        }
+        self.decode_lanes_generic(llrs, out_codewords, results, max_iterations);
+    }
+
+    // The generic decoder functions are always inlined, so that they are
+    // compiled with AVX2 enabled here.
+    #[cfg(target_arch = "x86_64")]
+    #[target_feature(enable = "avx2")]
+    fn decode_lanes_avx2(
+        &mut self,
+        llrs: &[f32],
+        out_codewords: &mut [u8],
+        results: &mut [Result<usize, usize>],
+        max_iterations: usize,
+    ) {
+        self.decode_lanes_generic(llrs, out_codewords, results, max_iterations);
+    }
+
+    #[inline(always)]
+    fn decode_lanes_generic(
+        &mut self,
+        llrs: &[f32],
+        out_codewords: &mut [u8],
+        results: &mut [Result<usize, usize>],
+        max_iterations: usize,
+    ) {
+        let n = self.graph.num_vars();
+        let count = llrs.len() / n;
+        // Unused lanes are filled with the all-zeros codeword, which is decoded
+        // in zero iterations
+        for (v, (input, hard)) in self
+            .input_llrs
+            .iter_mut()
+            .zip(self.hard_decisions.iter_mut())
+            .enumerate()
+        {
+            for lane in 0..LANES {
+                let llr = if lane < count {
+                    f64::from(llrs[lane * n + v])
+                } else {
+                    f64::INFINITY
+                };
+                input[lane] = (QUANTIZER_C * llr).round().clamp(-127.0, 127.0) as i8;
+                // The hard decisions in iteration zero are done on the
+                // unquantized LLRs, as in the flooding decoder
+                hard[lane] = u8::from(llr <= 0.0);
+            }
+        }
+        let mut done = self.check_parity();
+        self.write_decoded(&done, Ok(0), results, out_codewords);
+        if done.iter().all(|&d| d) {
+            return;
+        }
+
+        // First variable messages use only input LLRs
+        for (v, &llr) in self.input_llrs.iter().enumerate() {
+            for e in self.graph.var_edges(v) {
+                self.var_messages[e] = llr;
+            }
+        }
+        for iteration in 1..=max_iterations {
+            self.process_check_nodes();
+            self.process_variable_nodes();
+            let satisfied = self.check_parity();
+            let decoded = std::array::from_fn(|lane| satisfied[lane] && !done[lane]);
+            self.write_decoded(&decoded, Ok(iteration), results, out_codewords);
+            for (d, x) in done.iter_mut().zip(decoded) {
+                *d |= x;
+            }
+            if done.iter().all(|&d| d) {
+                return;
+            }
+        }
+        // Decode failed for the remaining codewords
+        let failed = done.map(|d| !d);
+        self.write_decoded(&failed, Err(max_iterations), results, out_codewords);
+    }
+
+    #[inline(always)]
+    fn process_check_nodes(&mut self) {
+        for c in 0..self.graph.num_checks() {
+            let edges = self.graph.check_edges(c);
+            let mut min1 = [i8::MAX; LANES];
+            let mut min2 = [i8::MAX; LANES];
+            // The sign bit of the XOR of the messages is the parity of the
+            // number of negative messages
+            let mut sign = [0i8; LANES];
+            for &e in edges {
+                let msg = &self.var_messages[e];
+                for lane in 0..LANES {
+                    let magnitude = msg[lane].abs();
+                    let smallest = magnitude < min1[lane];
+                    min2[lane] = if smallest {
+                        min1[lane]
+                    } else {
+                        min2[lane].min(magnitude)
+                    };
+                    min1[lane] = min1[lane].min(magnitude);
+                    sign[lane] ^= msg[lane];
+                }
+            }
+            let corrected1 = min1.map(|x| self.correction[x as usize]);
+            let corrected2 = min2.map(|x| self.correction[x as usize]);
+            for &e in edges {
+                let msg = &self.var_messages[e];
+                let out = &mut self.check_messages[e];
+                for lane in 0..LANES {
+                    // If the magnitude of the incoming message is equal to
+                    // min1, either this message is the minimum, or min2 is
+                    // also equal to min1
+                    let magnitude = if msg[lane].abs() == min1[lane] {
+                        corrected2[lane]
+                    } else {
+                        corrected1[lane]
+                    };
+                    out[lane] = if (sign[lane] ^ msg[lane]) < 0 {
+                        -magnitude
+                    } else {
+                        magnitude
+                    };
+                }
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn process_variable_nodes(&mut self) {
+        for (v, (input, output)) in self
+            .input_llrs
+            .iter()
+            .zip(self.output_llrs.iter_mut())
+            .enumerate()
+        {
+            // Compute new LLR. We use an i16 to avoid overflows.
+            let mut llr = input.map(i16::from);
+            let edges = self.graph.var_edges(v);
+            for msg in &self.check_messages[edges.clone()] {
+                for lane in 0..LANES {
+                    llr[lane] += i16::from(msg[lane]);
+                }
+            }
+            *output = llr.map(clip);
+            // Exclude the contribution of each check node to generate message
+            // for that check node
+            for (out, msg) in self.var_messages[edges.clone()]
+                .iter_mut()
+                .zip(&self.check_messages[edges])
+            {
+                for lane in 0..LANES {
+                    out[lane] = clip(llr[lane] - i16::from(msg[lane]));
+                }
+            }
+        }
+        for (hard, llr) in self.hard_decisions.iter_mut().zip(self.output_llrs.iter()) {
+            for lane in 0..LANES {
+                hard[lane] = u8::from(llr[lane] <= 0);
+            }
+        }
+    }
+
+    // Returns whether the hard decisions of each lane satisfy all the parity
+    // checks.
+    #[inline(always)]
+    fn check_parity(&self) -> [bool; LANES] {
+        let mut unsatisfied = [0u8; LANES];
+        for c in 0..self.graph.num_checks() {
+            let mut parity = [0u8; LANES];
+            for &v in self.graph.check_neighbours(c) {
+                let hard = &self.hard_decisions[v];
+                for lane in 0..LANES {
+                    parity[lane] ^= hard[lane];
+                }
+            }
+            for lane in 0..LANES {
+                unsatisfied[lane] |= parity[lane];
+            }
+        }
+        unsatisfied.map(|x| x == 0)
+    }
+
+    // Writes the hard decisions of the selected lanes to their codewords and
+    // sets their results.
+    fn write_decoded(
+        &self,
+        lanes: &[bool; LANES],
+        result: Result<usize, usize>,
+        results: &mut [Result<usize, usize>],
+        out_codewords: &mut [u8],
+    ) {
+        let n = self.graph.num_vars();
+        for (lane, (lane_result, out)) in results
+            .iter_mut()
+            .zip(out_codewords.chunks_exact_mut(n))
+            .enumerate()
+        {
+            if !lanes[lane] {
+                continue;
+            }
+            *lane_result = result;
+            for (x, hard) in out.iter_mut().zip(self.hard_decisions.iter()) {
+                *x = hard[lane];
+            }
+        }
+    }
+}
+
+fn clip(x: i16) -> i8 {
+    x.clamp(-127, 127) as i8
+}
+
+impl<const LANES: usize> LdpcBatchDecoder for MinSumi8<LANES> {
+    fn lanes(&self) -> usize {
+        LANES
+    }
+
+    fn decode_batch(
+        &mut self,
+        llrs: &[f32],
+        n_codewords: usize,
+        out_codewords: &mut [u8],
+        max_iterations: usize,
+    ) -> &[Result<usize, usize>] {
+        let n = self.graph.num_vars();
+        assert_eq!(llrs.len(), n_codewords * n);
+        assert_eq!(out_codewords.len(), llrs.len());
+        let mut results = std::mem::take(&mut self.results);
+        results.clear();
+        results.resize(n_codewords, Ok(0));
+        if n > 0 {
+            for ((llrs, out), results) in llrs
+                .chunks(LANES * n)
+                .zip(out_codewords.chunks_mut(LANES * n))
+                .zip(results.chunks_mut(LANES))
+            {
+                self.decode_lanes(llrs, out, results, max_iterations);
+            }
+        }
+        self.results = results;
+        &self.results
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::{
+        arithmetic::{NormMinSumi8, OffsetMinSumi8, Phif64},
+        flooding,
+    };
+    use super::*;
+    use crate::codes::ccsds::{AR4JACode, AR4JAInfoSize, AR4JARate};
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+
+    fn random_llrs(n: usize, n_codewords: usize) -> Vec<f32> {
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        // All-zeros codeword with noise. The punctured bits have zero LLR.
+        (0..n_codewords * n)
+            .map(|j| {
+                if j % n >= 2048 {
+                    0.0
+                } else {
+                    3.0 + 8.0 * (rng.gen::<f32>() - 0.5)
+                }
+            })
+            .collect()
+    }
+
+    fn compare_with_flooding<const LANES: usize>(
+        mut batch: MinSumi8<LANES>,
+        mut serial: Box<dyn LdpcDecoder>,
+    ) {
+        let n = 2560;
+        let n_codewords = LANES + 3;
+        let llrs = random_llrs(n, n_codewords);
+        let mut out = vec![0; llrs.len()];
+        for max_iterations in [5, 50] {
+            let results = batch
+                .decode_batch(&llrs, n_codewords, &mut out, max_iterations)
+                .to_vec();
+            assert_eq!(results.len(), n_codewords);
+            // Decoding fails with 5 iterations and succeeds with 50
+            assert!(results.iter().any(|r| r.is_ok() == (max_iterations == 50)));
+            for (j, result) in results.into_iter().enumerate() {
+                let llrs = llrs[j * n..(j + 1) * n]
+                    .iter()
+                    .map(|&x| f64::from(x))
+                    .collect::<Vec<_>>();
+                let mut expected = vec![0; n];
+                assert_eq!(
+                    result,
+                    serial.decode_into(&llrs, &mut expected, max_iterations)
+                );
+                assert_eq!(&out[j * n..(j + 1) * n], &expected);
+            }
+        }
+    }
+
+    #[test]
+    fn same_as_flooding() {
+        let h = AR4JACode::new(AR4JARate::R1_2, AR4JAInfoSize::K1024).h();
+        let graph = Arc::new(CompiledGraph::new(h));
+        compare_with_flooding(
+            MinSumi8::<16>::normalized(Arc::clone(&graph), 0.75),
+            Box::new(flooding::Decoder::from_graph(
+                Arc::clone(&graph),
+                NormMinSumi8::new(0.75),
+            )),
+        );
+        compare_with_flooding(
+            MinSumi8::<32>::offset(Arc::clone(&graph), 0.5),
+            Box::new(flooding::Decoder::from_graph(
+                graph,
+                OffsetMinSumi8::new(0.5),
+            )),
+        );
+    }
+
+    #[test]
+    fn sequential() {
+        let h = AR4JACode::new(AR4JARate::R1_2, AR4JAInfoSize::K1024).h();
+        let graph = Arc::new(CompiledGraph::new(h));
+        let mut batch = Sequential::new(Box::new(flooding::Decoder::from_graph(
+            graph,
+            Phif64::new(),
+        )));
+        assert_eq!(batch.lanes(), 1);
+        let mut llrs = vec![2.0; 3 * 2560];
+        llrs[2560 + 7] = -1.0;
+        let mut out = vec![1; llrs.len()];
+        assert_eq!(
+            batch.decode_batch(&llrs, 3, &mut out, 10),
+            &[Ok(0), Ok(1), Ok(0)]
+        );
+        assert!(out.iter().all(|&b| b == 0));
+    }
+}
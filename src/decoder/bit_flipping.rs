@@ -0,0 +1,422 @@
+//! Hard-decision LDPC decoders.
+//!
+//! This module implements two low-complexity decoders that work with hard
+//! decisions instead of LLRs, which are useful to compare the complexity and
+//! performance trade-offs with belief propagation.
+//!
+//! The [`GallagerB`] algorithm is a message passing decoder in which the
+//! messages are bits. Check nodes send the XOR of the other incoming messages,
+//! and variable nodes send the channel bit, unless at least a threshold number
+//! of the other incoming messages disagree with it, in which case they send
+//! the opposite bit. See *R. G. Gallager, "Low-Density Parity-Check Codes,"
+//! MIT Press, 1963*.
+//!
+//! The [`WeightedBitFlipping`] algorithm flips in each iteration the bit that
+//! is involved in the most unsatisfied parity checks, where each check is
+//! weighted by the reliability of its least reliable bit. The reliability of
+//! the channel bit can also be taken into account, as in the modified weighted
+//! bit flipping algorithm. See *Y. Kou, S. Lin and M. P. C. Fossorier,
+//! "Low-density parity-check codes based on finite geometries: a rediscovery
+//! and new results," in IEEE Transactions on Information Theory, vol. 47,
+//! no. 7, pp. 2711-2736, Nov. 2001* and *J. Zhang and M. P. C. Fossorier, "A
+//! modified weighted bit-flipping decoding of low-density parity-check codes,"
+//! in IEEE Communications Letters, vol. 8, no. 3, pp. 165-167, March 2004*.
+//!
+//! Only the signs of the input LLRs are used by Gallager-B, while weighted bit
+//! flipping uses their magnitudes as reliabilities. In weighted bit flipping,
+//! each bit flip counts as an iteration.
+
+use super::{
+    decoder_output, graph::CompiledGraph, source_llrs, write_hard_decisions, DecoderOutput,
+    LdpcDecoder, LlrSource, StridedLlrs,
+};
+use crate::sparse::SparseMatrix;
+use std::sync::Arc;
+
+/// Gallager-B algorithm.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct GallagerB {
+    /// Number of disagreeing incoming messages needed to flip the channel bit
+    /// in the outgoing message of a variable node.
+    ///
+    /// If this is `None`, a majority of the other incoming messages is
+    /// required, which is the usual choice for column weight 3.
+    pub threshold: Option<usize>,
+}
+
+impl GallagerB {
+    /// Creates the Gallager-B algorithm with the majority threshold.
+    pub fn new() -> GallagerB {
+        GallagerB::default()
+    }
+}
+
+/// Weighted bit flipping algorithm.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WeightedBitFlipping {
+    /// Weight of the channel reliability of each bit in its flipping metric.
+    ///
+    /// A value of zero gives the original weighted bit flipping algorithm, and
+    /// positive values give the modified weighted bit flipping algorithm, in
+    /// which reliable bits are less likely to be flipped.
+    pub alpha: f64,
+}
+
+impl WeightedBitFlipping {
+    /// Creates the weighted bit flipping algorithm.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `alpha` is negative or not finite.
+    pub fn new(alpha: f64) -> WeightedBitFlipping {
+        assert!(alpha >= 0.0 && alpha.is_finite());
+        WeightedBitFlipping { alpha }
+    }
+}
+
+/// Hard-decision decoding algorithm.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Algorithm {
+    /// Gallager-B algorithm.
+    GallagerB(GallagerB),
+    /// Weighted bit flipping algorithm.
+    WeightedBitFlipping(WeightedBitFlipping),
+}
+
+impl From<GallagerB> for Algorithm {
+    fn from(algorithm: GallagerB) -> Algorithm {
+        Algorithm::GallagerB(algorithm)
+    }
+}
+
+impl From<WeightedBitFlipping> for Algorithm {
+    fn from(algorithm: WeightedBitFlipping) -> Algorithm {
+        Algorithm::WeightedBitFlipping(algorithm)
+    }
+}
+
+/// Hard-decision LDPC decoder.
+#[derive(Debug, Clone)]
+pub struct Decoder {
+    algorithm: Algorithm,
+    graph: Arc<CompiledGraph>,
+    // Channel hard decisions, and their reliabilities (only used by weighted
+    // bit flipping)
+    channel: Box<[bool]>,
+    reliabilities: Box<[f64]>,
+    // Current hard decisions
+    decisions: Box<[bool]>,
+    // Messages of Gallager-B. The edges are numbered as in the compiled graph.
+    var_messages: Box<[bool]>,
+    check_messages: Box<[bool]>,
+    // Syndrome, check weights and flipping metrics of weighted bit flipping
+    syndrome: Box<[bool]>,
+    check_weights: Box<[f64]>,
+    metrics: Box<[f64]>,
+}
+
+impl Decoder {
+    /// Creates a new hard-decision LDPC decoder.
+    ///
+    /// The parameter `h` indicates the parity check matrix, which can be given
+    /// either as a [`SparseMatrix`] or as an `Arc<SparseMatrix>` shared with
+    /// other decoders.
+    pub fn new(h: impl Into<Arc<SparseMatrix>>, algorithm: impl Into<Algorithm>) -> Decoder {
+        Decoder::from_graph(Arc::new(CompiledGraph::new(h)), algorithm)
+    }
+
+    /// Creates a new hard-decision LDPC decoder from a compiled Tanner graph.
+    ///
+    /// The graph is shared with the other decoders built from the same `Arc`.
+    pub fn from_graph(graph: Arc<CompiledGraph>, algorithm: impl Into<Algorithm>) -> Decoder {
+        let num_edges = graph.num_edges();
+        let num_vars = graph.num_vars();
+        let num_checks = graph.num_checks();
+        Decoder {
+            algorithm: algorithm.into(),
+            channel: vec![false; num_vars].into_boxed_slice(),
+            reliabilities: vec![0.0; num_vars].into_boxed_slice(),
+            decisions: vec![false; num_vars].into_boxed_slice(),
+            var_messages: vec![false; num_edges].into_boxed_slice(),
+            check_messages: vec![false; num_edges].into_boxed_slice(),
+            syndrome: vec![false; num_checks].into_boxed_slice(),
+            check_weights: vec![0.0; num_checks].into_boxed_slice(),
+            metrics: vec![0.0; num_vars].into_boxed_slice(),
+            graph,
+        }
+    }
+
+    /// Decodes a codeword.
+    ///
+    /// The parameters are the LLRs for the received codeword and the maximum
+    /// number of iterations to perform. If decoding is successful, the
+    /// function returns an `Ok` containing the (hard decision) on the decoded
+    /// codeword and the number of iterations used in decoding. If decoding is
+    /// not successful, the function returns an `Err` containing the current
+    /// hard decisions of the decoder and the number of iterations used in
+    /// decoding (which is equal to `max_iterations`).
+    pub fn decode(
+        &mut self,
+        llrs: &[f64],
+        max_iterations: usize,
+    ) -> Result<DecoderOutput, DecoderOutput> {
+        decoder_output(self, llrs, |decoder, out| {
+            decoder.decode_into(llrs, out, max_iterations)
+        })
+    }
+
+    /// Decodes a codeword into a buffer provided by the caller.
+    ///
+    /// See [`LdpcDecoder::decode_into`]. This function does not allocate any
+    /// memory.
+    pub fn decode_into(
+        &mut self,
+        llrs: &[f64],
+        out_codeword: &mut [u8],
+        max_iterations: usize,
+    ) -> Result<usize, usize> {
+        self.decode_from(llrs, out_codeword, max_iterations)
+    }
+
+    /// Decodes a codeword from an LLR source into a buffer provided by the
+    /// caller.
+    ///
+    /// This is similar to [`Decoder::decode_into`], but the LLRs are read
+    /// from an [`LlrSource`], such as a [`StridedLlrs`] view, without copying
+    /// them first.
+    pub fn decode_from<L: LlrSource + ?Sized>(
+        &mut self,
+        llrs: &L,
+        out_codeword: &mut [u8],
+        max_iterations: usize,
+    ) -> Result<usize, usize> {
+        assert_eq!(llrs.len(), self.channel.len());
+        for ((channel, reliability), llr) in self
+            .channel
+            .iter_mut()
+            .zip(self.reliabilities.iter_mut())
+            .zip(source_llrs(llrs))
+        {
+            *channel = llr <= 0.0;
+            *reliability = llr.abs();
+        }
+        self.decisions.copy_from_slice(&self.channel);
+        let result = if self.graph.check_parity(&self.decisions, |x| x) {
+            // No bit errors case
+            Ok(0)
+        } else {
+            match self.algorithm.clone() {
+                Algorithm::GallagerB(algorithm) => self.gallager_b(&algorithm, max_iterations),
+                Algorithm::WeightedBitFlipping(algorithm) => {
+                    self.weighted_bit_flipping(&algorithm, max_iterations)
+                }
+            }
+        };
+        write_hard_decisions(self.decisions.iter().copied(), out_codeword, |x| x);
+        result
+    }
+
+    fn gallager_b(&mut self, algorithm: &GallagerB, max_iterations: usize) -> Result<usize, usize> {
+        for v in 0..self.channel.len() {
+            self.var_messages[self.graph.var_edges(v)].fill(self.channel[v]);
+        }
+        for iteration in 1..=max_iterations {
+            for c in 0..self.graph.num_checks() {
+                let edges = self.graph.check_edges(c);
+                let parity = edges
+                    .iter()
+                    .fold(false, |acc, &e| acc ^ self.var_messages[e]);
+                for &e in edges.iter() {
+                    self.check_messages[e] = parity ^ self.var_messages[e];
+                }
+            }
+            for v in 0..self.channel.len() {
+                let edges = self.graph.var_edges(v);
+                let degree = edges.len();
+                let channel = self.channel[v];
+                let disagree = self.check_messages[edges.clone()]
+                    .iter()
+                    .filter(|&&b| b != channel)
+                    .count();
+                // Majority of the other degree - 1 messages by default
+                let threshold = algorithm.threshold.unwrap_or(degree.div_ceil(2));
+                for e in edges {
+                    let disagree_others = disagree - usize::from(self.check_messages[e] != channel);
+                    self.var_messages[e] = if disagree_others >= threshold.max(1) {
+                        !channel
+                    } else {
+                        channel
+                    };
+                }
+                // Majority vote of the channel bit and all the check messages
+                self.decisions[v] = if disagree > degree + 1 - disagree {
+                    !channel
+                } else {
+                    channel
+                };
+            }
+            if self.graph.check_parity(&self.decisions, |x| x) {
+                return Ok(iteration);
+            }
+        }
+        Err(max_iterations)
+    }
+
+    fn weighted_bit_flipping(
+        &mut self,
+        algorithm: &WeightedBitFlipping,
+        max_iterations: usize,
+    ) -> Result<usize, usize> {
+        for c in 0..self.graph.num_checks() {
+            let vars = self.graph.check_neighbours(c);
+            self.syndrome[c] = vars.iter().fold(false, |acc, &v| acc ^ self.decisions[v]);
+            self.check_weights[c] = vars
+                .iter()
+                .map(|&v| self.reliabilities[v])
+                .fold(f64::INFINITY, f64::min);
+        }
+        for v in 0..self.channel.len() {
+            self.metrics[v] = self
+                .graph
+                .var_neighbours(v)
+                .iter()
+                .map(|&c| self.check_metric(c))
+                .sum::<f64>()
+                - algorithm.alpha * self.reliabilities[v];
+        }
+        for iteration in 1..=max_iterations {
+            // Flip the bit with the largest metric
+            let Some(flip) = (0..self.metrics.len()).max_by(|&a, &b| {
+                self.metrics[a]
+                    .total_cmp(&self.metrics[b])
+                    .then_with(|| b.cmp(&a))
+            }) else {
+                break;
+            };
+            self.decisions[flip] = !self.decisions[flip];
+            for &c in self.graph.var_neighbours(flip) {
+                let old = self.check_metric(c);
+                self.syndrome[c] = !self.syndrome[c];
+                let delta = self.check_metric(c) - old;
+                for &v in self.graph.check_neighbours(c) {
+                    self.metrics[v] += delta;
+                }
+            }
+            if self.syndrome.iter().all(|&s| !s) {
+                return Ok(iteration);
+            }
+        }
+        Err(max_iterations)
+    }
+
+    // Contribution of a check node to the flipping metric of its bits
+    fn check_metric(&self, check: usize) -> f64 {
+        if self.syndrome[check] {
+            self.check_weights[check]
+        } else {
+            -self.check_weights[check]
+        }
+    }
+}
+
+impl LdpcDecoder for Decoder {
+    fn decode(
+        &mut self,
+        llrs: &[f64],
+        max_iterations: usize,
+    ) -> Result<DecoderOutput, DecoderOutput> {
+        Decoder::decode(self, llrs, max_iterations)
+    }
+
+    fn decode_into(
+        &mut self,
+        llrs: &[f64],
+        out_codeword: &mut [u8],
+        max_iterations: usize,
+    ) -> Result<usize, usize> {
+        Decoder::decode_into(self, llrs, out_codeword, max_iterations)
+    }
+
+    fn decode_strided(
+        &mut self,
+        llrs: StridedLlrs<'_>,
+        out_codeword: &mut [u8],
+        max_iterations: usize,
+    ) -> Result<usize, usize> {
+        Decoder::decode_from(self, &llrs, out_codeword, max_iterations)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::codes::examples::ExampleCode;
+
+    fn test_h() -> SparseMatrix {
+        // Example 2.5 in Sarah J. Johnson - Iterative Error Correction
+        ExampleCode::Johnson2_5.h()
+    }
+
+    fn to_llrs(bits: &[u8]) -> Vec<f64> {
+        bits.iter()
+            .map(|&b| if b == 0 { 3.0 } else { -3.0 })
+            .collect()
+    }
+
+    #[test]
+    fn no_errors() {
+        for algorithm in [
+            Algorithm::from(GallagerB::new()),
+            WeightedBitFlipping::new(0.0).into(),
+        ] {
+            let mut decoder = Decoder::new(test_h(), algorithm);
+            let codeword = [0, 0, 1, 0, 1, 1];
+            let DecoderOutput {
+                codeword: decoded,
+                iterations,
+                ..
+            } = decoder.decode(&to_llrs(&codeword), 100).unwrap();
+            assert_eq!(&decoded, &codeword);
+            assert_eq!(iterations, 0);
+        }
+    }
+
+    #[test]
+    fn weighted_bit_flipping_single_error() {
+        let mut decoder = Decoder::new(test_h(), WeightedBitFlipping::new(0.5));
+        let codeword_good = [0, 0, 1, 0, 1, 1];
+        for j in 0..codeword_good.len() {
+            let mut llrs = to_llrs(&codeword_good);
+            // The wrong bit is the least reliable one
+            llrs[j] *= -0.5;
+            let DecoderOutput {
+                codeword: decoded,
+                iterations,
+                ..
+            } = decoder.decode(&llrs, 10).unwrap();
+            assert_eq!(&decoded, &codeword_good);
+            assert_eq!(iterations, 1);
+        }
+    }
+
+    #[test]
+    fn gallager_b_single_error() {
+        // Column weight 3 code, in which Gallager-B corrects any single error:
+        // the parity check matrix of the (7, 3) simplex code dual, with each
+        // bit in 3 checks.
+        let mut h = SparseMatrix::new(7, 7);
+        for j in 0..7 {
+            h.insert_row(j, [j, (j + 1) % 7, (j + 3) % 7].iter());
+        }
+        let mut decoder = Decoder::new(h, GallagerB::new());
+        let codeword_good = [0; 7];
+        for j in 0..codeword_good.len() {
+            let mut codeword_bad = codeword_good;
+            codeword_bad[j] ^= 1;
+            let DecoderOutput {
+                codeword: decoded, ..
+            } = decoder.decode(&to_llrs(&codeword_bad), 10).unwrap();
+            assert_eq!(&decoded, &codeword_good);
+        }
+    }
+}
@@ -3,10 +3,19 @@
 //! This module contains routines to build an LDPC decoder generically over
 //! different internal implementations. Such decoders are represented by
 //! `Box<dyn LdpcDecoder>`, using the trait [`LdpcDecoder`].
+//!
+//! The function [`DecoderImplementation::recommend`] can be used to choose a
+//! suitable decoder implementation for a particular code.
 
-use super::{arithmetic::*, flooding, horizontal_layered, LdpcDecoder};
+use super::{
+    arithmetic::*,
+    batch::{self, LdpcBatchDecoder},
+    bit_flipping, flooding, flooding_parallel,
+    graph::CompiledGraph,
+    horizontal_layered, stochastic, vertical_layered, LdpcDecoder,
+};
 use crate::sparse::SparseMatrix;
-use std::fmt::Display;
+use std::{fmt::Display, sync::Arc};
 
 /// Decoder factory.
 ///
@@ -15,18 +24,89 @@ use std::fmt::Display;
 /// implemented by the user in order to run a BER test with an LDPC decoder
 /// implemented externally to ldpc-toolbox (such decoder must be wrapped as a
 /// `Box <dyn LdpcDecoder>`).
+///
+/// Implementors must implement [`DecoderFactory::build_decoder`]. They should
+/// also implement [`DecoderFactory::build_graph_decoder`] if their decoders
+/// can share the compiled Tanner graph, since its default implementation
+/// builds each decoder from a copy of the parity check matrix.
 pub trait DecoderFactory: Display + Clone + Sync + Send + 'static {
     /// Builds and LDPC decoder.
     ///
     /// Given a parity check matrix, this function builds an LDPC decoder
     /// corresponding to this decoder implementation.
     fn build_decoder(&self, h: SparseMatrix) -> Box<dyn LdpcDecoder>;
+
+    /// Builds an LDPC decoder from a compiled Tanner graph.
+    ///
+    /// Given the compiled Tanner graph of a code, this function builds an LDPC
+    /// decoder corresponding to this decoder implementation. The graph is
+    /// shared by all the decoders built for the same code, so decoders should
+    /// keep the `Arc` rather than copying the graph or its parity check
+    /// matrix, which can be obtained with [`CompiledGraph::h`]. The default
+    /// implementation calls [`DecoderFactory::build_decoder`] with a copy of
+    /// the parity check matrix.
+    fn build_graph_decoder(&self, graph: Arc<CompiledGraph>) -> Box<dyn LdpcDecoder> {
+        self.build_decoder(SparseMatrix::clone(graph.h()))
+    }
+
+    /// Builds an LDPC decoder with a given seed.
+    ///
+    /// This function is used instead of
+    /// [`DecoderFactory::build_graph_decoder`] when the results of the decoder
+    /// need to be reproducible, as in a BER test. The `seed` is used to
+    /// initialize the random number generator of the decoders that make random
+    /// choices, such as the [`Stochastic`](DecoderImplementation::Stochastic)
+    /// decoder. The default implementation ignores the seed and calls
+    /// [`DecoderFactory::build_graph_decoder`].
+    fn build_seeded_decoder(&self, graph: Arc<CompiledGraph>, seed: u64) -> Box<dyn LdpcDecoder> {
+        let _ = seed;
+        self.build_graph_decoder(graph)
+    }
+
+    /// Builds an LDPC batch decoder.
+    ///
+    /// This function builds a decoder that decodes several codewords with a
+    /// single call (see [`LdpcBatchDecoder`]). The `seed` has the same meaning
+    /// as in [`DecoderFactory::build_seeded_decoder`]. The default
+    /// implementation wraps the decoder given by
+    /// [`DecoderFactory::build_seeded_decoder`] in a [`batch::Sequential`]
+    /// adapter. The implementation for [`DecoderImplementation`] uses a
+    /// [`batch::MinSumi8`] decoder for the
+    /// [`NormMinSumi8`](DecoderImplementation::NormMinSumi8) and
+    /// [`OffsetMinSumi8`](DecoderImplementation::OffsetMinSumi8)
+    /// implementations.
+    fn build_batch_decoder(
+        &self,
+        graph: Arc<CompiledGraph>,
+        seed: u64,
+    ) -> Box<dyn LdpcBatchDecoder> {
+        Box::new(batch::Sequential::new(
+            self.build_seeded_decoder(graph, seed),
+        ))
+    }
+
+    /// Returns the recommended decoder for a code.
+    ///
+    /// Given the parity check matrix of a code and the Eb/N0's (in dB) at
+    /// which it will be simulated, this function returns a suitable decoder
+    /// for the code, or `None` if the factory cannot make a recommendation.
+    ///
+    /// The default implementation returns `None`. The implementation for
+    /// [`DecoderImplementation`] uses [`DecoderImplementation::recommend`].
+    fn recommended(_h: &SparseMatrix, _ebn0s_db: &[f32]) -> Option<Self> {
+        None
+    }
 }
 
 /// LDPC decoder implementation.
 ///
 /// This enum lists the LDPC decoder implementations corresponding to different
 /// arithmetic rules.
+///
+/// The min-sum implementations are parametrized by their scaling factor or
+/// offset, which is given after a hyphen in their string representation (for
+/// instance, `NMS-0.75`). The parameters are stored as a [`Parameter`], so
+/// that the enum implements `Eq` and `Hash`.
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
 pub enum DecoderImplementation {
     /// The [`Phif64`] implementation, using `f64` and the involution
@@ -139,6 +219,32 @@ pub enum DecoderImplementation {
     /// nodes, partial hard-limiting for check nodes, and degree-1 variable node
     /// clipping. This uses a flooding schedule.
     Aminstari8JonesPartialHardLimitDeg1Clip,
+    /// The [`Referencef64`] implementation, a slow reference using `f64` with
+    /// compensated summation, intended to quantify the numerical error of the
+    /// other implementations. This uses a flooding schedule.
+    Referencef64,
+    /// The [`NormMinSumf64`] implementation, using `f64` and the normalized
+    /// min-sum rule with the given scaling factor. This uses a flooding
+    /// schedule.
+    NormMinSumf64(Parameter),
+    /// The [`NormMinSumf32`] implementation, using `f32` and the normalized
+    /// min-sum rule with the given scaling factor. This uses a flooding
+    /// schedule.
+    NormMinSumf32(Parameter),
+    /// The [`NormMinSumi8`] implementation, using 8-bit quantization and the
+    /// normalized min-sum rule with the given scaling factor. This uses a
+    /// flooding schedule.
+    NormMinSumi8(Parameter),
+    /// The [`OffsetMinSumf64`] implementation, using `f64` and the offset
+    /// min-sum rule with the given offset. This uses a flooding schedule.
+    OffsetMinSumf64(Parameter),
+    /// The [`OffsetMinSumf32`] implementation, using `f32` and the offset
+    /// min-sum rule with the given offset. This uses a flooding schedule.
+    OffsetMinSumf32(Parameter),
+    /// The [`OffsetMinSumi8`] implementation, using 8-bit quantization and the
+    /// offset min-sum rule with the given offset. This uses a flooding
+    /// schedule.
+    OffsetMinSumi8(Parameter),
     /// The [`Phif64`] implementation, using `f64` and the involution
     /// `phi(x)`. This uses a horizontal layered schedule.
     HLPhif64,
@@ -183,33 +289,398 @@ pub enum DecoderImplementation {
     /// (implemented using small table lookup), and partial hard-limiting for
     /// check nodes. This uses a horizontal layered schedule.
     HLAminstari8PartialHardLimit,
+    /// The [`Referencef64`] implementation, a slow reference using `f64` with
+    /// compensated summation, intended to quantify the numerical error of the
+    /// other implementations. This uses a horizontal layered schedule.
+    HLReferencef64,
+    /// The [`NormMinSumf64`] implementation, using `f64` and the normalized
+    /// min-sum rule with the given scaling factor. This uses a horizontal
+    /// layered schedule.
+    HLNormMinSumf64(Parameter),
+    /// The [`NormMinSumf32`] implementation, using `f32` and the normalized
+    /// min-sum rule with the given scaling factor. This uses a horizontal
+    /// layered schedule.
+    HLNormMinSumf32(Parameter),
+    /// The [`NormMinSumi8`] implementation, using 8-bit quantization and the
+    /// normalized min-sum rule with the given scaling factor. This uses a
+    /// horizontal layered schedule.
+    HLNormMinSumi8(Parameter),
+    /// The [`OffsetMinSumf64`] implementation, using `f64` and the offset
+    /// min-sum rule with the given offset. This uses a horizontal layered
+    /// schedule.
+    HLOffsetMinSumf64(Parameter),
+    /// The [`OffsetMinSumf32`] implementation, using `f32` and the offset
+    /// min-sum rule with the given offset. This uses a horizontal layered
+    /// schedule.
+    HLOffsetMinSumf32(Parameter),
+    /// The [`OffsetMinSumi8`] implementation, using 8-bit quantization and the
+    /// offset min-sum rule with the given offset. This uses a horizontal
+    /// layered schedule.
+    HLOffsetMinSumi8(Parameter),
+    /// The [`Phif64`] implementation, using `f64` and the involution
+    /// `phi(x)`. This uses a flooding schedule with the
+    /// [`flooding_parallel::Decoder`] parallel decoder.
+    ParPhif64,
+    /// The [`Phif32`] implementation, using `f32` and the involution
+    /// `phi(x)`. This uses a flooding schedule with the
+    /// [`flooding_parallel::Decoder`] parallel decoder.
+    ParPhif32,
+    /// The [`Aminstarf32`] implementation, using `f32` and an approximation to
+    /// the min* function. This uses a flooding schedule with the
+    /// [`flooding_parallel::Decoder`] parallel decoder.
+    ParAminstarf32,
+    /// The [`Aminstari8`] implementation, using 8-bit quantization and a
+    /// quantized approximation to the min* function (implemented using small
+    /// table lookup). This uses a flooding schedule with the
+    /// [`flooding_parallel::Decoder`] parallel decoder.
+    ParAminstari8,
+    /// The [`Aminstari8Deg1Clip`] implementation, using 8-bit quantization, a
+    /// quantized approximation to the min* function (implemented using small
+    /// table lookup), and degree-1 variable node clipping. This uses a flooding
+    /// schedule with the [`flooding_parallel::Decoder`] parallel decoder.
+    ParAminstari8Deg1Clip,
+    /// The [`Phif64`] implementation, using `f64` and the involution
+    /// `phi(x)`. This uses a vertical layered schedule.
+    VLPhif64,
+    /// The [`Phif32`] implementation, using `f32` and the involution
+    /// `phi(x)`. This uses a vertical layered schedule.
+    VLPhif32,
+    /// The [`Aminstarf64`] implementation, using `f64` and an approximation to
+    /// the min* function. This uses a vertical layered schedule.
+    VLAminstarf64,
+    /// The [`Aminstarf32`] implementation, using `f32` and an approximation to
+    /// the min* function. This uses a vertical layered schedule.
+    VLAminstarf32,
+    /// The [`Aminstari8`] implementation, using 8-bit quantization and a
+    /// quantized approximation to the min* function (implemented using small
+    /// table lookup). This uses a vertical layered schedule.
+    VLAminstari8,
+    /// The [`Aminstari8Deg1Clip`] implementation, using 8-bit quantization, a
+    /// quantized approximation to the min* function (implemented using small
+    /// table lookup), and degree-1 variable node clipping. This uses a
+    /// vertical layered schedule.
+    VLAminstari8Deg1Clip,
+    /// The [`NormMinSumf64`] implementation, using `f64` and the normalized
+    /// min-sum rule with the given scaling factor. This uses a vertical
+    /// layered schedule.
+    VLNormMinSumf64(Parameter),
+    /// The [`NormMinSumi8`] implementation, using 8-bit quantization and the
+    /// normalized min-sum rule with the given scaling factor. This uses a
+    /// vertical layered schedule.
+    VLNormMinSumi8(Parameter),
+    /// The [`stochastic::Decoder`] bit-serial stochastic decoder with the
+    /// default [`stochastic::Config`]. Each decoding cycle counts as an
+    /// iteration.
+    Stochastic,
+    /// The [`bit_flipping::Decoder`] hard-decision decoder with the
+    /// [`bit_flipping::GallagerB`] algorithm.
+    GallagerB,
+    /// The [`bit_flipping::Decoder`] hard-decision decoder with the
+    /// [`bit_flipping::WeightedBitFlipping`] algorithm. Each bit flip counts as
+    /// an iteration.
+    WeightedBitFlipping,
+    /// The [`bit_flipping::Decoder`] hard-decision decoder with the modified
+    /// [`bit_flipping::WeightedBitFlipping`] algorithm, using the given weight
+    /// for the channel reliabilities. Each bit flip counts as an iteration.
+    ModifiedWeightedBitFlipping(Parameter),
+}
+
+/// Parameter of a decoder implementation.
+///
+/// This contains the scaling factor, offset or weight of a parametrized
+/// [`DecoderImplementation`]. It wraps an `f64`, but it implements `Eq` and
+/// `Hash` by comparing the bit patterns of the values.
+///
+/// # Examples
+/// ```
+/// # use ldpc_toolbox::decoder::factory::{DecoderImplementation, Parameter};
+/// let factor = Parameter::from(0.75);
+/// assert_eq!(factor.value(), 0.75);
+/// assert_eq!(
+///     "NMS-0.75".parse::<DecoderImplementation>(),
+///     Ok(DecoderImplementation::NormMinSumf64(factor))
+/// );
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Parameter(f64);
+
+impl Parameter {
+    /// Returns the value of the parameter.
+    pub fn value(self) -> f64 {
+        self.0
+    }
+}
+
+impl From<f64> for Parameter {
+    fn from(value: f64) -> Parameter {
+        Parameter(value)
+    }
+}
+
+impl PartialEq for Parameter {
+    fn eq(&self, other: &Parameter) -> bool {
+        self.0.to_bits() == other.0.to_bits()
+    }
+}
+
+impl Eq for Parameter {}
+
+impl std::hash::Hash for Parameter {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.0.to_bits().hash(state);
+    }
 }
 
+impl Display for Parameter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Decoder recommendation.
+///
+/// This is returned by [`DecoderImplementation::recommend`]. It contains the
+/// recommended decoder implementation and the reasons for choosing it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recommendation {
+    /// Recommended decoder implementation.
+    pub implementation: DecoderImplementation,
+    /// Reasons for the recommendation, written as short sentences.
+    pub reasons: Vec<String>,
+}
+
+impl DecoderImplementation {
+    // Codes with fewer edges than this are decoded with exact belief
+    // propagation, since decoding speed is not a concern.
+    const SMALL_CODE_EDGES: usize = 4096;
+    // The error of the table-based 8-bit min* approximation accumulates with
+    // the check node degree.
+    const I8_MAX_CHECK_DEGREE: usize = 20;
+    // Maximum average channel LLR magnitude for 8-bit decoders. The 8-bit
+    // decoders saturate LLRs at 127/8, so above this average a large fraction
+    // of the channel LLRs is clipped.
+    const I8_MAX_AVERAGE_LLR: f64 = 8.0;
+
+    /// Recommends a decoder implementation for a code.
+    ///
+    /// The recommendation is based on the size of the code, its degree
+    /// distributions, and the maximum Eb/N0 (in dB) at which it will be
+    /// simulated, which determines the magnitude of the channel LLRs. The
+    /// channel LLRs are estimated assuming BPSK modulation and the design rate
+    /// of the code. If `ebn0s_db` is empty, the channel LLRs are not taken into
+    /// account.
+    ///
+    /// The rules are the following:
+    ///
+    /// - Small codes are decoded with [`HLPhif64`](DecoderImplementation::HLPhif64),
+    ///   which implements exact belief propagation.
+    ///
+    /// - Otherwise, the 8-bit [`HLAminstari8`](DecoderImplementation::HLAminstari8)
+    ///   decoder is used, since it is the fastest. If the code has variable
+    ///   nodes of degree one, the
+    ///   [`Aminstari8Deg1Clip`](DecoderImplementation::Aminstari8Deg1Clip)
+    ///   decoder is used instead.
+    ///
+    /// - 8-bit decoders are avoided if the code has check nodes of high degree,
+    ///   since the error of the 8-bit min* approximation accumulates with the
+    ///   degree, or if the channel LLRs are large enough to saturate
+    ///   frequently. In these cases
+    ///   [`HLAminstarf32`](DecoderImplementation::HLAminstarf32) is used.
+    ///
+    /// # Examples
+    /// ```
+    /// # use ldpc_toolbox::{
+    /// #     codes::{reference::ReferenceCode, Code},
+    /// #     decoder::factory::DecoderImplementation,
+    /// # };
+    /// let h = ReferenceCode::Hamming7_4.h();
+    /// let recommendation = DecoderImplementation::recommend(&h, &[]);
+    /// assert_eq!(recommendation.implementation, DecoderImplementation::HLPhif64);
+    /// ```
+    pub fn recommend(h: &SparseMatrix, ebn0s_db: &[f32]) -> Recommendation {
+        let edges = h.iter_all().count();
+        if edges < Self::SMALL_CODE_EDGES {
+            return Recommendation {
+                implementation: DecoderImplementation::HLPhif64,
+                reasons: vec![format!(
+                    "The code is small ({edges} edges), so exact belief propagation \
+                     with a layered schedule is used."
+                )],
+            };
+        }
+        let mut reasons = Vec::new();
+        let max_check_degree = (0..h.num_rows())
+            .map(|r| h.row_weight(r))
+            .max()
+            .unwrap_or(0);
+        let mut use_i8 = true;
+        if max_check_degree > Self::I8_MAX_CHECK_DEGREE {
+            use_i8 = false;
+            reasons.push(format!(
+                "The maximum check node degree is {max_check_degree}, which is too \
+                 large for the 8-bit min* approximation."
+            ));
+        }
+        if let Some(max_ebn0_db) = ebn0s_db.iter().copied().reduce(f32::max) {
+            let rate = (h.num_cols() - h.num_rows()) as f64 / h.num_cols() as f64;
+            // The average channel LLR for BPSK is 4 Es/N0.
+            let average_llr = 4.0 * rate * 10.0_f64.powf(0.1 * f64::from(max_ebn0_db));
+            if average_llr > Self::I8_MAX_AVERAGE_LLR {
+                use_i8 = false;
+                reasons.push(format!(
+                    "At Eb/N0 = {max_ebn0_db:.2} dB the average channel LLR is \
+                     {average_llr:.1}, which saturates 8-bit LLRs frequently."
+                ));
+            }
+        }
+        if !use_i8 {
+            reasons.push("A 32-bit floating point decoder with a layered schedule is used.".into());
+            return Recommendation {
+                implementation: DecoderImplementation::HLAminstarf32,
+                reasons,
+            };
+        }
+        reasons.push("The 8-bit decoders are the fastest and suitable for this code.".into());
+        if (0..h.num_cols()).any(|c| h.col_weight(c) == 1) {
+            reasons.push(
+                "The code has variable nodes of degree 1, which require \
+                 degree-1 clipping (only available with a flooding schedule)."
+                    .into(),
+            );
+            Recommendation {
+                implementation: DecoderImplementation::Aminstari8Deg1Clip,
+                reasons,
+            }
+        } else {
+            Recommendation {
+                implementation: DecoderImplementation::HLAminstari8,
+                reasons,
+            }
+        }
+    }
+}
+
+// Valid scaling factors for the normalized min-sum implementations.
+fn valid_factor(factor: f64) -> bool {
+    factor > 0.0 && factor <= 1.0
+}
+
+// Valid offsets for the offset min-sum implementations.
+fn valid_offset(offset: f64) -> bool {
+    offset >= 0.0 && offset.is_finite()
+}
+
+// Valid channel reliability weights for the modified weighted bit flipping
+// implementation.
+fn valid_weight(weight: f64) -> bool {
+    weight >= 0.0 && weight.is_finite()
+}
+
+// The seed is only used by the stochastic decoder.
 macro_rules! new_decoder {
-    (flooding, $arith:ty, $h:expr) => {
-        flooding::Decoder::new($h, <$arith>::new())
+    (flooding, $arith:ty, $h:expr; $seed:expr) => {
+        flooding::Decoder::from_graph($h, <$arith>::new())
+    };
+    (flooding_parallel, $arith:ty, $h:expr; $seed:expr) => {
+        flooding_parallel::Decoder::from_graph($h, <$arith>::new())
+    };
+    (horizontal_layered, $arith:ty, $h:expr; $seed:expr) => {
+        horizontal_layered::Decoder::from_graph($h, <$arith>::new())
+    };
+    (vertical_layered, $arith:ty, $h:expr; $seed:expr) => {
+        vertical_layered::Decoder::from_graph($h, <$arith>::new())
+    };
+    (stochastic, $config:ty, $h:expr; $seed:expr) => {
+        stochastic::Decoder::from_graph($h, <$config>::default(), $seed)
+    };
+    (bit_flipping, $algorithm:ty, $h:expr; $seed:expr) => {
+        bit_flipping::Decoder::from_graph($h, <$algorithm>::default())
     };
-    (horizontal_layered, $arith:ty, $h:expr) => {
-        horizontal_layered::Decoder::new($h, <$arith>::new())
+    (flooding, $arith:ty, $h:expr, $param:expr) => {
+        flooding::Decoder::from_graph($h, <$arith>::new($param))
+    };
+    (horizontal_layered, $arith:ty, $h:expr, $param:expr) => {
+        horizontal_layered::Decoder::from_graph($h, <$arith>::new($param))
+    };
+    (vertical_layered, $arith:ty, $h:expr, $param:expr) => {
+        vertical_layered::Decoder::from_graph($h, <$arith>::new($param))
+    };
+    (bit_flipping, $algorithm:ty, $h:expr, $param:expr) => {
+        bit_flipping::Decoder::from_graph($h, <$algorithm>::new($param))
     };
 }
 
 macro_rules! impl_decoderimplementation {
-    ($($var:path, $arith:ty, $decoder:tt, $text:expr);+;) => {
+    (
+        $($var:path, $arith:ty, $decoder:tt, $text:expr);+;
+        @parametrized
+        $($pvar:path, $parith:ty, $pdecoder:tt, $ptext:expr, $check:expr);+;
+    ) => {
         impl DecoderFactory for DecoderImplementation {
             fn build_decoder(&self, h: SparseMatrix) -> Box<dyn LdpcDecoder> {
+                self.build_graph_decoder(Arc::new(CompiledGraph::new(h)))
+            }
+
+            fn build_graph_decoder(&self, graph: Arc<CompiledGraph>) -> Box<dyn LdpcDecoder> {
+                self.build_seeded_decoder(graph, rand::random())
+            }
+
+            fn build_seeded_decoder(
+                &self,
+                graph: Arc<CompiledGraph>,
+                seed: u64,
+            ) -> Box<dyn LdpcDecoder> {
                 match self {
                     $(
-                        $var => Box::new(new_decoder!($decoder, $arith, h)),
+                        $var => Box::new(new_decoder!($decoder, $arith, graph; seed)),
+                    )+
+                    $(
+                        $pvar(param) => {
+                            Box::new(new_decoder!($pdecoder, $parith, graph, param.value()))
+                        }
                     )+
                 }
             }
+
+            fn build_batch_decoder(
+                &self,
+                graph: Arc<CompiledGraph>,
+                seed: u64,
+            ) -> Box<dyn LdpcBatchDecoder> {
+                match *self {
+                    DecoderImplementation::NormMinSumi8(factor) => {
+                        Box::new(batch::MinSumi8::<32>::normalized(graph, factor.value()))
+                    }
+                    DecoderImplementation::OffsetMinSumi8(offset) => {
+                        Box::new(batch::MinSumi8::<32>::offset(graph, offset.value()))
+                    }
+                    _ => Box::new(batch::Sequential::new(
+                        self.build_seeded_decoder(graph, seed),
+                    )),
+                }
+            }
+
+            fn recommended(h: &SparseMatrix, ebn0s_db: &[f32]) -> Option<Self> {
+                Some(DecoderImplementation::recommend(h, ebn0s_db).implementation)
+            }
         }
 
         impl std::str::FromStr for DecoderImplementation {
             type Err = &'static str;
 
             fn from_str(s: &str) -> Result<Self, Self::Err> {
+                if let Some((name, param)) = s.split_once('-') {
+                    let param = param
+                        .parse::<f64>()
+                        .map_err(|_| "invalid decoder implementation parameter")?;
+                    return Ok(match name {
+                        $(
+                            $ptext if $check(param) => $pvar(param.into()),
+                            $ptext => return Err("decoder implementation parameter out of range"),
+                        )+
+                        _ => return Err("invalid decoder implementation"),
+                    });
+                }
                 Ok(match s {
                     $(
                         $text => $var,
@@ -221,15 +692,14 @@ macro_rules! impl_decoderimplementation {
 
         impl Display for DecoderImplementation {
             fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
-                write!(
-                    f,
-                    "{}",
-                    match self {
-                        $(
-                            $var => $text,
-                        )+
-                    }
-                )
+                match self {
+                    $(
+                        $var => write!(f, "{}", $text),
+                    )+
+                    $(
+                        $pvar(param) => write!(f, "{}-{}", $ptext, param),
+                    )+
+                }
             }
         }
     }
@@ -260,6 +730,7 @@ impl_decoderimplementation!(
     DecoderImplementation::Aminstari8JonesDeg1Clip, Aminstari8JonesDeg1Clip, flooding, "Aminstari8JonesDeg1Clip";
     DecoderImplementation::Aminstari8PartialHardLimitDeg1Clip, Aminstari8PartialHardLimitDeg1Clip, flooding, "Aminstari8PartialHardLimitDeg1Clip";
     DecoderImplementation::Aminstari8JonesPartialHardLimitDeg1Clip, Aminstari8JonesPartialHardLimitDeg1Clip, flooding, "Aminstari8JonesPartialHardLimitDeg1Clip";
+    DecoderImplementation::Referencef64, Referencef64, flooding, "Referencef64";
     DecoderImplementation::HLPhif64, Phif64, horizontal_layered, "HLPhif64";
     DecoderImplementation::HLPhif32, Phif32, horizontal_layered, "HLPhif32";
     DecoderImplementation::HLTanhf64, Tanhf64, horizontal_layered, "HLTanhf64";
@@ -272,4 +743,170 @@ impl_decoderimplementation!(
     DecoderImplementation::HLAminstarf32, Aminstarf32, horizontal_layered, "HLAminstarf32";
     DecoderImplementation::HLAminstari8, Aminstari8, horizontal_layered, "HLAminstari8";
     DecoderImplementation::HLAminstari8PartialHardLimit, Aminstari8, horizontal_layered, "HLAminstari8PartialHardLimit";
+    DecoderImplementation::HLReferencef64, Referencef64, horizontal_layered, "HLReferencef64";
+    DecoderImplementation::ParPhif64, Phif64, flooding_parallel, "ParPhif64";
+    DecoderImplementation::ParPhif32, Phif32, flooding_parallel, "ParPhif32";
+    DecoderImplementation::ParAminstarf32, Aminstarf32, flooding_parallel, "ParAminstarf32";
+    DecoderImplementation::ParAminstari8, Aminstari8, flooding_parallel, "ParAminstari8";
+    DecoderImplementation::ParAminstari8Deg1Clip, Aminstari8Deg1Clip, flooding_parallel, "ParAminstari8Deg1Clip";
+    DecoderImplementation::VLPhif64, Phif64, vertical_layered, "VLPhif64";
+    DecoderImplementation::VLPhif32, Phif32, vertical_layered, "VLPhif32";
+    DecoderImplementation::VLAminstarf64, Aminstarf64, vertical_layered, "VLAminstarf64";
+    DecoderImplementation::VLAminstarf32, Aminstarf32, vertical_layered, "VLAminstarf32";
+    DecoderImplementation::VLAminstari8, Aminstari8, vertical_layered, "VLAminstari8";
+    DecoderImplementation::VLAminstari8Deg1Clip, Aminstari8Deg1Clip, vertical_layered, "VLAminstari8Deg1Clip";
+    DecoderImplementation::Stochastic, stochastic::Config, stochastic, "Stochastic";
+    DecoderImplementation::GallagerB, bit_flipping::GallagerB, bit_flipping, "GallagerB";
+    DecoderImplementation::WeightedBitFlipping, bit_flipping::WeightedBitFlipping, bit_flipping, "WBF";
+    @parametrized
+    DecoderImplementation::NormMinSumf64, NormMinSumf64, flooding, "NMS", valid_factor;
+    DecoderImplementation::NormMinSumf32, NormMinSumf32, flooding, "NMSf32", valid_factor;
+    DecoderImplementation::NormMinSumi8, NormMinSumi8, flooding, "NMSi8", valid_factor;
+    DecoderImplementation::OffsetMinSumf64, OffsetMinSumf64, flooding, "OMS", valid_offset;
+    DecoderImplementation::OffsetMinSumf32, OffsetMinSumf32, flooding, "OMSf32", valid_offset;
+    DecoderImplementation::OffsetMinSumi8, OffsetMinSumi8, flooding, "OMSi8", valid_offset;
+    DecoderImplementation::HLNormMinSumf64, NormMinSumf64, horizontal_layered, "HLNMS", valid_factor;
+    DecoderImplementation::HLNormMinSumf32, NormMinSumf32, horizontal_layered, "HLNMSf32", valid_factor;
+    DecoderImplementation::HLNormMinSumi8, NormMinSumi8, horizontal_layered, "HLNMSi8", valid_factor;
+    DecoderImplementation::HLOffsetMinSumf64, OffsetMinSumf64, horizontal_layered, "HLOMS", valid_offset;
+    DecoderImplementation::HLOffsetMinSumf32, OffsetMinSumf32, horizontal_layered, "HLOMSf32", valid_offset;
+    DecoderImplementation::HLOffsetMinSumi8, OffsetMinSumi8, horizontal_layered, "HLOMSi8", valid_offset;
+    DecoderImplementation::VLNormMinSumf64, NormMinSumf64, vertical_layered, "VLNMS", valid_factor;
+    DecoderImplementation::VLNormMinSumi8, NormMinSumi8, vertical_layered, "VLNMSi8", valid_factor;
+    DecoderImplementation::ModifiedWeightedBitFlipping, bit_flipping::WeightedBitFlipping, bit_flipping, "MWBF", valid_weight;
 );
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::codes::ccsds::{AR4JACode, AR4JAInfoSize, AR4JARate, C2Code};
+
+    #[test]
+    fn recommend() {
+        let h = AR4JACode::new(AR4JARate::R1_2, AR4JAInfoSize::K1024).h();
+        assert_eq!(
+            DecoderImplementation::recommend(&h, &[1.0, 2.0]).implementation,
+            DecoderImplementation::Aminstari8Deg1Clip
+        );
+        assert_eq!(
+            DecoderImplementation::recommend(&h, &[8.0]).implementation,
+            DecoderImplementation::HLAminstarf32
+        );
+        // The C2 code has check nodes of degree 32
+        let h = C2Code::new().h();
+        assert_eq!(
+            DecoderImplementation::recommend(&h, &[]).implementation,
+            DecoderImplementation::HLAminstarf32
+        );
+    }
+
+    #[test]
+    fn min_sum_parameters() {
+        for (s, implementation) in [
+            (
+                "NMS-0.75",
+                DecoderImplementation::NormMinSumf64(0.75.into()),
+            ),
+            (
+                "HLOMSi8-0.5",
+                DecoderImplementation::HLOffsetMinSumi8(0.5.into()),
+            ),
+            (
+                "VLNMS-0.75",
+                DecoderImplementation::VLNormMinSumf64(0.75.into()),
+            ),
+            (
+                "MWBF-0.25",
+                DecoderImplementation::ModifiedWeightedBitFlipping(0.25.into()),
+            ),
+        ] {
+            assert_eq!(s.parse::<DecoderImplementation>(), Ok(implementation));
+            assert_eq!(implementation.to_string(), s);
+        }
+        assert!("NMS-1.5".parse::<DecoderImplementation>().is_err());
+        assert!("OMS--0.5".parse::<DecoderImplementation>().is_err());
+        assert!("NMS-x".parse::<DecoderImplementation>().is_err());
+        assert!("Phif64-0.5".parse::<DecoderImplementation>().is_err());
+        // The parametrized implementations can be used as keys
+        let keys = ["NMS-0.75", "NMS-0.8", "NMS-0.75", "NMSf32-0.75"]
+            .iter()
+            .map(|s| s.parse::<DecoderImplementation>().unwrap())
+            .collect::<std::collections::HashSet<_>>();
+        assert_eq!(keys.len(), 3);
+    }
+
+    #[test]
+    fn sparse_matrix_factory() {
+        // A factory that only implements build_decoder can be used to build
+        // decoders from a compiled graph
+        #[derive(Debug, Clone)]
+        struct Factory;
+
+        impl Display for Factory {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "Factory")
+            }
+        }
+
+        impl DecoderFactory for Factory {
+            fn build_decoder(&self, h: SparseMatrix) -> Box<dyn LdpcDecoder> {
+                Box::new(flooding::Decoder::new(h, Aminstarf32::new()))
+            }
+        }
+
+        let h = AR4JACode::new(AR4JARate::R1_2, AR4JAInfoSize::K1024).h();
+        let graph = Arc::new(CompiledGraph::new(h));
+        let mut decoder = Factory.build_seeded_decoder(graph, 0);
+        let mut llrs = vec![4.0; 2560];
+        llrs[7] = -2.0;
+        let output = decoder.decode(&llrs, 10).unwrap();
+        assert!(output.codeword.iter().all(|&b| b == 0));
+    }
+
+    #[test]
+    fn parallel_decoders() {
+        let h = AR4JACode::new(AR4JARate::R1_2, AR4JAInfoSize::K1024).h();
+        let graph = Arc::new(CompiledGraph::new(h));
+        let implementation = "ParAminstari8".parse::<DecoderImplementation>().unwrap();
+        assert_eq!(implementation, DecoderImplementation::ParAminstari8);
+        assert_eq!(implementation.to_string(), "ParAminstari8");
+        let mut decoder = implementation.build_graph_decoder(graph);
+        let llrs = vec![1.0; 2560];
+        let output = decoder.decode(&llrs, 10).unwrap();
+        assert_eq!(output.iterations, 0);
+        assert!(output.codeword.iter().all(|&b| b == 0));
+    }
+
+    #[test]
+    fn batch_decoders() {
+        let h = AR4JACode::new(AR4JARate::R1_2, AR4JAInfoSize::K1024).h();
+        let graph = Arc::new(CompiledGraph::new(h));
+        for (implementation, lanes) in [
+            (DecoderImplementation::NormMinSumi8(0.75.into()), 32),
+            (DecoderImplementation::OffsetMinSumi8(0.5.into()), 32),
+            (DecoderImplementation::Aminstari8, 1),
+        ] {
+            let mut decoder = implementation.build_batch_decoder(Arc::clone(&graph), 0);
+            assert_eq!(decoder.lanes(), lanes);
+            let llrs = vec![1.0; 3 * 2560];
+            let mut out = vec![1; llrs.len()];
+            assert_eq!(decoder.decode_batch(&llrs, 3, &mut out, 10), &[Ok(0); 3]);
+            assert!(out.iter().all(|&b| b == 0));
+        }
+    }
+
+    #[test]
+    fn vertical_layered_decoders() {
+        let h = AR4JACode::new(AR4JARate::R1_2, AR4JAInfoSize::K1024).h();
+        let graph = Arc::new(CompiledGraph::new(h));
+        let implementation = "VLAminstari8".parse::<DecoderImplementation>().unwrap();
+        assert_eq!(implementation, DecoderImplementation::VLAminstari8);
+        assert_eq!(implementation.to_string(), "VLAminstari8");
+        let mut decoder = implementation.build_graph_decoder(graph);
+        let mut llrs = vec![4.0; 2560];
+        llrs[7] = -2.0;
+        let output = decoder.decode(&llrs, 10).unwrap();
+        assert!(output.codeword.iter().all(|&b| b == 0));
+        assert_eq!(output.corrected_bits, 1);
+    }
+}
@@ -3,39 +3,141 @@
 //! This module implements a generice belief propagation LDPC decoder with a
 //! flooding message passing schedule.
 
+#[cfg(feature = "profiling")]
+use super::DecoderCounters;
 use super::{
-    arithmetic::DecoderArithmetic, check_llrs, hard_decisions, DecoderOutput, LdpcDecoder, Messages,
+    arithmetic::{CheckMessageScaling, DecoderArithmetic, NodeContext},
+    decoder_output,
+    graph::CompiledGraph,
+    source_llrs,
+    trace::{Direction, IterationTrace, LlrTrace, MessageTrace, TracedMessage},
+    update_max_magnitude, write_hard_decisions, DecoderOutput, LdpcDecoder, LlrSource, Messages,
+    StridedLlrs,
 };
 use crate::sparse::SparseMatrix;
+use std::sync::Arc;
 
 /// LDPC belief propagation flooding decoder.
 #[derive(Debug, Clone, PartialEq)]
 pub struct Decoder<A: DecoderArithmetic> {
     arithmetic: A,
-    h: SparseMatrix,
+    graph: Arc<CompiledGraph>,
     input_llrs: Box<[A::Llr]>,
     output_llrs: Box<[A::Llr]>,
     check_messages: Messages<A::CheckMessage>,
     variable_messages: Messages<A::VarMessage>,
+    edge_scaling: Option<EdgeScaling<A>>,
+    llr_magnitude_tracking: bool,
+    max_llr_magnitude: Option<f64>,
+    message_tracing: bool,
+    message_trace: MessageTrace,
+    iteration_tracing: bool,
+    iteration_trace: IterationTrace,
+    llr_tracing: bool,
+    llr_trace: LlrTrace,
+    #[cfg(feature = "profiling")]
+    counters: DecoderCounters,
+    decoded: bool,
+}
+
+// Scaling of the check messages sent through a subset of the edges.
+#[derive(Debug, Clone)]
+struct EdgeScaling<A: DecoderArithmetic> {
+    // Indexed by the edge numbers of the CompiledGraph
+    scaled: Box<[bool]>,
+    factor: f64,
+    scale: fn(A::CheckMessage, f64) -> A::CheckMessage,
+}
+
+impl<A: DecoderArithmetic> PartialEq for EdgeScaling<A> {
+    fn eq(&self, other: &Self) -> bool {
+        // The scale function is always A::scale_check_message, so it does not
+        // need to be compared.
+        self.scaled == other.scaled && self.factor == other.factor
+    }
 }
 
 impl<A: DecoderArithmetic> Decoder<A> {
     /// Creates a new flooding LDPC decoder.
     ///
-    /// The parameter `h` indicates the parity check matrix.
-    pub fn new(h: SparseMatrix, arithmetic: A) -> Self {
-        let input_llrs = vec![Default::default(); h.num_cols()].into_boxed_slice();
+    /// The parameter `h` indicates the parity check matrix. It can be given
+    /// either as a [`SparseMatrix`] or as an `Arc<SparseMatrix>`. In the latter
+    /// case, the matrix is shared with other users of the `Arc`, such as other
+    /// decoders for the same code, instead of being copied.
+    pub fn new(h: impl Into<Arc<SparseMatrix>>, arithmetic: A) -> Self {
+        Decoder::from_graph(Arc::new(CompiledGraph::new(h)), arithmetic)
+    }
+
+    /// Creates a new flooding LDPC decoder from a compiled Tanner graph.
+    ///
+    /// The graph is shared with the other decoders built from the same `Arc`.
+    pub fn from_graph(graph: Arc<CompiledGraph>, arithmetic: A) -> Self {
+        let input_llrs = vec![Default::default(); graph.num_vars()].into_boxed_slice();
         let output_llrs = input_llrs.clone();
-        let check_messages = Messages::from_iter((0..h.num_cols()).map(|c| h.iter_col(c)));
-        let variable_messages = Messages::from_iter((0..h.num_rows()).map(|r| h.iter_row(r)));
+        let check_messages =
+            Messages::from_iter((0..graph.num_vars()).map(|v| graph.var_neighbours(v).iter()));
+        let variable_messages =
+            Messages::from_iter((0..graph.num_checks()).map(|c| graph.check_neighbours(c).iter()));
         Decoder {
             arithmetic,
-            h,
+            graph,
             input_llrs,
             output_llrs,
             check_messages,
             variable_messages,
+            edge_scaling: None,
+            llr_magnitude_tracking: false,
+            max_llr_magnitude: None,
+            message_tracing: false,
+            message_trace: MessageTrace::new(),
+            iteration_tracing: false,
+            iteration_trace: IterationTrace::new(),
+            llr_tracing: false,
+            llr_trace: LlrTrace::new(),
+            #[cfg(feature = "profiling")]
+            counters: DecoderCounters::default(),
+            decoded: false,
+        }
+    }
+
+    /// Creates a new flooding LDPC decoder with scaling on short cycles.
+    ///
+    /// The check node messages sent through the edges that belong to cycles of
+    /// length smaller or equal than `max_cycle_length` are multiplied by
+    /// `factor`. The remaining messages are not modified. Typically
+    /// `max_cycle_length` is 4 or 6, and `factor` is smaller than one, which
+    /// reduces the overconfidence of the messages that are correlated due to
+    /// these short cycles.
+    ///
+    /// Only the scaling of the messages is supported. Damping of the messages
+    /// (mixing them with the messages of the previous iteration) is not
+    /// implemented.
+    pub fn with_short_cycle_scaling(
+        h: impl Into<Arc<SparseMatrix>>,
+        arithmetic: A,
+        max_cycle_length: usize,
+        factor: f64,
+    ) -> Self
+    where
+        A: CheckMessageScaling,
+    {
+        let mut decoder = Decoder::new(h, arithmetic);
+        let graph = &decoder.graph;
+        let mut scaled = vec![false; graph.num_edges()].into_boxed_slice();
+        for (row, col) in graph.h().short_cycle_edges(max_cycle_length) {
+            let j = graph
+                .var_neighbours(col)
+                .iter()
+                .position(|&c| c == row)
+                .expect("edge not found in column");
+            scaled[graph.var_edges(col).start + j] = true;
         }
+        decoder.edge_scaling = Some(EdgeScaling {
+            scaled,
+            factor,
+            scale: A::scale_check_message,
+        });
+        decoder
     }
 
     /// Decodes a codeword.
@@ -53,62 +155,162 @@ impl<A: DecoderArithmetic> Decoder<A> {
         llrs: &[f64],
         max_iterations: usize,
     ) -> Result<DecoderOutput, DecoderOutput> {
+        decoder_output(self, llrs, |decoder, out| {
+            decoder.decode_into(llrs, out, max_iterations)
+        })
+    }
+
+    /// Decodes a codeword into a buffer provided by the caller.
+    ///
+    /// See [`LdpcDecoder::decode_into`]. This function does not allocate any
+    /// memory.
+    pub fn decode_into(
+        &mut self,
+        llrs: &[f64],
+        out_codeword: &mut [u8],
+        max_iterations: usize,
+    ) -> Result<usize, usize> {
+        self.decode_from(llrs, out_codeword, max_iterations)
+    }
+
+    /// Decodes a codeword from an LLR source into a buffer provided by the
+    /// caller.
+    ///
+    /// This is similar to [`Decoder::decode_into`], but the LLRs are read
+    /// from an [`LlrSource`], such as a [`StridedLlrs`] view, without copying
+    /// them first.
+    pub fn decode_from<L: LlrSource + ?Sized>(
+        &mut self,
+        llrs: &L,
+        out_codeword: &mut [u8],
+        max_iterations: usize,
+    ) -> Result<usize, usize> {
         assert_eq!(llrs.len(), self.input_llrs.len());
+        self.max_llr_magnitude = None;
+        self.message_trace.clear();
+        self.iteration_trace.clear();
+        self.llr_trace.clear();
+        #[cfg(feature = "profiling")]
+        {
+            self.counters = DecoderCounters::default();
+        }
         let input_llrs_hard_decision = |x| x <= 0.0;
-        if check_llrs(&self.h, llrs, input_llrs_hard_decision) {
+        if self
+            .graph
+            .check_parity_by(|v| input_llrs_hard_decision(llrs.llr(v)))
+        {
             // No bit errors case
-            return Ok(DecoderOutput {
-                codeword: hard_decisions(llrs, input_llrs_hard_decision),
-                iterations: 0,
-            });
+            if self.llr_magnitude_tracking {
+                update_max_magnitude(&mut self.max_llr_magnitude, source_llrs(llrs), |x| {
+                    self.arithmetic
+                        .llr_magnitude(self.arithmetic.input_llr_quantize(x))
+                });
+            }
+            for (x, y) in self.output_llrs.iter_mut().zip(source_llrs(llrs)) {
+                *x = self.arithmetic.input_llr_quantize(y);
+            }
+            self.trace_iteration(0, false);
+            self.decoded = true;
+            write_hard_decisions(source_llrs(llrs), out_codeword, input_llrs_hard_decision);
+            return Ok(0);
         }
         self.initialize(llrs);
+        self.decoded = true;
+        self.trace_variable_messages(0);
+        self.trace_iteration(0, true);
+        if self.llr_magnitude_tracking {
+            update_max_magnitude(
+                &mut self.max_llr_magnitude,
+                self.input_llrs.iter().copied(),
+                |x| self.arithmetic.llr_magnitude(x),
+            );
+        }
         for iteration in 1..=max_iterations {
-            self.process_check_nodes();
-            self.process_variable_nodes();
-            if check_llrs(&self.h, &self.output_llrs, |x| {
-                self.arithmetic.llr_hard_decision(x)
-            }) {
+            self.process_check_nodes(iteration);
+            self.trace_check_messages(iteration);
+            self.process_variable_nodes(iteration);
+            self.trace_variable_messages(iteration);
+            self.trace_iteration(iteration, false);
+            #[cfg(feature = "profiling")]
+            {
+                // Each check node and each variable node sends a message
+                // through each of its edges
+                let edges = self.graph.num_edges() as u64;
+                self.counters += DecoderCounters {
+                    check_node_updates: self.graph.num_checks() as u64,
+                    variable_node_updates: self.graph.num_vars() as u64,
+                    messages: 2 * edges,
+                };
+            }
+            if self.llr_magnitude_tracking {
+                update_max_magnitude(
+                    &mut self.max_llr_magnitude,
+                    self.output_llrs.iter().copied(),
+                    |x| self.arithmetic.llr_magnitude(x),
+                );
+            }
+            if self
+                .graph
+                .check_parity(&self.output_llrs, |x| self.arithmetic.llr_hard_decision(x))
+            {
                 // Decode succeeded
-                return Ok(DecoderOutput {
-                    codeword: hard_decisions(&self.output_llrs, |x| {
-                        self.arithmetic.llr_hard_decision(x)
-                    }),
-                    iterations: iteration,
+                write_hard_decisions(self.output_llrs.iter().copied(), out_codeword, |x| {
+                    self.arithmetic.llr_hard_decision(x)
                 });
+                return Ok(iteration);
             }
         }
         // Decode failed
-        Err(DecoderOutput {
-            codeword: hard_decisions(&self.output_llrs, |x| self.arithmetic.llr_hard_decision(x)),
-            iterations: max_iterations,
-        })
+        write_hard_decisions(self.output_llrs.iter().copied(), out_codeword, |x| {
+            self.arithmetic.llr_hard_decision(x)
+        });
+        Err(max_iterations)
     }
 
-    fn initialize(&mut self, llrs: &[f64]) {
-        for (x, &y) in self.input_llrs.iter_mut().zip(llrs.iter()) {
+    fn initialize<L: LlrSource + ?Sized>(&mut self, llrs: &L) {
+        for (x, y) in self.input_llrs.iter_mut().zip(source_llrs(llrs)) {
             *x = self.arithmetic.input_llr_quantize(y)
         }
 
         // First variable messages use only input LLRs
         for (v, &llr) in self.input_llrs.iter().enumerate() {
-            for &c in self.h.iter_col(v) {
+            for &c in self.graph.var_neighbours(v) {
                 self.variable_messages
                     .send(v, c, self.arithmetic.llr_to_var_message(llr));
             }
         }
+        #[cfg(feature = "profiling")]
+        {
+            self.counters.messages += self.graph.num_edges() as u64;
+        }
     }
 
-    fn process_check_nodes(&mut self) {
+    fn process_check_nodes(&mut self, iteration: usize) {
         for (c, messages) in self.variable_messages.per_destination.iter().enumerate() {
-            self.arithmetic.send_check_messages(messages, {
+            let context = NodeContext::new(iteration, messages.len()).with_node(c);
+            self.arithmetic.send_check_messages(messages, context, {
                 let check_messages = &mut self.check_messages;
                 move |msg| check_messages.send(c, msg.dest, msg.value)
             });
         }
+        self.scale_check_messages();
     }
 
-    fn process_variable_nodes(&mut self) {
+    fn scale_check_messages(&mut self) {
+        let Some(scaling) = self.edge_scaling.as_ref() else {
+            return;
+        };
+        // The messages arriving to each variable node are listed in the same
+        // order as its edges
+        for (v, messages) in self.check_messages.per_destination.iter_mut().enumerate() {
+            let scaled = &scaling.scaled[self.graph.var_edges(v)];
+            for (msg, _) in messages.iter_mut().zip(scaled).filter(|(_, &s)| s) {
+                msg.value = (scaling.scale)(msg.value, scaling.factor);
+            }
+        }
+    }
+
+    fn process_variable_nodes(&mut self, iteration: usize) {
         for (((v, messages), output_llr), &input_llr) in self
             .check_messages
             .per_destination
@@ -117,10 +319,75 @@ impl<A: DecoderArithmetic> Decoder<A> {
             .zip(self.output_llrs.iter_mut())
             .zip(self.input_llrs.iter())
         {
-            *output_llr = self.arithmetic.send_var_messages(input_llr, messages, {
-                let var_messages = &mut self.variable_messages;
-                move |msg| var_messages.send(v, msg.dest, msg.value)
-            });
+            let context = NodeContext::new(iteration, messages.len()).with_node(v);
+            *output_llr = self
+                .arithmetic
+                .send_var_messages(input_llr, messages, context, {
+                    let var_messages = &mut self.variable_messages;
+                    move |msg| var_messages.send(v, msg.dest, msg.value)
+                });
+        }
+    }
+
+    fn trace_iteration(&mut self, iteration: usize, input_llrs: bool) {
+        let llrs = if input_llrs {
+            &self.input_llrs
+        } else {
+            &self.output_llrs
+        };
+        let arithmetic = &self.arithmetic;
+        if self.iteration_tracing {
+            self.iteration_trace.record(
+                iteration,
+                &self.graph,
+                llrs.iter()
+                    .map(|&x| (arithmetic.llr_hard_decision(x), arithmetic.llr_saturated(x))),
+            );
+        }
+        if self.llr_tracing {
+            self.llr_trace.record(
+                &self.graph,
+                llrs.iter()
+                    .map(|&x| (arithmetic.llr_hard_decision(x), arithmetic.llr_magnitude(x))),
+            );
+        }
+    }
+
+    fn trace_check_messages(&mut self, iteration: usize) {
+        if !self.message_tracing {
+            return;
+        }
+        for (v, messages) in self.check_messages.per_destination.iter().enumerate() {
+            for msg in messages.iter() {
+                if let Some(value) = self.arithmetic.check_message_value(msg.value) {
+                    self.message_trace.push(TracedMessage {
+                        iteration,
+                        direction: Direction::CheckToVar,
+                        check: msg.source,
+                        var: v,
+                        value,
+                    });
+                }
+            }
+        }
+    }
+
+    fn trace_variable_messages(&mut self, iteration: usize) {
+        if !self.message_tracing {
+            return;
+        }
+        for (c, messages) in self.variable_messages.per_destination.iter().enumerate() {
+            for msg in messages.iter() {
+                if let Some(value) = self.arithmetic.var_message_value(msg.value) {
+                    self.message_trace.push(TracedMessage {
+                        iteration,
+                        direction: Direction::VarToCheck,
+                        check: c,
+                        var: msg.source,
+                        value,
+                    });
+                }
+            }
         }
     }
 }
@@ -133,20 +400,95 @@ impl<A: DecoderArithmetic> LdpcDecoder for Decoder<A> {
     ) -> Result<DecoderOutput, DecoderOutput> {
         Decoder::decode(self, llrs, max_iterations)
     }
+
+    fn decode_into(
+        &mut self,
+        llrs: &[f64],
+        out_codeword: &mut [u8],
+        max_iterations: usize,
+    ) -> Result<usize, usize> {
+        Decoder::decode_into(self, llrs, out_codeword, max_iterations)
+    }
+
+    fn decode_strided(
+        &mut self,
+        llrs: StridedLlrs<'_>,
+        out_codeword: &mut [u8],
+        max_iterations: usize,
+    ) -> Result<usize, usize> {
+        Decoder::decode_from(self, &llrs, out_codeword, max_iterations)
+    }
+
+    fn set_llr_magnitude_tracking(&mut self, enabled: bool) {
+        self.llr_magnitude_tracking = enabled;
+    }
+
+    fn max_llr_magnitude(&self) -> Option<f64> {
+        self.max_llr_magnitude
+    }
+
+    fn set_message_tracing(&mut self, enabled: bool) {
+        self.message_tracing = enabled;
+    }
+
+    fn message_trace(&self) -> Option<&MessageTrace> {
+        if self.message_tracing {
+            Some(&self.message_trace)
+        } else {
+            None
+        }
+    }
+
+    fn set_iteration_tracing(&mut self, enabled: bool) {
+        self.iteration_tracing = enabled;
+    }
+
+    fn iteration_trace(&self) -> Option<&IterationTrace> {
+        if self.iteration_tracing {
+            Some(&self.iteration_trace)
+        } else {
+            None
+        }
+    }
+
+    fn set_llr_tracing(&mut self, enabled: bool) {
+        self.llr_tracing = enabled;
+    }
+
+    fn llr_trace(&self) -> Option<&LlrTrace> {
+        if self.llr_tracing && self.llr_trace.is_complete() {
+            Some(&self.llr_trace)
+        } else {
+            None
+        }
+    }
+
+    fn bit_reliabilities(&self) -> Option<Vec<f64>> {
+        if !self.decoded {
+            return None;
+        }
+        self.output_llrs
+            .iter()
+            .map(|&x| self.arithmetic.llr_magnitude(x))
+            .collect()
+    }
+
+    #[cfg(feature = "profiling")]
+    fn update_counters(&self) -> Option<DecoderCounters> {
+        Some(self.counters)
+    }
 }
 
 #[cfg(test)]
 mod test {
     use super::super::arithmetic::Phif64;
+    use super::super::{trace::IterationStatistics, FailureReason};
     use super::*;
+    use crate::codes::examples::ExampleCode;
 
     fn test_decoder() -> Decoder<Phif64> {
         // Example 2.5 in Sarah J. Johnson - Iterative Error Correction
-        let mut h = SparseMatrix::new(4, 6);
-        h.insert_row(0, [0, 1, 3].iter());
-        h.insert_row(1, [1, 2, 4].iter());
-        h.insert_row(2, [0, 4, 5].iter());
-        h.insert_row(3, [2, 3, 5].iter());
+        let h = ExampleCode::Johnson2_5.h();
         Decoder::new(h, Phif64::new())
     }
 
@@ -166,9 +508,79 @@ mod test {
         let DecoderOutput {
             codeword: decoded,
             iterations,
+            corrected_bits,
+            ..
         } = decoder.decode(&to_llrs(&codeword), max_iter).unwrap();
         assert_eq!(&decoded, &codeword);
         assert_eq!(iterations, 0);
+        assert_eq!(corrected_bits, 0);
+    }
+
+    #[test]
+    fn single_error_short_cycle_scaling() {
+        let test_decoder = test_decoder();
+        let mut decoder = Decoder::with_short_cycle_scaling(
+            Arc::clone(test_decoder.graph.h()),
+            Phif64::new(),
+            6,
+            0.8,
+        );
+        let codeword_good = [0, 0, 1, 0, 1, 1];
+        for j in 0..codeword_good.len() {
+            let mut codeword_bad = codeword_good;
+            codeword_bad[j] ^= 1;
+            let DecoderOutput {
+                codeword: decoded, ..
+            } = decoder.decode(&to_llrs(&codeword_bad), 100).unwrap();
+            assert_eq!(&decoded, &codeword_good);
+        }
+    }
+
+    #[test]
+    fn short_cycle_scaling_messages() {
+        // The edges of checks 0 and 1 with variables 0 and 1 form a 4-cycle
+        let mut h = SparseMatrix::new(3, 4);
+        h.insert_row(0, [0, 1, 2].iter());
+        h.insert_row(1, [0, 1].iter());
+        h.insert_row(2, [2, 3].iter());
+        let h = Arc::new(h);
+        let max_cycle_length = 4;
+        let factor = 0.5;
+        let short_cycle_edges = h.short_cycle_edges(max_cycle_length);
+        let mut plain = Decoder::new(Arc::clone(&h), Phif64::new());
+        let mut scaled = Decoder::with_short_cycle_scaling(
+            Arc::clone(&h),
+            Phif64::new(),
+            max_cycle_length,
+            factor,
+        );
+        // After one iteration, the check node messages of both decoders are
+        // computed from the same variable node messages
+        let llrs = [1.3, -0.2, -2.1, 0.4];
+        let _ = plain.decode(&llrs, 1);
+        let _ = scaled.decode(&llrs, 1);
+        let (mut num_scaled, mut num_unscaled) = (0, 0);
+        for (v, (plain, scaled)) in plain
+            .check_messages
+            .per_destination
+            .iter()
+            .zip(scaled.check_messages.per_destination.iter())
+            .enumerate()
+        {
+            for (plain, scaled) in plain.iter().zip(scaled.iter()) {
+                assert_eq!(plain.source, scaled.source);
+                if short_cycle_edges.contains(&(plain.source, v)) {
+                    assert_ne!(plain.value, 0.0);
+                    assert!((scaled.value - factor * plain.value).abs() < 1e-12);
+                    num_scaled += 1;
+                } else {
+                    assert_eq!(scaled.value, plain.value);
+                    num_unscaled += 1;
+                }
+            }
+        }
+        assert!(num_scaled > 0);
+        assert!(num_unscaled > 0);
     }
 
     #[test]
@@ -182,9 +594,228 @@ mod test {
             let DecoderOutput {
                 codeword: decoded,
                 iterations,
+                corrected_bits,
+                ..
             } = decoder.decode(&to_llrs(&codeword_bad), max_iter).unwrap();
             assert_eq!(&decoded, &codeword_good);
             assert_eq!(iterations, 1);
+            assert_eq!(corrected_bits, 1);
+        }
+    }
+
+    #[test]
+    fn decode_into() {
+        let mut decoder = test_decoder();
+        let codeword_good = [0, 0, 1, 0, 1, 1];
+        let mut decoded = [0xff; 6];
+        for j in 0..codeword_good.len() {
+            let mut codeword_bad = codeword_good;
+            codeword_bad[j] ^= 1;
+            let iterations = decoder
+                .decode_into(&to_llrs(&codeword_bad), &mut decoded, 100)
+                .unwrap();
+            assert_eq!(&decoded, &codeword_good);
+            assert_eq!(iterations, 1);
+        }
+    }
+
+    #[test]
+    fn decode_strided() {
+        let mut decoder = test_decoder();
+        let codeword_good = [0, 0, 1, 0, 1, 1];
+        let mut codeword_bad = codeword_good;
+        codeword_bad[3] ^= 1;
+        // Interleave the LLRs of a good and a bad codeword
+        let buffer = to_llrs(&codeword_good)
+            .into_iter()
+            .zip(to_llrs(&codeword_bad))
+            .flat_map(|(a, b)| [a, b])
+            .collect::<Vec<f64>>();
+        let mut decoded = [0xff; 6];
+        for (channel, expected_iterations) in [(0, 0), (1, 1)] {
+            let llrs = StridedLlrs::new(&buffer[channel..], 2);
+            let iterations =
+                LdpcDecoder::decode_strided(&mut decoder, llrs, &mut decoded, 100).unwrap();
+            assert_eq!(&decoded, &codeword_good);
+            assert_eq!(iterations, expected_iterations);
+        }
+    }
+
+    #[test]
+    fn llr_magnitude_tracking() {
+        let mut decoder = test_decoder();
+        let mut codeword = [0, 0, 1, 0, 1, 1];
+        codeword[0] ^= 1;
+        decoder.decode(&to_llrs(&codeword), 100).unwrap();
+        assert_eq!(LdpcDecoder::max_llr_magnitude(&decoder), None);
+        decoder.set_llr_magnitude_tracking(true);
+        decoder.decode(&to_llrs(&codeword), 100).unwrap();
+        let max = LdpcDecoder::max_llr_magnitude(&decoder).unwrap();
+        // After one iteration, the LLRs are larger than the channel LLRs
+        assert!(max > 1.3863);
+    }
+
+    #[cfg(feature = "profiling")]
+    #[test]
+    fn update_counters() {
+        let mut decoder = test_decoder();
+        let mut codeword = [0, 0, 1, 0, 1, 1];
+        let output = decoder.decode(&to_llrs(&codeword), 100).unwrap();
+        assert_eq!(output.counters, Some(DecoderCounters::default()));
+        codeword[0] ^= 1;
+        let output = decoder.decode(&to_llrs(&codeword), 100).unwrap();
+        assert_eq!(output.iterations, 1);
+        // 12 edges, with the variable messages of the initialization and the
+        // check and variable messages of iteration 1
+        assert_eq!(
+            output.counters,
+            Some(DecoderCounters {
+                check_node_updates: decoder.graph.num_checks() as u64,
+                variable_node_updates: 6,
+                messages: 3 * 12,
+            })
+        );
+    }
+
+    #[test]
+    fn least_reliable_bits() {
+        let mut decoder = test_decoder();
+        assert_eq!(LdpcDecoder::least_reliable_bits(&decoder, 2), None);
+        let codeword = [0, 0, 1, 0, 1, 1];
+        let mut llrs = to_llrs(&codeword);
+        llrs[3] = 0.1;
+        llrs[4] = -0.5;
+        decoder.decode(&llrs, 100).unwrap();
+        // Without bit errors the reliabilities are those of the input LLRs
+        assert_eq!(
+            LdpcDecoder::least_reliable_bits(&decoder, 3),
+            Some(vec![3, 4, 0])
+        );
+
+        llrs[0] = -0.2;
+        llrs[1] = -0.3;
+        decoder.decode(&llrs, 1).unwrap_err();
+        let reliabilities = LdpcDecoder::bit_reliabilities(&decoder).unwrap();
+        let least_reliable = LdpcDecoder::least_reliable_bits(&decoder, 6).unwrap();
+        assert!(least_reliable
+            .windows(2)
+            .all(|w| reliabilities[w[0]] <= reliabilities[w[1]]));
+    }
+
+    #[test]
+    fn message_tracing() {
+        let mut decoder = test_decoder();
+        let mut codeword = [0, 0, 1, 0, 1, 1];
+        codeword[0] ^= 1;
+        let llrs = to_llrs(&codeword);
+        decoder.decode(&llrs, 100).unwrap();
+        assert!(LdpcDecoder::message_trace(&decoder).is_none());
+        decoder.set_message_tracing(true);
+        decoder.decode(&llrs, 100).unwrap();
+        let trace = LdpcDecoder::message_trace(&decoder).unwrap();
+        // 12 edges, with the variable messages of iteration 0 and the check
+        // and variable messages of iteration 1
+        assert_eq!(trace.messages().len(), 3 * 12);
+        for msg in &trace.messages()[..12] {
+            assert_eq!(msg.iteration, 0);
+            assert_eq!(msg.direction, Direction::VarToCheck);
+            assert_eq!(msg.value, llrs[msg.var]);
+        }
+        for msg in &trace.messages()[12..24] {
+            assert_eq!(msg.iteration, 1);
+            assert_eq!(msg.direction, Direction::CheckToVar);
+        }
+        for msg in &trace.messages()[24..] {
+            assert_eq!(msg.iteration, 1);
+            assert_eq!(msg.direction, Direction::VarToCheck);
+        }
+    }
+
+    #[test]
+    fn iteration_tracing() {
+        let mut decoder = test_decoder();
+        let codeword = [0, 0, 1, 0, 1, 1];
+        let mut llrs = to_llrs(&codeword);
+        decoder.decode(&llrs, 100).unwrap();
+        assert!(LdpcDecoder::iteration_trace(&decoder).is_none());
+        decoder.set_iteration_tracing(true);
+        decoder.decode(&llrs, 100).unwrap();
+        let trace = LdpcDecoder::iteration_trace(&decoder).unwrap();
+        assert_eq!(trace.iterations(), [IterationStatistics::default()]);
+
+        llrs[0] = -llrs[0];
+        decoder.decode(&llrs, 100).unwrap();
+        let trace = LdpcDecoder::iteration_trace(&decoder).unwrap();
+        let unsatisfied_checks = decoder.graph.var_neighbours(0).len();
+        assert_eq!(
+            trace.iterations(),
+            [
+                IterationStatistics {
+                    iteration: 0,
+                    unsatisfied_checks,
+                    flipped_bits: 0,
+                    saturated_llrs: 0,
+                },
+                IterationStatistics {
+                    iteration: 1,
+                    unsatisfied_checks: 0,
+                    flipped_bits: 1,
+                    saturated_llrs: 0,
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn try_decode() {
+        let mut decoder = test_decoder();
+        let codeword_good = [0, 0, 1, 0, 1, 1];
+        let mut llrs = to_llrs(&codeword_good);
+        llrs[0] = -llrs[0];
+        let output = decoder.try_decode(&llrs, 100).unwrap();
+        assert_eq!(&output.codeword, &codeword_good);
+        // Iteration tracing is only enabled during the decoding
+        assert!(LdpcDecoder::iteration_trace(&decoder).is_none());
+
+        let failure = decoder.try_decode(&llrs, 0).unwrap_err();
+        assert_eq!(failure.reason, FailureReason::MaxIterations);
+        assert_eq!(failure.output.iterations, 0);
+
+        llrs[1] = f64::NAN;
+        let failure = decoder.try_decode(&llrs, 100).unwrap_err();
+        assert_eq!(failure.reason, FailureReason::NumericalFailure);
+
+        // These bit errors form a state in which the hard decisions never
+        // change
+        let h = ExampleCode::Gallager20_8.h();
+        let mut llrs = vec![1.3863; h.num_cols()];
+        for j in [0, 1, 9] {
+            llrs[j] = -llrs[j];
+        }
+        let mut decoder = Decoder::new(h, Phif64::new());
+        decoder.set_iteration_tracing(true);
+        let failure = decoder.try_decode(&llrs, 20).unwrap_err();
+        assert_eq!(failure.reason, FailureReason::Stalled { since: 0 });
+        assert!(LdpcDecoder::iteration_trace(&decoder).is_some());
+        // With few iterations a stall cannot be detected
+        let failure = decoder.try_decode(&llrs, 3).unwrap_err();
+        assert_eq!(failure.reason, FailureReason::MaxIterations);
+    }
+
+    #[test]
+    fn shared_matrix() {
+        let h = Arc::clone(test_decoder().graph.h());
+        let mut decoders = (0..2)
+            .map(|_| Decoder::new(Arc::clone(&h), Phif64::new()))
+            .collect::<Vec<_>>();
+        assert_eq!(Arc::strong_count(&h), 3);
+        let mut codeword = [0, 0, 1, 0, 1, 1];
+        codeword[2] ^= 1;
+        for decoder in decoders.iter_mut() {
+            let DecoderOutput {
+                codeword: decoded, ..
+            } = decoder.decode(&to_llrs(&codeword), 100).unwrap();
+            assert_eq!(&decoded, &[0, 0, 1, 0, 1, 1]);
         }
     }
 }
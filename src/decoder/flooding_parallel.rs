@@ -0,0 +1,580 @@
+//! Parallel LDPC decoder with flooding schedule.
+//!
+//! This module implements a belief propagation LDPC decoder with a flooding
+//! message passing schedule that processes the check nodes and the variable
+//! nodes of each iteration in parallel using [rayon](https://docs.rs/rayon).
+//! It gives the same results as the serial [flooding
+//! decoder](super::flooding::Decoder), but it decodes a single codeword of a
+//! long code, such as the DVB-S2 normal FECFRAME codes, several times faster.
+//!
+//! The check nodes and the variable nodes are partitioned into blocks of
+//! consecutive nodes with a similar number of edges, and each block is processed
+//! by a rayon task with its own copy of the [`DecoderArithmetic`]. The messages
+//! are stored in per-edge buffers, so that each block writes to a contiguous
+//! part of the buffers. Codes with few edges use a single block, since the
+//! overhead of the parallel tasks would exceed the gain.
+//!
+//! The BER test already decodes different frames in parallel, so this decoder
+//! is most useful to reduce the latency of decoding a single codeword. Without
+//! the `rayon` feature, the blocks are processed sequentially.
+
+#[cfg(feature = "profiling")]
+use super::DecoderCounters;
+use super::{
+    arithmetic::{DecoderArithmetic, NodeContext},
+    decoder_output,
+    graph::CompiledGraph,
+    source_llrs, update_max_magnitude, write_hard_decisions, DecoderOutput, LdpcDecoder, LlrSource,
+    Message, StridedLlrs,
+};
+use crate::sparse::SparseMatrix;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use std::{ops::Range, sync::Arc};
+
+// Processes the blocks of an iteration, in parallel if the rayon feature is
+// enabled.
+#[cfg(feature = "rayon")]
+macro_rules! for_each_block {
+    ($blocks:expr, $f:expr) => {
+        $blocks.into_par_iter().for_each($f)
+    };
+}
+
+#[cfg(not(feature = "rayon"))]
+macro_rules! for_each_block {
+    ($blocks:expr, $f:expr) => {
+        $blocks.into_iter().for_each($f)
+    };
+}
+
+/// LDPC belief propagation parallel flooding decoder.
+#[derive(Debug, Clone)]
+pub struct Decoder<A: DecoderArithmetic> {
+    graph: Arc<CompiledGraph>,
+    workers: Box<[Worker<A>]>,
+    // Blocks of check nodes and of variable nodes. Block j is processed by
+    // worker j.
+    check_blocks: Box<[Range<usize>]>,
+    var_blocks: Box<[Range<usize>]>,
+    // Position in check_messages of the message sent through each edge
+    edge_check_positions: Box<[usize]>,
+    input_llrs: Box<[A::Llr]>,
+    output_llrs: Box<[A::Llr]>,
+    // Check messages, listed by check node in the order of
+    // CompiledGraph::check_neighbours
+    check_messages: Box<[A::CheckMessage]>,
+    // Variable messages, indexed by edge
+    var_messages: Box<[A::VarMessage]>,
+    llr_magnitude_tracking: bool,
+    max_llr_magnitude: Option<f64>,
+    #[cfg(feature = "profiling")]
+    counters: DecoderCounters,
+    decoded: bool,
+}
+
+// Arithmetic and scratch buffers used to process a block of nodes.
+#[derive(Debug, Clone)]
+struct Worker<A: DecoderArithmetic> {
+    arithmetic: A,
+    var_messages: Vec<Message<A::VarMessage>>,
+    check_messages: Vec<Message<A::CheckMessage>>,
+}
+
+impl<A> Decoder<A>
+where
+    A: DecoderArithmetic + Clone,
+    A::CheckMessage: Sync,
+    A::VarMessage: Sync,
+    A::Llr: Sync,
+{
+    // Minimum number of edges per block
+    const MIN_BLOCK_EDGES: usize = 8192;
+
+    /// Creates a new parallel flooding LDPC decoder.
+    ///
+    /// The parameter `h` indicates the parity check matrix. It can be given
+    /// either as a [`SparseMatrix`] or as an `Arc<SparseMatrix>`.
+    ///
+    /// The number of blocks is chosen according to the number of threads of
+    /// the current rayon thread pool.
+    pub fn new(h: impl Into<Arc<SparseMatrix>>, arithmetic: A) -> Self {
+        Decoder::from_graph(Arc::new(CompiledGraph::new(h)), arithmetic)
+    }
+
+    /// Creates a new parallel flooding LDPC decoder from a compiled Tanner
+    /// graph.
+    ///
+    /// The graph is shared with the other decoders built from the same `Arc`.
+    pub fn from_graph(graph: Arc<CompiledGraph>, arithmetic: A) -> Self {
+        #[cfg(feature = "rayon")]
+        let threads = rayon::current_num_threads();
+        #[cfg(not(feature = "rayon"))]
+        let threads = 1;
+        // Use a few blocks per thread to balance the load
+        let num_blocks = (4 * threads)
+            .min(graph.num_edges() / Self::MIN_BLOCK_EDGES)
+            .max(1);
+        Decoder::with_blocks(graph, arithmetic, num_blocks)
+    }
+
+    /// Creates a new parallel flooding LDPC decoder with a given number of
+    /// blocks.
+    ///
+    /// The check nodes and the variable nodes are each partitioned into at
+    /// most `num_blocks` blocks.
+    ///
+    /// # Panics
+    /// This function panics if `num_blocks` is zero.
+    pub fn with_blocks(graph: Arc<CompiledGraph>, arithmetic: A, num_blocks: usize) -> Self {
+        assert!(num_blocks > 0);
+        let check_blocks = partition(
+            (0..graph.num_checks()).map(|c| graph.check_neighbours(c).len()),
+            graph.num_edges(),
+            num_blocks,
+        );
+        let var_blocks = partition(
+            (0..graph.num_vars()).map(|v| graph.var_edges(v).len()),
+            graph.num_edges(),
+            num_blocks,
+        );
+        let num_workers = check_blocks.len().max(var_blocks.len());
+        let workers = (0..num_workers)
+            .map(|_| Worker {
+                arithmetic: arithmetic.clone(),
+                var_messages: Vec::new(),
+                check_messages: Vec::new(),
+            })
+            .collect();
+        let mut edge_check_positions = vec![0; graph.num_edges()].into_boxed_slice();
+        let mut position = 0;
+        for c in 0..graph.num_checks() {
+            for &e in graph.check_edges(c) {
+                edge_check_positions[e] = position;
+                position += 1;
+            }
+        }
+        let input_llrs = vec![Default::default(); graph.num_vars()].into_boxed_slice();
+        let output_llrs = input_llrs.clone();
+        let check_messages = vec![Default::default(); graph.num_edges()].into_boxed_slice();
+        let var_messages = vec![Default::default(); graph.num_edges()].into_boxed_slice();
+        Decoder {
+            graph,
+            workers,
+            check_blocks,
+            var_blocks,
+            edge_check_positions,
+            input_llrs,
+            output_llrs,
+            check_messages,
+            var_messages,
+            llr_magnitude_tracking: false,
+            max_llr_magnitude: None,
+            #[cfg(feature = "profiling")]
+            counters: DecoderCounters::default(),
+            decoded: false,
+        }
+    }
+
+    /// Returns the number of blocks of check nodes and of variable nodes.
+    pub fn num_blocks(&self) -> (usize, usize) {
+        (self.check_blocks.len(), self.var_blocks.len())
+    }
+
+    /// Decodes a codeword.
+    ///
+    /// See [`flooding::Decoder::decode`](super::flooding::Decoder::decode).
+    pub fn decode(
+        &mut self,
+        llrs: &[f64],
+        max_iterations: usize,
+    ) -> Result<DecoderOutput, DecoderOutput> {
+        decoder_output(self, llrs, |decoder, out| {
+            decoder.decode_into(llrs, out, max_iterations)
+        })
+    }
+
+    /// Decodes a codeword into a buffer provided by the caller.
+    ///
+    /// See [`LdpcDecoder::decode_into`].
+    pub fn decode_into(
+        &mut self,
+        llrs: &[f64],
+        out_codeword: &mut [u8],
+        max_iterations: usize,
+    ) -> Result<usize, usize> {
+        self.decode_from(llrs, out_codeword, max_iterations)
+    }
+
+    /// Decodes a codeword from an LLR source into a buffer provided by the
+    /// caller.
+    ///
+    /// This is similar to [`Decoder::decode_into`], but the LLRs are read
+    /// from an [`LlrSource`], such as a [`StridedLlrs`] view, without copying
+    /// them first.
+    pub fn decode_from<L: LlrSource + ?Sized>(
+        &mut self,
+        llrs: &L,
+        out_codeword: &mut [u8],
+        max_iterations: usize,
+    ) -> Result<usize, usize> {
+        assert_eq!(llrs.len(), self.input_llrs.len());
+        self.max_llr_magnitude = None;
+        #[cfg(feature = "profiling")]
+        {
+            self.counters = DecoderCounters::default();
+        }
+        let input_llrs_hard_decision = |x| x <= 0.0;
+        let arithmetic = &self.workers[0].arithmetic;
+        for (x, y) in self.input_llrs.iter_mut().zip(source_llrs(llrs)) {
+            *x = arithmetic.input_llr_quantize(y);
+        }
+        self.decoded = true;
+        if self.llr_magnitude_tracking {
+            update_max_magnitude(
+                &mut self.max_llr_magnitude,
+                self.input_llrs.iter().copied(),
+                |x| arithmetic.llr_magnitude(x),
+            );
+        }
+        if self
+            .graph
+            .check_parity_by(|v| input_llrs_hard_decision(llrs.llr(v)))
+        {
+            // No bit errors case
+            self.output_llrs.copy_from_slice(&self.input_llrs);
+            write_hard_decisions(source_llrs(llrs), out_codeword, input_llrs_hard_decision);
+            return Ok(0);
+        }
+        self.initialize();
+        for iteration in 1..=max_iterations {
+            self.process_check_nodes(iteration);
+            self.process_variable_nodes(iteration);
+            #[cfg(feature = "profiling")]
+            {
+                // Each check node and each variable node sends a message
+                // through each of its edges
+                let edges = self.graph.num_edges() as u64;
+                self.counters += DecoderCounters {
+                    check_node_updates: self.graph.num_checks() as u64,
+                    variable_node_updates: self.graph.num_vars() as u64,
+                    messages: 2 * edges,
+                };
+            }
+            let arithmetic = &self.workers[0].arithmetic;
+            if self.llr_magnitude_tracking {
+                update_max_magnitude(
+                    &mut self.max_llr_magnitude,
+                    self.output_llrs.iter().copied(),
+                    |x| arithmetic.llr_magnitude(x),
+                );
+            }
+            if self
+                .graph
+                .check_parity(&self.output_llrs, |x| arithmetic.llr_hard_decision(x))
+            {
+                // Decode succeeded
+                write_hard_decisions(self.output_llrs.iter().copied(), out_codeword, |x| {
+                    arithmetic.llr_hard_decision(x)
+                });
+                return Ok(iteration);
+            }
+        }
+        // Decode failed
+        let arithmetic = &self.workers[0].arithmetic;
+        write_hard_decisions(self.output_llrs.iter().copied(), out_codeword, |x| {
+            arithmetic.llr_hard_decision(x)
+        });
+        Err(max_iterations)
+    }
+
+    fn initialize(&mut self) {
+        // First variable messages use only input LLRs
+        let arithmetic = &self.workers[0].arithmetic;
+        for (v, &llr) in self.input_llrs.iter().enumerate() {
+            let msg = arithmetic.llr_to_var_message(llr);
+            self.var_messages[self.graph.var_edges(v)].fill(msg);
+        }
+        #[cfg(feature = "profiling")]
+        {
+            self.counters.messages += self.graph.num_edges() as u64;
+        }
+    }
+
+    fn process_check_nodes(&mut self, iteration: usize) {
+        let graph = &*self.graph;
+        let var_messages = &*self.var_messages;
+        let blocks = split_blocks(&mut self.check_messages, &self.check_blocks, |checks| {
+            checks
+                .clone()
+                .map(|c| graph.check_neighbours(c).len())
+                .sum()
+        });
+        let jobs = self
+            .workers
+            .iter_mut()
+            .zip(blocks)
+            .zip(self.check_blocks.iter().cloned())
+            .collect::<Vec<_>>();
+        for_each_block!(jobs, |((worker, mut messages), checks)| {
+            for c in checks {
+                let (this, rest) = messages.split_at_mut(graph.check_neighbours(c).len());
+                messages = rest;
+                worker.var_messages.clear();
+                worker.var_messages.extend(
+                    graph
+                        .check_neighbours(c)
+                        .iter()
+                        .zip(graph.check_edges(c))
+                        .map(|(&v, &e)| Message {
+                            source: v,
+                            value: var_messages[e],
+                        }),
+                );
+                let context = NodeContext::new(iteration, this.len()).with_node(c);
+                let neighbours = graph.check_neighbours(c);
+                worker
+                    .arithmetic
+                    .send_check_messages(&worker.var_messages, context, |msg| {
+                        let j = neighbours
+                            .iter()
+                            .position(|&v| v == msg.dest)
+                            .expect("message for destination not found");
+                        this[j] = msg.value;
+                    });
+            }
+        });
+    }
+
+    fn process_variable_nodes(&mut self, iteration: usize) {
+        let graph = &*self.graph;
+        let check_messages = &*self.check_messages;
+        let edge_check_positions = &*self.edge_check_positions;
+        let input_llrs = &*self.input_llrs;
+        let blocks = split_blocks(&mut self.var_messages, &self.var_blocks, |vars| {
+            vars.clone().map(|v| graph.var_edges(v).len()).sum()
+        });
+        let llr_blocks = split_blocks(&mut self.output_llrs, &self.var_blocks, |vars| vars.len());
+        let jobs = self
+            .workers
+            .iter_mut()
+            .zip(blocks)
+            .zip(llr_blocks)
+            .zip(self.var_blocks.iter().cloned())
+            .collect::<Vec<_>>();
+        for_each_block!(jobs, |(((worker, messages), output_llrs), vars)| {
+            let first_edge = graph.var_edges(vars.start).start;
+            for (v, output_llr) in vars.zip(output_llrs.iter_mut()) {
+                let edges = graph.var_edges(v);
+                worker.check_messages.clear();
+                worker.check_messages.extend(
+                    graph
+                        .var_neighbours(v)
+                        .iter()
+                        .zip(edges.clone())
+                        .map(|(&c, e)| Message {
+                            source: c,
+                            value: check_messages[edge_check_positions[e]],
+                        }),
+                );
+                let context = NodeContext::new(iteration, edges.len()).with_node(v);
+                let neighbours = graph.var_neighbours(v);
+                let this = &mut messages[edges.start - first_edge..edges.end - first_edge];
+                *output_llr = worker.arithmetic.send_var_messages(
+                    input_llrs[v],
+                    &worker.check_messages,
+                    context,
+                    |msg| {
+                        let j = neighbours
+                            .iter()
+                            .position(|&c| c == msg.dest)
+                            .expect("message for destination not found");
+                        this[j] = msg.value;
+                    },
+                );
+            }
+        });
+    }
+}
+
+// Partitions a list of nodes, given by their number of edges, into at most
+// num_blocks blocks of consecutive nodes with a similar number of edges.
+fn partition(
+    degrees: impl Iterator<Item = usize>,
+    num_edges: usize,
+    num_blocks: usize,
+) -> Box<[Range<usize>]> {
+    let target = num_edges.div_ceil(num_blocks).max(1);
+    let mut blocks = Vec::with_capacity(num_blocks);
+    let mut start = 0;
+    let mut edges = 0;
+    let mut end = 0;
+    for degree in degrees {
+        end += 1;
+        edges += degree;
+        if edges >= target {
+            blocks.push(start..end);
+            start = end;
+            edges = 0;
+        }
+    }
+    if start < end || blocks.is_empty() {
+        blocks.push(start..end);
+    }
+    blocks.into_boxed_slice()
+}
+
+// Splits a buffer into the consecutive parts corresponding to some blocks,
+// whose lengths are given by the len function.
+fn split_blocks<'a, T>(
+    mut buffer: &'a mut [T],
+    blocks: &[Range<usize>],
+    len: impl Fn(&Range<usize>) -> usize,
+) -> Vec<&'a mut [T]> {
+    blocks
+        .iter()
+        .map(|block| {
+            let (this, rest) = std::mem::take(&mut buffer).split_at_mut(len(block));
+            buffer = rest;
+            this
+        })
+        .collect()
+}
+
+impl<A> LdpcDecoder for Decoder<A>
+where
+    A: DecoderArithmetic + Clone,
+    A::CheckMessage: Sync,
+    A::VarMessage: Sync,
+    A::Llr: Sync,
+{
+    fn decode(
+        &mut self,
+        llrs: &[f64],
+        max_iterations: usize,
+    ) -> Result<DecoderOutput, DecoderOutput> {
+        Decoder::decode(self, llrs, max_iterations)
+    }
+
+    fn decode_into(
+        &mut self,
+        llrs: &[f64],
+        out_codeword: &mut [u8],
+        max_iterations: usize,
+    ) -> Result<usize, usize> {
+        Decoder::decode_into(self, llrs, out_codeword, max_iterations)
+    }
+
+    fn decode_strided(
+        &mut self,
+        llrs: StridedLlrs<'_>,
+        out_codeword: &mut [u8],
+        max_iterations: usize,
+    ) -> Result<usize, usize> {
+        Decoder::decode_from(self, &llrs, out_codeword, max_iterations)
+    }
+
+    fn set_llr_magnitude_tracking(&mut self, enabled: bool) {
+        self.llr_magnitude_tracking = enabled;
+    }
+
+    fn max_llr_magnitude(&self) -> Option<f64> {
+        self.max_llr_magnitude
+    }
+
+    fn bit_reliabilities(&self) -> Option<Vec<f64>> {
+        if !self.decoded {
+            return None;
+        }
+        let arithmetic = &self.workers[0].arithmetic;
+        self.output_llrs
+            .iter()
+            .map(|&x| arithmetic.llr_magnitude(x))
+            .collect()
+    }
+
+    #[cfg(feature = "profiling")]
+    fn update_counters(&self) -> Option<DecoderCounters> {
+        Some(self.counters)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::{
+        arithmetic::{Aminstari8, Phif64},
+        flooding,
+    };
+    use super::*;
+    use crate::codes::{
+        ccsds::{AR4JACode, AR4JAInfoSize, AR4JARate},
+        examples::ExampleCode,
+    };
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn partition_blocks() {
+        let blocks = partition([3, 3, 3, 3, 3].into_iter(), 15, 2);
+        assert_eq!(&*blocks, &[0..3, 3..5]);
+        let blocks = partition([1, 1].into_iter(), 2, 4);
+        assert_eq!(&*blocks, &[0..1, 1..2]);
+        let blocks = partition(std::iter::empty(), 0, 4);
+        assert_eq!(blocks.len(), 1);
+        assert!(blocks[0].is_empty());
+    }
+
+    fn compare_with_flooding<A>(arithmetic: A)
+    where
+        A: DecoderArithmetic + Clone,
+        A::CheckMessage: Sync,
+        A::VarMessage: Sync,
+        A::Llr: Sync,
+    {
+        let h = Arc::new(AR4JACode::new(AR4JARate::R1_2, AR4JAInfoSize::K1024).h());
+        let graph = Arc::new(CompiledGraph::new(Arc::clone(&h)));
+        let mut serial = flooding::Decoder::from_graph(Arc::clone(&graph), arithmetic.clone());
+        let mut parallel = Decoder::with_blocks(graph, arithmetic, 7);
+        assert_eq!(parallel.num_blocks(), (7, 7));
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        for _ in 0..10 {
+            // All-zeros codeword with noise. The punctured bits have zero LLR.
+            let llrs = (0..h.num_cols())
+                .map(|j| {
+                    if j >= 2048 {
+                        0.0
+                    } else {
+                        2.0 + 2.5 * (rng.gen::<f64>() - 0.5) * 4.0
+                    }
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(serial.decode(&llrs, 20), parallel.decode(&llrs, 20));
+        }
+    }
+
+    #[test]
+    fn same_as_flooding() {
+        compare_with_flooding(Phif64::new());
+        compare_with_flooding(Aminstari8::new());
+    }
+
+    #[test]
+    fn single_error() {
+        // Example 2.5 in Sarah J. Johnson - Iterative Error Correction
+        let h = ExampleCode::Johnson2_5.h();
+        let mut decoder = Decoder::new(h, Phif64::new());
+        assert_eq!(decoder.num_blocks(), (1, 1));
+        let codeword_good = [0, 0, 1, 0, 1, 1];
+        for j in 0..codeword_good.len() {
+            let mut codeword_bad = codeword_good;
+            codeword_bad[j] ^= 1;
+            let llrs = codeword_bad
+                .iter()
+                .map(|&b| if b == 0 { 1.3863 } else { -1.3863 })
+                .collect::<Vec<_>>();
+            let output = decoder.decode(&llrs, 100).unwrap();
+            assert_eq!(&output.codeword, &codeword_good);
+            assert_eq!(output.iterations, 1);
+        }
+    }
+}
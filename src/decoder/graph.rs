@@ -0,0 +1,282 @@
+//! Compiled Tanner graph.
+//!
+//! This module contains [`CompiledGraph`], an immutable representation of the
+//! Tanner graph of an LDPC code that is prepared once from a
+//! [`SparseMatrix`] and then shared, by means of an [`Arc`], by all the
+//! decoders of the same code. It stores the adjacency lists of the check and
+//! variable nodes in flat arrays, as well as a numbering of the edges of the
+//! graph, so that the decoders only need to allocate their message buffers
+//! when they are created.
+//!
+//! The graph also partitions the check nodes into independent layers, which
+//! are sets of check nodes that do not have any variable node in common. If
+//! two check nodes have a variable node in common, the one with the smaller
+//! index belongs to an earlier layer. Therefore, a layered decoder can process
+//! the layers in order, and the check nodes of each layer in any order, or in
+//! parallel, without changing its results.
+
+use crate::sparse::SparseMatrix;
+use std::{ops::Range, sync::Arc};
+
+/// Compiled Tanner graph.
+///
+/// The edges of the graph are numbered in column order. The edges of variable
+/// node `v` are the range of edge indices returned by
+/// [`var_edges`](CompiledGraph::var_edges), listed in the same order as
+/// [`SparseMatrix::iter_col`]. The edges of check node `c` are listed by
+/// [`check_edges`](CompiledGraph::check_edges) in the same order as
+/// [`SparseMatrix::iter_row`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompiledGraph {
+    h: Arc<SparseMatrix>,
+    // var_offsets[v]..var_offsets[v + 1] are the edges of variable node v,
+    // and var_neighbours[e] is the check node of edge e.
+    var_offsets: Box<[usize]>,
+    var_neighbours: Box<[usize]>,
+    // check_offsets[c]..check_offsets[c + 1] are the positions in
+    // check_neighbours and check_edges corresponding to check node c.
+    check_offsets: Box<[usize]>,
+    check_neighbours: Box<[usize]>,
+    check_edges: Box<[usize]>,
+    // layer_offsets[j]..layer_offsets[j + 1] are the positions in
+    // layer_checks corresponding to layer j.
+    layer_offsets: Box<[usize]>,
+    layer_checks: Box<[usize]>,
+}
+
+impl CompiledGraph {
+    /// Compiles the Tanner graph of a parity check matrix.
+    ///
+    /// The parity check matrix can be given either as a [`SparseMatrix`] or as
+    /// an `Arc<SparseMatrix>`.
+    pub fn new(h: impl Into<Arc<SparseMatrix>>) -> CompiledGraph {
+        let h = h.into();
+        let mut var_offsets = Vec::with_capacity(h.num_cols() + 1);
+        let mut var_neighbours = Vec::new();
+        // Edges of each check node, as (variable node, edge) pairs
+        let mut check_lists = vec![Vec::new(); h.num_rows()];
+        for v in 0..h.num_cols() {
+            var_offsets.push(var_neighbours.len());
+            for &c in h.iter_col(v) {
+                check_lists[c].push((v, var_neighbours.len()));
+                var_neighbours.push(c);
+            }
+        }
+        var_offsets.push(var_neighbours.len());
+        let mut check_offsets = Vec::with_capacity(h.num_rows() + 1);
+        let mut check_neighbours = Vec::with_capacity(var_neighbours.len());
+        let mut check_edges = Vec::with_capacity(var_neighbours.len());
+        for (c, list) in check_lists.iter().enumerate() {
+            check_offsets.push(check_neighbours.len());
+            for &v in h.iter_row(c) {
+                let &(_, e) = list
+                    .iter()
+                    .find(|&&(w, _)| w == v)
+                    .expect("edge not found in column");
+                check_neighbours.push(v);
+                check_edges.push(e);
+            }
+        }
+        check_offsets.push(check_neighbours.len());
+        let (layer_offsets, layer_checks) = independent_layers(&h);
+        CompiledGraph {
+            h,
+            var_offsets: var_offsets.into_boxed_slice(),
+            var_neighbours: var_neighbours.into_boxed_slice(),
+            check_offsets: check_offsets.into_boxed_slice(),
+            check_neighbours: check_neighbours.into_boxed_slice(),
+            check_edges: check_edges.into_boxed_slice(),
+            layer_offsets: layer_offsets.into_boxed_slice(),
+            layer_checks: layer_checks.into_boxed_slice(),
+        }
+    }
+
+    /// Returns the parity check matrix.
+    pub fn h(&self) -> &Arc<SparseMatrix> {
+        &self.h
+    }
+
+    /// Returns the number of check nodes.
+    pub fn num_checks(&self) -> usize {
+        self.check_offsets.len() - 1
+    }
+
+    /// Returns the number of variable nodes.
+    pub fn num_vars(&self) -> usize {
+        self.var_offsets.len() - 1
+    }
+
+    /// Returns the number of edges.
+    pub fn num_edges(&self) -> usize {
+        self.var_neighbours.len()
+    }
+
+    /// Returns the check nodes connected to a variable node.
+    pub fn var_neighbours(&self, var: usize) -> &[usize] {
+        &self.var_neighbours[self.var_edges(var)]
+    }
+
+    /// Returns the variable nodes connected to a check node.
+    pub fn check_neighbours(&self, check: usize) -> &[usize] {
+        &self.check_neighbours[self.check_range(check)]
+    }
+
+    /// Returns the edges of a variable node.
+    pub fn var_edges(&self, var: usize) -> Range<usize> {
+        self.var_offsets[var]..self.var_offsets[var + 1]
+    }
+
+    /// Returns the edges of a check node.
+    pub fn check_edges(&self, check: usize) -> &[usize] {
+        &self.check_edges[self.check_range(check)]
+    }
+
+    /// Returns the number of independent layers.
+    pub fn num_layers(&self) -> usize {
+        self.layer_offsets.len() - 1
+    }
+
+    /// Returns the check nodes of an independent layer.
+    ///
+    /// The check nodes of a layer do not have variable nodes in common, and
+    /// they are listed in increasing order. See the [module
+    /// documentation](self) for the definition of the layers.
+    pub fn layer(&self, layer: usize) -> &[usize] {
+        &self.layer_checks[self.layer_offsets[layer]..self.layer_offsets[layer + 1]]
+    }
+
+    /// Checks if some hard decisions satisfy all the parity check equations.
+    pub fn check_parity<T, F>(&self, values: &[T], hard_decision: F) -> bool
+    where
+        T: Copy,
+        F: Fn(T) -> bool,
+    {
+        self.check_parity_by(|v| hard_decision(values[v]))
+    }
+
+    /// Checks if the hard decisions of the variable nodes satisfy all the
+    /// parity checks.
+    ///
+    /// This is similar to [`CompiledGraph::check_parity`], but the function
+    /// `hard_decision` gives the hard decision for each variable node index.
+    pub fn check_parity_by<F>(&self, hard_decision: F) -> bool
+    where
+        F: Fn(usize) -> bool,
+    {
+        (0..self.num_checks()).all(|c| {
+            self.check_neighbours(c)
+                .iter()
+                .filter(|&&v| hard_decision(v))
+                .count()
+                % 2
+                == 0
+        })
+    }
+
+    /// Returns the number of parity checks that are not satisfied by the hard
+    /// decisions of the variable nodes.
+    ///
+    /// This is the weight of the syndrome. The function `hard_decision` gives
+    /// the hard decision for each variable node index, as in
+    /// [`CompiledGraph::check_parity_by`].
+    pub fn unsatisfied_checks_by<F>(&self, hard_decision: F) -> usize
+    where
+        F: Fn(usize) -> bool,
+    {
+        (0..self.num_checks())
+            .filter(|&c| {
+                self.check_neighbours(c)
+                    .iter()
+                    .filter(|&&v| hard_decision(v))
+                    .count()
+                    % 2
+                    == 1
+            })
+            .count()
+    }
+
+    fn check_range(&self, check: usize) -> Range<usize> {
+        self.check_offsets[check]..self.check_offsets[check + 1]
+    }
+}
+
+// Partitions the rows of H into independent layers. Each row is placed in the
+// layer that follows the last layer containing a previous row that shares
+// some column with it. Returns the layer offsets and the rows of the layers.
+fn independent_layers(h: &SparseMatrix) -> (Vec<usize>, Vec<usize>) {
+    // Layer of the last row that contains each column
+    let mut column_layer: Vec<Option<usize>> = vec![None; h.num_cols()];
+    let mut layers: Vec<Vec<usize>> = Vec::new();
+    for c in 0..h.num_rows() {
+        let layer = h
+            .iter_row(c)
+            .filter_map(|&v| column_layer[v])
+            .max()
+            .map_or(0, |l| l + 1);
+        if layer == layers.len() {
+            layers.push(Vec::new());
+        }
+        layers[layer].push(c);
+        for &v in h.iter_row(c) {
+            column_layer[v] = Some(layer);
+        }
+    }
+    let mut offsets = vec![0];
+    for layer in layers.iter() {
+        offsets.push(offsets.last().unwrap() + layer.len());
+    }
+    (offsets, layers.concat())
+}
+
+impl From<SparseMatrix> for CompiledGraph {
+    fn from(h: SparseMatrix) -> CompiledGraph {
+        CompiledGraph::new(h)
+    }
+}
+
+impl From<Arc<SparseMatrix>> for CompiledGraph {
+    fn from(h: Arc<SparseMatrix>) -> CompiledGraph {
+        CompiledGraph::new(h)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn edges() {
+        let mut h = SparseMatrix::new(3, 5);
+        h.insert_row(0, [4, 0, 2].iter());
+        h.insert_row(1, [1, 2].iter());
+        h.insert_row(2, [3, 0, 1].iter());
+        let graph = CompiledGraph::new(h.clone());
+        assert_eq!(graph.num_checks(), 3);
+        assert_eq!(graph.num_vars(), 5);
+        assert_eq!(graph.num_edges(), 8);
+        for v in 0..h.num_cols() {
+            assert!(graph.var_neighbours(v).iter().eq(h.iter_col(v)));
+        }
+        for c in 0..h.num_rows() {
+            assert!(graph.check_neighbours(c).iter().eq(h.iter_row(c)));
+            for (&v, &e) in graph.check_neighbours(c).iter().zip(graph.check_edges(c)) {
+                assert!(graph.var_edges(v).contains(&e));
+                assert_eq!(graph.var_neighbours[e], c);
+            }
+        }
+        // Rows 0 and 1 share variable 2, and rows 1 and 2 share variable 1
+        assert_eq!(graph.num_layers(), 3);
+        assert_eq!(graph.layer(1), &[1]);
+        assert!(graph.check_parity(&[1, 1, 1, 0, 0], |x| x == 1));
+
+        let mut h = SparseMatrix::new(3, 5);
+        h.insert_row(0, [0, 1].iter());
+        h.insert_row(1, [1, 2].iter());
+        h.insert_row(2, [3, 4].iter());
+        let graph = CompiledGraph::new(h);
+        assert_eq!(graph.num_layers(), 2);
+        assert_eq!(graph.layer(0), &[0, 2]);
+        assert_eq!(graph.layer(1), &[1]);
+        assert!(!graph.check_parity(&[1, 0, 0, 0, 0], |x| x == 1));
+    }
+}
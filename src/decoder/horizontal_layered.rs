@@ -5,34 +5,82 @@
 //! Efficient Message-Passing Schedule for LDPC
 //! Decoding](https://www.eng.biu.ac.il/~goldbej/papers/engisrael.pdf), by
 //! E. Sharon, S. Litsyn, and J. Goldberg.
+//!
+//! When the `rayon` feature is enabled, the check nodes of each of the
+//! independent layers of the graph (see [`CompiledGraph::layer`]) can be
+//! processed in parallel with [`Decoder::decode_parallel`]. Since these check
+//! nodes do not share any variable nodes, the results are the same as with the
+//! serial schedule.
 
+#[cfg(feature = "profiling")]
+use super::DecoderCounters;
+#[cfg(feature = "rayon")]
+use super::SentMessage;
 use super::{
-    arithmetic::DecoderArithmetic, check_llrs, hard_decisions, DecoderOutput, LdpcDecoder,
-    SentMessages,
+    arithmetic::{DecoderArithmetic, NodeContext},
+    decoder_output,
+    graph::CompiledGraph,
+    source_llrs,
+    trace::{IterationTrace, LlrTrace},
+    update_max_magnitude, write_hard_decisions, DecoderOutput, LdpcDecoder, LlrSource,
+    SentMessages, StridedLlrs,
 };
 use crate::sparse::SparseMatrix;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use std::sync::Arc;
 
 /// LDPC belief propagation horizontal layered decoder.
 #[derive(Debug, Clone, PartialEq)]
 pub struct Decoder<A: DecoderArithmetic> {
     arithmetic: A,
-    h: SparseMatrix,
+    graph: Arc<CompiledGraph>,
     llrs: Box<[A::VarLlr]>,                        // Qv
     check_messages: SentMessages<A::CheckMessage>, // Rcv
+    llr_magnitude_tracking: bool,
+    max_llr_magnitude: Option<f64>,
+    iteration_tracing: bool,
+    iteration_trace: IterationTrace,
+    llr_tracing: bool,
+    llr_trace: LlrTrace,
+    #[cfg(feature = "profiling")]
+    counters: DecoderCounters,
+    decoded: bool,
 }
 
 impl<A: DecoderArithmetic> Decoder<A> {
     /// Creates a new horizontal layered LDPC decoder.
     ///
-    /// The parameter `h` indicates the parity check matrix.
-    pub fn new(h: SparseMatrix, arithmetic: A) -> Self {
-        let llrs = vec![Default::default(); h.num_cols()].into_boxed_slice();
-        let check_messages = SentMessages::from_iter((0..h.num_rows()).map(|r| h.iter_row(r)));
+    /// The parameter `h` indicates the parity check matrix. It can be given
+    /// either as a [`SparseMatrix`] or as an `Arc<SparseMatrix>`, which allows
+    /// sharing the matrix between several decoders.
+    pub fn new(h: impl Into<Arc<SparseMatrix>>, arithmetic: A) -> Self {
+        Decoder::from_graph(Arc::new(CompiledGraph::new(h)), arithmetic)
+    }
+
+    /// Creates a new horizontal layered LDPC decoder from a compiled Tanner
+    /// graph.
+    ///
+    /// The graph is shared with the other decoders built from the same `Arc`.
+    pub fn from_graph(graph: Arc<CompiledGraph>, arithmetic: A) -> Self {
+        let llrs = vec![Default::default(); graph.num_vars()].into_boxed_slice();
+        let check_messages = SentMessages::from_iter(
+            (0..graph.num_checks()).map(|c| graph.check_neighbours(c).iter()),
+        );
         Decoder {
             arithmetic,
-            h,
+            graph,
             llrs,
             check_messages,
+            llr_magnitude_tracking: false,
+            max_llr_magnitude: None,
+            iteration_tracing: false,
+            iteration_trace: IterationTrace::new(),
+            llr_tracing: false,
+            llr_trace: LlrTrace::new(),
+            #[cfg(feature = "profiling")]
+            counters: DecoderCounters::default(),
+            decoded: false,
         }
     }
 
@@ -51,45 +99,124 @@ impl<A: DecoderArithmetic> Decoder<A> {
         llrs: &[f64],
         max_iterations: usize,
     ) -> Result<DecoderOutput, DecoderOutput> {
+        decoder_output(self, llrs, |decoder, out| {
+            decoder.decode_into(llrs, out, max_iterations)
+        })
+    }
+
+    /// Decodes a codeword into a buffer provided by the caller.
+    ///
+    /// See [`LdpcDecoder::decode_into`]. This function does not allocate any
+    /// memory.
+    pub fn decode_into(
+        &mut self,
+        llrs: &[f64],
+        out_codeword: &mut [u8],
+        max_iterations: usize,
+    ) -> Result<usize, usize> {
+        self.decode_from(llrs, out_codeword, max_iterations)
+    }
+
+    /// Decodes a codeword from an LLR source into a buffer provided by the
+    /// caller.
+    ///
+    /// This is similar to [`Decoder::decode_into`], but the LLRs are read
+    /// from an [`LlrSource`], such as a [`StridedLlrs`] view, without copying
+    /// them first.
+    pub fn decode_from<L: LlrSource + ?Sized>(
+        &mut self,
+        llrs: &L,
+        out_codeword: &mut [u8],
+        max_iterations: usize,
+    ) -> Result<usize, usize> {
+        self.decode_with(
+            llrs,
+            out_codeword,
+            max_iterations,
+            Self::process_check_nodes,
+        )
+    }
+
+    fn decode_with<L: LlrSource + ?Sized>(
+        &mut self,
+        llrs: &L,
+        out_codeword: &mut [u8],
+        max_iterations: usize,
+        process_check_nodes: fn(&mut Self, usize),
+    ) -> Result<usize, usize> {
         assert_eq!(llrs.len(), self.llrs.len());
+        self.max_llr_magnitude = None;
+        self.iteration_trace.clear();
+        self.llr_trace.clear();
+        #[cfg(feature = "profiling")]
+        {
+            self.counters = DecoderCounters::default();
+        }
         let input_llrs_hard_decision = |x| x <= 0.0;
-        if check_llrs(&self.h, llrs, input_llrs_hard_decision) {
+        if self
+            .graph
+            .check_parity_by(|v| input_llrs_hard_decision(llrs.llr(v)))
+        {
             // No bit errors case
-            return Ok(DecoderOutput {
-                codeword: hard_decisions(llrs, input_llrs_hard_decision),
-                iterations: 0,
-            });
+            if self.llr_magnitude_tracking {
+                update_max_magnitude(&mut self.max_llr_magnitude, source_llrs(llrs), |x| {
+                    self.arithmetic
+                        .llr_magnitude(self.arithmetic.input_llr_quantize(x))
+                });
+            }
+            for (x, y) in self.llrs.iter_mut().zip(source_llrs(llrs)) {
+                *x = self
+                    .arithmetic
+                    .llr_to_var_llr(self.arithmetic.input_llr_quantize(y));
+            }
+            self.trace_iteration(0);
+            self.decoded = true;
+            write_hard_decisions(source_llrs(llrs), out_codeword, input_llrs_hard_decision);
+            return Ok(0);
         }
         self.initialize(llrs);
+        self.decoded = true;
+        self.track_llr_magnitudes();
+        self.trace_iteration(0);
         for iteration in 1..=max_iterations {
-            self.process_check_nodes();
-            if check_llrs(&self.h, &self.llrs, |x| {
+            process_check_nodes(self, iteration);
+            self.track_llr_magnitudes();
+            self.trace_iteration(iteration);
+            #[cfg(feature = "profiling")]
+            {
+                // Each check node update reads and updates the LLRs of all its
+                // neighbouring variable nodes
+                let edges = self.graph.num_edges() as u64;
+                self.counters += DecoderCounters {
+                    check_node_updates: self.graph.num_checks() as u64,
+                    variable_node_updates: edges,
+                    messages: 2 * edges,
+                };
+            }
+            if self.graph.check_parity(&self.llrs, |x| {
                 self.arithmetic
                     .llr_hard_decision(self.arithmetic.var_llr_to_llr(x))
             }) {
                 // Decode succeeded
-                return Ok(DecoderOutput {
-                    codeword: hard_decisions(&self.llrs, |x| {
-                        self.arithmetic
-                            .llr_hard_decision(self.arithmetic.var_llr_to_llr(x))
-                    }),
-                    iterations: iteration,
-                });
+                self.write_hard_decisions(out_codeword);
+                return Ok(iteration);
             }
         }
         // Decode failed
-        Err(DecoderOutput {
-            codeword: hard_decisions(&self.llrs, |x| {
-                self.arithmetic
-                    .llr_hard_decision(self.arithmetic.var_llr_to_llr(x))
-            }),
-            iterations: max_iterations,
-        })
+        self.write_hard_decisions(out_codeword);
+        Err(max_iterations)
+    }
+
+    fn write_hard_decisions(&self, out_codeword: &mut [u8]) {
+        write_hard_decisions(self.llrs.iter().copied(), out_codeword, |x| {
+            self.arithmetic
+                .llr_hard_decision(self.arithmetic.var_llr_to_llr(x))
+        });
     }
 
-    fn initialize(&mut self, llrs: &[f64]) {
+    fn initialize<L: LlrSource + ?Sized>(&mut self, llrs: &L) {
         // Initialize Qv to input LLRs.
-        for (x, &y) in self.llrs.iter_mut().zip(llrs.iter()) {
+        for (x, y) in self.llrs.iter_mut().zip(source_llrs(llrs)) {
             *x = self
                 .arithmetic
                 .llr_to_var_llr(self.arithmetic.input_llr_quantize(y))
@@ -102,10 +229,124 @@ impl<A: DecoderArithmetic> Decoder<A> {
         }
     }
 
-    fn process_check_nodes(&mut self) {
-        for messages in self.check_messages.per_source.iter_mut() {
+    fn trace_iteration(&mut self, iteration: usize) {
+        let arithmetic = &self.arithmetic;
+        if self.iteration_tracing {
+            self.iteration_trace.record(
+                iteration,
+                &self.graph,
+                self.llrs.iter().map(|&x| {
+                    let x = arithmetic.var_llr_to_llr(x);
+                    (arithmetic.llr_hard_decision(x), arithmetic.llr_saturated(x))
+                }),
+            );
+        }
+        if self.llr_tracing {
+            self.llr_trace.record(
+                &self.graph,
+                self.llrs.iter().map(|&x| {
+                    (
+                        arithmetic.llr_hard_decision(arithmetic.var_llr_to_llr(x)),
+                        arithmetic.var_llr_magnitude(x),
+                    )
+                }),
+            );
+        }
+    }
+
+    fn track_llr_magnitudes(&mut self) {
+        if self.llr_magnitude_tracking {
+            update_max_magnitude(
+                &mut self.max_llr_magnitude,
+                self.llrs.iter().copied(),
+                |x| self.arithmetic.var_llr_magnitude(x),
+            );
+        }
+    }
+
+    fn process_check_nodes(&mut self, iteration: usize) {
+        for (c, messages) in self.check_messages.per_source.iter_mut().enumerate() {
+            let context = NodeContext::new(iteration, messages.len()).with_node(c);
             self.arithmetic
-                .update_check_messages_and_vars(messages, &mut self.llrs);
+                .update_check_messages_and_vars(messages, &mut self.llrs, context);
+        }
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<A> Decoder<A>
+where
+    A: DecoderArithmetic + Clone + Sync,
+    A::CheckMessage: Sync,
+    A::VarLlr: Sync,
+{
+    /// Decodes a codeword processing the check nodes of each layer in
+    /// parallel.
+    ///
+    /// This function gives the same results as [`Decoder::decode`], but the
+    /// check nodes of each of the independent layers of the graph are
+    /// processed in parallel using the rayon thread pool. This is useful to
+    /// reduce the decoding latency of a single codeword. Each thread uses its
+    /// own clone of the decoder arithmetic.
+    ///
+    /// This function is only available when the `rayon` feature is enabled.
+    pub fn decode_parallel(
+        &mut self,
+        llrs: &[f64],
+        max_iterations: usize,
+    ) -> Result<DecoderOutput, DecoderOutput> {
+        decoder_output(self, llrs, |decoder, out| {
+            decoder.decode_with(
+                llrs,
+                out,
+                max_iterations,
+                Self::process_check_nodes_parallel,
+            )
+        })
+    }
+
+    fn process_check_nodes_parallel(&mut self, iteration: usize) {
+        for layer in 0..self.graph.num_layers() {
+            let checks = self.graph.layer(layer);
+            let llrs = &self.llrs;
+            let check_messages = &self.check_messages;
+            // Each check node is processed with local copies of its messages
+            // and variable LLRs, which are written back once the layer is done.
+            let updated = checks
+                .par_iter()
+                .map_with(self.arithmetic.clone(), |arithmetic, &c| {
+                    let messages = &check_messages.per_source[c];
+                    let mut local_messages = messages
+                        .iter()
+                        .enumerate()
+                        .map(|(j, msg)| SentMessage {
+                            dest: j,
+                            value: msg.value,
+                        })
+                        .collect::<Vec<_>>();
+                    let mut vars = messages
+                        .iter()
+                        .map(|msg| llrs[msg.dest])
+                        .collect::<Vec<_>>();
+                    let context = NodeContext::new(iteration, messages.len()).with_node(c);
+                    arithmetic.update_check_messages_and_vars(
+                        &mut local_messages,
+                        &mut vars,
+                        context,
+                    );
+                    (local_messages, vars)
+                })
+                .collect::<Vec<_>>();
+            for (&c, (local_messages, vars)) in checks.iter().zip(updated) {
+                for ((msg, local), var) in self.check_messages.per_source[c]
+                    .iter_mut()
+                    .zip(local_messages)
+                    .zip(vars)
+                {
+                    msg.value = local.value;
+                    self.llrs[msg.dest] = var;
+                }
+            }
         }
     }
 }
@@ -118,4 +359,115 @@ impl<A: DecoderArithmetic> LdpcDecoder for Decoder<A> {
     ) -> Result<DecoderOutput, DecoderOutput> {
         Decoder::decode(self, llrs, max_iterations)
     }
+
+    fn decode_into(
+        &mut self,
+        llrs: &[f64],
+        out_codeword: &mut [u8],
+        max_iterations: usize,
+    ) -> Result<usize, usize> {
+        Decoder::decode_into(self, llrs, out_codeword, max_iterations)
+    }
+
+    fn decode_strided(
+        &mut self,
+        llrs: StridedLlrs<'_>,
+        out_codeword: &mut [u8],
+        max_iterations: usize,
+    ) -> Result<usize, usize> {
+        Decoder::decode_from(self, &llrs, out_codeword, max_iterations)
+    }
+
+    fn set_llr_magnitude_tracking(&mut self, enabled: bool) {
+        self.llr_magnitude_tracking = enabled;
+    }
+
+    fn max_llr_magnitude(&self) -> Option<f64> {
+        self.max_llr_magnitude
+    }
+
+    fn set_iteration_tracing(&mut self, enabled: bool) {
+        self.iteration_tracing = enabled;
+    }
+
+    fn iteration_trace(&self) -> Option<&IterationTrace> {
+        if self.iteration_tracing {
+            Some(&self.iteration_trace)
+        } else {
+            None
+        }
+    }
+
+    fn set_llr_tracing(&mut self, enabled: bool) {
+        self.llr_tracing = enabled;
+    }
+
+    fn llr_trace(&self) -> Option<&LlrTrace> {
+        if self.llr_tracing && self.llr_trace.is_complete() {
+            Some(&self.llr_trace)
+        } else {
+            None
+        }
+    }
+
+    fn bit_reliabilities(&self) -> Option<Vec<f64>> {
+        if !self.decoded {
+            return None;
+        }
+        self.llrs
+            .iter()
+            .map(|&x| self.arithmetic.var_llr_magnitude(x))
+            .collect()
+    }
+
+    #[cfg(feature = "profiling")]
+    fn update_counters(&self) -> Option<DecoderCounters> {
+        Some(self.counters)
+    }
+}
+
+#[cfg(all(test, feature = "rayon"))]
+mod test {
+    use super::super::arithmetic::Phif64;
+    use super::*;
+    use crate::{
+        codes::ccsds::{AR4JACode, AR4JAInfoSize, AR4JARate},
+        encoder::Encoder,
+        gf2::GF2,
+        rand::*,
+    };
+    use ndarray::Array1;
+    use num_traits::{One, Zero};
+    use rand::Rng as _;
+
+    #[test]
+    fn parallel_layers() {
+        let h = AR4JACode::new(AR4JARate::R1_2, AR4JAInfoSize::K1024).h();
+        let encoder = Encoder::from_h(&h).unwrap();
+        let k = h.num_cols() - h.num_rows();
+        let mut decoder = Decoder::new(h, Phif64::new());
+        assert!(decoder.graph.num_layers() < decoder.graph.num_checks());
+        let mut rng = Rng::seed_from_u64(0);
+        for _ in 0..4 {
+            let message = Array1::from_iter((0..k).map(|_| {
+                if rng.gen::<bool>() {
+                    GF2::one()
+                } else {
+                    GF2::zero()
+                }
+            }));
+            let codeword = encoder.encode(&message);
+            let llrs = codeword
+                .iter()
+                .map(|x| {
+                    let symbol = if x.is_one() { -1.0 } else { 1.0 };
+                    // Uniform noise is enough to produce some bit errors
+                    2.0 * (symbol + rng.gen_range(-1.5..1.5))
+                })
+                .collect::<Vec<_>>();
+            let serial = decoder.decode(&llrs, 20);
+            let parallel = decoder.decode_parallel(&llrs, 20);
+            assert_eq!(serial, parallel);
+        }
+    }
 }
@@ -0,0 +1,679 @@
+//! Min-sum decoders with learned parameters.
+//!
+//! This module implements min-sum decoder arithmetics whose check node
+//! messages are corrected with parameters given for each edge or for each
+//! check node of the Tanner graph, possibly changing with the iteration. This
+//! is the structure of the neural offset min-sum and neural normalized min-sum
+//! decoders, whose parameters are learned from data by unrolling the iterations
+//! of the decoder into a neural network. The parameters are trained with an
+//! external machine learning framework (see
+//! [`training_data`](crate::simulation::training_data) for the collection of
+//! training data) and loaded from a file with [`LearnedParameters`], so that
+//! the trained decoder can be evaluated in the BER test of this crate by means
+//! of the [`LearnedDecoder`] factory.
+//!
+//! # Parameters file format
+//!
+//! The parameters file is a text file. Empty lines and lines starting by `#`
+//! are ignored. The first line is a header of the form `<correction>
+//! <granularity>`, where `<correction>` is `offset` or `scale` and
+//! `<granularity>` is `edge` or `check`. Each of the remaining lines contains
+//! the parameters for one iteration, separated by whitespace or commas. The
+//! parameters of the last line are used for all the subsequent iterations, so
+//! a file with a single line of parameters gives parameters that do not depend
+//! on the iteration.
+//!
+//! Per-check parameters are listed in the order of the rows of the parity check
+//! matrix. Per-edge parameters are listed in the order of the rows of the
+//! parity check matrix, and within each row in the order of
+//! [`SparseMatrix::iter_row`](crate::sparse::SparseMatrix::iter_row). This is
+//! the order of the ones of the parity check matrix in the row-major order
+//! when the rows are sorted.
+//!
+//! # Examples
+//! ```
+//! # use ldpc_toolbox::decoder::{
+//! #     flooding::Decoder,
+//! #     graph::CompiledGraph,
+//! #     learned::{LearnedMinSumf64, LearnedParameters},
+//! # };
+//! # use ldpc_toolbox::sparse::SparseMatrix;
+//! # use std::sync::Arc;
+//! let mut h = SparseMatrix::new(4, 6);
+//! h.insert_row(0, [0, 1, 3].iter());
+//! h.insert_row(1, [1, 2, 4].iter());
+//! h.insert_row(2, [0, 4, 5].iter());
+//! h.insert_row(3, [2, 3, 5].iter());
+//! let graph = Arc::new(CompiledGraph::new(h));
+//! // Offsets per check node in the first iteration, and then constant offsets
+//! let parameters: LearnedParameters = "offset check\n\
+//!                                      0.5 0.5 0.25 0.25\n\
+//!                                      0.1 0.1 0.1 0.1"
+//!     .parse()?;
+//! let arithmetic = LearnedMinSumf64::new(Arc::new(parameters), &graph)?;
+//! let mut decoder = Decoder::from_graph(graph, arithmetic);
+//! let llrs = [1.5, 1.5, -1.5, 1.5, -1.5, 1.5];
+//! let output = decoder.decode(&llrs, 10).unwrap();
+//! assert_eq!(&output.codeword, &[0, 0, 1, 0, 1, 1]);
+//! # Ok::<(), ldpc_toolbox::decoder::learned::Error>(())
+//! ```
+
+use super::{
+    arithmetic::{send_var_messages_no_clip, DecoderArithmetic, NodeContext},
+    factory::DecoderFactory,
+    flooding,
+    graph::CompiledGraph,
+    horizontal_layered,
+    min_sum::reduce,
+    LdpcDecoder, Message, SentMessage,
+};
+use crate::sparse::SparseMatrix;
+use std::{
+    fmt::{self, Display},
+    path::PathBuf,
+    str::FromStr,
+    sync::Arc,
+};
+use thiserror::Error;
+
+/// Learned parameters error.
+#[derive(Debug, Error)]
+pub enum Error {
+    /// The header of the parameters file is missing or invalid.
+    #[error("invalid header: {0}")]
+    InvalidHeader(String),
+    /// There are no parameters.
+    #[error("no parameters")]
+    NoParameters,
+    /// A parameter could not be parsed.
+    #[error("invalid parameter: {0}")]
+    InvalidParameter(String),
+    /// The number of parameters is not valid for the Tanner graph.
+    #[error("wrong number of parameters (expected {expected}, got {got})")]
+    WrongNumberOfParameters {
+        /// Number of parameters expected for each iteration.
+        expected: usize,
+        /// Number of parameters found.
+        got: usize,
+    },
+    /// The parameters file could not be read.
+    #[error("could not read parameters file: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// Correction applied to the check node messages.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Correction {
+    /// The parameter is subtracted from the magnitude of the messages, which is
+    /// clamped to zero from below, as in offset min-sum.
+    Offset,
+    /// The magnitude of the messages is multiplied by the parameter, as in
+    /// normalized min-sum.
+    Scale,
+}
+
+/// Granularity of the parameters.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Granularity {
+    /// One parameter for each check node.
+    Check,
+    /// One parameter for each edge of the Tanner graph.
+    Edge,
+}
+
+/// Learned min-sum parameters.
+///
+/// See the [module documentation](self) for the file format and the ordering
+/// of the parameters.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LearnedParameters {
+    correction: Correction,
+    granularity: Granularity,
+    iterations: Vec<Vec<f64>>,
+}
+
+impl LearnedParameters {
+    /// Creates new learned parameters.
+    ///
+    /// The element `j` of `iterations` contains the parameters used in
+    /// iteration `j + 1`. The last element is used for all the subsequent
+    /// iterations. All the elements must have the same length.
+    pub fn new(
+        correction: Correction,
+        granularity: Granularity,
+        iterations: Vec<Vec<f64>>,
+    ) -> Result<LearnedParameters, Error> {
+        let len = iterations.first().ok_or(Error::NoParameters)?.len();
+        if len == 0 {
+            return Err(Error::NoParameters);
+        }
+        if let Some(other) = iterations.iter().find(|p| p.len() != len) {
+            return Err(Error::WrongNumberOfParameters {
+                expected: len,
+                got: other.len(),
+            });
+        }
+        if let Some(x) = iterations.iter().flatten().find(|x| !x.is_finite()) {
+            return Err(Error::InvalidParameter(x.to_string()));
+        }
+        Ok(LearnedParameters {
+            correction,
+            granularity,
+            iterations,
+        })
+    }
+
+    /// Reads learned parameters from a file.
+    pub fn from_file(path: impl AsRef<std::path::Path>) -> Result<LearnedParameters, Error> {
+        std::fs::read_to_string(path)?.parse()
+    }
+
+    /// Returns the correction applied with the parameters.
+    pub fn correction(&self) -> Correction {
+        self.correction
+    }
+
+    /// Returns the granularity of the parameters.
+    pub fn granularity(&self) -> Granularity {
+        self.granularity
+    }
+
+    /// Returns the number of iterations with different parameters.
+    pub fn num_iterations(&self) -> usize {
+        self.iterations.len()
+    }
+
+    /// Returns the parameters used in an iteration.
+    ///
+    /// Iterations are numbered starting by 1.
+    pub fn iteration(&self, iteration: usize) -> &[f64] {
+        let j = iteration.clamp(1, self.iterations.len()) - 1;
+        &self.iterations[j]
+    }
+
+    /// Checks that the parameters can be used with a Tanner graph.
+    ///
+    /// Returns an error if the number of parameters per iteration does not
+    /// match the number of check nodes or edges of the graph.
+    pub fn check_graph(&self, graph: &CompiledGraph) -> Result<(), Error> {
+        let expected = match self.granularity {
+            Granularity::Check => graph.num_checks(),
+            Granularity::Edge => graph.num_edges(),
+        };
+        let got = self.iterations[0].len();
+        if got != expected {
+            return Err(Error::WrongNumberOfParameters { expected, got });
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for LearnedParameters {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<LearnedParameters, Error> {
+        let mut lines = s
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty() && !l.starts_with('#'));
+        let header = lines
+            .next()
+            .ok_or_else(|| Error::InvalidHeader("missing header".to_string()))?;
+        let mut fields = header.split_whitespace();
+        let correction = match fields.next() {
+            Some("offset") => Correction::Offset,
+            Some("scale") => Correction::Scale,
+            _ => return Err(Error::InvalidHeader(header.to_string())),
+        };
+        let granularity = match fields.next() {
+            Some("check") => Granularity::Check,
+            Some("edge") => Granularity::Edge,
+            _ => return Err(Error::InvalidHeader(header.to_string())),
+        };
+        if fields.next().is_some() {
+            return Err(Error::InvalidHeader(header.to_string()));
+        }
+        let iterations = lines
+            .map(|line| {
+                line.split(|c: char| c.is_whitespace() || c == ',')
+                    .filter(|x| !x.is_empty())
+                    .map(|x| {
+                        x.parse()
+                            .map_err(|_| Error::InvalidParameter(x.to_string()))
+                    })
+                    .collect::<Result<Vec<f64>, Error>>()
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        LearnedParameters::new(correction, granularity, iterations)
+    }
+}
+
+impl Display for LearnedParameters {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let correction = match self.correction {
+            Correction::Offset => "offset",
+            Correction::Scale => "scale",
+        };
+        let granularity = match self.granularity {
+            Granularity::Check => "check",
+            Granularity::Edge => "edge",
+        };
+        writeln!(f, "{correction} {granularity}")?;
+        for parameters in &self.iterations {
+            let line = parameters
+                .iter()
+                .map(|x| x.to_string())
+                .collect::<Vec<_>>()
+                .join(" ");
+            writeln!(f, "{line}")?;
+        }
+        Ok(())
+    }
+}
+
+macro_rules! impl_learned_min_sumf {
+    ($ty:ident, $f:ty) => {
+        #[doc = concat!(
+                    "LDPC decoder arithmetic with `",
+                    stringify!($f),
+                    "` and the min-sum rule with learned parameters."
+                )]
+        ///
+        #[doc = concat!(
+                    "This is a [`DecoderArithmetic`] that uses `",
+                    stringify!($f),
+                    "` to represent the LLRs"
+                )]
+        /// and messages and computes the check node messages using the min-sum
+        /// rule, correcting the magnitude of each message with the
+        /// [`LearnedParameters`] of its edge or check node in the current
+        /// iteration.
+        ///
+        /// The arithmetic uses the node index given in the [`NodeContext`], so
+        /// it can be used with all the decoders of this crate.
+        #[derive(Debug, Clone)]
+        pub struct $ty {
+            parameters: Arc<LearnedParameters>,
+            // check_offsets[c] is the index of the first edge of check node c
+            check_offsets: Arc<[usize]>,
+            values: Vec<$f>,
+        }
+
+        impl $ty {
+            #[doc = concat!("Creates a new [`", stringify!($ty), "`] decoder arithmetic object.")]
+            ///
+            /// The parameters are checked against the Tanner graph with
+            /// [`LearnedParameters::check_graph`].
+            pub fn new(
+                parameters: Arc<LearnedParameters>,
+                graph: &CompiledGraph,
+            ) -> Result<$ty, Error> {
+                parameters.check_graph(graph)?;
+                let check_offsets = (0..graph.num_checks())
+                    .scan(0, |offset, c| {
+                        let first = *offset;
+                        *offset += graph.check_neighbours(c).len();
+                        Some(first)
+                    })
+                    .collect();
+                Ok($ty {
+                    parameters,
+                    check_offsets,
+                    values: Vec::new(),
+                })
+            }
+
+            // Corrects the magnitudes of the min-sum messages of a check node,
+            // which are given in the order of its edges.
+            fn correct(&self, context: NodeContext, messages: &mut [$f]) {
+                let parameters = self.parameters.iteration(context.iteration);
+                let parameters = match self.parameters.granularity {
+                    Granularity::Check => {
+                        let p = parameters[context.node];
+                        for x in messages.iter_mut() {
+                            *x = self.correct_message(*x, p);
+                        }
+                        return;
+                    }
+                    Granularity::Edge => {
+                        let first = self.check_offsets[context.node];
+                        &parameters[first..first + messages.len()]
+                    }
+                };
+                for (x, &p) in messages.iter_mut().zip(parameters) {
+                    *x = self.correct_message(*x, p);
+                }
+            }
+
+            fn correct_message(&self, message: $f, parameter: f64) -> $f {
+                let magnitude = message.abs();
+                let magnitude = match self.parameters.correction {
+                    Correction::Offset => (magnitude - parameter as $f).max(0.0),
+                    Correction::Scale => magnitude * parameter as $f,
+                };
+                if message < 0.0 {
+                    -magnitude
+                } else {
+                    magnitude
+                }
+            }
+
+            // Replaces self.values by the corrected min-sum check messages.
+            fn check_messages(&mut self, context: NodeContext) {
+                let reduction = reduce(&self.values)
+                    .expect("only one variable message connected to check node");
+                for (j, x) in self.values.iter_mut().enumerate() {
+                    *x = reduction.message(j, *x);
+                }
+                let mut values = std::mem::take(&mut self.values);
+                self.correct(context, &mut values);
+                self.values = values;
+            }
+        }
+
+        impl DecoderArithmetic for $ty {
+            type Llr = $f;
+            type CheckMessage = $f;
+            type VarMessage = $f;
+            type VarLlr = $f;
+
+            fn input_llr_quantize(&self, llr: f64) -> $f {
+                llr as $f
+            }
+
+            fn llr_hard_decision(&self, llr: $f) -> bool {
+                llr <= 0.0
+            }
+
+            fn llr_to_var_message(&self, llr: $f) -> $f {
+                llr
+            }
+
+            fn llr_to_var_llr(&self, llr: $f) -> $f {
+                llr
+            }
+
+            fn var_llr_to_llr(&self, var_llr: $f) -> $f {
+                var_llr
+            }
+
+            fn llr_magnitude(&self, llr: $f) -> Option<f64> {
+                Some(f64::from(llr.abs()))
+            }
+
+            fn var_llr_magnitude(&self, var_llr: $f) -> Option<f64> {
+                Some(f64::from(var_llr.abs()))
+            }
+
+            fn check_message_value(&self, msg: $f) -> Option<f64> {
+                Some(f64::from(msg))
+            }
+
+            fn var_message_value(&self, msg: $f) -> Option<f64> {
+                Some(f64::from(msg))
+            }
+
+            fn send_check_messages<F>(
+                &mut self,
+                var_messages: &[Message<$f>],
+                context: NodeContext,
+                mut send: F,
+            ) where
+                F: FnMut(SentMessage<$f>),
+            {
+                self.values.clear();
+                self.values.extend(var_messages.iter().map(|msg| msg.value));
+                self.check_messages(context);
+                for (msg, &value) in var_messages.iter().zip(self.values.iter()) {
+                    send(SentMessage {
+                        dest: msg.source,
+                        value,
+                    })
+                }
+            }
+
+            fn send_var_messages<F>(
+                &mut self,
+                input_llr: $f,
+                check_messages: &[Message<$f>],
+                _context: NodeContext,
+                send: F,
+            ) -> $f
+            where
+                F: FnMut(SentMessage<$f>),
+            {
+                send_var_messages_no_clip(input_llr, check_messages, send)
+            }
+
+            fn update_check_messages_and_vars(
+                &mut self,
+                check_messages: &mut [SentMessage<$f>],
+                vars: &mut [$f],
+                context: NodeContext,
+            ) {
+                self.values.clear();
+                self.values
+                    .extend(check_messages.iter().map(|msg| vars[msg.dest] - msg.value));
+                self.check_messages(context);
+                // Update Rcv's and Qv's
+                for (msg, &value) in check_messages.iter_mut().zip(self.values.iter()) {
+                    vars[msg.dest] += value - msg.value;
+                    msg.value = value;
+                }
+            }
+        }
+    };
+}
+
+impl_learned_min_sumf!(LearnedMinSumf64, f64);
+impl_learned_min_sumf!(LearnedMinSumf32, f32);
+
+/// Schedule of a [`LearnedDecoder`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Schedule {
+    /// Flooding schedule (see [`flooding::Decoder`]).
+    Flooding,
+    /// Horizontal layered schedule (see [`horizontal_layered::Decoder`]).
+    HorizontalLayered,
+}
+
+/// Factory of decoders with learned parameters.
+///
+/// This [`DecoderFactory`] builds decoders that use the [`LearnedMinSumf64`]
+/// arithmetic with some [`LearnedParameters`], so that a trained decoder can be
+/// evaluated with a [`BerTest`](crate::simulation::ber::BerTest).
+///
+/// The factory can be parsed from a string of the form `<schedule>:<path>`,
+/// where `<schedule>` is `flooding` or `layered` and `<path>` is the path of
+/// the parameters file, which is read when parsing.
+#[derive(Debug, Clone)]
+pub struct LearnedDecoder {
+    schedule: Schedule,
+    parameters: Arc<LearnedParameters>,
+    path: Option<PathBuf>,
+}
+
+impl LearnedDecoder {
+    /// Creates a new factory of decoders with learned parameters.
+    pub fn new(schedule: Schedule, parameters: Arc<LearnedParameters>) -> LearnedDecoder {
+        LearnedDecoder {
+            schedule,
+            parameters,
+            path: None,
+        }
+    }
+
+    /// Returns the schedule of the decoders.
+    pub fn schedule(&self) -> Schedule {
+        self.schedule
+    }
+
+    /// Returns the learned parameters of the decoders.
+    pub fn parameters(&self) -> &Arc<LearnedParameters> {
+        &self.parameters
+    }
+}
+
+impl DecoderFactory for LearnedDecoder {
+    /// Builds an LDPC decoder.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the parameters cannot be used with the graph
+    /// (see [`LearnedParameters::check_graph`]).
+    fn build_decoder(&self, h: SparseMatrix) -> Box<dyn LdpcDecoder> {
+        self.build_graph_decoder(Arc::new(CompiledGraph::new(h)))
+    }
+
+    /// Builds an LDPC decoder from a compiled Tanner graph.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the parameters cannot be used with the graph
+    /// (see [`LearnedParameters::check_graph`]).
+    fn build_graph_decoder(&self, graph: Arc<CompiledGraph>) -> Box<dyn LdpcDecoder> {
+        let arithmetic = LearnedMinSumf64::new(Arc::clone(&self.parameters), &graph)
+            .expect("learned parameters do not match the code");
+        match self.schedule {
+            Schedule::Flooding => Box::new(flooding::Decoder::from_graph(graph, arithmetic)),
+            Schedule::HorizontalLayered => {
+                Box::new(horizontal_layered::Decoder::from_graph(graph, arithmetic))
+            }
+        }
+    }
+}
+
+impl FromStr for LearnedDecoder {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<LearnedDecoder, Error> {
+        let (schedule, path) = s
+            .split_once(':')
+            .ok_or_else(|| Error::InvalidHeader(format!("invalid learned decoder {s}")))?;
+        let schedule = match schedule {
+            "flooding" => Schedule::Flooding,
+            "layered" => Schedule::HorizontalLayered,
+            _ => return Err(Error::InvalidHeader(format!("invalid schedule {schedule}"))),
+        };
+        let parameters = LearnedParameters::from_file(path)?;
+        Ok(LearnedDecoder {
+            schedule,
+            parameters: Arc::new(parameters),
+            path: Some(path.into()),
+        })
+    }
+}
+
+impl Display for LearnedDecoder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let schedule = match self.schedule {
+            Schedule::Flooding => "flooding",
+            Schedule::HorizontalLayered => "layered",
+        };
+        match &self.path {
+            Some(path) => write!(f, "{schedule}:{}", path.display()),
+            None => write!(f, "{schedule}:<learned parameters>"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        codes::examples::ExampleCode,
+        decoder::arithmetic::{NormMinSumf64, OffsetMinSumf64},
+    };
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn parse() {
+        let parameters: LearnedParameters = "# trained\nscale edge\n0.5, 0.75 1\n\n0.8 0.8 0.8\n"
+            .parse()
+            .unwrap();
+        assert_eq!(parameters.correction(), Correction::Scale);
+        assert_eq!(parameters.granularity(), Granularity::Edge);
+        assert_eq!(parameters.num_iterations(), 2);
+        assert_eq!(parameters.iteration(1), [0.5, 0.75, 1.0]);
+        assert_eq!(parameters.iteration(7), [0.8, 0.8, 0.8]);
+        assert_eq!(
+            parameters.to_string().parse::<LearnedParameters>().unwrap(),
+            parameters
+        );
+
+        assert!("scale\n1.0".parse::<LearnedParameters>().is_err());
+        assert!("offset edge".parse::<LearnedParameters>().is_err());
+        assert!("offset edge\n1 x".parse::<LearnedParameters>().is_err());
+        assert!("offset edge\n1 2\n1".parse::<LearnedParameters>().is_err());
+        assert!("offset edge\n1 NaN".parse::<LearnedParameters>().is_err());
+    }
+
+    // Decodes random noisy frames with two decoders and checks that they give
+    // the same results.
+    fn compare<A: DecoderArithmetic, B: DecoderArithmetic>(graph: &Arc<CompiledGraph>, a: A, b: B) {
+        let mut a = horizontal_layered::Decoder::from_graph(Arc::clone(graph), a);
+        let mut b = horizontal_layered::Decoder::from_graph(Arc::clone(graph), b);
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        for _ in 0..20 {
+            let llrs = (0..graph.num_vars())
+                .map(|_| 2.0 + 2.0 * rng.gen::<f64>() - if rng.gen_bool(0.1) { 4.0 } else { 0.0 })
+                .collect::<Vec<_>>();
+            let output_a = a.decode(&llrs, 20);
+            let output_b = b.decode(&llrs, 20);
+            assert_eq!(output_a.is_ok(), output_b.is_ok());
+            let (output_a, output_b) = (
+                output_a.unwrap_or_else(|e| e),
+                output_b.unwrap_or_else(|e| e),
+            );
+            assert_eq!(output_a.codeword, output_b.codeword);
+            assert_eq!(output_a.iterations, output_b.iterations);
+        }
+    }
+
+    #[test]
+    fn constant_parameters() {
+        let graph = Arc::new(CompiledGraph::new(ExampleCode::Qc42_21.h()));
+        let edges = vec![vec![0.75; graph.num_edges()]];
+        let parameters = LearnedParameters::new(Correction::Scale, Granularity::Edge, edges);
+        let learned = LearnedMinSumf64::new(Arc::new(parameters.unwrap()), &graph).unwrap();
+        compare(&graph, learned, NormMinSumf64::new(0.75));
+
+        let checks = vec![vec![0.5; graph.num_checks()]];
+        let parameters = LearnedParameters::new(Correction::Offset, Granularity::Check, checks);
+        let learned = LearnedMinSumf64::new(Arc::new(parameters.unwrap()), &graph).unwrap();
+        compare(&graph, learned, OffsetMinSumf64::new(0.5));
+    }
+
+    #[test]
+    fn wrong_graph() {
+        let graph = CompiledGraph::new(ExampleCode::Qc42_21.h());
+        let parameters =
+            LearnedParameters::new(Correction::Scale, Granularity::Edge, vec![vec![1.0; 3]])
+                .unwrap();
+        assert!(matches!(
+            LearnedMinSumf32::new(Arc::new(parameters), &graph),
+            Err(Error::WrongNumberOfParameters { got: 3, .. })
+        ));
+    }
+
+    #[test]
+    fn factory() {
+        let h = ExampleCode::Qc42_21.h();
+        let graph = Arc::new(CompiledGraph::new(h));
+        let path = std::env::temp_dir().join("ldpc-toolbox-learned-parameters.txt");
+        let edges = vec![vec![0.25; graph.num_edges()]; 3];
+        let parameters =
+            LearnedParameters::new(Correction::Offset, Granularity::Edge, edges).unwrap();
+        std::fs::write(&path, parameters.to_string()).unwrap();
+        let factory: LearnedDecoder = format!("layered:{}", path.display()).parse().unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(factory.schedule(), Schedule::HorizontalLayered);
+        assert_eq!(**factory.parameters(), parameters);
+        assert_eq!(factory.to_string(), format!("layered:{}", path.display()));
+        let mut decoder = factory.build_graph_decoder(graph);
+        let output = decoder.decode(&[1.0; 42], 10).unwrap();
+        assert_eq!(output.codeword, [0; 42]);
+        assert!("bad:file".parse::<LearnedDecoder>().is_err());
+    }
+}
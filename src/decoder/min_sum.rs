@@ -0,0 +1,164 @@
+//! Min-sum check node reduction.
+//!
+//! This module implements the reduction used by min-sum check node units,
+//! which summarizes the variable messages received by a check node with the
+//! two smallest magnitudes, the index of the smallest magnitude and the
+//! product of the signs. The outgoing check messages are obtained from this
+//! summary. The reduction is exposed so that it can be used to generate test
+//! vectors for the verification of hardware implementations of check node
+//! units.
+//!
+//! # Examples
+//! ```
+//! # use ldpc_toolbox::decoder::min_sum::{reduce, Reduction};
+//! let messages = [3, -1, 4, -5, 2];
+//! let reduction = reduce(&messages).unwrap();
+//! assert_eq!(
+//!     reduction,
+//!     Reduction {
+//!         min1: 1,
+//!         min2: 2,
+//!         argmin: 1,
+//!         sign: false,
+//!     }
+//! );
+//! // Message sent back to each of the variable nodes
+//! let out = (0..messages.len())
+//!     .map(|j| reduction.message(j, messages[j]))
+//!     .collect::<Vec<_>>();
+//! assert_eq!(out, vec![1, -2, 1, -1, 1]);
+//! ```
+
+use num_traits::Signed;
+
+/// Min-sum reduction of the variable messages of a check node.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Reduction<T> {
+    /// Smallest magnitude of the messages.
+    pub min1: T,
+    /// Second smallest magnitude of the messages.
+    ///
+    /// If several messages have the smallest magnitude, this is equal to
+    /// `min1`.
+    pub min2: T,
+    /// Index of the message with the smallest magnitude.
+    ///
+    /// If several messages have the smallest magnitude, this is the index of
+    /// the first of them.
+    pub argmin: usize,
+    /// Product of the signs of the messages.
+    ///
+    /// This is `true` if the number of negative messages is odd. Zero is
+    /// considered positive, including `-0.0` for floating point types.
+    pub sign: bool,
+}
+
+/// Computes the min-sum reduction of the variable messages of a check node.
+///
+/// Returns `None` if there are less than two messages, since in this case the
+/// second smallest magnitude is not defined.
+///
+/// For integer types, the magnitude of the messages must be representable, so
+/// the messages should not be equal to the minimum value of the type (for
+/// instance, `-128` for `i8`), whose magnitude is taken as the minimum value
+/// itself.
+pub fn reduce<T: Signed + PartialOrd + Copy>(messages: &[T]) -> Option<Reduction<T>> {
+    if messages.len() < 2 {
+        return None;
+    }
+    let mut min1 = messages[0].abs();
+    let mut min2 = messages[1].abs();
+    let mut argmin = 0;
+    if min2 < min1 {
+        std::mem::swap(&mut min1, &mut min2);
+        argmin = 1;
+    }
+    for (j, x) in messages.iter().enumerate().skip(2) {
+        let x = x.abs();
+        if x < min1 {
+            min2 = min1;
+            min1 = x;
+            argmin = j;
+        } else if x < min2 {
+            min2 = x;
+        }
+    }
+    let sign = messages.iter().filter(|&&x| x < T::zero()).count() % 2 == 1;
+    Some(Reduction {
+        min1,
+        min2,
+        argmin,
+        sign,
+    })
+}
+
+impl<T: Signed + PartialOrd + Copy> Reduction<T> {
+    /// Returns the min-sum message sent to one of the variable nodes.
+    ///
+    /// The parameter `index` is the index of the variable node in the list of
+    /// messages given to [`reduce`], and `input` is the message received from
+    /// that variable node. The magnitude of the outgoing message is `min2` for
+    /// the variable node `argmin` and `min1` for the rest. Its sign is the
+    /// product of the signs of the messages received from the other variable
+    /// nodes.
+    pub fn message(&self, index: usize, input: T) -> T {
+        let magnitude = if index == self.argmin {
+            self.min2
+        } else {
+            self.min1
+        };
+        if self.sign ^ (input < T::zero()) {
+            -magnitude
+        } else {
+            magnitude
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn too_few_messages() {
+        assert_eq!(reduce::<f64>(&[]), None);
+        assert_eq!(reduce(&[1.5]), None);
+    }
+
+    #[test]
+    fn ties() {
+        let messages = [-2.0, 5.0, 2.0, -2.0];
+        let reduction = reduce(&messages).unwrap();
+        assert_eq!(
+            reduction,
+            Reduction {
+                min1: 2.0,
+                min2: 2.0,
+                argmin: 0,
+                sign: false,
+            }
+        );
+        assert_eq!(reduction.message(0, messages[0]), -2.0);
+        assert_eq!(reduction.message(1, messages[1]), 2.0);
+    }
+
+    #[test]
+    fn matches_brute_force() {
+        let messages: [i8; 6] = [7, -3, 0, 12, -3, -9];
+        let reduction = reduce(&messages).unwrap();
+        assert_eq!((reduction.min1, reduction.min2), (0, 3));
+        assert_eq!(reduction.argmin, 2);
+        assert!(reduction.sign);
+        for (j, &x) in messages.iter().enumerate() {
+            let others = messages
+                .iter()
+                .enumerate()
+                .filter(|&(k, _)| k != j)
+                .map(|(_, &y)| y);
+            let magnitude = others.clone().map(|y| y.abs()).min().unwrap();
+            let negative = others.filter(|y| *y < 0).count() % 2 == 1;
+            let expected = if negative { -magnitude } else { magnitude };
+            assert_eq!(reduction.message(j, x), expected);
+        }
+    }
+}
@@ -0,0 +1,283 @@
+//! Export of the layered decoder schedule.
+//!
+//! This module contains [`LayeredSchedule`], which describes the order in which
+//! the [horizontal layered decoder](super::horizontal_layered) processes the
+//! check nodes of a code when it uses the independent layers of its
+//! [`CompiledGraph`], and the memory accesses that this involves. It is
+//! intended for hardware architects studying the memory conflicts of layered
+//! decoders, especially for QC-LDPC codes.
+//!
+//! The layers are processed in order, and the check nodes of each layer are
+//! processed in increasing order (or in parallel, since they do not share any
+//! variable nodes). Each check node reads and writes the LLR of each of its
+//! variable nodes and the message stored for each of its edges. The edges are
+//! numbered as in [`CompiledGraph`].
+//!
+//! The LLR memory is described in terms of banks and addresses. If a circulant
+//! size `z` is given, variable node `v` is stored in bank `v / z` at address
+//! `v % z`, which corresponds to the usual arrangement of QC-LDPC decoders, with
+//! one memory per column block. Otherwise, all the variable nodes are stored in
+//! bank 0.
+//!
+//! The schedule can be exported as CSV with [`LayeredSchedule::write_csv`] or as
+//! JSON with [`LayeredSchedule::write_json`].
+
+use super::graph::CompiledGraph;
+use std::io::{Result, Write};
+
+/// Layered decoder schedule.
+///
+/// See the [module documentation](self) for more details.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LayeredSchedule {
+    num_checks: usize,
+    num_vars: usize,
+    num_edges: usize,
+    circulant_size: Option<usize>,
+    layers: Vec<Layer>,
+}
+
+/// Layer of a [`LayeredSchedule`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Layer {
+    /// Check nodes of the layer, in processing order.
+    pub checks: Vec<usize>,
+    /// Memory accesses of the layer, in processing order.
+    pub accesses: Vec<EdgeAccess>,
+    /// Variable nodes of the layer that are also updated by the previous
+    /// layer, in increasing order.
+    ///
+    /// A pipelined decoder must wait until the previous layer has written the
+    /// LLRs of these variable nodes before reading them. The list is empty for
+    /// the first layer.
+    pub hazards: Vec<usize>,
+}
+
+/// Memory access through an edge of the Tanner graph.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct EdgeAccess {
+    /// Check node being processed.
+    pub check: usize,
+    /// Variable node of the edge.
+    pub var: usize,
+    /// Index of the edge.
+    pub edge: usize,
+    /// LLR memory bank of the variable node.
+    pub bank: usize,
+    /// Address of the variable node in its LLR memory bank.
+    pub address: usize,
+}
+
+impl LayeredSchedule {
+    /// Creates the layered schedule of a compiled Tanner graph.
+    ///
+    /// The `circulant_size` determines the LLR memory banks (see the [module
+    /// documentation](self)).
+    ///
+    /// # Panics
+    /// This function panics if `circulant_size` is zero.
+    pub fn new(graph: &CompiledGraph, circulant_size: Option<usize>) -> LayeredSchedule {
+        assert_ne!(circulant_size, Some(0));
+        // Bank and address of each variable node
+        let location = |v: usize| match circulant_size {
+            Some(z) => (v / z, v % z),
+            None => (0, v),
+        };
+        let mut layers: Vec<Layer> = Vec::with_capacity(graph.num_layers());
+        // Layer in which each variable node was last updated
+        let mut var_layer = vec![None; graph.num_vars()];
+        for j in 0..graph.num_layers() {
+            let checks = graph.layer(j).to_vec();
+            let mut accesses = Vec::new();
+            let mut hazards = Vec::new();
+            for &c in &checks {
+                for (&v, &e) in graph.check_neighbours(c).iter().zip(graph.check_edges(c)) {
+                    if j > 0 && var_layer[v] == Some(j - 1) {
+                        hazards.push(v);
+                    }
+                    var_layer[v] = Some(j);
+                    let (bank, address) = location(v);
+                    accesses.push(EdgeAccess {
+                        check: c,
+                        var: v,
+                        edge: e,
+                        bank,
+                        address,
+                    });
+                }
+            }
+            hazards.sort_unstable();
+            layers.push(Layer {
+                checks,
+                accesses,
+                hazards,
+            });
+        }
+        LayeredSchedule {
+            num_checks: graph.num_checks(),
+            num_vars: graph.num_vars(),
+            num_edges: graph.num_edges(),
+            circulant_size,
+            layers,
+        }
+    }
+
+    /// Returns the layers of the schedule, in processing order.
+    pub fn layers(&self) -> &[Layer] {
+        &self.layers
+    }
+
+    /// Returns the circulant size used to assign the LLR memory banks.
+    pub fn circulant_size(&self) -> Option<usize> {
+        self.circulant_size
+    }
+
+    /// Writes the schedule in CSV format.
+    ///
+    /// The CSV contains a header line and a line for each memory access, in
+    /// processing order, with the columns `step`, `layer`, `check`, `var`,
+    /// `edge`, `bank`, `address` and `hazard`. The `step` is the index of the
+    /// access in the whole schedule, and `hazard` is `1` if the variable node
+    /// is also updated by the previous layer and `0` otherwise.
+    pub fn write_csv<W: Write>(&self, mut w: W) -> Result<()> {
+        writeln!(w, "step,layer,check,var,edge,bank,address,hazard")?;
+        let mut step = 0;
+        for (j, layer) in self.layers.iter().enumerate() {
+            for access in &layer.accesses {
+                let hazard = layer.hazards.binary_search(&access.var).is_ok();
+                writeln!(
+                    w,
+                    "{},{},{},{},{},{},{},{}",
+                    step,
+                    j,
+                    access.check,
+                    access.var,
+                    access.edge,
+                    access.bank,
+                    access.address,
+                    u8::from(hazard)
+                )?;
+                step += 1;
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the schedule in JSON format.
+    ///
+    /// The JSON is an object with the fields `num_checks`, `num_vars`,
+    /// `num_edges`, `circulant_size` (which is `null` if not given), and
+    /// `layers`. The latter is an array of objects with the fields `checks`,
+    /// `hazards`, and `accesses`, which is an array of objects with the same
+    /// fields as [`EdgeAccess`].
+    pub fn write_json<W: Write>(&self, mut w: W) -> Result<()> {
+        writeln!(w, "{{")?;
+        writeln!(w, "  \"num_checks\": {},", self.num_checks)?;
+        writeln!(w, "  \"num_vars\": {},", self.num_vars)?;
+        writeln!(w, "  \"num_edges\": {},", self.num_edges)?;
+        match self.circulant_size {
+            Some(z) => writeln!(w, "  \"circulant_size\": {z},")?,
+            None => writeln!(w, "  \"circulant_size\": null,")?,
+        }
+        write!(w, "  \"layers\": [")?;
+        for (j, layer) in self.layers.iter().enumerate() {
+            if j != 0 {
+                write!(w, ",")?;
+            }
+            write!(
+                w,
+                "\n    {{\"checks\": {:?}, \"hazards\": {:?}, \"accesses\": [",
+                layer.checks, layer.hazards
+            )?;
+            for (k, access) in layer.accesses.iter().enumerate() {
+                if k != 0 {
+                    write!(w, ",")?;
+                }
+                write!(
+                    w,
+                    "\n      {{\"check\": {}, \"var\": {}, \"edge\": {}, \"bank\": {}, \"address\": {}}}",
+                    access.check, access.var, access.edge, access.bank, access.address
+                )?;
+            }
+            write!(w, "\n    ]}}")?;
+        }
+        writeln!(w, "\n  ]\n}}")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::sparse::SparseMatrix;
+
+    fn schedule() -> LayeredSchedule {
+        let mut h = SparseMatrix::new(3, 4);
+        h.insert_row(0, [0, 1].iter());
+        h.insert_row(1, [3, 2].iter());
+        h.insert_row(2, [1, 2].iter());
+        LayeredSchedule::new(&CompiledGraph::new(h), Some(2))
+    }
+
+    #[test]
+    fn layers() {
+        let schedule = schedule();
+        let layers = schedule.layers();
+        assert_eq!(layers.len(), 2);
+        assert_eq!(layers[0].checks, [0, 1]);
+        assert!(layers[0].hazards.is_empty());
+        assert_eq!(layers[1].checks, [2]);
+        assert_eq!(layers[1].hazards, [1, 2]);
+        assert_eq!(
+            layers[0].accesses[2],
+            EdgeAccess {
+                check: 1,
+                var: 3,
+                edge: 5,
+                bank: 1,
+                address: 1
+            }
+        );
+    }
+
+    #[test]
+    fn csv() {
+        let mut out = Vec::new();
+        schedule().write_csv(&mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "step,layer,check,var,edge,bank,address,hazard\n\
+             0,0,0,0,0,0,0,0\n\
+             1,0,0,1,1,0,1,0\n\
+             2,0,1,3,5,1,1,0\n\
+             3,0,1,2,3,1,0,0\n\
+             4,1,2,1,2,0,1,1\n\
+             5,1,2,2,4,1,0,1\n"
+        );
+    }
+
+    #[test]
+    fn json() {
+        let mut out = Vec::new();
+        let mut h = SparseMatrix::new(1, 2);
+        h.insert_row(0, [0, 1].iter());
+        LayeredSchedule::new(&CompiledGraph::new(h), None)
+            .write_json(&mut out)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            r#"{
+  "num_checks": 1,
+  "num_vars": 2,
+  "num_edges": 2,
+  "circulant_size": null,
+  "layers": [
+    {"checks": [0], "hazards": [], "accesses": [
+      {"check": 0, "var": 0, "edge": 0, "bank": 0, "address": 0},
+      {"check": 0, "var": 1, "edge": 1, "bank": 0, "address": 1}
+    ]}
+  ]
+}
+"#
+        );
+    }
+}
@@ -0,0 +1,351 @@
+//! Decoder server.
+//!
+//! This module contains a [`DecoderServer`] that decodes a stream of frames
+//! read from a byte stream, such as the standard input or a UNIX socket, and
+//! writes the decoded frames to another byte stream. It allows applications
+//! written in other languages to use the decoders of this crate without FFI,
+//! by running the `ldpc-toolbox serve` CLI subcommand as a separate process.
+//! The decoders are constructed only once and are kept between frames.
+//!
+//! The frames are read by a separate thread. All the frames that are available
+//! when the server becomes idle are decoded together as a batch, which is split
+//! between several decoders running in parallel. The server never waits for a
+//! batch to fill, so a client can send a single frame and wait for its
+//! response.
+//!
+//! # Protocol
+//!
+//! Each request frame contains the `n` LLRs of a codeword as little-endian
+//! `f32` values, where `n` is the codeword length. A positive LLR means that
+//! the bit is more likely to be a zero. There are no delimiters between
+//! frames.
+//!
+//! For each request frame, the server writes a response frame, in the same
+//! order. A response frame contains the number of iterations used in decoding
+//! as a little-endian `u32`, a status byte which is 1 if decoding was
+//! successful and 0 otherwise, and the `n` bits of the decoded codeword, each
+//! in a byte whose value is 0 or 1.
+//!
+//! The server stops when the input stream is closed at a frame boundary.
+//!
+//! # Examples
+//! ```
+//! # use ldpc_toolbox::{
+//! #     codes::examples::ExampleCode,
+//! #     decoder::{factory::DecoderImplementation, server::DecoderServer},
+//! # };
+//! # use std::io::Cursor;
+//! let h = ExampleCode::Qc42_21.h();
+//! let n = h.num_cols();
+//! let mut server = DecoderServer::new(DecoderImplementation::Phif64, h, 50);
+//! // Two frames containing the all-zeros codeword
+//! let input = [1.5f32; 84].iter().flat_map(|x| x.to_le_bytes()).collect::<Vec<u8>>();
+//! let mut output = Vec::new();
+//! let statistics = server.serve(Cursor::new(input), &mut output)?;
+//! assert_eq!(statistics.frames, 2);
+//! assert_eq!(output.len(), 2 * (5 + n));
+//! # Ok::<(), std::io::Error>(())
+//! ```
+
+use super::{factory::DecoderFactory, graph::CompiledGraph, LdpcDecoder};
+use crate::sparse::SparseMatrix;
+use std::{
+    io::{ErrorKind, Read, Write},
+    sync::{mpsc, Arc},
+};
+
+/// Decoder server.
+///
+/// The server decodes the frames read from a byte stream with decoders built
+/// by a [`DecoderFactory`]. See the [module documentation](self) for the
+/// protocol.
+#[derive(Debug)]
+pub struct DecoderServer<F> {
+    factory: F,
+    graph: Arc<CompiledGraph>,
+    max_iterations: usize,
+    num_threads: usize,
+    batch_size: usize,
+    decoders: Vec<Box<dyn LdpcDecoder>>,
+}
+
+/// Decoder server statistics.
+///
+/// These are the statistics of the frames decoded by a call to
+/// [`DecoderServer::serve`].
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub struct ServerStatistics {
+    /// Number of frames decoded.
+    pub frames: u64,
+    /// Number of frames in which decoding failed.
+    pub failures: u64,
+    /// Total number of iterations used in decoding.
+    pub iterations: u64,
+}
+
+impl ServerStatistics {
+    /// Returns the average number of iterations per frame.
+    ///
+    /// Returns zero if no frames have been decoded.
+    pub fn average_iterations(&self) -> f64 {
+        if self.frames == 0 {
+            0.0
+        } else {
+            self.iterations as f64 / self.frames as f64
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Response {
+    codeword: Vec<u8>,
+    result: Result<usize, usize>,
+}
+
+impl<F: DecoderFactory> DecoderServer<F> {
+    /// Creates a new decoder server.
+    ///
+    /// The server decodes the code with parity check matrix `h`, using
+    /// decoders built by `factory` and performing at most `max_iterations`
+    /// iterations per frame. By default, a single decoder thread is used and
+    /// batches contain at most 64 frames.
+    pub fn new(factory: F, h: SparseMatrix, max_iterations: usize) -> DecoderServer<F> {
+        DecoderServer {
+            factory,
+            graph: Arc::new(CompiledGraph::new(h)),
+            max_iterations,
+            num_threads: 1,
+            batch_size: 64,
+            decoders: Vec::new(),
+        }
+    }
+
+    /// Sets the number of decoder threads.
+    ///
+    /// Each thread uses its own decoder to decode a part of each batch.
+    ///
+    /// # Panics
+    /// Panics if `num_threads` is zero.
+    pub fn with_threads(mut self, num_threads: usize) -> DecoderServer<F> {
+        assert!(num_threads > 0, "the number of threads must be positive");
+        self.num_threads = num_threads;
+        self
+    }
+
+    /// Sets the maximum number of frames in a batch.
+    ///
+    /// # Panics
+    /// Panics if `batch_size` is zero.
+    pub fn with_batch_size(mut self, batch_size: usize) -> DecoderServer<F> {
+        assert!(batch_size > 0, "the batch size must be positive");
+        self.batch_size = batch_size;
+        self
+    }
+
+    /// Returns the codeword length.
+    pub fn codeword_length(&self) -> usize {
+        self.graph.num_vars()
+    }
+
+    /// Returns the size in bytes of a request frame.
+    pub fn request_size(&self) -> usize {
+        self.codeword_length() * std::mem::size_of::<f32>()
+    }
+
+    /// Returns the size in bytes of a response frame.
+    pub fn response_size(&self) -> usize {
+        5 + self.codeword_length()
+    }
+
+    /// Decodes the frames read from `reader` and writes them to `writer`.
+    ///
+    /// The function returns when `reader` reaches the end of the stream. The
+    /// decoders are kept by the server, so several streams can be served
+    /// without constructing them again.
+    ///
+    /// # Errors
+    /// Returns an error if reading or writing fails, or with kind
+    /// [`ErrorKind::UnexpectedEof`] if the stream ends in the middle of a
+    /// frame. The frames received before the error have already been decoded
+    /// and written.
+    pub fn serve<R, W>(&mut self, reader: R, mut writer: W) -> std::io::Result<ServerStatistics>
+    where
+        R: Read + Send + 'static,
+        W: Write,
+    {
+        while self.decoders.len() < self.num_threads {
+            self.decoders
+                .push(self.factory.build_graph_decoder(Arc::clone(&self.graph)));
+        }
+        // The reader thread is not joined if writing fails, since it could be
+        // blocked reading. It finishes when it tries to send the next frame.
+        let (frames_tx, frames_rx) = mpsc::sync_channel(2 * self.batch_size);
+        let request_size = self.request_size();
+        let reader_thread =
+            std::thread::spawn(move || read_frames(reader, request_size, frames_tx));
+
+        let mut statistics = ServerStatistics::default();
+        let mut batch = Vec::with_capacity(self.batch_size);
+        let mut responses = Vec::with_capacity(self.batch_size);
+        // recv() fails when the reader thread has finished
+        while let Ok(frame) = frames_rx.recv() {
+            batch.clear();
+            batch.push(frame);
+            while batch.len() < self.batch_size {
+                match frames_rx.try_recv() {
+                    Ok(frame) => batch.push(frame),
+                    Err(_) => break,
+                }
+            }
+            responses.resize_with(batch.len(), || Response {
+                codeword: vec![0; self.codeword_length()],
+                result: Ok(0),
+            });
+            self.decode_batch(&batch, &mut responses[..batch.len()]);
+            for response in &responses[..batch.len()] {
+                let (iterations, success) = match response.result {
+                    Ok(iterations) => (iterations, true),
+                    Err(iterations) => (iterations, false),
+                };
+                statistics.frames += 1;
+                statistics.failures += u64::from(!success);
+                statistics.iterations += iterations as u64;
+                writer.write_all(&(iterations as u32).to_le_bytes())?;
+                writer.write_all(&[u8::from(success)])?;
+                writer.write_all(&response.codeword)?;
+            }
+            writer.flush()?;
+        }
+        reader_thread.join().unwrap()?;
+        Ok(statistics)
+    }
+
+    fn decode_batch(&mut self, batch: &[Vec<f64>], responses: &mut [Response]) {
+        let max_iterations = self.max_iterations;
+        let decode = |decoder: &mut Box<dyn LdpcDecoder>,
+                      batch: &[Vec<f64>],
+                      responses: &mut [Response]| {
+            for (llrs, response) in batch.iter().zip(responses) {
+                response.result = decoder.decode_into(llrs, &mut response.codeword, max_iterations);
+            }
+        };
+        if batch.len() == 1 || self.decoders.len() == 1 {
+            decode(&mut self.decoders[0], batch, responses);
+            return;
+        }
+        let chunk_size = batch.len().div_ceil(self.decoders.len());
+        std::thread::scope(|s| {
+            for ((decoder, batch), responses) in self
+                .decoders
+                .iter_mut()
+                .zip(batch.chunks(chunk_size))
+                .zip(responses.chunks_mut(chunk_size))
+            {
+                s.spawn(move || decode(decoder, batch, responses));
+            }
+        });
+    }
+}
+
+// Reads frames until the end of the stream and sends them to the channel.
+fn read_frames<R: Read>(
+    mut reader: R,
+    request_size: usize,
+    frames: mpsc::SyncSender<Vec<f64>>,
+) -> std::io::Result<()> {
+    let mut buffer = vec![0; request_size];
+    loop {
+        let mut filled = 0;
+        while filled < request_size {
+            match reader.read(&mut buffer[filled..]) {
+                Ok(0) if filled == 0 => return Ok(()),
+                Ok(0) => {
+                    return Err(std::io::Error::new(
+                        ErrorKind::UnexpectedEof,
+                        "the stream ends in the middle of a frame",
+                    ))
+                }
+                Ok(read) => filled += read,
+                Err(e) if e.kind() == ErrorKind::Interrupted => (),
+                Err(e) => return Err(e),
+            }
+        }
+        let llrs = buffer
+            .chunks_exact(std::mem::size_of::<f32>())
+            .map(|bytes| f64::from(f32::from_le_bytes(bytes.try_into().unwrap())))
+            .collect();
+        if frames.send(llrs).is_err() {
+            // The server has stopped because of a write error
+            return Ok(());
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        codes::examples::ExampleCode, decoder::factory::DecoderImplementation, encoder::Encoder,
+        gf2::GF2,
+    };
+    use ndarray::Array1;
+    use num_traits::{One, Zero};
+    use std::io::Cursor;
+
+    #[test]
+    fn serve() {
+        let h = ExampleCode::Qc42_21.h();
+        let n = h.num_cols();
+        let encoder = Encoder::from_h(&h).unwrap();
+        let mut server = DecoderServer::new(DecoderImplementation::Phif64, h, 50)
+            .with_threads(3)
+            .with_batch_size(4);
+        let codewords = (0..10)
+            .map(|j| {
+                let message = Array1::from_iter((0..21).map(|k| {
+                    if (j + k) % 3 == 0 {
+                        GF2::one()
+                    } else {
+                        GF2::zero()
+                    }
+                }));
+                encoder.encode(&message)
+            })
+            .collect::<Vec<_>>();
+        let mut input = Vec::new();
+        for (j, codeword) in codewords.iter().enumerate() {
+            for (k, bit) in codeword.iter().enumerate() {
+                let llr: f32 = if bit.is_one() { -2.0 } else { 2.0 };
+                // One bit error in each frame
+                let llr = if k == j { -0.5 * llr } else { llr };
+                input.extend_from_slice(&llr.to_le_bytes());
+            }
+        }
+        let mut output = Vec::new();
+        for _ in 0..2 {
+            output.clear();
+            let statistics = server
+                .serve(Cursor::new(input.clone()), &mut output)
+                .unwrap();
+            assert_eq!(statistics.frames, 10);
+            assert_eq!(statistics.failures, 0);
+            assert!(statistics.average_iterations() >= 1.0);
+            assert_eq!(output.len(), 10 * server.response_size());
+            for (response, codeword) in output.chunks(server.response_size()).zip(&codewords) {
+                assert_eq!(response[4], 1);
+                let decoded = &response[5..];
+                assert_eq!(decoded.len(), n);
+                assert!(decoded
+                    .iter()
+                    .zip(codeword)
+                    .all(|(&b, c)| (b == 1) == c.is_one()));
+            }
+        }
+
+        input.pop();
+        output.clear();
+        let err = server.serve(Cursor::new(input), &mut output).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+        assert_eq!(output.len(), 9 * server.response_size());
+    }
+}
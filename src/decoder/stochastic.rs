@@ -0,0 +1,342 @@
+//! Stochastic LDPC decoder.
+//!
+//! This module implements a bit-serial stochastic LDPC decoder. In a stochastic
+//! decoder, probabilities are represented by Bernoulli streams, and each node
+//! of the graph exchanges a single bit per edge in each decoding cycle. Check
+//! nodes compute the XOR of their input bits. Variable nodes output the common
+//! value of their input bits when all of them agree (a so-called regenerative
+//! bit), and otherwise output a bit taken at random from an edge memory that
+//! stores the most recent regenerative bits of that edge. The hard decisions
+//! are obtained with saturating up/down counters.
+//!
+//! See *S. S. Tehrani, W. J. Gross and S. Mannor, "Stochastic Decoding of LDPC
+//! Codes," in IEEE Communications Letters, vol. 10, no. 10, pp. 716-718,
+//! Oct. 2006* and *S. S. Tehrani, S. Mannor and W. J. Gross, "Fully Parallel
+//! Stochastic LDPC Decoders," in IEEE Transactions on Signal Processing,
+//! vol. 56, no. 11, pp. 5692-5703, Nov. 2008*.
+//!
+//! Since each decoding cycle carries much less information than an iteration of
+//! belief propagation, stochastic decoders typically need hundreds or thousands
+//! of decoding cycles. In this decoder, each decoding cycle counts as an
+//! iteration.
+
+use super::{
+    decoder_output, graph::CompiledGraph, source_llrs, write_hard_decisions, DecoderOutput,
+    LdpcDecoder, LlrSource, StridedLlrs,
+};
+use crate::{
+    rand::{Rng, *},
+    sparse::SparseMatrix,
+};
+use rand::Rng as _;
+use std::sync::Arc;
+
+/// Stochastic decoder configuration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+    /// Length of the edge memories (between 1 and 64 bits).
+    pub edge_memory_length: usize,
+    /// Noise-dependent scaling factor.
+    ///
+    /// The channel LLRs are multiplied by this factor before they are converted
+    /// to probabilities. Values smaller than one reduce the probability of
+    /// latching in the stochastic streams.
+    pub scaling: f64,
+    /// Saturation limit of the up/down counters used for hard decisions.
+    pub counter_limit: i32,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            edge_memory_length: 16,
+            scaling: 0.5,
+            counter_limit: 16,
+        }
+    }
+}
+
+/// Stochastic LDPC decoder.
+#[derive(Debug, Clone)]
+pub struct Decoder {
+    config: Config,
+    graph: Arc<CompiledGraph>,
+    rng: Rng,
+    // The edges are numbered as in the compiled graph.
+    probabilities: Box<[f64]>,
+    var_bits: Box<[bool]>,
+    check_bits: Box<[bool]>,
+    memories: Box<[u64]>,
+    memory_positions: Box<[usize]>,
+    counters: Box<[i32]>,
+    decisions: Box<[bool]>,
+}
+
+impl Decoder {
+    /// Creates a new stochastic LDPC decoder.
+    ///
+    /// The parameter `h` indicates the parity check matrix, which can be given
+    /// either as a [`SparseMatrix`] or as an `Arc<SparseMatrix>` shared with
+    /// other decoders, and `seed` is the seed for the random number generator
+    /// used by the decoder.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the edge memory length is not between 1 and 64.
+    pub fn new(h: impl Into<Arc<SparseMatrix>>, config: Config, seed: u64) -> Decoder {
+        Decoder::from_graph(Arc::new(CompiledGraph::new(h)), config, seed)
+    }
+
+    /// Creates a new stochastic LDPC decoder from a compiled Tanner graph.
+    ///
+    /// The graph is shared with the other decoders built from the same `Arc`.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the edge memory length is not between 1 and 64.
+    pub fn from_graph(graph: Arc<CompiledGraph>, config: Config, seed: u64) -> Decoder {
+        assert!((1..=64).contains(&config.edge_memory_length));
+        let num_edges = graph.num_edges();
+        let num_vars = graph.num_vars();
+        Decoder {
+            config,
+            rng: Rng::seed_from_u64(seed),
+            probabilities: vec![0.0; num_vars].into_boxed_slice(),
+            var_bits: vec![false; num_edges].into_boxed_slice(),
+            check_bits: vec![false; num_edges].into_boxed_slice(),
+            memories: vec![0; num_edges].into_boxed_slice(),
+            memory_positions: vec![0; num_edges].into_boxed_slice(),
+            counters: vec![0; num_vars].into_boxed_slice(),
+            decisions: vec![false; num_vars].into_boxed_slice(),
+            graph,
+        }
+    }
+
+    /// Decodes a codeword.
+    ///
+    /// The parameters are the LLRs for the received codeword and the maximum
+    /// number of decoding cycles to perform. If decoding is successful, the
+    /// function returns an `Ok` containing the (hard decision) on the decoded
+    /// codeword and the number of decoding cycles used in decoding. If decoding
+    /// is not successful, the function returns an `Err` containing the hard
+    /// decisions of the up/down counters and the number of decoding cycles used
+    /// in decoding (which is equal to `max_iterations`).
+    pub fn decode(
+        &mut self,
+        llrs: &[f64],
+        max_iterations: usize,
+    ) -> Result<DecoderOutput, DecoderOutput> {
+        decoder_output(self, llrs, |decoder, out| {
+            decoder.decode_into(llrs, out, max_iterations)
+        })
+    }
+
+    /// Decodes a codeword into a buffer provided by the caller.
+    ///
+    /// See [`LdpcDecoder::decode_into`]. This function does not allocate any
+    /// memory.
+    pub fn decode_into(
+        &mut self,
+        llrs: &[f64],
+        out_codeword: &mut [u8],
+        max_iterations: usize,
+    ) -> Result<usize, usize> {
+        self.decode_from(llrs, out_codeword, max_iterations)
+    }
+
+    /// Decodes a codeword from an LLR source into a buffer provided by the
+    /// caller.
+    ///
+    /// This is similar to [`Decoder::decode_into`], but the LLRs are read
+    /// from an [`LlrSource`], such as a [`StridedLlrs`] view, without copying
+    /// them first.
+    pub fn decode_from<L: LlrSource + ?Sized>(
+        &mut self,
+        llrs: &L,
+        out_codeword: &mut [u8],
+        max_iterations: usize,
+    ) -> Result<usize, usize> {
+        assert_eq!(llrs.len(), self.probabilities.len());
+        let input_llrs_hard_decision = |x| x <= 0.0;
+        if self
+            .graph
+            .check_parity_by(|v| input_llrs_hard_decision(llrs.llr(v)))
+        {
+            // No bit errors case
+            write_hard_decisions(source_llrs(llrs), out_codeword, input_llrs_hard_decision);
+            return Ok(0);
+        }
+        self.initialize(llrs);
+        for iteration in 1..=max_iterations {
+            self.process_check_nodes();
+            self.process_variable_nodes();
+            if self.graph.check_parity(&self.decisions, |x| x) {
+                // Decode succeeded
+                write_hard_decisions(self.decisions.iter().copied(), out_codeword, |x| x);
+                return Ok(iteration);
+            }
+        }
+        // Decode failed
+        write_hard_decisions(self.decisions.iter().copied(), out_codeword, |x| x);
+        Err(max_iterations)
+    }
+
+    fn initialize<L: LlrSource + ?Sized>(&mut self, llrs: &L) {
+        for (p, llr) in self.probabilities.iter_mut().zip(source_llrs(llrs)) {
+            // Probability of the bit being one
+            *p = 1.0 / (1.0 + (self.config.scaling * llr).exp());
+        }
+        for (v, &p) in self.probabilities.iter().enumerate() {
+            for e in self.graph.var_edges(v) {
+                let mut memory = 0;
+                for j in 0..self.config.edge_memory_length {
+                    if self.rng.gen::<f64>() < p {
+                        memory |= 1 << j;
+                    }
+                }
+                self.memories[e] = memory;
+                self.memory_positions[e] = 0;
+                self.var_bits[e] = self.rng.gen::<f64>() < p;
+            }
+        }
+        self.counters.fill(0);
+        for (decision, llr) in self.decisions.iter_mut().zip(source_llrs(llrs)) {
+            *decision = llr <= 0.0;
+        }
+    }
+
+    fn process_check_nodes(&mut self) {
+        for c in 0..self.graph.num_checks() {
+            let edges = self.graph.check_edges(c);
+            let parity = edges.iter().fold(false, |acc, &e| acc ^ self.var_bits[e]);
+            for &e in edges.iter() {
+                self.check_bits[e] = parity ^ self.var_bits[e];
+            }
+        }
+    }
+
+    fn process_variable_nodes(&mut self) {
+        let memory_length = self.config.edge_memory_length;
+        for v in 0..self.probabilities.len() {
+            let edges = self.graph.var_edges(v);
+            let degree = edges.len();
+            let channel_bit = self.rng.gen::<f64>() < self.probabilities[v];
+            let ones = self.check_bits[edges.clone()]
+                .iter()
+                .filter(|&&b| b)
+                .count();
+            for e in edges {
+                let ones_others = ones - usize::from(self.check_bits[e]);
+                let regenerative = if channel_bit {
+                    ones_others == degree - 1
+                } else {
+                    ones_others == 0
+                };
+                self.var_bits[e] = if regenerative {
+                    let pos = self.memory_positions[e];
+                    if channel_bit {
+                        self.memories[e] |= 1 << pos;
+                    } else {
+                        self.memories[e] &= !(1 << pos);
+                    }
+                    self.memory_positions[e] = (pos + 1) % memory_length;
+                    channel_bit
+                } else {
+                    let j = self.rng.gen_range(0..memory_length);
+                    (self.memories[e] >> j) & 1 != 0
+                };
+            }
+            // Majority vote of the channel bit and the check bits to update the
+            // up/down counter.
+            let votes_one = ones + usize::from(channel_bit);
+            let votes_zero = degree + 1 - votes_one;
+            let limit = self.config.counter_limit;
+            let counter = &mut self.counters[v];
+            if votes_one > votes_zero {
+                *counter = (*counter + 1).min(limit);
+            } else if votes_zero > votes_one {
+                *counter = (*counter - 1).max(-limit);
+            }
+            if *counter != 0 {
+                self.decisions[v] = *counter > 0;
+            }
+        }
+    }
+}
+
+impl LdpcDecoder for Decoder {
+    fn decode(
+        &mut self,
+        llrs: &[f64],
+        max_iterations: usize,
+    ) -> Result<DecoderOutput, DecoderOutput> {
+        Decoder::decode(self, llrs, max_iterations)
+    }
+
+    fn decode_into(
+        &mut self,
+        llrs: &[f64],
+        out_codeword: &mut [u8],
+        max_iterations: usize,
+    ) -> Result<usize, usize> {
+        Decoder::decode_into(self, llrs, out_codeword, max_iterations)
+    }
+
+    fn decode_strided(
+        &mut self,
+        llrs: StridedLlrs<'_>,
+        out_codeword: &mut [u8],
+        max_iterations: usize,
+    ) -> Result<usize, usize> {
+        Decoder::decode_from(self, &llrs, out_codeword, max_iterations)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::codes::examples::ExampleCode;
+
+    fn test_decoder() -> Decoder {
+        // Example 2.5 in Sarah J. Johnson - Iterative Error Correction
+        let h = ExampleCode::Johnson2_5.h();
+        let config = Config {
+            scaling: 1.0,
+            ..Config::default()
+        };
+        Decoder::new(h, config, 0)
+    }
+
+    fn to_llrs(bits: &[u8]) -> Vec<f64> {
+        bits.iter()
+            .map(|&b| if b == 0 { 3.0 } else { -3.0 })
+            .collect()
+    }
+
+    #[test]
+    fn no_errors() {
+        let mut decoder = test_decoder();
+        let codeword = [0, 0, 1, 0, 1, 1];
+        let DecoderOutput {
+            codeword: decoded,
+            iterations,
+            ..
+        } = decoder.decode(&to_llrs(&codeword), 100).unwrap();
+        assert_eq!(&decoded, &codeword);
+        assert_eq!(iterations, 0);
+    }
+
+    #[test]
+    fn single_error() {
+        let mut decoder = test_decoder();
+        let codeword_good = [0, 0, 1, 0, 1, 1];
+        for j in 0..codeword_good.len() {
+            let mut codeword_bad = codeword_good;
+            codeword_bad[j] ^= 1;
+            let DecoderOutput {
+                codeword: decoded, ..
+            } = decoder.decode(&to_llrs(&codeword_bad), 1000).unwrap();
+            assert_eq!(&decoded, &codeword_good);
+        }
+    }
+}
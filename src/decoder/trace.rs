@@ -0,0 +1,416 @@
+//! Tracing of decoder messages.
+//!
+//! This module contains [`MessageTrace`], which records every message exchanged
+//! through every edge of the Tanner graph during the decoding of a codeword. It
+//! is intended to be used with small codes, to visualize the evolution of the
+//! messages of belief propagation or to debug new
+//! [`DecoderArithmetic`](super::arithmetic::DecoderArithmetic) implementations
+//! edge by edge. Tracing is enabled with
+//! [`LdpcDecoder::set_message_tracing`](super::LdpcDecoder::set_message_tracing).
+//!
+//! The trace can be exported as CSV with [`MessageTrace::write_csv`] or as JSON
+//! with [`MessageTrace::write_json`].
+//!
+//! The module also contains [`IterationTrace`], which records a few
+//! [`IterationStatistics`] per iteration, such as the number of unsatisfied
+//! checks. This is much cheaper than tracing all the messages, so it can be used
+//! to study the convergence of the decoder with long codes. It is enabled with
+//! [`LdpcDecoder::set_iteration_tracing`](super::LdpcDecoder::set_iteration_tracing).
+//!
+//! Finally, [`LlrTrace`] records the LLR of each variable node at the end of
+//! each iteration. It is used to collect training data for learned decoders
+//! (see [`training_data`](crate::simulation::training_data)), and it is
+//! enabled with
+//! [`LdpcDecoder::set_llr_tracing`](super::LdpcDecoder::set_llr_tracing).
+
+use super::graph::CompiledGraph;
+use std::io::{Result, Write};
+
+/// Direction of a message.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Direction {
+    /// Message sent from a variable node to a check node.
+    VarToCheck,
+    /// Message sent from a check node to a variable node.
+    CheckToVar,
+}
+
+impl std::fmt::Display for Direction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Direction::VarToCheck => "v2c",
+                Direction::CheckToVar => "c2v",
+            }
+        )
+    }
+}
+
+/// Message recorded in a trace.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct TracedMessage {
+    /// Iteration in which the message was sent.
+    ///
+    /// The variable node messages sent in iteration 0 are the channel LLRs.
+    pub iteration: usize,
+    /// Direction of the message.
+    pub direction: Direction,
+    /// Check node of the edge.
+    pub check: usize,
+    /// Variable node of the edge.
+    pub var: usize,
+    /// Value of the message.
+    ///
+    /// The value is given in the internal units of the decoder arithmetic (see
+    /// [`DecoderArithmetic::check_message_value`](super::arithmetic::DecoderArithmetic::check_message_value)).
+    pub value: f64,
+}
+
+/// Trace of the messages of a decoding.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MessageTrace {
+    messages: Vec<TracedMessage>,
+}
+
+impl MessageTrace {
+    /// Creates a new empty trace.
+    pub fn new() -> MessageTrace {
+        MessageTrace::default()
+    }
+
+    /// Returns the messages in the trace, in the order in which they were
+    /// recorded.
+    pub fn messages(&self) -> &[TracedMessage] {
+        &self.messages
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.messages.clear();
+    }
+
+    pub(crate) fn push(&mut self, message: TracedMessage) {
+        self.messages.push(message);
+    }
+
+    /// Writes the trace in CSV format.
+    ///
+    /// The CSV contains a header line and a line for each message, with the
+    /// columns `iteration`, `direction` (`v2c` or `c2v`), `check`, `var` and
+    /// `value`.
+    pub fn write_csv<W: Write>(&self, mut w: W) -> Result<()> {
+        writeln!(w, "iteration,direction,check,var,value")?;
+        for msg in &self.messages {
+            writeln!(
+                w,
+                "{},{},{},{},{}",
+                msg.iteration, msg.direction, msg.check, msg.var, msg.value
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Writes the trace in JSON format.
+    ///
+    /// The JSON is an array of objects with the same fields as the columns of
+    /// [`MessageTrace::write_csv`]. Values which are not finite are written as
+    /// `null`.
+    pub fn write_json<W: Write>(&self, mut w: W) -> Result<()> {
+        write!(w, "[")?;
+        for (j, msg) in self.messages.iter().enumerate() {
+            if j != 0 {
+                write!(w, ",")?;
+            }
+            write!(
+                w,
+                "\n  {{\"iteration\": {}, \"direction\": \"{}\", \"check\": {}, \"var\": {}, \"value\": ",
+                msg.iteration, msg.direction, msg.check, msg.var
+            )?;
+            if msg.value.is_finite() {
+                write!(w, "{}}}", msg.value)?;
+            } else {
+                write!(w, "null}}")?;
+            }
+        }
+        writeln!(w, "\n]")
+    }
+}
+
+/// Statistics of a decoder iteration.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub struct IterationStatistics {
+    /// Iteration.
+    ///
+    /// The statistics of iteration 0 are those of the channel LLRs, before the
+    /// first iteration.
+    pub iteration: usize,
+    /// Number of unsatisfied parity checks.
+    ///
+    /// This is the weight of the syndrome of the hard decisions at the end of
+    /// the iteration. Decoding stops when it is zero.
+    pub unsatisfied_checks: usize,
+    /// Number of hard decisions that have changed in the iteration.
+    ///
+    /// This is always zero in iteration 0.
+    pub flipped_bits: usize,
+    /// Number of LLRs that are saturated at the end of the iteration.
+    ///
+    /// See
+    /// [`DecoderArithmetic::llr_saturated`](super::arithmetic::DecoderArithmetic::llr_saturated).
+    pub saturated_llrs: usize,
+}
+
+/// Trace of the statistics of each iteration of a decoding.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct IterationTrace {
+    iterations: Vec<IterationStatistics>,
+    // hard decisions at the end of the last recorded iteration
+    decisions: Vec<bool>,
+}
+
+impl IterationTrace {
+    /// Creates a new empty trace.
+    pub fn new() -> IterationTrace {
+        IterationTrace::default()
+    }
+
+    /// Returns the statistics of each iteration, starting by iteration 0.
+    pub fn iterations(&self) -> &[IterationStatistics] {
+        &self.iterations
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.iterations.clear();
+        self.decisions.clear();
+    }
+
+    // Records the statistics of an iteration. The iterator gives the hard
+    // decision and the saturation of the LLR of each variable node.
+    pub(crate) fn record(
+        &mut self,
+        iteration: usize,
+        graph: &CompiledGraph,
+        llrs: impl Iterator<Item = (bool, bool)>,
+    ) {
+        let first = self.decisions.is_empty();
+        self.decisions.resize(graph.num_vars(), false);
+        let mut flipped_bits = 0;
+        let mut saturated_llrs = 0;
+        for (decision, (hard_decision, saturated)) in self.decisions.iter_mut().zip(llrs) {
+            if !first && *decision != hard_decision {
+                flipped_bits += 1;
+            }
+            if saturated {
+                saturated_llrs += 1;
+            }
+            *decision = hard_decision;
+        }
+        let unsatisfied_checks = graph.unsatisfied_checks_by(|v| self.decisions[v]);
+        self.iterations.push(IterationStatistics {
+            iteration,
+            unsatisfied_checks,
+            flipped_bits,
+            saturated_llrs,
+        });
+    }
+
+    /// Writes the trace in CSV format.
+    ///
+    /// The CSV contains a header line and a line for each iteration, with the
+    /// columns `iteration`, `unsatisfied_checks`, `flipped_bits` and
+    /// `saturated_llrs`.
+    pub fn write_csv<W: Write>(&self, mut w: W) -> Result<()> {
+        writeln!(
+            w,
+            "iteration,unsatisfied_checks,flipped_bits,saturated_llrs"
+        )?;
+        for it in &self.iterations {
+            writeln!(
+                w,
+                "{},{},{},{}",
+                it.iteration, it.unsatisfied_checks, it.flipped_bits, it.saturated_llrs
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Trace of the LLRs of the variable nodes in each iteration of a decoding.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LlrTrace {
+    num_vars: usize,
+    // LLRs of all the iterations, one after another
+    llrs: Vec<f64>,
+    // some of the LLRs could not be obtained from the decoder arithmetic
+    incomplete: bool,
+}
+
+impl LlrTrace {
+    /// Creates a new empty trace.
+    pub fn new() -> LlrTrace {
+        LlrTrace::default()
+    }
+
+    /// Returns the number of iterations in the trace.
+    ///
+    /// This includes iteration 0, so it is one more than the number of
+    /// iterations performed by the decoder.
+    pub fn num_iterations(&self) -> usize {
+        self.llrs.len().checked_div(self.num_vars).unwrap_or(0)
+    }
+
+    /// Returns the LLRs of the variable nodes at the end of an iteration.
+    ///
+    /// The LLRs of iteration 0 are the channel LLRs, as quantized by the
+    /// decoder. The LLRs are given in the internal units of the decoder
+    /// arithmetic (see
+    /// [`DecoderArithmetic::llr_magnitude`](super::arithmetic::DecoderArithmetic::llr_magnitude)),
+    /// with a positive sign for the bit 0.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the iteration is not in the trace.
+    pub fn llrs(&self, iteration: usize) -> &[f64] {
+        &self.llrs[iteration * self.num_vars..(iteration + 1) * self.num_vars]
+    }
+
+    pub(crate) fn is_complete(&self) -> bool {
+        !self.incomplete
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.llrs.clear();
+        self.incomplete = false;
+    }
+
+    // Records the LLRs of the next iteration. The iterator gives the hard
+    // decision and the magnitude of the LLR of each variable node, or None if
+    // the magnitude is not supported by the arithmetic.
+    pub(crate) fn record(
+        &mut self,
+        graph: &CompiledGraph,
+        llrs: impl Iterator<Item = (bool, Option<f64>)>,
+    ) {
+        self.num_vars = graph.num_vars();
+        for (hard_decision, magnitude) in llrs {
+            let Some(magnitude) = magnitude else {
+                self.incomplete = true;
+                return;
+            };
+            self.llrs
+                .push(if hard_decision { -magnitude } else { magnitude });
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn trace() -> MessageTrace {
+        let mut trace = MessageTrace::new();
+        trace.push(TracedMessage {
+            iteration: 0,
+            direction: Direction::VarToCheck,
+            check: 1,
+            var: 2,
+            value: -1.5,
+        });
+        trace.push(TracedMessage {
+            iteration: 1,
+            direction: Direction::CheckToVar,
+            check: 1,
+            var: 2,
+            value: f64::INFINITY,
+        });
+        trace
+    }
+
+    #[test]
+    fn csv() {
+        let mut out = Vec::new();
+        trace().write_csv(&mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "iteration,direction,check,var,value\n0,v2c,1,2,-1.5\n1,c2v,1,2,inf\n"
+        );
+    }
+
+    #[test]
+    fn json() {
+        let mut out = Vec::new();
+        trace().write_json(&mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            r#"[
+  {"iteration": 0, "direction": "v2c", "check": 1, "var": 2, "value": -1.5},
+  {"iteration": 1, "direction": "c2v", "check": 1, "var": 2, "value": null}
+]
+"#
+        );
+    }
+
+    #[test]
+    fn iteration_trace() {
+        let mut h = crate::sparse::SparseMatrix::new(2, 3);
+        h.insert_row(0, [0, 1].iter());
+        h.insert_row(1, [1, 2].iter());
+        let graph = CompiledGraph::new(h);
+        let mut trace = IterationTrace::new();
+        trace.record(
+            0,
+            &graph,
+            [(false, false), (true, false), (false, true)].into_iter(),
+        );
+        trace.record(
+            1,
+            &graph,
+            [(false, true), (false, true), (false, true)].into_iter(),
+        );
+        assert_eq!(
+            trace.iterations(),
+            [
+                IterationStatistics {
+                    iteration: 0,
+                    unsatisfied_checks: 2,
+                    flipped_bits: 0,
+                    saturated_llrs: 1,
+                },
+                IterationStatistics {
+                    iteration: 1,
+                    unsatisfied_checks: 0,
+                    flipped_bits: 1,
+                    saturated_llrs: 3,
+                }
+            ]
+        );
+        let mut out = Vec::new();
+        trace.write_csv(&mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "iteration,unsatisfied_checks,flipped_bits,saturated_llrs\n0,2,0,1\n1,0,1,3\n"
+        );
+        trace.clear();
+        assert!(trace.iterations().is_empty());
+    }
+
+    #[test]
+    fn llr_trace() {
+        let mut h = crate::sparse::SparseMatrix::new(1, 2);
+        h.insert_row(0, [0, 1].iter());
+        let graph = CompiledGraph::new(h);
+        let mut trace = LlrTrace::new();
+        trace.record(&graph, [(false, Some(1.5)), (true, Some(0.5))].into_iter());
+        trace.record(&graph, [(false, Some(3.0)), (false, Some(2.0))].into_iter());
+        assert_eq!(trace.num_iterations(), 2);
+        assert_eq!(trace.llrs(0), [1.5, -0.5]);
+        assert_eq!(trace.llrs(1), [3.0, 2.0]);
+        assert!(trace.is_complete());
+        trace.record(&graph, [(false, None), (false, None)].into_iter());
+        assert!(!trace.is_complete());
+        trace.clear();
+        assert_eq!(trace.num_iterations(), 0);
+        assert!(trace.is_complete());
+    }
+}
@@ -0,0 +1,474 @@
+//! LDPC decoder with vertical layered schedule.
+//!
+//! This module implements a generic belief propagation LDPC decoder with a
+//! serial, per variable node (vertical layered or column-shuffled) schedule, as
+//! described in [Shuffled Iterative
+//! Decoding](https://doi.org/10.1109/TCOMM.2004.841982), by J. Zhang and
+//! M. P. C. Fossorier.
+//!
+//! In each iteration, the variable nodes are processed in order. To process a
+//! variable node, the messages sent to it by its check nodes are computed using
+//! the most recent messages of the other variable nodes, and then the variable
+//! node sends its new messages. Therefore, the information obtained by a
+//! variable node is used by the following variable nodes in the same
+//! iteration, which typically reduces the number of iterations with respect to
+//! the flooding schedule, as the horizontal layered schedule does.
+//!
+//! The variable nodes can also be processed in groups of consecutive variable
+//! nodes (see [`Decoder::with_group_size`]). The check node messages for all
+//! the variable nodes of a group are computed at once, and then all the
+//! variable nodes of the group are updated. This is the group-shuffled schedule
+//! used by many hardware architectures for quasi-cyclic codes, in which the
+//! group is a column block of circulants.
+//!
+//! The decoder only uses the check node and variable node rules of the
+//! [`DecoderArithmetic`], so it supports all the arithmetics. Since the
+//! check node rule computes the messages for all the neighbours of a check
+//! node, each iteration costs roughly as many operations as a flooding
+//! iteration multiplied by the average check node degree divided by the number
+//! of variable nodes of a group connected to each check node.
+
+#[cfg(feature = "profiling")]
+use super::DecoderCounters;
+use super::{
+    arithmetic::{DecoderArithmetic, NodeContext},
+    decoder_output,
+    graph::CompiledGraph,
+    source_llrs, update_max_magnitude, write_hard_decisions, DecoderOutput, LdpcDecoder, LlrSource,
+    Messages, StridedLlrs,
+};
+use crate::sparse::SparseMatrix;
+use std::{ops::Range, sync::Arc};
+
+/// LDPC belief propagation vertical layered decoder.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Decoder<A: DecoderArithmetic> {
+    arithmetic: A,
+    graph: Arc<CompiledGraph>,
+    input_llrs: Box<[A::Llr]>,
+    output_llrs: Box<[A::Llr]>,
+    check_messages: Messages<A::CheckMessage>,
+    variable_messages: Messages<A::VarMessage>,
+    groups: Box<[Group]>,
+    llr_magnitude_tracking: bool,
+    max_llr_magnitude: Option<f64>,
+    #[cfg(feature = "profiling")]
+    counters: DecoderCounters,
+    decoded: bool,
+}
+
+// Group of variable nodes that are processed together.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Group {
+    vars: Range<usize>,
+    // Check nodes connected to the variable nodes of the group
+    checks: Box<[usize]>,
+}
+
+impl<A: DecoderArithmetic> Decoder<A> {
+    /// Creates a new vertical layered LDPC decoder.
+    ///
+    /// The parameter `h` indicates the parity check matrix. It can be given
+    /// either as a [`SparseMatrix`] or as an `Arc<SparseMatrix>`, which allows
+    /// sharing the matrix between several decoders.
+    pub fn new(h: impl Into<Arc<SparseMatrix>>, arithmetic: A) -> Self {
+        Decoder::from_graph(Arc::new(CompiledGraph::new(h)), arithmetic)
+    }
+
+    /// Creates a new vertical layered LDPC decoder from a compiled Tanner
+    /// graph.
+    ///
+    /// The variable nodes are processed one by one. The graph is shared with
+    /// the other decoders built from the same `Arc`.
+    pub fn from_graph(graph: Arc<CompiledGraph>, arithmetic: A) -> Self {
+        Decoder::with_group_size(graph, arithmetic, 1)
+    }
+
+    /// Creates a new vertical layered LDPC decoder that processes the variable
+    /// nodes in groups.
+    ///
+    /// The variable nodes are divided in groups of `group_size` consecutive
+    /// variable nodes (the last group can be smaller). For quasi-cyclic codes,
+    /// using the circulant size as the group size gives the group-shuffled
+    /// schedule. A group size of one gives the column-shuffled schedule, as
+    /// [`Decoder::from_graph`].
+    ///
+    /// # Panics
+    /// This function panics if `group_size` is zero.
+    pub fn with_group_size(graph: Arc<CompiledGraph>, arithmetic: A, group_size: usize) -> Self {
+        assert!(group_size > 0, "the group size must be positive");
+        let input_llrs = vec![Default::default(); graph.num_vars()].into_boxed_slice();
+        let output_llrs = input_llrs.clone();
+        let check_messages =
+            Messages::from_iter((0..graph.num_vars()).map(|v| graph.var_neighbours(v).iter()));
+        let variable_messages =
+            Messages::from_iter((0..graph.num_checks()).map(|c| graph.check_neighbours(c).iter()));
+        let groups = (0..graph.num_vars())
+            .step_by(group_size)
+            .map(|start| {
+                let vars = start..(start + group_size).min(graph.num_vars());
+                let mut checks = vars
+                    .clone()
+                    .flat_map(|v| graph.var_neighbours(v).iter().copied())
+                    .collect::<Vec<_>>();
+                checks.sort_unstable();
+                checks.dedup();
+                Group {
+                    vars,
+                    checks: checks.into_boxed_slice(),
+                }
+            })
+            .collect();
+        Decoder {
+            arithmetic,
+            graph,
+            input_llrs,
+            output_llrs,
+            check_messages,
+            variable_messages,
+            groups,
+            llr_magnitude_tracking: false,
+            max_llr_magnitude: None,
+            #[cfg(feature = "profiling")]
+            counters: DecoderCounters::default(),
+            decoded: false,
+        }
+    }
+
+    /// Returns the number of groups of variable nodes.
+    pub fn num_groups(&self) -> usize {
+        self.groups.len()
+    }
+
+    /// Decodes a codeword.
+    ///
+    /// The parameters are the LLRs for the received codeword and the maximum
+    /// number of iterations to perform. If decoding is successful, the function
+    /// returns an `Ok` containing the (hard decision) on the decoded codeword
+    /// and the number of iterations used in decoding. If decoding is not
+    /// successful, the function returns an `Err` containing the hard decision
+    /// on the final decoder LLRs (which still has some bit errors) and the
+    /// number of iterations used in decoding (which is equal to
+    /// `max_iterations`).
+    pub fn decode(
+        &mut self,
+        llrs: &[f64],
+        max_iterations: usize,
+    ) -> Result<DecoderOutput, DecoderOutput> {
+        decoder_output(self, llrs, |decoder, out| {
+            decoder.decode_into(llrs, out, max_iterations)
+        })
+    }
+
+    /// Decodes a codeword into a buffer provided by the caller.
+    ///
+    /// See [`LdpcDecoder::decode_into`]. This function does not allocate any
+    /// memory.
+    pub fn decode_into(
+        &mut self,
+        llrs: &[f64],
+        out_codeword: &mut [u8],
+        max_iterations: usize,
+    ) -> Result<usize, usize> {
+        self.decode_from(llrs, out_codeword, max_iterations)
+    }
+
+    /// Decodes a codeword from an LLR source into a buffer provided by the
+    /// caller.
+    ///
+    /// This is similar to [`Decoder::decode_into`], but the LLRs are read
+    /// from an [`LlrSource`], such as a [`StridedLlrs`] view, without copying
+    /// them first.
+    pub fn decode_from<L: LlrSource + ?Sized>(
+        &mut self,
+        llrs: &L,
+        out_codeword: &mut [u8],
+        max_iterations: usize,
+    ) -> Result<usize, usize> {
+        assert_eq!(llrs.len(), self.input_llrs.len());
+        self.max_llr_magnitude = None;
+        #[cfg(feature = "profiling")]
+        {
+            self.counters = DecoderCounters::default();
+        }
+        let input_llrs_hard_decision = |x| x <= 0.0;
+        if self
+            .graph
+            .check_parity_by(|v| input_llrs_hard_decision(llrs.llr(v)))
+        {
+            // No bit errors case
+            if self.llr_magnitude_tracking {
+                update_max_magnitude(&mut self.max_llr_magnitude, source_llrs(llrs), |x| {
+                    self.arithmetic
+                        .llr_magnitude(self.arithmetic.input_llr_quantize(x))
+                });
+            }
+            for (x, y) in self.output_llrs.iter_mut().zip(source_llrs(llrs)) {
+                *x = self.arithmetic.input_llr_quantize(y);
+            }
+            self.decoded = true;
+            write_hard_decisions(source_llrs(llrs), out_codeword, input_llrs_hard_decision);
+            return Ok(0);
+        }
+        self.initialize(llrs);
+        self.decoded = true;
+        self.track_llr_magnitudes();
+        for iteration in 1..=max_iterations {
+            for group in 0..self.groups.len() {
+                self.process_group(group, iteration);
+            }
+            self.track_llr_magnitudes();
+            #[cfg(feature = "profiling")]
+            {
+                // Each group updates its check nodes, and each variable node
+                // receives and sends a message through each of its edges
+                let check_node_updates = self
+                    .groups
+                    .iter()
+                    .map(|group| group.checks.len() as u64)
+                    .sum();
+                self.counters += DecoderCounters {
+                    check_node_updates,
+                    variable_node_updates: self.graph.num_vars() as u64,
+                    messages: 2 * self.graph.num_edges() as u64,
+                };
+            }
+            if self
+                .graph
+                .check_parity(&self.output_llrs, |x| self.arithmetic.llr_hard_decision(x))
+            {
+                // Decode succeeded
+                self.write_hard_decisions(out_codeword);
+                return Ok(iteration);
+            }
+        }
+        // Decode failed
+        self.write_hard_decisions(out_codeword);
+        Err(max_iterations)
+    }
+
+    fn write_hard_decisions(&self, out_codeword: &mut [u8]) {
+        write_hard_decisions(self.output_llrs.iter().copied(), out_codeword, |x| {
+            self.arithmetic.llr_hard_decision(x)
+        });
+    }
+
+    fn initialize<L: LlrSource + ?Sized>(&mut self, llrs: &L) {
+        for ((x, z), y) in self
+            .input_llrs
+            .iter_mut()
+            .zip(self.output_llrs.iter_mut())
+            .zip(source_llrs(llrs))
+        {
+            *x = self.arithmetic.input_llr_quantize(y);
+            *z = *x;
+        }
+        // First variable messages use only input LLRs
+        for (v, &llr) in self.input_llrs.iter().enumerate() {
+            for &c in self.graph.var_neighbours(v) {
+                self.variable_messages
+                    .send(v, c, self.arithmetic.llr_to_var_message(llr));
+            }
+        }
+        #[cfg(feature = "profiling")]
+        {
+            self.counters.messages += self.graph.num_edges() as u64;
+        }
+    }
+
+    fn track_llr_magnitudes(&mut self) {
+        if self.llr_magnitude_tracking {
+            update_max_magnitude(
+                &mut self.max_llr_magnitude,
+                self.output_llrs.iter().copied(),
+                |x| self.arithmetic.llr_magnitude(x),
+            );
+        }
+    }
+
+    fn process_group(&mut self, group: usize, iteration: usize) {
+        let Group { vars, checks } = &self.groups[group];
+        // Check messages for the variable nodes of the group, computed with
+        // the most recent variable messages
+        for &c in checks.iter() {
+            let messages = &self.variable_messages.per_destination[c];
+            let context = NodeContext::new(iteration, messages.len()).with_node(c);
+            let check_messages = &mut self.check_messages;
+            self.arithmetic
+                .send_check_messages(messages, context, |msg| {
+                    if vars.contains(&msg.dest) {
+                        check_messages.send(c, msg.dest, msg.value);
+                    }
+                });
+        }
+        for v in vars.clone() {
+            let messages = &self.check_messages.per_destination[v];
+            let context = NodeContext::new(iteration, messages.len()).with_node(v);
+            let var_messages = &mut self.variable_messages;
+            self.output_llrs[v] =
+                self.arithmetic
+                    .send_var_messages(self.input_llrs[v], messages, context, |msg| {
+                        var_messages.send(v, msg.dest, msg.value)
+                    });
+        }
+    }
+}
+
+impl<A: DecoderArithmetic> LdpcDecoder for Decoder<A> {
+    fn decode(
+        &mut self,
+        llrs: &[f64],
+        max_iterations: usize,
+    ) -> Result<DecoderOutput, DecoderOutput> {
+        Decoder::decode(self, llrs, max_iterations)
+    }
+
+    fn decode_into(
+        &mut self,
+        llrs: &[f64],
+        out_codeword: &mut [u8],
+        max_iterations: usize,
+    ) -> Result<usize, usize> {
+        Decoder::decode_into(self, llrs, out_codeword, max_iterations)
+    }
+
+    fn decode_strided(
+        &mut self,
+        llrs: StridedLlrs<'_>,
+        out_codeword: &mut [u8],
+        max_iterations: usize,
+    ) -> Result<usize, usize> {
+        Decoder::decode_from(self, &llrs, out_codeword, max_iterations)
+    }
+
+    fn set_llr_magnitude_tracking(&mut self, enabled: bool) {
+        self.llr_magnitude_tracking = enabled;
+    }
+
+    fn max_llr_magnitude(&self) -> Option<f64> {
+        self.max_llr_magnitude
+    }
+
+    fn bit_reliabilities(&self) -> Option<Vec<f64>> {
+        if !self.decoded {
+            return None;
+        }
+        self.output_llrs
+            .iter()
+            .map(|&x| self.arithmetic.llr_magnitude(x))
+            .collect()
+    }
+
+    #[cfg(feature = "profiling")]
+    fn update_counters(&self) -> Option<DecoderCounters> {
+        Some(self.counters)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::{
+        arithmetic::{Aminstari8, Phif64},
+        flooding,
+    };
+    use super::*;
+    use crate::codes::{
+        ccsds::{AR4JACode, AR4JAInfoSize, AR4JARate},
+        examples::ExampleCode,
+    };
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+
+    fn test_decoder() -> Decoder<Phif64> {
+        // Example 2.5 in Sarah J. Johnson - Iterative Error Correction
+        let h = ExampleCode::Johnson2_5.h();
+        Decoder::new(h, Phif64::new())
+    }
+
+    fn to_llrs(bits: &[u8]) -> Vec<f64> {
+        bits.iter()
+            .map(|&b| if b == 0 { 1.3863 } else { -1.3863 })
+            .collect()
+    }
+
+    #[test]
+    fn single_error() {
+        let mut decoder = test_decoder();
+        let codeword_good = [0, 0, 1, 0, 1, 1];
+        for j in 0..codeword_good.len() {
+            let mut codeword_bad = codeword_good;
+            codeword_bad[j] ^= 1;
+            let output = decoder.decode(&to_llrs(&codeword_bad), 100).unwrap();
+            assert_eq!(&output.codeword, &codeword_good);
+            assert_eq!(output.corrected_bits, 1);
+        }
+    }
+
+    #[test]
+    fn groups() {
+        let h = AR4JACode::new(AR4JARate::R1_2, AR4JAInfoSize::K1024).h();
+        let graph = Arc::new(CompiledGraph::new(h));
+        let decoder = Decoder::from_graph(Arc::clone(&graph), Phif64::new());
+        assert_eq!(decoder.num_groups(), graph.num_vars());
+        let decoder = Decoder::with_group_size(Arc::clone(&graph), Phif64::new(), 512);
+        assert_eq!(decoder.num_groups(), 5);
+        let decoder = Decoder::with_group_size(graph, Phif64::new(), 1000);
+        assert_eq!(decoder.num_groups(), 3);
+    }
+
+    fn noisy_llrs(rng: &mut ChaCha8Rng) -> Vec<f64> {
+        // All-zeros AR4JA codeword with noise. The punctured bits have zero
+        // LLR.
+        (0..2560)
+            .map(|j| {
+                if j >= 2048 {
+                    0.0
+                } else {
+                    2.0 + 5.0 * (rng.gen::<f64>() - 0.5)
+                }
+            })
+            .collect()
+    }
+
+    fn ar4ja_graph() -> Arc<CompiledGraph> {
+        let h = AR4JACode::new(AR4JARate::R1_2, AR4JAInfoSize::K1024).h();
+        Arc::new(CompiledGraph::new(h))
+    }
+
+    #[test]
+    fn faster_than_flooding() {
+        let graph = ar4ja_graph();
+        let mut flooding = flooding::Decoder::from_graph(Arc::clone(&graph), Phif64::new());
+        let mut shuffled = Decoder::from_graph(Arc::clone(&graph), Phif64::new());
+        let mut group_shuffled = Decoder::with_group_size(graph, Phif64::new(), 128);
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        for _ in 0..10 {
+            let llrs = noisy_llrs(&mut rng);
+            let flooding = flooding.decode(&llrs, 100).unwrap().iterations;
+            for decoder in [&mut shuffled, &mut group_shuffled] {
+                let output = decoder.decode(&llrs, 100).unwrap();
+                assert!(output.codeword.iter().all(|&b| b == 0));
+                assert!(output.iterations < flooding);
+            }
+        }
+    }
+
+    fn compare_with_flooding<A: DecoderArithmetic + Clone>(arithmetic: A) {
+        // With a single group, the schedule is the flooding schedule
+        let graph = ar4ja_graph();
+        let mut flooding = flooding::Decoder::from_graph(Arc::clone(&graph), arithmetic.clone());
+        let mut vertical = Decoder::with_group_size(graph, arithmetic, 2560);
+        assert_eq!(vertical.num_groups(), 1);
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        for _ in 0..10 {
+            let llrs = noisy_llrs(&mut rng);
+            assert_eq!(flooding.decode(&llrs, 20), vertical.decode(&llrs, 20));
+        }
+    }
+
+    #[test]
+    fn single_group_same_as_flooding() {
+        compare_with_flooding(Phif64::new());
+        compare_with_flooding(Aminstari8::new());
+    }
+}
@@ -0,0 +1,248 @@
+//! Sliding window decoder.
+//!
+//! This module contains a [`WindowedDecoder`], which decodes codes whose parity
+//! check matrix is stored in a [`ChunkedMatrix`], such as long spatially
+//! coupled LDPC chains, without reading the whole matrix into memory.
+//!
+//! The codeword is decoded one window of the chunked matrix at a time. To
+//! decode a target window, the decoder reads the target window, the
+//! `window_size - 1` windows that follow it and the window that precedes it.
+//! It forms the submatrix given by the columns of these windows and the rows
+//! whose entries are all contained in them, and decodes it with a decoder
+//! built by a [`DecoderFactory`]. The bits of the preceding window have already
+//! been decided, so they are given saturated LLRs. Only the decisions for the
+//! bits of the target window are kept, and then the window slides by one
+//! window. Thus the memory usage is bounded by the size of `window_size + 1`
+//! windows, besides the LLRs, the decoded codeword and the row weights of the
+//! matrix.
+//!
+//! Rows with entries in columns more than one window before the target window
+//! are never included in a submatrix, so the windows of the chunked matrix
+//! should be at least as wide as the coupling memory of the code.
+//!
+//! # Examples
+//! ```
+//! # use ldpc_toolbox::{
+//! #     codes::examples::ExampleCode,
+//! #     decoder::{factory::DecoderImplementation, windowed::WindowedDecoder},
+//! #     sparse::chunked::ChunkedMatrix,
+//! # };
+//! let h = ExampleCode::Qc42_21.h();
+//! let path = std::env::temp_dir().join("ldpc-toolbox-windowed-doctest.bin");
+//! let chunked = ChunkedMatrix::from_sparse(&h, &path, 21)?;
+//! let decoder = WindowedDecoder::new(DecoderImplementation::Phif64, &chunked, 2, 50)?;
+//! // Decode the all-zeros codeword
+//! let output = decoder.decode(&[1.5; 42])?;
+//! assert_eq!(output.codeword, vec![0; 42]);
+//! assert_eq!(output.failed_windows, 0);
+//! # std::fs::remove_file(&path)?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use super::factory::DecoderFactory;
+use crate::sparse::{
+    chunked::{ChunkedMatrix, Result, Window},
+    SparseMatrix,
+};
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// LLR given to the bits that have already been decided.
+///
+/// This has the same magnitude as
+/// [`KNOWN_BIT_LLR`](crate::simulation::known_bits::KNOWN_BIT_LLR).
+const DECIDED_BIT_LLR: f64 = 1000.0;
+
+/// Sliding window decoder.
+///
+/// See the [module documentation](self).
+#[derive(Debug)]
+pub struct WindowedDecoder<'a, F> {
+    factory: F,
+    h: &'a ChunkedMatrix,
+    window_size: usize,
+    max_iterations: usize,
+    row_weights: Vec<usize>,
+}
+
+/// Output of the sliding window decoder.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct WindowedOutput {
+    /// Decoded codeword.
+    ///
+    /// Contains the hard decision bits of the decoded codeword.
+    pub codeword: Vec<u8>,
+    /// Total number of iterations used in the decoding of all the windows.
+    pub iterations: usize,
+    /// Number of windows in which decoding failed.
+    ///
+    /// The decisions for the bits of these windows are taken from the hard
+    /// decision on the final decoder LLRs.
+    pub failed_windows: usize,
+}
+
+impl<'a, F: DecoderFactory> WindowedDecoder<'a, F> {
+    /// Creates a new sliding window decoder.
+    ///
+    /// The parameters are the factory of the decoder used for each window, the
+    /// chunked parity check matrix `h`, the number of windows of the matrix
+    /// that are decoded together, and the maximum number of iterations
+    /// performed in each window. The matrix is read once to compute its row
+    /// weights.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `window_size` is zero.
+    pub fn new(
+        factory: F,
+        h: &'a ChunkedMatrix,
+        window_size: usize,
+        max_iterations: usize,
+    ) -> Result<WindowedDecoder<'a, F>> {
+        assert!(window_size > 0);
+        Ok(WindowedDecoder {
+            factory,
+            h,
+            window_size,
+            max_iterations,
+            row_weights: h.row_weights()?,
+        })
+    }
+
+    /// Decodes a codeword.
+    ///
+    /// The parameter contains the LLRs for the received codeword. The windows
+    /// of the matrix are read from the file as the decoder slides through
+    /// them.
+    pub fn decode(&self, llrs: &[f64]) -> Result<WindowedOutput> {
+        assert_eq!(llrs.len(), self.h.num_cols());
+        let num_windows = self.h.num_windows();
+        let mut output = WindowedOutput {
+            codeword: Vec::with_capacity(llrs.len()),
+            iterations: 0,
+            failed_windows: 0,
+        };
+        // Windows from the one preceding the target up to the end of the span
+        let mut loaded: VecDeque<Window> = VecDeque::with_capacity(self.window_size + 1);
+        let mut next_window = 0;
+        for target in 0..num_windows {
+            let end = (target + self.window_size).min(num_windows);
+            while next_window < end {
+                loaded.push_back(self.h.window(next_window)?);
+                next_window += 1;
+            }
+            while loaded.len() > end - target.saturating_sub(1) {
+                loaded.pop_front();
+            }
+            let span_start = loaded.front().unwrap().cols().start;
+            let span_end = loaded.back().unwrap().cols().end;
+            let target_cols = target * self.h.window_cols()
+                ..((target + 1) * self.h.window_cols()).min(self.h.num_cols());
+
+            let h = self.submatrix(&loaded, span_start, target_cols.start);
+            let span_llrs = (span_start..span_end)
+                .map(|col| {
+                    if col < target_cols.start {
+                        if output.codeword[col] == 0 {
+                            DECIDED_BIT_LLR
+                        } else {
+                            -DECIDED_BIT_LLR
+                        }
+                    } else {
+                        llrs[col]
+                    }
+                })
+                .collect::<Vec<_>>();
+            let mut decoder = self.factory.build_decoder(h);
+            let decoded = decoder
+                .decode(&span_llrs, self.max_iterations)
+                .unwrap_or_else(|failed| {
+                    output.failed_windows += 1;
+                    failed
+                });
+            output.iterations += decoded.iterations;
+            output.codeword.extend_from_slice(
+                &decoded.codeword[target_cols.start - span_start..target_cols.end - span_start],
+            );
+        }
+        Ok(output)
+    }
+
+    // Returns the submatrix formed by the columns of the loaded windows and the
+    // rows whose entries are all contained in these windows and which have
+    // some entry in a column that has not been decided yet.
+    fn submatrix(
+        &self,
+        loaded: &VecDeque<Window>,
+        span_start: usize,
+        undecided_start: usize,
+    ) -> SparseMatrix {
+        let mut counts = HashMap::new();
+        let mut undecided = HashSet::new();
+        for window in loaded {
+            for col in window.cols() {
+                for &row in window.iter_col(col) {
+                    *counts.entry(row).or_insert(0) += 1;
+                    if col >= undecided_start {
+                        undecided.insert(row);
+                    }
+                }
+            }
+        }
+        let mut rows = counts
+            .into_iter()
+            .filter(|&(row, count)| {
+                count == self.row_weights[row as usize] && undecided.contains(&row)
+            })
+            .map(|(row, _)| row)
+            .collect::<Vec<_>>();
+        rows.sort_unstable();
+        let span_end = loaded.back().unwrap().cols().end;
+        let mut h = SparseMatrix::new(rows.len(), span_end - span_start);
+        for window in loaded {
+            for col in window.cols() {
+                for row in window.iter_col(col) {
+                    if let Ok(j) = rows.binary_search(row) {
+                        h.insert(j, col - span_start);
+                    }
+                }
+            }
+        }
+        h
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{codes::examples::ExampleCode, decoder::factory::DecoderImplementation};
+
+    #[test]
+    fn corrects_errors() {
+        // Chain of copies of a code, with one copy per window
+        let copies = 4;
+        let h0 = ExampleCode::Qc42_21.h();
+        let (m, n) = (h0.num_rows(), h0.num_cols());
+        let mut h = SparseMatrix::new(copies * m, copies * n);
+        for copy in 0..copies {
+            for (row, col) in h0.iter_all() {
+                h.insert(copy * m + row, copy * n + col);
+            }
+        }
+        let path = std::env::temp_dir().join(format!(
+            "ldpc-toolbox-windowed-test-{}.bin",
+            std::process::id()
+        ));
+        let chunked = ChunkedMatrix::from_sparse(&h, &path, n).unwrap();
+        // All-zeros codeword with an unreliable bit in error in each copy
+        let mut llrs = vec![2.0; h.num_cols()];
+        for copy in 0..copies {
+            llrs[copy * n + 5 * copy] = -0.5;
+        }
+        let decoder = WindowedDecoder::new(DecoderImplementation::Phif64, &chunked, 2, 50).unwrap();
+        let output = decoder.decode(&llrs).unwrap();
+        assert_eq!(output.codeword, vec![0; h.num_cols()]);
+        assert_eq!(output.failed_windows, 0);
+        assert!(output.iterations > 0);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -6,9 +6,9 @@
 //! these codes, the encoder uses the first k symbols of the codeword as
 //! systematic.
 //!
-//! There are two cases handled by the encoder, depending on the structure of
-//! the parity check matrix H = [H0 H1], where H1 is square. In both cases, the
-//! matrix H1 is required to be invertible.
+//! There are three cases handled by the encoder, depending on the structure of
+//! the parity check matrix H = [H0 H1], where H1 is square. In all the cases,
+//! the matrix H1 is required to be invertible.
 //!
 //! The first case is the case of a "staircase-type" LDPC code (this is the case
 //! for DVB-S2 codes, for example). In this case, H1 has its main diagonal and
@@ -17,17 +17,76 @@
 //! (as a column vector on the right) and by computing the n-k running sums of
 //! the components of the resulting vector of size n-k.
 //!
-//! In the second case (non staircase-type), the encoder computes G0 =
+//! The second case is the case of the CCSDS AR4JA codes, which is detected by
+//! comparing H with the parity check matrices of all the AR4JA codes. The
+//! encoder uses the block structure of H, which is formed by sums of
+//! permutation matrices composed of circulant blocks, to reduce the encoding to
+//! the multiplication by the inverse of a 4 x 4 matrix of circulant blocks. This
+//! avoids a dense Gaussian elimination on the large matrices of the codes with
+//! longer block sizes.
+//!
+//! In the third case (neither staircase-type nor AR4JA), the encoder computes G0 =
 //! H1^{-1}H0, which in general is a dense matrix. To encode a message, the
 //! matrix G0 is multiplied by the k message bits (as a column vector on the
 //! right) to obtain the n-k parity check bits. In this case, the encoding
-//! complexity is O(n^2).
+//! complexity is O(n^2). For large codes, for which computing G0 is too
+//! expensive, the encoder instead factorizes H1 with the
+//! [`SparseSolver`](crate::linalg::SparseSolver), and obtains the parity check
+//! bits by solving H1 p = H0 s, where s are the k message bits.
+//!
+//! If the square matrix formed by the last n-k columns of H is not invertible
+//! but H has full rank, the encoder can be constructed with
+//! [`Encoder::from_h_with_permutation`], which permutes the columns of H with
+//! [`parity_to_systematic`](crate::systematic::parity_to_systematic) and
+//! stores the permutation. The encoder still outputs codewords in the order
+//! given by the columns of H, and the message bits can be read from a decoded
+//! codeword with [`Encoder::systematic_order`].
+//!
+//! Codes whose generator matrix is published as systematic circulants, rather
+//! than their parity check matrix, can be encoded directly with those
+//! circulants by constructing the encoder with [`Encoder::from_qc_generator`]
+//! (see the [`qc_generator`] module).
+//!
+//! Codes that do not satisfy these requirements can be encoded with the encoder
+//! in the [`erasure`] module, which treats the parity bits as erasures and
+//! recovers them with an erasure decoder.
+//!
+//! By default, the encoders output the codeword in the order given by the
+//! columns of H, which for the systematic encoder is [systematic | parity].
+//! Standards that transmit the codeword bits in a different order can be
+//! supported by giving an [`OutputOrder`] to the encoder. The same encoder can
+//! then be used to restore the order of the received LLRs before decoding, as
+//! shown below.
+//!
+//! # Examples
+//! ```
+//! # use ldpc_toolbox::{
+//! #     codes::{ccsds::{AR4JACode, AR4JAInfoSize, AR4JARate}, Code},
+//! #     encoder::{Encoder, OutputOrder},
+//! #     gf2::GF2,
+//! # };
+//! # use ndarray::Array1;
+//! # use num_traits::{One, Zero};
+//! let h = AR4JACode::new(AR4JARate::R1_2, AR4JAInfoSize::K1024).h();
+//! let encoder = Encoder::from_h(&h)?;
+//! let parity_first = Encoder::from_h(&h)?.with_output_order(OutputOrder::ParityFirst)?;
+//! let message = Array1::from_iter((0..1024).map(|j| if j % 3 == 0 { GF2::one() } else { GF2::zero() }));
+//! let transmitted = parity_first.encode(&message);
+//! assert_eq!(transmitted.slice(ndarray::s![1536..]), message);
+//! // On the receive side, the original order is restored before decoding
+//! let restored = parity_first.restore_order(transmitted.as_slice().unwrap());
+//! assert_eq!(restored, encoder.encode(&message).to_vec());
+//! # Ok::<(), ldpc_toolbox::encoder::Error>(())
+//! ```
 
 use crate::{gf2::GF2, linalg, sparse::SparseMatrix};
 use ndarray::{s, Array1, Array2, ArrayBase, Data, Ix1};
 use num_traits::One;
 use thiserror::Error;
 
+mod ar4ja;
+pub mod erasure;
+pub mod qc_generator;
 mod staircase;
 
 /// LDPC encoder error.
@@ -37,23 +96,199 @@ pub enum Error {
     /// matrix is not invertible, so the encoder cannot be constructed.
     #[error("the square matrix formed by the last columns of the parity check is not invertible")]
     SubmatrixNotInvertible,
+    /// The output permutation is not a permutation of the codeword positions.
+    #[error("the output permutation is not a permutation of the codeword positions")]
+    InvalidPermutation,
+    /// The parity check matrix cannot be permuted to support systematic
+    /// encoding.
+    #[error(transparent)]
+    Systematic(#[from] crate::systematic::Error),
 }
 
 /// LDPC systematic encoder.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Encoder {
     encoder: EncoderType,
+    // Column of H placed at each position of [systematic | parity]. None
+    // represents the identity.
+    columns: Option<Box<[usize]>>,
+    order: OutputPermutation,
+}
+
+/// Transmission order of the codeword bits.
+///
+/// This indicates the order in which an encoder outputs the bits of the
+/// codeword.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub enum OutputOrder {
+    /// The order given by the columns of the parity check matrix, which is
+    /// [systematic | parity] for the systematic encoder.
+    #[default]
+    Natural,
+    /// The parity bits first, followed by the information bits.
+    ///
+    /// Each group of bits keeps the order given by the columns of the parity
+    /// check matrix.
+    ParityFirst,
+    /// A custom permutation.
+    ///
+    /// The bit in position `j` of the output is the bit in position
+    /// `permutation[j]` of the codeword in the natural order.
+    Permutation(Vec<usize>),
+}
+
+impl std::str::FromStr for OutputOrder {
+    type Err = String;
+
+    /// Parses an output order.
+    ///
+    /// The accepted formats are `natural`, `parity-first`, and a permutation
+    /// given as a list of comma-separated positions, such as `3,0,2,1`.
+    fn from_str(s: &str) -> Result<OutputOrder, String> {
+        Ok(match s {
+            "natural" => OutputOrder::Natural,
+            "parity-first" => OutputOrder::ParityFirst,
+            _ => OutputOrder::Permutation(
+                s.split(',')
+                    .map(|x| x.trim().parse())
+                    .collect::<Result<_, _>>()
+                    .map_err(|_| format!("invalid output order {s}"))?,
+            ),
+        })
+    }
+}
+
+impl std::fmt::Display for OutputOrder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OutputOrder::Natural => write!(f, "natural"),
+            OutputOrder::ParityFirst => write!(f, "parity-first"),
+            OutputOrder::Permutation(permutation) => {
+                for (j, x) in permutation.iter().enumerate() {
+                    if j != 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{x}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+// Permutation applied to the codeword in natural order to obtain the output.
+// None represents the identity.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct OutputPermutation(Option<Box<[usize]>>);
+
+impl OutputPermutation {
+    pub(crate) fn new(
+        order: OutputOrder,
+        information: &[usize],
+        parity: &[usize],
+    ) -> Result<OutputPermutation, Error> {
+        let n = information.len() + parity.len();
+        Ok(OutputPermutation(match order {
+            OutputOrder::Natural => None,
+            OutputOrder::ParityFirst => Some(parity.iter().chain(information).copied().collect()),
+            OutputOrder::Permutation(permutation) => {
+                let mut seen = vec![false; n];
+                if permutation.len() != n {
+                    return Err(Error::InvalidPermutation);
+                }
+                for &j in &permutation {
+                    if j >= n || std::mem::replace(&mut seen[j], true) {
+                        return Err(Error::InvalidPermutation);
+                    }
+                }
+                Some(permutation.into_boxed_slice())
+            }
+        }))
+    }
+
+    pub(crate) fn apply(&self, codeword: Array1<GF2>) -> Array1<GF2> {
+        match &self.0 {
+            None => codeword,
+            Some(permutation) => Array1::from_iter(permutation.iter().map(|&j| codeword[j])),
+        }
+    }
+
+    pub(crate) fn restore<T: Clone>(&self, received: &[T]) -> Vec<T> {
+        match &self.0 {
+            None => received.to_vec(),
+            Some(permutation) => {
+                assert_eq!(received.len(), permutation.len());
+                let mut restored = received.to_vec();
+                for (&j, x) in permutation.iter().zip(received.iter()) {
+                    restored[j] = x.clone();
+                }
+                restored
+            }
+        }
+    }
+}
+
+/// Encoding method.
+///
+/// This enum lists the methods that can be used by an [`Encoder`], depending on
+/// the structure of the parity check matrix. See the [module
+/// documentation](self) for a description of each method.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum EncodingMethod {
+    /// Encoding of a staircase-type code, with O(n) complexity.
+    Staircase,
+    /// Encoding of a CCSDS AR4JA code using its circulant block structure.
+    Ar4ja,
+    /// Encoding with a dense generator matrix, with O(n^2) complexity.
+    DenseGenerator,
+    /// Encoding by solving a sparse linear system, used for large codes.
+    SparseSolver,
+    /// Encoding with the circulants of a quasi-cyclic generator matrix.
+    QcGenerator,
+}
+
+impl std::fmt::Display for EncodingMethod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                EncodingMethod::Staircase => "staircase",
+                EncodingMethod::Ar4ja => "AR4JA circulant blocks",
+                EncodingMethod::DenseGenerator => "dense generator matrix",
+                EncodingMethod::SparseSolver => "sparse linear system",
+                EncodingMethod::QcGenerator => "quasi-cyclic generator circulants",
+            }
+        )
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 enum EncoderType {
     // Encoder with a general dense generator matrix for the parity.
-    DenseGenerator { gen_matrix: Array2<GF2> },
+    DenseGenerator {
+        gen_matrix: Array2<GF2>,
+    },
     // Encoder for a staircase type (repeat-accumulate) code. The encoder sparse
     // matrix computes the parity data before accumulation.
-    Staircase { gen: SparseMatrix },
+    Staircase {
+        gen: SparseMatrix,
+    },
+    // Encoder for the CCSDS AR4JA codes.
+    Ar4ja(Box<ar4ja::Ar4jaEncoder>),
+    // Encoder that solves H1 p = H0 s with a sparse factorization of H1.
+    SparseSolver {
+        h0: SparseMatrix,
+        solver: Box<linalg::SparseSolver>,
+    },
+    // Encoder with the circulants of a systematic QC generator matrix.
+    QcGenerator(Box<qc_generator::QcGenerator>),
 }
 
+// Maximum size of the generator matrix G0 for which the dense generator encoder
+// is used. Larger codes use the sparse solver encoder.
+const DENSE_GENERATOR_MAX_SIZE: usize = 1 << 22;
+
 impl Encoder {
     /// Creates the systematic encoder corresponding to a parity check matrix.
     pub fn from_h(h: &SparseMatrix) -> Result<Encoder, Error> {
@@ -72,6 +307,10 @@ impl Encoder {
                 }
             }
             EncoderType::Staircase { gen }
+        } else if let Some(code) = ar4ja::detect(h) {
+            EncoderType::Ar4ja(Box::new(ar4ja::Ar4jaEncoder::new(&code, h.clone())))
+        } else if n * (m - n) > DENSE_GENERATOR_MAX_SIZE {
+            Self::sparse_solver(h)?
         } else {
             // General case, in which the generator matrix is obtained by
             // Gaussian reduction (it will be a dense matrix in general).
@@ -86,21 +325,269 @@ impl Encoder {
 
             match linalg::gauss_reduction(&mut a) {
                 Ok(()) => (),
-                Err(linalg::Error::NotInvertible) => return Err(Error::SubmatrixNotInvertible),
+                Err(_) => return Err(Error::SubmatrixNotInvertible),
             };
 
             let gen_matrix = a.slice(s![.., n..]).to_owned();
             EncoderType::DenseGenerator { gen_matrix }
         };
-        Ok(Encoder { encoder })
+        Ok(Encoder {
+            encoder,
+            columns: None,
+            order: OutputPermutation::default(),
+        })
+    }
+
+    /// Creates an encoder from a systematic quasi-cyclic generator matrix.
+    ///
+    /// The encoder computes the parity bits with the circulants of the
+    /// generator, so the parity check matrix of the code is not needed. The
+    /// codewords are [systematic | parity], as for [`Encoder::from_h`] with the
+    /// matrix returned by [`QcGenerator::h`](qc_generator::QcGenerator::h).
+    pub fn from_qc_generator(generator: qc_generator::QcGenerator) -> Encoder {
+        Encoder {
+            encoder: EncoderType::QcGenerator(Box::new(generator)),
+            columns: None,
+            order: OutputPermutation::default(),
+        }
+    }
+
+    /// Creates a systematic encoder for any full-rank parity check matrix.
+    ///
+    /// If the square submatrix formed by the last columns of `h` is invertible,
+    /// this is the same as [`Encoder::from_h`]. Otherwise, the columns of `h`
+    /// are permuted with
+    /// [`parity_to_systematic_with_permutation`](crate::systematic::parity_to_systematic_with_permutation)
+    /// and the encoder is constructed for the permuted matrix. The encoder
+    /// outputs the codewords of `h` in the order given by its columns, so the
+    /// message bits are not the first bits of the codeword. They are in the
+    /// positions given by the first elements of the [column
+    /// permutation](Encoder::column_permutation), and can be extracted with
+    /// [`Encoder::systematic_order`].
+    pub fn from_h_with_permutation(h: &SparseMatrix) -> Result<Encoder, Error> {
+        match Encoder::from_h(h) {
+            Err(Error::SubmatrixNotInvertible) => {
+                let (h_sys, permutation) =
+                    crate::systematic::parity_to_systematic_with_permutation(h)?;
+                let mut encoder = Encoder::from_h(&h_sys)?;
+                encoder.columns = Some(permutation.into_boxed_slice());
+                Ok(encoder)
+            }
+            result => result,
+        }
+    }
+
+    /// Returns the column permutation used by the encoder.
+    ///
+    /// The element `j` of the permutation is the column of the parity check
+    /// matrix that holds the bit `j` of [systematic | parity]. This returns
+    /// `None` if the columns are not permuted, which is always the case for an
+    /// encoder constructed with [`Encoder::from_h`].
+    pub fn column_permutation(&self) -> Option<&[usize]> {
+        self.columns.as_deref()
+    }
+
+    /// Puts a codeword in [systematic | parity] order.
+    ///
+    /// The codeword (for instance, the output of a decoder) is given in the
+    /// order of the columns of the parity check matrix. The first k elements
+    /// of the returned vector are the message bits. This undoes the [column
+    /// permutation](Encoder::column_permutation), so it only copies the
+    /// codeword if the columns are not permuted.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the length of `codeword` is not equal to the
+    /// codeword length.
+    pub fn systematic_order<T: Clone>(&self, codeword: &[T]) -> Vec<T> {
+        let (_, n) = self.dimensions();
+        assert_eq!(codeword.len(), n);
+        match &self.columns {
+            None => codeword.to_vec(),
+            Some(columns) => columns.iter().map(|&j| codeword[j].clone()).collect(),
+        }
+    }
+
+    // Encoder that uses the sparse solver, with H = [H0 H1].
+    fn sparse_solver(h: &SparseMatrix) -> Result<EncoderType, Error> {
+        let n = h.num_rows();
+        let k = h.num_cols() - n;
+        let mut h0 = SparseMatrix::new(n, k);
+        let mut h1 = SparseMatrix::new(n, n);
+        for (j, t) in h.iter_all() {
+            if t < k {
+                h0.insert(j, t);
+            } else {
+                h1.insert(j, t - k);
+            }
+        }
+        let solver = linalg::SparseSolver::new(&h1);
+        if solver.rank() != n {
+            return Err(Error::SubmatrixNotInvertible);
+        }
+        Ok(EncoderType::SparseSolver {
+            h0,
+            solver: Box::new(solver),
+        })
+    }
+
+    /// Sets the order in which the encoder outputs the codeword bits.
+    ///
+    /// By default, the encoder outputs the codeword in the order given by the
+    /// columns of the parity check matrix, which is [systematic | parity]
+    /// unless the [columns are permuted](Encoder::column_permutation). The
+    /// parity-first order puts the parity bits first in any case. This returns an
+    /// error if `order` is a permutation that is not a valid permutation of
+    /// the codeword positions.
+    pub fn with_output_order(mut self, order: OutputOrder) -> Result<Encoder, Error> {
+        let (k, n) = self.dimensions();
+        let positions = match &self.columns {
+            None => (0..n).collect::<Vec<_>>(),
+            Some(columns) => columns.to_vec(),
+        };
+        let (information, parity) = positions.split_at(k);
+        self.order = OutputPermutation::new(order, information, parity)?;
+        Ok(self)
+    }
+
+    /// Restores the order of a received codeword.
+    ///
+    /// This applies the inverse of the [output order](Encoder::with_output_order)
+    /// to the received codeword (for instance, to its LLRs), so that it is in
+    /// the order given by the columns of the parity check matrix, which is the
+    /// order used by the decoders.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the length of `received` is not equal to the
+    /// codeword length.
+    pub fn restore_order<T: Clone>(&self, received: &[T]) -> Vec<T> {
+        self.order.restore(received)
+    }
+
+    // Returns the message length and codeword length.
+    fn dimensions(&self) -> (usize, usize) {
+        match &self.encoder {
+            EncoderType::DenseGenerator { gen_matrix } => {
+                (gen_matrix.ncols(), gen_matrix.ncols() + gen_matrix.nrows())
+            }
+            EncoderType::Staircase { gen } => (gen.num_cols(), gen.num_cols() + gen.num_rows()),
+            EncoderType::Ar4ja(encoder) => encoder.dimensions(),
+            EncoderType::SparseSolver { h0, .. } => (h0.num_cols(), h0.num_cols() + h0.num_rows()),
+            EncoderType::QcGenerator(generator) => {
+                (generator.message_length(), generator.codeword_length())
+            }
+        }
+    }
+
+    /// Returns the encoding method used by the encoder.
+    pub fn method(&self) -> EncodingMethod {
+        match &self.encoder {
+            EncoderType::DenseGenerator { .. } => EncodingMethod::DenseGenerator,
+            EncoderType::Staircase { .. } => EncodingMethod::Staircase,
+            EncoderType::Ar4ja(_) => EncodingMethod::Ar4ja,
+            EncoderType::SparseSolver { .. } => EncodingMethod::SparseSolver,
+            EncoderType::QcGenerator(_) => EncodingMethod::QcGenerator,
+        }
     }
 
     /// Encodes a message into a codeword.
+    ///
+    /// The codeword bits are given in the [output
+    /// order](Encoder::with_output_order) of the encoder.
     pub fn encode<S>(&self, message: &ArrayBase<S, Ix1>) -> Array1<GF2>
     where
         S: Data<Elem = GF2>,
     {
-        let parity = match &self.encoder {
+        let parity = self.parity(message);
+        let codeword =
+            ndarray::concatenate(ndarray::Axis(0), &[message.view(), parity.view()]).unwrap();
+        self.arrange(codeword)
+    }
+
+    /// Returns the generator matrix of the code.
+    ///
+    /// The generator matrix G has size k x n, and the codeword corresponding
+    /// to a message m (as a row vector on the left) is m G, so that
+    /// `encoder.encode(&m)` is equal to `m.dot(&g)`. The columns of G are
+    /// given in the [output order](Encoder::with_output_order) of the encoder
+    /// and follow the [column permutation](Encoder::column_permutation), if
+    /// any. Therefore, G has the systematic form [I | P] when the encoder uses
+    /// the natural order and does not permute the columns.
+    ///
+    /// For the dense generator encoding method, P is the transpose of the
+    /// matrix G0 stored by the encoder. For the other methods, the rows of G
+    /// are obtained by encoding each of the k unit vectors.
+    ///
+    /// # Examples
+    /// ```
+    /// # use ldpc_toolbox::{encoder::Encoder, gf2::GF2, sparse::SparseMatrix};
+    /// # use ndarray::{arr1, s, Array2};
+    /// # use num_traits::{One, Zero};
+    /// let mut h = SparseMatrix::new(2, 4);
+    /// h.insert_row(0, [0, 2].iter());
+    /// h.insert_row(1, [0, 1, 3].iter());
+    /// let encoder = Encoder::from_h(&h)?;
+    /// let g = encoder.generator_matrix();
+    /// assert_eq!(g.slice(s![.., ..2]), Array2::<GF2>::eye(2));
+    /// let message = arr1(&[GF2::one(), GF2::zero()]);
+    /// assert_eq!(message.dot(&g), encoder.encode(&message));
+    /// # Ok::<(), ldpc_toolbox::encoder::Error>(())
+    /// ```
+    pub fn generator_matrix(&self) -> Array2<GF2> {
+        let (k, n) = self.dimensions();
+        let mut g = Array2::zeros((k, n));
+        for (j, row) in self.generator_rows().enumerate() {
+            g.row_mut(j).assign(&row);
+        }
+        g
+    }
+
+    /// Returns the generator matrix of the code as a sparse matrix.
+    ///
+    /// The matrix is the same as the one returned by
+    /// [`Encoder::generator_matrix`], but it is constructed row by row, without
+    /// allocating a dense k x n matrix. This is useful to write the generator
+    /// matrix of large codes, such as the DVB-S2 codes, as an alist. Note that
+    /// the generator matrix of an LDPC code is usually not sparse. For
+    /// instance, for staircase-type codes each parity bit is a running sum.
+    pub fn generator_matrix_sparse(&self) -> SparseMatrix {
+        let (k, n) = self.dimensions();
+        let mut g = SparseMatrix::new(k, n);
+        for (j, row) in self.generator_rows().enumerate() {
+            g.insert_row(
+                j,
+                row.iter()
+                    .enumerate()
+                    .filter(|(_, x)| x.is_one())
+                    .map(|(c, _)| c),
+            );
+        }
+        g
+    }
+
+    // Returns the rows of the generator matrix in the output order.
+    fn generator_rows(&self) -> impl Iterator<Item = Array1<GF2>> + '_ {
+        let (k, _) = self.dimensions();
+        (0..k).map(move |j| {
+            let mut message = Array1::zeros(k);
+            message[j] = GF2::one();
+            let parity = match &self.encoder {
+                EncoderType::DenseGenerator { gen_matrix } => gen_matrix.column(j).to_owned(),
+                _ => self.parity(&message),
+            };
+            let codeword =
+                ndarray::concatenate(ndarray::Axis(0), &[message.view(), parity.view()]).unwrap();
+            self.arrange(codeword)
+        })
+    }
+
+    // Computes the parity bits of a message.
+    fn parity<S>(&self, message: &ArrayBase<S, Ix1>) -> Array1<GF2>
+    where
+        S: Data<Elem = GF2>,
+    {
+        match &self.encoder {
             EncoderType::DenseGenerator { gen_matrix } => gen_matrix.dot(message),
             EncoderType::Staircase { gen } => {
                 // initial parity (needs to be accumulated)
@@ -114,8 +601,35 @@ impl Encoder {
                 }
                 parity
             }
+            EncoderType::Ar4ja(encoder) => encoder.parity(message),
+            EncoderType::SparseSolver { h0, solver } => {
+                let syndrome = (0..h0.num_rows())
+                    .map(|j| h0.iter_row(j).map(|&k| message[k]).sum())
+                    .collect::<Vec<GF2>>();
+                Array1::from_vec(
+                    solver
+                        .solve(&syndrome)
+                        .expect("H1 is invertible, so the system has a solution"),
+                )
+            }
+            EncoderType::QcGenerator(generator) => generator.parity(message),
+        }
+    }
+
+    // Applies the column permutation and the output order to a codeword given
+    // as [systematic | parity].
+    fn arrange(&self, codeword: Array1<GF2>) -> Array1<GF2> {
+        let codeword = match &self.columns {
+            None => codeword,
+            Some(columns) => {
+                let mut permuted = codeword.clone();
+                for (&j, &x) in columns.iter().zip(codeword.iter()) {
+                    permuted[j] = x;
+                }
+                permuted
+            }
         };
-        ndarray::concatenate(ndarray::Axis(0), &[message.view(), parity.view()]).unwrap()
+        self.order.apply(codeword)
     }
 }
 
@@ -124,9 +638,7 @@ mod test {
     use super::*;
     use num_traits::Zero;
 
-    #[test]
-    fn encode() {
-        let alist = "12 4
+    const ALIST: &str = "12 4
 3 9 
 3 3 3 3 3 3 3 3 3 3 3 3 
 9 9 9 9 
@@ -147,7 +659,10 @@ mod test {
 1 2 3 4 6 7 9 10 12 
 2 3 4 5 7 8 10 11 12 
 ";
-        let h = SparseMatrix::from_alist(alist).unwrap();
+
+    #[test]
+    fn encode() {
+        let h = SparseMatrix::from_alist(ALIST).unwrap();
         let encoder = Encoder::from_h(&h).unwrap();
         let i = GF2::one();
         let o = GF2::zero();
@@ -163,6 +678,128 @@ mod test {
         assert_eq!(&codeword.as_slice().unwrap(), &expected);
     }
 
+    #[test]
+    fn output_order() {
+        let h = SparseMatrix::from_alist(ALIST).unwrap();
+        let encoder = Encoder::from_h(&h).unwrap();
+        let i = GF2::one();
+        let o = GF2::zero();
+        let message = ndarray::arr1(&[i, o, i, i, o, o, i, o]);
+        let codeword = encoder.encode(&message).to_vec();
+
+        let parity_first = encoder
+            .clone()
+            .with_output_order(OutputOrder::ParityFirst)
+            .unwrap();
+        let transmitted = parity_first.encode(&message).to_vec();
+        assert_eq!(transmitted, [&codeword[8..], &codeword[..8]].concat());
+        assert_eq!(parity_first.restore_order(&transmitted), codeword);
+
+        let permutation = vec![11, 0, 10, 1, 9, 2, 8, 3, 7, 4, 6, 5];
+        let permuted = encoder
+            .clone()
+            .with_output_order(OutputOrder::Permutation(permutation.clone()))
+            .unwrap();
+        let transmitted = permuted.encode(&message).to_vec();
+        for (j, &k) in permutation.iter().enumerate() {
+            assert_eq!(transmitted[j], codeword[k]);
+        }
+        let llrs = transmitted
+            .iter()
+            .map(|&x| if x == i { -1.0 } else { 1.0 })
+            .collect::<Vec<f64>>();
+        let restored = permuted.restore_order(&llrs);
+        for (x, y) in restored.iter().zip(codeword.iter()) {
+            assert_eq!(*x < 0.0, *y == i);
+        }
+
+        assert_eq!(
+            "parity-first".parse::<OutputOrder>(),
+            Ok(OutputOrder::ParityFirst)
+        );
+        let order = "11,0,10,1,9,2,8,3,7,4,6,5".parse::<OutputOrder>().unwrap();
+        assert_eq!(order, OutputOrder::Permutation(permutation));
+        assert_eq!(order.to_string(), "11,0,10,1,9,2,8,3,7,4,6,5");
+        assert!("parity".parse::<OutputOrder>().is_err());
+
+        for invalid in [vec![0; 12], (0..11).collect(), (1..13).collect()] {
+            assert_eq!(
+                encoder
+                    .clone()
+                    .with_output_order(OutputOrder::Permutation(invalid)),
+                Err(Error::InvalidPermutation)
+            );
+        }
+    }
+
+    #[test]
+    fn encode_with_permutation() {
+        let h = SparseMatrix::from_alist(ALIST).unwrap();
+        // Columns 0 and 5 are equal, so placing them at the end makes the last
+        // columns singular
+        let order = [1, 2, 3, 4, 6, 7, 8, 9, 10, 11, 0, 5];
+        let mut h_perm = SparseMatrix::new(h.num_rows(), h.num_cols());
+        for (j, &k) in order.iter().enumerate() {
+            h_perm.insert_col(j, h.iter_col(k));
+        }
+        assert_eq!(Encoder::from_h(&h_perm), Err(Error::SubmatrixNotInvertible));
+        assert_eq!(
+            Encoder::from_h_with_permutation(&h).unwrap(),
+            Encoder::from_h(&h).unwrap()
+        );
+        let encoder = Encoder::from_h_with_permutation(&h_perm).unwrap();
+        let permutation = encoder.column_permutation().unwrap().to_vec();
+        let parity_first = encoder
+            .clone()
+            .with_output_order(OutputOrder::ParityFirst)
+            .unwrap();
+        for j in 0..256_usize {
+            let message = Array1::from_iter((0..8).map(|b| {
+                if j & (1 << b) != 0 {
+                    GF2::one()
+                } else {
+                    GF2::zero()
+                }
+            }));
+            let codeword = encoder.encode(&message).to_vec();
+            for row in 0..h_perm.num_rows() {
+                let check: GF2 = h_perm.iter_row(row).map(|&k| codeword[k]).sum();
+                assert_eq!(check, GF2::zero());
+            }
+            for (b, &k) in permutation[..8].iter().enumerate() {
+                assert_eq!(codeword[k], message[b]);
+            }
+            let systematic = encoder.systematic_order(&codeword);
+            assert_eq!(systematic[..8], message.to_vec());
+            let transmitted = parity_first.encode(&message).to_vec();
+            assert_eq!(transmitted[4..], message.to_vec());
+            assert_eq!(parity_first.restore_order(&transmitted), codeword);
+        }
+    }
+
+    #[test]
+    fn encode_sparse_solver() {
+        let h = SparseMatrix::from_alist(ALIST).unwrap();
+        let dense = Encoder::from_h(&h).unwrap();
+        assert_eq!(dense.method(), EncodingMethod::DenseGenerator);
+        let sparse = Encoder {
+            encoder: Encoder::sparse_solver(&h).unwrap(),
+            columns: None,
+            order: OutputPermutation::default(),
+        };
+        assert_eq!(sparse.method(), EncodingMethod::SparseSolver);
+        for j in 0..256_usize {
+            let message = Array1::from_iter((0..8).map(|b| {
+                if j & (1 << b) != 0 {
+                    GF2::one()
+                } else {
+                    GF2::zero()
+                }
+            }));
+            assert_eq!(sparse.encode(&message), dense.encode(&message));
+        }
+    }
+
     #[test]
     fn encode_staircase() {
         let alist = "5 3
@@ -194,4 +831,104 @@ mod test {
         let expected = [o, i, o, i, o];
         assert_eq!(&codeword.as_slice().unwrap(), &expected);
     }
+
+    #[test]
+    fn generator_matrix() {
+        let h = SparseMatrix::from_alist(ALIST).unwrap();
+        let encoder = Encoder::from_h(&h).unwrap();
+        let g = encoder.generator_matrix();
+        assert_eq!(g.dim(), (8, 12));
+        assert_eq!(g.slice(s![.., ..8]), Array2::<GF2>::eye(8));
+        for row in g.rows() {
+            for r in 0..h.num_rows() {
+                let check: GF2 = h.iter_row(r).map(|&k| row[k]).sum();
+                assert_eq!(check, GF2::zero());
+            }
+        }
+
+        let sparse_solver = Encoder {
+            encoder: Encoder::sparse_solver(&h).unwrap(),
+            columns: None,
+            order: OutputPermutation::default(),
+        };
+        assert_eq!(sparse_solver.generator_matrix(), g);
+
+        let order = [1, 2, 3, 4, 6, 7, 8, 9, 10, 11, 0, 5];
+        let mut h_perm = SparseMatrix::new(h.num_rows(), h.num_cols());
+        for (j, &k) in order.iter().enumerate() {
+            h_perm.insert_col(j, h.iter_col(k));
+        }
+        let permuted = Encoder::from_h_with_permutation(&h_perm)
+            .unwrap()
+            .with_output_order(OutputOrder::ParityFirst)
+            .unwrap();
+        let staircase = Encoder::from_h(
+            &SparseMatrix::from_alist(
+                "5 3
+2 4
+2 2 2 2 1
+2 4 4
+1 3
+2 3
+1 2
+2 3
+3
+1 3
+2 3 4
+1 2 4 5
+",
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        for encoder in [encoder, permuted, staircase] {
+            let g = encoder.generator_matrix();
+            let (k, n) = encoder.dimensions();
+            for j in 0..1_usize << k {
+                let message = Array1::from_iter((0..k).map(|b| {
+                    if j & (1 << b) != 0 {
+                        GF2::one()
+                    } else {
+                        GF2::zero()
+                    }
+                }));
+                assert_eq!(message.dot(&g), encoder.encode(&message));
+            }
+            let sparse = encoder.generator_matrix_sparse();
+            for r in 0..k {
+                for c in 0..n {
+                    assert_eq!(sparse.contains(r, c), g[[r, c]].is_one());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn encode_ar4ja() {
+        use crate::codes::ccsds::{AR4JACode, AR4JAInfoSize, AR4JARate};
+
+        let h = AR4JACode::new(AR4JARate::R2_3, AR4JAInfoSize::K1024).h();
+        let encoder = Encoder::from_h(&h).unwrap();
+        assert!(matches!(encoder.encoder, EncoderType::Ar4ja(_)));
+        assert_eq!(encoder.method(), EncodingMethod::Ar4ja);
+        // Compare with the generic dense encoder
+        let n = h.num_rows();
+        let m = h.num_cols();
+        let mut a = Array2::zeros((n, m));
+        for (j, k) in h.iter_all() {
+            let t = if k < m - n { k + n } else { k - (m - n) };
+            a[[j, t]] = GF2::one();
+        }
+        linalg::gauss_reduction(&mut a).unwrap();
+        let gen_matrix = a.slice(s![.., n..]).to_owned();
+        let message = Array1::from_iter((0..m - n).map(|j| {
+            if (j * j + 3 * j) % 7 < 3 {
+                GF2::one()
+            } else {
+                GF2::zero()
+            }
+        }));
+        let codeword = encoder.encode(&message);
+        assert_eq!(codeword.slice(s![m - n..]), gen_matrix.dot(&message));
+    }
 }
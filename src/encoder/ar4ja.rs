@@ -0,0 +1,374 @@
+use crate::{
+    codes::ccsds::{AR4JACode, AR4JAInfoSize, AR4JARate},
+    gf2::GF2,
+    sparse::SparseMatrix,
+};
+use ndarray::{s, Array1, ArrayBase, Data, Ix1};
+use num_traits::{One, Zero};
+
+// Returns the AR4JA code whose parity check matrix is equal to h, if any.
+pub fn detect(h: &SparseMatrix) -> Option<AR4JACode> {
+    enum_iterator::all::<AR4JARate>()
+        .flat_map(|rate| {
+            enum_iterator::all::<AR4JAInfoSize>().map(move |k| AR4JACode::new(rate, k))
+        })
+        .filter(|code| 3 * code.submatrix_size() == h.num_rows())
+        .find(|code| same_matrix(&code.h(), h))
+}
+
+// Checks if two matrices are equal, regardless of the order in which their
+// entries were inserted.
+fn same_matrix(a: &SparseMatrix, b: &SparseMatrix) -> bool {
+    let sorted_row = |h: &SparseMatrix, row| {
+        let mut row = h.iter_row(row).copied().collect::<Vec<_>>();
+        row.sort_unstable();
+        row
+    };
+    a.num_rows() == b.num_rows()
+        && a.num_cols() == b.num_cols()
+        && (0..a.num_rows()).all(|row| sorted_row(a, row) == sorted_row(b, row))
+}
+
+// Encoder for the AR4JA codes.
+//
+// The parity part of H is formed by the last three block columns of size M,
+// which contain the parity bits p2, p3 and p4 (p4 is punctured). The
+// equations of the three block rows are
+//
+//   p2 + (I + Pi_1) p4 = 0
+//   p3 + (Pi_2 + Pi_3 + Pi_4) p4 = s1
+//   (Pi_7 + Pi_8) p3 + p4 = s2
+//
+// where s1 and s2 depend on the information bits. Substituting p3 in the last
+// equation gives A p4 = s2 + (Pi_7 + Pi_8) s1, where
+// A = I + (Pi_7 + Pi_8)(Pi_2 + Pi_3 + Pi_4). Since each permutation Pi_k is
+// formed by 4 x 4 blocks that are circulant matrices of size M/4, the inverse
+// of A is formed by 4 x 4 circulant blocks, which are computed when the
+// encoder is constructed. Then p3 and p2 are obtained from p4 using the first
+// two equations.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ar4jaEncoder {
+    h: SparseMatrix,
+    m: usize,
+    // Inverse of A, as 4 x 4 circulant blocks in row-major order
+    a_inverse: Box<[Circulant]>,
+}
+
+impl Ar4jaEncoder {
+    pub fn new(code: &AR4JACode, h: SparseMatrix) -> Ar4jaEncoder {
+        let m = code.submatrix_size();
+        let z = m / 4;
+        let k = h.num_cols() - 3 * m;
+        let block = |row: usize, col: usize| {
+            (0..4)
+                .flat_map(|r| (0..4).map(move |c| (r, c)))
+                .map(|(r, c)| Circulant::from_h(&h, row + r * z, col + c * z, z))
+                .collect::<Vec<_>>()
+        };
+        // Pi_2 + Pi_3 + Pi_4 and Pi_7 + Pi_8
+        let b1 = block(m, k + 2 * m);
+        let b2 = block(2 * m, k + m);
+        let mut a = (0..16)
+            .map(|j| {
+                let (r, c) = (j / 4, j % 4);
+                let mut x = if r == c {
+                    Circulant::identity(z)
+                } else {
+                    Circulant::zero(z)
+                };
+                for l in 0..4 {
+                    x.add_assign(&b2[4 * r + l].mul(&b1[4 * l + c]));
+                }
+                x
+            })
+            .collect::<Vec<_>>();
+        let a_inverse = invert(&mut a, z).expect("AR4JA parity submatrix is not invertible");
+        Ar4jaEncoder {
+            h,
+            m,
+            a_inverse: a_inverse.into_boxed_slice(),
+        }
+    }
+
+    // Returns the message length and codeword length.
+    pub fn dimensions(&self) -> (usize, usize) {
+        let n = self.h.num_cols();
+        (n - 3 * self.m, n)
+    }
+
+    // Returns the parity bits corresponding to a message.
+    pub fn parity<S>(&self, message: &ArrayBase<S, Ix1>) -> Array1<GF2>
+    where
+        S: Data<Elem = GF2>,
+    {
+        let m = self.m;
+        let z = m / 4;
+        let k = self.h.num_cols() - 3 * m;
+        assert_eq!(message.len(), k);
+        let mut codeword = Array1::zeros(self.h.num_cols());
+        codeword.slice_mut(s![..k]).assign(message);
+        let row_sum =
+            |codeword: &Array1<GF2>, row| self.h.iter_row(row).map(|&j| codeword[j]).sum::<GF2>();
+        // With p3 = p4 = 0, the syndrome of the second block row is s1. Setting
+        // p3 = s1, the syndrome of the third block row is
+        // s2 + (Pi_7 + Pi_8) s1.
+        for i in 0..m {
+            codeword[k + m + i] = row_sum(&codeword, m + i);
+        }
+        let t = (0..4)
+            .map(|r| Circulant::from_bits((0..z).map(|i| row_sum(&codeword, 2 * m + r * z + i))))
+            .collect::<Vec<_>>();
+        for c in 0..4 {
+            let mut p4 = Circulant::zero(z);
+            for (r, t) in t.iter().enumerate() {
+                p4.add_assign(&self.a_inverse[4 * c + r].apply(t));
+            }
+            for i in 0..z {
+                codeword[k + 2 * m + c * z + i] = p4.bit(i);
+            }
+        }
+        // Compute p3 and p2 from p4
+        codeword.slice_mut(s![k + m..k + 2 * m]).fill(GF2::zero());
+        for i in 0..m {
+            codeword[k + m + i] = row_sum(&codeword, m + i);
+            codeword[k + i] = row_sum(&codeword, i);
+        }
+        codeword.slice(s![k..]).to_owned()
+    }
+}
+
+// Circulant matrix of size z, represented by its first row. Row i of the
+// matrix is the first row cyclically shifted i positions to the right. This
+// struct is also used to represent vectors of size z.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Circulant {
+    z: usize,
+    words: Box<[u64]>,
+}
+
+impl Circulant {
+    fn zero(z: usize) -> Circulant {
+        Circulant {
+            z,
+            words: vec![0; z.div_ceil(64)].into_boxed_slice(),
+        }
+    }
+
+    fn identity(z: usize) -> Circulant {
+        let mut x = Circulant::zero(z);
+        x.toggle(0);
+        x
+    }
+
+    fn from_bits<I: ExactSizeIterator<Item = GF2>>(bits: I) -> Circulant {
+        let mut x = Circulant::zero(bits.len());
+        for (j, b) in bits.enumerate() {
+            if b.is_one() {
+                x.toggle(j);
+            }
+        }
+        x
+    }
+
+    // Extracts the circulant block of H whose top left corner is at (row, col).
+    fn from_h(h: &SparseMatrix, row: usize, col: usize, z: usize) -> Circulant {
+        let mut x = Circulant::zero(z);
+        for &j in h.iter_row(row) {
+            if (col..col + z).contains(&j) {
+                x.toggle(j - col);
+            }
+        }
+        x
+    }
+
+    fn bit(&self, j: usize) -> GF2 {
+        if (self.words[j / 64] >> (j % 64)) & 1 != 0 {
+            GF2::one()
+        } else {
+            GF2::zero()
+        }
+    }
+
+    fn toggle(&mut self, j: usize) {
+        self.words[j / 64] ^= 1 << (j % 64);
+    }
+
+    fn ones(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.z).filter(|&j| (self.words[j / 64] >> (j % 64)) & 1 != 0)
+    }
+
+    fn is_zero(&self) -> bool {
+        self.words.iter().all(|&w| w == 0)
+    }
+
+    // A circulant of size z = 2^n is invertible if and only if its first row
+    // has an odd weight, because x^z + 1 = (x + 1)^z.
+    fn is_invertible(&self) -> bool {
+        self.words.iter().map(|w| w.count_ones()).sum::<u32>() % 2 == 1
+    }
+
+    fn add_assign(&mut self, other: &Circulant) {
+        for (a, b) in self.words.iter_mut().zip(other.words.iter()) {
+            *a ^= b;
+        }
+    }
+
+    // Returns the vector w with w_i = v_{(i + d) mod z}.
+    fn rotated(&self, d: usize) -> Circulant {
+        let z = self.z;
+        let mut x = Circulant::zero(z);
+        if z.is_multiple_of(64) {
+            let (q, r) = (d / 64, d % 64);
+            let n = self.words.len();
+            for (j, w) in x.words.iter_mut().enumerate() {
+                let lo = self.words[(j + q) % n] >> r;
+                let hi = if r == 0 {
+                    0
+                } else {
+                    self.words[(j + q + 1) % n] << (64 - r)
+                };
+                *w = lo | hi;
+            }
+        } else {
+            for j in self.ones() {
+                x.toggle((j + z - d) % z);
+            }
+        }
+        x
+    }
+
+    fn mul(&self, other: &Circulant) -> Circulant {
+        // The first row of the product is the cyclic convolution of the first
+        // rows.
+        let mut x = Circulant::zero(self.z);
+        for d in self.ones() {
+            x.add_assign(&other.rotated((self.z - d) % self.z));
+        }
+        x
+    }
+
+    // Multiplies the circulant matrix by a vector.
+    fn apply(&self, v: &Circulant) -> Circulant {
+        let mut x = Circulant::zero(self.z);
+        for d in self.ones() {
+            x.add_assign(&v.rotated(d));
+        }
+        x
+    }
+
+    // Computes the inverse of an invertible circulant. Writing the circulant as
+    // 1 + u, where u is nilpotent, the inverse is the product of the terms
+    // 1 + u^(2^j).
+    fn inverse(&self) -> Circulant {
+        debug_assert!(self.is_invertible());
+        let mut u = self.clone();
+        u.toggle(0);
+        let mut inverse = Circulant::identity(self.z);
+        while !u.is_zero() {
+            let mut term = u.clone();
+            term.toggle(0);
+            inverse = inverse.mul(&term);
+            u = u.mul(&u);
+        }
+        inverse
+    }
+}
+
+// Computes the inverse of a 4 x 4 matrix of circulants of size z by
+// Gauss-Jordan elimination. The matrix is destroyed. Returns None if the
+// matrix is not invertible.
+fn invert(a: &mut [Circulant], z: usize) -> Option<Vec<Circulant>> {
+    let mut inverse = (0..16)
+        .map(|j| {
+            if j / 4 == j % 4 {
+                Circulant::identity(z)
+            } else {
+                Circulant::zero(z)
+            }
+        })
+        .collect::<Vec<_>>();
+    for col in 0..4 {
+        // In the ring of circulants of size 2^n, a matrix is invertible if and
+        // only if each column of the remaining submatrix has an invertible
+        // entry.
+        let pivot = (col..4).find(|&r| a[4 * r + col].is_invertible())?;
+        for c in 0..4 {
+            a.swap(4 * col + c, 4 * pivot + c);
+            inverse.swap(4 * col + c, 4 * pivot + c);
+        }
+        let pivot_inverse = a[4 * col + col].inverse();
+        for c in 0..4 {
+            a[4 * col + c] = pivot_inverse.mul(&a[4 * col + c]);
+            inverse[4 * col + c] = pivot_inverse.mul(&inverse[4 * col + c]);
+        }
+        for r in (0..4).filter(|&r| r != col) {
+            let factor = a[4 * r + col].clone();
+            if factor.is_zero() {
+                continue;
+            }
+            for c in 0..4 {
+                let x = factor.mul(&a[4 * col + c]);
+                a[4 * r + c].add_assign(&x);
+                let x = factor.mul(&inverse[4 * col + c]);
+                inverse[4 * r + c].add_assign(&x);
+            }
+        }
+    }
+    Some(inverse)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rand::*;
+    use rand::Rng as _;
+
+    #[test]
+    fn circulants() {
+        let mut rng = Rng::seed_from_u64(0);
+        for z in [32, 128] {
+            let random = |rng: &mut Rng| {
+                Circulant::from_bits((0..z).map(|_| {
+                    if rng.gen::<bool>() {
+                        GF2::one()
+                    } else {
+                        GF2::zero()
+                    }
+                }))
+            };
+            let mut a = random(&mut rng);
+            if !a.is_invertible() {
+                a.toggle(0);
+            }
+            assert_eq!(a.mul(&a.inverse()), Circulant::identity(z));
+            let v = random(&mut rng);
+            let b = random(&mut rng);
+            assert_eq!(a.mul(&b).apply(&v), a.apply(&b.apply(&v)));
+        }
+    }
+
+    #[test]
+    fn encode() {
+        let mut rng = Rng::seed_from_u64(0);
+        for rate in enum_iterator::all::<AR4JARate>() {
+            let code = AR4JACode::new(rate, AR4JAInfoSize::K1024);
+            let h = code.h();
+            assert_eq!(detect(&h), Some(code));
+            let encoder = Ar4jaEncoder::new(&code, h.clone());
+            let k = h.num_cols() - h.num_rows();
+            let message = Array1::from_iter((0..k).map(|_| {
+                if rng.gen::<bool>() {
+                    GF2::one()
+                } else {
+                    GF2::zero()
+                }
+            }));
+            let parity = encoder.parity(&message);
+            let codeword =
+                ndarray::concatenate(ndarray::Axis(0), &[message.view(), parity.view()]).unwrap();
+            for row in 0..h.num_rows() {
+                assert!(h.iter_row(row).map(|&j| codeword[j]).sum::<GF2>().is_zero());
+            }
+        }
+    }
+}
@@ -0,0 +1,652 @@
+//! LDPC encoding by erasure decoding.
+//!
+//! This module implements an encoder that only uses the parity check matrix H
+//! and that does not require the square matrix formed by the last columns of H
+//! to be invertible. The parity bits of the codeword are treated as erasures,
+//! and they are recovered by running an erasure decoder for the binary erasure
+//! channel on the codeword formed by the message bits.
+//!
+//! The erasure decoder uses the peeling algorithm, which repeatedly finds a
+//! check node with a single erased variable node and recovers the value of
+//! this variable from the other variables of the check. When the peeling
+//! algorithm gets stuck, one of the erased variables is *inactivated*, which
+//! means that its value is treated as an unknown, and the peeling algorithm
+//! continues. The values of the inactivated variables are then obtained by
+//! maximum likelihood decoding, which amounts to solving a (small, hopefully)
+//! dense linear system given by the check nodes that have not been used by the
+//! peeling algorithm. All the decisions taken by the erasure decoder depend only
+//! on the positions of the erasures, so they are computed when the encoder is
+//! constructed.
+//!
+//! For codes whose parity part has a structure like the dual-diagonal plus
+//! high-density core of the 5G NR codes, only a block of parity bits needs to
+//! be inactivated, and the remaining parity bits are obtained by forward
+//! substitution. This is much faster than solving for all the parity bits by
+//! Gaussian elimination.
+//!
+//! The parity positions are also chosen with the peeling algorithm, so that
+//! the encoder can be constructed for long codes. The last m columns of H,
+//! where m is the number of rows, are treated as erasures and the peeling
+//! algorithm with inactivation is run on them. Gaussian elimination is only
+//! applied to the subsystem formed by the inactivated columns and the checks
+//! not used by the peeling algorithm. If the square matrix formed by the last
+//! columns of H is invertible, the parity positions are the last n-k positions
+//! of the codeword, as in the systematic [`Encoder`](super::Encoder).
+//! Otherwise, the inactivated columns that are linearly dependent are replaced
+//! by the last columns before them that increase the rank, and the message bits
+//! are placed in the positions given by
+//! [`information_positions`](Encoder::information_positions). Redundant rows in
+//! H are also supported. In this case, the number of message bits is the
+//! codeword length minus the rank of H.
+//!
+//! As with the systematic encoder, the order in which the codeword bits are
+//! output can be changed with an [`OutputOrder`].
+
+use super::{OutputOrder, OutputPermutation};
+use crate::{gf2::GF2, linalg, sparse::SparseMatrix};
+use ndarray::{s, Array1, Array2, ArrayBase, Data, Ix1};
+use num_traits::{One, Zero};
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashSet, VecDeque},
+};
+
+/// LDPC encoder by erasure decoding.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Encoder {
+    h: SparseMatrix,
+    information: Box<[usize]>,
+    parity: Box<[usize]>,
+    // Parity positions recovered by the peeling algorithm, in order, together
+    // with the check node used to recover them.
+    peeling: Box<[(usize, usize)]>,
+    // Inactivated parity positions, which are recovered by maximum likelihood
+    // decoding.
+    ml_positions: Box<[usize]>,
+    // Check nodes used for maximum likelihood decoding.
+    ml_checks: Box<[usize]>,
+    // Inverse of the linear map from the values of the inactivated positions
+    // to the syndrome of the checks in ml_checks.
+    ml_inverse: Array2<GF2>,
+    order: OutputPermutation,
+}
+
+impl Encoder {
+    /// Creates the encoder corresponding to a parity check matrix.
+    pub fn from_h(h: &SparseMatrix) -> Encoder {
+        let n = h.num_cols();
+        let parity = parity_positions(h);
+        let mut erased = vec![false; n];
+        for &j in parity.iter() {
+            erased[j] = true;
+        }
+        let information = (0..n).filter(|&j| !erased[j]).collect();
+        let (peeling, ml_positions) = peel(h, &mut erased);
+        let (ml_checks, ml_inverse) = ml_system(h, &peeling, &ml_positions);
+        Encoder {
+            h: h.clone(),
+            information,
+            parity: parity.into_boxed_slice(),
+            peeling: peeling.into_boxed_slice(),
+            ml_positions: ml_positions.into_boxed_slice(),
+            ml_checks: ml_checks.into_boxed_slice(),
+            ml_inverse,
+            order: OutputPermutation::default(),
+        }
+    }
+
+    /// Sets the order in which the encoder outputs the codeword bits.
+    ///
+    /// By default, the encoder outputs the codeword in the order given by the
+    /// columns of H. With [`OutputOrder::ParityFirst`], the bits in the
+    /// [`parity_positions`](Encoder::parity_positions) are output first,
+    /// followed by the bits in the
+    /// [`information_positions`](Encoder::information_positions). This returns
+    /// an error if `order` is a permutation that is not a valid permutation of
+    /// the codeword positions.
+    pub fn with_output_order(mut self, order: OutputOrder) -> Result<Encoder, super::Error> {
+        self.order = OutputPermutation::new(order, &self.information, &self.parity)?;
+        Ok(self)
+    }
+
+    /// Restores the order of a received codeword.
+    ///
+    /// This applies the inverse of the [output order](Encoder::with_output_order)
+    /// to the received codeword, so that it is in the order given by the
+    /// columns of H.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the length of `received` is not equal to the
+    /// codeword length.
+    pub fn restore_order<T: Clone>(&self, received: &[T]) -> Vec<T> {
+        self.order.restore(received)
+    }
+
+    /// Returns the number of message bits.
+    pub fn message_length(&self) -> usize {
+        self.information.len()
+    }
+
+    /// Returns the codeword positions that contain the message bits.
+    ///
+    /// The positions are given in increasing order.
+    pub fn information_positions(&self) -> &[usize] {
+        &self.information
+    }
+
+    /// Returns the codeword positions that contain the parity bits.
+    ///
+    /// The positions are given in increasing order.
+    pub fn parity_positions(&self) -> &[usize] {
+        &self.parity
+    }
+
+    /// Returns the number of parity bits that are recovered by maximum
+    /// likelihood decoding.
+    ///
+    /// These are the parity bits inactivated by the peeling algorithm. The
+    /// remaining parity bits are recovered by the peeling algorithm. The
+    /// encoding complexity grows with the square of this number.
+    pub fn ml_parity_bits(&self) -> usize {
+        self.ml_positions.len()
+    }
+
+    /// Encodes a message into a codeword.
+    ///
+    /// The codeword bits are given in the [output
+    /// order](Encoder::with_output_order) of the encoder.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the length of the message is not equal to
+    /// [`message_length`](Encoder::message_length).
+    pub fn encode<S>(&self, message: &ArrayBase<S, Ix1>) -> Array1<GF2>
+    where
+        S: Data<Elem = GF2>,
+    {
+        assert_eq!(message.len(), self.information.len());
+        let mut codeword = Array1::zeros(self.h.num_cols());
+        for (&j, &x) in self.information.iter().zip(message.iter()) {
+            codeword[j] = x;
+        }
+        // Run the peeling algorithm with the inactivated positions set to zero
+        // to obtain the syndrome of the remaining checks.
+        peel_values(&self.h, &self.peeling, &mut codeword);
+        let syndrome = Array1::from_iter(
+            self.ml_checks
+                .iter()
+                .map(|&check| self.h.iter_row(check).map(|&j| codeword[j]).sum()),
+        );
+        let solution = self.ml_inverse.dot(&syndrome);
+        if solution.iter().any(|x| !x.is_zero()) {
+            // Run the peeling algorithm again with the correct values of the
+            // inactivated positions.
+            for (&j, &x) in self.ml_positions.iter().zip(solution.iter()) {
+                codeword[j] = x;
+            }
+            for &(_, var) in self.peeling.iter() {
+                codeword[var] = GF2::zero();
+            }
+            peel_values(&self.h, &self.peeling, &mut codeword);
+        }
+        self.order.apply(codeword)
+    }
+}
+
+// Returns the parity positions, in increasing order.
+//
+// The last m columns are erased and the peeling algorithm is run on them. The
+// peeled columns are linearly independent. A set of non-peeled columns is
+// linearly independent together with the peeled columns if and only if the
+// residuals of these columns (see `Residuals`) are linearly independent. The
+// inactivated columns whose residuals are linearly dependent on those of later
+// columns are replaced by the last columns before the erased ones that increase
+// the rank of the residuals.
+fn parity_positions(h: &SparseMatrix) -> Vec<usize> {
+    let n = h.num_cols();
+    let first = n.saturating_sub(h.num_rows());
+    let mut erased = vec![false; n];
+    erased[first..].fill(true);
+    let mut parity = erased.clone();
+    let (peeling, inactivated) = peel(h, &mut erased);
+    let residuals = Residuals::new(h, &peeling);
+    let mut basis = Basis::default();
+    for &j in inactivated.iter().rev() {
+        if !basis.insert(residuals.residual(j)) {
+            parity[j] = false;
+        }
+    }
+    for j in (0..first).rev() {
+        if basis.len() == residuals.num_checks() {
+            break;
+        }
+        if basis.insert(residuals.residual(j)) {
+            parity[j] = true;
+        }
+    }
+    (0..n).filter(|&j| parity[j]).collect()
+}
+
+// Residuals of the columns of H with respect to the peeling algorithm.
+//
+// The residual of a column is the syndrome of the checks not used by the
+// peeling algorithm for a word in which the bit in this column is one, the
+// other non-peeled bits are zero, and the peeled bits are computed by the
+// peeling algorithm. It is represented as a bitset.
+struct Residuals<'a> {
+    h: &'a SparseMatrix,
+    peeling: &'a [(usize, usize)],
+    // Peeling step in which each check is used
+    steps: Vec<Option<usize>>,
+    // Index of each check not used by the peeling algorithm
+    unused: Vec<Option<usize>>,
+    num_unused: usize,
+}
+
+impl<'a> Residuals<'a> {
+    fn new(h: &'a SparseMatrix, peeling: &'a [(usize, usize)]) -> Residuals<'a> {
+        let mut steps = vec![None; h.num_rows()];
+        for (step, &(check, _)) in peeling.iter().enumerate() {
+            steps[check] = Some(step);
+        }
+        let mut num_unused = 0;
+        let unused = steps
+            .iter()
+            .map(|step| {
+                step.is_none().then(|| {
+                    num_unused += 1;
+                    num_unused - 1
+                })
+            })
+            .collect();
+        Residuals {
+            h,
+            peeling,
+            steps,
+            unused,
+            num_unused,
+        }
+    }
+
+    fn num_checks(&self) -> usize {
+        self.num_unused
+    }
+
+    fn residual(&self, col: usize) -> Vec<u64> {
+        // Checks with odd parity. The peeled bits are only visited if they are
+        // affected by the column, in the order of the peeling algorithm. The
+        // other bits of the check used to recover a peeled bit are known
+        // before the step in which it is recovered, so the checks of a peeled
+        // bit used in later steps always come after it.
+        let mut odd = HashSet::new();
+        let mut pending = BinaryHeap::new();
+        self.flip(col, &mut odd, &mut pending);
+        let mut last = None;
+        while let Some(Reverse(step)) = pending.pop() {
+            if last == Some(step) {
+                continue;
+            }
+            last = Some(step);
+            let (check, var) = self.peeling[step];
+            if odd.contains(&check) {
+                self.flip(var, &mut odd, &mut pending);
+            }
+        }
+        let mut residual = vec![0; self.num_unused.div_ceil(64)];
+        for check in odd {
+            if let Some(j) = self.unused[check] {
+                residual[j / 64] |= 1 << (j % 64);
+            }
+        }
+        residual
+    }
+
+    // Sets a bit to one, updating the parity of its checks and enqueuing the
+    // peeling steps that use them.
+    fn flip(&self, var: usize, odd: &mut HashSet<usize>, pending: &mut BinaryHeap<Reverse<usize>>) {
+        for &check in self.h.iter_col(var) {
+            if !odd.remove(&check) {
+                odd.insert(check);
+            }
+            if let Some(step) = self.steps[check] {
+                pending.push(Reverse(step));
+            }
+        }
+    }
+}
+
+// Basis in row echelon form of a space of bitsets.
+#[derive(Default)]
+struct Basis {
+    // Vectors of the basis together with their pivot bit. Each vector is zero
+    // in the pivot bits of the previous vectors.
+    vectors: Vec<(usize, Vec<u64>)>,
+}
+
+impl Basis {
+    fn len(&self) -> usize {
+        self.vectors.len()
+    }
+
+    // Adds a vector to the basis if it is linearly independent from the basis,
+    // returning whether it has been added.
+    fn insert(&mut self, mut vector: Vec<u64>) -> bool {
+        for (pivot, v) in self.vectors.iter() {
+            if (vector[pivot / 64] >> (pivot % 64)) & 1 != 0 {
+                for (x, y) in vector.iter_mut().zip(v.iter()) {
+                    *x ^= y;
+                }
+            }
+        }
+        let Some(word) = vector.iter().position(|&x| x != 0) else {
+            return false;
+        };
+        let pivot = 64 * word + vector[word].trailing_zeros() as usize;
+        self.vectors.push((pivot, vector));
+        true
+    }
+}
+
+// Returns the pivot columns of the row echelon form of a matrix.
+fn pivot_columns(mut a: Array2<GF2>) -> Vec<usize> {
+    linalg::row_echelon_form(&mut a);
+    a.rows()
+        .into_iter()
+        .map_while(|row| row.iter().position(|x| !x.is_zero()))
+        .collect()
+}
+
+// Runs the peeling algorithm on the erasures, updating them as they get
+// recovered or inactivated. Returns the recovered positions, in order,
+// together with the check used to recover each of them, and the inactivated
+// positions, in increasing order.
+fn peel(h: &SparseMatrix, erased: &mut [bool]) -> (Vec<(usize, usize)>, Vec<usize>) {
+    let mut num_erased = (0..h.num_rows())
+        .map(|check| h.iter_row(check).filter(|&&j| erased[j]).count())
+        .collect::<Vec<_>>();
+    let mut queue = (0..h.num_rows())
+        .filter(|&check| num_erased[check] == 1)
+        .collect::<VecDeque<_>>();
+    let mut recovered = Vec::new();
+    let mut inactivated = Vec::new();
+    loop {
+        while let Some(check) = queue.pop_front() {
+            // The erasure in this check could have been recovered by other
+            // check after the check was enqueued.
+            let Some(&var) = h.iter_row(check).find(|&&j| erased[j]) else {
+                continue;
+            };
+            recovered.push((check, var));
+            resolve(h, var, erased, &mut num_erased, &mut queue);
+        }
+        // The peeling algorithm is stuck. Inactivate the erased variable with
+        // most erased checks among those in a check with the fewest erasures.
+        let Some(check) = (0..h.num_rows())
+            .filter(|&check| num_erased[check] >= 2)
+            .min_by_key(|&check| num_erased[check])
+        else {
+            break;
+        };
+        let &var = h
+            .iter_row(check)
+            .filter(|&&j| erased[j])
+            .max_by_key(|&&j| h.iter_col(j).filter(|&&c| num_erased[c] != 0).count())
+            .unwrap();
+        inactivated.push(var);
+        resolve(h, var, erased, &mut num_erased, &mut queue);
+    }
+    // Erasures in columns that do not belong to any check with other erasures
+    // (for instance, all-zero columns) can only be inactivated.
+    inactivated.extend((0..erased.len()).filter(|&j| erased[j]));
+    inactivated.sort_unstable();
+    (recovered, inactivated)
+}
+
+// Marks a variable as no longer erased, enqueuing the checks that become
+// solvable by the peeling algorithm.
+fn resolve(
+    h: &SparseMatrix,
+    var: usize,
+    erased: &mut [bool],
+    num_erased: &mut [usize],
+    queue: &mut VecDeque<usize>,
+) {
+    erased[var] = false;
+    for &c in h.iter_col(var) {
+        num_erased[c] -= 1;
+        if num_erased[c] == 1 {
+            queue.push_back(c);
+        }
+    }
+}
+
+// Computes the values of the positions recovered by the peeling algorithm,
+// which must be zero in the codeword.
+fn peel_values(h: &SparseMatrix, peeling: &[(usize, usize)], codeword: &mut Array1<GF2>) {
+    for &(check, var) in peeling.iter() {
+        codeword[var] = h.iter_row(check).map(|&j| codeword[j]).sum();
+    }
+}
+
+// Selects a set of checks that determines the inactivated positions and
+// returns these checks together with the inverse of the linear map from the
+// values of the inactivated positions to the syndrome of these checks.
+//
+// The inactivated positions must be uniquely determined by the checks not used
+// by the peeling algorithm.
+fn ml_system(
+    h: &SparseMatrix,
+    peeling: &[(usize, usize)],
+    positions: &[usize],
+) -> (Vec<usize>, Array2<GF2>) {
+    let r = positions.len();
+    let mut used = vec![false; h.num_rows()];
+    for &(check, _) in peeling.iter() {
+        used[check] = true;
+    }
+    let candidates = (0..h.num_rows())
+        .filter(|&check| !used[check])
+        .collect::<Vec<_>>();
+    // Transpose of the matrix of the linear map from the values of the
+    // inactivated positions to the syndrome of the candidate checks, computed
+    // by running the peeling algorithm with each inactivated position set to
+    // one. The pivot columns of this matrix give a set of checks that
+    // determine the inactivated positions.
+    let mut a = Array2::zeros((r, candidates.len()));
+    let mut codeword = Array1::zeros(h.num_cols());
+    for (t, &j) in positions.iter().enumerate() {
+        codeword.fill(GF2::zero());
+        codeword[j] = GF2::one();
+        peel_values(h, peeling, &mut codeword);
+        for (s, &check) in candidates.iter().enumerate() {
+            a[[t, s]] = h.iter_row(check).map(|&k| codeword[k]).sum();
+        }
+    }
+    let pivots = pivot_columns(a.clone());
+    assert_eq!(pivots.len(), r);
+    // Gauss-Jordan inversion of the square submatrix
+    let mut b = Array2::zeros((r, 2 * r));
+    for (s, &p) in pivots.iter().enumerate() {
+        for t in 0..r {
+            b[[s, t]] = a[[t, p]];
+        }
+        b[[s, r + s]] = GF2::one();
+    }
+    linalg::gauss_reduction(&mut b).expect("ML erasure decoding submatrix is not invertible");
+    let checks = pivots.iter().map(|&p| candidates[p]).collect();
+    (checks, b.slice(s![.., r..]).to_owned())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        codes::{
+            examples::ExampleCode,
+            wimax::{WimaxCode, WimaxRate},
+            Code,
+        },
+        rand::*,
+    };
+    use rand::Rng as _;
+
+    fn check_all_messages(h: &SparseMatrix, encoder: &Encoder) {
+        let k = encoder.message_length();
+        for message in 0..1 << k {
+            let message = Array1::from_iter((0..k).map(|j| {
+                if (message >> j) & 1 != 0 {
+                    GF2::one()
+                } else {
+                    GF2::zero()
+                }
+            }));
+            let codeword = encoder.encode(&message);
+            for check in 0..h.num_rows() {
+                assert!(h
+                    .iter_row(check)
+                    .map(|&j| codeword[j])
+                    .sum::<GF2>()
+                    .is_zero());
+            }
+            for (&j, &x) in encoder.information_positions().iter().zip(message.iter()) {
+                assert_eq!(codeword[j], x);
+            }
+        }
+    }
+
+    #[test]
+    fn redundant_rows() {
+        // Example 2.5 in Sarah J. Johnson - Iterative Error Correction
+        let h = ExampleCode::Johnson2_5.h();
+        // H has rank 3, so the systematic encoder cannot be used
+        assert!(crate::encoder::Encoder::from_h(&h).is_err());
+        let encoder = Encoder::from_h(&h);
+        // Column 4 is inactivated when peeling the last 4 columns, and it is
+        // linearly dependent on the others
+        assert_eq!(encoder.information_positions(), &[0, 1, 4]);
+        assert_eq!(encoder.parity_positions(), &[2, 3, 5]);
+        assert_eq!(encoder.ml_parity_bits(), 0);
+        let i = GF2::one();
+        let o = GF2::zero();
+        let codeword = encoder.encode(&ndarray::arr1(&[o, o, i]));
+        assert_eq!(codeword.as_slice().unwrap(), &[o, o, i, o, i, i]);
+        check_all_messages(&h, &encoder);
+    }
+
+    #[test]
+    fn non_invertible_parity_part() {
+        // The last 3 columns of this matrix add up to zero
+        let mut h = SparseMatrix::new(3, 6);
+        h.insert_row(0, [0, 3, 4].iter());
+        h.insert_row(1, [1, 3, 5].iter());
+        h.insert_row(2, [2, 4, 5].iter());
+        assert!(crate::encoder::Encoder::from_h(&h).is_err());
+        let encoder = Encoder::from_h(&h);
+        // Column 4 is inactivated and replaced by column 2
+        assert_eq!(encoder.information_positions(), &[0, 1, 4]);
+        assert_eq!(encoder.parity_positions(), &[2, 3, 5]);
+        assert_eq!(encoder.ml_parity_bits(), 0);
+        check_all_messages(&h, &encoder);
+    }
+
+    #[test]
+    fn maximum_likelihood() {
+        // All the checks contain at least two parity bits, so the peeling
+        // algorithm cannot recover any of them without inactivation. After
+        // inactivating bit 4, the remaining bits can be recovered.
+        let mut h = SparseMatrix::new(3, 6);
+        h.insert_row(0, [0, 3, 4].iter());
+        h.insert_row(1, [1, 4, 5].iter());
+        h.insert_row(2, [2, 3, 4, 5].iter());
+        let encoder = Encoder::from_h(&h);
+        assert_eq!(encoder.parity_positions(), &[3, 4, 5]);
+        assert_eq!(encoder.ml_parity_bits(), 1);
+        check_all_messages(&h, &encoder);
+    }
+
+    // Lifts a base matrix with circulants of size z, given by their shifts
+    // (None is the zero block).
+    fn lift(base: &[[Option<usize>; 7]], z: usize) -> SparseMatrix {
+        let mut h = SparseMatrix::new(base.len() * z, base[0].len() * z);
+        for (r, row) in base.iter().enumerate() {
+            for (c, shift) in row.iter().enumerate() {
+                if let Some(shift) = shift {
+                    for j in 0..z {
+                        h.insert(r * z + j, c * z + (j + shift) % z);
+                    }
+                }
+            }
+        }
+        h
+    }
+
+    #[test]
+    fn dual_diagonal_core() {
+        // Quasi-cyclic code with the structure of the 5G NR codes. Columns 0
+        // and 1 are information, columns 2 to 5 are the core parity (a column
+        // of weight 3 followed by a dual-diagonal), and column 6 is the parity
+        // of the extension row.
+        let z = 3;
+        let base = [
+            [Some(0), Some(2), Some(1), Some(0), None, None, None],
+            [Some(1), Some(0), Some(1), Some(0), Some(0), None, None],
+            [Some(2), Some(1), None, None, Some(0), Some(0), None],
+            [Some(0), None, Some(1), None, None, Some(0), None],
+            [Some(1), Some(2), Some(0), None, None, None, Some(0)],
+        ];
+        let h = lift(&base, z);
+        let encoder = Encoder::from_h(&h);
+        assert_eq!(encoder.message_length(), 2 * z);
+        // The weight 3 column has the same shift in all the core rows, so the
+        // core splits into z independent subsystems, and one bit of the first
+        // core parity column is inactivated in each of them. The remaining
+        // parity bits are obtained by peeling.
+        assert_eq!(encoder.ml_parity_bits(), z);
+        check_all_messages(&h, &encoder);
+
+        // With different shifts in the weight 3 column, as in the 5G NR base
+        // graphs, inactivating a single bit lets the peeling algorithm go
+        // through all the bits of the circulant.
+        let mut base = base;
+        base[1][2] = Some(0);
+        let h = lift(&base, z);
+        let encoder = Encoder::from_h(&h);
+        assert_eq!(encoder.ml_parity_bits(), 1);
+        check_all_messages(&h, &encoder);
+    }
+
+    #[test]
+    fn large_qc() {
+        // The dense elimination is only done on the inactivated bits, so the
+        // encoder can be built for long codes
+        let code = WimaxCode::new(WimaxRate::R1_2, 2304).unwrap();
+        let h = code.h();
+        let encoder = Encoder::from_h(&h);
+        assert_eq!(encoder.message_length(), code.k());
+        assert_eq!(
+            encoder.parity_positions(),
+            (code.k()..code.n()).collect::<Vec<_>>()
+        );
+        // As in the 5G NR codes, a single bit of the weight 3 parity column
+        // needs to be inactivated
+        assert_eq!(encoder.ml_parity_bits(), 1);
+        let mut rng = Rng::seed_from_u64(0);
+        let message = Array1::from_iter((0..code.k()).map(|_| {
+            if rng.gen::<bool>() {
+                GF2::one()
+            } else {
+                GF2::zero()
+            }
+        }));
+        let codeword = encoder.encode(&message);
+        for check in 0..h.num_rows() {
+            assert!(h
+                .iter_row(check)
+                .map(|&j| codeword[j])
+                .sum::<GF2>()
+                .is_zero());
+        }
+        assert_eq!(codeword.slice(s![..code.k()]), message);
+    }
+}
@@ -0,0 +1,347 @@
+//! Encoding with quasi-cyclic generator matrices.
+//!
+//! Some standards document their codes with a systematic quasi-cyclic
+//! generator matrix G = [I P], where P is formed by circulant blocks that are
+//! given by their first rows. For instance, this is how the generator of the
+//! CCSDS C2 code is published, with 511 x 511 circulants given in
+//! hexadecimal. This module contains [`QcGenerator`], which holds the
+//! circulants of P as a [`QcMatrix`]. It can be used to construct an
+//! [`Encoder`](super::Encoder) that encodes directly with the circulants (see
+//! [`Encoder::from_qc_generator`](super::Encoder::from_qc_generator)), and to
+//! construct the parity check matrix H = [P^T I] of the code for decoding.
+//!
+//! The circulants of a generator matrix are usually dense, so the parity check
+//! matrix obtained from the generator is not a good choice for belief
+//! propagation decoding. When the standard also publishes a sparse parity
+//! check matrix, as in the case of C2, it should be used instead, and
+//! [`QcGenerator::is_generator_of`] can be used to check that the generator
+//! and the parity check matrix describe the same code.
+//!
+//! # Examples
+//! ```
+//! # use ldpc_toolbox::{encoder::{qc_generator::QcGenerator, Encoder}, gf2::GF2};
+//! # use ndarray::Array1;
+//! # use num_traits::{One, Zero};
+//! // Generator with 2 x 1 circulants of size 5
+//! let generator = QcGenerator::from_hex(5, "# P\n13\n0a\n")?;
+//! assert_eq!(generator.message_length(), 10);
+//! assert_eq!(generator.parity_circulants().shifts(0, 0), &[0, 3, 4]);
+//! let h = generator.h();
+//! assert!(generator.is_generator_of(&h));
+//! let encoder = Encoder::from_qc_generator(generator);
+//! let message = Array1::from_iter((0..10).map(|j| if j < 2 { GF2::one() } else { GF2::zero() }));
+//! assert_eq!(encoder.encode(&message), Encoder::from_h(&h)?.encode(&message));
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use crate::{
+    gf2::GF2,
+    sparse::{QcMatrix, SparseMatrix},
+};
+use ndarray::{Array1, ArrayBase, Data, Ix1};
+use num_traits::{One, Zero};
+use thiserror::Error;
+
+/// QC generator error.
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Error)]
+pub enum Error {
+    /// A circulant is not a valid hexadecimal number.
+    #[error("invalid hexadecimal circulant {0}")]
+    InvalidHex(String),
+    /// A circulant has ones beyond the circulant size.
+    #[error("circulant {circulant} does not fit in {size} bits")]
+    CirculantTooLong {
+        /// Circulant, as given in hexadecimal.
+        circulant: String,
+        /// Circulant size.
+        size: usize,
+    },
+    /// The block rows do not have the same number of circulants.
+    #[error("block row {row} has {found} circulants instead of {expected}")]
+    RowLength {
+        /// Index of the block row.
+        row: usize,
+        /// Number of circulants of the first block row.
+        expected: usize,
+        /// Number of circulants of this block row.
+        found: usize,
+    },
+    /// The generator does not have any circulant.
+    #[error("the generator does not have any circulant")]
+    Empty,
+    /// The circulant size is zero.
+    #[error("the circulant size is zero")]
+    ZeroSize,
+}
+
+/// QC generator result.
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Systematic quasi-cyclic generator matrix.
+///
+/// The generator matrix is G = [I P], where P is given by a [`QcMatrix`] of
+/// circulants of size `z`. The block `(i, j)` of P has ones in the positions
+/// `(r, (r + s) % z)` for each of its shifts `s`, so the shifts are the
+/// positions of the ones in the first row of the circulant.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QcGenerator {
+    parity: QcMatrix,
+    size: usize,
+}
+
+impl QcGenerator {
+    /// Creates a generator from the circulants of its parity part.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `size` is zero or if some of the shifts of
+    /// `parity` is not smaller than `size`.
+    pub fn new(parity: QcMatrix, size: usize) -> QcGenerator {
+        assert!(size > 0, "circulant size is zero");
+        assert!(
+            parity.max_shift().is_none_or(|s| s < size),
+            "shift larger than the circulant size"
+        );
+        QcGenerator { parity, size }
+    }
+
+    /// Creates a generator from the first rows of its circulants in
+    /// hexadecimal.
+    ///
+    /// The text contains a line for each block row of P, with the circulants
+    /// separated by whitespace or commas. Each circulant is given by its first
+    /// row as a hexadecimal number, whose least significant bit is the last
+    /// element of the row. If `size` is not a multiple of 4, the row is padded
+    /// with zeros on the left. Empty lines and lines starting with `#` are
+    /// ignored.
+    pub fn from_hex(size: usize, text: &str) -> Result<QcGenerator> {
+        if size == 0 {
+            return Err(Error::ZeroSize);
+        }
+        let rows = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| {
+                line.split(|c: char| c.is_whitespace() || c == ',')
+                    .filter(|x| !x.is_empty())
+                    .map(|x| parse_circulant(x, size))
+                    .collect::<Result<Vec<_>>>()
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let expected = rows.first().map_or(0, |row| row.len());
+        if expected == 0 {
+            return Err(Error::Empty);
+        }
+        let mut parity = QcMatrix::new(rows.len(), expected);
+        for (row, circulants) in rows.iter().enumerate() {
+            if circulants.len() != expected {
+                return Err(Error::RowLength {
+                    row,
+                    expected,
+                    found: circulants.len(),
+                });
+            }
+            for (col, shifts) in circulants.iter().enumerate() {
+                parity.set_shifts(row, col, shifts.iter());
+            }
+        }
+        Ok(QcGenerator::new(parity, size))
+    }
+
+    /// Returns the circulant size.
+    pub fn circulant_size(&self) -> usize {
+        self.size
+    }
+
+    /// Returns the circulants of the parity part P of the generator.
+    pub fn parity_circulants(&self) -> &QcMatrix {
+        &self.parity
+    }
+
+    /// Returns the number of message bits `k`.
+    pub fn message_length(&self) -> usize {
+        self.parity.num_row_blocks() * self.size
+    }
+
+    /// Returns the codeword length `n`.
+    pub fn codeword_length(&self) -> usize {
+        (self.parity.num_row_blocks() + self.parity.num_col_blocks()) * self.size
+    }
+
+    /// Constructs the parity check matrix H = [P^T I].
+    ///
+    /// See the [module documentation](self) for the limitations of this matrix
+    /// for decoding.
+    pub fn h(&self) -> SparseMatrix {
+        let z = self.size;
+        let message_blocks = self.parity.num_row_blocks();
+        let parity_blocks = self.parity.num_col_blocks();
+        let mut h = QcMatrix::new(parity_blocks, message_blocks + parity_blocks);
+        for i in 0..message_blocks {
+            for j in 0..parity_blocks {
+                // The transpose of a circulant is the circulant with the
+                // opposite shifts
+                let shifts = self.parity.shifts(i, j).iter().map(|&s| (z - s) % z);
+                h.set_shifts(j, i, shifts);
+            }
+        }
+        for j in 0..parity_blocks {
+            h.set_shifts(j, message_blocks + j, [0].iter());
+        }
+        h.lift(z)
+    }
+
+    /// Checks if this is a generator matrix of the code with parity check
+    /// matrix `h`.
+    ///
+    /// This returns `true` if all the rows of the generator matrix are
+    /// codewords of `h`. If `h` has full rank, this means that the generator
+    /// and `h` describe the same code.
+    pub fn is_generator_of(&self, h: &SparseMatrix) -> bool {
+        if h.num_cols() != self.codeword_length() {
+            return false;
+        }
+        let k = self.message_length();
+        let z = self.size;
+        let mut syndrome = vec![false; h.num_rows()];
+        let add_col = |syndrome: &mut [bool], col: usize| {
+            for &row in h.iter_col(col) {
+                syndrome[row] ^= true;
+            }
+        };
+        for i in 0..self.parity.num_row_blocks() {
+            for r in 0..z {
+                add_col(&mut syndrome, i * z + r);
+                for j in 0..self.parity.num_col_blocks() {
+                    for &s in self.parity.shifts(i, j) {
+                        add_col(&mut syndrome, k + j * z + (r + s) % z);
+                    }
+                }
+                if syndrome.iter().any(|&x| x) {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+
+    // Computes the parity bits of a message.
+    pub(crate) fn parity<S>(&self, message: &ArrayBase<S, Ix1>) -> Array1<GF2>
+    where
+        S: Data<Elem = GF2>,
+    {
+        let z = self.size;
+        let mut parity = Array1::from_elem(self.parity.num_col_blocks() * z, GF2::zero());
+        for i in 0..self.parity.num_row_blocks() {
+            for r in 0..z {
+                if message[i * z + r].is_zero() {
+                    continue;
+                }
+                // Add row r of the block row i of P
+                for j in 0..self.parity.num_col_blocks() {
+                    for &s in self.parity.shifts(i, j) {
+                        parity[j * z + (r + s) % z] += GF2::one();
+                    }
+                }
+            }
+        }
+        parity
+    }
+}
+
+// Parses the first row of a circulant given in hexadecimal, returning the
+// positions of its ones.
+fn parse_circulant(hex: &str, size: usize) -> Result<Vec<usize>> {
+    let bits = 4 * hex.len();
+    let mut shifts = Vec::new();
+    for (j, c) in hex.chars().enumerate() {
+        let digit = c
+            .to_digit(16)
+            .ok_or_else(|| Error::InvalidHex(hex.to_string()))?;
+        for b in 0..4 {
+            if digit & (8 >> b) == 0 {
+                continue;
+            }
+            // Position of the bit counting from the end of the row
+            let from_end = bits - 1 - (4 * j + b);
+            if from_end >= size {
+                return Err(Error::CirculantTooLong {
+                    circulant: hex.to_string(),
+                    size,
+                });
+            }
+            shifts.push(size - 1 - from_end);
+        }
+    }
+    Ok(shifts)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::encoder::{Encoder, EncodingMethod};
+
+    #[test]
+    fn from_hex() {
+        let generator = QcGenerator::from_hex(5, "11, 3\n1f 0\n").unwrap();
+        assert_eq!(generator.circulant_size(), 5);
+        assert_eq!(generator.message_length(), 10);
+        assert_eq!(generator.codeword_length(), 20);
+        let parity = generator.parity_circulants();
+        assert_eq!(parity.shifts(0, 0), &[0, 4]);
+        assert_eq!(parity.shifts(0, 1), &[3, 4]);
+        assert_eq!(parity.shifts(1, 0), &[0, 1, 2, 3, 4]);
+        assert!(parity.shifts(1, 1).is_empty());
+
+        assert_eq!(
+            QcGenerator::from_hex(5, "3f"),
+            Err(Error::CirculantTooLong {
+                circulant: "3f".to_string(),
+                size: 5
+            })
+        );
+        assert_eq!(
+            QcGenerator::from_hex(5, "1g"),
+            Err(Error::InvalidHex("1g".to_string()))
+        );
+        assert_eq!(
+            QcGenerator::from_hex(5, "1 2\n3"),
+            Err(Error::RowLength {
+                row: 1,
+                expected: 2,
+                found: 1
+            })
+        );
+        assert_eq!(QcGenerator::from_hex(5, "# empty"), Err(Error::Empty));
+        assert_eq!(QcGenerator::from_hex(0, "1"), Err(Error::ZeroSize));
+    }
+
+    #[test]
+    fn encode() {
+        let parity = QcMatrix::from_exponents("0+2 5\n1 -1\n3+4+6 0").unwrap();
+        let generator = QcGenerator::new(parity, 7);
+        let h = generator.h();
+        assert_eq!(h.num_rows(), 14);
+        assert_eq!(h.num_cols(), 35);
+        assert!(generator.is_generator_of(&h));
+        let mut wrong = h.clone();
+        wrong.toggle(0, 0);
+        assert!(!generator.is_generator_of(&wrong));
+        assert!(!generator.is_generator_of(&SparseMatrix::new(14, 28)));
+
+        let encoder = Encoder::from_qc_generator(generator);
+        assert_eq!(encoder.method(), EncodingMethod::QcGenerator);
+        let reference = Encoder::from_h(&h).unwrap();
+        for j in 0..50_usize {
+            let message = Array1::from_iter((0..21).map(|b| {
+                if (j * 7 + b * b) % 5 < 2 {
+                    GF2::one()
+                } else {
+                    GF2::zero()
+                }
+            }));
+            assert_eq!(encoder.encode(&message), reference.encode(&message));
+        }
+    }
+}
@@ -2,11 +2,18 @@
 //!
 //! This module contains the struct [GF2], which implements the finite field
 //! arithmetic in GF(2).
+//!
+//! The submodule [`poly`] implements polynomials over GF(2), and the submodule
+//! [`gf2m`] implements the extension fields GF(2^m). These are used in the
+//! algebraic constructions of cyclic and quasi-cyclic LDPC codes.
 
 use core::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Sub, SubAssign};
 use ndarray::ScalarOperand;
 use num_traits::{One, Zero};
 
+pub mod gf2m;
+pub mod poly;
+
 /// Finite field GF(2) element.
 ///
 /// This struct represents an element of the finite field GF(2).
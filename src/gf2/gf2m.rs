@@ -0,0 +1,312 @@
+//! Finite field GF(2^m) arithmetic.
+//!
+//! This module contains the struct [`GF2m`], which implements the arithmetic
+//! of the finite field GF(2^m) defined by a primitive polynomial. It is used in
+//! the algebraic constructions of quasi-cyclic LDPC codes based on finite
+//! fields, such as the codes based on Reed-Solomon codes, in which a matrix of
+//! field elements is turned into a parity check matrix by replacing each
+//! element by a circulant permutation matrix (see [`GF2m::dispersion`]).
+//!
+//! The elements of the field are represented as `u32` integers whose bits are
+//! the coefficients of the element in the polynomial basis, so the bit `j` is
+//! the coefficient of α^`j`, where α is a root of the primitive polynomial.
+
+use super::poly::Poly;
+use crate::sparse::SparseMatrix;
+use ndarray::Array2;
+use thiserror::Error;
+
+/// Finite field error.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Error)]
+pub enum Error {
+    /// The polynomial is not primitive.
+    #[error("the polynomial is not primitive")]
+    NotPrimitive,
+    /// The degree of the polynomial is not supported.
+    #[error("unsupported field degree {0} (must be between 1 and {max})", max = GF2m::MAX_DEGREE)]
+    UnsupportedDegree(usize),
+}
+
+/// Finite field GF(2^m).
+///
+/// The field is constructed from a primitive polynomial of degree m, and the
+/// multiplication uses tables of logarithms and powers of the primitive
+/// element α.
+///
+/// # Examples
+/// ```
+/// # use ldpc_toolbox::gf2::{gf2m::GF2m, poly::Poly};
+/// let field = GF2m::new(&"x^3 + x + 1".parse()?)?;
+/// assert_eq!(field.order(), 8);
+/// let a = field.alpha_pow(3);
+/// assert_eq!(a, 0b011); // α^3 = α + 1
+/// assert_eq!(field.mul(a, field.alpha_pow(4)), 1);
+/// assert_eq!(field.inv(a), field.alpha_pow(4));
+/// assert_eq!(field.minimal_polynomial(a).to_string(), "x^3 + x^2 + 1");
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct GF2m {
+    m: usize,
+    // exp[j] = α^j, for j = 0, ..., 2^m - 2
+    exp: Vec<u32>,
+    // log[x] = j such that α^j = x, for x != 0
+    log: Vec<u32>,
+}
+
+impl GF2m {
+    /// Maximum supported degree m.
+    pub const MAX_DEGREE: usize = 20;
+
+    /// Creates the field defined by a primitive polynomial.
+    ///
+    /// The degree m of the polynomial must be between 1 and
+    /// [`GF2m::MAX_DEGREE`].
+    pub fn new(primitive: &Poly) -> Result<GF2m, Error> {
+        let m = primitive.degree().unwrap_or(0);
+        if !(1..=Self::MAX_DEGREE).contains(&m) {
+            return Err(Error::UnsupportedDegree(m));
+        }
+        if !primitive.is_primitive() {
+            return Err(Error::NotPrimitive);
+        }
+        let reduction = primitive
+            .exponents()
+            .filter(|&e| e < m)
+            .fold(0u32, |acc, e| acc | (1 << e));
+        let size = 1usize << m;
+        let mut exp = Vec::with_capacity(size - 1);
+        let mut log = vec![0; size];
+        let mut x = 1u32;
+        for j in 0..size - 1 {
+            exp.push(x);
+            log[x as usize] = j as u32;
+            x <<= 1;
+            if x & (1 << m) != 0 {
+                x ^= (1 << m) | reduction;
+            }
+        }
+        Ok(GF2m { m, exp, log })
+    }
+
+    /// Returns the degree m of the field over GF(2).
+    pub fn degree(&self) -> usize {
+        self.m
+    }
+
+    /// Returns the number of elements of the field, which is 2^m.
+    pub fn order(&self) -> usize {
+        1 << self.m
+    }
+
+    /// Returns the power α^`j` of the primitive element.
+    pub fn alpha_pow(&self, j: usize) -> u32 {
+        self.exp[j % self.exp.len()]
+    }
+
+    /// Returns the logarithm of an element in base α.
+    ///
+    /// The logarithm is between 0 and 2^m - 2. The zero element has no
+    /// logarithm, so `None` is returned for it.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `x` is not an element of the field.
+    pub fn log(&self, x: u32) -> Option<usize> {
+        assert!((x as usize) < self.order(), "invalid field element");
+        (x != 0).then(|| self.log[x as usize] as usize)
+    }
+
+    /// Adds two elements.
+    pub fn add(&self, a: u32, b: u32) -> u32 {
+        a ^ b
+    }
+
+    /// Multiplies two elements.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `a` or `b` are not elements of the field.
+    pub fn mul(&self, a: u32, b: u32) -> u32 {
+        match (self.log(a), self.log(b)) {
+            (Some(i), Some(j)) => self.alpha_pow(i + j),
+            _ => 0,
+        }
+    }
+
+    /// Computes the multiplicative inverse of an element.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `a` is zero or is not an element of the field.
+    pub fn inv(&self, a: u32) -> u32 {
+        let j = self.log(a).expect("zero has no inverse");
+        self.alpha_pow(self.exp.len() - j)
+    }
+
+    /// Returns the minimal polynomial of an element over GF(2).
+    ///
+    /// The minimal polynomial is the product of x + b for all the conjugates
+    /// b = `a`^(2^j) of the element. The generator polynomial of a cyclic code
+    /// with given roots, such as a BCH code, is the least common multiple of
+    /// the minimal polynomials of its roots (see
+    /// [`cyclic_parity_check`](super::poly::cyclic_parity_check)).
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `a` is not an element of the field.
+    pub fn minimal_polynomial(&self, a: u32) -> Poly {
+        let mut conjugates = vec![a];
+        let mut b = self.mul(a, a);
+        while b != a {
+            conjugates.push(b);
+            b = self.mul(b, b);
+        }
+        // Coefficients of the product in GF(2^m), lowest degree first
+        let mut product = vec![1u32];
+        for c in conjugates {
+            let mut next = vec![0; product.len() + 1];
+            for (j, &p) in product.iter().enumerate() {
+                next[j + 1] ^= p;
+                next[j] ^= self.mul(p, c);
+            }
+            product = next;
+        }
+        Poly::from_exponents(
+            product
+                .iter()
+                .enumerate()
+                .filter(|(_, &p)| p != 0)
+                .map(|(j, &p)| {
+                    assert_eq!(p, 1, "the minimal polynomial has binary coefficients");
+                    j
+                }),
+        )
+    }
+
+    /// Replaces each element of a matrix by a circulant permutation matrix.
+    ///
+    /// This returns the binary matrix obtained by replacing each element of
+    /// `w` by a square block of size 2^m - 1. The zero element is replaced by
+    /// the zero block, and the element α^`j` is replaced by the circulant
+    /// permutation matrix whose first row has a one in column `j`. This is the
+    /// "dispersion" used in the finite field constructions of quasi-cyclic LDPC
+    /// codes. If the matrix `w` satisfies the row-column constraint of these
+    /// constructions, the Tanner graph of the result has girth at least 6.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if some entry of `w` is not an element of the
+    /// field.
+    ///
+    /// # Examples
+    /// ```
+    /// # use ldpc_toolbox::gf2::{gf2m::GF2m, poly::Poly};
+    /// # use ndarray::Array2;
+    /// // Base matrix W = [α^(i j)] of size 3 x 7 over GF(8)
+    /// let field = GF2m::new(&"x^3 + x + 1".parse()?)?;
+    /// let w = Array2::from_shape_fn((3, 7), |(i, j)| field.alpha_pow(i * j));
+    /// let h = field.dispersion(&w);
+    /// assert_eq!((h.num_rows(), h.num_cols()), (21, 49));
+    /// assert!(h.girth().unwrap() >= 6);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn dispersion(&self, w: &Array2<u32>) -> SparseMatrix {
+        let size = self.exp.len();
+        let mut h = SparseMatrix::new(w.nrows() * size, w.ncols() * size);
+        for ((i, j), &x) in w.indexed_iter() {
+            if let Some(shift) = self.log(x) {
+                for r in 0..size {
+                    h.insert(i * size + r, j * size + (r + shift) % size);
+                }
+            }
+        }
+        h
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn field(p: &str) -> GF2m {
+        GF2m::new(&p.parse().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn arithmetic() {
+        let f = field("x^4 + x + 1");
+        assert_eq!(f.degree(), 4);
+        assert_eq!(f.order(), 16);
+        for a in 1..16 {
+            assert_eq!(f.mul(a, f.inv(a)), 1);
+            assert_eq!(f.alpha_pow(f.log(a).unwrap()), a);
+            for b in 0..16 {
+                // Distributivity
+                for c in 0..16 {
+                    assert_eq!(f.mul(a, f.add(b, c)), f.add(f.mul(a, b), f.mul(a, c)));
+                }
+            }
+        }
+        assert_eq!(f.log(0), None);
+        assert_eq!(f.mul(0, 7), 0);
+        let elements = (0..15)
+            .map(|j| f.alpha_pow(j))
+            .collect::<std::collections::HashSet<_>>();
+        assert_eq!(elements.len(), 15);
+    }
+
+    #[test]
+    fn errors() {
+        let p = |s: &str| s.parse::<Poly>().unwrap();
+        assert_eq!(
+            GF2m::new(&p("x^4 + x^3 + x^2 + x + 1")),
+            Err(Error::NotPrimitive)
+        );
+        assert_eq!(GF2m::new(&p("1")), Err(Error::UnsupportedDegree(0)));
+        assert_eq!(
+            GF2m::new(&p("x^21 + x^2 + 1")),
+            Err(Error::UnsupportedDegree(21))
+        );
+    }
+
+    #[test]
+    fn minimal_polynomials() {
+        let f = field("x^4 + x + 1");
+        assert_eq!(f.minimal_polynomial(1).to_string(), "x + 1");
+        assert_eq!(
+            f.minimal_polynomial(f.alpha_pow(1)).to_string(),
+            "x^4 + x + 1"
+        );
+        assert_eq!(
+            f.minimal_polynomial(f.alpha_pow(3)).to_string(),
+            "x^4 + x^3 + x^2 + x + 1"
+        );
+        assert_eq!(
+            f.minimal_polynomial(f.alpha_pow(5)).to_string(),
+            "x^2 + x + 1"
+        );
+        // The product of the minimal polynomials of the conjugacy classes is
+        // x^15 + 1
+        let product = [0, 1, 3, 5, 7]
+            .iter()
+            .map(|&j| f.minimal_polynomial(f.alpha_pow(j)))
+            .fold(Poly::monomial(1), |acc, p| &acc * &p);
+        assert_eq!(product, Poly::from_exponents([1, 16]));
+    }
+
+    #[test]
+    fn dispersion() {
+        let f = field("x^3 + x + 1");
+        let w = ndarray::arr2(&[[1, 0], [f.alpha_pow(2), f.alpha_pow(6)]]);
+        let h = f.dispersion(&w);
+        assert_eq!((h.num_rows(), h.num_cols()), (14, 14));
+        for r in 0..7 {
+            assert_eq!(h.iter_row(r).copied().collect::<Vec<_>>(), [r]);
+            assert_eq!(
+                h.iter_row(7 + r).copied().collect::<Vec<_>>(),
+                [(r + 2) % 7, 7 + (r + 6) % 7]
+            );
+        }
+    }
+}
@@ -0,0 +1,569 @@
+//! Polynomials over GF(2).
+//!
+//! This module contains the struct [`Poly`], which implements the arithmetic
+//! of polynomials with coefficients in GF(2), together with some utilities to
+//! construct LDPC codes from cyclic and quasi-cyclic algebraic designs. The
+//! multiplication by a polynomial modulo x^n + 1 is given by a circulant
+//! matrix (see [`Poly::circulant`]), and the parity check matrix of a cyclic
+//! code is obtained from its generator polynomial with
+//! [`cyclic_parity_check`].
+//!
+//! # Examples
+//! ```
+//! # use ldpc_toolbox::gf2::poly::Poly;
+//! # use num_traits::Zero;
+//! let g: Poly = "x^3 + x + 1".parse()?;
+//! assert!(g.is_primitive());
+//! let x7_plus_1 = Poly::monomial(7) + Poly::monomial(0);
+//! let (h, rem) = x7_plus_1.div_rem(&g);
+//! assert!(rem.is_zero());
+//! assert_eq!(h.to_string(), "x^4 + x^2 + x + 1");
+//! # Ok::<(), String>(())
+//! ```
+
+use super::GF2;
+use crate::sparse::SparseMatrix;
+use core::ops::{Add, AddAssign, Mul, Sub};
+use num_traits::{One, Zero};
+use thiserror::Error;
+
+/// Polynomial error.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Error)]
+pub enum Error {
+    /// The generator polynomial does not divide x^n + 1.
+    #[error("the generator polynomial does not divide x^n + 1")]
+    NotDivisor,
+}
+
+/// Polynomial over GF(2).
+///
+/// The coefficients are stored packed in 64-bit words, so that the addition
+/// and the multiplication operate on 64 coefficients at a time.
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Default)]
+pub struct Poly {
+    // Bit j of word i is the coefficient of x^(64 * i + j). The last word is
+    // not zero.
+    words: Vec<u64>,
+}
+
+impl Poly {
+    /// Returns the monomial x^`degree`.
+    pub fn monomial(degree: usize) -> Poly {
+        let mut words = vec![0; degree / 64 + 1];
+        words[degree / 64] = 1 << (degree % 64);
+        Poly { words }
+    }
+
+    /// Creates a polynomial from the exponents of its terms.
+    ///
+    /// Repeated exponents cancel each other.
+    pub fn from_exponents<I: IntoIterator<Item = usize>>(exponents: I) -> Poly {
+        let mut words = Vec::new();
+        for e in exponents {
+            if words.len() <= e / 64 {
+                words.resize(e / 64 + 1, 0);
+            }
+            words[e / 64] ^= 1 << (e % 64);
+        }
+        Poly::normalized(words)
+    }
+
+    /// Creates a polynomial from its coefficients.
+    ///
+    /// The coefficient of x^j is `coefficients[j]`.
+    pub fn from_coefficients(coefficients: &[GF2]) -> Poly {
+        Poly::from_exponents(
+            coefficients
+                .iter()
+                .enumerate()
+                .filter(|(_, c)| c.is_one())
+                .map(|(j, _)| j),
+        )
+    }
+
+    fn normalized(mut words: Vec<u64>) -> Poly {
+        while words.last() == Some(&0) {
+            words.pop();
+        }
+        Poly { words }
+    }
+
+    /// Returns the degree of the polynomial.
+    ///
+    /// The zero polynomial has no degree, so `None` is returned for it.
+    pub fn degree(&self) -> Option<usize> {
+        let last = *self.words.last()?;
+        Some(64 * (self.words.len() - 1) + 63 - last.leading_zeros() as usize)
+    }
+
+    /// Returns the coefficient of x^`j`.
+    pub fn coefficient(&self, j: usize) -> GF2 {
+        match self.words.get(j / 64) {
+            Some(w) if (w >> (j % 64)) & 1 == 1 => GF2::one(),
+            _ => GF2::zero(),
+        }
+    }
+
+    /// Returns the exponents of the terms of the polynomial, in increasing
+    /// order.
+    pub fn exponents(&self) -> impl Iterator<Item = usize> + '_ {
+        self.words.iter().enumerate().flat_map(|(i, &w)| {
+            (0..64)
+                .filter(move |j| (w >> j) & 1 == 1)
+                .map(move |j| 64 * i + j)
+        })
+    }
+
+    /// Returns the number of non-zero terms of the polynomial.
+    pub fn weight(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    // Adds x^shift * other to self.
+    fn add_shifted(&mut self, other: &Poly, shift: usize) {
+        let (word_shift, bit_shift) = (shift / 64, shift % 64);
+        let len = other.words.len() + word_shift + 1;
+        if self.words.len() < len {
+            self.words.resize(len, 0);
+        }
+        for (i, &w) in other.words.iter().enumerate() {
+            self.words[i + word_shift] ^= w << bit_shift;
+            if bit_shift != 0 {
+                self.words[i + word_shift + 1] ^= w >> (64 - bit_shift);
+            }
+        }
+        *self = Poly::normalized(std::mem::take(&mut self.words));
+    }
+
+    /// Computes the quotient and the remainder of the division by `divisor`.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `divisor` is zero.
+    pub fn div_rem(&self, divisor: &Poly) -> (Poly, Poly) {
+        let d = divisor.degree().expect("division by zero");
+        let mut quotient = Vec::new();
+        let mut rem = self.clone();
+        while let Some(r) = rem.degree().filter(|&r| r >= d) {
+            quotient.push(r - d);
+            rem.add_shifted(divisor, r - d);
+        }
+        (Poly::from_exponents(quotient), rem)
+    }
+
+    /// Computes the greatest common divisor with another polynomial.
+    ///
+    /// The greatest common divisor of two zero polynomials is zero.
+    pub fn gcd(&self, other: &Poly) -> Poly {
+        let (mut a, mut b) = (self.clone(), other.clone());
+        while !b.is_zero() {
+            let (_, rem) = a.div_rem(&b);
+            a = b;
+            b = rem;
+        }
+        a
+    }
+
+    /// Computes the product with `other` modulo `modulus`.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `modulus` is zero.
+    pub fn mul_mod(&self, other: &Poly, modulus: &Poly) -> Poly {
+        (self * other).div_rem(modulus).1
+    }
+
+    /// Computes the power `self^exponent` modulo `modulus`.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `modulus` is zero.
+    pub fn pow_mod(&self, exponent: u64, modulus: &Poly) -> Poly {
+        let mut result = Poly::one().div_rem(modulus).1;
+        let mut base = self.div_rem(modulus).1;
+        let mut exponent = exponent;
+        while exponent != 0 {
+            if exponent & 1 == 1 {
+                result = result.mul_mod(&base, modulus);
+            }
+            base = base.mul_mod(&base, modulus);
+            exponent >>= 1;
+        }
+        result
+    }
+
+    /// Returns the reciprocal polynomial.
+    ///
+    /// The reciprocal of a polynomial p(x) of degree d is x^d p(1/x), which
+    /// has the coefficients of p(x) in reverse order.
+    pub fn reciprocal(&self) -> Poly {
+        match self.degree() {
+            Some(d) => Poly::from_exponents(self.exponents().map(|e| d - e)),
+            None => Poly::zero(),
+        }
+    }
+
+    /// Returns `true` if the polynomial is irreducible.
+    ///
+    /// Polynomials of degree zero and the zero polynomial are not irreducible.
+    /// The test checks that gcd(x^(2^j) + x, p(x)) = 1 for j = 1, ..., d/2,
+    /// where d is the degree of p(x).
+    pub fn is_irreducible(&self) -> bool {
+        let d = match self.degree() {
+            None | Some(0) => return false,
+            Some(d) => d,
+        };
+        let x = Poly::monomial(1);
+        let mut x_pow = x.clone();
+        for _ in 0..d / 2 {
+            x_pow = x_pow.mul_mod(&x_pow, self);
+            if !self.gcd(&(&x_pow + &x)).is_one() {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Returns `true` if the polynomial is primitive.
+    ///
+    /// A polynomial of degree m is primitive if it is irreducible and x has
+    /// order 2^m - 1 modulo the polynomial, so that x is a primitive element
+    /// of the field GF(2^m) that it defines.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the degree of the polynomial is larger than 63.
+    pub fn is_primitive(&self) -> bool {
+        // x is irreducible, but it is not invertible modulo itself
+        if !self.is_irreducible() || self.coefficient(0).is_zero() {
+            return false;
+        }
+        let m = self.degree().unwrap();
+        assert!(m < 64, "primitivity test only supported up to degree 63");
+        let order = (1u64 << m) - 1;
+        let x = Poly::monomial(1);
+        prime_factors(order)
+            .into_iter()
+            .all(|q| !x.pow_mod(order / q, self).is_one())
+    }
+
+    /// Returns the circulant matrix of the polynomial.
+    ///
+    /// The circulant matrix has size `size` x `size`, and its row `j` contains
+    /// the coefficients of x^j p(x) modulo x^`size` + 1. Therefore, the first
+    /// row contains the coefficients of the polynomial reduced modulo
+    /// x^`size` + 1, and each row is the cyclic shift to the right of the
+    /// previous one.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `size` is zero.
+    ///
+    /// # Examples
+    /// ```
+    /// # use ldpc_toolbox::gf2::poly::Poly;
+    /// // x^6 = x^2 modulo x^4 + 1
+    /// let c = Poly::from_exponents([0, 1, 6]).circulant(4);
+    /// assert_eq!(c.row_weight(0), 3);
+    /// assert!(c.contains(0, 2) && c.contains(3, 1) && !c.contains(3, 2));
+    /// ```
+    pub fn circulant(&self, size: usize) -> SparseMatrix {
+        assert!(size > 0);
+        let first_row = Poly::from_exponents(self.exponents().map(|e| e % size));
+        let mut c = SparseMatrix::new(size, size);
+        for j in 0..size {
+            c.insert_row(j, first_row.exponents().map(|e| (e + j) % size));
+        }
+        c
+    }
+}
+
+// Returns the distinct prime factors of n.
+fn prime_factors(mut n: u64) -> Vec<u64> {
+    let mut factors = Vec::new();
+    let mut q = 2;
+    while q * q <= n {
+        if n.is_multiple_of(q) {
+            factors.push(q);
+            while n.is_multiple_of(q) {
+                n /= q;
+            }
+        }
+        q += 1;
+    }
+    if n > 1 {
+        factors.push(n);
+    }
+    factors
+}
+
+/// Returns the parity check matrix of a cyclic code.
+///
+/// The cyclic code has length `n` and generator polynomial g(x), which must
+/// divide x^`n` + 1. The parity check polynomial is h(x) = (x^`n` + 1) /
+/// g(x), and the row `j` of the parity check matrix checks the coefficient of
+/// x^`j` of the product c(x) h(x) modulo x^`n` + 1, which is zero for all the
+/// codewords c(x).
+///
+/// The parity check matrix has `n` rows, which are the cyclic shifts of the
+/// reversed h(x), so its rank is only n - k, where k is the degree of h(x).
+/// The redundant rows are kept, since they improve the performance of
+/// iterative decoding of cyclic LDPC codes, such as the finite geometry
+/// codes. An [`Encoder`](crate::encoder::Encoder) for the code requires a
+/// parity check matrix with maximum rank, which can be obtained from the
+/// generator matrix instead.
+///
+/// # Examples
+/// ```
+/// # use ldpc_toolbox::gf2::poly::{cyclic_parity_check, Poly};
+/// // (7, 4) Hamming code
+/// let g = Poly::from_exponents([0, 1, 3]);
+/// let h = cyclic_parity_check(7, &g)?;
+/// assert_eq!(h.num_rows(), 7);
+/// assert!((0..7).all(|j| h.row_weight(j) == 4));
+/// # Ok::<(), ldpc_toolbox::gf2::poly::Error>(())
+/// ```
+pub fn cyclic_parity_check(n: usize, generator: &Poly) -> Result<SparseMatrix, Error> {
+    let (parity, rem) = (Poly::monomial(n) + Poly::one()).div_rem(generator);
+    if !rem.is_zero() {
+        return Err(Error::NotDivisor);
+    }
+    let mut h = SparseMatrix::new(n, n);
+    for j in 0..n {
+        h.insert_row(j, parity.exponents().map(|e| (j + n - e % n) % n));
+    }
+    Ok(h)
+}
+
+impl Zero for Poly {
+    fn zero() -> Poly {
+        Poly::default()
+    }
+
+    fn is_zero(&self) -> bool {
+        self.words.is_empty()
+    }
+}
+
+impl One for Poly {
+    fn one() -> Poly {
+        Poly::monomial(0)
+    }
+
+    fn is_one(&self) -> bool {
+        self.words == [1]
+    }
+}
+
+impl Add<&Poly> for &Poly {
+    type Output = Poly;
+
+    fn add(self, rhs: &Poly) -> Poly {
+        let mut sum = self.clone();
+        sum += rhs;
+        sum
+    }
+}
+
+impl Add for Poly {
+    type Output = Poly;
+
+    fn add(mut self, rhs: Poly) -> Poly {
+        self += &rhs;
+        self
+    }
+}
+
+impl AddAssign<&Poly> for Poly {
+    fn add_assign(&mut self, rhs: &Poly) {
+        self.add_shifted(rhs, 0);
+    }
+}
+
+impl Sub<&Poly> for &Poly {
+    type Output = Poly;
+
+    #[allow(clippy::suspicious_arithmetic_impl)]
+    fn sub(self, rhs: &Poly) -> Poly {
+        self + rhs
+    }
+}
+
+impl Sub for Poly {
+    type Output = Poly;
+
+    #[allow(clippy::suspicious_arithmetic_impl)]
+    fn sub(self, rhs: Poly) -> Poly {
+        self + rhs
+    }
+}
+
+impl Mul<&Poly> for &Poly {
+    type Output = Poly;
+
+    fn mul(self, rhs: &Poly) -> Poly {
+        let mut product = Poly::zero();
+        for e in self.exponents() {
+            product.add_shifted(rhs, e);
+        }
+        product
+    }
+}
+
+impl Mul for Poly {
+    type Output = Poly;
+
+    fn mul(self, rhs: Poly) -> Poly {
+        &self * &rhs
+    }
+}
+
+impl std::fmt::Display for Poly {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_zero() {
+            return write!(f, "0");
+        }
+        let exponents = self.exponents().collect::<Vec<_>>();
+        for (j, &e) in exponents.iter().rev().enumerate() {
+            if j != 0 {
+                write!(f, " + ")?;
+            }
+            match e {
+                0 => write!(f, "1")?,
+                1 => write!(f, "x")?,
+                _ => write!(f, "x^{e}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for Poly {
+    type Err = String;
+
+    /// Parses a polynomial.
+    ///
+    /// The polynomial is given as a sum of terms `1`, `x` and `x^<exponent>`,
+    /// such as `x^3 + x + 1`, or as `0`. Repeated terms cancel each other.
+    fn from_str(s: &str) -> Result<Poly, String> {
+        if s.trim() == "0" {
+            return Ok(Poly::zero());
+        }
+        let exponents = s
+            .split('+')
+            .map(|term| match term.trim() {
+                "1" => Ok(0),
+                "x" => Ok(1),
+                t => t
+                    .strip_prefix("x^")
+                    .and_then(|e| e.parse().ok())
+                    .ok_or_else(|| format!("invalid polynomial {s}")),
+            })
+            .collect::<Result<Vec<usize>, _>>()?;
+        Ok(Poly::from_exponents(exponents))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn arithmetic() {
+        let a = Poly::from_exponents([0, 1, 70]);
+        let b = Poly::from_exponents([1, 3, 64, 130]);
+        assert_eq!(a.degree(), Some(70));
+        assert_eq!(Poly::zero().degree(), None);
+        assert_eq!(&a + &a, Poly::zero());
+        assert_eq!((&a + &b).to_string(), "x^130 + x^70 + x^64 + x^3 + 1");
+        let product = &a * &b;
+        assert_eq!(product.degree(), Some(200));
+        let (q, r) = product.div_rem(&b);
+        assert_eq!(q, a);
+        assert!(r.is_zero());
+        let c = &product + &Poly::from_exponents([2, 5]);
+        let (q, r) = c.div_rem(&a);
+        assert_eq!(q, b);
+        assert_eq!(r, Poly::from_exponents([2, 5]));
+        assert_eq!(a.gcd(&product), a);
+        assert_eq!(a.reciprocal(), Poly::from_exponents([0, 69, 70]));
+        assert_eq!(a.coefficient(70), GF2::one());
+        assert_eq!(a.coefficient(69), GF2::zero());
+        assert_eq!(a.weight(), 3);
+    }
+
+    #[test]
+    fn irreducible_and_primitive() {
+        let p = |s: &str| s.parse::<Poly>().unwrap();
+        assert!(p("x^2 + x + 1").is_primitive());
+        assert!(p("x^4 + x + 1").is_primitive());
+        // Irreducible but not primitive: x has order 5
+        assert!(p("x^4 + x^3 + x^2 + x + 1").is_irreducible());
+        assert!(!p("x^4 + x^3 + x^2 + x + 1").is_primitive());
+        assert!(!p("x^4 + x^2 + 1").is_irreducible());
+        assert!(!p("1").is_irreducible());
+        assert!(p("x").is_irreducible());
+        assert!(!p("x").is_primitive());
+        assert!(p("x + 1").is_primitive());
+        // Number of primitive polynomials of degree 6 is phi(63) / 6 = 6
+        let primitive = (0..64)
+            .map(|j| Poly::from_exponents((0..6).filter(|b| (j >> b) & 1 == 1).chain([6])))
+            .filter(Poly::is_primitive)
+            .count();
+        assert_eq!(primitive, 6);
+        // Fermat: x^(2^m) = x modulo an irreducible polynomial of degree m
+        let q = p("x^31 + x^3 + 1");
+        assert!(q.is_primitive());
+        assert_eq!(Poly::monomial(1).pow_mod(1 << 31, &q), Poly::monomial(1));
+    }
+
+    #[test]
+    fn parse() {
+        for s in ["0", "1", "x", "x^5 + x^2 + 1"] {
+            assert_eq!(s.parse::<Poly>().unwrap().to_string(), s);
+        }
+        assert_eq!("x + x + 1".parse::<Poly>().unwrap(), Poly::one());
+        for s in ["", "x^", "y + 1", "x^-1"] {
+            assert!(s.parse::<Poly>().is_err());
+        }
+    }
+
+    #[test]
+    fn circulant() {
+        let p = Poly::from_exponents([0, 2, 3]);
+        let c = p.circulant(7);
+        for j in 0..7 {
+            let row = Poly::from_exponents(c.iter_row(j).copied());
+            assert_eq!(
+                row,
+                p.mul_mod(&Poly::monomial(j), &(Poly::monomial(7) + Poly::one()))
+            );
+        }
+    }
+
+    #[test]
+    fn cyclic_code() {
+        // (15, 7) BCH code, generated by the minimal polynomials of alpha and
+        // alpha^3 in GF(16)
+        let g = &"x^4 + x + 1".parse::<Poly>().unwrap()
+            * &"x^4 + x^3 + x^2 + x + 1".parse::<Poly>().unwrap();
+        let h = cyclic_parity_check(15, &g).unwrap();
+        let solver = crate::linalg::SparseSolver::new(&h);
+        assert_eq!(solver.rank(), 8);
+        for message in 0..128_usize {
+            let m = Poly::from_exponents((0..7).filter(|b| (message >> b) & 1 == 1));
+            let codeword = &m * &g;
+            for j in 0..15 {
+                let check: usize = h
+                    .iter_row(j)
+                    .filter(|&&k| codeword.coefficient(k).is_one())
+                    .count();
+                assert_eq!(check % 2, 0);
+            }
+        }
+        assert_eq!(
+            cyclic_parity_check(15, &Poly::from_exponents([0, 2, 3])),
+            Err(Error::NotDivisor)
+        );
+    }
+}
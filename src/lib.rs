@@ -7,21 +7,59 @@
 //! It can be used as a Rust library or as a CLI tool that allows access from
 //! the command line to many of the algorithms implemented in `ldpc-toolbox`. See
 //! [`cli`] for documentation about the usage of the CLI tool.
+//!
+//! # Features
+//!
+//! The core of the crate (sparse matrices, code constructions, standard codes,
+//! encoders and decoders) is always available and has a small set of
+//! dependencies. The following Cargo features enable additional parts of the
+//! crate:
+//!
+//! - `simulation`: the [`simulation`] module, which contains the BER test and
+//!   the channel and modulation models.
+//! - `cli`: the [`cli`] module and the `ldpc-toolbox` binary. This implies
+//!   `simulation` and `rayon`.
+//! - `c_api`: the C API for the encoder and decoder. This implies `simulation`.
+//! - `rayon`: parallel searches, such as
+//!   [`mackay_neal::Config::search`], and parallel decoding of a single
+//!   codeword with
+//!   [`horizontal_layered::Decoder::decode_parallel`](decoder::horizontal_layered::Decoder::decode_parallel).
+//! - `profiling`: collection of the [`DecoderCounters`](decoder::DecoderCounters)
+//!   in the decoders, which count the node updates and messages of each
+//!   decoding.
+//! - `serde`: `Serialize` and `Deserialize` implementations for
+//!   [`SparseMatrix`](sparse::SparseMatrix), which is serialized as its
+//!   dimensions and the list of the positions of its ones, for the
+//!   configurations [`mackay_neal::Config`] and [`peg::Config`], and for the
+//!   BER test [`Statistics`](simulation::ber::Statistics). This allows storing
+//!   the generated codes and the results of simulation campaigns as JSON or in
+//!   other formats supported by serde.
+//!
+//! The `cli` and `c_api` features are enabled by default. Library users that
+//! only need the core can use `default-features = false`.
 
 #![warn(missing_docs)]
 
+#[cfg(feature = "c_api")]
 mod c_api;
+pub mod cancel;
+#[cfg(feature = "cli")]
 pub mod cli;
 pub mod codes;
 pub mod decoder;
 pub mod encoder;
 pub mod gf2;
+pub mod linalg;
 pub mod mackay_neal;
 pub mod peg;
+pub mod progress;
+pub mod protograph;
 pub mod rand;
+pub mod rate_compatible;
+pub mod report;
+#[cfg(feature = "simulation")]
 pub mod simulation;
 pub mod sparse;
 pub mod systematic;
 
-mod linalg;
 mod util;
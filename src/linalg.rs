@@ -1,11 +1,268 @@
+//! Linear algebra over GF(2).
+//!
+//! This module contains [`SparseSolver`], which solves linear systems over
+//! GF(2) whose matrix is a [`SparseMatrix`]. The solver uses sparse Gaussian
+//! elimination, choosing the pivots to keep the fill-in low, so it can handle
+//! the parity check matrices of long codes, for which a dense Gaussian
+//! elimination is too slow. This can be used for instance to recover erased
+//! bits of a codeword or to compute a coset leader for a syndrome.
+//!
+//! # Examples
+//! Recovering the erased bits of a codeword, by solving `H_E x_E = H_K x_K`,
+//! where `E` are the erased positions and `K` are the known positions.
+//! ```
+//! # use ldpc_toolbox::{gf2::GF2, linalg::SparseSolver, sparse::SparseMatrix};
+//! # use num_traits::{One, Zero};
+//! // Hamming (7, 4) code
+//! let mut h = SparseMatrix::new(3, 7);
+//! h.insert_row(0, [0, 1, 2, 4].iter());
+//! h.insert_row(1, [0, 1, 3, 5].iter());
+//! h.insert_row(2, [0, 2, 3, 6].iter());
+//! let (o, i) = (GF2::zero(), GF2::one());
+//! let codeword = [i, o, i, i, o, o, i];
+//! let erased = [1, 2, 5];
+//! let mut h_erased = SparseMatrix::new(3, erased.len());
+//! let mut syndrome = vec![o; 3];
+//! for (row, col) in h.iter_all() {
+//!     match erased.iter().position(|&e| e == col) {
+//!         Some(j) => h_erased.insert(row, j),
+//!         None => syndrome[row] += codeword[col],
+//!     }
+//! }
+//! let solver = SparseSolver::new(&h_erased);
+//! assert_eq!(solver.rank(), erased.len());
+//! assert_eq!(solver.solve(&syndrome)?, vec![o, i, o]);
+//! # Ok::<(), ldpc_toolbox::linalg::Error>(())
+//! ```
+
+use crate::{gf2::GF2, sparse::SparseMatrix};
 use ndarray::{s, Array2, LinalgScalar};
+use num_traits::Zero;
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashSet},
+};
+use thiserror::Error;
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+/// Linear algebra error.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Error)]
 pub enum Error {
+    /// The matrix is not invertible.
+    #[error("the matrix is not invertible")]
     NotInvertible,
+    /// The linear system does not have any solution.
+    #[error("the linear system does not have any solution")]
+    Inconsistent,
+}
+
+/// Sparse linear system solver over GF(2).
+///
+/// The solver computes a factorization of a matrix `A` by sparse Gaussian
+/// elimination, which can then be used to solve the systems `A x = b` for
+/// many different `b`. The pivots are chosen with the Markowitz criterion:
+/// the pivot column is the column with the fewest non-zero entries among the
+/// rows that have not been used as pivots yet, and the pivot row is the row
+/// with the fewest non-zero entries in that column. For parity check matrices
+/// of LDPC codes, this keeps the fill-in low.
+///
+/// The matrix does not need to be square or to have full rank. See
+/// [`SparseSolver::solve`] for the solutions that are returned in this case.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SparseSolver {
+    num_cols: usize,
+    // Rows of the reduced matrix. Only the pivot rows are kept.
+    rows: Vec<Vec<usize>>,
+    // (row, col) of each pivot, in elimination order
+    pivots: Vec<(usize, usize)>,
+    // Row operations performed during the elimination, as (target, source),
+    // meaning that row source is added to row target
+    operations: Vec<(usize, usize)>,
+}
+
+impl SparseSolver {
+    /// Computes the factorization of a matrix.
+    ///
+    /// The rows of `a` are the equations of the system, and its columns are
+    /// the unknowns.
+    pub fn new(a: &SparseMatrix) -> SparseSolver {
+        let num_rows = a.num_rows();
+        let num_cols = a.num_cols();
+        let mut rows = (0..num_rows)
+            .map(|row| {
+                let mut cols = a.iter_row(row).copied().collect::<Vec<_>>();
+                cols.sort_unstable();
+                cols
+            })
+            .collect::<Vec<_>>();
+        // Rows that have not been used as pivots and contain each column
+        let mut col_rows = vec![HashSet::new(); num_cols];
+        for (row, col) in a.iter_all() {
+            col_rows[col].insert(row);
+        }
+        let mut col_done = vec![false; num_cols];
+        // Columns by number of entries. Entries whose count is outdated are
+        // skipped when they are popped.
+        let mut heap = col_rows
+            .iter()
+            .enumerate()
+            .map(|(col, r)| Reverse((r.len(), col)))
+            .collect::<BinaryHeap<_>>();
+        let mut pivots = Vec::new();
+        let mut operations = Vec::new();
+        while let Some(Reverse((count, col))) = heap.pop() {
+            if col_done[col] || count == 0 || col_rows[col].len() != count {
+                continue;
+            }
+            col_done[col] = true;
+            let pivot_row = *col_rows[col]
+                .iter()
+                .min_by_key(|&&row| (rows[row].len(), row))
+                .unwrap();
+            let pivot = std::mem::take(&mut rows[pivot_row]);
+            for &c in &pivot {
+                col_rows[c].remove(&pivot_row);
+            }
+            let mut targets = col_rows[col].iter().copied().collect::<Vec<_>>();
+            targets.sort_unstable();
+            for target in targets {
+                operations.push((target, pivot_row));
+                for &c in &pivot {
+                    if !col_rows[c].remove(&target) {
+                        col_rows[c].insert(target);
+                    }
+                }
+                rows[target] = xor_sorted(&rows[target], &pivot);
+            }
+            for &c in &pivot {
+                if !col_done[c] {
+                    heap.push(Reverse((col_rows[c].len(), c)));
+                }
+            }
+            rows[pivot_row] = pivot;
+            pivots.push((pivot_row, col));
+        }
+        // The rows that have not been used as pivots are zero now
+        debug_assert!(rows
+            .iter()
+            .enumerate()
+            .all(|(row, cols)| cols.is_empty() || pivots.iter().any(|&(r, _)| r == row)));
+        SparseSolver {
+            num_cols,
+            rows,
+            pivots,
+            operations,
+        }
+    }
+
+    /// Returns the number of rows of the matrix.
+    pub fn num_rows(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Returns the number of columns of the matrix.
+    pub fn num_cols(&self) -> usize {
+        self.num_cols
+    }
+
+    /// Returns the rank of the matrix.
+    pub fn rank(&self) -> usize {
+        self.pivots.len()
+    }
+
+    /// Returns the free columns of the matrix.
+    ///
+    /// These are the columns that have not been used as pivots. Their
+    /// number is the dimension of the null space of the matrix. The list is
+    /// sorted in increasing order.
+    pub fn free_cols(&self) -> Vec<usize> {
+        let mut pivot = vec![false; self.num_cols];
+        for &(_, col) in &self.pivots {
+            pivot[col] = true;
+        }
+        (0..self.num_cols).filter(|&col| !pivot[col]).collect()
+    }
+
+    /// Returns the number of non-zero entries of the reduced matrix.
+    ///
+    /// This measures the fill-in produced by the elimination, and determines
+    /// the cost of [`SparseSolver::solve`].
+    pub fn num_entries(&self) -> usize {
+        self.rows.iter().map(|row| row.len()).sum()
+    }
+
+    /// Solves the linear system `A x = b`.
+    ///
+    /// Returns a solution `x`, in which the unknowns corresponding to the
+    /// [free columns](SparseSolver::free_cols) are set to zero. If the matrix
+    /// has full column rank, the solution is unique.
+    ///
+    /// # Errors
+    /// Returns [`Error::Inconsistent`] if the system does not have any
+    /// solution.
+    ///
+    /// # Panics
+    /// Panics if the length of `b` is not equal to the number of rows of the
+    /// matrix.
+    pub fn solve(&self, b: &[GF2]) -> Result<Vec<GF2>, Error> {
+        assert_eq!(b.len(), self.num_rows());
+        let mut b = b.to_vec();
+        for &(target, source) in &self.operations {
+            let x = b[source];
+            b[target] += x;
+        }
+        let mut pivot_row = vec![false; self.num_rows()];
+        for &(row, _) in &self.pivots {
+            pivot_row[row] = true;
+        }
+        if b.iter()
+            .zip(pivot_row.iter())
+            .any(|(x, &pivot)| !pivot && !x.is_zero())
+        {
+            return Err(Error::Inconsistent);
+        }
+        // Back substitution. Each pivot row only contains its pivot column and
+        // columns that were pivoted later or are free.
+        let mut x = vec![GF2::zero(); self.num_cols];
+        for &(row, col) in self.pivots.iter().rev() {
+            x[col] = b[row]
+                + self.rows[row]
+                    .iter()
+                    .filter(|&&c| c != col)
+                    .map(|&c| x[c])
+                    .sum::<GF2>();
+        }
+        Ok(x)
+    }
+}
+
+// Symmetric difference of two sorted lists
+fn xor_sorted(a: &[usize], b: &[usize]) -> Vec<usize> {
+    let mut out = Vec::with_capacity(a.len() + b.len());
+    let (mut j, mut k) = (0, 0);
+    while j < a.len() && k < b.len() {
+        match a[j].cmp(&b[k]) {
+            std::cmp::Ordering::Less => {
+                out.push(a[j]);
+                j += 1;
+            }
+            std::cmp::Ordering::Greater => {
+                out.push(b[k]);
+                k += 1;
+            }
+            std::cmp::Ordering::Equal => {
+                j += 1;
+                k += 1;
+            }
+        }
+    }
+    out.extend_from_slice(&a[j..]);
+    out.extend_from_slice(&b[k..]);
+    out
 }
 
-pub fn gauss_reduction<A: LinalgScalar + PartialEq>(array: &mut Array2<A>) -> Result<(), Error> {
+pub(crate) fn gauss_reduction<A: LinalgScalar + PartialEq>(
+    array: &mut Array2<A>,
+) -> Result<(), Error> {
     let (n, m) = array.dim();
     assert!(n <= m);
 
@@ -65,7 +322,7 @@ pub fn gauss_reduction<A: LinalgScalar + PartialEq>(array: &mut Array2<A>) -> Re
     Ok(())
 }
 
-pub fn row_echelon_form<A: LinalgScalar + PartialEq>(array: &mut Array2<A>) {
+pub(crate) fn row_echelon_form<A: LinalgScalar + PartialEq>(array: &mut Array2<A>) {
     let (n, m) = array.dim();
 
     let mut j = 0;
@@ -112,9 +369,8 @@ pub fn row_echelon_form<A: LinalgScalar + PartialEq>(array: &mut Array2<A>) {
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::gf2::GF2;
     use ndarray::arr2;
-    use num_traits::{One, Zero};
+    use num_traits::One;
 
     #[test]
     fn gauss() {
@@ -151,4 +407,53 @@ mod test {
         ]);
         assert_eq!(&a, &expected);
     }
+
+    #[test]
+    fn sparse_solver() {
+        let i = GF2::one();
+        let o = GF2::zero();
+        // Rank 2 matrix (the last row is the sum of the first two)
+        let mut a = SparseMatrix::new(3, 4);
+        a.insert_row(0, [0, 1, 3].iter());
+        a.insert_row(1, [1, 2].iter());
+        a.insert_row(2, [0, 2, 3].iter());
+        let solver = SparseSolver::new(&a);
+        assert_eq!(solver.rank(), 2);
+        assert_eq!(solver.free_cols().len(), 2);
+        let b = [i, o, i];
+        let x = solver.solve(&b).unwrap();
+        for (row, &y) in b.iter().enumerate() {
+            assert_eq!(a.iter_row(row).map(|&c| x[c]).sum::<GF2>(), y);
+        }
+        for &col in &solver.free_cols() {
+            assert_eq!(x[col], o);
+        }
+        assert_eq!(solver.solve(&[i, o, o]), Err(Error::Inconsistent));
+    }
+
+    #[test]
+    fn sparse_solver_dvbs2() {
+        use crate::codes::dvbs2::Code;
+        // The parity part of the DVB-S2 codes is a staircase, which gives no
+        // fill-in when the pivots are chosen well
+        let h = Code::from_rate("3/4", true).unwrap().h();
+        let n = h.num_rows();
+        let k = h.num_cols() - n;
+        let mut h1 = SparseMatrix::new(n, n);
+        for (row, col) in h.iter_all() {
+            if col >= k {
+                h1.insert(row, col - k);
+            }
+        }
+        let solver = SparseSolver::new(&h1);
+        assert_eq!(solver.rank(), n);
+        assert!(solver.num_entries() <= h1.iter_all().count());
+        let b = (0..n)
+            .map(|j| if j % 5 < 2 { GF2::one() } else { GF2::zero() })
+            .collect::<Vec<_>>();
+        let x = solver.solve(&b).unwrap();
+        for (row, &y) in b.iter().enumerate() {
+            assert_eq!(h1.iter_row(row).map(|&c| x[c]).sum::<GF2>(), y);
+        }
+    }
 }
@@ -37,13 +37,21 @@
 //!  print!("{}", h.alist());
 //!  ```
 
+use crate::cancel::CancellationToken;
+use crate::progress::{Progress, ProgressSender, Reporter};
 use crate::rand::{Rng, *};
-use crate::sparse::{Node, SparseMatrix};
+use crate::sparse::{Col, Node, Row, SparseMatrix};
 use crate::util::*;
 use rand::seq::IteratorRandom;
+#[cfg(feature = "rayon")]
 use rayon::prelude::*;
 use std::fmt;
 use std::fmt::{Display, Formatter};
+#[cfg(feature = "rayon")]
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Mutex,
+};
 
 /// Runtime errors of the MacKay-Neal construction.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -56,6 +64,8 @@ pub enum Error {
     NoMoreBacktrack,
     /// Exceeded girth trials.
     NoMoreTrials,
+    /// The construction was cancelled.
+    Cancelled,
 }
 
 impl Display for Error {
@@ -65,6 +75,7 @@ impl Display for Error {
             Error::GirthTooSmall => write!(f, "girth is too small"),
             Error::NoMoreBacktrack => write!(f, "exceeded backtrack trials"),
             Error::NoMoreTrials => write!(f, "exceeded girth trials"),
+            Error::Cancelled => write!(f, "cancelled"),
         }
     }
 }
@@ -80,6 +91,7 @@ pub type Result<T> = std::result::Result<T, Error>;
 /// LDPC code to construct as well as some options that affect
 /// the exectution of the algorithm.
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Config {
     /// Number of rows of the parity check matrix.
     pub nrows: usize,
@@ -108,7 +120,27 @@ pub struct Config {
 impl Config {
     /// Runs the MacKay-Neal algorith using a random seed `seed`.
     pub fn run(&self, seed: u64) -> Result<SparseMatrix> {
-        MacKayNeal::new(self, seed).run()
+        MacKayNeal::new(self, seed, None, None).run()
+    }
+
+    /// Runs the MacKay-Neal algorithm with a cancellation token.
+    ///
+    /// This is like [`Config::run`], but the construction stops and returns
+    /// [`Error::Cancelled`] when the token is cancelled.
+    pub fn run_cancellable(&self, seed: u64, token: &CancellationToken) -> Result<SparseMatrix> {
+        MacKayNeal::new(self, seed, Some(token.clone()), None).run()
+    }
+
+    /// Runs the MacKay-Neal algorithm with progress reporting.
+    ///
+    /// This is like [`Config::run`], but sends [`Progress`] reports with the
+    /// number of columns placed to `reporter`.
+    pub fn run_with_progress(
+        &self,
+        seed: u64,
+        reporter: &Reporter<Progress>,
+    ) -> Result<SparseMatrix> {
+        MacKayNeal::new(self, seed, None, Some(ProgressSender::new(reporter))).run()
     }
 
     /// Searches for a seed for a successful MacKay-Neal construction
@@ -118,12 +150,67 @@ impl Config {
     /// from the rayon crate. This function returns the successful seed
     /// and the corresponding parity check matrix, if one is found, or
     /// `None` otherwise.
+    ///
+    /// This function is only available when the `rayon` feature is enabled.
+    #[cfg(feature = "rayon")]
     pub fn search(&self, start_seed: u64, max_tries: u64) -> Option<(u64, SparseMatrix)> {
         (start_seed..start_seed + max_tries)
             .into_par_iter()
             .filter_map(|s| self.run(s).ok().map(|x| (s, x)))
             .find_any(|_| true)
     }
+
+    /// Searches for a seed for a successful MacKay-Neal construction
+    /// with a cancellation token.
+    ///
+    /// This is like [`Config::search`], but the search stops and returns
+    /// `None` when the token is cancelled.
+    ///
+    /// This function is only available when the `rayon` feature is enabled.
+    #[cfg(feature = "rayon")]
+    pub fn search_cancellable(
+        &self,
+        start_seed: u64,
+        max_tries: u64,
+        token: &CancellationToken,
+    ) -> Option<(u64, SparseMatrix)> {
+        (start_seed..start_seed + max_tries)
+            .into_par_iter()
+            .filter_map(|s| self.run_cancellable(s, token).ok().map(|x| (s, x)))
+            .find_any(|_| true)
+    }
+
+    /// Searches for a seed for a successful MacKay-Neal construction
+    /// with progress reporting.
+    ///
+    /// This is like [`Config::search`], but sends [`Progress`] reports with
+    /// the number of seeds tried to `reporter`.
+    ///
+    /// This function is only available when the `rayon` feature is enabled.
+    #[cfg(feature = "rayon")]
+    pub fn search_with_progress(
+        &self,
+        start_seed: u64,
+        max_tries: u64,
+        reporter: &Reporter<Progress>,
+    ) -> Option<(u64, SparseMatrix)> {
+        let tried = AtomicU64::new(0);
+        let progress = Mutex::new(ProgressSender::new(reporter));
+        let ret = (start_seed..start_seed + max_tries)
+            .into_par_iter()
+            .filter_map(|s| {
+                let ret = self.run(s).ok().map(|x| (s, x));
+                let done = tried.fetch_add(1, Ordering::Relaxed) + 1;
+                // Only one thread needs to send the report
+                if let Ok(mut progress) = progress.try_lock() {
+                    progress.update(done, max_tries, None);
+                }
+                ret
+            })
+            .find_any(|_| true);
+        progress.lock().unwrap().finish();
+        ret
+    }
 }
 
 /// Policy used to select the rows to fill when adding a new column
@@ -145,6 +232,8 @@ impl Config {
 /// it is not possible to exchange one of the choosen rows by
 /// another row that was not chosen and has stricly less weight.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
 pub enum FillPolicy {
     /// Choose randomly from the set of rows whose weight is less
     /// than the maximum row weight.
@@ -164,10 +253,17 @@ struct MacKayNeal {
     girth_trials: usize,
     fill_policy: FillPolicy,
     current_col: usize,
+    cancellation: Option<CancellationToken>,
+    progress: Option<ProgressSender>,
 }
 
 impl MacKayNeal {
-    fn new(conf: &Config, seed: u64) -> MacKayNeal {
+    fn new(
+        conf: &Config,
+        seed: u64,
+        cancellation: Option<CancellationToken>,
+        progress: Option<ProgressSender>,
+    ) -> MacKayNeal {
         MacKayNeal {
             wr: conf.wr,
             wc: conf.wc,
@@ -179,19 +275,21 @@ impl MacKayNeal {
             girth_trials: conf.girth_trials,
             fill_policy: conf.fill_policy,
             current_col: 0,
+            cancellation,
+            progress,
         }
     }
 
     fn try_insert_column(&mut self) -> Result<()> {
         let rows = self.select_rows()?;
-        self.h.insert_col(self.current_col, rows.into_iter());
+        self.h.insert_col(Col(self.current_col), rows.into_iter());
         if let Some(g) = self.min_girth {
             if self
                 .h
                 .girth_at_node_with_max(Node::Col(self.current_col), g - 1)
                 .is_some()
             {
-                self.h.clear_col(self.current_col);
+                self.h.clear_col(Col(self.current_col));
                 return Err(Error::GirthTooSmall);
             }
         }
@@ -203,7 +301,7 @@ impl MacKayNeal {
             FillPolicy::Random => {
                 let h = &self.h;
                 let wr = self.wr;
-                let avail_rows = (0..self.h.num_rows()).filter(|&r| h.row_weight(r) < wr);
+                let avail_rows = (0..self.h.num_rows()).filter(|&r| h.row_weight(Row(r)) < wr);
                 let select_rows = avail_rows.choose_multiple(&mut self.rng, self.wc);
                 if select_rows.len() < self.wc {
                     return Err(Error::NoAvailRows);
@@ -213,7 +311,7 @@ impl MacKayNeal {
             FillPolicy::Uniform => {
                 let avail_rows: Vec<(usize, usize)> = (0..self.h.num_rows())
                     .filter_map(|r| {
-                        let w = self.h.row_weight(r);
+                        let w = self.h.row_weight(Row(r));
                         if w < self.wr {
                             Some((r, w))
                         } else {
@@ -237,7 +335,7 @@ impl MacKayNeal {
         let b = std::cmp::min(self.current_col, self.backtrack_cols);
         let a = self.current_col - b;
         for col in a..self.current_col {
-            self.h.clear_col(col);
+            self.h.clear_col(Col(col));
         }
         self.current_col = a;
         Ok(())
@@ -252,7 +350,22 @@ impl MacKayNeal {
     }
 
     fn run(mut self) -> Result<SparseMatrix> {
-        while self.current_col < self.h.num_cols() {
+        let ret = self.place_columns();
+        if let Some(progress) = &self.progress {
+            progress.finish();
+        }
+        ret.map(|_| self.h)
+    }
+
+    fn place_columns(&mut self) -> Result<()> {
+        let num_cols = self.h.num_cols();
+        while self.current_col < num_cols {
+            if self.cancellation.as_ref().is_some_and(|t| t.is_cancelled()) {
+                return Err(Error::Cancelled);
+            }
+            if let Some(progress) = &mut self.progress {
+                progress.update(self.current_col as u64, num_cols as u64, None);
+            }
             match self.try_insert_column() {
                 Ok(_) => self.current_col += 1,
                 Err(Error::NoAvailRows) => self.backtrack()?,
@@ -260,7 +373,7 @@ impl MacKayNeal {
                 Err(e) => return Err(e),
             };
         }
-        Ok(self.h)
+        Ok(())
     }
 }
 
@@ -22,11 +22,36 @@
 //!
 //! This procedure tries to maximize local girth greedily and to fill the
 //! check nodes uniformly.
+//!
+//! Optionally, the number of shortest cycles closed by the new edge can be used
+//! as a secondary criterion: among the candidate nodes, only those that close
+//! the fewest cycles of minimum length are kept (see
+//! [`Config::fewest_cycles`]).
+//!
+//! The construction can also be constrained by the approximate cycle extrinsic
+//! message degree (ACE) of the cycles, as proposed in *T. Tian, C. R. Jones, J.
+//! D. Villasenor and R. D. Wesel, "Selective avoidance of cycles in irregular
+//! LDPC code construction," in IEEE Transactions on Communications, vol. 52,
+//! no. 8, pp. 1242-1247, Aug. 2004* and *H. Xiao and A. H. Banihashemi,
+//! "Improved progressive-edge-growth (PEG) construction of irregular LDPC
+//! codes," in IEEE Communications Letters, vol. 8, no. 12, pp. 715-717, Dec.
+//! 2004.* The check nodes for which the new edge would close a cycle of length
+//! at most `2 * ace_depth` with an ACE smaller than `ace_min` are rejected
+//! before applying the PEG criteria (see [`Config::ace_depth`] and
+//! [`SparseMatrix::ace_spectrum`]).
+//!
+//! How the remaining ties are broken is controlled with [`TieBreaking`]. By
+//! default a node is picked at random, using the random selection functions of
+//! the `rand` crate, whose output for a given seed is not guaranteed to be
+//! stable across versions of `rand`. The [`TieBreaking::LowestIndex`] rule picks
+//! the node with the lowest index instead, and it makes the construction fully
+//! deterministic and independent of the RNG.
 
+use crate::cancel::CancellationToken;
+use crate::progress::{Progress, ProgressSender, Reporter};
 use crate::rand::{Rng, *};
-use crate::sparse::{Node, SparseMatrix};
+use crate::sparse::{BFSResults, Col, Node, Row, SparseMatrix};
 use crate::util::{compare_some, *};
-use std::cmp::Ordering;
 use std::fmt;
 use std::fmt::{Display, Formatter};
 
@@ -35,12 +60,18 @@ use std::fmt::{Display, Formatter};
 pub enum Error {
     /// Not enought rows available.
     NoAvailRows,
+    /// The construction was cancelled.
+    Cancelled,
+    /// All the rows violate the ACE constraint.
+    AceConstraint,
 }
 
 impl Display for Error {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
             Error::NoAvailRows => write!(f, "not enough rows available"),
+            Error::Cancelled => write!(f, "cancelled"),
+            Error::AceConstraint => write!(f, "the ACE constraint cannot be satisfied"),
         }
     }
 }
@@ -55,6 +86,7 @@ pub type Result<T> = std::result::Result<T, Error>;
 /// This configuration is used to set the parameters of the
 /// LDPC code to construct.
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Config {
     /// Number of rows of the parity check matrix.
     pub nrows: usize,
@@ -62,57 +94,382 @@ pub struct Config {
     pub ncols: usize,
     /// Column weight of the parity check matrix.
     pub wc: usize,
+    /// Rule used to break the ties between candidate check nodes.
+    pub tie_breaking: TieBreaking,
+    /// Use the number of shortest cycles closed by each edge as a secondary
+    /// criterion.
+    ///
+    /// If this is `true`, among the candidate check nodes only those for which
+    /// the new edge closes the fewest cycles of minimum length are considered.
+    pub fewest_cycles: bool,
+    /// Depth of the ACE constraint.
+    ///
+    /// The ACE constraint applies to the cycles of length at most `2 *
+    /// ace_depth`. A value of zero disables the ACE constraint.
+    pub ace_depth: usize,
+    /// Minimum ACE of the cycles covered by the ACE constraint.
+    ///
+    /// The edges that would close a cycle of length at most `2 * ace_depth`
+    /// whose ACE is smaller than `ace_min` are rejected. The ACE of each
+    /// column is computed with its final weight `wc`.
+    pub ace_min: usize,
+}
+
+/// Tie-breaking rule for the PEG construction.
+///
+/// This selects how to choose among several candidate check nodes that are
+/// equally good according to the PEG criteria.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum TieBreaking {
+    /// Select one of the candidates at random.
+    #[default]
+    Random,
+    /// Select the candidate with the lowest index.
+    ///
+    /// This rule does not use the RNG, so the construction is deterministic.
+    LowestIndex,
+}
+
+impl std::str::FromStr for TieBreaking {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<TieBreaking, String> {
+        Ok(match s {
+            "random" => TieBreaking::Random,
+            "lowest-index" => TieBreaking::LowestIndex,
+            _ => Err(format!("invalid tie-breaking rule {s}"))?,
+        })
+    }
+}
+
+impl Display for TieBreaking {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                TieBreaking::Random => "random",
+                TieBreaking::LowestIndex => "lowest-index",
+            }
+        )
+    }
 }
 
 impl Config {
     /// Runs the Progressive Edge Growth algorith using a random seed `seed`.
     pub fn run(&self, seed: u64) -> Result<SparseMatrix> {
-        Peg::new(self, seed).run()
+        Peg::new(self, seed, None, None).run()
+    }
+
+    /// Runs the Progressive Edge Growth algorithm with a cancellation token.
+    ///
+    /// This is like [`Config::run`], but the construction stops and returns
+    /// [`Error::Cancelled`] when the token is cancelled.
+    pub fn run_cancellable(&self, seed: u64, token: &CancellationToken) -> Result<SparseMatrix> {
+        Peg::new(self, seed, Some(token.clone()), None).run()
+    }
+
+    /// Runs the Progressive Edge Growth algorithm with progress reporting.
+    ///
+    /// This is like [`Config::run`], but sends [`Progress`] reports with the
+    /// number of columns placed to `reporter`.
+    pub fn run_with_progress(
+        &self,
+        seed: u64,
+        reporter: &Reporter<Progress>,
+    ) -> Result<SparseMatrix> {
+        Peg::new(self, seed, None, Some(ProgressSender::new(reporter))).run()
     }
 }
 
 struct Peg {
     wc: usize,
+    tie_breaking: TieBreaking,
+    fewest_cycles: bool,
+    ace_depth: usize,
+    ace_min: usize,
     h: SparseMatrix,
     rng: Rng,
+    cancellation: Option<CancellationToken>,
+    progress: Option<ProgressSender>,
 }
 
 impl Peg {
-    fn new(conf: &Config, seed: u64) -> Peg {
+    fn new(
+        conf: &Config,
+        seed: u64,
+        cancellation: Option<CancellationToken>,
+        progress: Option<ProgressSender>,
+    ) -> Peg {
         Peg {
             wc: conf.wc,
+            tie_breaking: conf.tie_breaking,
+            fewest_cycles: conf.fewest_cycles,
+            ace_depth: conf.ace_depth,
+            ace_min: conf.ace_min,
             h: SparseMatrix::new(conf.nrows, conf.ncols),
             rng: Rng::seed_from_u64(seed),
+            cancellation,
+            progress,
         }
     }
 
     fn insert_edge(&mut self, col: usize) -> Result<()> {
-        let row_dist = self.h.bfs(Node::Col(col)).row_nodes_distance;
-        let row_num_dist_and_weight: Vec<_> = row_dist
+        let bfs = self.h.bfs(Node::Col(col));
+        let cycles = if self.fewest_cycles {
+            shortest_path_counts(&self.h, &bfs)
+        } else {
+            vec![0; self.h.num_rows()]
+        };
+        let mut row_num_dist_and_weight: Vec<_> = bfs
+            .row_nodes_distance
             .into_iter()
+            .zip(cycles)
             .enumerate()
-            .map(|(j, d)| (j, d, self.h.row_weight(j)))
+            .map(|(j, (d, c))| (j, d, self.h.row_weight(Row(j)), c))
             .collect();
-        let selected_row = row_num_dist_and_weight
-            .sort_by_random_min(
-                |(_, x, w), (_, y, v)| match compare_some(x, y).reverse() {
-                    Ordering::Equal => w.cmp(v),
-                    c => c,
-                },
-                &mut self.rng,
-            )
-            .ok_or(Error::NoAvailRows)?
-            .0;
-        self.h.insert(selected_row, col);
+        if self.ace_depth > 0 && !row_num_dist_and_weight.is_empty() {
+            let aces = min_path_aces(&self.h, col, self.ace_depth, self.wc.saturating_sub(2));
+            row_num_dist_and_weight.retain(|&(j, ..)| {
+                // Rows already connected to the column cannot be selected
+                !self.h.contains(Row(j), Col(col)) && aces[j].is_none_or(|ace| ace >= self.ace_min)
+            });
+            if row_num_dist_and_weight.is_empty() {
+                return Err(Error::AceConstraint);
+            }
+        }
+        let compare = |(_, x, w, c): &(usize, Option<usize>, usize, u64),
+                       (_, y, v, e): &(usize, Option<usize>, usize, u64)| {
+            compare_some(x, y)
+                .reverse()
+                .then_with(|| w.cmp(v))
+                .then_with(|| c.cmp(e))
+        };
+        let selected = match self.tie_breaking {
+            TieBreaking::Random => {
+                row_num_dist_and_weight.sort_by_random_min(compare, &mut self.rng)
+            }
+            // min_by returns the first of the minimum elements, which is the
+            // one with the lowest row index
+            TieBreaking::LowestIndex => row_num_dist_and_weight.into_iter().min_by(compare),
+        };
+        let selected_row = selected.ok_or(Error::NoAvailRows)?.0;
+        self.h.insert(Row(selected_row), Col(col));
         Ok(())
     }
 
     fn run(mut self) -> Result<SparseMatrix> {
-        for col in 0..self.h.num_cols() {
+        let ret = self.place_columns();
+        if let Some(progress) = &self.progress {
+            progress.finish();
+        }
+        ret.map(|_| self.h)
+    }
+
+    fn place_columns(&mut self) -> Result<()> {
+        let num_cols = self.h.num_cols();
+        for col in 0..num_cols {
+            if self.cancellation.as_ref().is_some_and(|t| t.is_cancelled()) {
+                return Err(Error::Cancelled);
+            }
+            if let Some(progress) = &mut self.progress {
+                progress.update(col as u64, num_cols as u64, None);
+            }
             for _ in 0..self.wc {
                 self.insert_edge(col)?;
             }
         }
-        Ok(self.h)
+        Ok(())
+    }
+}
+
+// Counts the number of shortest paths from the root of a BFS to each of the
+// row nodes. This is the number of cycles of minimum length that are closed by
+// adding an edge between the root column and each row. Rows that are not
+// reachable from the root have no paths.
+fn shortest_path_counts(h: &SparseMatrix, bfs: &BFSResults) -> Vec<u64> {
+    let mut nodes = bfs
+        .row_nodes_distance
+        .iter()
+        .enumerate()
+        .filter_map(|(j, d)| d.map(|d| (d, Node::Row(j))))
+        .chain(
+            bfs.col_nodes_distance
+                .iter()
+                .enumerate()
+                .filter_map(|(j, d)| d.map(|d| (d, Node::Col(j)))),
+        )
+        .collect::<Vec<_>>();
+    nodes.sort_unstable_by_key(|&(d, _)| d);
+    let mut row_paths = vec![0u64; h.num_rows()];
+    let mut col_paths = vec![0u64; h.num_cols()];
+    for (d, node) in nodes {
+        // The number of paths to a node is the sum of the number of paths to
+        // its neighbours that are one step closer to the root column
+        match node {
+            Node::Col(j) if d == 0 => col_paths[j] = 1,
+            Node::Row(j) => {
+                row_paths[j] = h
+                    .iter_row(Row(j))
+                    .filter(|&&k| bfs.col_nodes_distance[k] == Some(d - 1))
+                    .fold(0u64, |acc, &k| acc.saturating_add(col_paths[k]))
+            }
+            Node::Col(j) => {
+                col_paths[j] = h
+                    .iter_col(Col(j))
+                    .filter(|&&k| bfs.row_nodes_distance[k] == Some(d - 1))
+                    .fold(0u64, |acc, &k| acc.saturating_add(row_paths[k]))
+            }
+        }
+    }
+    row_paths
+}
+
+// Computes the minimum ACE of the paths of length at most `2 * depth - 1`
+// from the root column to each of the row nodes, counting the ACE of the
+// root. This is the minimum ACE of the cycles of length at most `2 * depth`
+// that are closed by adding an edge between the root and each row. Rows that
+// cannot be reached with such paths have no ACE.
+//
+// The minimum is computed over walks rather than over paths, but since the
+// ACE of the columns is non-negative, removing the loops of a walk gives a
+// path with smaller or equal ACE.
+fn min_path_aces(
+    h: &SparseMatrix,
+    root: usize,
+    depth: usize,
+    col_ace: usize,
+) -> Vec<Option<usize>> {
+    let mut col_aces = vec![None; h.num_cols()];
+    col_aces[root] = Some(col_ace);
+    let mut row_aces = vec![None; h.num_rows()];
+    let update = |entry: &mut Option<usize>, ace: usize| {
+        *entry = Some(entry.map_or(ace, |x: usize| x.min(ace)));
+    };
+    for step in 0..depth {
+        for (col, ace) in col_aces.iter().enumerate() {
+            if let Some(ace) = *ace {
+                for &row in h.iter_col(col) {
+                    update(&mut row_aces[row], ace);
+                }
+            }
+        }
+        if step + 1 == depth {
+            break;
+        }
+        for (row, ace) in row_aces.iter().enumerate() {
+            if let Some(ace) = *ace {
+                for &col in h.iter_row(row) {
+                    update(&mut col_aces[col], ace + col_ace);
+                }
+            }
+        }
+    }
+    row_aces
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn config(tie_breaking: TieBreaking, fewest_cycles: bool) -> Config {
+        Config {
+            nrows: 50,
+            ncols: 100,
+            wc: 3,
+            tie_breaking,
+            fewest_cycles,
+            ace_depth: 0,
+            ace_min: 0,
+        }
+    }
+
+    #[test]
+    fn lowest_index_is_deterministic() {
+        for fewest_cycles in [false, true] {
+            let conf = config(TieBreaking::LowestIndex, fewest_cycles);
+            let h = conf.run(0).unwrap();
+            assert_eq!(h, conf.run(1).unwrap());
+            for col in 0..h.num_cols() {
+                assert_eq!(h.col_weight(col), 3);
+            }
+        }
+    }
+
+    #[test]
+    fn cancelled() {
+        let conf = config(TieBreaking::Random, false);
+        let token = CancellationToken::new();
+        assert_eq!(conf.run_cancellable(0, &token), conf.run(0));
+        token.cancel();
+        assert_eq!(conf.run_cancellable(0, &token), Err(Error::Cancelled));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde() {
+        let conf = config(TieBreaking::LowestIndex, true);
+        let json = serde_json::to_string(&conf).unwrap();
+        assert!(json.contains(r#""tie_breaking":"lowest-index""#));
+        assert_eq!(serde_json::from_str::<Config>(&json).unwrap(), conf);
+        // The generated matrix can be stored together with its configuration
+        let h = conf.run(0).unwrap();
+        let h2: SparseMatrix = serde_json::from_str(&serde_json::to_string(&h).unwrap()).unwrap();
+        assert_eq!(h2.fingerprint(), h.fingerprint());
+    }
+
+    #[test]
+    fn random_depends_on_seed() {
+        for fewest_cycles in [false, true] {
+            let conf = config(TieBreaking::Random, fewest_cycles);
+            assert_eq!(conf.run(0).unwrap(), conf.run(0).unwrap());
+            assert_ne!(conf.run(0).unwrap(), conf.run(1).unwrap());
+        }
+    }
+
+    #[test]
+    fn ace_constraint() {
+        // With column weight 3, the ACE of a cycle of length 2 * l is l, so
+        // requiring an ACE of 3 in the cycles of length up to 6 avoids the
+        // 4-cycles
+        let mut conf = config(TieBreaking::Random, false);
+        conf.ncols = 60;
+        conf.nrows = 30;
+        conf.ace_depth = 3;
+        conf.ace_min = 3;
+        let h = conf.run(0).unwrap();
+        assert_eq!(h.girth(), Some(6));
+        assert!(h.ace_spectrum(6).iter().all(|&(_, ace)| ace >= 3));
+        // An ACE of 4 in the cycles of length up to 6 would require a girth of
+        // 8, which is impossible with these parameters
+        conf.ace_min = 4;
+        assert_eq!(conf.run(0), Err(Error::AceConstraint));
+    }
+
+    #[test]
+    fn path_aces() {
+        // Columns 0 and 1 are connected through rows 0 and 1, and column 1 is
+        // connected to row 2
+        let mut h = SparseMatrix::new(4, 2);
+        h.insert_col(0, [0, 1].iter());
+        h.insert_col(1, [0, 1, 2].iter());
+        assert_eq!(min_path_aces(&h, 0, 1, 1), [Some(1), Some(1), None, None]);
+        assert_eq!(
+            min_path_aces(&h, 0, 2, 1),
+            [Some(1), Some(1), Some(2), None]
+        );
+    }
+
+    #[test]
+    fn path_counts() {
+        // Columns 0 and 1 are connected through rows 0 and 1, so there are two
+        // shortest paths between column 0 and row 2
+        let mut h = SparseMatrix::new(3, 2);
+        h.insert_col(0, [0, 1].iter());
+        h.insert_col(1, [0, 1, 2].iter());
+        let bfs = h.bfs(Node::Col(0));
+        assert_eq!(shortest_path_counts(&h, &bfs), [1, 1, 2]);
     }
 }
@@ -0,0 +1,118 @@
+//! # Progress reporting
+//!
+//! Long-running operations can optionally send periodic progress reports
+//! through a [`Reporter`], so that a UI can show their progress. The BER test
+//! sends reports with its statistics (see the `simulation` module). Code
+//! constructions and analyses send [`Progress`] reports with the number
+//! of steps done. The operations that support progress reporting are the
+//! following:
+//!
+//! - [`peg::Config::run_with_progress`](crate::peg::Config::run_with_progress).
+//!   Each column placed is a step.
+//! - [`mackay_neal::Config::run_with_progress`](crate::mackay_neal::Config::run_with_progress).
+//!   Each column placed is a step. The number of steps done decreases when the
+//!   construction backtracks.
+//! - [`mackay_neal::Config::search_with_progress`](crate::mackay_neal::Config::search_with_progress).
+//!   Each seed tried is a step.
+//! - [`SparseMatrix::girth_with_progress`](crate::sparse::SparseMatrix::girth_with_progress).
+//!   Each column analyzed is a step, and the best metric is the smallest
+//!   local girth found so far.
+//!
+//! # Examples
+//! ```
+//! # use ldpc_toolbox::{
+//! #     peg::Config,
+//! #     progress::{Progress, Reporter},
+//! # };
+//! # use std::{sync::mpsc, time::Duration};
+//! let conf = Config {
+//!     nrows: 50,
+//!     ncols: 100,
+//!     wc: 3,
+//!     tie_breaking: Default::default(),
+//!     fewest_cycles: false,
+//!     ace_depth: 0,
+//!     ace_min: 0,
+//! };
+//! let (tx, rx) = mpsc::channel();
+//! let reporter = Reporter {
+//!     tx,
+//!     interval: Duration::ZERO,
+//! };
+//! conf.run_with_progress(0, &reporter).unwrap();
+//! let reports = rx.try_iter().collect::<Vec<_>>();
+//! assert_eq!(reports.last(), Some(&Progress::Finished));
+//! ```
+
+use std::{
+    sync::mpsc::Sender,
+    time::{Duration, Instant},
+};
+
+/// Progress reporter.
+///
+/// A reporter can optionally be supplied to long-running operations in order
+/// to receive periodic messages reporting their progress. The type `T` is the
+/// type of the reports.
+#[derive(Debug, Clone)]
+pub struct Reporter<T> {
+    /// Sender element of a channel used to send the reports.
+    pub tx: Sender<T>,
+    /// Reporting interval.
+    pub interval: Duration,
+}
+
+/// Progress report of a code construction or analysis.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Progress {
+    /// Progress of the operation.
+    ///
+    /// This is sent periodically while the operation runs.
+    Update {
+        /// Number of steps done.
+        done: u64,
+        /// Total number of steps.
+        total: u64,
+        /// Best value found so far of the metric optimized by the operation,
+        /// if the operation has such a metric.
+        best: Option<u64>,
+    },
+    /// The operation has finished.
+    ///
+    /// This is sent when the operation finishes, either successfully or with
+    /// an error.
+    Finished,
+}
+
+// Sends Progress reports with the interval of a reporter.
+#[derive(Debug)]
+pub(crate) struct ProgressSender {
+    reporter: Reporter<Progress>,
+    last_reported: Instant,
+}
+
+impl ProgressSender {
+    pub(crate) fn new(reporter: &Reporter<Progress>) -> ProgressSender {
+        ProgressSender {
+            reporter: reporter.clone(),
+            last_reported: Instant::now(),
+        }
+    }
+
+    pub(crate) fn update(&mut self, done: u64, total: u64, best: Option<u64>) {
+        let now = Instant::now();
+        if self.last_reported + self.reporter.interval <= now {
+            // Errors are ignored, since the receiver not being interested in
+            // the reports anymore should not stop the operation.
+            let _ = self
+                .reporter
+                .tx
+                .send(Progress::Update { done, total, best });
+            self.last_reported = now;
+        }
+    }
+
+    pub(crate) fn finish(&self) {
+        let _ = self.reporter.tx.send(Progress::Finished);
+    }
+}
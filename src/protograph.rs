@@ -0,0 +1,509 @@
+//! # Protograph LDPC construction
+//!
+//! A protograph is a small Tanner graph, possibly with parallel edges, which is
+//! described by its protomatrix: the entry `(r, c)` of the protomatrix gives the
+//! number of edges between the check node `r` and the variable node `c` of the
+//! protograph. A protograph code is obtained by lifting the protograph with a
+//! lifting size `z`. This replaces each node by `z` copies and each entry `e`
+//! of the protomatrix by a `z x z` block that is the sum of `e` permutation
+//! matrices without common non-zero entries. The AR4JA codes are protograph
+//! codes (see [`Protograph::ar4ja`]).
+//!
+//! Two kinds of [`Lifting`] are supported. In the circulant lifting, the
+//! permutation matrices are circulant, so the resulting code is quasi-cyclic,
+//! and the construction returns a [`QcMatrix`]. In the random lifting, the
+//! permutation matrices are arbitrary.
+//!
+//! In both cases, the permutations are selected in the spirit of the
+//! [Progressive Edge Growth](crate::peg) construction, to avoid short cycles.
+//! The edges of the protograph are lifted one at a time, by variable node. In
+//! the circulant lifting, the shift of each circulant is selected so that the
+//! check node connected to the first variable node of the column block is as
+//! far as possible from it in the graph constructed so far (all the variable
+//! nodes of the column block are equivalent because of the quasi-cyclic
+//! structure). In the random lifting, each edge of each variable node is placed
+//! separately, selecting among the check nodes of the row block that have
+//! received the fewest edges from that column block the one that is farthest
+//! from the variable node. The remaining ties are broken according to a
+//! [`TieBreaking`] rule.
+//!
+//! # Examples
+//! A quasi-cyclic code with the protograph of the AR4JA r=1/2 code and a
+//! circulant size of 64 can be constructed with
+//! ```
+//! # use ldpc_toolbox::{
+//! #     codes::ccsds::AR4JARate,
+//! #     peg::TieBreaking,
+//! #     protograph::{Config, Lifting, Protograph},
+//! # };
+//! let protograph = Protograph::ar4ja(AR4JARate::R1_2);
+//! let conf = Config {
+//!     lifting_size: 64,
+//!     lifting: Lifting::Circulant,
+//!     tie_breaking: TieBreaking::LowestIndex,
+//! };
+//! let h = conf.run(&protograph, 0)?;
+//! assert_eq!(h.num_rows(), 3 * 64);
+//! assert_eq!(h.num_cols(), 5 * 64);
+//! # Ok::<(), ldpc_toolbox::protograph::Error>(())
+//! ```
+
+use crate::codes::ccsds::AR4JARate;
+use crate::peg::TieBreaking;
+use crate::rand::{Rng, *};
+use crate::sparse::{Col, Node, QcMatrix, Row, SparseMatrix};
+use crate::util::{compare_some, *};
+use thiserror::Error;
+
+/// Protograph construction error.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum Error {
+    /// The protomatrix could not be parsed.
+    #[error("invalid protomatrix: {0}")]
+    InvalidProtomatrix(String),
+    /// The lifting size is zero.
+    #[error("the lifting size must be positive")]
+    ZeroLiftingSize,
+    /// An entry of the protomatrix is larger than the lifting size.
+    #[error("entry ({row}, {col}) of the protomatrix has {edges} edges, but the lifting size is {lifting_size}")]
+    TooManyParallelEdges {
+        /// Row of the entry.
+        row: usize,
+        /// Column of the entry.
+        col: usize,
+        /// Number of edges of the entry.
+        edges: usize,
+        /// Lifting size.
+        lifting_size: usize,
+    },
+    /// The random lifting could not place an edge without repeating an edge
+    /// of the lifted graph.
+    #[error("could not place the edges of variable node {0}")]
+    LiftingFailed(usize),
+}
+
+/// Result type used to indicate protograph construction errors.
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Protograph.
+///
+/// The protograph is given by its protomatrix. See the [module
+/// documentation](self) for more details.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Protograph {
+    // Number of edges of each entry, by rows
+    entries: Vec<Vec<usize>>,
+}
+
+impl Protograph {
+    /// Creates a new protograph without edges.
+    pub fn new(num_rows: usize, num_cols: usize) -> Protograph {
+        Protograph {
+            entries: vec![vec![0; num_cols]; num_rows],
+        }
+    }
+
+    /// Returns the protograph of the CCSDS AR4JA code of a given rate.
+    ///
+    /// The last column of the protograph is punctured in the CCSDS codes. Note
+    /// that the CCSDS codes are not obtained with a circulant lifting, so the
+    /// codes constructed from this protograph have the same degree distribution
+    /// and thresholds, but different parity check matrices.
+    pub fn ar4ja(rate: AR4JARate) -> Protograph {
+        // Columns added in front of the protomatrix of the previous rate
+        let extra: &[[usize; 3]] = match rate {
+            AR4JARate::R1_2 => &[],
+            AR4JARate::R2_3 => &[[0, 3, 1], [0, 1, 3]],
+            AR4JARate::R4_5 => &[
+                [0, 3, 1],
+                [0, 1, 3],
+                [0, 3, 1],
+                [0, 1, 3],
+                [0, 3, 1],
+                [0, 1, 3],
+            ],
+        };
+        let r1_2 = [[0, 0, 1, 0, 2], [1, 1, 0, 1, 3], [1, 2, 0, 2, 1]];
+        Protograph {
+            entries: (0..3)
+                .map(|r| {
+                    extra
+                        .iter()
+                        .map(|col| col[r])
+                        .chain(r1_2[r].iter().copied())
+                        .collect()
+                })
+                .collect(),
+        }
+    }
+
+    /// Returns the number of rows (check nodes) of the protograph.
+    pub fn num_rows(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns the number of columns (variable nodes) of the protograph.
+    pub fn num_cols(&self) -> usize {
+        self.entries.first().map_or(0, |row| row.len())
+    }
+
+    /// Returns the number of edges between a check node and a variable node.
+    pub fn edges(&self, row: usize, col: usize) -> usize {
+        self.entries[row][col]
+    }
+
+    /// Sets the number of edges between a check node and a variable node.
+    pub fn set_edges(&mut self, row: usize, col: usize, edges: usize) {
+        self.entries[row][col] = edges;
+    }
+
+    /// Returns the maximum number of parallel edges of the protograph.
+    pub fn max_edges(&self) -> usize {
+        self.entries.iter().flatten().copied().max().unwrap_or(0)
+    }
+
+    /// Checks that the protograph can be lifted with a lifting size.
+    ///
+    /// # Errors
+    /// Returns an error if `z` is zero or if some entry of the protomatrix is
+    /// larger than `z`.
+    pub fn check_lifting_size(&self, z: usize) -> Result<()> {
+        if z == 0 {
+            return Err(Error::ZeroLiftingSize);
+        }
+        for (row, entries) in self.entries.iter().enumerate() {
+            for (col, &edges) in entries.iter().enumerate() {
+                if edges > z {
+                    return Err(Error::TooManyParallelEdges {
+                        row,
+                        col,
+                        edges,
+                        lifting_size: z,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for Protograph {
+    type Err = Error;
+
+    /// Parses a protomatrix.
+    ///
+    /// The protomatrix is given as one line per row, with the number of edges
+    /// of each entry separated by whitespace. Empty lines and lines starting
+    /// with `#` are ignored.
+    fn from_str(s: &str) -> Result<Protograph> {
+        let entries = s
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| {
+                line.split_whitespace()
+                    .map(|x| {
+                        x.parse::<usize>()
+                            .map_err(|_| Error::InvalidProtomatrix(format!("invalid entry {x}")))
+                    })
+                    .collect::<Result<Vec<_>>>()
+            })
+            .collect::<Result<Vec<_>>>()?;
+        if let Some(first) = entries.first() {
+            if let Some(row) = entries.iter().position(|row| row.len() != first.len()) {
+                return Err(Error::InvalidProtomatrix(format!(
+                    "row {row} has {} entries, but row 0 has {}",
+                    entries[row].len(),
+                    first.len()
+                )));
+            }
+        }
+        Ok(Protograph { entries })
+    }
+}
+
+impl std::fmt::Display for Protograph {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for entries in &self.entries {
+            for (col, edges) in entries.iter().enumerate() {
+                if col != 0 {
+                    write!(f, " ")?;
+                }
+                write!(f, "{edges}")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Lifting of a protograph.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Lifting {
+    /// Lifting with circulant permutation matrices, which gives a
+    /// quasi-cyclic code.
+    #[default]
+    Circulant,
+    /// Lifting with arbitrary permutation matrices.
+    Random,
+}
+
+impl std::str::FromStr for Lifting {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Lifting, String> {
+        Ok(match s {
+            "circulant" => Lifting::Circulant,
+            "random" => Lifting::Random,
+            _ => Err(format!("invalid lifting {s}"))?,
+        })
+    }
+}
+
+impl std::fmt::Display for Lifting {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Lifting::Circulant => "circulant",
+                Lifting::Random => "random",
+            }
+        )
+    }
+}
+
+/// Configuration for the protograph construction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Config {
+    /// Lifting size.
+    pub lifting_size: usize,
+    /// Kind of lifting.
+    pub lifting: Lifting,
+    /// Rule used to break the ties between candidate permutations.
+    pub tie_breaking: TieBreaking,
+}
+
+impl Config {
+    /// Lifts a protograph using a random seed `seed`.
+    ///
+    /// # Errors
+    /// Returns an error if the lifting size is not valid for the protograph
+    /// (see [`Protograph::check_lifting_size`]) or if the random lifting fails.
+    pub fn run(&self, protograph: &Protograph, seed: u64) -> Result<SparseMatrix> {
+        match self.lifting {
+            Lifting::Circulant => Ok(self
+                .run_circulant(protograph, seed)?
+                .lift(self.lifting_size)),
+            Lifting::Random => self.run_random(protograph, seed),
+        }
+    }
+
+    /// Lifts a protograph with circulant permutation matrices.
+    ///
+    /// This returns the base matrix of the quasi-cyclic code, which can be
+    /// lifted with [`QcMatrix::lift`] using the lifting size of the
+    /// configuration. The [`lifting`](Config::lifting) field is ignored.
+    ///
+    /// # Errors
+    /// Returns an error if the lifting size is not valid for the protograph
+    /// (see [`Protograph::check_lifting_size`]).
+    pub fn run_circulant(&self, protograph: &Protograph, seed: u64) -> Result<QcMatrix> {
+        let z = self.lifting_size;
+        protograph.check_lifting_size(z)?;
+        let mut rng = Rng::seed_from_u64(seed);
+        let mut qc = QcMatrix::new(protograph.num_rows(), protograph.num_cols());
+        let mut h = SparseMatrix::new(protograph.num_rows() * z, protograph.num_cols() * z);
+        for col in 0..protograph.num_cols() {
+            for row in 0..protograph.num_rows() {
+                for _ in 0..protograph.edges(row, col) {
+                    let bfs = h.bfs(Node::Col(col * z));
+                    let shifts = qc.shifts(row, col);
+                    // The first column of the block is connected to the row
+                    // (z - shift) % z of the block. The BFS does not see the
+                    // 4-cycles that use the new circulant twice, which happen
+                    // when 2 * shift = a + b (mod z) for shifts a, b already
+                    // in the block. These are given the distance of a 4-cycle.
+                    let candidates = (0..z)
+                        .filter(|s| !shifts.contains(s))
+                        .map(|s| {
+                            let distance = bfs.row_nodes_distance[row * z + (z - s) % z];
+                            let four_cycle = shifts
+                                .iter()
+                                .any(|a| shifts.iter().any(|b| (2 * s) % z == (a + b) % z));
+                            (
+                                s,
+                                if four_cycle {
+                                    Some(distance.map_or(3, |d| d.min(3)))
+                                } else {
+                                    distance
+                                },
+                            )
+                        })
+                        .collect::<Vec<_>>();
+                    let shift = select(candidates, self.tie_breaking, &mut rng)
+                        .expect("no shifts available");
+                    let mut shifts = shifts.to_vec();
+                    shifts.push(shift);
+                    qc.set_shifts(row, col, shifts.iter());
+                    for j in 0..z {
+                        h.insert(Row(row * z + j), Col(col * z + (j + shift) % z));
+                    }
+                }
+            }
+        }
+        Ok(qc)
+    }
+
+    fn run_random(&self, protograph: &Protograph, seed: u64) -> Result<SparseMatrix> {
+        let z = self.lifting_size;
+        protograph.check_lifting_size(z)?;
+        let mut rng = Rng::seed_from_u64(seed);
+        let mut h = SparseMatrix::new(protograph.num_rows() * z, protograph.num_cols() * z);
+        for col_block in 0..protograph.num_cols() {
+            // Number of edges placed in each row of the current column block
+            let mut row_edges = vec![0; h.num_rows()];
+            for j in 0..z {
+                let col = col_block * z + j;
+                for row_block in 0..protograph.num_rows() {
+                    let edges = protograph.edges(row_block, col_block);
+                    for _ in 0..edges {
+                        let bfs = h.bfs(Node::Col(col));
+                        let available = (row_block * z..(row_block + 1) * z)
+                            .filter(|&r| row_edges[r] < edges && !h.contains(Row(r), Col(col)))
+                            .collect::<Vec<_>>();
+                        // Only the rows with the fewest edges are candidates,
+                        // so that the last columns of the block do not run out
+                        // of rows
+                        let fewest = available.iter().map(|&r| row_edges[r]).min();
+                        let candidates = available
+                            .into_iter()
+                            .filter(|&r| Some(row_edges[r]) == fewest)
+                            .map(|r| (r, bfs.row_nodes_distance[r]))
+                            .collect::<Vec<_>>();
+                        let row = select(candidates, self.tie_breaking, &mut rng)
+                            .ok_or(Error::LiftingFailed(col))?;
+                        row_edges[row] += 1;
+                        h.insert(Row(row), Col(col));
+                    }
+                }
+            }
+        }
+        Ok(h)
+    }
+}
+
+// Selects the candidate that is farthest from the root of the BFS, breaking
+// ties with the tie-breaking rule. Unreachable candidates are preferred.
+fn select(
+    candidates: Vec<(usize, Option<usize>)>,
+    tie_breaking: TieBreaking,
+    rng: &mut Rng,
+) -> Option<usize> {
+    let compare = |(_, x): &(usize, Option<usize>), (_, y): &(usize, Option<usize>)| {
+        compare_some(x, y).reverse()
+    };
+    let selected = match tie_breaking {
+        TieBreaking::Random => candidates.sort_by_random_min(compare, rng),
+        // min_by returns the first of the minimum elements, which is the one
+        // with the lowest index
+        TieBreaking::LowestIndex => candidates.into_iter().min_by(compare),
+    };
+    selected.map(|(j, _)| j)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::codes::ccsds::{AR4JACode, AR4JAInfoSize};
+    use enum_iterator::all;
+
+    #[test]
+    fn ar4ja_protographs() {
+        for rate in all::<AR4JARate>() {
+            let protograph = Protograph::ar4ja(rate);
+            let code = AR4JACode::new(rate, AR4JAInfoSize::K1024);
+            let h = code.h();
+            let m = h.num_rows() / 3;
+            assert_eq!(protograph.num_cols() * m, h.num_cols());
+            for row in 0..3 {
+                for col in 0..protograph.num_cols() {
+                    let edges = h.iter_row(row * m).filter(|&&c| c / m == col).count();
+                    assert_eq!(protograph.edges(row, col), edges);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn parse() {
+        let protograph: Protograph = "# comment\n1 2 0\n\n0 1 3\n".parse().unwrap();
+        assert_eq!(protograph.num_rows(), 2);
+        assert_eq!(protograph.num_cols(), 3);
+        assert_eq!(protograph.edges(1, 2), 3);
+        assert_eq!(protograph.max_edges(), 3);
+        assert_eq!(protograph.to_string(), "1 2 0\n0 1 3\n");
+        assert!("1 2\n3".parse::<Protograph>().is_err());
+        assert!("1 -1".parse::<Protograph>().is_err());
+        assert_eq!(
+            protograph.check_lifting_size(2),
+            Err(Error::TooManyParallelEdges {
+                row: 1,
+                col: 2,
+                edges: 3,
+                lifting_size: 2
+            })
+        );
+        assert_eq!(
+            protograph.check_lifting_size(0),
+            Err(Error::ZeroLiftingSize)
+        );
+    }
+
+    fn check_lifting(protograph: &Protograph, h: &SparseMatrix, z: usize) {
+        assert_eq!(h.num_rows(), protograph.num_rows() * z);
+        assert_eq!(h.num_cols(), protograph.num_cols() * z);
+        for row in 0..h.num_rows() {
+            for col_block in 0..protograph.num_cols() {
+                let edges = h.iter_row(row).filter(|&&c| c / z == col_block).count();
+                assert_eq!(edges, protograph.edges(row / z, col_block));
+            }
+        }
+        for col in 0..h.num_cols() {
+            for row_block in 0..protograph.num_rows() {
+                let edges = h.iter_col(col).filter(|&&r| r / z == row_block).count();
+                assert_eq!(edges, protograph.edges(row_block, col / z));
+            }
+        }
+    }
+
+    #[test]
+    fn circulant_lifting() {
+        let protograph = Protograph::ar4ja(AR4JARate::R1_2);
+        let conf = Config {
+            lifting_size: 32,
+            lifting: Lifting::Circulant,
+            tie_breaking: TieBreaking::LowestIndex,
+        };
+        let qc = conf.run_circulant(&protograph, 0).unwrap();
+        assert_eq!(qc.shifts(1, 4).len(), 3);
+        let h = conf.run(&protograph, 1).unwrap();
+        assert_eq!(h, qc.lift(32));
+        check_lifting(&protograph, &h, 32);
+        assert!(h.girth().unwrap() >= 6);
+    }
+
+    #[test]
+    fn random_lifting() {
+        let protograph = Protograph::ar4ja(AR4JARate::R2_3);
+        let conf = Config {
+            lifting_size: 32,
+            lifting: Lifting::Random,
+            tie_breaking: TieBreaking::Random,
+        };
+        let h = conf.run(&protograph, 0).unwrap();
+        check_lifting(&protograph, &h, 32);
+        assert_eq!(h, conf.run(&protograph, 0).unwrap());
+        assert_ne!(h, conf.run(&protograph, 1).unwrap());
+    }
+}
@@ -11,6 +11,16 @@
 //! let mut rng = Rng::seed_from_u64(seed);
 //! assert_eq!(rng.next_u64(), 12578764544318200737);
 //! ```
+//!
+//! # Random streams
+//!
+//! Algorithms that use randomness for several independent purposes (for
+//! instance, a simulation that generates random messages and random noise)
+//! should draw each of them from a separate named stream derived from a master
+//! seed using [`stream_rng`]. In this way, a change in how much randomness is
+//! used for one purpose does not shift the random numbers used for the other
+//! purposes, so the results obtained with the same master seed remain
+//! comparable.
 pub use rand_chacha::rand_core::SeedableRng;
 use rand_chacha::ChaCha8Rng;
 pub use rand_core::RngCore;
@@ -18,3 +28,41 @@ pub use rand_core::RngCore;
 /// The RNG used in throughout this crate for algorithms using pseudorandom
 /// generation.
 pub type Rng = ChaCha8Rng;
+
+/// Creates the RNG for a named random stream.
+///
+/// The RNG is derived from the master `seed`, the `name` of the stream and an
+/// `index`, which can be used to distinguish several instances of the same
+/// stream (for instance, one for each worker thread). RNGs with the same
+/// master seed and different names or indices are independent: they use the
+/// same ChaCha key and different ChaCha stream numbers.
+///
+/// # Examples
+/// ```
+/// # use ldpc_toolbox::rand::*;
+/// let mut noise = stream_rng(42, "noise", 0);
+/// let mut messages = stream_rng(42, "message", 0);
+/// assert_ne!(noise.next_u64(), messages.next_u64());
+/// // The same stream can be recreated from the master seed
+/// let mut noise2 = stream_rng(42, "noise", 0);
+/// noise.set_word_pos(0);
+/// assert_eq!(noise.next_u64(), noise2.next_u64());
+/// ```
+pub fn stream_rng(seed: u64, name: &str, index: u64) -> Rng {
+    let mut rng = Rng::seed_from_u64(seed);
+    rng.set_stream(stream_number(name, index));
+    rng
+}
+
+// Computes the ChaCha stream number for a named stream using the FNV-1a hash
+// of the name and index.
+fn stream_number(name: &str, index: u64) -> u64 {
+    const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const FNV_PRIME: u64 = 0x100000001b3;
+    name.bytes()
+        .chain([0])
+        .chain(index.to_le_bytes())
+        .fold(FNV_OFFSET_BASIS, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME)
+        })
+}
@@ -0,0 +1,695 @@
+//! Rate-compatible code families.
+//!
+//! This module generates a nested family of codes of different rates from a
+//! mother code, as used in adaptive coding and modulation systems. The rate of
+//! the mother code is increased by row merging and decreased by shortening:
+//!
+//! - Row merging replaces the two check nodes connected to a parity variable
+//!   node of degree two by their sum, which does not involve this variable
+//!   node, and removes the variable node. The resulting code is the mother
+//!   code punctured in the removed variable node, so it contains the mother
+//!   code (after puncturing), and the merged check node keeps the information
+//!   of the removed one. Check nodes are merged only if they do not have any
+//!   other variable node in common, so that the degrees of the remaining
+//!   variable nodes are preserved. Among the candidates, the pair of check
+//!   nodes whose merged check node has the smallest degree is merged first.
+//! - Shortening removes the last information bits of the mother code, which
+//!   are fixed to zero.
+//!
+//! The mother code must support systematic encoding using its first variable
+//! nodes (see [`systematic`](crate::systematic)), and its last columns are taken
+//! as the parity columns. Since only parity columns are removed by row merging,
+//! and the square submatrix of the parity columns remains invertible, all the
+//! codes of the family can also be encoded systematically, with the
+//! information bits in their first variable nodes.
+//!
+//! Each code of the family is described by a [`FamilyMember`], which contains
+//! its parity check matrix together with the positions of the mother codeword
+//! that are punctured or shortened. These descriptors allow the codes of the
+//! family to be used with the encoder and decoder of the mother code.
+//!
+//! For incremental redundancy (HARQ) schemes, in which the receiver decodes
+//! all the rates with the parity check matrix of the mother code, the function
+//! [`nested_puncturing`] searches instead for an order in which to puncture
+//! the parity bits of the mother code. The punctured bits are chosen with the
+//! grouping criterion of the k-step recoverable (k-SR) nodes: a punctured bit
+//! is k-SR if it has a check node whose other variable nodes are unpunctured
+//! or (j-SR, j < k) punctured, so that its value is recovered after k
+//! iterations of the decoder when there are no channel errors. Bits with
+//! smaller recovery levels are punctured first. The punctured bits of each
+//! rate are the first bits of the order, so the puncturing patterns are
+//! nested.
+//!
+//! # Examples
+//! ```
+//! # use ldpc_toolbox::{rate_compatible::nested_family, sparse::SparseMatrix};
+//! // Mother code with rate 1/2 and a staircase parity part
+//! let m = 20;
+//! let mut h = SparseMatrix::new(m, 2 * m);
+//! for j in 0..m {
+//!     h.insert_row(j, [j, (j + 3) % m, (j + 7) % m].iter());
+//!     h.insert(j, m + j);
+//!     if j > 0 {
+//!         h.insert(j, m + j - 1);
+//!     }
+//! }
+//! let family = nested_family(&h, &[1.0 / 3.0, 0.5, 2.0 / 3.0])?;
+//! assert_eq!(family[0].shortened(), (10..20).collect::<Vec<_>>());
+//! assert_eq!(family[1].h(), &h);
+//! assert_eq!(family[2].punctured().len(), 10);
+//! assert_eq!(family[2].h().num_cols(), 30);
+//! assert_eq!(family[2].h().num_rows(), 10);
+//! # Ok::<(), ldpc_toolbox::rate_compatible::Error>(())
+//! ```
+
+use crate::{linalg::SparseSolver, sparse::SparseMatrix};
+use std::collections::BTreeSet;
+use thiserror::Error;
+
+/// Rate-compatible family error.
+#[derive(Debug, Copy, Clone, PartialEq, Error)]
+pub enum Error {
+    /// The parity check matrix has more rows than columns.
+    #[error("the parity check matrix has more rows than columns")]
+    ParityOverdetermined,
+    /// The submatrix formed by the last columns of the parity check matrix is
+    /// not invertible.
+    #[error("the last columns of the parity check matrix are not invertible")]
+    NotEncodable,
+    /// The rate is not valid.
+    #[error("invalid rate {0}")]
+    InvalidRate(f64),
+    /// The rate cannot be obtained by row merging.
+    #[error("rate {0} cannot be obtained by row merging")]
+    NotEnoughMerges(f64),
+    /// The rate cannot be obtained by puncturing recoverable nodes.
+    #[error("rate {0} cannot be obtained by puncturing recoverable nodes")]
+    NotEnoughRecoverable(f64),
+}
+
+/// Code of a rate-compatible family.
+///
+/// The code is described with respect to the mother code of the family. The
+/// columns of its parity check matrix are the columns of the mother code that
+/// are neither punctured nor shortened, in the same order, and each of its rows
+/// is the sum of some rows of the mother code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FamilyMember {
+    h: SparseMatrix,
+    columns: Vec<usize>,
+    rows: Vec<Vec<usize>>,
+    punctured: Vec<usize>,
+    shortened: Vec<usize>,
+}
+
+impl FamilyMember {
+    /// Returns the parity check matrix of the code.
+    pub fn h(&self) -> &SparseMatrix {
+        &self.h
+    }
+
+    /// Returns the rate of the code.
+    pub fn rate(&self) -> f64 {
+        let n = self.h.num_cols();
+        (n - self.h.num_rows()) as f64 / n as f64
+    }
+
+    /// Returns the column of the mother code corresponding to each column of
+    /// the code.
+    pub fn columns(&self) -> &[usize] {
+        &self.columns
+    }
+
+    /// Returns the rows of the mother code that are added to obtain each row
+    /// of the code.
+    pub fn rows(&self) -> &[Vec<usize>] {
+        &self.rows
+    }
+
+    /// Returns the columns of the mother code that are punctured.
+    ///
+    /// The positions are sorted in increasing order.
+    pub fn punctured(&self) -> &[usize] {
+        &self.punctured
+    }
+
+    /// Returns the columns of the mother code that are shortened.
+    ///
+    /// These are information columns of the mother code, sorted in increasing
+    /// order.
+    pub fn shortened(&self) -> &[usize] {
+        &self.shortened
+    }
+
+    /// Returns the puncturing pattern of the mother codeword.
+    ///
+    /// The pattern has an element for each column of the mother code, which is
+    /// `false` if the column is punctured. Shortened columns are not marked in
+    /// the pattern. This corresponds to a pattern defined per bit in
+    /// [`puncturing`](crate::simulation::puncturing).
+    pub fn puncturing_pattern(&self, mother_columns: usize) -> Vec<bool> {
+        let mut pattern = vec![true; mother_columns];
+        for &j in &self.punctured {
+            pattern[j] = false;
+        }
+        pattern
+    }
+}
+
+/// Generates a nested family of codes from a mother code.
+///
+/// The parameter `rates` lists the rates of the codes of the family, which is
+/// returned in order of increasing rate. Rates larger than the rate of the
+/// mother code are obtained by row merging and rates smaller than the rate of
+/// the mother code by shortening. The number of variable nodes removed is
+/// rounded to obtain the closest possible rate. See the [module
+/// documentation](self) for more details.
+pub fn nested_family(h: &SparseMatrix, rates: &[f64]) -> Result<Vec<FamilyMember>, Error> {
+    let n = h.num_cols();
+    let m = h.num_rows();
+    if m > n {
+        return Err(Error::ParityOverdetermined);
+    }
+    let k = n - m;
+    let mut parity = SparseMatrix::new(m, m);
+    for (row, col) in h.iter_all() {
+        if col >= k {
+            parity.insert(row, col - k);
+        }
+    }
+    if SparseSolver::new(&parity).rank() != m {
+        return Err(Error::NotEncodable);
+    }
+    let mut rates = rates.to_vec();
+    if let Some(&rate) = rates
+        .iter()
+        .find(|r| !(r.is_finite() && **r > 0.0 && **r < 1.0))
+    {
+        return Err(Error::InvalidRate(rate));
+    }
+    rates.sort_by(f64::total_cmp);
+    let mut merger = RowMerger::new(h);
+    rates
+        .into_iter()
+        .map(|rate| {
+            if rate * n as f64 <= k as f64 {
+                // Shortening: k - s = rate * (n - s)
+                let s = ((k as f64 - rate * n as f64) / (1.0 - rate)).round() as usize;
+                if s >= k {
+                    return Err(Error::InvalidRate(rate));
+                }
+                Ok(merger.mother_member((k - s..k).collect()))
+            } else {
+                // Row merging: k = rate * (n - p)
+                let p = (n as f64 - k as f64 / rate).round() as usize;
+                while merger.punctured.len() < p {
+                    if !merger.merge() {
+                        return Err(Error::NotEnoughMerges(rate));
+                    }
+                }
+                Ok(merger.member())
+            }
+        })
+        .collect()
+}
+
+// State of the row merging, starting by the mother code.
+#[derive(Debug)]
+struct RowMerger {
+    mother: SparseMatrix,
+    num_cols: usize,
+    k: usize,
+    // Sorted columns of each row, or None if the row has been merged into
+    // another
+    rows: Vec<Option<Vec<usize>>>,
+    // Mother rows added to obtain each row
+    groups: Vec<Vec<usize>>,
+    col_rows: Vec<Vec<usize>>,
+    // Candidate parity columns, as (merged row degree, column)
+    candidates: BTreeSet<(usize, usize)>,
+    cost: Vec<Option<usize>>,
+    punctured: Vec<usize>,
+}
+
+impl RowMerger {
+    fn new(h: &SparseMatrix) -> RowMerger {
+        let num_cols = h.num_cols();
+        let rows = (0..h.num_rows())
+            .map(|row| {
+                let mut cols = h.iter_row(row).copied().collect::<Vec<_>>();
+                cols.sort_unstable();
+                Some(cols)
+            })
+            .collect::<Vec<_>>();
+        let col_rows = (0..num_cols)
+            .map(|col| h.iter_col(col).copied().collect())
+            .collect();
+        let mut merger = RowMerger {
+            mother: h.clone(),
+            num_cols,
+            k: num_cols - h.num_rows(),
+            rows,
+            groups: (0..h.num_rows()).map(|row| vec![row]).collect(),
+            col_rows,
+            candidates: BTreeSet::new(),
+            cost: vec![None; num_cols],
+            punctured: Vec::new(),
+        };
+        for col in merger.k..num_cols {
+            merger.update_candidate(col);
+        }
+        merger
+    }
+
+    // Degree of the merged row obtained by removing a parity column, if the
+    // column can be removed.
+    fn merge_cost(&self, col: usize) -> Option<usize> {
+        let &[a, b] = self.col_rows[col].as_slice() else {
+            return None;
+        };
+        let (a, b) = (self.rows[a].as_ref()?, self.rows[b].as_ref()?);
+        let shared = a.iter().filter(|x| b.binary_search(x).is_ok()).count();
+        (shared == 1).then(|| a.len() + b.len() - 2)
+    }
+
+    fn update_candidate(&mut self, col: usize) {
+        if let Some(cost) = self.cost[col].take() {
+            self.candidates.remove(&(cost, col));
+        }
+        if col >= self.k && !self.punctured.contains(&col) {
+            self.cost[col] = self.merge_cost(col);
+            if let Some(cost) = self.cost[col] {
+                self.candidates.insert((cost, col));
+            }
+        }
+    }
+
+    // Removes the best candidate column. Returns false if there are no
+    // candidates.
+    fn merge(&mut self) -> bool {
+        let Some(&(_, col)) = self.candidates.first() else {
+            return false;
+        };
+        self.punctured.push(col);
+        self.update_candidate(col);
+        let (a, b) = (self.col_rows[col][0], self.col_rows[col][1]);
+        // Row b is merged into row a
+        let row_b = self.rows[b].take().unwrap();
+        let group_b = std::mem::take(&mut self.groups[b]);
+        self.groups[a].extend(group_b);
+        self.groups[a].sort_unstable();
+        let row_a = self.rows[a].as_mut().unwrap();
+        row_a.extend(row_b.iter().copied());
+        row_a.retain(|&x| x != col);
+        row_a.sort_unstable();
+        for &x in &row_b {
+            for r in self.col_rows[x].iter_mut() {
+                if *r == b {
+                    *r = a;
+                }
+            }
+        }
+        self.col_rows[col].clear();
+        for x in self.rows[a].clone().unwrap() {
+            self.update_candidate(x);
+        }
+        true
+    }
+
+    // Returns the current code obtained by row merging.
+    fn member(&self) -> FamilyMember {
+        let mut punctured = self.punctured.clone();
+        punctured.sort_unstable();
+        self.build(punctured, Vec::new())
+    }
+
+    // Returns the mother code with some shortened columns.
+    fn mother_member(&self, shortened: Vec<usize>) -> FamilyMember {
+        assert!(self.punctured.is_empty());
+        let mut member = self.build(Vec::new(), shortened);
+        if member.shortened.is_empty() {
+            // Keep the row ordering and the column metadata of the mother code
+            member.h = self.mother.clone();
+        }
+        member
+    }
+
+    fn build(&self, punctured: Vec<usize>, shortened: Vec<usize>) -> FamilyMember {
+        let columns = (0..self.num_cols)
+            .filter(|j| punctured.binary_search(j).is_err() && shortened.binary_search(j).is_err())
+            .collect::<Vec<_>>();
+        let mut new_col = vec![None; self.num_cols];
+        for (j, &col) in columns.iter().enumerate() {
+            new_col[col] = Some(j);
+        }
+        let rows = self
+            .rows
+            .iter()
+            .zip(&self.groups)
+            .filter(|(row, _)| row.is_some())
+            .map(|(_, group)| group.clone())
+            .collect::<Vec<_>>();
+        let mut h = SparseMatrix::new(rows.len(), columns.len());
+        for (j, row) in self.rows.iter().flatten().enumerate() {
+            h.insert_row(j, row.iter().filter_map(|&col| new_col[col]));
+        }
+        FamilyMember {
+            h,
+            columns,
+            rows,
+            punctured,
+            shortened,
+        }
+    }
+}
+
+/// Nested puncturing patterns of a mother code.
+///
+/// This gives the parity columns of the mother code in the order in which they
+/// are punctured, together with their recovery levels. It is obtained with
+/// [`nested_puncturing`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NestedPuncturing {
+    num_cols: usize,
+    k: usize,
+    order: Vec<usize>,
+    levels: Vec<usize>,
+}
+
+impl NestedPuncturing {
+    /// Returns the columns of the mother code in puncturing order.
+    pub fn order(&self) -> &[usize] {
+        &self.order
+    }
+
+    /// Returns the recovery level of each column of
+    /// [`NestedPuncturing::order`].
+    ///
+    /// A column with recovery level k is recovered after k iterations of the
+    /// decoder when there are no channel errors. The levels are in increasing
+    /// order.
+    pub fn recovery_levels(&self) -> &[usize] {
+        &self.levels
+    }
+
+    /// Returns the largest rate that can be obtained by puncturing.
+    pub fn max_rate(&self) -> f64 {
+        self.k as f64 / (self.num_cols - self.order.len()) as f64
+    }
+
+    /// Returns the columns of the mother code that are punctured to obtain a
+    /// rate.
+    ///
+    /// The number of punctured columns is rounded to obtain the closest
+    /// possible rate. The rate must not be smaller than the rate of the mother
+    /// code.
+    pub fn punctured(&self, rate: f64) -> Result<&[usize], Error> {
+        if !(rate.is_finite() && rate > 0.0 && rate < 1.0) {
+            return Err(Error::InvalidRate(rate));
+        }
+        // k = rate * (n - p)
+        let p = (self.num_cols as f64 - self.k as f64 / rate).round();
+        if p < 0.0 {
+            return Err(Error::InvalidRate(rate));
+        }
+        self.order
+            .get(..p as usize)
+            .ok_or(Error::NotEnoughRecoverable(rate))
+    }
+
+    /// Returns the puncturing pattern of the mother codeword for a rate.
+    ///
+    /// The pattern has an element for each column of the mother code, which is
+    /// `false` if the column is punctured. This corresponds to a pattern
+    /// defined per bit in [`puncturing`](crate::simulation::puncturing), so
+    /// it can be given to
+    /// [`Puncturer::per_bit`](crate::simulation::puncturing::Puncturer::per_bit).
+    pub fn pattern(&self, rate: f64) -> Result<Vec<bool>, Error> {
+        let mut pattern = vec![true; self.num_cols];
+        for &j in self.punctured(rate)? {
+            pattern[j] = false;
+        }
+        Ok(pattern)
+    }
+}
+
+/// Searches for nested puncturing patterns of a mother code.
+///
+/// The parity columns of the mother code, which are its last columns, are
+/// punctured in groups of increasing recovery level. For each recovery level
+/// k, a parity column is a candidate if it has an unused check node whose other
+/// variable nodes are unpunctured or punctured with a level smaller than k.
+/// When a column is punctured, this check node is reserved to recover it, and
+/// the other variable nodes of the check node cannot be punctured afterwards,
+/// since this would delay the recovery. Among the candidates, the check nodes
+/// with fewer punctured variable nodes and the smallest degree are chosen
+/// first, since they give a more reliable recovery. The search finishes when no
+/// more columns can be punctured. See the [module documentation](self) for
+/// more details.
+///
+/// # Examples
+/// ```
+/// # use ldpc_toolbox::{rate_compatible::nested_puncturing, sparse::SparseMatrix};
+/// // Mother code with rate 1/2 and a staircase parity part
+/// let m = 20;
+/// let mut h = SparseMatrix::new(m, 2 * m);
+/// for j in 0..m {
+///     h.insert_row(j, [j, (j + 3) % m, (j + 7) % m].iter());
+///     h.insert(j, m + j);
+///     if j > 0 {
+///         h.insert(j, m + j - 1);
+///     }
+/// }
+/// let puncturing = nested_puncturing(&h)?;
+/// assert!(puncturing.max_rate() >= 2.0 / 3.0);
+/// let pattern = puncturing.pattern(2.0 / 3.0)?;
+/// assert_eq!(pattern.iter().filter(|&&b| !b).count(), 10);
+/// assert_eq!(puncturing.recovery_levels()[0], 1);
+/// # Ok::<(), ldpc_toolbox::rate_compatible::Error>(())
+/// ```
+pub fn nested_puncturing(h: &SparseMatrix) -> Result<NestedPuncturing, Error> {
+    let n = h.num_cols();
+    let m = h.num_rows();
+    if m > n {
+        return Err(Error::ParityOverdetermined);
+    }
+    let k = n - m;
+    let rows = (0..m)
+        .map(|row| h.iter_row(row).copied().collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    // Recovery level of each column, or 0 if the column is not punctured
+    let mut level = vec![0; n];
+    let mut protected = vec![false; n];
+    let mut used = vec![false; m];
+    let mut order = Vec::new();
+    let mut levels = Vec::new();
+    let punctured_count = |level: &[usize], row: usize| -> usize {
+        rows[row].iter().filter(|&&col| level[col] != 0).count()
+    };
+    for l in 1.. {
+        // Candidates as (punctured variable nodes, check degree, column, check)
+        let mut candidates = BTreeSet::new();
+        for (row, cols) in rows.iter().enumerate() {
+            if used[row] {
+                continue;
+            }
+            let count = punctured_count(&level, row);
+            for &col in cols {
+                if col >= k && level[col] == 0 && !protected[col] {
+                    candidates.insert((count, cols.len(), col, row));
+                }
+            }
+        }
+        let num_punctured = order.len();
+        while let Some((count, degree, col, row)) = candidates.pop_first() {
+            if level[col] != 0 || protected[col] || used[row] {
+                continue;
+            }
+            // Nodes punctured in this level cannot be used for recovery
+            if rows[row].iter().any(|&x| level[x] >= l) {
+                continue;
+            }
+            let current = punctured_count(&level, row);
+            if current != count {
+                candidates.insert((current, degree, col, row));
+                continue;
+            }
+            level[col] = l;
+            used[row] = true;
+            for &x in &rows[row] {
+                protected[x] = true;
+            }
+            order.push(col);
+            levels.push(l);
+        }
+        if order.len() == num_punctured {
+            break;
+        }
+    }
+    Ok(NestedPuncturing {
+        num_cols: n,
+        k,
+        order,
+        levels,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{encoder::Encoder, gf2::GF2};
+    use ndarray::Array1;
+    use num_traits::{One, Zero};
+
+    // Rate 1/2 code with staircase parity and information columns of degree 3
+    fn mother() -> SparseMatrix {
+        let m = 20;
+        let mut h = SparseMatrix::new(m, 2 * m);
+        for j in 0..m {
+            h.insert_row(j, [j, (j + 3) % m, (j + 7) % m].iter());
+            h.insert(j, m + j);
+            if j > 0 {
+                h.insert(j, m + j - 1);
+            }
+        }
+        h
+    }
+
+    #[test]
+    fn family() {
+        let h = mother();
+        let family = nested_family(&h, &[2.0 / 3.0, 0.5, 1.0 / 3.0, 5.0 / 8.0]).unwrap();
+        let rates = family.iter().map(|c| c.rate()).collect::<Vec<_>>();
+        assert!(rates
+            .iter()
+            .zip([1.0 / 3.0, 0.5, 5.0 / 8.0, 2.0 / 3.0])
+            .all(|(r, s)| (r - s).abs() < 1e-9));
+        assert_eq!(family[0].shortened(), (10..20).collect::<Vec<_>>());
+        assert_eq!(family[0].h().num_cols(), 30);
+        assert_eq!(family[1].h(), &h);
+        assert_eq!(family[2].punctured().len(), 8);
+        assert_eq!(family[3].punctured().len(), 10);
+        // The family is nested
+        assert!(family[2]
+            .punctured()
+            .iter()
+            .all(|p| family[3].punctured().contains(p)));
+
+        for code in &family[2..] {
+            // Punctured columns are parity columns and each row is the sum of
+            // its mother rows
+            assert!(code.punctured().iter().all(|&p| p >= 20));
+            for (j, rows) in code.rows().iter().enumerate() {
+                let mut sum = [0u8; 40];
+                for &r in rows {
+                    for &c in h.iter_row(r) {
+                        sum[c] ^= 1;
+                    }
+                }
+                let mut expected = code
+                    .h()
+                    .iter_row(j)
+                    .map(|&c| code.columns()[c])
+                    .collect::<Vec<_>>();
+                expected.sort_unstable();
+                let got = (0..40).filter(|&c| sum[c] == 1).collect::<Vec<_>>();
+                assert_eq!(got, expected);
+            }
+            // Mother codewords punctured are codewords of the code
+            let encoder = Encoder::from_h(&h).unwrap();
+            let message =
+                Array1::from_iter((0..20).map(
+                    |j| {
+                        if j % 3 == 0 {
+                            GF2::one()
+                        } else {
+                            GF2::zero()
+                        }
+                    },
+                ));
+            let codeword = encoder.encode(&message);
+            let pattern = code.puncturing_pattern(40);
+            let punctured = codeword
+                .iter()
+                .zip(&pattern)
+                .filter(|(_, &b)| b)
+                .map(|(&x, _)| x)
+                .collect::<Vec<_>>();
+            for j in 0..code.h().num_rows() {
+                let parity = code
+                    .h()
+                    .iter_row(j)
+                    .fold(GF2::zero(), |acc, &c| acc + punctured[c]);
+                assert_eq!(parity, GF2::zero());
+            }
+            // The code can be encoded systematically
+            assert!(Encoder::from_h(code.h()).is_ok());
+        }
+    }
+
+    #[test]
+    fn puncturing() {
+        let h = mother();
+        let puncturing = nested_puncturing(&h).unwrap();
+        let order = puncturing.order();
+        assert!(order.iter().all(|&p| p >= 20));
+        let levels = puncturing.recovery_levels();
+        assert!(levels.windows(2).all(|w| w[0] <= w[1]));
+        assert_eq!(puncturing.max_rate(), 20.0 / (40 - order.len()) as f64);
+        assert!(puncturing.punctured(0.5).unwrap().is_empty());
+        let p = puncturing.punctured(0.6).unwrap();
+        assert_eq!(p.len(), 7);
+        assert_eq!(p, &order[..7]);
+        // The punctured bits are recovered as erasures by a peeling decoder
+        // with the number of iterations given by the recovery levels
+        let mut erased = [false; 40];
+        for &j in order {
+            erased[j] = true;
+        }
+        let mut iterations = 0;
+        while erased.iter().any(|&e| e) {
+            let recovered = (0..20)
+                .filter_map(|row| {
+                    let mut cols = h.iter_row(row).filter(|&&c| erased[c]);
+                    match (cols.next(), cols.next()) {
+                        (Some(&c), None) => Some(c),
+                        _ => None,
+                    }
+                })
+                .collect::<Vec<_>>();
+            assert!(!recovered.is_empty());
+            for c in recovered {
+                erased[c] = false;
+            }
+            iterations += 1;
+        }
+        assert!(iterations <= *levels.last().unwrap());
+        let pattern = puncturing.pattern(0.6).unwrap();
+        assert_eq!(pattern.iter().filter(|&&b| !b).count(), 7);
+        assert_eq!(
+            puncturing.punctured(0.4).unwrap_err(),
+            Error::InvalidRate(0.4)
+        );
+        assert_eq!(
+            puncturing.punctured(0.99).unwrap_err(),
+            Error::NotEnoughRecoverable(0.99)
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let h = mother();
+        assert_eq!(
+            nested_family(&h, &[0.99]).unwrap_err(),
+            Error::NotEnoughMerges(0.99)
+        );
+        assert_eq!(
+            nested_family(&h, &[1.0]).unwrap_err(),
+            Error::InvalidRate(1.0)
+        );
+        let mut h = SparseMatrix::new(2, 4);
+        h.insert_row(0, [0, 1].iter());
+        h.insert_row(1, [2, 3].iter());
+        assert_eq!(nested_family(&h, &[0.5]).unwrap_err(), Error::NotEncodable);
+    }
+}
@@ -0,0 +1,516 @@
+//! Code property reports.
+//!
+//! This module generates a report that summarizes the properties of an LDPC
+//! code: its dimensions and rate, the degree distributions of its Tanner graph,
+//! its girth and short cycles, the encoding method that can be used, and some
+//! suggested decoder settings. Optionally, the report can include an estimate of
+//! the BER of the code at a few Eb/N0's, which must be obtained separately (for
+//! instance, with a short BER simulation) and is added with
+//! [`CodeReport::ber`].
+//!
+//! The report can be formatted as Markdown with [`CodeReport::write_markdown`]
+//! or as HTML with [`CodeReport::write_html`].
+//!
+//! # Examples
+//! ```
+//! # use ldpc_toolbox::{codes::reference::ReferenceCode, report::CodeReport};
+//! let report = CodeReport::new(&ReferenceCode::Hamming7_4, 8);
+//! assert_eq!((report.n, report.k), (7, 4));
+//! assert_eq!(report.girth, Some(4));
+//! let mut markdown = String::new();
+//! report.write_markdown(&mut markdown).unwrap();
+//! assert!(markdown.starts_with("# Hamming (7, 4)"));
+//! ```
+
+use crate::{
+    codes::Code,
+    decoder::factory::DecoderImplementation,
+    encoder::{Encoder, EncodingMethod},
+    sparse::SparseMatrix,
+};
+use std::fmt::{Result, Write};
+
+/// Code property report.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CodeReport {
+    /// Name of the code.
+    pub name: String,
+    /// Codeword length (number of columns of the parity check matrix).
+    pub n: usize,
+    /// Number of information bits.
+    pub k: usize,
+    /// Number of parity checks (number of rows of the parity check matrix).
+    pub m: usize,
+    /// Code rate, taking into account the standard puncturing of the code.
+    pub rate: f64,
+    /// Number of punctured bits.
+    pub punctured: usize,
+    /// Number of edges of the Tanner graph.
+    pub edges: usize,
+    /// Variable node degree distribution.
+    pub var_degrees: Vec<DegreeCount>,
+    /// Check node degree distribution.
+    pub check_degrees: Vec<DegreeCount>,
+    /// Maximum cycle length considered in the analysis of cycles.
+    pub max_cycle_length: usize,
+    /// Girth of the Tanner graph.
+    ///
+    /// This is `None` if there are no cycles of length smaller or equal than
+    /// [`CodeReport::max_cycle_length`].
+    pub girth: Option<usize>,
+    /// Short cycles.
+    ///
+    /// For each even cycle length from 4 to [`CodeReport::max_cycle_length`],
+    /// this contains the number of edges that belong to some cycle of length
+    /// smaller or equal than it.
+    pub short_cycles: Vec<ShortCycles>,
+    /// Encoding method.
+    ///
+    /// This is `None` if the code cannot be encoded with the systematic
+    /// [`Encoder`].
+    pub encoding: Option<EncodingMethod>,
+    /// Suggested decoder settings.
+    pub suggestions: Vec<String>,
+    /// BER estimates.
+    pub ber: Vec<BerEstimate>,
+}
+
+/// Number of nodes with a given degree.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DegreeCount {
+    /// Degree.
+    pub degree: usize,
+    /// Number of nodes with this degree.
+    pub nodes: usize,
+    /// Fraction of the edges that are connected to nodes with this degree.
+    pub edge_fraction: f64,
+}
+
+/// Number of edges in short cycles.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShortCycles {
+    /// Maximum cycle length.
+    pub max_length: usize,
+    /// Number of edges that belong to a cycle of length smaller or equal than
+    /// `max_length`.
+    pub edges: usize,
+}
+
+/// BER estimate at an Eb/N0.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BerEstimate {
+    /// Eb/N0 in dB units.
+    pub ebn0_db: f64,
+    /// Number of frames simulated.
+    pub frames: u64,
+    /// Bit error rate.
+    pub ber: f64,
+    /// Frame error rate.
+    pub fer: f64,
+    /// 95% confidence interval for the bit error rate.
+    pub ber_confidence_interval: (f64, f64),
+    /// 95% confidence interval for the frame error rate.
+    pub fer_confidence_interval: (f64, f64),
+}
+
+impl CodeReport {
+    /// Creates the report of a code.
+    ///
+    /// The cycles of the Tanner graph are analyzed up to length
+    /// `max_cycle_length`. The cost of this analysis grows quickly with
+    /// `max_cycle_length`, so typically a value between 6 and 10 is used.
+    pub fn new(code: &dyn Code, max_cycle_length: usize) -> CodeReport {
+        let h = code.h();
+        let short_cycles = (4..=max_cycle_length)
+            .step_by(2)
+            .map(|max_length| ShortCycles {
+                max_length,
+                edges: h.short_cycle_edges(max_length).len(),
+            })
+            .collect::<Vec<_>>();
+        let girth = short_cycles
+            .iter()
+            .find(|c| c.edges > 0)
+            .map(|c| c.max_length);
+        let encoding = Encoder::from_h(&h).ok().map(|encoder| encoder.method());
+        let mut report = CodeReport {
+            name: code.name(),
+            n: code.n(),
+            k: code.k(),
+            m: h.num_rows(),
+            rate: code.rate(),
+            punctured: code.standard_puncturing().map_or(0, |p| {
+                code.n() / p.len() * p.iter().filter(|&&x| !x).count()
+            }),
+            edges: h.iter_all().count(),
+            var_degrees: degree_distribution((0..h.num_cols()).map(|c| h.col_weight(c))),
+            check_degrees: degree_distribution((0..h.num_rows()).map(|r| h.row_weight(r))),
+            max_cycle_length,
+            girth,
+            short_cycles,
+            encoding,
+            suggestions: Vec::new(),
+            ber: Vec::new(),
+        };
+        report.suggestions = report.suggest(&h);
+        report
+    }
+
+    fn suggest(&self, h: &SparseMatrix) -> Vec<String> {
+        let recommendation = DecoderImplementation::recommend(h, &[]);
+        let mut suggestions = vec![
+            format!(
+                "Recommended decoder for simulations: {}. {}",
+                recommendation.implementation,
+                recommendation.reasons.join(" ")
+            ),
+            "Use about 50 iterations with a horizontal layered decoder (such as HLAminstarf32) \
+             or 100 iterations with a flooding decoder (such as Aminstarf32). \
+             The layered schedule needs about half of the iterations of the flooding schedule."
+                .to_string(),
+        ];
+        if self.var_degrees.iter().any(|d| d.degree == 1) {
+            suggestions.push(
+                "There are variable nodes of degree 1. For 8-bit fixed point decoding, \
+                 use the Deg1Clip variants (for instance Aminstari8Deg1Clip)."
+                    .to_string(),
+            );
+        } else {
+            suggestions.push("For 8-bit fixed point decoding, use Aminstari8.".to_string());
+        }
+        let chains = h.degree_two_chains();
+        if let Some(longest) = chains.iter().map(|chain| chain.cols.len()).max() {
+            suggestions.push(format!(
+                "There are {} chains of degree-2 variable nodes (the longest has {} nodes), \
+                 as in accumulator structures. Decoders can absorb these chains by merging \
+                 their check nodes (see SparseMatrix::reduce).",
+                chains.len(),
+                longest
+            ));
+        }
+        let components = h.connected_components();
+        if components.len() > 1 {
+            suggestions.push(format!(
+                "The Tanner graph is not connected: it splits into {} independent sub-codes, \
+                 which are decoded separately by belief propagation. This is usually an \
+                 accident of the code construction.",
+                components.len()
+            ));
+        }
+        if self.girth == Some(4) {
+            suggestions.push(format!(
+                "The Tanner graph contains 4-cycles ({} edges). Scaling the check node messages \
+                 sent through these edges can improve the performance of belief propagation.",
+                self.short_cycles[0].edges
+            ));
+        }
+        if self.punctured > 0 {
+            suggestions.push(format!(
+                "The code punctures {} bits, which must be given to the decoder as erasures \
+                 (LLR equal to zero).",
+                self.punctured
+            ));
+        }
+        if self.encoding.is_none() {
+            suggestions.push(
+                "The last columns of the parity check matrix are not invertible. \
+                 Use the erasure encoder to encode this code."
+                    .to_string(),
+            );
+        }
+        if self.k != h.num_cols() - h.num_rows() {
+            suggestions.push(
+                "The parity check matrix does not have full rank, so it contains \
+                 redundant parity checks."
+                    .to_string(),
+            );
+        }
+        suggestions
+    }
+
+    /// Writes the report in Markdown format.
+    pub fn write_markdown<W: Write>(&self, w: &mut W) -> Result {
+        writeln!(w, "# {}", self.name)?;
+        writeln!(w)?;
+        writeln!(w, "## Parameters")?;
+        writeln!(w)?;
+        writeln!(w, "| Parameter | Value |")?;
+        writeln!(w, "|---|---|")?;
+        for (parameter, value) in self.parameters() {
+            writeln!(w, "| {parameter} | {value} |")?;
+        }
+        for (title, degrees) in [
+            ("Variable node degrees", &self.var_degrees),
+            ("Check node degrees", &self.check_degrees),
+        ] {
+            writeln!(w)?;
+            writeln!(w, "## {title}")?;
+            writeln!(w)?;
+            writeln!(w, "| Degree | Nodes | Fraction of edges |")?;
+            writeln!(w, "|---|---|---|")?;
+            for d in degrees {
+                writeln!(w, "| {} | {} | {:.4} |", d.degree, d.nodes, d.edge_fraction)?;
+            }
+        }
+        writeln!(w)?;
+        writeln!(w, "## Short cycles")?;
+        writeln!(w)?;
+        writeln!(w, "| Cycle length | Edges in cycles |")?;
+        writeln!(w, "|---|---|")?;
+        for c in &self.short_cycles {
+            writeln!(w, "| ≤ {} | {} |", c.max_length, c.edges)?;
+        }
+        writeln!(w)?;
+        writeln!(w, "## Suggested decoder settings")?;
+        writeln!(w)?;
+        for s in &self.suggestions {
+            writeln!(w, "- {s}")?;
+        }
+        if !self.ber.is_empty() {
+            writeln!(w)?;
+            writeln!(w, "## BER estimate")?;
+            writeln!(w)?;
+            writeln!(
+                w,
+                "| Eb/N0 (dB) | Frames | BER | BER 95% CI | FER | FER 95% CI |"
+            )?;
+            writeln!(w, "|---|---|---|---|---|---|")?;
+            for b in &self.ber {
+                writeln!(
+                    w,
+                    "| {:.2} | {} | {:.3e} | {} | {:.3e} | {} |",
+                    b.ebn0_db,
+                    b.frames,
+                    b.ber,
+                    format_interval(b.ber_confidence_interval),
+                    b.fer,
+                    format_interval(b.fer_confidence_interval)
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the report in HTML format.
+    ///
+    /// The report is written as a complete HTML document.
+    pub fn write_html<W: Write>(&self, w: &mut W) -> Result {
+        let name = html_escape(&self.name);
+        writeln!(w, "<!DOCTYPE html>")?;
+        writeln!(w, "<html>")?;
+        writeln!(w, "<head>")?;
+        writeln!(w, "<meta charset=\"utf-8\">")?;
+        writeln!(w, "<title>{name}</title>")?;
+        writeln!(w, "</head>")?;
+        writeln!(w, "<body>")?;
+        writeln!(w, "<h1>{name}</h1>")?;
+        writeln!(w, "<h2>Parameters</h2>")?;
+        write_html_table(
+            w,
+            &["Parameter", "Value"],
+            self.parameters()
+                .into_iter()
+                .map(|(p, v)| vec![p.to_string(), v]),
+        )?;
+        for (title, degrees) in [
+            ("Variable node degrees", &self.var_degrees),
+            ("Check node degrees", &self.check_degrees),
+        ] {
+            writeln!(w, "<h2>{title}</h2>")?;
+            write_html_table(
+                w,
+                &["Degree", "Nodes", "Fraction of edges"],
+                degrees.iter().map(|d| {
+                    vec![
+                        d.degree.to_string(),
+                        d.nodes.to_string(),
+                        format!("{:.4}", d.edge_fraction),
+                    ]
+                }),
+            )?;
+        }
+        writeln!(w, "<h2>Short cycles</h2>")?;
+        write_html_table(
+            w,
+            &["Cycle length", "Edges in cycles"],
+            self.short_cycles
+                .iter()
+                .map(|c| vec![format!("&le; {}", c.max_length), c.edges.to_string()]),
+        )?;
+        writeln!(w, "<h2>Suggested decoder settings</h2>")?;
+        writeln!(w, "<ul>")?;
+        for s in &self.suggestions {
+            writeln!(w, "<li>{}</li>", html_escape(s))?;
+        }
+        writeln!(w, "</ul>")?;
+        if !self.ber.is_empty() {
+            writeln!(w, "<h2>BER estimate</h2>")?;
+            write_html_table(
+                w,
+                &[
+                    "Eb/N0 (dB)",
+                    "Frames",
+                    "BER",
+                    "BER 95% CI",
+                    "FER",
+                    "FER 95% CI",
+                ],
+                self.ber.iter().map(|b| {
+                    vec![
+                        format!("{:.2}", b.ebn0_db),
+                        b.frames.to_string(),
+                        format!("{:.3e}", b.ber),
+                        format_interval(b.ber_confidence_interval),
+                        format!("{:.3e}", b.fer),
+                        format_interval(b.fer_confidence_interval),
+                    ]
+                }),
+            )?;
+        }
+        writeln!(w, "</body>")?;
+        writeln!(w, "</html>")
+    }
+
+    fn parameters(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("n", self.n.to_string()),
+            ("k", self.k.to_string()),
+            ("Parity checks", self.m.to_string()),
+            ("Rate", format!("{:.4}", self.rate)),
+            ("Punctured bits", self.punctured.to_string()),
+            ("Edges", self.edges.to_string()),
+            (
+                "Girth",
+                match self.girth {
+                    Some(g) => g.to_string(),
+                    None => format!("> {}", self.max_cycle_length),
+                },
+            ),
+            (
+                "Encoding",
+                match self.encoding {
+                    Some(method) => method.to_string(),
+                    None => "not systematically encodable".to_string(),
+                },
+            ),
+        ]
+    }
+}
+
+// Computes the degree distribution of a list of node degrees, sorted by degree.
+pub(crate) fn degree_distribution(degrees: impl Iterator<Item = usize>) -> Vec<DegreeCount> {
+    let mut counts = std::collections::BTreeMap::new();
+    for d in degrees {
+        *counts.entry(d).or_insert(0) += 1;
+    }
+    let edges = counts.iter().map(|(d, n)| d * n).sum::<usize>();
+    counts
+        .into_iter()
+        .map(|(degree, nodes)| DegreeCount {
+            degree,
+            nodes,
+            edge_fraction: (degree * nodes) as f64 / edges as f64,
+        })
+        .collect()
+}
+
+fn format_interval((low, high): (f64, f64)) -> String {
+    format!("[{low:.3e}, {high:.3e}]")
+}
+
+fn write_html_table<W: Write>(
+    w: &mut W,
+    header: &[&str],
+    rows: impl Iterator<Item = Vec<String>>,
+) -> Result {
+    writeln!(w, "<table>")?;
+    write!(w, "<tr>")?;
+    for h in header {
+        write!(w, "<th>{h}</th>")?;
+    }
+    writeln!(w, "</tr>")?;
+    for row in rows {
+        write!(w, "<tr>")?;
+        for x in row {
+            write!(w, "<td>{x}</td>")?;
+        }
+        writeln!(w, "</tr>")?;
+    }
+    writeln!(w, "</table>")
+}
+
+fn html_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::codes::{reference::ReferenceCode, MatrixCode};
+
+    #[test]
+    fn hamming() {
+        let report = CodeReport::new(&ReferenceCode::Hamming7_4, 6);
+        assert_eq!(report.m, 3);
+        assert_eq!(report.edges, 12);
+        assert_eq!(
+            report.check_degrees,
+            vec![DegreeCount {
+                degree: 4,
+                nodes: 3,
+                edge_fraction: 1.0
+            }]
+        );
+        assert_eq!(report.var_degrees.len(), 3);
+        assert_eq!(report.var_degrees[0].degree, 1);
+        assert_eq!(report.var_degrees[0].nodes, 3);
+        assert_eq!(report.short_cycles.len(), 2);
+        let mut html = String::new();
+        report.write_html(&mut html).unwrap();
+        assert!(html.contains("<h1>Hamming (7, 4)</h1>"));
+        assert!(html.ends_with("</html>\n"));
+    }
+
+    #[test]
+    fn not_encodable() {
+        let mut h = SparseMatrix::new(2, 4);
+        h.insert_row(0, [0, 2, 3].iter());
+        h.insert_row(1, [1, 2, 3].iter());
+        h.insert(1, 0);
+        let report = CodeReport::new(&MatrixCode::new("test", h), 6);
+        assert_eq!(report.girth, Some(4));
+        assert_eq!(report.encoding, None);
+        assert_eq!(
+            report.short_cycles,
+            vec![
+                ShortCycles {
+                    max_length: 4,
+                    edges: 6
+                },
+                ShortCycles {
+                    max_length: 6,
+                    edges: 6
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn no_cycles() {
+        let mut h = SparseMatrix::new(2, 4);
+        h.insert_row(0, [0, 2].iter());
+        h.insert_row(1, [1, 3].iter());
+        let report = CodeReport::new(&MatrixCode::new("test & <code>", h), 8);
+        assert_eq!(report.girth, None);
+        assert_eq!(report.encoding, Some(EncodingMethod::DenseGenerator));
+        let mut markdown = String::new();
+        report.write_markdown(&mut markdown).unwrap();
+        assert!(markdown.contains("| Girth | > 8 |"));
+        let mut html = String::new();
+        report.write_html(&mut html).unwrap();
+        assert!(html.contains("<h1>test &amp; &lt;code&gt;</h1>"));
+    }
+}
@@ -4,7 +4,21 @@
 
 pub mod ber;
 pub mod channel;
+pub mod distributed;
+pub mod error_patterns;
 pub mod factory;
+pub mod frame_errors;
 pub mod interleaving;
+pub mod iterations;
+pub mod known_bits;
+pub mod llr_transform;
+pub mod mixture;
 pub mod modulation;
+pub mod ordering;
 pub mod puncturing;
+pub mod quantization_sweep;
+pub mod reference_curves;
+pub mod scrambling;
+pub mod shortening;
+pub mod snr_profile;
+pub mod training_data;
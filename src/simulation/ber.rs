@@ -3,26 +3,46 @@
 //! This module contains utilities for BER simulation.
 
 use super::{
-    channel::{AwgnChannel, Channel},
+    channel::{self, Channel, ChannelModel, Impairments, ModelChannel},
+    error_patterns::{ErrorPattern, ErrorPatternCapture},
     factory::Ber,
+    frame_errors::{ber_confidence_interval, fer_confidence_interval, FrameErrorPolicy},
     interleaving::Interleaver,
-    modulation::{Demodulator, Modulation, Modulator},
+    iterations::IterationSchedule,
+    known_bits::KnownBits,
+    llr_transform::LlrTransform,
+    modulation::{Bpsk, Demodulator, HardDecisionDemodulator, Modulation, Modulator},
+    ordering::BitOrdering,
     puncturing::Puncturer,
+    scrambling::Scrambler,
+    shortening::{self, Shortening},
+    snr_profile::SnrProfile,
+    training_data::TrainingDataSink,
 };
 use crate::{
+    cancel::CancellationToken,
     decoder::{
+        batch::LdpcBatchDecoder,
         factory::{DecoderFactory, DecoderImplementation},
+        graph::CompiledGraph,
         LdpcDecoder,
     },
     encoder::{Encoder, Error},
     gf2::GF2,
+    rand::{stream_rng, Rng as StreamRng},
     sparse::SparseMatrix,
 };
 use ndarray::Array1;
 use num_traits::{One, Zero};
 use rand::{distributions::Standard, Rng};
 use std::{
-    sync::mpsc::{self, Receiver, Sender, SyncSender, TryRecvError},
+    any::TypeId,
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc::{self, Receiver, Sender, SyncSender, TryRecvError},
+        Arc,
+    },
     time::{Duration, Instant},
 };
 
@@ -32,37 +52,116 @@ use std::{
 #[derive(Debug)]
 pub struct BerTest<Mod: Modulation, Dec = DecoderImplementation> {
     decoder_implementation: Dec,
-    h: SparseMatrix,
+    graph: Arc<CompiledGraph>,
     num_workers: usize,
     k: usize,
+    k_unknown: usize,
     n: usize,
     n_cw: usize,
     rate: f64,
     encoder: Encoder,
-    puncturer: Option<Puncturer>,
-    interleaver: Option<Interleaver>,
+    ordering: BitOrdering,
+    known_bits: Option<KnownBits>,
+    shortening: Option<Shortening>,
+    shortened_positions: Option<Arc<[usize]>>,
+    llr_transform: Option<LlrTransform>,
     modulator: Mod::Modulator,
+    channel_model: ChannelModel,
+    impairments: Impairments,
+    snr_profile: Option<Arc<SnrProfile>>,
+    snr_bin_width_db: f32,
     ebn0s_db: Vec<f32>,
     statistics: Vec<Statistics>,
     bch_max_errors: u64,
     max_iterations: usize,
+    iteration_schedule: IterationSchedule,
     max_frame_errors: u64,
+    frame_error_policy: FrameErrorPolicy,
+    max_frames: Option<u64>,
+    warm_up_frames: u64,
+    batch_decoding: bool,
+    llr_magnitude_tracking: bool,
+    training_data: Option<Arc<TrainingDataSink>>,
+    error_patterns: Option<Arc<ErrorPatternCapture>>,
+    seed: u64,
+    cancellation: Option<CancellationToken>,
     reporter: Option<Reporter>,
     last_reported: Instant,
+    metadata: Metadata,
 }
 
 #[derive(Debug)]
 struct Worker<Mod: Modulation> {
+    index: usize,
     terminate_rx: Receiver<()>,
     results_tx: Sender<WorkerResult>,
     k: usize,
     encoder: Encoder,
-    puncturer: Option<Puncturer>,
-    interleaver: Option<Interleaver>,
+    ordering: BitOrdering,
+    known_bits: Option<KnownBits>,
+    shortened_positions: Option<Arc<[usize]>>,
+    llr_transform: Option<LlrTransform>,
     modulator: Mod::Modulator,
-    channel: AwgnChannel,
+    channel: ModelChannel,
+    impairments: Impairments,
     demodulator: Mod::Demodulator,
+    hard_decision: Option<HardDecisionDemodulator>,
     decoder: Box<dyn LdpcDecoder>,
+    decoded: Vec<u8>,
+    batch: Option<BatchDecoding>,
+    ebn0_db: f32,
+    max_iterations: usize,
+    message_rng: StreamRng,
+    noise_rng: StreamRng,
+    training_data: Option<TrainingDataCollector>,
+    error_patterns: Option<Arc<ErrorPatternCapture>>,
+    profile: Option<ProfileFrames>,
+}
+
+// Batch decoder of a worker and its buffers.
+#[derive(Debug)]
+struct BatchDecoding {
+    decoder: Box<dyn LdpcBatchDecoder>,
+    llrs: Vec<f32>,
+    decoded: Vec<u8>,
+    // Frames that remain to be simulated by all the workers when there is a
+    // maximum number of frames
+    remaining_frames: Option<Arc<AtomicU64>>,
+}
+
+// Frame transmitted through the channel, ready for decoding.
+struct Frame {
+    message: Vec<u8>,
+    codeword: Array1<GF2>,
+    llrs: Vec<f64>,
+}
+
+// Sampling of the frames simulated by a worker for the training data sink.
+#[derive(Debug)]
+struct TrainingDataCollector {
+    sink: Arc<TrainingDataSink>,
+    rng: StreamRng,
+    // Global maximum number of iterations, which gives the size of the
+    // records
+    max_iterations: usize,
+}
+
+// Frames of an SNR profile simulated by a worker.
+//
+// The worker simulates the frames `next`, `next + stride`, etc. Each frame
+// uses its own random streams, so the results do not depend on the number of
+// workers.
+#[derive(Debug)]
+struct ProfileFrames {
+    profile: Arc<SnrProfile>,
+    bin_width_db: f32,
+    next: u64,
+    stride: u64,
+    seed: u64,
+    channel_model: ChannelModel,
+    // Es/N0 divided by Eb/N0
+    esn0_per_ebn0: f64,
+    iteration_schedule: IterationSchedule,
     max_iterations: usize,
 }
 
@@ -71,7 +170,13 @@ struct WorkerResultOk {
     bit_errors: u64,
     frame_error: bool,
     false_decode: bool,
+    false_decode_distance: Option<usize>,
     iterations: u64,
+    max_llr_magnitude: Option<f64>,
+    worker: usize,
+    busy: Duration,
+    // Eb/N0 bin of the frame when an SNR profile is used
+    snr_bin: Option<i64>,
 }
 
 type WorkerResult = Result<WorkerResultOk, ()>;
@@ -80,8 +185,14 @@ type WorkerResult = Result<WorkerResultOk, ()>;
 struct CurrentStatistics {
     num_frames: u64,
     false_decodes: u64,
+    false_decode_distances: BTreeMap<usize, u64>,
     total_iterations: u64,
+    max_llr_magnitude: Option<f64>,
+    max_iterations: usize,
+    warm_up_frames: u64,
+    // time at which the warm-up finished
     start: Instant,
+    worker_busy: Vec<Duration>,
     ldpc: CurrentCodeStatistics,
     bch: Option<CurrentCodeStatistics>,
 }
@@ -98,6 +209,7 @@ struct CurrentCodeStatistics {
 /// This structure contains the statistics for a single Eb/N0 case in a BER
 /// test.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Statistics {
     /// Eb/N0 in dB units.
     pub ebn0_db: f32,
@@ -110,16 +222,125 @@ pub struct Statistics {
     /// These are frames for which the decoder has converged to a valid
     /// codeword, but the codeword is different from the transmitted codeword.
     pub false_decodes: u64,
+    /// Distribution of the distances of the false decodes.
+    ///
+    /// This maps the Hamming distance between the decoded codeword and the
+    /// transmitted codeword to the number of false decodes with that distance,
+    /// giving the profile of the undetected errors. Its values add up to
+    /// `false_decodes`.
+    pub false_decode_distances: BTreeMap<usize, u64>,
     /// Average iterations per frame.
     pub average_iterations: f64,
     /// Elapsed time for this test case.
+    ///
+    /// This does not include the warm-up period.
     pub elapsed: Duration,
     /// Throughput in Mbps (referred to information bits).
+    ///
+    /// The warm-up frames are not taken into account.
     pub throughput_mbps: f64,
+    /// Number of warm-up frames.
+    ///
+    /// These frames are excluded from the `elapsed` time and the throughput,
+    /// but they are counted in the rest of the statistics. This is smaller
+    /// than the number of warm-up frames configured in the test if the test
+    /// case finished during the warm-up.
+    pub warm_up_frames: u64,
+    /// Utilization of each worker thread.
+    ///
+    /// This is the fraction of the `elapsed` time that each worker thread has
+    /// spent simulating frames. Values significantly smaller than one indicate
+    /// that the workers did not get enough CPU time or that the load is not
+    /// balanced. This is empty if the utilization has not been measured.
+    pub worker_utilization: Vec<f64>,
+    /// Maximum LLR magnitude.
+    ///
+    /// This is the maximum magnitude of the internal LLRs of the decoder over
+    /// all the frames, in the internal units of the decoder arithmetic. It is
+    /// `None` if LLR magnitude tracking is disabled or not supported by the
+    /// decoder.
+    pub max_llr_magnitude: Option<f64>,
+    /// Maximum number of iterations per frame used for this test case.
+    ///
+    /// This depends on the Eb/N0 if an [`IterationSchedule`] is used.
+    pub max_iterations: usize,
     /// Statistics of the inner LDPC decoder.
     pub ldpc: CodeStatistics,
     /// Statistics of the combined inner LDPC decoder plus outer BCH decoder (if it exists).
     pub bch: Option<CodeStatistics>,
+    /// Metadata of the BER test that produced these statistics.
+    ///
+    /// This is `None` if the statistics do not correspond to a single parity
+    /// check matrix, as in the aggregate statistics of a
+    /// [`MixtureTest`](super::mixture::MixtureTest).
+    pub metadata: Option<Metadata>,
+}
+
+/// BER test metadata.
+///
+/// This structure contains information that identifies how the results of a
+/// BER test were produced, so that they can be traced back to the exact parity
+/// check matrix, decoder and version of ldpc-toolbox that were used.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Metadata {
+    /// Version of ldpc-toolbox.
+    pub version: String,
+    /// Output of `git describe` for the ldpc-toolbox source tree.
+    ///
+    /// This is `None` if the crate was not built from its own git checkout.
+    pub git_describe: Option<String>,
+    /// Fingerprint of the parity check matrix.
+    ///
+    /// See [`SparseMatrix::fingerprint`].
+    pub h_fingerprint: u64,
+    /// Decoder implementation.
+    pub decoder: String,
+    /// Modulation.
+    pub modulation: String,
+    /// Channel model.
+    ///
+    /// See [`ChannelModel`].
+    pub channel: String,
+    /// Master seed of the random streams.
+    pub seed: u64,
+}
+
+impl Metadata {
+    pub(crate) fn new<Mod: Modulation, Dec: DecoderFactory>(
+        h: &SparseMatrix,
+        decoder: &Dec,
+        seed: u64,
+    ) -> Metadata {
+        Metadata {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            git_describe: option_env!("LDPC_TOOLBOX_GIT_DESCRIBE").map(String::from),
+            h_fingerprint: h.fingerprint(),
+            decoder: decoder.to_string(),
+            modulation: Mod::NAME.to_string(),
+            channel: ChannelModel::Awgn.to_string(),
+            seed,
+        }
+    }
+}
+
+impl std::fmt::Display for Metadata {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "ldpc-toolbox version: {}", self.version)?;
+        if let Some(git_describe) = &self.git_describe {
+            write!(f, " ({git_describe})")?;
+        }
+        writeln!(f)?;
+        writeln!(
+            f,
+            "Parity check matrix fingerprint: {:016x}",
+            self.h_fingerprint
+        )?;
+        writeln!(f, "Decoder: {}", self.decoder)?;
+        writeln!(f, "Modulation: {}", self.modulation)?;
+        writeln!(f, "Channel: {}", self.channel)?;
+        write!(f, "Seed: {}", self.seed)
+    }
 }
 
 /// BER test statistics for a particular code.
@@ -129,6 +350,7 @@ pub struct Statistics {
 /// BCH. `Statistics` has two instances of this structure: one for LDPC-only and
 /// another for LDPC plus BCH (which is only present when BCH is enabled).
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CodeStatistics {
     /// Number of bit errors.
     pub bit_errors: u64,
@@ -140,6 +362,14 @@ pub struct CodeStatistics {
     pub ber: f64,
     /// Frame error rate.
     pub fer: f64,
+    /// 95% confidence interval for the bit error rate.
+    ///
+    /// See [`ber_confidence_interval`].
+    pub ber_confidence_interval: (f64, f64),
+    /// 95% confidence interval for the frame error rate.
+    ///
+    /// See [`fer_confidence_interval`].
+    pub fer_confidence_interval: (f64, f64),
     /// Average iterations per correct frame.
     pub average_iterations_correct: f64,
 }
@@ -148,19 +378,16 @@ pub struct CodeStatistics {
 ///
 /// A reporter can optionally be supplied to the BER test on contruction in
 /// order to receive periodic messages reporting the test progress.
-#[derive(Debug, Clone)]
-pub struct Reporter {
-    /// Sender element of a channel used to send the reports.
-    pub tx: Sender<Report>,
-    /// Reporting interval.
-    pub interval: Duration,
-}
+pub type Reporter = crate::progress::Reporter<Report>;
 
 /// BER test progress report.
 ///
 /// Progress reports are optionally sent out periodically by the BER test. These
 /// can be used to update a UI to show the progress.
 #[derive(Debug, Clone, PartialEq)]
+// Reports are sent at most a few times per second, so their size is not
+// important.
+#[allow(clippy::large_enum_variant)]
 pub enum Report {
     /// Statistics for the current Eb/N0 being tested.
     ///
@@ -182,7 +409,8 @@ macro_rules! report {
                     .send(Report::Statistics(Statistics::from_current(
                         &$current_statistics,
                         $ebn0_db,
-                        $self.k,
+                        $self.k_unknown,
+                        &$self.metadata,
                     )))
                     .unwrap();
                 $self.last_reported = now;
@@ -195,19 +423,24 @@ impl<Mod: Modulation, Dec: DecoderFactory> BerTest<Mod, Dec> {
     /// Creates a new BER test.
     ///
     /// The parameters required to define the test are the parity check matrix
-    /// `h`, an optional puncturing pattern (which uses the semantics of
-    /// [`Puncturer`]), an optional interleaving pattern, the maximum number of
-    /// frame errors at which to stop the simulation for each Eb/N0, the maximum
-    /// number of iterations of the LDPC decoder, a list of Eb/N0's in dB units,
-    /// and an optional [`Reporter`] to send messages about the test progress.
+    /// `h`, an optional [`Puncturer`], an optional interleaving pattern, the
+    /// maximum number of frame errors at which to stop the simulation for each
+    /// Eb/N0, the maximum number of iterations of the LDPC decoder, a list of
+    /// Eb/N0's in dB units, an optional [`Reporter`] to send messages about the
+    /// test progress, and the maximum number of bit errors that the BCH decoder
+    /// can correct.
+    ///
+    /// The parity check matrix can be given either as a [`SparseMatrix`] or as
+    /// an `Arc<SparseMatrix>`. Its Tanner graph is compiled once and shared by
+    /// the decoders of all the worker threads.
     ///
     /// This function only defines the BER test. To run it it is necessary to
     /// call the [`BerTest::run`] method.
     #[allow(clippy::too_many_arguments)]
     pub fn new(
-        h: SparseMatrix,
+        h: impl Into<Arc<SparseMatrix>>,
         decoder_implementation: Dec,
-        puncturing_pattern: Option<&[bool]>,
+        puncturer: Option<Puncturer>,
         interleaving_columns: Option<isize>,
         max_frame_errors: u64,
         max_iterations: usize,
@@ -215,45 +448,393 @@ impl<Mod: Modulation, Dec: DecoderFactory> BerTest<Mod, Dec> {
         reporter: Option<Reporter>,
         bch_max_errors: u64,
     ) -> Result<BerTest<Mod, Dec>, Error> {
+        let h = h.into();
         let k = h.num_cols() - h.num_rows();
         let n_cw = h.num_cols();
-        let puncturer = puncturing_pattern.map(Puncturer::new);
         let interleaver = interleaving_columns.map(|n| Interleaver::new(n.unsigned_abs(), n < 0));
         let puncturer_rate = if let Some(p) = puncturer.as_ref() {
             p.rate()
         } else {
             1.0
         };
+        let ordering = BitOrdering::new(puncturer, None, interleaver);
         let n = (n_cw as f64 / puncturer_rate).round() as usize;
-        let rate = k as f64 / n as f64;
+        let seed = rand::random();
+        let metadata = Metadata::new::<Mod, Dec>(&h, &decoder_implementation, seed);
         Ok(BerTest {
             decoder_implementation,
             num_workers: num_cpus::get(),
             k,
+            k_unknown: k,
             n,
             n_cw,
-            rate,
+            rate: k as f64 / n as f64,
             encoder: Encoder::from_h(&h)?,
-            h,
-            puncturer,
-            interleaver,
+            graph: Arc::new(CompiledGraph::new(h)),
+            ordering,
+            known_bits: None,
+            shortening: None,
+            shortened_positions: None,
+            llr_transform: None,
             modulator: Mod::Modulator::default(),
+            channel_model: ChannelModel::Awgn,
+            impairments: Impairments::default(),
+            snr_profile: None,
+            snr_bin_width_db: 0.0,
             ebn0s_db: ebn0s_db.to_owned(),
             statistics: Vec::with_capacity(ebn0s_db.len()),
             bch_max_errors,
             max_iterations,
+            iteration_schedule: IterationSchedule::Fixed,
             max_frame_errors,
+            frame_error_policy: FrameErrorPolicy::Fixed,
+            max_frames: None,
+            warm_up_frames: 0,
+            batch_decoding: false,
+            llr_magnitude_tracking: false,
+            training_data: None,
+            error_patterns: None,
+            seed,
+            cancellation: None,
             reporter,
             last_reported: Instant::now(),
+            metadata,
         })
     }
 
+    /// Sets the master seed of the BER test.
+    ///
+    /// The random messages, the channel noise and the seeds of the decoders
+    /// (see [`DecoderFactory::build_seeded_decoder`]) are drawn from separate
+    /// random streams derived from the master seed (see [`stream_rng`]), so
+    /// that the noise realizations do not depend on the features that are
+    /// enabled. By default, a random seed is chosen. The seed is included in
+    /// the [`Metadata`]. Since the frames are simulated in parallel by several
+    /// threads, the results are only reproducible if the number of threads is
+    /// the same.
+    pub fn with_seed(mut self, seed: u64) -> BerTest<Mod, Dec> {
+        self.seed = seed;
+        self.metadata.seed = seed;
+        self
+    }
+
+    /// Sets the known bits of the BER test.
+    ///
+    /// The known bits are set to their values in each simulated message, given
+    /// saturated LLRs before decoding, and excluded from the error
+    /// counting. They do not count as information bits when computing the code
+    /// rate. By default there are no known bits.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if some of the known bits are not in the
+    /// information part of the codeword or if some of them are shortened.
+    pub fn with_known_bits(mut self, known_bits: KnownBits) -> BerTest<Mod, Dec> {
+        known_bits
+            .check_range(self.k)
+            .expect("known bits outside of the information part of the codeword");
+        self.known_bits = Some(known_bits);
+        self.update_known_bits();
+        self
+    }
+
+    /// Sets the shortening used in the BER test.
+    ///
+    /// The shortened bits are treated as known bits with value zero, and they
+    /// are also removed from the transmitted frame, so they reduce the frame
+    /// size (see [`shortening`](super::shortening)). By default there is no
+    /// shortening.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if some of the shortened bits are not in the
+    /// information part of the codeword or are known bits, or if the codeword
+    /// length is not compatible with the puncturing pattern or the
+    /// interleaver.
+    pub fn with_shortening(mut self, shortening: Shortening) -> BerTest<Mod, Dec> {
+        shortening
+            .check_range(self.k)
+            .expect("shortened bits outside of the information part of the codeword");
+        let positions = Arc::<[usize]>::from(
+            shortening
+                .transmission_positions(&self.ordering, self.n_cw)
+                .expect("codeword length not compatible with the bit ordering"),
+        );
+        let n_unshortened = self.n + self.shortened_positions.as_ref().map_or(0, |p| p.len());
+        self.n = n_unshortened - positions.len();
+        self.shortened_positions = Some(positions);
+        self.shortening = Some(shortening);
+        self.update_known_bits();
+        self
+    }
+
+    // Returns the known bits of each frame, which include the shortened bits.
+    fn frame_known_bits(&self) -> Option<KnownBits> {
+        match (&self.known_bits, &self.shortening) {
+            (Some(known), Some(shortening)) => Some(
+                KnownBits::new(
+                    &known
+                        .iter()
+                        .chain(shortening.positions().iter().map(|&p| (p, 0)))
+                        .collect::<Vec<_>>(),
+                )
+                .expect("bits both known and shortened"),
+            ),
+            (known, shortening) => known
+                .clone()
+                .or_else(|| shortening.as_ref().map(|s| s.known_bits())),
+        }
+    }
+
+    // Updates the number of unknown information bits and the rate.
+    fn update_known_bits(&mut self) {
+        self.k_unknown = self.k - self.frame_known_bits().map_or(0, |known| known.len());
+        self.rate = self.k_unknown as f64 / self.n as f64;
+    }
+
+    /// Sets a transform of the LLRs used in the BER test.
+    ///
+    /// The [`LlrTransform`] is applied to the LLRs produced by the
+    /// demodulator, before converting them to codeword order. By default the
+    /// LLRs are not transformed.
+    pub fn with_llr_transform(mut self, transform: LlrTransform) -> BerTest<Mod, Dec> {
+        self.llr_transform = Some(transform);
+        self
+    }
+
+    /// Sets the scrambler used in the BER test.
+    ///
+    /// The [`Scrambler`] is applied to the codeword after puncturing and
+    /// before interleaving (see [`BitOrdering`]). By default there is no
+    /// scrambler.
+    pub fn with_scrambler(mut self, scrambler: Scrambler) -> BerTest<Mod, Dec> {
+        self.ordering = self.ordering.with_scrambler(scrambler);
+        self
+    }
+
+    /// Sets the modulator used in the BER test.
+    ///
+    /// By default, the BER test uses the [`Default`] modulator. This function
+    /// can be used to give a modulator with parameters, such as the ring ratio
+    /// of the [`Apsk16Modulator`](super::modulation::Apsk16Modulator). The
+    /// demodulator is obtained from the modulator with
+    /// [`Modulation::demodulator`].
+    pub fn with_modulator(mut self, modulator: Mod::Modulator) -> BerTest<Mod, Dec> {
+        self.modulator = modulator;
+        self
+    }
+
+    /// Sets the channel model used in the BER test.
+    ///
+    /// By default, the BER test uses an AWGN channel. With a fading channel,
+    /// the demodulator uses the fading gains as perfect channel state
+    /// information (see [`Demodulator::demodulate_faded`]). With a binary
+    /// symmetric channel, the LLRs given by the demodulator are replaced by
+    /// those of their hard decisions (see [`HardDecisionDemodulator`]).
+    ///
+    /// # Errors
+    ///
+    /// This function returns an error if the channel is a binary symmetric
+    /// channel and the modulation is not BPSK, or if its crossover probability
+    /// is not between 0 and 0.5.
+    pub fn with_channel(
+        mut self,
+        channel_model: ChannelModel,
+    ) -> Result<BerTest<Mod, Dec>, channel::Error> {
+        if let ChannelModel::Bsc(crossover) = channel_model {
+            if TypeId::of::<Mod>() != TypeId::of::<Bpsk>() {
+                return Err(channel::Error::BscModulation);
+            }
+            if let Some(p) = crossover.filter(|p| !(0.0..=0.5).contains(p)) {
+                return Err(channel::Error::InvalidCrossover(p));
+            }
+        }
+        self.channel_model = channel_model;
+        self.update_channel_metadata();
+        Ok(self)
+    }
+
+    /// Sets the receiver front-end impairments used in the BER test.
+    ///
+    /// The impairments are applied to the symbols after the channel, and the
+    /// demodulator does not compensate them, so they can be used to simulate
+    /// the sensitivity of the decoder to an imperfect normalization of the
+    /// symbols (see [`Impairments`]). By default there are no impairments.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the gain jitter is negative or if some of the
+    /// parameters is not finite.
+    pub fn with_impairments(mut self, impairments: Impairments) -> BerTest<Mod, Dec> {
+        assert!(impairments.amplitude.is_finite() && impairments.dc_offset.is_finite());
+        assert!(impairments.gain_jitter.is_finite() && impairments.gain_jitter >= 0.0);
+        self.impairments = impairments;
+        self.update_channel_metadata();
+        self
+    }
+
+    /// Sets a time-varying SNR profile for the BER test.
+    ///
+    /// Instead of simulating each of the Eb/N0's given in [`BerTest::new`], the
+    /// test simulates all the frames of the [`SnrProfile`] in a single run,
+    /// using for each frame the Eb/N0 given by the profile. The statistics are
+    /// reported for each Eb/N0 bin of width `bin_width_db` dB (see
+    /// [`SnrProfile::bin`]), in increasing order of Eb/N0, and the
+    /// [`Statistics::ebn0_db`] is the center of the bin. The maximum number of
+    /// iterations of each frame is obtained from the center of its bin with the
+    /// iteration schedule, without using the statistics of other bins.
+    ///
+    /// With a profile, the maximum number of frame errors and frames, the
+    /// frame error policy and the warm-up frames are not used, and the
+    /// statistics are only reported when the profile has finished. Each frame
+    /// uses its own random streams, so the results do not depend on the number
+    /// of threads.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the bin width is not positive and finite.
+    pub fn with_snr_profile(mut self, profile: SnrProfile, bin_width_db: f32) -> BerTest<Mod, Dec> {
+        assert!(bin_width_db > 0.0 && bin_width_db.is_finite());
+        self.snr_profile = Some(Arc::new(profile));
+        self.snr_bin_width_db = bin_width_db;
+        self.update_channel_metadata();
+        self
+    }
+
+    fn update_channel_metadata(&mut self) {
+        self.metadata.channel = if self.impairments.is_ideal() {
+            self.channel_model.to_string()
+        } else {
+            format!("{} ({})", self.channel_model, self.impairments)
+        };
+        if let Some(profile) = &self.snr_profile {
+            self.metadata.channel = format!(
+                "{}, Eb/N0 profile {profile} (bins of {} dB)",
+                self.metadata.channel, self.snr_bin_width_db
+            );
+        }
+    }
+
+    /// Sets the number of warm-up frames per Eb/N0.
+    ///
+    /// The first `frames` frames simulated for each Eb/N0 are excluded from
+    /// the elapsed time and the throughput in the [`Statistics`], so that
+    /// these are not affected by the start up of the worker threads in short
+    /// tests. The warm-up frames are still counted in the error statistics.
+    /// By default there are no warm-up frames.
+    pub fn with_warm_up_frames(mut self, frames: u64) -> BerTest<Mod, Dec> {
+        self.warm_up_frames = frames;
+        self
+    }
+
+    /// Sets the maximum number of frames per Eb/N0.
+    ///
+    /// The simulation of each Eb/N0 stops when `max_frames` frames have been
+    /// simulated, even if the maximum number of frame errors has not been
+    /// reached. By default there is no maximum number of frames.
+    pub fn with_max_frames(mut self, max_frames: u64) -> BerTest<Mod, Dec> {
+        self.max_frames = Some(max_frames);
+        self
+    }
+
+    /// Sets a cancellation token for the BER test.
+    ///
+    /// If the token is cancelled while the test is running, the test stops as
+    /// soon as possible and [`BerTest::run`] returns the statistics obtained
+    /// so far, including the partial statistics of the Eb/N0 that was being
+    /// simulated.
+    pub fn with_cancellation(mut self, cancellation: CancellationToken) -> BerTest<Mod, Dec> {
+        self.cancellation = Some(cancellation);
+        self
+    }
+
+    /// Sets the iteration schedule.
+    ///
+    /// The maximum number of iterations used for each Eb/N0 is given by the
+    /// schedule, which uses the maximum number of iterations given in
+    /// [`BerTest::new`] as the global maximum (see
+    /// [`IterationSchedule::max_iterations`]). By default,
+    /// [`IterationSchedule::Fixed`] is used.
+    pub fn with_iteration_schedule(mut self, schedule: IterationSchedule) -> BerTest<Mod, Dec> {
+        self.iteration_schedule = schedule;
+        self
+    }
+
+    /// Sets the frame error policy.
+    ///
+    /// The policy determines the number of frame errors to collect for each
+    /// Eb/N0 as a function of the measured FER, using the maximum number of
+    /// frame errors given in [`BerTest::new`] as the global maximum (see
+    /// [`FrameErrorPolicy::frame_errors`]). By default,
+    /// [`FrameErrorPolicy::Fixed`] is used.
+    pub fn with_frame_error_policy(mut self, policy: FrameErrorPolicy) -> BerTest<Mod, Dec> {
+        self.frame_error_policy = policy;
+        self
+    }
+
+    /// Enables or disables batch decoding.
+    ///
+    /// With batch decoding, each worker thread simulates a batch of frames at
+    /// once, decoding them with a single call to the batch decoder given by
+    /// [`DecoderFactory::build_batch_decoder`]. For the decoder
+    /// implementations that decode several codewords in lockstep, such as
+    /// [`MinSumi8`](crate::decoder::batch::MinSumi8), this increases the
+    /// throughput of the simulation. The LLRs are converted to `f32` for the
+    /// batch decoder. The frames and the random streams are the same as
+    /// without batch decoding. If there is a maximum number of frames, the
+    /// last batches are shortened so that no more frames than the maximum are
+    /// simulated.
+    ///
+    /// Batch decoding is not used with an SNR profile, with a training data
+    /// sink, or with LLR magnitude tracking, since these need the decoder to be
+    /// configured for each frame. It is disabled by default.
+    pub fn with_batch_decoding(mut self, enabled: bool) -> BerTest<Mod, Dec> {
+        self.batch_decoding = enabled;
+        self
+    }
+
+    /// Enables or disables tracking of the maximum LLR magnitude.
+    ///
+    /// When enabled, the maximum magnitude of the internal LLRs of the decoder
+    /// is reported in [`Statistics::max_llr_magnitude`] (see
+    /// [`LdpcDecoder::set_llr_magnitude_tracking`]). It is disabled by default.
+    pub fn with_llr_magnitude_tracking(mut self, enabled: bool) -> BerTest<Mod, Dec> {
+        self.llr_magnitude_tracking = enabled;
+        self
+    }
+
+    /// Sets a sink for training data.
+    ///
+    /// The frames sampled by the sink are written to it, together with the
+    /// LLRs of each decoder iteration (see
+    /// [`training_data`](super::training_data)). The sink is flushed when the
+    /// test finishes. The decoder must support LLR tracing (see
+    /// [`LdpcDecoder::set_llr_tracing`]), or the test fails when the first
+    /// frame is sampled.
+    pub fn with_training_data(mut self, sink: Arc<TrainingDataSink>) -> BerTest<Mod, Dec> {
+        self.training_data = Some(sink);
+        self
+    }
+
+    /// Sets a capture for the error patterns of the failed frames.
+    ///
+    /// The error pattern of each frame with bit errors is stored in the
+    /// capture until it is full. The patterns can be analyzed after the test
+    /// with [`cluster_patterns`](super::error_patterns::cluster_patterns).
+    pub fn with_error_patterns(mut self, capture: Arc<ErrorPatternCapture>) -> BerTest<Mod, Dec> {
+        self.error_patterns = Some(capture);
+        self
+    }
+
     /// Runs the BER test.
     ///
     /// This function runs the BER test until completion. It returns a list of
     /// statistics for each Eb/N0, or an error.
     pub fn run(mut self) -> Result<Vec<Statistics>, Box<dyn std::error::Error>> {
-        let ret = self.do_run();
+        let mut ret = self.do_run();
+        if let (Ok(()), Some(sink)) = (&ret, &self.training_data) {
+            ret = sink.flush().map_err(Into::into);
+        }
         if let Some(reporter) = self.reporter.as_ref() {
             reporter.tx.send(Report::Finished).unwrap();
         }
@@ -261,94 +842,255 @@ impl<Mod: Modulation, Dec: DecoderFactory> BerTest<Mod, Dec> {
         Ok(self.statistics)
     }
 
+    fn cancelled(&self) -> bool {
+        self.cancellation.as_ref().is_some_and(|t| t.is_cancelled())
+    }
+
     fn do_run(&mut self) -> Result<(), Box<dyn std::error::Error>> {
         self.last_reported = Instant::now();
-        for &ebn0_db in &self.ebn0s_db {
-            let ebn0 = 10.0_f64.powf(0.1 * f64::from(ebn0_db));
-            let esn0 = self.rate * Mod::BITS_PER_SYMBOL * ebn0;
-            let noise_sigma = (0.5 / esn0).sqrt();
+        if let Some(profile) = self.snr_profile.clone() {
+            return self.do_run_profile(profile);
+        }
+        for (ebn0_index, &ebn0_db) in self.ebn0s_db.iter().enumerate() {
+            let max_iterations = self.iteration_schedule.max_iterations(
+                ebn0_db,
+                self.max_iterations,
+                self.statistics.last(),
+            );
             let (results_tx, results_rx) = mpsc::channel();
-            let workers = std::iter::repeat_with(|| {
-                let (mut worker, terminate_tx) = self.make_worker(noise_sigma, results_tx.clone());
-                let handle = std::thread::spawn(move || worker.work());
-                (handle, terminate_tx)
-            })
-            .take(self.num_workers)
-            .collect::<Vec<_>>();
+            let remaining_frames = self.max_frames.map(|max| Arc::new(AtomicU64::new(max)));
+            let workers = (0..self.num_workers)
+                .map(|worker_index| {
+                    let (mut worker, terminate_tx) = self.make_worker(
+                        worker_index,
+                        ebn0_db,
+                        max_iterations,
+                        results_tx.clone(),
+                        ((ebn0_index as u64) << 32) | worker_index as u64,
+                    );
+                    if let Some(batch) = worker.batch.as_mut() {
+                        batch.remaining_frames = remaining_frames.clone();
+                    }
+                    let handle = std::thread::spawn(move || worker.work());
+                    (handle, terminate_tx)
+                })
+                .collect::<Vec<_>>();
 
-            let mut current_statistics = CurrentStatistics::new(self.bch_max_errors > 0);
-            while current_statistics.errors_for_termination() < self.max_frame_errors {
+            let mut current_statistics = CurrentStatistics::new(
+                self.bch_max_errors > 0,
+                max_iterations,
+                self.warm_up_frames,
+                self.num_workers,
+            );
+            while current_statistics.errors_for_termination()
+                < self
+                    .frame_error_policy
+                    .frame_errors(self.max_frame_errors, current_statistics.fer())
+                && self
+                    .max_frames
+                    .is_none_or(|max| current_statistics.num_frames < max)
+                && !self.cancelled()
+            {
                 match results_rx.recv().unwrap() {
-                    Ok(result) => {
-                        current_statistics.ldpc.bit_errors += result.bit_errors;
-                        current_statistics.ldpc.frame_errors += u64::from(result.frame_error);
-                        current_statistics.false_decodes += u64::from(result.false_decode);
-                        current_statistics.total_iterations += result.iterations;
-                        if !result.frame_error {
-                            current_statistics.ldpc.correct_iterations += result.iterations;
-                        }
-                        current_statistics.num_frames += 1;
-                        if let Some(bch) = &mut current_statistics.bch {
-                            if result.bit_errors > self.bch_max_errors {
-                                // BCH cannot decode codeword
-                                bch.bit_errors += result.bit_errors;
-                                bch.frame_errors += 1;
-                            } else {
-                                // BCH can decode codeword
-                                bch.correct_iterations += result.iterations;
-                            }
-                        }
-                    }
+                    Ok(result) => current_statistics.update(&result, self.bch_max_errors),
                     Err(()) => break,
                 }
                 report!(self, current_statistics, ebn0_db, false);
             }
-            report!(self, current_statistics, ebn0_db, true);
-
-            for (_, terminate_tx) in workers.iter() {
-                // we don't care if this fails because the worker has terminated
-                // and dropped the channel.
-                let _ = terminate_tx.send(());
+            // If the test is cancelled before simulating any frames for this
+            // Eb/N0, there are no statistics to report.
+            let has_statistics = current_statistics.num_frames > 0;
+            if has_statistics {
+                report!(self, current_statistics, ebn0_db, true);
             }
 
-            let mut join_error = None;
-            for (handle, _) in workers.into_iter() {
-                if let Err(e) = handle.join().unwrap() {
-                    join_error = Some(e);
-                }
+            Self::stop_workers(workers)?;
+
+            if has_statistics {
+                self.statistics.push(Statistics::from_current(
+                    &current_statistics,
+                    ebn0_db,
+                    self.k_unknown,
+                    &self.metadata,
+                ));
             }
-            if let Some(e) = join_error {
-                return Err(e);
+            if self.cancelled() {
+                break;
             }
+        }
+        Ok(())
+    }
+
+    fn do_run_profile(
+        &mut self,
+        profile: Arc<SnrProfile>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let bin_width_db = self.snr_bin_width_db;
+        let max_iterations = |bin: i64| {
+            self.iteration_schedule.max_iterations(
+                bin as f32 * bin_width_db,
+                self.max_iterations,
+                None,
+            )
+        };
+        let (results_tx, results_rx) = mpsc::channel();
+        let workers = (0..self.num_workers)
+            .map(|worker_index| {
+                // The Eb/N0 and the maximum number of iterations are set by
+                // the worker for each frame
+                let (mut worker, terminate_tx) = self.make_worker(
+                    worker_index,
+                    0.0,
+                    self.max_iterations,
+                    results_tx.clone(),
+                    worker_index as u64,
+                );
+                worker.profile = Some(ProfileFrames {
+                    profile: Arc::clone(&profile),
+                    bin_width_db,
+                    next: worker_index as u64,
+                    stride: self.num_workers as u64,
+                    seed: self.seed,
+                    channel_model: self.channel_model,
+                    esn0_per_ebn0: self.esn0_per_ebn0(),
+                    iteration_schedule: self.iteration_schedule.clone(),
+                    max_iterations: self.max_iterations,
+                });
+                let handle = std::thread::spawn(move || worker.work());
+                (handle, terminate_tx)
+            })
+            .collect::<Vec<_>>();
+        // The workers drop their results channel when they finish their frames
+        drop(results_tx);
 
+        let mut bins = BTreeMap::new();
+        while !self.cancelled() {
+            let Ok(Ok(result)) = results_rx.recv() else {
+                // All the frames have been simulated, or a worker has failed
+                break;
+            };
+            let bin = result.snr_bin.unwrap();
+            bins.entry(bin)
+                .or_insert_with(|| {
+                    CurrentStatistics::new(
+                        self.bch_max_errors > 0,
+                        max_iterations(bin),
+                        0,
+                        self.num_workers,
+                    )
+                })
+                .update(&result, self.bch_max_errors);
+        }
+        Self::stop_workers(workers)?;
+
+        for (bin, current_statistics) in bins {
+            let ebn0_db = bin as f32 * bin_width_db;
+            report!(self, current_statistics, ebn0_db, true);
             self.statistics.push(Statistics::from_current(
                 &current_statistics,
                 ebn0_db,
-                self.k,
+                self.k_unknown,
+                &self.metadata,
             ));
         }
         Ok(())
     }
 
+    #[allow(clippy::type_complexity)]
+    fn stop_workers(
+        workers: Vec<(
+            std::thread::JoinHandle<Result<(), Box<dyn std::error::Error + Send + Sync>>>,
+            SyncSender<()>,
+        )>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        for (_, terminate_tx) in workers.iter() {
+            // we don't care if this fails because the worker has terminated
+            // and dropped the channel.
+            let _ = terminate_tx.send(());
+        }
+
+        let mut join_error = None;
+        for (handle, _) in workers.into_iter() {
+            if let Err(e) = handle.join().unwrap() {
+                join_error = Some(e);
+            }
+        }
+        match join_error {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    // Ratio between the Es/N0 and the Eb/N0
+    fn esn0_per_ebn0(&self) -> f64 {
+        self.rate * Mod::BITS_PER_SYMBOL
+    }
+
     fn make_worker(
         &self,
-        noise_sigma: f64,
+        worker_index: usize,
+        ebn0_db: f32,
+        max_iterations: usize,
         results_tx: Sender<WorkerResult>,
+        stream_index: u64,
     ) -> (Worker<Mod>, SyncSender<()>) {
         let (terminate_tx, terminate_rx) = mpsc::sync_channel(1);
+        let noise_sigma = noise_sigma(ebn0_db, self.esn0_per_ebn0());
+        let channel = self.channel_model.channel(noise_sigma);
+        let mut decoder_rng = stream_rng(self.seed, "decoder", stream_index);
+        let mut decoder = self
+            .decoder_implementation
+            .build_seeded_decoder(Arc::clone(&self.graph), decoder_rng.gen());
+        decoder.set_llr_magnitude_tracking(self.llr_magnitude_tracking);
+        let batch = (self.batch_decoding
+            && self.snr_profile.is_none()
+            && self.training_data.is_none()
+            && !self.llr_magnitude_tracking)
+            .then(|| {
+                let decoder = self
+                    .decoder_implementation
+                    .build_batch_decoder(Arc::clone(&self.graph), decoder_rng.gen());
+                let frames = decoder.lanes();
+                BatchDecoding {
+                    decoder,
+                    llrs: Vec::with_capacity(frames * self.n_cw),
+                    decoded: vec![0; frames * self.n_cw],
+                    remaining_frames: None,
+                }
+            });
         (
             Worker {
+                index: worker_index,
                 terminate_rx,
                 results_tx,
                 k: self.k,
                 encoder: self.encoder.clone(),
-                puncturer: self.puncturer.clone(),
-                interleaver: self.interleaver.clone(),
+                ordering: self.ordering.clone(),
+                known_bits: self.frame_known_bits(),
+                shortened_positions: self.shortened_positions.clone(),
+                llr_transform: self.llr_transform.clone(),
                 modulator: self.modulator.clone(),
-                channel: AwgnChannel::new(noise_sigma),
-                demodulator: Mod::Demodulator::from_noise_sigma(noise_sigma),
-                decoder: self.decoder_implementation.build_decoder(self.h.clone()),
-                max_iterations: self.max_iterations,
+                hard_decision: channel.hard_decision_demodulator(),
+                channel,
+                impairments: self.impairments,
+                demodulator: Mod::demodulator(&self.modulator, noise_sigma),
+                decoder,
+                decoded: vec![0; self.n_cw],
+                batch,
+                ebn0_db,
+                max_iterations,
+                message_rng: stream_rng(self.seed, "message", stream_index),
+                noise_rng: stream_rng(self.seed, "noise", stream_index),
+                training_data: self
+                    .training_data
+                    .as_ref()
+                    .map(|sink| TrainingDataCollector {
+                        sink: Arc::clone(sink),
+                        rng: stream_rng(self.seed, "training data", stream_index),
+                        max_iterations: self.max_iterations,
+                    }),
+                error_patterns: self.error_patterns.clone(),
+                profile: None,
             },
             terminate_tx,
         )
@@ -375,18 +1117,36 @@ impl<Mod: Modulation, Dec: DecoderFactory> Ber for BerTest<Mod, Dec> {
     fn rate(&self) -> f64 {
         self.rate
     }
+
+    fn metadata(&self) -> &Metadata {
+        &self.metadata
+    }
 }
 
 impl<Mod: Modulation> Worker<Mod> {
     fn work(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
-        let mut rng = rand::thread_rng();
+        if self.batch.is_some() {
+            return self.work_batches();
+        }
         loop {
             match self.terminate_rx.try_recv() {
                 Ok(()) => return Ok(()),
                 Err(TryRecvError::Disconnected) => panic!(),
                 Err(TryRecvError::Empty) => (),
             };
-            let result = self.simulate(&mut rng);
+            let snr_bin = match self.profile.is_some() {
+                true => match self.next_profile_frame() {
+                    Some(bin) => Some(bin),
+                    None => return Ok(()),
+                },
+                false => None,
+            };
+            let start = Instant::now();
+            let mut result = self.simulate();
+            if let Ok(r) = result.as_mut() {
+                r.busy = start.elapsed();
+                r.snr_bin = snr_bin;
+            }
             let to_send = match result.as_ref() {
                 Ok(r) => Ok(r.clone()),
                 Err(_) => Err(()),
@@ -396,53 +1156,267 @@ impl<Mod: Modulation> Worker<Mod> {
         }
     }
 
-    fn simulate<R: Rng>(
+    fn work_batches(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+        loop {
+            match self.terminate_rx.try_recv() {
+                Ok(()) => return Ok(()),
+                Err(TryRecvError::Disconnected) => panic!(),
+                Err(TryRecvError::Empty) => (),
+            };
+            let num_frames = self.batch_frames();
+            if num_frames == 0 {
+                // The other workers are simulating the remaining frames
+                return Ok(());
+            }
+            let start = Instant::now();
+            match self.simulate_batch(num_frames) {
+                Ok(results) => {
+                    // The time is shared evenly by the frames of the batch
+                    let busy = start.elapsed() / results.len() as u32;
+                    for mut r in results {
+                        r.busy = busy;
+                        self.results_tx.send(Ok(r)).unwrap();
+                    }
+                }
+                Err(e) => {
+                    self.results_tx.send(Err(())).unwrap();
+                    return Err(e);
+                }
+            }
+        }
+    }
+
+    fn simulate(
         &mut self,
-        rng: &mut R,
     ) -> Result<WorkerResultOk, Box<dyn std::error::Error + Send + Sync + 'static>> {
-        let message = Self::random_message(rng, self.k);
-        let codeword = self.encoder.encode(&Self::gf2_array(&message));
-        let transmitted = match self.puncturer.as_ref() {
-            Some(p) => p.puncture(&codeword)?,
-            None => codeword,
-        };
-        let transmitted = match self.interleaver.as_ref() {
-            Some(i) => i.interleave(&transmitted),
-            None => transmitted,
+        let frame = self.transmit_frame()?;
+
+        // LLR tracing is only enabled for the frames sampled for the training
+        // data
+        let sampled = match self.training_data.as_mut() {
+            Some(training) => {
+                let sampled = training.sink.sample(&mut training.rng);
+                self.decoder.set_llr_tracing(sampled);
+                sampled
+            }
+            None => false,
         };
+        let decode_result =
+            self.decoder
+                .decode_into(&frame.llrs, &mut self.decoded, self.max_iterations);
+        self.frame_result(
+            &frame,
+            &self.decoded,
+            decode_result,
+            sampled,
+            self.decoder.max_llr_magnitude(),
+        )
+    }
+
+    // Returns the number of frames of the next batch. This is the number of
+    // lanes of the batch decoder, except when fewer frames remain to reach
+    // the maximum number of frames.
+    fn batch_frames(&self) -> usize {
+        let batch = self.batch.as_ref().unwrap();
+        let lanes = batch.decoder.lanes();
+        match batch.remaining_frames.as_ref() {
+            Some(remaining) => {
+                let remaining = remaining
+                    .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |r| {
+                        Some(r.saturating_sub(lanes as u64))
+                    })
+                    .unwrap();
+                remaining.min(lanes as u64) as usize
+            }
+            None => lanes,
+        }
+    }
+
+    fn simulate_batch(
+        &mut self,
+        num_frames: usize,
+    ) -> Result<Vec<WorkerResultOk>, Box<dyn std::error::Error + Send + Sync + 'static>> {
+        let frames = (0..num_frames)
+            .map(|_| self.transmit_frame())
+            .collect::<Result<Vec<_>, _>>()?;
+        let batch = self.batch.as_mut().unwrap();
+        batch.llrs.clear();
+        batch.llrs.extend(
+            frames
+                .iter()
+                .flat_map(|frame| frame.llrs.iter().map(|&x| x as f32)),
+        );
+        let decode_results = batch
+            .decoder
+            .decode_batch(
+                &batch.llrs,
+                num_frames,
+                &mut batch.decoded[..num_frames * self.decoded.len()],
+                self.max_iterations,
+            )
+            .to_vec();
+        let batch = self.batch.as_ref().unwrap();
+        frames
+            .iter()
+            .zip(decode_results)
+            .zip(batch.decoded.chunks_exact(self.decoded.len()))
+            .map(|((frame, decode_result), decoded)| {
+                self.frame_result(frame, decoded, decode_result, false, None)
+            })
+            .collect()
+    }
+
+    // Generates a random message, encodes it and transmits it through the
+    // channel, giving the LLRs for the decoder.
+    fn transmit_frame(
+        &mut self,
+    ) -> Result<Frame, Box<dyn std::error::Error + Send + Sync + 'static>> {
+        let mut message = Self::random_message(&mut self.message_rng, self.k);
+        if let Some(known) = self.known_bits.as_ref() {
+            known.apply_to_bits(&mut message)?;
+        }
+        let codeword = self.encoder.encode(&Self::gf2_array(&message));
+        let mut transmitted = self.ordering.codeword_to_transmission(&codeword)?;
+        if let Some(positions) = self.shortened_positions.as_ref() {
+            transmitted = Array1::from_vec(shortening::remove_positions(transmitted, positions));
+        }
         let mut symbols = self.modulator.modulate(&transmitted);
-        self.channel.add_noise(rng, &mut symbols);
-        let llrs_demod = self.demodulator.demodulate(&symbols);
-        let llrs_decoder = match self.interleaver.as_ref() {
-            Some(i) => i.deinterleave(&llrs_demod),
-            None => llrs_demod,
-        };
-        let llrs_decoder = match self.puncturer.as_ref() {
-            Some(p) => p.depuncture(&llrs_decoder)?,
-            None => llrs_decoder,
+        let gains = self.channel.transmit(&mut self.noise_rng, &mut symbols);
+        if !self.impairments.is_ideal() {
+            self.impairments.apply(&mut self.noise_rng, &mut symbols);
+        }
+        let mut llrs_demod = match gains {
+            Some(gains) => self.demodulator.demodulate_faded(&symbols, &gains),
+            None => self.demodulator.demodulate(&symbols),
         };
+        if let Some(hard_decision) = self.hard_decision.as_ref() {
+            hard_decision.harden(&mut llrs_demod);
+        }
+        if let Some(transform) = self.llr_transform.as_ref() {
+            transform.apply(&mut llrs_demod);
+        }
+        // The LLRs of the shortened bits are set by the known bits
+        if let Some(positions) = self.shortened_positions.as_ref() {
+            llrs_demod = shortening::insert_positions(&llrs_demod, positions);
+        }
+        let mut llrs_decoder = self.ordering.llrs_to_codeword(&llrs_demod)?;
+        if let Some(known) = self.known_bits.as_ref() {
+            known.apply_to_llrs(&mut llrs_decoder)?;
+        }
+        Ok(Frame {
+            message,
+            codeword,
+            llrs: llrs_decoder,
+        })
+    }
 
-        let (decoded, iterations, success) =
-            match self.decoder.decode(&llrs_decoder, self.max_iterations) {
-                Ok(output) => (output.codeword, output.iterations, true),
-                Err(output) => (output.codeword, output.iterations, false),
-            };
+    // Computes the result of a frame from its decoded codeword.
+    fn frame_result(
+        &self,
+        frame: &Frame,
+        decoded: &[u8],
+        decode_result: Result<usize, usize>,
+        sampled: bool,
+        max_llr_magnitude: Option<f64>,
+    ) -> Result<WorkerResultOk, Box<dyn std::error::Error + Send + Sync + 'static>> {
+        let Frame {
+            message,
+            codeword,
+            llrs: llrs_decoder,
+        } = frame;
+        let (iterations, success) = match decode_result {
+            Ok(iterations) => (iterations, true),
+            Err(iterations) => (iterations, false),
+        };
         // Count only bit errors in the systematic part of the codeword
-        let bit_errors = message
+        let mut bit_errors = message
             .iter()
             .zip(decoded.iter())
             .filter(|(&a, &b)| a != b)
             .count() as u64;
+        // Exclude errors in the known bits
+        if let Some(known) = self.known_bits.as_ref() {
+            bit_errors -= known.count_errors(&decoded[..self.k]);
+        }
         let frame_error = bit_errors > 0;
         let false_decode = frame_error && success;
+        let false_decode_distance = false_decode.then(|| codeword_distance(codeword, decoded));
+        if sampled {
+            let training = self.training_data.as_ref().unwrap();
+            let trace = self
+                .decoder
+                .llr_trace()
+                .ok_or("the decoder does not support LLR tracing")?;
+            training.sink.write_record(
+                self.ebn0_db,
+                iterations,
+                frame_error,
+                codeword,
+                llrs_decoder,
+                trace,
+                training.max_iterations,
+            )?;
+        }
+        if let Some(capture) = self.error_patterns.as_ref() {
+            if frame_error && !capture.is_full() {
+                capture.record(ErrorPattern {
+                    ebn0_db: self.ebn0_db,
+                    positions: codeword
+                        .iter()
+                        .zip(decoded.iter())
+                        .enumerate()
+                        .filter(|(_, (a, &b))| a.is_one() != (b == 1))
+                        .map(|(j, _)| j)
+                        .collect(),
+                    false_decode,
+                });
+            }
+        }
         Ok(WorkerResultOk {
             bit_errors,
             frame_error,
             false_decode,
+            false_decode_distance,
             iterations: iterations as u64,
+            max_llr_magnitude,
+            worker: self.index,
+            // filled by Worker::work
+            busy: Duration::ZERO,
+            snr_bin: None,
         })
     }
 
+    // Sets up the channel, the demodulator, the maximum number of iterations
+    // and the random streams for the next frame of the SNR profile. Returns
+    // the Eb/N0 bin of the frame, or None if the worker has finished its
+    // frames.
+    fn next_profile_frame(&mut self) -> Option<i64> {
+        let frames = self.profile.as_mut().unwrap();
+        let frame = frames.next;
+        if frame >= frames.profile.num_frames() {
+            return None;
+        }
+        frames.next += frames.stride;
+        let ebn0_db = frames.profile.ebn0_db(frame);
+        let bin = frames.profile.bin(frame, frames.bin_width_db);
+        let noise_sigma = noise_sigma(ebn0_db, frames.esn0_per_ebn0);
+        self.channel = frames.channel_model.channel(noise_sigma);
+        self.hard_decision = self.channel.hard_decision_demodulator();
+        self.demodulator = Mod::demodulator(&self.modulator, noise_sigma);
+        self.max_iterations = frames.iteration_schedule.max_iterations(
+            bin as f32 * frames.bin_width_db,
+            frames.max_iterations,
+            None,
+        );
+        self.message_rng = stream_rng(frames.seed, "profile message", frame);
+        self.noise_rng = stream_rng(frames.seed, "profile noise", frame);
+        if let Some(training) = self.training_data.as_mut() {
+            training.rng = stream_rng(frames.seed, "profile training data", frame);
+        }
+        self.ebn0_db = ebn0_db;
+        Some(bin)
+    }
+
     fn random_message<R: Rng>(rng: &mut R, size: usize) -> Vec<u8> {
         rng.sample_iter(Standard)
             .map(<u8 as From<bool>>::from)
@@ -458,13 +1432,39 @@ impl<Mod: Modulation> Worker<Mod> {
     }
 }
 
+// Noise standard deviation per real dimension for an Eb/N0 in dB.
+fn noise_sigma(ebn0_db: f32, esn0_per_ebn0: f64) -> f64 {
+    let ebn0 = 10.0_f64.powf(0.1 * f64::from(ebn0_db));
+    let esn0 = esn0_per_ebn0 * ebn0;
+    (0.5 / esn0).sqrt()
+}
+
+// Hamming distance between a codeword and the output of the decoder.
+pub(crate) fn codeword_distance(codeword: &Array1<GF2>, decoded: &[u8]) -> usize {
+    codeword
+        .iter()
+        .zip(decoded.iter())
+        .filter(|(a, &b)| a.is_one() != (b == 1))
+        .count()
+}
+
 impl CurrentStatistics {
-    fn new(has_bch: bool) -> CurrentStatistics {
+    fn new(
+        has_bch: bool,
+        max_iterations: usize,
+        warm_up_frames: u64,
+        num_workers: usize,
+    ) -> CurrentStatistics {
         CurrentStatistics {
             num_frames: 0,
             false_decodes: 0,
+            false_decode_distances: BTreeMap::new(),
             total_iterations: 0,
+            max_llr_magnitude: None,
+            max_iterations,
+            warm_up_frames,
             start: Instant::now(),
+            worker_busy: vec![Duration::ZERO; num_workers],
             ldpc: CurrentCodeStatistics::new(),
             bch: if has_bch {
                 Some(CurrentCodeStatistics::new())
@@ -474,6 +1474,45 @@ impl CurrentStatistics {
         }
     }
 
+    fn update(&mut self, result: &WorkerResultOk, bch_max_errors: u64) {
+        self.ldpc.bit_errors += result.bit_errors;
+        self.ldpc.frame_errors += u64::from(result.frame_error);
+        self.false_decodes += u64::from(result.false_decode);
+        if let Some(distance) = result.false_decode_distance {
+            *self.false_decode_distances.entry(distance).or_default() += 1;
+        }
+        self.total_iterations += result.iterations;
+        if let Some(m) = result.max_llr_magnitude {
+            self.max_llr_magnitude = Some(self.max_llr_magnitude.map_or(m, |x| x.max(m)));
+        }
+        if !result.frame_error {
+            self.ldpc.correct_iterations += result.iterations;
+        }
+        if self.num_frames >= self.warm_up_frames {
+            self.worker_busy[result.worker] += result.busy;
+        }
+        self.num_frames += 1;
+        if self.num_frames == self.warm_up_frames {
+            self.start = Instant::now();
+        }
+        if let Some(bch) = &mut self.bch {
+            if result.bit_errors > bch_max_errors {
+                // BCH cannot decode codeword
+                bch.bit_errors += result.bit_errors;
+                bch.frame_errors += 1;
+            } else {
+                // BCH can decode codeword
+                bch.correct_iterations += result.iterations;
+            }
+        }
+    }
+
+    // Current FER estimate, measured with the same frame errors as
+    // errors_for_termination
+    fn fer(&self) -> Option<f64> {
+        (self.num_frames > 0).then(|| self.errors_for_termination() as f64 / self.num_frames as f64)
+    }
+
     fn errors_for_termination(&self) -> u64 {
         if let Some(bch) = &self.bch {
             bch.frame_errors
@@ -500,35 +1539,257 @@ impl Default for CurrentCodeStatistics {
 }
 
 impl CodeStatistics {
-    fn from_current(stats: &CurrentCodeStatistics, num_frames: u64, k: usize) -> CodeStatistics {
+    /// Computes the statistics of a code from its error counts.
+    ///
+    /// The parameters are the number of bit errors, the number of frame
+    /// errors, the sum of iterations in correct frames, the number of frames
+    /// and the number of information bits per frame.
+    pub fn from_counts(
+        bit_errors: u64,
+        frame_errors: u64,
+        correct_iterations: u64,
+        num_frames: u64,
+        k: usize,
+    ) -> CodeStatistics {
         CodeStatistics {
-            bit_errors: stats.bit_errors,
-            frame_errors: stats.frame_errors,
-            correct_iterations: stats.correct_iterations,
-            ber: stats.bit_errors as f64 / (k as f64 * num_frames as f64),
-            fer: stats.frame_errors as f64 / num_frames as f64,
-            average_iterations_correct: stats.correct_iterations as f64
-                / (num_frames - stats.frame_errors) as f64,
+            bit_errors,
+            frame_errors,
+            correct_iterations,
+            ber: bit_errors as f64 / (k as f64 * num_frames as f64),
+            fer: frame_errors as f64 / num_frames as f64,
+            ber_confidence_interval: ber_confidence_interval(bit_errors, k as u64 * num_frames),
+            fer_confidence_interval: fer_confidence_interval(frame_errors, num_frames),
+            average_iterations_correct: correct_iterations as f64
+                / (num_frames - frame_errors) as f64,
         }
     }
+
+    fn from_current(stats: &CurrentCodeStatistics, num_frames: u64, k: usize) -> CodeStatistics {
+        CodeStatistics::from_counts(
+            stats.bit_errors,
+            stats.frame_errors,
+            stats.correct_iterations,
+            num_frames,
+            k,
+        )
+    }
+
+    fn combine(parts: &[&CodeStatistics], num_frames: u64, k: usize) -> CodeStatistics {
+        CodeStatistics::from_counts(
+            parts.iter().map(|s| s.bit_errors).sum(),
+            parts.iter().map(|s| s.frame_errors).sum(),
+            parts.iter().map(|s| s.correct_iterations).sum(),
+            num_frames,
+            k,
+        )
+    }
 }
 
 impl Statistics {
-    fn from_current(stats: &CurrentStatistics, ebn0_db: f32, k: usize) -> Statistics {
-        let elapsed = Instant::now() - stats.start;
+    /// Combines the statistics of several BER tests of the same Eb/N0.
+    ///
+    /// This is used to merge the results of tests that have simulated
+    /// different frames of the same Eb/N0, for instance in different machines
+    /// (see [`distributed`](super::distributed)). The counts of the tests are
+    /// added, and the error rates, confidence intervals and averages are
+    /// computed again using `k` as the number of information bits per frame.
+    /// The tests are assumed to have run in parallel, so the elapsed time is
+    /// the maximum of their elapsed times and the throughput is the sum of
+    /// their throughputs. The worker utilizations of all the tests are listed
+    /// together. The BCH statistics are only present if they are present in
+    /// all the tests.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `parts` is empty.
+    pub fn combine(parts: &[Statistics], k: usize) -> Statistics {
+        let first = parts.first().expect("no statistics to combine");
+        let num_frames = parts.iter().map(|s| s.num_frames).sum();
+        let total_iterations = parts.iter().map(|s| s.total_iterations).sum::<u64>();
+        let mut false_decode_distances = BTreeMap::new();
+        for (&distance, &count) in parts.iter().flat_map(|s| &s.false_decode_distances) {
+            *false_decode_distances.entry(distance).or_insert(0) += count;
+        }
+        let bch = parts
+            .iter()
+            .map(|s| s.bch.as_ref())
+            .collect::<Option<Vec<_>>>();
+        Statistics {
+            ebn0_db: first.ebn0_db,
+            num_frames,
+            total_iterations,
+            false_decodes: parts.iter().map(|s| s.false_decodes).sum(),
+            false_decode_distances,
+            average_iterations: total_iterations as f64 / num_frames as f64,
+            elapsed: parts.iter().map(|s| s.elapsed).max().unwrap(),
+            throughput_mbps: parts.iter().map(|s| s.throughput_mbps).sum(),
+            warm_up_frames: parts.iter().map(|s| s.warm_up_frames).sum(),
+            worker_utilization: parts
+                .iter()
+                .flat_map(|s| s.worker_utilization.iter().copied())
+                .collect(),
+            max_llr_magnitude: parts
+                .iter()
+                .filter_map(|s| s.max_llr_magnitude)
+                .reduce(f64::max),
+            max_iterations: parts.iter().map(|s| s.max_iterations).max().unwrap(),
+            ldpc: CodeStatistics::combine(
+                &parts.iter().map(|s| &s.ldpc).collect::<Vec<_>>(),
+                num_frames,
+                k,
+            ),
+            bch: bch.map(|bch| CodeStatistics::combine(&bch, num_frames, k)),
+            metadata: first.metadata.clone(),
+        }
+    }
+
+    fn from_current(
+        stats: &CurrentStatistics,
+        ebn0_db: f32,
+        k: usize,
+        metadata: &Metadata,
+    ) -> Statistics {
+        let warm_up_frames = stats.warm_up_frames.min(stats.num_frames);
+        let elapsed = if stats.num_frames >= stats.warm_up_frames {
+            Instant::now() - stats.start
+        } else {
+            Duration::ZERO
+        };
+        let per_second = |x: f64| {
+            if elapsed.is_zero() {
+                0.0
+            } else {
+                x / elapsed.as_secs_f64()
+            }
+        };
         Statistics {
             ebn0_db,
             num_frames: stats.num_frames,
             false_decodes: stats.false_decodes,
+            false_decode_distances: stats.false_decode_distances.clone(),
             total_iterations: stats.total_iterations,
             average_iterations: stats.total_iterations as f64 / stats.num_frames as f64,
             elapsed,
-            throughput_mbps: 1e-6 * (k as f64 * stats.num_frames as f64) / elapsed.as_secs_f64(),
+            throughput_mbps: per_second(
+                1e-6 * (k as f64 * (stats.num_frames - warm_up_frames) as f64),
+            ),
+            warm_up_frames,
+            worker_utilization: stats
+                .worker_busy
+                .iter()
+                .map(|busy| per_second(busy.as_secs_f64()))
+                .collect(),
+            max_llr_magnitude: stats.max_llr_magnitude,
+            max_iterations: stats.max_iterations,
             ldpc: CodeStatistics::from_current(&stats.ldpc, stats.num_frames, k),
             bch: stats
                 .bch
                 .as_ref()
                 .map(|b| CodeStatistics::from_current(b, stats.num_frames, k)),
+            metadata: Some(metadata.clone()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        codes::examples::ExampleCode,
+        decoder::factory::DecoderImplementation,
+        simulation::modulation::{Bpsk, Qpsk},
+    };
+
+    fn new_test<Mod: Modulation>() -> BerTest<Mod> {
+        BerTest::new(
+            ExampleCode::Qc42_21.h(),
+            DecoderImplementation::HLPhif64,
+            None,
+            None,
+            10,
+            20,
+            &[2.0],
+            None,
+            0,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn bsc_channel() {
+        assert!(new_test::<Bpsk>()
+            .with_channel(ChannelModel::Bsc(Some(0.1)))
+            .is_ok());
+        assert_eq!(
+            new_test::<Qpsk>()
+                .with_channel(ChannelModel::Bsc(None))
+                .unwrap_err(),
+            channel::Error::BscModulation
+        );
+        assert_eq!(
+            new_test::<Bpsk>()
+                .with_channel(ChannelModel::Bsc(Some(0.7)))
+                .unwrap_err(),
+            channel::Error::InvalidCrossover(0.7)
+        );
+        assert!(new_test::<Qpsk>().with_channel(ChannelModel::Awgn).is_ok());
+    }
+
+    #[test]
+    fn seeded_stochastic_decoder() {
+        let run = |seed| {
+            let mut test = BerTest::<Bpsk>::new(
+                ExampleCode::Qc42_21.h(),
+                DecoderImplementation::Stochastic,
+                None,
+                None,
+                20,
+                200,
+                &[2.0, 3.0],
+                None,
+                0,
+            )
+            .unwrap()
+            .with_max_frames(50)
+            .with_seed(seed);
+            // The results are only reproducible with a fixed number of threads
+            test.num_workers = 1;
+            test.run()
+                .unwrap()
+                .into_iter()
+                .map(|s| {
+                    (
+                        s.num_frames,
+                        s.total_iterations,
+                        s.ldpc.bit_errors,
+                        s.ldpc.frame_errors,
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(run(42), run(42));
+    }
+
+    #[test]
+    fn batch_decoding_max_frames() {
+        let mut test = BerTest::<Bpsk>::new(
+            ExampleCode::Qc42_21.h(),
+            DecoderImplementation::NormMinSumi8(0.75.into()),
+            None,
+            None,
+            1000,
+            20,
+            &[1.0, 2.0],
+            None,
+            0,
+        )
+        .unwrap()
+        .with_max_frames(40)
+        .with_batch_decoding(true);
+        // One of the workers simulates a batch of only 8 frames
+        test.num_workers = 2;
+        for statistics in test.run().unwrap() {
+            assert_eq!(statistics.num_frames, 40);
         }
     }
 }
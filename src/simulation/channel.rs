@@ -1,10 +1,15 @@
 //! Channel simulation.
 //!
-//! This module contains the simulation of an AWGN channel.
+//! This module contains the simulation of an AWGN channel, of a Rayleigh flat
+//! fading channel and of a binary symmetric channel, as well as of the
+//! [`Impairments`] of the receiver front-end, which cause the received symbols
+//! to be imperfectly normalized.
 
+use super::modulation::HardDecisionDemodulator;
 use num_complex::Complex;
 use rand::Rng;
-use rand_distr::{Distribution, Normal};
+use rand_distr::{Distribution, Normal, StandardNormal};
+use thiserror::Error;
 
 /// Channel type.
 ///
@@ -12,9 +17,17 @@ use rand_distr::{Distribution, Normal};
 ///
 /// This trait is implemented for `f64` and `Complex<f64>` as a way of handling
 /// both real and complex channels internally.
-pub trait ChannelType: sealed::Sealed + std::ops::AddAssign + Sized {
+pub trait ChannelType:
+    sealed::Sealed + std::ops::AddAssign + std::ops::Mul<f64, Output = Self> + Copy + Sized
+{
     #[doc(hidden)]
     fn noise<R: Rng>(awgn_channel: &AwgnChannel, rng: &mut R) -> Self;
+
+    #[doc(hidden)]
+    fn dc_offset(offset: f64) -> Self;
+
+    #[doc(hidden)]
+    fn flip_signs<R: Rng>(self, crossover: f64, rng: &mut R) -> Self;
 }
 
 /// Channel model.
@@ -27,6 +40,20 @@ pub trait Channel {
     /// The noise is added in-place to the slice `symbols`. An [Rng] is used as
     /// source of randomness.
     fn add_noise<R: Rng, T: ChannelType>(&self, rng: &mut R, symbols: &mut [T]);
+
+    /// Passes a sequence of symbols through the channel.
+    ///
+    /// The symbols are modified in-place. If the channel has fading, this
+    /// returns the gain applied to each symbol, which can be used by the
+    /// demodulator as perfect channel state information (see
+    /// [`Demodulator::demodulate_faded`](super::modulation::Demodulator::demodulate_faded)).
+    ///
+    /// The default implementation calls [`Channel::add_noise`] and returns
+    /// `None`.
+    fn transmit<R: Rng, T: ChannelType>(&self, rng: &mut R, symbols: &mut [T]) -> Option<Vec<f64>> {
+        self.add_noise(rng, symbols);
+        None
+    }
 }
 
 /// AWGN channel simulation.
@@ -65,10 +92,509 @@ impl Channel for AwgnChannel {
     }
 }
 
+/// Fading of a Rayleigh channel.
+///
+/// Describes the time correlation of the fading gains.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Fading {
+    /// Independent fading gain for each symbol.
+    #[default]
+    Fast,
+    /// Block fading.
+    ///
+    /// The fading gain is constant over blocks of the given number of symbols,
+    /// and independent between blocks.
+    Block(usize),
+    /// Fading with a Jakes Doppler spectrum.
+    ///
+    /// The parameter is the maximum Doppler frequency normalized by the symbol
+    /// rate.
+    Doppler(f64),
+}
+
+/// Rayleigh flat fading channel simulation.
+///
+/// The channel multiplies each symbol by a fading gain and then adds AWGN.
+/// The fading gain is the magnitude of a circularly symmetric complex normal
+/// random variable with unit power, so the average Eb/N0 is the same as in
+/// the AWGN channel. The phase of the fading is assumed to be perfectly
+/// compensated by the receiver.
+///
+/// The fading process of each call to [`Channel::transmit`] is independent of
+/// the others, so each codeword sees an independent realization of the
+/// fading.
+#[derive(Debug, Clone)]
+pub struct RayleighChannel {
+    awgn: AwgnChannel,
+    fading: Fading,
+}
+
+impl RayleighChannel {
+    // Number of sinusoids used to generate the Doppler fading process.
+    const DOPPLER_SINUSOIDS: usize = 16;
+
+    /// Creates a new Rayleigh channel (either real or complex).
+    ///
+    /// The noise is as in [`AwgnChannel::new`].
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `noise_sigma` is not a positive finite number,
+    /// if the block size of [`Fading::Block`] is zero, or if the Doppler
+    /// frequency of [`Fading::Doppler`] is negative or not finite.
+    pub fn new(noise_sigma: f64, fading: Fading) -> RayleighChannel {
+        match fading {
+            Fading::Fast => (),
+            Fading::Block(size) => assert!(size > 0),
+            Fading::Doppler(doppler) => assert!(doppler.is_finite() && doppler >= 0.0),
+        }
+        RayleighChannel {
+            awgn: AwgnChannel::new(noise_sigma),
+            fading,
+        }
+    }
+
+    /// Generates the fading gains for a sequence of symbols.
+    pub fn gains<R: Rng>(&self, rng: &mut R, len: usize) -> Vec<f64> {
+        let mut rayleigh = || {
+            let x: f64 = StandardNormal.sample(rng);
+            let y: f64 = StandardNormal.sample(rng);
+            (0.5 * (x * x + y * y)).sqrt()
+        };
+        match self.fading {
+            Fading::Fast => (0..len).map(|_| rayleigh()).collect(),
+            Fading::Block(size) => {
+                let mut gains = Vec::with_capacity(len);
+                while gains.len() < len {
+                    let gain = rayleigh();
+                    let n = size.min(len - gains.len());
+                    gains.extend(std::iter::repeat_n(gain, n));
+                }
+                gains
+            }
+            Fading::Doppler(doppler) => {
+                // Sum of sinusoids with random angles of arrival and phases
+                use std::f64::consts::PI;
+                let sinusoids = (0..Self::DOPPLER_SINUSOIDS)
+                    .map(|_| {
+                        let angle = rng.gen_range(0.0..2.0 * PI);
+                        let phase = rng.gen_range(0.0..2.0 * PI);
+                        (2.0 * PI * doppler * angle.cos(), phase)
+                    })
+                    .collect::<Vec<_>>();
+                let scale = (Self::DOPPLER_SINUSOIDS as f64).sqrt().recip();
+                (0..len)
+                    .map(|t| {
+                        let h = sinusoids
+                            .iter()
+                            .map(|&(freq, phase)| Complex::from_polar(1.0, freq * t as f64 + phase))
+                            .sum::<Complex<f64>>();
+                        scale * h.norm()
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+impl Channel for RayleighChannel {
+    fn add_noise<R: Rng, T: ChannelType>(&self, rng: &mut R, symbols: &mut [T]) {
+        self.transmit(rng, symbols);
+    }
+
+    fn transmit<R: Rng, T: ChannelType>(&self, rng: &mut R, symbols: &mut [T]) -> Option<Vec<f64>> {
+        let gains = self.gains(rng, symbols.len());
+        for (x, &gain) in symbols.iter_mut().zip(gains.iter()) {
+            *x = *x * gain;
+        }
+        self.awgn.add_noise(rng, symbols);
+        Some(gains)
+    }
+}
+
+/// Binary symmetric channel simulation.
+///
+/// The channel flips each bit with probability `crossover`. It is simulated on
+/// the modulated symbols by changing the sign of each real component with
+/// probability `crossover`, without adding noise, so it models a BPSK
+/// transmission followed by hard decisions. For complex symbols, the real and
+/// imaginary parts are flipped independently.
+///
+/// The LLRs of the received bits are computed with a
+/// [`HardDecisionDemodulator`](super::modulation::HardDecisionDemodulator).
+#[derive(Debug, Clone)]
+pub struct BscChannel {
+    crossover: f64,
+}
+
+impl BscChannel {
+    /// Creates a new binary symmetric channel.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `crossover` is not between 0 and 0.5.
+    pub fn new(crossover: f64) -> BscChannel {
+        assert!((0.0..=0.5).contains(&crossover));
+        BscChannel { crossover }
+    }
+
+    /// Creates the binary symmetric channel given by hard decisions on an AWGN
+    /// channel.
+    ///
+    /// The crossover probability is the probability that a BPSK symbol of
+    /// amplitude one is received with the wrong sign when real AWGN with
+    /// standard deviation `noise_sigma` is added, which is Q(1 /
+    /// `noise_sigma`).
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `noise_sigma` is negative or NaN.
+    ///
+    /// # Examples
+    /// ```
+    /// # use ldpc_toolbox::simulation::channel::BscChannel;
+    /// let channel = BscChannel::from_noise_sigma(1.0);
+    /// assert!((channel.crossover() - 0.158655).abs() < 1e-6);
+    /// ```
+    pub fn from_noise_sigma(noise_sigma: f64) -> BscChannel {
+        assert!(noise_sigma >= 0.0);
+        BscChannel::new(q_function(noise_sigma.recip()))
+    }
+
+    /// Returns the crossover probability of the channel.
+    pub fn crossover(&self) -> f64 {
+        self.crossover
+    }
+}
+
+impl Channel for BscChannel {
+    fn add_noise<R: Rng, T: ChannelType>(&self, rng: &mut R, symbols: &mut [T]) {
+        for x in symbols.iter_mut() {
+            *x = x.flip_signs(self.crossover, rng);
+        }
+    }
+}
+
+// Gaussian Q-function.
+fn q_function(x: f64) -> f64 {
+    0.5 * erfc(x * std::f64::consts::FRAC_1_SQRT_2)
+}
+
+// Complementary error function.
+//
+// This uses the Chebyshev approximation from Numerical Recipes, which has a
+// relative error smaller than 1.2e-7.
+fn erfc(x: f64) -> f64 {
+    let z = x.abs();
+    let t = 1.0 / (1.0 + 0.5 * z);
+    let poly = [
+        -1.26551223,
+        1.00002368,
+        0.37409196,
+        0.09678418,
+        -0.18628806,
+        0.27886807,
+        -1.13520398,
+        1.48851587,
+        -0.82215223,
+        0.17087277,
+    ]
+    .iter()
+    .rev()
+    .fold(0.0, |acc, &c| c + t * acc);
+    let r = t * (-z * z + poly).exp();
+    if x >= 0.0 {
+        r
+    } else {
+        2.0 - r
+    }
+}
+
+/// Channel model error.
+#[derive(Debug, Copy, Clone, PartialEq, Error)]
+pub enum Error {
+    /// The binary symmetric channel is used with a modulation other than BPSK.
+    #[error("the BSC channel can only be used with BPSK modulation")]
+    BscModulation,
+    /// The crossover probability of the binary symmetric channel is not
+    /// between 0 and 0.5.
+    #[error("invalid crossover probability {0}")]
+    InvalidCrossover(f64),
+}
+
+/// Channel model.
+///
+/// This enum represents the channel models that can be simulated. The channel
+/// for a particular noise standard deviation is created with
+/// [`ChannelModel::channel`].
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ChannelModel {
+    /// AWGN channel.
+    #[default]
+    Awgn,
+    /// Rayleigh flat fading channel.
+    Rayleigh(Fading),
+    /// Binary symmetric channel.
+    ///
+    /// If the crossover probability is `None`, it is obtained from the noise
+    /// standard deviation with [`BscChannel::from_noise_sigma`], which gives
+    /// the channel seen by a hard-decision receiver. Otherwise, the crossover
+    /// probability is fixed and does not depend on the Eb/N0.
+    Bsc(Option<f64>),
+}
+
+impl ChannelModel {
+    /// Creates a channel with this model.
+    ///
+    /// The parameter `noise_sigma` is the noise standard deviation, as in
+    /// [`AwgnChannel::new`].
+    pub fn channel(&self, noise_sigma: f64) -> ModelChannel {
+        match *self {
+            ChannelModel::Awgn => ModelChannel::Awgn(AwgnChannel::new(noise_sigma)),
+            ChannelModel::Rayleigh(fading) => {
+                ModelChannel::Rayleigh(RayleighChannel::new(noise_sigma, fading))
+            }
+            ChannelModel::Bsc(None) => ModelChannel::Bsc(BscChannel::from_noise_sigma(noise_sigma)),
+            ChannelModel::Bsc(Some(crossover)) => ModelChannel::Bsc(BscChannel::new(crossover)),
+        }
+    }
+}
+
+impl std::str::FromStr for ChannelModel {
+    type Err = String;
+
+    /// Parses a channel model.
+    ///
+    /// The accepted formats are `awgn`, `rayleigh` (fast fading),
+    /// `rayleigh-block-<symbols>` (block fading),
+    /// `rayleigh-doppler-<frequency>` (Doppler fading with a normalized Doppler
+    /// frequency), `bsc` (binary symmetric channel with the crossover
+    /// probability given by the Eb/N0) and `bsc-<crossover>` (binary symmetric
+    /// channel with a fixed crossover probability).
+    fn from_str(s: &str) -> Result<ChannelModel, String> {
+        let invalid = || format!("invalid channel model {s}");
+        Ok(match s {
+            "awgn" => ChannelModel::Awgn,
+            "rayleigh" => ChannelModel::Rayleigh(Fading::Fast),
+            "bsc" => ChannelModel::Bsc(None),
+            _ => {
+                if let Some(size) = s.strip_prefix("rayleigh-block-") {
+                    let size = size.parse::<usize>().map_err(|_| invalid())?;
+                    if size == 0 {
+                        return Err(invalid());
+                    }
+                    ChannelModel::Rayleigh(Fading::Block(size))
+                } else if let Some(doppler) = s.strip_prefix("rayleigh-doppler-") {
+                    let doppler = doppler.parse::<f64>().map_err(|_| invalid())?;
+                    if !doppler.is_finite() || doppler < 0.0 {
+                        return Err(invalid());
+                    }
+                    ChannelModel::Rayleigh(Fading::Doppler(doppler))
+                } else if let Some(crossover) = s.strip_prefix("bsc-") {
+                    let crossover = crossover.parse::<f64>().map_err(|_| invalid())?;
+                    if !(0.0..=0.5).contains(&crossover) {
+                        return Err(invalid());
+                    }
+                    ChannelModel::Bsc(Some(crossover))
+                } else {
+                    return Err(invalid());
+                }
+            }
+        })
+    }
+}
+
+impl std::fmt::Display for ChannelModel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChannelModel::Awgn => write!(f, "awgn"),
+            ChannelModel::Rayleigh(Fading::Fast) => write!(f, "rayleigh"),
+            ChannelModel::Rayleigh(Fading::Block(size)) => write!(f, "rayleigh-block-{size}"),
+            ChannelModel::Rayleigh(Fading::Doppler(doppler)) => {
+                write!(f, "rayleigh-doppler-{doppler}")
+            }
+            ChannelModel::Bsc(None) => write!(f, "bsc"),
+            ChannelModel::Bsc(Some(crossover)) => write!(f, "bsc-{crossover}"),
+        }
+    }
+}
+
+/// Channel created from a [`ChannelModel`].
+#[derive(Debug, Clone)]
+pub enum ModelChannel {
+    /// AWGN channel.
+    Awgn(AwgnChannel),
+    /// Rayleigh flat fading channel.
+    Rayleigh(RayleighChannel),
+    /// Binary symmetric channel.
+    Bsc(BscChannel),
+}
+
+impl ModelChannel {
+    /// Returns the hard-decision demodulator for the channel.
+    ///
+    /// For the binary symmetric channel, this returns the demodulator that
+    /// gives the LLRs of the received bits. For other channels, the symbols are
+    /// demodulated with soft decisions, so this returns `None`.
+    pub fn hard_decision_demodulator(&self) -> Option<HardDecisionDemodulator> {
+        match self {
+            ModelChannel::Bsc(channel) => Some(HardDecisionDemodulator::new(channel.crossover())),
+            _ => None,
+        }
+    }
+}
+
+impl Channel for ModelChannel {
+    fn add_noise<R: Rng, T: ChannelType>(&self, rng: &mut R, symbols: &mut [T]) {
+        match self {
+            ModelChannel::Awgn(channel) => channel.add_noise(rng, symbols),
+            ModelChannel::Rayleigh(channel) => channel.add_noise(rng, symbols),
+            ModelChannel::Bsc(channel) => channel.add_noise(rng, symbols),
+        }
+    }
+
+    fn transmit<R: Rng, T: ChannelType>(&self, rng: &mut R, symbols: &mut [T]) -> Option<Vec<f64>> {
+        match self {
+            ModelChannel::Awgn(channel) => channel.transmit(rng, symbols),
+            ModelChannel::Rayleigh(channel) => channel.transmit(rng, symbols),
+            ModelChannel::Bsc(channel) => channel.transmit(rng, symbols),
+        }
+    }
+}
+
+/// Receiver front-end impairments.
+///
+/// The impairments model the imperfect normalization of the symbols delivered
+/// by a real receiver to the demodulator, which computes the LLRs assuming
+/// that the symbols have the nominal amplitude. After the channel, each
+/// symbol `y` is replaced by `amplitude * (1 + e) * y + dc_offset`, where `e`
+/// is a normal random variable with mean zero and standard deviation
+/// `gain_jitter`, drawn independently for each symbol. For complex symbols,
+/// the DC offset is added to both the real and the imaginary part.
+///
+/// Since the gain is applied to the noisy symbols, an amplitude error does not
+/// change the SNR, but only the scaling of the LLRs, while a DC offset and the
+/// gain jitter degrade the SNR.
+///
+/// # Examples
+/// ```
+/// # use ldpc_toolbox::simulation::channel::Impairments;
+/// let impairments: Impairments = "amplitude=0.8,dc-offset=0.05".parse()?;
+/// assert_eq!(impairments.amplitude, 0.8);
+/// assert_eq!(impairments.dc_offset, 0.05);
+/// assert_eq!(impairments.gain_jitter, 0.0);
+/// assert!(!impairments.is_ideal());
+/// # Ok::<(), String>(())
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Impairments {
+    /// Amplitude scaling of the symbols.
+    pub amplitude: f64,
+    /// DC offset added to the symbols.
+    pub dc_offset: f64,
+    /// Standard deviation of the relative per-symbol gain error.
+    pub gain_jitter: f64,
+}
+
+impl Default for Impairments {
+    fn default() -> Impairments {
+        Impairments {
+            amplitude: 1.0,
+            dc_offset: 0.0,
+            gain_jitter: 0.0,
+        }
+    }
+}
+
+impl Impairments {
+    /// Returns `true` if the impairments do not modify the symbols.
+    pub fn is_ideal(&self) -> bool {
+        *self == Impairments::default()
+    }
+
+    /// Applies the impairments to a sequence of symbols.
+    ///
+    /// The symbols are modified in-place. An [Rng] is used as source of
+    /// randomness for the gain jitter. No random numbers are drawn if the gain
+    /// jitter is zero.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the gain jitter is negative or if some of the
+    /// parameters is not finite.
+    pub fn apply<R: Rng, T: ChannelType>(&self, rng: &mut R, symbols: &mut [T]) {
+        assert!(self.amplitude.is_finite() && self.dc_offset.is_finite());
+        assert!(self.gain_jitter.is_finite() && self.gain_jitter >= 0.0);
+        let dc_offset = T::dc_offset(self.dc_offset);
+        if self.gain_jitter == 0.0 {
+            for x in symbols.iter_mut() {
+                *x = *x * self.amplitude;
+                *x += dc_offset;
+            }
+        } else {
+            for x in symbols.iter_mut() {
+                let e: f64 = StandardNormal.sample(rng);
+                *x = *x * (self.amplitude * (1.0 + self.gain_jitter * e));
+                *x += dc_offset;
+            }
+        }
+    }
+}
+
+impl std::str::FromStr for Impairments {
+    type Err = String;
+
+    /// Parses the impairments.
+    ///
+    /// The format is a comma-separated list of `amplitude=<value>`,
+    /// `dc-offset=<value>` and `gain-jitter=<value>`. The parameters which are
+    /// not given take their ideal values.
+    fn from_str(s: &str) -> Result<Impairments, String> {
+        let mut impairments = Impairments::default();
+        for item in s.split(',') {
+            let invalid = || format!("invalid impairment {item}");
+            let (name, value) = item.split_once('=').ok_or_else(invalid)?;
+            let value = value.trim().parse::<f64>().map_err(|_| invalid())?;
+            if !value.is_finite() {
+                return Err(invalid());
+            }
+            match name.trim() {
+                "amplitude" => impairments.amplitude = value,
+                "dc-offset" => impairments.dc_offset = value,
+                "gain-jitter" if value >= 0.0 => impairments.gain_jitter = value,
+                _ => return Err(invalid()),
+            }
+        }
+        Ok(impairments)
+    }
+}
+
+impl std::fmt::Display for Impairments {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "amplitude={},dc-offset={},gain-jitter={}",
+            self.amplitude, self.dc_offset, self.gain_jitter
+        )
+    }
+}
+
 impl ChannelType for f64 {
     fn noise<R: Rng>(awgn_channel: &AwgnChannel, rng: &mut R) -> f64 {
         awgn_channel.distr.sample(rng)
     }
+
+    fn dc_offset(offset: f64) -> f64 {
+        offset
+    }
+
+    fn flip_signs<R: Rng>(self, crossover: f64, rng: &mut R) -> f64 {
+        if rng.gen_bool(crossover) {
+            -self
+        } else {
+            self
+        }
+    }
 }
 
 impl ChannelType for Complex<f64> {
@@ -78,6 +604,17 @@ impl ChannelType for Complex<f64> {
             awgn_channel.distr.sample(rng),
         )
     }
+
+    fn dc_offset(offset: f64) -> Complex<f64> {
+        Complex::new(offset, offset)
+    }
+
+    fn flip_signs<R: Rng>(self, crossover: f64, rng: &mut R) -> Complex<f64> {
+        Complex::new(
+            self.re.flip_signs(crossover, rng),
+            self.im.flip_signs(crossover, rng),
+        )
+    }
 }
 
 mod sealed {
@@ -111,4 +648,104 @@ mod test {
         channel.add_noise(&mut rng, &mut symbols);
         assert_eq!(&symbols, &symbols_orig);
     }
+
+    #[test]
+    fn parse_channel_model() {
+        for s in [
+            "awgn",
+            "rayleigh",
+            "rayleigh-block-100",
+            "rayleigh-doppler-0.01",
+            "bsc",
+            "bsc-0.05",
+        ] {
+            assert_eq!(s.parse::<ChannelModel>().unwrap().to_string(), s);
+        }
+        for s in [
+            "rician",
+            "rayleigh-block-0",
+            "rayleigh-doppler--1",
+            "bsc-0.6",
+            "bsc-x",
+        ] {
+            assert!(s.parse::<ChannelModel>().is_err());
+        }
+    }
+
+    #[test]
+    fn rayleigh_gains() {
+        let mut rng = rand::thread_rng();
+        let n = 100_000;
+        for fading in [Fading::Fast, Fading::Block(10), Fading::Doppler(0.01)] {
+            let channel = RayleighChannel::new(0.0, fading);
+            let gains = channel.gains(&mut rng, n);
+            assert_eq!(gains.len(), n);
+            assert!(gains.iter().all(|&g| g >= 0.0));
+            if fading != Fading::Doppler(0.01) {
+                // The Doppler process is too correlated to estimate its power
+                // from a single realization.
+                let power = gains.iter().map(|g| g * g).sum::<f64>() / n as f64;
+                assert!((power - 1.0).abs() < 0.05);
+            }
+        }
+        let gains = RayleighChannel::new(0.0, Fading::Block(10)).gains(&mut rng, 25);
+        assert!(gains[..10].iter().all(|&g| g == gains[0]));
+        assert!(gains[20..].iter().all(|&g| g == gains[20]));
+        let mut symbols = vec![1.0; 25];
+        let gains = RayleighChannel::new(0.0, Fading::Fast)
+            .transmit(&mut rng, &mut symbols)
+            .unwrap();
+        assert_eq!(symbols, gains);
+    }
+
+    #[test]
+    fn bsc() {
+        let mut rng = rand::thread_rng();
+        let n = 100_000;
+        let mut symbols = vec![1.0; n];
+        BscChannel::new(0.1).add_noise(&mut rng, &mut symbols);
+        assert!(symbols.iter().all(|&x| x == 1.0 || x == -1.0));
+        let flipped = symbols.iter().filter(|&&x| x < 0.0).count();
+        assert!((flipped as f64 / n as f64 - 0.1).abs() < 0.01);
+        assert_eq!(BscChannel::from_noise_sigma(0.0).crossover(), 0.0);
+        // Hard decisions with Eb/N0 = 6 dB for BPSK
+        let sigma = (0.5 / 10.0_f64.powf(0.6)).sqrt();
+        let crossover = BscChannel::from_noise_sigma(sigma).crossover();
+        assert!((crossover - 2.3883e-3).abs() < 1e-7);
+        assert!((erfc(-1.0) - 1.8427008).abs() < 1e-6);
+    }
+
+    #[test]
+    fn impairments() {
+        let mut rng = rand::thread_rng();
+        let impairments: Impairments = "amplitude=0.5,dc-offset=0.25".parse().unwrap();
+        assert_eq!(
+            impairments.to_string(),
+            "amplitude=0.5,dc-offset=0.25,gain-jitter=0"
+        );
+        let mut symbols = vec![1.0, -1.0];
+        impairments.apply(&mut rng, &mut symbols);
+        assert_eq!(symbols, [0.75, -0.25]);
+        let mut symbols = vec![Complex::new(1.0, -1.0)];
+        impairments.apply(&mut rng, &mut symbols);
+        assert_eq!(symbols, [Complex::new(0.75, -0.25)]);
+        let impairments: Impairments = "gain-jitter=0.1".parse().unwrap();
+        let n = 100_000;
+        let mut symbols = vec![1.0; n];
+        impairments.apply(&mut rng, &mut symbols);
+        let mean = symbols.iter().sum::<f64>() / n as f64;
+        let var = symbols.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / n as f64;
+        assert!((mean - 1.0).abs() < 0.01);
+        assert!((var.sqrt() - 0.1).abs() < 0.01);
+        assert!(Impairments::default().is_ideal());
+        for s in [
+            "amplitude",
+            "amplitude=x",
+            "gain-jitter=-0.1",
+            "phase=0.1",
+            "",
+        ] {
+            assert!(s.parse::<Impairments>().is_err());
+        }
+    }
 }
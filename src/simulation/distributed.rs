@@ -0,0 +1,894 @@
+//! Distributed BER simulation.
+//!
+//! This module splits a BER test between several worker processes, which can
+//! run in different machines. A [`Coordinator`] listens on a TCP port and
+//! assigns jobs to the workers that connect to it. Each [`Job`] consists in
+//! simulating one of the Eb/N0's of the test with its own seed, until a number
+//! of frames have been simulated or a number of frame errors have been
+//! collected. The workers run each job with an ordinary BER test (see
+//! [`run_worker`]) and send its [`Statistics`] back to the coordinator, which
+//! merges the statistics of each Eb/N0 with [`Statistics::combine`].
+//!
+//! The Eb/N0's are simulated in order. The jobs are assigned to the first
+//! Eb/N0 that has not collected the maximum number of frame errors or frames
+//! yet, so all the workers simulate the same Eb/N0 in parallel, and the
+//! remaining frame errors of the Eb/N0 are split between them. Workers can
+//! join the test at any time, and the job of a worker that disconnects is
+//! assigned to another worker.
+//!
+//! The workers must run the same BER test as the coordinator, except for the
+//! Eb/N0's, the number of frames and frame errors, and the seed, which are
+//! given by the jobs. The coordinator rejects the workers whose
+//! [`TestIdentity`] is different from its own, which detects the most common
+//! mistakes, such as using a different code or decoder.
+//!
+//! # Protocol
+//!
+//! The protocol is line based. Each message is a line of text whose fields
+//! are separated by spaces.
+//!
+//! - When a worker connects, it sends `hello <version> <identity>`, where
+//!   `<version>` is the protocol version (currently 1) and `<identity>` is its
+//!   [`TestIdentity`]. If the version or the identity do not match, the
+//!   coordinator sends `error <message>` and closes the connection.
+//! - The coordinator sends `job <id> <Eb/N0> <frames> <frame errors> <seed>`
+//!   to assign a job, or `done` when the test has finished.
+//! - The worker answers each job with `result <id> <statistics>`, and then
+//!   waits for the next job. The statistics are a list of `key=value` fields
+//!   containing the counts of the [`Statistics`].
+
+use super::{
+    ber::{CodeStatistics, Metadata, Report, Reporter, Statistics},
+    factory::Ber,
+};
+use crate::{cancel::CancellationToken, rand::stream_rng};
+use rand::Rng;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Display,
+    io::{BufRead, BufReader, ErrorKind, Write},
+    net::{TcpListener, TcpStream},
+    sync::{Arc, Condvar, Mutex},
+    time::{Duration, Instant},
+};
+use thiserror::Error;
+
+/// Distributed BER simulation error.
+#[derive(Debug, Error)]
+pub enum Error {
+    /// I/O error in the connection.
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    /// A message does not follow the protocol.
+    #[error("protocol error: {0}")]
+    Protocol(String),
+    /// The coordinator has rejected the worker.
+    #[error("rejected by the coordinator: {0}")]
+    Rejected(String),
+    /// The BER test of a job has failed.
+    #[error("job failed: {0}")]
+    Job(String),
+}
+
+// Version of the protocol
+const PROTOCOL_VERSION: u32 = 1;
+
+// Interval at which the coordinator checks for new connections and for
+// cancellation
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+// Maximum time that the coordinator waits for its connections to send `done`
+// to the workers when the test finishes
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Identity of a BER test.
+///
+/// The identity contains the fingerprint of the parity check matrix, the
+/// frame size and the number of information bits, the decoder
+/// implementation, the modulation and the channel of a BER test. The
+/// coordinator only accepts the workers whose identity is equal to its own.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TestIdentity(String);
+
+impl TestIdentity {
+    /// Returns the identity of a BER test.
+    pub fn new(test: &dyn Ber) -> TestIdentity {
+        let metadata = test.metadata();
+        TestIdentity(format!(
+            "h={:016x} n={} k={} decoder={} modulation={} channel={}",
+            metadata.h_fingerprint,
+            test.n(),
+            info_bits(test),
+            metadata.decoder,
+            metadata.modulation,
+            metadata.channel
+        ))
+    }
+}
+
+impl Display for TestIdentity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+// Number of information bits per frame, excluding the known bits.
+fn info_bits(test: &dyn Ber) -> usize {
+    (test.rate() * test.n() as f64).round() as usize
+}
+
+/// Job of a distributed BER test.
+///
+/// A job is simulated by a worker with a BER test of a single Eb/N0, which
+/// stops when `max_frames` frames have been simulated or `max_frame_errors`
+/// frame errors have been collected.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Job {
+    /// Identifier of the job.
+    pub id: u64,
+    /// Eb/N0 in dB units.
+    pub ebn0_db: f32,
+    /// Maximum number of frames.
+    pub max_frames: u64,
+    /// Maximum number of frame errors.
+    pub max_frame_errors: u64,
+    /// Master seed of the random streams.
+    pub seed: u64,
+}
+
+impl Job {
+    fn parse(fields: &[&str]) -> Result<Job, Error> {
+        let invalid = || Error::Protocol(format!("invalid job {}", fields.join(" ")));
+        let [id, ebn0_db, max_frames, max_frame_errors, seed] = fields else {
+            return Err(invalid());
+        };
+        Ok(Job {
+            id: id.parse().map_err(|_| invalid())?,
+            ebn0_db: ebn0_db.parse().map_err(|_| invalid())?,
+            max_frames: max_frames.parse().map_err(|_| invalid())?,
+            max_frame_errors: max_frame_errors.parse().map_err(|_| invalid())?,
+            seed: seed.parse().map_err(|_| invalid())?,
+        })
+    }
+}
+
+impl Display for Job {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "job {} {} {} {} {}",
+            self.id, self.ebn0_db, self.max_frames, self.max_frame_errors, self.seed
+        )
+    }
+}
+
+/// Coordinator of a distributed BER test.
+///
+/// The coordinator assigns the jobs of a BER test to the workers that connect
+/// to it and merges their results. See the [module documentation](self).
+#[derive(Debug)]
+pub struct Coordinator {
+    identity: TestIdentity,
+    k: usize,
+    metadata: Metadata,
+    ebn0s_db: Vec<f32>,
+    max_frame_errors: u64,
+    max_frames: Option<u64>,
+    frames_per_job: u64,
+    reporter: Option<Reporter>,
+    cancellation: Option<CancellationToken>,
+}
+
+impl Coordinator {
+    /// Creates a new coordinator.
+    ///
+    /// The coordinator distributes the BER test `test`, which is not run by
+    /// the coordinator, but only gives the [`TestIdentity`] and the master
+    /// seed of the test. The Eb/N0's in dB units are given in `ebn0s_db`. The
+    /// simulation of each Eb/N0 stops when `max_frame_errors` frame errors
+    /// have been collected or, if `max_frames` is not `None`, when
+    /// `max_frames` frames have been simulated.
+    ///
+    /// By default, each job contains at most 10000 frames.
+    pub fn new(
+        test: &dyn Ber,
+        ebn0s_db: &[f32],
+        max_frame_errors: u64,
+        max_frames: Option<u64>,
+    ) -> Coordinator {
+        Coordinator {
+            identity: TestIdentity::new(test),
+            k: info_bits(test),
+            metadata: test.metadata().clone(),
+            ebn0s_db: ebn0s_db.to_owned(),
+            max_frame_errors,
+            max_frames,
+            frames_per_job: 10000,
+            reporter: None,
+            cancellation: None,
+        }
+    }
+
+    /// Sets the maximum number of frames per job.
+    ///
+    /// Smaller jobs balance the load better and lose less work when a worker
+    /// disconnects, but they have more overhead.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `frames` is zero.
+    pub fn with_frames_per_job(mut self, frames: u64) -> Coordinator {
+        assert!(frames > 0);
+        self.frames_per_job = frames;
+        self
+    }
+
+    /// Sets a progress reporter.
+    ///
+    /// The statistics of each Eb/N0 are reported in order, each time that a
+    /// job of the first unfinished Eb/N0 finishes and when the Eb/N0 is
+    /// finished, in the same way as in a [`BerTest`](super::ber::BerTest).
+    pub fn with_reporter(mut self, reporter: Reporter) -> Coordinator {
+        self.reporter = Some(reporter);
+        self
+    }
+
+    /// Sets a cancellation token.
+    ///
+    /// When the token is cancelled, the coordinator stops assigning jobs and
+    /// [`Coordinator::run`] returns the statistics obtained so far.
+    pub fn with_cancellation(mut self, cancellation: CancellationToken) -> Coordinator {
+        self.cancellation = Some(cancellation);
+        self
+    }
+
+    /// Runs the distributed BER test.
+    ///
+    /// The coordinator accepts the connections of the workers on `listener`
+    /// until the test finishes. It returns the combined statistics of each
+    /// Eb/N0 for which some frames have been simulated.
+    pub fn run(self, listener: TcpListener) -> Result<Vec<Statistics>, Error> {
+        listener.set_nonblocking(true)?;
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State::new(&self)),
+            condvar: Condvar::new(),
+            identity: self.identity.clone(),
+            cancellation: self.cancellation.clone(),
+        });
+        let mut handlers = Vec::new();
+        while !shared.finished() {
+            match listener.accept() {
+                Ok((stream, _)) => {
+                    stream.set_nonblocking(false)?;
+                    let shared = Arc::clone(&shared);
+                    // Errors in a connection only affect its worker, whose
+                    // job is assigned again
+                    handlers.push(std::thread::spawn(move || {
+                        let _ = shared.handle(stream);
+                    }));
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => std::thread::sleep(POLL_INTERVAL),
+                Err(e) => return Err(e.into()),
+            }
+        }
+        shared.condvar.notify_all();
+        let deadline = Instant::now() + SHUTDOWN_TIMEOUT;
+        while handlers.iter().any(|h| !h.is_finished()) && Instant::now() < deadline {
+            std::thread::sleep(POLL_INTERVAL / 10);
+        }
+        let mut state = shared.state.lock().unwrap();
+        Ok(state.finish())
+    }
+}
+
+#[derive(Debug)]
+struct Shared {
+    state: Mutex<State>,
+    condvar: Condvar,
+    identity: TestIdentity,
+    cancellation: Option<CancellationToken>,
+}
+
+#[derive(Debug)]
+struct State {
+    points: Vec<Point>,
+    k: usize,
+    metadata: Metadata,
+    max_frame_errors: u64,
+    max_frames: Option<u64>,
+    frames_per_job: u64,
+    next_job_id: u64,
+    // Number of workers connected
+    workers: u64,
+    // Point index of each job that has been assigned and not finished
+    assigned: HashMap<u64, (usize, u64)>,
+    // Index of the first point whose final statistics have not been reported
+    next_report: usize,
+    reporter: Option<Reporter>,
+    cancelled: bool,
+}
+
+// Eb/N0 point of the test
+#[derive(Debug)]
+struct Point {
+    ebn0_db: f32,
+    parts: Vec<Statistics>,
+    frames: u64,
+    frame_errors: u64,
+    assigned_jobs: usize,
+    assigned_frames: u64,
+}
+
+impl Shared {
+    fn finished(&self) -> bool {
+        let mut state = self.state.lock().unwrap();
+        if self.cancellation.as_ref().is_some_and(|t| t.is_cancelled()) {
+            state.cancelled = true;
+        }
+        state.finished()
+    }
+
+    // Waits until there is a job for a worker. Returns None if the test has
+    // finished.
+    fn next_job(&self) -> Option<Job> {
+        let mut state = self.state.lock().unwrap();
+        loop {
+            if self.cancellation.as_ref().is_some_and(|t| t.is_cancelled()) {
+                state.cancelled = true;
+            }
+            if state.finished() {
+                return None;
+            }
+            if let Some(job) = state.assign_job() {
+                return Some(job);
+            }
+            state = self.condvar.wait_timeout(state, POLL_INTERVAL).unwrap().0;
+        }
+    }
+
+    fn handle(&self, stream: TcpStream) -> Result<(), Error> {
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let mut writer = stream;
+        let hello = read_message(&mut reader)?
+            .ok_or_else(|| Error::Protocol("connection closed before hello".to_string()))?;
+        let expected = format!("hello {PROTOCOL_VERSION} {}", self.identity);
+        if hello != expected {
+            let message = match hello.split(' ').nth(1) {
+                Some(version) if version != PROTOCOL_VERSION.to_string() => {
+                    format!("unsupported protocol version {version}")
+                }
+                _ => format!("the worker test does not match {}", self.identity),
+            };
+            writeln!(writer, "error {message}")?;
+            return Err(Error::Rejected(message));
+        }
+        self.state.lock().unwrap().workers += 1;
+        let ret = self.run_jobs(&mut reader, &mut writer);
+        self.state.lock().unwrap().workers -= 1;
+        ret
+    }
+
+    fn run_jobs(
+        &self,
+        reader: &mut BufReader<TcpStream>,
+        writer: &mut TcpStream,
+    ) -> Result<(), Error> {
+        while let Some(job) = self.next_job() {
+            let result = Self::run_job(reader, writer, &job);
+            let mut state = self.state.lock().unwrap();
+            match result {
+                Ok(statistics) => state.complete(job.id, statistics),
+                Err(e) => {
+                    state.abandon(job.id);
+                    self.condvar.notify_all();
+                    return Err(e);
+                }
+            }
+            self.condvar.notify_all();
+        }
+        writeln!(writer, "done")?;
+        Ok(())
+    }
+
+    fn run_job(
+        reader: &mut BufReader<TcpStream>,
+        writer: &mut TcpStream,
+        job: &Job,
+    ) -> Result<StatisticsCounts, Error> {
+        writeln!(writer, "{job}")?;
+        let message = read_message(reader)?
+            .ok_or_else(|| Error::Protocol("connection closed during a job".to_string()))?;
+        let fields = message.split(' ').collect::<Vec<_>>();
+        match fields[..] {
+            ["result", id, ref counts @ ..] if id == job.id.to_string() => {
+                StatisticsCounts::parse(counts)
+            }
+            _ => Err(Error::Protocol(format!("unexpected message {message}"))),
+        }
+    }
+}
+
+impl State {
+    fn new(coordinator: &Coordinator) -> State {
+        State {
+            points: coordinator
+                .ebn0s_db
+                .iter()
+                .map(|&ebn0_db| Point {
+                    ebn0_db,
+                    parts: Vec::new(),
+                    frames: 0,
+                    frame_errors: 0,
+                    assigned_jobs: 0,
+                    assigned_frames: 0,
+                })
+                .collect(),
+            k: coordinator.k,
+            metadata: coordinator.metadata.clone(),
+            max_frame_errors: coordinator.max_frame_errors,
+            max_frames: coordinator.max_frames,
+            frames_per_job: coordinator.frames_per_job,
+            next_job_id: 0,
+            workers: 0,
+            assigned: HashMap::new(),
+            next_report: 0,
+            reporter: coordinator.reporter.clone(),
+            cancelled: false,
+        }
+    }
+
+    fn point_complete(&self, point: &Point) -> bool {
+        point.frame_errors >= self.max_frame_errors
+            || self.max_frames.is_some_and(|max| point.frames >= max)
+    }
+
+    fn finished(&self) -> bool {
+        self.cancelled
+            || (self.assigned.is_empty() && self.points.iter().all(|p| self.point_complete(p)))
+    }
+
+    fn assign_job(&mut self) -> Option<Job> {
+        let (index, max_frames) = self.points.iter().enumerate().find_map(|(j, point)| {
+            if self.point_complete(point) {
+                return None;
+            }
+            let frames = match self.max_frames {
+                Some(max) => self
+                    .frames_per_job
+                    .min(max.saturating_sub(point.frames + point.assigned_frames)),
+                None => self.frames_per_job,
+            };
+            (frames > 0).then_some((j, frames))
+        })?;
+        let id = self.next_job_id;
+        self.next_job_id += 1;
+        // The remaining frame errors are split between the workers, which
+        // simulate the same Eb/N0 in parallel, so that the number of frame
+        // errors collected does not exceed the maximum by much
+        let point = &mut self.points[index];
+        let max_frame_errors = (self.max_frame_errors - point.frame_errors).div_ceil(self.workers);
+        point.assigned_jobs += 1;
+        point.assigned_frames += max_frames;
+        self.assigned.insert(id, (index, max_frames));
+        Some(Job {
+            id,
+            ebn0_db: point.ebn0_db,
+            max_frames,
+            max_frame_errors,
+            seed: stream_rng(self.metadata.seed, "distributed job", id).gen(),
+        })
+    }
+
+    fn unassign(&mut self, id: u64) -> Option<usize> {
+        let (index, frames) = self.assigned.remove(&id)?;
+        let point = &mut self.points[index];
+        point.assigned_jobs -= 1;
+        point.assigned_frames -= frames;
+        Some(index)
+    }
+
+    fn abandon(&mut self, id: u64) {
+        self.unassign(id);
+    }
+
+    fn complete(&mut self, id: u64, counts: StatisticsCounts) {
+        let Some(index) = self.unassign(id) else {
+            return;
+        };
+        let k = self.k;
+        let point = &mut self.points[index];
+        let mut statistics = counts.statistics(point.ebn0_db, k);
+        statistics.metadata = Some(self.metadata.clone());
+        point.frames += statistics.num_frames;
+        point.frame_errors += statistics.ldpc.frame_errors;
+        point.parts.push(statistics);
+        self.report();
+    }
+
+    // Reports the final statistics of the points that have finished, and the
+    // current statistics of the first point that has not finished.
+    fn report(&mut self) {
+        while let Some(point) = self.points.get(self.next_report) {
+            if !(self.point_complete(point) && point.assigned_jobs == 0) {
+                break;
+            }
+            self.report_point(self.next_report);
+            self.next_report += 1;
+        }
+        self.report_point(self.next_report);
+    }
+
+    fn report_point(&self, index: usize) {
+        if let (Some(reporter), Some(statistics)) = (&self.reporter, self.statistics(index)) {
+            // Errors are ignored, since the receiver not being interested in
+            // the reports should not stop the test
+            let _ = reporter.tx.send(Report::Statistics(statistics));
+        }
+    }
+
+    fn statistics(&self, index: usize) -> Option<Statistics> {
+        let point = self.points.get(index)?;
+        (!point.parts.is_empty()).then(|| Statistics::combine(&point.parts, self.k))
+    }
+
+    fn finish(&mut self) -> Vec<Statistics> {
+        // The points that have not finished (if the test has been cancelled)
+        // are reported with the statistics obtained so far
+        for index in self.next_report..self.points.len() {
+            self.report_point(index);
+        }
+        if let Some(reporter) = &self.reporter {
+            let _ = reporter.tx.send(Report::Finished);
+        }
+        (0..self.points.len())
+            .filter_map(|index| self.statistics(index))
+            .collect()
+    }
+}
+
+/// Runs a worker of a distributed BER test.
+///
+/// The worker connects to the coordinator through `stream`, identifying
+/// itself with `identity`, which is usually obtained from a BER test
+/// configured in the same way as the test of the coordinator. Each job
+/// assigned by the coordinator is simulated by calling `run`, which must run a
+/// BER test of the Eb/N0 of the job, limited to the maximum number of frames
+/// and frame errors of the job and using the seed of the job, and return its
+/// statistics.
+///
+/// The function returns the number of jobs that have been run when the
+/// coordinator indicates that the test has finished or closes the connection.
+pub fn run_worker<F>(stream: TcpStream, identity: &TestIdentity, mut run: F) -> Result<u64, Error>
+where
+    F: FnMut(&Job) -> Result<Statistics, Box<dyn std::error::Error>>,
+{
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut writer = stream;
+    writeln!(writer, "hello {PROTOCOL_VERSION} {identity}")?;
+    let mut jobs = 0;
+    while let Some(message) = read_message(&mut reader)? {
+        let fields = message.split(' ').collect::<Vec<_>>();
+        match fields[..] {
+            ["done"] => break,
+            ["error", ..] => {
+                return Err(Error::Rejected(message["error ".len()..].to_string()));
+            }
+            ["job", ref job @ ..] => {
+                let job = Job::parse(job)?;
+                let statistics = run(&job).map_err(|e| Error::Job(e.to_string()))?;
+                writeln!(
+                    writer,
+                    "result {} {}",
+                    job.id,
+                    StatisticsCounts::from(&statistics)
+                )?;
+                jobs += 1;
+            }
+            _ => return Err(Error::Protocol(format!("unexpected message {message}"))),
+        }
+    }
+    Ok(jobs)
+}
+
+// Reads a message. Returns None if the connection has been closed.
+fn read_message(reader: &mut BufReader<TcpStream>) -> Result<Option<String>, Error> {
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    Ok(Some(line.trim_end().to_string()))
+}
+
+// Counts of the statistics of a job, which are sent by the workers. The rates
+// and averages are computed again by the coordinator.
+#[derive(Debug, Clone, PartialEq, Default)]
+struct StatisticsCounts {
+    num_frames: u64,
+    total_iterations: u64,
+    false_decodes: u64,
+    false_decode_distances: BTreeMap<usize, u64>,
+    elapsed: Duration,
+    throughput_mbps: f64,
+    warm_up_frames: u64,
+    worker_utilization: Vec<f64>,
+    max_llr_magnitude: Option<f64>,
+    max_iterations: usize,
+    ldpc: [u64; 3],
+    bch: Option<[u64; 3]>,
+}
+
+impl From<&Statistics> for StatisticsCounts {
+    fn from(statistics: &Statistics) -> StatisticsCounts {
+        let counts = |s: &CodeStatistics| [s.bit_errors, s.frame_errors, s.correct_iterations];
+        StatisticsCounts {
+            num_frames: statistics.num_frames,
+            total_iterations: statistics.total_iterations,
+            false_decodes: statistics.false_decodes,
+            false_decode_distances: statistics.false_decode_distances.clone(),
+            elapsed: statistics.elapsed,
+            throughput_mbps: statistics.throughput_mbps,
+            warm_up_frames: statistics.warm_up_frames,
+            worker_utilization: statistics.worker_utilization.clone(),
+            max_llr_magnitude: statistics.max_llr_magnitude,
+            max_iterations: statistics.max_iterations,
+            ldpc: counts(&statistics.ldpc),
+            bch: statistics.bch.as_ref().map(counts),
+        }
+    }
+}
+
+impl StatisticsCounts {
+    fn statistics(&self, ebn0_db: f32, k: usize) -> Statistics {
+        let code_statistics = |[bit_errors, frame_errors, correct_iterations]: [u64; 3]| {
+            CodeStatistics::from_counts(
+                bit_errors,
+                frame_errors,
+                correct_iterations,
+                self.num_frames,
+                k,
+            )
+        };
+        Statistics {
+            ebn0_db,
+            num_frames: self.num_frames,
+            total_iterations: self.total_iterations,
+            false_decodes: self.false_decodes,
+            false_decode_distances: self.false_decode_distances.clone(),
+            average_iterations: self.total_iterations as f64 / self.num_frames as f64,
+            elapsed: self.elapsed,
+            throughput_mbps: self.throughput_mbps,
+            warm_up_frames: self.warm_up_frames,
+            worker_utilization: self.worker_utilization.clone(),
+            max_llr_magnitude: self.max_llr_magnitude,
+            max_iterations: self.max_iterations,
+            ldpc: code_statistics(self.ldpc),
+            bch: self.bch.map(code_statistics),
+            metadata: None,
+        }
+    }
+
+    fn parse(fields: &[&str]) -> Result<StatisticsCounts, Error> {
+        let mut counts = StatisticsCounts::default();
+        for field in fields {
+            let invalid = || Error::Protocol(format!("invalid statistics field {field}"));
+            let (key, value) = field.split_once('=').ok_or_else(invalid)?;
+            let list = |value: &str| {
+                value
+                    .split(',')
+                    .map(|x| x.parse::<u64>())
+                    .collect::<Result<Vec<_>, _>>()
+                    .ok()
+                    .and_then(|x| <[u64; 3]>::try_from(x).ok())
+                    .ok_or_else(invalid)
+            };
+            match key {
+                "frames" => counts.num_frames = value.parse().map_err(|_| invalid())?,
+                "iterations" => counts.total_iterations = value.parse().map_err(|_| invalid())?,
+                "false_decodes" => counts.false_decodes = value.parse().map_err(|_| invalid())?,
+                "distances" => {
+                    for entry in value.split(',') {
+                        let (distance, count) = entry.split_once(':').ok_or_else(invalid)?;
+                        counts.false_decode_distances.insert(
+                            distance.parse().map_err(|_| invalid())?,
+                            count.parse().map_err(|_| invalid())?,
+                        );
+                    }
+                }
+                "elapsed_ns" => {
+                    counts.elapsed = Duration::from_nanos(value.parse().map_err(|_| invalid())?)
+                }
+                "throughput" => counts.throughput_mbps = value.parse().map_err(|_| invalid())?,
+                "warm_up" => counts.warm_up_frames = value.parse().map_err(|_| invalid())?,
+                "utilization" => {
+                    counts.worker_utilization = value
+                        .split(',')
+                        .map(|x| x.parse())
+                        .collect::<Result<_, _>>()
+                        .map_err(|_| invalid())?
+                }
+                "max_llr" => counts.max_llr_magnitude = Some(value.parse().map_err(|_| invalid())?),
+                "max_iter" => counts.max_iterations = value.parse().map_err(|_| invalid())?,
+                "ldpc" => counts.ldpc = list(value)?,
+                "bch" => counts.bch = Some(list(value)?),
+                _ => return Err(invalid()),
+            }
+        }
+        if counts.num_frames == 0 {
+            return Err(Error::Protocol("statistics without frames".to_string()));
+        }
+        Ok(counts)
+    }
+}
+
+impl Display for StatisticsCounts {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let join = |values: &[u64]| {
+            values
+                .iter()
+                .map(|x| x.to_string())
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+        write!(
+            f,
+            "frames={} iterations={} false_decodes={} elapsed_ns={} throughput={} \
+             warm_up={} max_iter={} ldpc={}",
+            self.num_frames,
+            self.total_iterations,
+            self.false_decodes,
+            self.elapsed.as_nanos(),
+            self.throughput_mbps,
+            self.warm_up_frames,
+            self.max_iterations,
+            join(&self.ldpc)
+        )?;
+        if let Some(bch) = &self.bch {
+            write!(f, " bch={}", join(bch))?;
+        }
+        if !self.false_decode_distances.is_empty() {
+            let distances = self
+                .false_decode_distances
+                .iter()
+                .map(|(d, n)| format!("{d}:{n}"))
+                .collect::<Vec<_>>();
+            write!(f, " distances={}", distances.join(","))?;
+        }
+        if !self.worker_utilization.is_empty() {
+            let utilization = self
+                .worker_utilization
+                .iter()
+                .map(|x| x.to_string())
+                .collect::<Vec<_>>();
+            write!(f, " utilization={}", utilization.join(","))?;
+        }
+        if let Some(max_llr) = self.max_llr_magnitude {
+            write!(f, " max_llr={max_llr}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        codes::examples::ExampleCode,
+        decoder::factory::DecoderImplementation,
+        simulation::{ber::BerTest, modulation::Bpsk},
+    };
+
+    fn ber_test(
+        decoder: DecoderImplementation,
+        ebn0s_db: &[f32],
+        max_frame_errors: u64,
+        max_frames: u64,
+        seed: u64,
+    ) -> BerTest<Bpsk> {
+        BerTest::<Bpsk>::new(
+            ExampleCode::Qc42_21.h(),
+            decoder,
+            None,
+            None,
+            max_frame_errors,
+            20,
+            ebn0s_db,
+            None,
+            0,
+        )
+        .unwrap()
+        .with_max_frames(max_frames)
+        .with_seed(seed)
+    }
+
+    #[test]
+    fn statistics_roundtrip() {
+        let statistics = Box::new(ber_test(DecoderImplementation::Phif64, &[1.0], 20, 500, 1))
+            .run()
+            .unwrap()
+            .remove(0);
+        let counts = StatisticsCounts::from(&statistics);
+        let message = counts.to_string();
+        let parsed = StatisticsCounts::parse(&message.split(' ').collect::<Vec<_>>()).unwrap();
+        assert_eq!(parsed, counts);
+        let k = 21;
+        let rebuilt = parsed.statistics(1.0, k);
+        assert_eq!(rebuilt.num_frames, statistics.num_frames);
+        assert_eq!(rebuilt.ldpc, statistics.ldpc);
+        assert_eq!(Statistics::combine(&[rebuilt], k).ldpc, statistics.ldpc);
+        assert!(StatisticsCounts::parse(&["frames=10", "ldpc=1,2"]).is_err());
+        assert!(StatisticsCounts::parse(&["unknown=1"]).is_err());
+    }
+
+    #[test]
+    fn distributed_test() {
+        let decoder = DecoderImplementation::Phif64;
+        let ebn0s_db = [0.0, 3.0];
+        let (max_frame_errors, max_frames) = (50, 2000);
+        let test = ber_test(decoder, &ebn0s_db, max_frame_errors, max_frames, 42);
+        let identity = TestIdentity::new(&test);
+        let (tx, rx) = std::sync::mpsc::channel();
+        let reporter = Reporter {
+            tx,
+            interval: Duration::ZERO,
+        };
+        let coordinator = Coordinator::new(&test, &ebn0s_db, max_frame_errors, Some(max_frames))
+            .with_frames_per_job(300)
+            .with_reporter(reporter);
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let workers = (0..2)
+            .map(|_| {
+                let identity = identity.clone();
+                std::thread::spawn(move || {
+                    let stream = TcpStream::connect(addr).unwrap();
+                    run_worker(stream, &identity, |job| {
+                        let test = ber_test(
+                            decoder,
+                            &[job.ebn0_db],
+                            job.max_frame_errors,
+                            job.max_frames,
+                            job.seed,
+                        );
+                        Ok(Box::new(test).run()?.remove(0))
+                    })
+                    .unwrap()
+                })
+            })
+            .collect::<Vec<_>>();
+        let statistics = coordinator.run(listener).unwrap();
+        let jobs = workers.into_iter().map(|w| w.join().unwrap()).sum::<u64>();
+        assert!(jobs >= 2);
+        assert_eq!(statistics.len(), 2);
+        for (stats, &ebn0_db) in statistics.iter().zip(&ebn0s_db) {
+            assert_eq!(stats.ebn0_db, ebn0_db);
+            assert!(stats.num_frames <= max_frames);
+            assert!(stats.ldpc.frame_errors >= max_frame_errors || stats.num_frames == max_frames);
+            assert_eq!(stats.metadata.as_ref(), Some(test.metadata()));
+        }
+        let reports = rx.try_iter().collect::<Vec<_>>();
+        assert_eq!(reports.last(), Some(&Report::Finished));
+        assert_eq!(
+            reports[reports.len() - 2],
+            Report::Statistics(statistics[1].clone())
+        );
+    }
+
+    #[test]
+    fn identity_mismatch() {
+        let decoder = DecoderImplementation::Phif64;
+        let test = ber_test(decoder, &[1.0], 10, 100, 0);
+        let other = ber_test(DecoderImplementation::Aminstarf64, &[1.0], 10, 100, 0);
+        assert_ne!(TestIdentity::new(&test), TestIdentity::new(&other));
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let cancellation = CancellationToken::new();
+        let coordinator =
+            Coordinator::new(&test, &[1.0], 10, Some(100)).with_cancellation(cancellation.clone());
+        let coordinator = std::thread::spawn(move || coordinator.run(listener).unwrap());
+        let stream = TcpStream::connect(addr).unwrap();
+        let result = run_worker(stream, &TestIdentity::new(&other), |_| unreachable!());
+        assert!(matches!(result, Err(Error::Rejected(_))));
+        cancellation.cancel();
+        assert!(coordinator.join().unwrap().is_empty());
+    }
+}
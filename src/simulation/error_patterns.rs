@@ -0,0 +1,358 @@
+//! Error pattern capture and clustering.
+//!
+//! This module contains an [`ErrorPatternCapture`] that collects the error
+//! patterns of the failed frames of a BER test (see
+//! [`BerTest::with_error_patterns`](super::ber::BerTest::with_error_patterns)),
+//! and the function [`cluster_patterns`], which groups the error patterns that
+//! affect mostly the same variable nodes. In the error floor region, the
+//! failures of an LDPC decoder are usually caused by a few small structures of
+//! the Tanner graph, such as trapping sets and low-weight codewords, so the
+//! most frequent clusters point to the parts of the graph that limit the
+//! performance of the code.
+//!
+//! An error pattern is the set of bits of the codeword in which the decoded
+//! codeword differs from the transmitted codeword. Two error patterns are
+//! grouped together if their *overlap*, defined as the size of their
+//! intersection divided by the size of the smallest pattern, is at least a
+//! threshold. The patterns are processed by increasing size, and each pattern
+//! joins the existing cluster whose first (and smallest) pattern has the
+//! largest overlap with it, or forms a new cluster. The variable nodes of a
+//! cluster are those that are in error in at least half of its patterns. They
+//! are described as an (a, b) trapping set, where `a` is the number of
+//! variable nodes and `b` is the number of checks that are connected to an odd
+//! number of them.
+//!
+//! # Examples
+//! ```
+//! # use ldpc_toolbox::{
+//! #     codes::examples::ExampleCode,
+//! #     simulation::error_patterns::{cluster_patterns, ErrorPattern},
+//! # };
+//! let h = ExampleCode::Gallager20_8.h();
+//! let pattern = |positions: &[usize]| ErrorPattern {
+//!     ebn0_db: 1.0,
+//!     positions: positions.to_vec(),
+//!     false_decode: false,
+//! };
+//! let patterns = [
+//!     pattern(&[0, 1]),
+//!     pattern(&[0, 1, 7]),
+//!     pattern(&[4]),
+//!     pattern(&[0, 1, 2]),
+//! ];
+//! let clusters = cluster_patterns(&h, &patterns, 0.5);
+//! assert_eq!(clusters.len(), 2);
+//! assert_eq!(clusters[0].num_patterns, 3);
+//! assert_eq!(clusters[0].variables, vec![0, 1]);
+//! ```
+
+use crate::sparse::SparseMatrix;
+use std::{collections::BTreeMap, sync::Mutex};
+
+/// Error pattern of a failed frame.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ErrorPattern {
+    /// Eb/N0 of the frame in dB.
+    pub ebn0_db: f32,
+    /// Positions of the bits in error, in increasing order.
+    ///
+    /// The positions are indices of the columns of the parity check matrix.
+    pub positions: Vec<usize>,
+    /// The decoder converged to a codeword different from the transmitted
+    /// one.
+    pub false_decode: bool,
+}
+
+/// Capture of error patterns.
+///
+/// The capture stores the error patterns of the failed frames of a BER test,
+/// up to a maximum number of patterns. It is shared by the worker threads of
+/// the test by means of an `Arc`.
+#[derive(Debug)]
+pub struct ErrorPatternCapture {
+    max_patterns: usize,
+    patterns: Mutex<Vec<ErrorPattern>>,
+}
+
+impl ErrorPatternCapture {
+    /// Creates a new capture that stores at most `max_patterns` patterns.
+    pub fn new(max_patterns: usize) -> ErrorPatternCapture {
+        ErrorPatternCapture {
+            max_patterns,
+            patterns: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Returns the maximum number of patterns stored.
+    pub fn max_patterns(&self) -> usize {
+        self.max_patterns
+    }
+
+    /// Returns the number of patterns stored.
+    pub fn len(&self) -> usize {
+        self.patterns.lock().unwrap().len()
+    }
+
+    /// Returns `true` if no patterns have been stored.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns a copy of the patterns stored.
+    pub fn patterns(&self) -> Vec<ErrorPattern> {
+        self.patterns.lock().unwrap().clone()
+    }
+
+    pub(crate) fn is_full(&self) -> bool {
+        self.len() >= self.max_patterns
+    }
+
+    // Stores a pattern, unless the capture is full.
+    pub(crate) fn record(&self, pattern: ErrorPattern) {
+        let mut patterns = self.patterns.lock().unwrap();
+        if patterns.len() < self.max_patterns {
+            patterns.push(pattern);
+        }
+    }
+}
+
+/// Cluster of error patterns.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrorCluster {
+    /// Number of patterns in the cluster.
+    pub num_patterns: usize,
+    /// Number of patterns in the cluster that are false decodes.
+    pub false_decodes: usize,
+    /// Variable nodes in error in at least half of the patterns.
+    ///
+    /// The variable nodes are sorted in increasing order.
+    pub variables: Vec<usize>,
+    /// Checks connected to an odd number of the variable nodes.
+    ///
+    /// These are the checks that are unsatisfied when the variable nodes are
+    /// in error. The checks are sorted in increasing order.
+    pub unsatisfied_checks: Vec<usize>,
+}
+
+impl ErrorCluster {
+    /// Returns the (a, b) trapping set parameters of the cluster.
+    ///
+    /// These are the number of variable nodes and the number of unsatisfied
+    /// checks. A cluster with `b = 0` corresponds to a codeword.
+    pub fn trapping_set_parameters(&self) -> (usize, usize) {
+        (self.variables.len(), self.unsatisfied_checks.len())
+    }
+}
+
+/// Clusters error patterns by support overlap.
+///
+/// The error patterns in `patterns` are grouped as described in the [module
+/// documentation](self), with the threshold `min_overlap`. The patterns
+/// without errors are ignored. The clusters are returned by decreasing number
+/// of patterns.
+///
+/// # Panics
+/// Panics if `min_overlap` is not in the interval (0, 1], or if a pattern has
+/// a position outside of the columns of `h`.
+pub fn cluster_patterns(
+    h: &SparseMatrix,
+    patterns: &[ErrorPattern],
+    min_overlap: f64,
+) -> Vec<ErrorCluster> {
+    assert!(
+        min_overlap > 0.0 && min_overlap <= 1.0,
+        "the minimum overlap must be in (0, 1]"
+    );
+    let mut supports = patterns
+        .iter()
+        .filter(|pattern| !pattern.positions.is_empty())
+        .map(|pattern| {
+            let mut support = pattern.positions.clone();
+            support.sort_unstable();
+            support.dedup();
+            assert!(
+                support.last().is_some_and(|&v| v < h.num_cols()),
+                "error pattern outside of the parity check matrix"
+            );
+            (support, pattern.false_decode)
+        })
+        .collect::<Vec<_>>();
+    // The sort is stable, so patterns of the same size keep their order
+    supports.sort_by_key(|(support, _)| support.len());
+
+    // Each group contains the indices of its patterns in supports. The first
+    // pattern is the leader of the group.
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    for (j, (support, _)) in supports.iter().enumerate() {
+        let best = groups
+            .iter_mut()
+            .map(|group| (overlap(&supports[group[0]].0, support), group))
+            .filter(|(o, _)| *o >= min_overlap)
+            // max_by returns the last maximum, so the groups are reversed to
+            // prefer the oldest group in case of ties
+            .rev()
+            .max_by(|(a, _), (b, _)| a.total_cmp(b));
+        match best {
+            Some((_, group)) => group.push(j),
+            None => groups.push(vec![j]),
+        }
+    }
+
+    let mut clusters = groups
+        .iter()
+        .map(|group| {
+            let mut counts = BTreeMap::new();
+            for &j in group {
+                for &v in &supports[j].0 {
+                    *counts.entry(v).or_insert(0) += 1;
+                }
+            }
+            let variables = counts
+                .into_iter()
+                .filter(|&(_, count)| 2 * count >= group.len())
+                .map(|(v, _)| v)
+                .collect::<Vec<_>>();
+            let mut check_counts = BTreeMap::new();
+            for &v in &variables {
+                for &c in h.iter_col(v) {
+                    *check_counts.entry(c).or_insert(0) += 1;
+                }
+            }
+            ErrorCluster {
+                num_patterns: group.len(),
+                false_decodes: group.iter().filter(|&&j| supports[j].1).count(),
+                variables,
+                unsatisfied_checks: check_counts
+                    .into_iter()
+                    .filter(|&(_, count)| count % 2 == 1)
+                    .map(|(c, _)| c)
+                    .collect(),
+            }
+        })
+        .collect::<Vec<_>>();
+    clusters.sort_by_key(|cluster| std::cmp::Reverse(cluster.num_patterns));
+    clusters
+}
+
+// Size of the intersection of two sorted supports divided by the size of the
+// smallest one.
+fn overlap(a: &[usize], b: &[usize]) -> f64 {
+    let (mut i, mut j, mut common) = (0, 0, 0);
+    while i < a.len() && j < b.len() {
+        match a[i].cmp(&b[j]) {
+            std::cmp::Ordering::Less => i += 1,
+            std::cmp::Ordering::Greater => j += 1,
+            std::cmp::Ordering::Equal => {
+                common += 1;
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    common as f64 / a.len().min(b.len()) as f64
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        codes::examples::ExampleCode,
+        decoder::factory::DecoderImplementation,
+        simulation::{ber::BerTest, modulation::Bpsk},
+    };
+    use std::sync::Arc;
+
+    fn pattern(positions: &[usize], false_decode: bool) -> ErrorPattern {
+        ErrorPattern {
+            ebn0_db: 2.0,
+            positions: positions.to_vec(),
+            false_decode,
+        }
+    }
+
+    #[test]
+    fn overlaps() {
+        assert_eq!(overlap(&[1, 2, 3], &[2, 3, 4, 5]), 2.0 / 3.0);
+        assert_eq!(overlap(&[1], &[0, 1, 2]), 1.0);
+        assert_eq!(overlap(&[1, 2], &[3, 4]), 0.0);
+    }
+
+    #[test]
+    fn clusters() {
+        let h = ExampleCode::Gallager20_8.h();
+        let patterns = [
+            pattern(&[3, 7, 11, 15], false),
+            pattern(&[], false),
+            pattern(&[0, 5], false),
+            pattern(&[11, 3, 7], false),
+            pattern(&[3, 7, 11, 15, 19], true),
+            pattern(&[0, 5, 6, 9], false),
+            pattern(&[3, 7, 16, 18], false),
+        ];
+        let clusters = cluster_patterns(&h, &patterns, 0.6);
+        assert_eq!(clusters.len(), 2);
+        assert_eq!(clusters[0].num_patterns, 4);
+        assert_eq!(clusters[0].false_decodes, 1);
+        assert_eq!(clusters[0].variables, vec![3, 7, 11, 15]);
+        assert_eq!(clusters[1].num_patterns, 2);
+        assert_eq!(clusters[1].variables, vec![0, 5, 6, 9]);
+        for cluster in &clusters {
+            let (a, b) = cluster.trapping_set_parameters();
+            assert_eq!(a, cluster.variables.len());
+            assert_eq!(b, cluster.unsatisfied_checks.len());
+            for &c in &cluster.unsatisfied_checks {
+                let degree = h
+                    .iter_row(c)
+                    .filter(|v| cluster.variables.contains(v))
+                    .count();
+                assert_eq!(degree % 2, 1);
+            }
+        }
+        // With a higher threshold the last pattern forms its own cluster
+        assert_eq!(cluster_patterns(&h, &patterns, 1.0).len(), 3);
+    }
+
+    #[test]
+    fn capture() {
+        let capture = ErrorPatternCapture::new(2);
+        assert!(capture.is_empty());
+        for j in 0..3 {
+            capture.record(pattern(&[j], false));
+        }
+        assert!(capture.is_full());
+        assert_eq!(
+            capture.patterns(),
+            vec![pattern(&[0], false), pattern(&[1], false)]
+        );
+    }
+
+    #[test]
+    fn ber_test() {
+        let capture = Arc::new(ErrorPatternCapture::new(5));
+        let h = ExampleCode::Qc42_21.h();
+        let test = BerTest::<Bpsk>::new(
+            h.clone(),
+            DecoderImplementation::HLPhif64,
+            None,
+            None,
+            10,
+            20,
+            &[0.0],
+            None,
+            0,
+        )
+        .unwrap()
+        .with_max_frames(1000)
+        .with_seed(42)
+        .with_error_patterns(Arc::clone(&capture));
+        let statistics = test.run().unwrap();
+        assert_eq!(capture.len(), 5.min(statistics[0].ldpc.frame_errors as usize));
+        for pattern in capture.patterns() {
+            assert_eq!(pattern.ebn0_db, 0.0);
+            assert!(!pattern.positions.is_empty());
+            assert!(pattern.positions.windows(2).all(|w| w[0] < w[1]));
+            assert!(pattern.positions.iter().all(|&v| v < h.num_cols()));
+        }
+        assert!(!cluster_patterns(&h, &capture.patterns(), 0.5).is_empty());
+    }
+}
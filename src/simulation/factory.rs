@@ -4,13 +4,28 @@
 //! trait object using the [`BerTestBuilder`].
 
 use super::{
-    ber::{BerTest, Reporter, Statistics},
-    modulation::{Bpsk, Psk8},
+    ber::{BerTest, Metadata, Reporter, Statistics},
+    channel::{ChannelModel, Impairments},
+    error_patterns::ErrorPatternCapture,
+    frame_errors::FrameErrorPolicy,
+    interleaving::Interleaver,
+    iterations::IterationSchedule,
+    known_bits::KnownBits,
+    llr_transform::LlrTransform,
+    modulation::{self, Apsk16, Apsk16Modulator, Bpsk, Psk8, Qpsk, APSK16_DEFAULT_RING_RATIO},
+    ordering::BitOrdering,
+    puncturing::{Puncturer, PuncturingPattern},
+    scrambling::Scrambler,
+    shortening::Shortening,
+    snr_profile::SnrProfile,
+    training_data::TrainingDataSink,
 };
 use crate::{
+    cancel::CancellationToken,
     decoder::factory::{DecoderFactory, DecoderImplementation},
     sparse::SparseMatrix,
 };
+use std::sync::Arc;
 
 /// BER test.
 ///
@@ -18,7 +33,8 @@ use crate::{
 pub trait Ber {
     /// Runs the BER test.
     ///
-    /// This function runs the BER test until completion. It returns a list of
+    /// This function runs the BER test until completion or until it is
+    /// cancelled (see [`BerTestBuilder::cancellation`]). It returns a list of
     /// statistics for each Eb/N0, or an error.
     fn run(self: Box<Self>) -> Result<Vec<Statistics>, Box<dyn std::error::Error>>;
 
@@ -36,30 +52,97 @@ pub trait Ber {
     fn k(&self) -> usize;
 
     /// Returns the rate of the code.
+    ///
+    /// Known bits are not counted as information bits when computing the rate.
     fn rate(&self) -> f64;
+
+    /// Returns the metadata of the BER test.
+    ///
+    /// The metadata identifies the parity check matrix, decoder and version
+    /// of ldpc-toolbox used in the test.
+    fn metadata(&self) -> &Metadata;
 }
 
 /// BER test builder.
 ///
 /// This struct contains all the parameters needed to create a BER test.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct BerTestBuilder<'a, Dec = DecoderImplementation> {
     /// LDPC parity check matrix.
-    pub h: SparseMatrix,
+    pub h: Arc<SparseMatrix>,
     /// LDPC decoder implementation.
     pub decoder_implementation: Dec,
     /// Modulation.
     pub modulation: Modulation,
+    /// Ring ratio of the 16APSK constellation.
+    ///
+    /// This is only used with [`Modulation::Apsk16`]. If it is `None`,
+    /// [`APSK16_DEFAULT_RING_RATIO`] is used.
+    pub apsk16_ring_ratio: Option<f64>,
+    /// Channel model.
+    pub channel: ChannelModel,
+    /// Receiver front-end impairments.
+    ///
+    /// See [`BerTest::with_impairments`].
+    pub impairments: Impairments,
+    /// Time-varying SNR profile and width of its Eb/N0 bins (in dB).
+    ///
+    /// If this is not `None`, the frames of the profile are simulated instead
+    /// of the Eb/N0's in `ebn0s_db`. See [`BerTest::with_snr_profile`].
+    pub snr_profile: Option<(SnrProfile, f32)>,
     /// Codeword puncturing pattern.
-    pub puncturing_pattern: Option<&'a [bool]>,
+    ///
+    /// The [`Puncturer`] is obtained from the pattern and the codeword length
+    /// of `h` (see [`PuncturingPattern::puncturer`]).
+    pub puncturing: Option<PuncturingPattern>,
     /// Codeword interleaving.
     ///
     /// A negative value indicates that the columns should be read backwards.
     pub interleaving_columns: Option<isize>,
+    /// Codeword scrambler.
+    ///
+    /// The scrambler is applied to the punctured codeword, before interleaving.
+    pub scrambler: Option<Scrambler>,
+    /// Known bits.
+    ///
+    /// These must be in the information part of the codeword.
+    pub known_bits: Option<KnownBits>,
+    /// Shortening.
+    ///
+    /// The shortened bits must be in the information part of the codeword and
+    /// must not be known bits.
+    pub shortening: Option<Shortening>,
+    /// LLR transform.
+    ///
+    /// The transform is applied to the LLRs produced by the demodulator.
+    pub llr_transform: Option<LlrTransform>,
     /// Maximum number of frame errors per Eb/N0.
     pub max_frame_errors: u64,
+    /// Policy for the number of frame errors per Eb/N0.
+    ///
+    /// With [`FrameErrorPolicy::Fixed`], `max_frame_errors` is used for all
+    /// the Eb/N0's.
+    pub frame_error_policy: FrameErrorPolicy,
+    /// Maximum number of frames per Eb/N0.
+    ///
+    /// If this is `None`, the simulation of each Eb/N0 only stops when
+    /// `max_frame_errors` frame errors have been collected.
+    pub max_frames: Option<u64>,
+    /// Number of warm-up frames per Eb/N0.
+    ///
+    /// See [`BerTest::with_warm_up_frames`].
+    pub warm_up_frames: u64,
+    /// Enables batch decoding.
+    ///
+    /// See [`BerTest::with_batch_decoding`].
+    pub batch_decoding: bool,
     /// Maximum number of iterations per codeword.
     pub max_iterations: usize,
+    /// Schedule of the maximum number of iterations per Eb/N0.
+    ///
+    /// With [`IterationSchedule::Fixed`], `max_iterations` is used for all the
+    /// Eb/N0's.
+    pub iteration_schedule: IterationSchedule,
     /// List of Eb/N0's (in dB) to simulate.
     pub ebn0s_db: &'a [f32],
     /// An optional reporter object to which the BER test will send periodic
@@ -69,6 +152,27 @@ pub struct BerTestBuilder<'a, Dec = DecoderImplementation> {
     ///
     /// A value of zero means that there is no BCH decoder.
     pub bch_max_errors: u64,
+    /// Enables tracking of the maximum LLR magnitude in the decoder.
+    ///
+    /// See [`Statistics::max_llr_magnitude`].
+    pub llr_magnitude_tracking: bool,
+    /// Optional sink for training data.
+    ///
+    /// See [`BerTest::with_training_data`].
+    pub training_data: Option<Arc<TrainingDataSink>>,
+    /// Optional capture for the error patterns of the failed frames.
+    ///
+    /// See [`BerTest::with_error_patterns`].
+    pub error_patterns: Option<Arc<ErrorPatternCapture>>,
+    /// Master seed for the random streams.
+    ///
+    /// If this is `None`, a random seed is used.
+    pub seed: Option<u64>,
+    /// Optional token to cancel the BER test.
+    ///
+    /// When the token is cancelled, the test stops and returns the statistics
+    /// obtained so far.
+    pub cancellation: Option<CancellationToken>,
 }
 
 /// Modulation.
@@ -78,8 +182,12 @@ pub struct BerTestBuilder<'a, Dec = DecoderImplementation> {
 pub enum Modulation {
     /// BPSK modulation.
     Bpsk,
+    /// QPSK modulation.
+    Qpsk,
     /// 8PSK modulation.
     Psk8,
+    /// 16APSK modulation.
+    Apsk16,
 }
 
 impl std::str::FromStr for Modulation {
@@ -88,7 +196,9 @@ impl std::str::FromStr for Modulation {
     fn from_str(s: &str) -> Result<Modulation, String> {
         Ok(match s {
             "BPSK" => Modulation::Bpsk,
+            "QPSK" => Modulation::Qpsk,
             "8PSK" => Modulation::Psk8,
+            "16APSK" => Modulation::Apsk16,
             _ => Err(format!("invalid modulation {s}"))?,
         })
     }
@@ -101,7 +211,9 @@ impl std::fmt::Display for Modulation {
             "{}",
             match self {
                 Modulation::Bpsk => "BPSK",
+                Modulation::Qpsk => "QPSK",
                 Modulation::Psk8 => "8PSK",
+                Modulation::Apsk16 => "16APSK",
             }
         )
     }
@@ -113,29 +225,202 @@ impl<'a, Dec: DecoderFactory> BerTestBuilder<'a, Dec> {
     /// This function only defines the BER test. To run it it is necessary to
     /// call the [`Ber::run`] method.
     pub fn build(self) -> Result<Box<dyn Ber>, Box<dyn std::error::Error>> {
+        let k = self.h.num_cols() - self.h.num_rows();
+        let puncturer = self
+            .puncturing
+            .as_ref()
+            .map(|pattern| pattern.puncturer(self.h.num_cols()))
+            .transpose()?;
+        if let Some(known) = self.known_bits.as_ref() {
+            known.check_range(k)?;
+        }
+        if let Some(shortening) = self.shortening.as_ref() {
+            shortening.check_range(k)?;
+            if let Some(known) = self.known_bits.as_ref() {
+                if known
+                    .iter()
+                    .any(|(p, _)| shortening.positions().binary_search(&p).is_ok())
+                {
+                    return Err("some bits are both known and shortened".into());
+                }
+            }
+            let ordering = BitOrdering::new(
+                puncturer.clone(),
+                None,
+                self.interleaving_columns
+                    .map(|n| Interleaver::new(n.unsigned_abs(), n < 0)),
+            );
+            ordering.transmission_positions(self.h.num_cols())?;
+        }
+        let impairments = &self.impairments;
+        if !(impairments.amplitude.is_finite()
+            && impairments.dc_offset.is_finite()
+            && impairments.gain_jitter.is_finite()
+            && impairments.gain_jitter >= 0.0)
+        {
+            return Err(format!("invalid impairments {impairments}").into());
+        }
+        if let Some((_, bin_width_db)) = &self.snr_profile {
+            if !(*bin_width_db > 0.0 && bin_width_db.is_finite()) {
+                return Err(format!("invalid Eb/N0 bin width {bin_width_db}").into());
+            }
+        }
         Ok(match self.modulation {
-            Modulation::Bpsk => Box::new(BerTest::<Bpsk, Dec>::new(
-                self.h,
-                self.decoder_implementation,
-                self.puncturing_pattern,
-                self.interleaving_columns,
-                self.max_frame_errors,
-                self.max_iterations,
-                self.ebn0s_db,
-                self.reporter,
-                self.bch_max_errors,
-            )?),
-            Modulation::Psk8 => Box::new(BerTest::<Psk8, Dec>::new(
-                self.h,
-                self.decoder_implementation,
-                self.puncturing_pattern,
-                self.interleaving_columns,
-                self.max_frame_errors,
-                self.max_iterations,
-                self.ebn0s_db,
-                self.reporter,
-                self.bch_max_errors,
-            )?),
+            Modulation::Bpsk => Box::new(self.new_test::<Bpsk>(puncturer)?),
+            Modulation::Qpsk => Box::new(self.new_test::<Qpsk>(puncturer)?),
+            Modulation::Psk8 => Box::new(self.new_test::<Psk8>(puncturer)?),
+            Modulation::Apsk16 => {
+                let ring_ratio = self.apsk16_ring_ratio.unwrap_or(APSK16_DEFAULT_RING_RATIO);
+                if ring_ratio.is_nan() || ring_ratio <= 1.0 {
+                    return Err(format!("invalid 16APSK ring ratio {ring_ratio}").into());
+                }
+                Box::new(
+                    self.new_test::<Apsk16>(puncturer)?
+                        .with_modulator(Apsk16Modulator::new(ring_ratio)),
+                )
+            }
+        })
+    }
+
+    fn new_test<Mod: modulation::Modulation>(
+        self,
+        puncturer: Option<Puncturer>,
+    ) -> Result<BerTest<Mod, Dec>, Box<dyn std::error::Error>> {
+        let warm_up_frames = self.warm_up_frames;
+        let batch_decoding = self.batch_decoding;
+        let frame_error_policy = self.frame_error_policy;
+        let channel = self.channel;
+        let impairments = self.impairments;
+        let snr_profile = self.snr_profile;
+        let training_data = self.training_data;
+        let error_patterns = self.error_patterns;
+        let scrambler = self.scrambler;
+        let shortening = self.shortening;
+        let llr_transform = self.llr_transform;
+        let iteration_schedule = self.iteration_schedule;
+        let cancellation = self.cancellation;
+        let max_frames = self.max_frames;
+        let seed = self.seed;
+        let llr_magnitude_tracking = self.llr_magnitude_tracking;
+        let known_bits = self.known_bits;
+        let test = BerTest::new(
+            self.h,
+            self.decoder_implementation,
+            puncturer,
+            self.interleaving_columns,
+            self.max_frame_errors,
+            self.max_iterations,
+            self.ebn0s_db,
+            self.reporter,
+            self.bch_max_errors,
+        )?
+        .with_channel(channel)?
+        .with_impairments(impairments)
+        .with_warm_up_frames(warm_up_frames)
+        .with_batch_decoding(batch_decoding)
+        .with_frame_error_policy(frame_error_policy)
+        .with_iteration_schedule(iteration_schedule)
+        .with_llr_magnitude_tracking(llr_magnitude_tracking);
+        let test = match max_frames {
+            Some(max_frames) => test.with_max_frames(max_frames),
+            None => test,
+        };
+        let test = match cancellation {
+            Some(cancellation) => test.with_cancellation(cancellation),
+            None => test,
+        };
+        let test = match llr_transform {
+            Some(transform) => test.with_llr_transform(transform),
+            None => test,
+        };
+        let test = match seed {
+            Some(seed) => test.with_seed(seed),
+            None => test,
+        };
+        let test = match scrambler {
+            Some(scrambler) => test.with_scrambler(scrambler),
+            None => test,
+        };
+        let test = match shortening {
+            Some(shortening) => test.with_shortening(shortening),
+            None => test,
+        };
+        let test = match known_bits {
+            Some(known_bits) => test.with_known_bits(known_bits),
+            None => test,
+        };
+        let test = match training_data {
+            Some(sink) => test.with_training_data(sink),
+            None => test,
+        };
+        let test = match error_patterns {
+            Some(capture) => test.with_error_patterns(capture),
+            None => test,
+        };
+        Ok(match snr_profile {
+            Some((profile, bin_width_db)) => test.with_snr_profile(profile, bin_width_db),
+            None => test,
         })
     }
 }
+
+/// Computes a uniform grid of Eb/N0's.
+///
+/// Returns the Eb/N0's (in dB) from `min` to `max` (inclusive) in steps of
+/// `step`.
+///
+/// # Examples
+/// ```
+/// # use ldpc_toolbox::simulation::factory::ebn0_range;
+/// assert_eq!(ebn0_range(0.0, 1.0, 0.5), vec![0.0, 0.5, 1.0]);
+/// ```
+pub fn ebn0_range(min: f64, max: f64, step: f64) -> Vec<f32> {
+    let num_ebn0s = ((max - min) / step).floor() as usize + 1;
+    (0..num_ebn0s)
+        .map(|k| (min + k as f64 * step) as f32)
+        .collect()
+}
+
+/// Parses a list of Eb/N0's.
+///
+/// The Eb/N0's (in dB) can be separated by commas or whitespace, including
+/// newlines, so this function can be used both to parse a list given as
+/// `"0.0,0.5,1.0,1.25"` and to parse the contents of a file with one Eb/N0 per
+/// line. Everything after a `#` until the end of the line is treated as a
+/// comment. The list must not be empty or contain repeated Eb/N0's.
+///
+/// # Examples
+/// ```
+/// # use ldpc_toolbox::simulation::factory::parse_ebn0_list;
+/// assert_eq!(
+///     parse_ebn0_list("0.0,0.5,1.0,1.25").unwrap(),
+///     vec![0.0, 0.5, 1.0, 1.25]
+/// );
+/// assert_eq!(
+///     parse_ebn0_list("# Eb/N0 (dB)\n1.0\n1.5 # waterfall\n").unwrap(),
+///     vec![1.0, 1.5]
+/// );
+/// ```
+pub fn parse_ebn0_list(s: &str) -> Result<Vec<f32>, &'static str> {
+    let mut v = Vec::new();
+    for line in s.lines() {
+        let line = line.split_once('#').map_or(line, |(data, _)| data);
+        for a in line
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|a| !a.is_empty())
+        {
+            let ebn0 = a.parse::<f32>().map_err(|_| "invalid Eb/N0")?;
+            if !ebn0.is_finite() {
+                return Err("invalid Eb/N0");
+            }
+            if v.contains(&ebn0) {
+                return Err("repeated Eb/N0");
+            }
+            v.push(ebn0);
+        }
+    }
+    if v.is_empty() {
+        return Err("empty Eb/N0 list");
+    }
+    Ok(v)
+}
@@ -0,0 +1,338 @@
+//! Frame error policies.
+//!
+//! This module contains [`FrameErrorPolicy`], which determines the number of
+//! frame errors collected for each Eb/N0 in a BER test. The accuracy of the
+//! FER estimate depends on the number of frame errors, but at low FERs each
+//! frame error takes a long time to collect. Instead of using the same number
+//! of frame errors for all the Eb/N0's, the number can be decreased as the
+//! measured FER decreases, accepting a wider confidence interval at the lowest
+//! FERs (see [`fer_confidence_interval`]).
+//!
+//! The module also contains the functions that compute the confidence intervals
+//! of the FER and BER that are reported in the BER test
+//! [`Statistics`](super::ber::Statistics).
+//!
+//! # Examples
+//! ```
+//! # use ldpc_toolbox::simulation::frame_errors::FrameErrorPolicy;
+//! let policy: FrameErrorPolicy = "1e-3:100,1e-7:30".parse()?;
+//! assert_eq!(policy.frame_errors(1000, Some(1e-2)), 100);
+//! assert_eq!(policy.frame_errors(1000, Some(1e-5)), 65);
+//! assert_eq!(policy.frame_errors(1000, Some(1e-8)), 30);
+//! // The frame errors are limited by the global maximum
+//! assert_eq!(policy.frame_errors(50, Some(1e-2)), 50);
+//! # Ok::<(), String>(())
+//! ```
+
+/// Policy for the number of frame errors per Eb/N0.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum FrameErrorPolicy {
+    /// The same number of frame errors for all the Eb/N0's.
+    #[default]
+    Fixed,
+    /// Number of frame errors given by a table as a function of the FER.
+    ///
+    /// Each entry contains a FER and the number of frame errors to collect
+    /// at that FER. The entries are sorted by decreasing FER. The number of
+    /// frame errors is interpolated linearly in the logarithm of the FER
+    /// between the entries, and it is constant above the first entry and below
+    /// the last entry. The FER is measured while the Eb/N0 is simulated, so
+    /// the number of frame errors is updated as the FER estimate improves.
+    Fer(Vec<(f64, u64)>),
+}
+
+/// FER table of the `auto` frame error policy.
+///
+/// This collects 100 frame errors above a FER of 1e-3 and 30 frame errors at
+/// a FER of 1e-7 and below.
+pub const AUTO_FER_TABLE: [(f64, u64); 2] = [(1e-3, 100), (1e-7, 30)];
+
+impl FrameErrorPolicy {
+    /// Returns the number of frame errors to collect.
+    ///
+    /// The parameter `max_frame_errors` is the global maximum number of frame
+    /// errors, and `fer` is the current estimate of the FER, or `None` if no
+    /// frames have been simulated yet.
+    pub fn frame_errors(&self, max_frame_errors: u64, fer: Option<f64>) -> u64 {
+        match (self, fer) {
+            (FrameErrorPolicy::Fixed, _) | (FrameErrorPolicy::Fer(_), None) => max_frame_errors,
+            (FrameErrorPolicy::Fer(table), Some(fer)) => {
+                let errors = match table.iter().position(|&(f, _)| f <= fer) {
+                    Some(0) => table[0].1,
+                    None => table.last().map_or(max_frame_errors, |&(_, e)| e),
+                    Some(j) => {
+                        let (fer0, errors0) = table[j - 1];
+                        let (fer1, errors1) = table[j];
+                        let t = (fer0.log10() - fer.log10()) / (fer0.log10() - fer1.log10());
+                        let errors = errors0 as f64 + t * (errors1 as f64 - errors0 as f64);
+                        errors.ceil() as u64
+                    }
+                };
+                errors.min(max_frame_errors)
+            }
+        }
+    }
+}
+
+impl std::str::FromStr for FrameErrorPolicy {
+    type Err = String;
+
+    /// Parses a frame error policy.
+    ///
+    /// The accepted formats are `fixed`, `auto` (which uses
+    /// [`AUTO_FER_TABLE`]), and a table given as a comma-separated list of
+    /// `<fer>:<frame errors>` entries, such as `1e-3:100,1e-7:30`.
+    fn from_str(s: &str) -> Result<FrameErrorPolicy, String> {
+        let invalid = || format!("invalid frame error policy {s}");
+        if s == "fixed" {
+            return Ok(FrameErrorPolicy::Fixed);
+        }
+        if s == "auto" {
+            return Ok(FrameErrorPolicy::Fer(AUTO_FER_TABLE.to_vec()));
+        }
+        let table = s
+            .split(',')
+            .map(|entry| {
+                let (fer, errors) = entry.split_once(':')?;
+                let fer = fer.trim().parse::<f64>().ok()?;
+                let errors = errors.trim().parse::<u64>().ok()?;
+                (fer > 0.0 && fer <= 1.0 && errors > 0).then_some((fer, errors))
+            })
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(invalid)?;
+        if !table.windows(2).all(|w| w[0].0 > w[1].0) {
+            return Err(format!(
+                "the FERs of the frame error policy {s} are not decreasing"
+            ));
+        }
+        Ok(FrameErrorPolicy::Fer(table))
+    }
+}
+
+impl std::fmt::Display for FrameErrorPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FrameErrorPolicy::Fixed => write!(f, "fixed"),
+            FrameErrorPolicy::Fer(table) => {
+                for (j, (fer, errors)) in table.iter().enumerate() {
+                    if j != 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{fer:e}:{errors}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Computes a 95% confidence interval for the FER.
+///
+/// Given the number of frame errors and the number of frames simulated, this
+/// returns the lower and upper bounds of the Clopper-Pearson interval with a
+/// confidence level of 95%. This is an exact binomial interval, so its coverage
+/// is at least 95% even when the number of frame errors is small or zero. If
+/// no frames have been simulated, the interval is `(0.0, 1.0)`.
+///
+/// # Examples
+/// ```
+/// # use ldpc_toolbox::simulation::frame_errors::fer_confidence_interval;
+/// let (low, high) = fer_confidence_interval(100, 100_000);
+/// assert!(low > 0.8e-3 && high < 1.25e-3);
+/// ```
+pub fn fer_confidence_interval(frame_errors: u64, num_frames: u64) -> (f64, f64) {
+    if num_frames == 0 {
+        return (0.0, 1.0);
+    }
+    let x = frame_errors as f64;
+    let n = num_frames as f64;
+    let alpha = 1.0 - CONFIDENCE;
+    let low = if frame_errors == 0 {
+        0.0
+    } else {
+        beta_quantile(0.5 * alpha, x, n - x + 1.0)
+    };
+    let high = if frame_errors >= num_frames {
+        1.0
+    } else {
+        beta_quantile(1.0 - 0.5 * alpha, x + 1.0, n - x)
+    };
+    (low, high)
+}
+
+/// Computes a 95% confidence interval for the BER.
+///
+/// Given the number of bit errors and the number of bits simulated, this
+/// returns the lower and upper bounds of the normal approximation interval with
+/// a confidence level of 95%, clamped to `[0, 1]`. If no frames have been
+/// simulated, the interval is `(0.0, 1.0)`.
+///
+/// The bit errors are not independent, since each frame error usually causes
+/// many bit errors, so this interval is narrower than it should be. It gives an
+/// indication of the accuracy of the BER, but the FER interval given by
+/// [`fer_confidence_interval`] is more reliable.
+///
+/// # Examples
+/// ```
+/// # use ldpc_toolbox::simulation::frame_errors::ber_confidence_interval;
+/// let (low, high) = ber_confidence_interval(1000, 1_000_000);
+/// assert!(low > 0.9e-3 && high < 1.1e-3);
+/// ```
+pub fn ber_confidence_interval(bit_errors: u64, num_bits: u64) -> (f64, f64) {
+    // 97.5% quantile of the standard normal distribution
+    const Z: f64 = 1.959964;
+    if num_bits == 0 {
+        return (0.0, 1.0);
+    }
+    let n = num_bits as f64;
+    let p = bit_errors as f64 / n;
+    let half_width = Z * (p * (1.0 - p) / n).sqrt();
+    ((p - half_width).max(0.0), (p + half_width).min(1.0))
+}
+
+const CONFIDENCE: f64 = 0.95;
+
+// Computes the quantile function of the beta distribution by bisection of the
+// regularized incomplete beta function.
+fn beta_quantile(q: f64, a: f64, b: f64) -> f64 {
+    // Relative accuracy of the result
+    const TOLERANCE: f64 = 1e-10;
+    let (mut low, mut high) = (0.0, 1.0);
+    while high - low > TOLERANCE * high {
+        let mid = 0.5 * (low + high);
+        if regularized_beta(mid, a, b) < q {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    0.5 * (low + high)
+}
+
+// Regularized incomplete beta function I_x(a, b).
+//
+// This uses the continued fraction in Numerical Recipes, Section 6.4.
+fn regularized_beta(x: f64, a: f64, b: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return 1.0;
+    }
+    let front = (ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (-x).ln_1p()).exp();
+    // The continued fraction converges quickly for x < (a + 1) / (a + b + 2),
+    // and the symmetry I_x(a, b) = 1 - I_{1-x}(b, a) is used otherwise.
+    if x < (a + 1.0) / (a + b + 2.0) {
+        front * beta_continued_fraction(x, a, b) / a
+    } else {
+        1.0 - front * beta_continued_fraction(1.0 - x, b, a) / b
+    }
+}
+
+// Evaluates the continued fraction for the incomplete beta function with the
+// modified Lentz method.
+fn beta_continued_fraction(x: f64, a: f64, b: f64) -> f64 {
+    const MAX_ITERATIONS: usize = 1_000_000;
+    const EPSILON: f64 = 1e-15;
+    const TINY: f64 = 1e-300;
+    let nonzero = |v: f64| if v.abs() < TINY { TINY } else { v };
+    let mut c = 1.0;
+    let mut d = 1.0 / nonzero(1.0 - (a + b) * x / (a + 1.0));
+    let mut h = d;
+    for m in 1..MAX_ITERATIONS {
+        let m = m as f64;
+        let even = m * (b - m) * x / ((a + 2.0 * m - 1.0) * (a + 2.0 * m));
+        let odd = -(a + m) * (a + b + m) * x / ((a + 2.0 * m) * (a + 2.0 * m + 1.0));
+        for coefficient in [even, odd] {
+            d = 1.0 / nonzero(1.0 + coefficient * d);
+            c = nonzero(1.0 + coefficient / c);
+            h *= d * c;
+        }
+        // d * c is the factor applied to h by the odd step
+        if (d * c - 1.0).abs() < EPSILON {
+            break;
+        }
+    }
+    h
+}
+
+// Logarithm of the gamma function for x >= 1, computed with the Lanczos
+// approximation (g = 7, 9 coefficients).
+fn ln_gamma(x: f64) -> f64 {
+    const G: f64 = 7.0;
+    const COEFFICIENTS: [f64; 9] = [
+        0.999_999_999_999_809_9,
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_1,
+        -176.615_029_162_140_6,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+    let x = x - 1.0;
+    let sum = COEFFICIENTS[1..]
+        .iter()
+        .enumerate()
+        .fold(COEFFICIENTS[0], |sum, (j, &c)| {
+            sum + c / (x + (j + 1) as f64)
+        });
+    let t = x + G + 0.5;
+    0.5 * (2.0 * std::f64::consts::PI).ln() + (x + 0.5) * t.ln() - t + sum.ln()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_and_display() {
+        for s in ["fixed", "1e-3:100,1e-7:30"] {
+            assert_eq!(s.parse::<FrameErrorPolicy>().unwrap().to_string(), s);
+        }
+        assert_eq!(
+            "auto".parse::<FrameErrorPolicy>(),
+            Ok(FrameErrorPolicy::Fer(AUTO_FER_TABLE.to_vec()))
+        );
+        assert!("1e-7:30,1e-3:100".parse::<FrameErrorPolicy>().is_err());
+        assert!("1e-3:0".parse::<FrameErrorPolicy>().is_err());
+        assert!("2:10".parse::<FrameErrorPolicy>().is_err());
+    }
+
+    #[test]
+    fn frame_errors() {
+        let policy = FrameErrorPolicy::Fer(AUTO_FER_TABLE.to_vec());
+        assert_eq!(policy.frame_errors(100, None), 100);
+        assert_eq!(policy.frame_errors(100, Some(1e-3)), 100);
+        assert_eq!(policy.frame_errors(100, Some(1e-7)), 30);
+        assert_eq!(policy.frame_errors(100, Some(0.0)), 30);
+        assert_eq!(FrameErrorPolicy::Fixed.frame_errors(100, Some(1e-7)), 100);
+    }
+
+    #[test]
+    fn confidence_interval() {
+        assert_eq!(fer_confidence_interval(0, 0), (0.0, 1.0));
+        // Clopper-Pearson intervals computed from the binomial distribution
+        for (frame_errors, num_frames, expected) in [
+            (0, 1000, (0.0, 3.682084e-3)),
+            (30, 1000, (2.033049e-2, 4.255140e-2)),
+            (100, 100_000, (8.137117e-4, 1.216136e-3)),
+            (1, 10, (2.528579e-3, 4.450161e-1)),
+            (10, 10, (6.915029e-1, 1.0)),
+            (3, 1_000_000_000, (6.186737e-10, 8.767271e-9)),
+        ] {
+            let (low, high) = fer_confidence_interval(frame_errors, num_frames);
+            assert!((low - expected.0).abs() <= 1e-5 * expected.0, "{low}");
+            assert!((high - expected.1).abs() <= 1e-5 * expected.1, "{high}");
+        }
+    }
+
+    #[test]
+    fn ber_interval() {
+        assert_eq!(ber_confidence_interval(0, 0), (0.0, 1.0));
+        assert_eq!(ber_confidence_interval(0, 1000), (0.0, 0.0));
+        let (low, high) = ber_confidence_interval(100, 10_000);
+        assert!((low - 0.008050).abs() < 1e-6 && (high - 0.011950).abs() < 1e-6);
+    }
+}
@@ -32,6 +32,11 @@ impl Interleaver {
         }
     }
 
+    /// Returns the number of columns of the interleaver.
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
     /// Interleaves a codeword.
     ///
     /// # Panics
@@ -0,0 +1,215 @@
+//! Maximum iteration schedules.
+//!
+//! This module contains [`IterationSchedule`], which determines the maximum
+//! number of decoder iterations used for each Eb/N0 in a BER test. Low Eb/N0's
+//! need many iterations, while at high Eb/N0's the decoder converges in a few
+//! iterations, so a single maximum number of iterations either wastes time at
+//! the high Eb/N0's where the decoder fails (for instance, due to false
+//! decodes or error floors) or degrades the waterfall.
+//!
+//! # Examples
+//! ```
+//! # use ldpc_toolbox::simulation::iterations::IterationSchedule;
+//! let schedule: IterationSchedule = "0.0:200,1.0:100,2.0:50".parse()?;
+//! assert_eq!(schedule.max_iterations(0.5, 100, None), 200);
+//! assert_eq!(schedule.max_iterations(2.5, 100, None), 50);
+//! // Eb/N0's below the first entry use the first entry
+//! assert_eq!(schedule.max_iterations(-1.0, 100, None), 200);
+//! # Ok::<(), String>(())
+//! ```
+
+use super::ber::Statistics;
+
+/// Schedule of the maximum number of iterations per Eb/N0.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum IterationSchedule {
+    /// The same maximum number of iterations for all the Eb/N0's.
+    #[default]
+    Fixed,
+    /// Maximum number of iterations given by a table.
+    ///
+    /// Each entry contains an Eb/N0 in dB and the maximum number of iterations
+    /// used from that Eb/N0 up to the Eb/N0 of the next entry. The entries are
+    /// sorted by increasing Eb/N0. The first entry also applies to the Eb/N0's
+    /// below it.
+    Table(Vec<(f32, usize)>),
+    /// Maximum number of iterations obtained from the measured average
+    /// iterations.
+    ///
+    /// The first Eb/N0 uses the global maximum number of iterations. Each of
+    /// the following Eb/N0's uses `factor` times the average number of
+    /// iterations of the frames decoded correctly in the previous Eb/N0,
+    /// rounded up and limited to the global maximum. The Eb/N0's should be
+    /// simulated in increasing order for this rule to make sense.
+    Auto {
+        /// Ratio between the maximum number of iterations and the average
+        /// iterations of the previous Eb/N0.
+        factor: f64,
+    },
+}
+
+/// Default factor of [`IterationSchedule::Auto`].
+pub const DEFAULT_AUTO_FACTOR: f64 = 4.0;
+
+impl IterationSchedule {
+    /// Returns the maximum number of iterations for an Eb/N0.
+    ///
+    /// The parameter `max_iterations` is the global maximum number of
+    /// iterations, and `previous` contains the statistics of the previous
+    /// Eb/N0, if there is one.
+    pub fn max_iterations(
+        &self,
+        ebn0_db: f32,
+        max_iterations: usize,
+        previous: Option<&Statistics>,
+    ) -> usize {
+        match self {
+            IterationSchedule::Fixed => max_iterations,
+            IterationSchedule::Table(table) => table
+                .iter()
+                .rev()
+                .find(|&&(ebn0, _)| ebn0 <= ebn0_db)
+                .or(table.first())
+                .map_or(max_iterations, |&(_, iterations)| iterations),
+            IterationSchedule::Auto { factor } => {
+                match previous.map(|stats| stats.ldpc.average_iterations_correct) {
+                    Some(average) if average.is_finite() => {
+                        ((factor * average).ceil() as usize).clamp(1, max_iterations)
+                    }
+                    _ => max_iterations,
+                }
+            }
+        }
+    }
+}
+
+impl std::str::FromStr for IterationSchedule {
+    type Err = String;
+
+    /// Parses an iteration schedule.
+    ///
+    /// The accepted formats are `fixed`, `auto`, `auto:<factor>`, and a table
+    /// given as a comma-separated list of `<ebn0>:<iterations>` entries, such
+    /// as `0.0:200,1.0:100`.
+    fn from_str(s: &str) -> Result<IterationSchedule, String> {
+        let invalid = || format!("invalid iteration schedule {s}");
+        if s == "fixed" {
+            return Ok(IterationSchedule::Fixed);
+        }
+        if s == "auto" {
+            return Ok(IterationSchedule::Auto {
+                factor: DEFAULT_AUTO_FACTOR,
+            });
+        }
+        if let Some(factor) = s.strip_prefix("auto:") {
+            let factor = factor.parse::<f64>().map_err(|_| invalid())?;
+            if !(factor > 0.0 && factor.is_finite()) {
+                return Err(invalid());
+            }
+            return Ok(IterationSchedule::Auto { factor });
+        }
+        let table = s
+            .split(',')
+            .map(|entry| {
+                let (ebn0, iterations) = entry.split_once(':')?;
+                let ebn0 = ebn0.trim().parse::<f32>().ok()?;
+                let iterations = iterations.trim().parse::<usize>().ok()?;
+                (iterations > 0).then_some((ebn0, iterations))
+            })
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(invalid)?;
+        if !table.windows(2).all(|w| w[0].0 < w[1].0) {
+            return Err(format!(
+                "the Eb/N0's of the iteration schedule {s} are not increasing"
+            ));
+        }
+        Ok(IterationSchedule::Table(table))
+    }
+}
+
+impl std::fmt::Display for IterationSchedule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IterationSchedule::Fixed => write!(f, "fixed"),
+            IterationSchedule::Table(table) => {
+                for (j, (ebn0, iterations)) in table.iter().enumerate() {
+                    if j != 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{ebn0}:{iterations}")?;
+                }
+                Ok(())
+            }
+            IterationSchedule::Auto { factor } => write!(f, "auto:{factor}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::simulation::ber::CodeStatistics;
+    use std::time::Duration;
+
+    fn statistics(average_iterations_correct: f64) -> Statistics {
+        Statistics {
+            ebn0_db: 1.0,
+            num_frames: 100,
+            total_iterations: 0,
+            false_decodes: 0,
+            false_decode_distances: Default::default(),
+            average_iterations: 0.0,
+            elapsed: Duration::ZERO,
+            throughput_mbps: 0.0,
+            warm_up_frames: 0,
+            worker_utilization: Vec::new(),
+            max_llr_magnitude: None,
+            max_iterations: 100,
+            ldpc: CodeStatistics {
+                bit_errors: 0,
+                frame_errors: 0,
+                correct_iterations: 0,
+                ber: 0.0,
+                fer: 0.0,
+                ber_confidence_interval: (0.0, 1.0),
+                fer_confidence_interval: (0.0, 1.0),
+                average_iterations_correct,
+            },
+            bch: None,
+            metadata: None,
+        }
+    }
+
+    #[test]
+    fn auto() {
+        let schedule = "auto:2.5".parse::<IterationSchedule>().unwrap();
+        assert_eq!(schedule, IterationSchedule::Auto { factor: 2.5 });
+        assert_eq!(schedule.max_iterations(0.0, 100, None), 100);
+        let previous = statistics(10.1);
+        assert_eq!(schedule.max_iterations(0.5, 100, Some(&previous)), 26);
+        assert_eq!(schedule.max_iterations(0.5, 20, Some(&previous)), 20);
+        // No frames decoded correctly
+        let previous = statistics(f64::NAN);
+        assert_eq!(schedule.max_iterations(0.5, 100, Some(&previous)), 100);
+    }
+
+    #[test]
+    fn parse() {
+        let schedule = "0:200, 1.5:50".parse::<IterationSchedule>().unwrap();
+        assert_eq!(
+            schedule,
+            IterationSchedule::Table(vec![(0.0, 200), (1.5, 50)])
+        );
+        assert_eq!(schedule.to_string(), "0:200,1.5:50");
+        assert_eq!(schedule.max_iterations(1.5, 10, None), 50);
+        assert_eq!(
+            "auto".parse::<IterationSchedule>(),
+            Ok(IterationSchedule::Auto {
+                factor: DEFAULT_AUTO_FACTOR
+            })
+        );
+        for invalid in ["1.0:100,0.5:200", "1.0:0", "1.0", "auto:-1", ""] {
+            assert!(invalid.parse::<IterationSchedule>().is_err());
+        }
+    }
+}
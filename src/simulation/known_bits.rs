@@ -0,0 +1,193 @@
+//! Known bits.
+//!
+//! This module implements support for codeword bits whose values are known a
+//! priori by the decoder, such as shortened bits or protocol-fixed fields. Such
+//! bits are given saturated LLRs before decoding and are excluded from the
+//! error counting of a BER test. Unlike punctured bits, which are erasures,
+//! known bits give the decoder perfect information.
+
+use thiserror::Error;
+
+/// LLR used for known bits.
+///
+/// Known bits are given an LLR with this magnitude, which is large enough to be
+/// saturated by all the decoder arithmetics.
+pub const KNOWN_BIT_LLR: f64 = 1000.0;
+
+/// Known bits.
+///
+/// This struct contains the positions and values of the codeword bits that are
+/// known a priori by the decoder.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct KnownBits {
+    // sorted by position
+    bits: Box<[(usize, u8)]>,
+}
+
+/// Known bits error.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Error)]
+pub enum Error {
+    /// A position is repeated.
+    #[error("repeated known bit position")]
+    RepeatedPosition,
+    /// A value is not 0 or 1.
+    #[error("known bit value is not 0 or 1")]
+    InvalidValue,
+    /// A position is out of range.
+    #[error("known bit position out of range")]
+    PositionOutOfRange,
+}
+
+impl KnownBits {
+    /// Creates a new set of known bits.
+    ///
+    /// The `bits` are given as pairs containing a codeword position and the
+    /// value (0 or 1) of the bit in that position.
+    pub fn new(bits: &[(usize, u8)]) -> Result<KnownBits, Error> {
+        if bits.iter().any(|&(_, value)| value > 1) {
+            return Err(Error::InvalidValue);
+        }
+        let mut bits = bits.to_vec();
+        bits.sort_unstable_by_key(|&(position, _)| position);
+        if bits.windows(2).any(|w| w[0].0 == w[1].0) {
+            return Err(Error::RepeatedPosition);
+        }
+        Ok(KnownBits {
+            bits: bits.into_boxed_slice(),
+        })
+    }
+
+    /// Creates a set of known bits which are all zero.
+    ///
+    /// This is typically used for shortened bits.
+    pub fn zeros(positions: &[usize]) -> Result<KnownBits, Error> {
+        KnownBits::new(&positions.iter().map(|&p| (p, 0)).collect::<Vec<_>>())
+    }
+
+    /// Returns the number of known bits.
+    pub fn len(&self) -> usize {
+        self.bits.len()
+    }
+
+    /// Returns `true` if there are no known bits.
+    pub fn is_empty(&self) -> bool {
+        self.bits.is_empty()
+    }
+
+    /// Returns an iterator over the positions and values of the known bits.
+    ///
+    /// The iterator is sorted by position.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, u8)> + '_ {
+        self.bits.iter().copied()
+    }
+
+    /// Checks that all the known bits are within the first `len` positions.
+    pub fn check_range(&self, len: usize) -> Result<(), Error> {
+        match self.bits.last() {
+            Some(&(position, _)) if position >= len => Err(Error::PositionOutOfRange),
+            _ => Ok(()),
+        }
+    }
+
+    /// Sets the known bits in a message or codeword.
+    pub fn apply_to_bits(&self, bits: &mut [u8]) -> Result<(), Error> {
+        self.check_range(bits.len())?;
+        for &(position, value) in self.bits.iter() {
+            bits[position] = value;
+        }
+        Ok(())
+    }
+
+    /// Sets the LLRs of the known bits.
+    ///
+    /// The LLRs in the positions of the known bits are replaced by
+    /// [`KNOWN_BIT_LLR`] with the sign corresponding to the bit value.
+    pub fn apply_to_llrs(&self, llrs: &mut [f64]) -> Result<(), Error> {
+        self.check_range(llrs.len())?;
+        for &(position, value) in self.bits.iter() {
+            llrs[position] = if value == 0 {
+                KNOWN_BIT_LLR
+            } else {
+                -KNOWN_BIT_LLR
+            };
+        }
+        Ok(())
+    }
+
+    /// Counts the bit errors in the known bits.
+    ///
+    /// Compares the known bits with the corresponding bits of `decoded` and
+    /// returns the number of differences. Known bits beyond the length of
+    /// `decoded` are ignored.
+    pub fn count_errors(&self, decoded: &[u8]) -> u64 {
+        self.bits
+            .iter()
+            .filter(|&&(position, value)| decoded.get(position).is_some_and(|&d| d != value))
+            .count() as u64
+    }
+}
+
+/// Parses a list of known bit positions.
+///
+/// The list is given as comma-separated positions or inclusive ranges of
+/// positions, as in `"0-63,100"`.
+///
+/// # Examples
+/// ```
+/// # use ldpc_toolbox::simulation::known_bits::parse_known_bit_positions;
+/// assert_eq!(
+///     parse_known_bit_positions("0-2,5").unwrap(),
+///     vec![0, 1, 2, 5]
+/// );
+/// ```
+pub fn parse_known_bit_positions(s: &str) -> Result<Vec<usize>, &'static str> {
+    let mut v = Vec::new();
+    for a in s.split(',') {
+        let a = a.trim();
+        match a.split_once('-') {
+            Some((start, end)) => {
+                let start = start
+                    .trim()
+                    .parse::<usize>()
+                    .map_err(|_| "invalid known bit position")?;
+                let end = end
+                    .trim()
+                    .parse::<usize>()
+                    .map_err(|_| "invalid known bit position")?;
+                if end < start {
+                    return Err("invalid known bit range");
+                }
+                v.extend(start..=end);
+            }
+            None => v.push(a.parse().map_err(|_| "invalid known bit position")?),
+        }
+    }
+    Ok(v)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn known_bits() {
+        let known = KnownBits::new(&[(3, 1), (0, 0)]).unwrap();
+        assert_eq!(known.iter().collect::<Vec<_>>(), vec![(0, 0), (3, 1)]);
+        let mut llrs = vec![0.5; 4];
+        known.apply_to_llrs(&mut llrs).unwrap();
+        assert_eq!(llrs, vec![KNOWN_BIT_LLR, 0.5, 0.5, -KNOWN_BIT_LLR]);
+        let mut bits = vec![1, 1, 1, 0];
+        known.apply_to_bits(&mut bits).unwrap();
+        assert_eq!(bits, vec![0, 1, 1, 1]);
+        assert_eq!(known.count_errors(&[1, 0, 0, 1]), 1);
+        assert_eq!(
+            known.apply_to_bits(&mut [0; 3]),
+            Err(Error::PositionOutOfRange)
+        );
+        assert_eq!(
+            KnownBits::new(&[(1, 0), (1, 1)]),
+            Err(Error::RepeatedPosition)
+        );
+        assert_eq!(KnownBits::new(&[(1, 2)]), Err(Error::InvalidValue));
+    }
+}
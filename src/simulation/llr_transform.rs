@@ -0,0 +1,150 @@
+//! LLR transform.
+//!
+//! This module implements an affine transform that is applied to the LLRs
+//! produced by the demodulator before decoding. Each LLR `x` is replaced by
+//! `scale * x + offset`. The transform can be used to model errors in the
+//! estimation of the noise variance in the receiver front-end, which cause the
+//! LLRs to be scaled by a constant factor, and to match external receivers
+//! whose LLR conventions differ from the ones used by this crate by a constant
+//! factor.
+//!
+//! The scale and the offset can depend on the bit index by giving a pattern,
+//! which is repeated cyclically over the LLRs. For instance, a scale pattern
+//! with 3 elements can be used to give a different scale to each of the bits
+//! of an 8PSK symbol. The transform is applied to the LLRs in transmission
+//! order (see [`ordering`](super::ordering)), so the punctured bits are not
+//! affected by it.
+//!
+//! # Examples
+//! ```
+//! # use ldpc_toolbox::simulation::llr_transform::LlrTransform;
+//! let transform: LlrTransform = "2,1:0.5".parse()?;
+//! let mut llrs = [1.0, 1.0, -3.0, -3.0];
+//! transform.apply(&mut llrs);
+//! assert_eq!(llrs, [2.5, 1.5, -5.5, -2.5]);
+//! # Ok::<(), ldpc_toolbox::simulation::llr_transform::Error>(())
+//! ```
+
+use thiserror::Error;
+
+/// LLR transform.
+///
+/// This struct represents an affine transform applied to LLRs. See the
+/// [module documentation](self) for more details.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LlrTransform {
+    scale: Box<[f64]>,
+    offset: Box<[f64]>,
+}
+
+/// LLR transform error.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Error)]
+pub enum Error {
+    /// The scale or offset pattern is empty.
+    #[error("empty LLR transform pattern")]
+    EmptyPattern,
+    /// The scale or offset contains a value which is not finite.
+    #[error("LLR transform value is not finite")]
+    NotFinite,
+    /// The LLR transform string has an invalid format.
+    #[error("invalid LLR transform format")]
+    InvalidFormat,
+}
+
+impl LlrTransform {
+    /// Creates a new LLR transform.
+    ///
+    /// The `scale` and `offset` patterns are repeated cyclically over the
+    /// LLRs, so the LLR with index `j` is transformed as
+    /// `scale[j % scale.len()] * x + offset[j % offset.len()]`.
+    pub fn new(scale: &[f64], offset: &[f64]) -> Result<LlrTransform, Error> {
+        if scale.is_empty() || offset.is_empty() {
+            return Err(Error::EmptyPattern);
+        }
+        if !scale.iter().chain(offset.iter()).all(|x| x.is_finite()) {
+            return Err(Error::NotFinite);
+        }
+        Ok(LlrTransform {
+            scale: scale.into(),
+            offset: offset.into(),
+        })
+    }
+
+    /// Creates an LLR transform with a constant scale and offset.
+    pub fn affine(scale: f64, offset: f64) -> Result<LlrTransform, Error> {
+        LlrTransform::new(&[scale], &[offset])
+    }
+
+    /// Returns `true` if the transform leaves the LLRs unchanged.
+    pub fn is_identity(&self) -> bool {
+        self.scale.iter().all(|&s| s == 1.0) && self.offset.iter().all(|&o| o == 0.0)
+    }
+
+    /// Applies the transform to a list of LLRs.
+    pub fn apply(&self, llrs: &mut [f64]) {
+        for (j, x) in llrs.iter_mut().enumerate() {
+            *x = self.scale[j % self.scale.len()] * *x + self.offset[j % self.offset.len()];
+        }
+    }
+}
+
+impl std::str::FromStr for LlrTransform {
+    type Err = Error;
+
+    /// Parses an LLR transform.
+    ///
+    /// The format is `<scale>` or `<scale>:<offset>`, where each of `<scale>`
+    /// and `<offset>` is a number or a comma-separated pattern of numbers. For
+    /// instance, `0.8`, `1:0.1` and `1,1,0.5`.
+    fn from_str(s: &str) -> Result<LlrTransform, Error> {
+        let parse = |pattern: &str| {
+            pattern
+                .split(',')
+                .map(|x| x.trim().parse::<f64>().map_err(|_| Error::InvalidFormat))
+                .collect::<Result<Vec<_>, _>>()
+        };
+        let (scale, offset) = match s.split_once(':') {
+            Some((scale, offset)) => (parse(scale)?, parse(offset)?),
+            None => (parse(s)?, vec![0.0]),
+        };
+        LlrTransform::new(&scale, &offset)
+    }
+}
+
+impl std::fmt::Display for LlrTransform {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let join = |pattern: &[f64]| {
+            pattern
+                .iter()
+                .map(|x| x.to_string())
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+        write!(f, "{}:{}", join(&self.scale), join(&self.offset))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let transform = "0.5".parse::<LlrTransform>().unwrap();
+        assert_eq!(transform, LlrTransform::affine(0.5, 0.0).unwrap());
+        assert_eq!(transform.to_string(), "0.5:0");
+        assert_eq!(transform.to_string().parse(), Ok(transform));
+        assert!("1:0".parse::<LlrTransform>().unwrap().is_identity());
+        assert_eq!("1,2:".parse::<LlrTransform>(), Err(Error::InvalidFormat));
+        assert_eq!("inf".parse::<LlrTransform>(), Err(Error::NotFinite));
+        assert_eq!(LlrTransform::new(&[], &[0.0]), Err(Error::EmptyPattern));
+    }
+
+    #[test]
+    fn patterns() {
+        let transform = LlrTransform::new(&[1.0, 2.0, 3.0], &[0.0, -1.0]).unwrap();
+        let mut llrs = [1.0; 6];
+        transform.apply(&mut llrs);
+        assert_eq!(llrs, [1.0, 1.0, 3.0, 0.0, 2.0, 2.0]);
+    }
+}
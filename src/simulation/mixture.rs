@@ -0,0 +1,667 @@
+//! BER simulation of code mixtures.
+//!
+//! This module contains [`MixtureTest`], a BER test that simulates a traffic
+//! mix formed by frames of several codes, such as DVB-S2 short and normal
+//! FECFRAMEs with different rates, as they would be sent in an ACM system. Each
+//! simulated frame uses one of the codes of the mixture, chosen at random
+//! according to the proportions given by the weights of the
+//! [`MixtureComponent`]s.
+//!
+//! All the frames of an Eb/N0 go through the same AWGN channel, whose noise
+//! variance is computed from the Eb/N0 and the average rate of the mixture,
+//! which is the ratio between the average number of information bits and the
+//! average number of transmitted bits per frame. The test reports aggregate
+//! statistics over all the frames, in which the BER is referred to the total
+//! number of information bits, as well as the statistics of the frames of
+//! each component.
+
+use super::{
+    ber::{codeword_distance, CodeStatistics, Metadata, Report, Reporter, Statistics},
+    channel::{AwgnChannel, Channel},
+    frame_errors::{ber_confidence_interval, fer_confidence_interval},
+    modulation::{Demodulator, Modulation, Modulator},
+    ordering::BitOrdering,
+    puncturing::Puncturer,
+};
+use crate::{
+    cancel::CancellationToken,
+    decoder::{
+        factory::{DecoderFactory, DecoderImplementation},
+        graph::CompiledGraph,
+        LdpcDecoder,
+    },
+    encoder::{self, Encoder},
+    gf2::GF2,
+    rand::{stream_rng, Rng as StreamRng},
+    sparse::SparseMatrix,
+};
+use ndarray::Array1;
+use num_traits::{One, Zero};
+use rand::{
+    distributions::{Standard, WeightedIndex},
+    Rng,
+};
+use std::{
+    collections::BTreeMap,
+    sync::{
+        mpsc::{self, Receiver, Sender, SyncSender, TryRecvError},
+        Arc,
+    },
+    time::Instant,
+};
+use thiserror::Error;
+
+/// Mixture BER test error.
+#[derive(Debug, Error)]
+pub enum Error {
+    /// The mixture does not have any component.
+    #[error("the mixture does not have any component")]
+    Empty,
+    /// The weights of the components are not valid.
+    ///
+    /// The weights must be non-negative and finite, and at least one of them
+    /// must be positive.
+    #[error("invalid mixture weights")]
+    InvalidWeights,
+    /// The encoder of a component could not be constructed.
+    #[error("could not construct the encoder of {name}: {error}")]
+    Encoder {
+        /// Name of the component.
+        name: String,
+        /// Encoder error.
+        error: encoder::Error,
+    },
+}
+
+/// Component of a code mixture.
+#[derive(Debug, Clone)]
+pub struct MixtureComponent {
+    /// Name of the component.
+    ///
+    /// This is used to identify the component in the test results.
+    pub name: String,
+    /// LDPC parity check matrix.
+    pub h: Arc<SparseMatrix>,
+    /// Codeword puncturing pattern.
+    ///
+    /// The pattern uses the semantics of [`Puncturer`].
+    pub puncturing_pattern: Option<Vec<bool>>,
+    /// Weight of the component.
+    ///
+    /// The proportion of frames of this component is its weight divided by
+    /// the sum of the weights of all the components.
+    pub weight: f64,
+}
+
+/// Mixture BER test statistics.
+///
+/// This structure contains the statistics for a single Eb/N0 case in a
+/// mixture BER test.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MixtureStatistics {
+    /// Aggregate statistics over the frames of all the components.
+    pub aggregate: Statistics,
+    /// Statistics of the frames of each component.
+    ///
+    /// These are listed in the same order as the components of the test.
+    pub components: Vec<Statistics>,
+}
+
+/// Mixture BER test.
+///
+/// This struct is used to configure and run a BER test of a code
+/// mixture. See the [module documentation](self) for more details.
+#[derive(Debug)]
+pub struct MixtureTest<Mod: Modulation, Dec = DecoderImplementation> {
+    decoder_implementation: Dec,
+    num_workers: usize,
+    components: Vec<Component>,
+    weights: WeightedIndex<f64>,
+    rate: f64,
+    modulator: Mod::Modulator,
+    ebn0s_db: Vec<f32>,
+    statistics: Vec<MixtureStatistics>,
+    max_iterations: usize,
+    max_frame_errors: u64,
+    max_frames: Option<u64>,
+    seed: u64,
+    cancellation: Option<CancellationToken>,
+    reporter: Option<Reporter>,
+    last_reported: Instant,
+}
+
+#[derive(Debug)]
+struct Component {
+    graph: Arc<CompiledGraph>,
+    metadata: Metadata,
+    k: usize,
+    n: usize,
+    encoder: Encoder,
+    ordering: BitOrdering,
+}
+
+#[derive(Debug)]
+struct Worker<Mod: Modulation> {
+    terminate_rx: Receiver<()>,
+    results_tx: Sender<WorkerResult>,
+    components: Vec<WorkerComponent>,
+    weights: WeightedIndex<f64>,
+    modulator: Mod::Modulator,
+    channel: AwgnChannel,
+    demodulator: Mod::Demodulator,
+    max_iterations: usize,
+    mixture_rng: StreamRng,
+    message_rng: StreamRng,
+    noise_rng: StreamRng,
+}
+
+#[derive(Debug)]
+struct WorkerComponent {
+    k: usize,
+    encoder: Encoder,
+    ordering: BitOrdering,
+    decoder: Box<dyn LdpcDecoder>,
+    decoded: Vec<u8>,
+}
+
+#[derive(Debug, Clone)]
+struct WorkerResultOk {
+    component: usize,
+    bit_errors: u64,
+    frame_error: bool,
+    false_decode: bool,
+    false_decode_distance: Option<usize>,
+    iterations: u64,
+}
+
+type WorkerResult = Result<WorkerResultOk, ()>;
+
+#[derive(Debug, Clone, Default)]
+struct CurrentStatistics {
+    num_frames: u64,
+    info_bits: u64,
+    bit_errors: u64,
+    frame_errors: u64,
+    false_decodes: u64,
+    false_decode_distances: BTreeMap<usize, u64>,
+    total_iterations: u64,
+    correct_iterations: u64,
+}
+
+impl<Mod: Modulation, Dec: DecoderFactory> MixtureTest<Mod, Dec> {
+    /// Creates a new mixture BER test.
+    ///
+    /// The parameters required to define the test are the components of the
+    /// mixture, the decoder implementation, the maximum number of frame errors
+    /// (aggregated over all the components) at which to stop the simulation
+    /// for each Eb/N0, an optional maximum number of frames to simulate for
+    /// each Eb/N0, the maximum number of iterations of the LDPC decoder, a
+    /// list of Eb/N0's in dB units, an optional [`Reporter`] to send messages
+    /// about the test progress, an optional master seed, and an optional
+    /// [`CancellationToken`].
+    ///
+    /// The reports sent to the reporter contain the aggregate statistics. If
+    /// the test is cancelled, [`MixtureTest::run`] returns the statistics
+    /// obtained so far.
+    ///
+    /// This function only defines the BER test. To run it it is necessary to
+    /// call the [`MixtureTest::run`] method.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        components: &[MixtureComponent],
+        decoder_implementation: Dec,
+        max_frame_errors: u64,
+        max_frames: Option<u64>,
+        max_iterations: usize,
+        ebn0s_db: &[f32],
+        reporter: Option<Reporter>,
+        seed: Option<u64>,
+        cancellation: Option<CancellationToken>,
+    ) -> Result<MixtureTest<Mod, Dec>, Error> {
+        if components.is_empty() {
+            return Err(Error::Empty);
+        }
+        if components
+            .iter()
+            .any(|c| !c.weight.is_finite() || c.weight < 0.0)
+        {
+            return Err(Error::InvalidWeights);
+        }
+        let weights = WeightedIndex::new(components.iter().map(|c| c.weight))
+            .map_err(|_| Error::InvalidWeights)?;
+        let component_weights = components.iter().map(|c| c.weight).collect::<Vec<_>>();
+        let seed = seed.unwrap_or_else(rand::random);
+        let components = components
+            .iter()
+            .map(|c| {
+                let k = c.h.num_cols() - c.h.num_rows();
+                let puncturer = c.puncturing_pattern.as_deref().map(Puncturer::new);
+                let puncturer_rate = puncturer.as_ref().map_or(1.0, |p| p.rate());
+                let n = (c.h.num_cols() as f64 / puncturer_rate).round() as usize;
+                Ok(Component {
+                    k,
+                    n,
+                    encoder: Encoder::from_h(&c.h).map_err(|error| Error::Encoder {
+                        name: c.name.clone(),
+                        error,
+                    })?,
+                    graph: Arc::new(CompiledGraph::new(Arc::clone(&c.h))),
+                    metadata: Metadata::new::<Mod, Dec>(&c.h, &decoder_implementation, seed),
+                    ordering: BitOrdering::new(puncturer, None, None),
+                })
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        let (k, n) = components
+            .iter()
+            .zip(component_weights)
+            .fold((0.0, 0.0), |(k, n), (c, w)| {
+                (k + w * c.k as f64, n + w * c.n as f64)
+            });
+        let rate = k / n;
+        Ok(MixtureTest {
+            decoder_implementation,
+            num_workers: num_cpus::get(),
+            components,
+            weights,
+            rate,
+            modulator: Mod::Modulator::default(),
+            ebn0s_db: ebn0s_db.to_owned(),
+            statistics: Vec::with_capacity(ebn0s_db.len()),
+            max_iterations,
+            max_frame_errors,
+            max_frames,
+            seed,
+            cancellation,
+            reporter,
+            last_reported: Instant::now(),
+        })
+    }
+
+    /// Returns the average rate of the mixture.
+    ///
+    /// This is the ratio between the average number of information bits and
+    /// the average number of transmitted bits per frame.
+    pub fn rate(&self) -> f64 {
+        self.rate
+    }
+
+    /// Returns the master seed of the random streams.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Sets the modulator used in the mixture BER test.
+    ///
+    /// See [`BerTest::with_modulator`](super::ber::BerTest::with_modulator).
+    pub fn with_modulator(mut self, modulator: Mod::Modulator) -> MixtureTest<Mod, Dec> {
+        self.modulator = modulator;
+        self
+    }
+
+    /// Runs the mixture BER test.
+    ///
+    /// This function runs the BER test until completion or until it is
+    /// cancelled. It returns a list of statistics for each Eb/N0, or an error.
+    pub fn run(mut self) -> Result<Vec<MixtureStatistics>, Box<dyn std::error::Error>> {
+        let ret = self.do_run();
+        if let Some(reporter) = self.reporter.as_ref() {
+            reporter.tx.send(Report::Finished).unwrap();
+        }
+        ret?;
+        Ok(self.statistics)
+    }
+
+    fn cancelled(&self) -> bool {
+        self.cancellation.as_ref().is_some_and(|t| t.is_cancelled())
+    }
+
+    fn do_run(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        self.last_reported = Instant::now();
+        for (ebn0_index, ebn0_db) in self.ebn0s_db.clone().into_iter().enumerate() {
+            let ebn0 = 10.0_f64.powf(0.1 * f64::from(ebn0_db));
+            let esn0 = self.rate * Mod::BITS_PER_SYMBOL * ebn0;
+            let noise_sigma = (0.5 / esn0).sqrt();
+            let (results_tx, results_rx) = mpsc::channel();
+            let workers = (0..self.num_workers)
+                .map(|worker_index| {
+                    let (mut worker, terminate_tx) = self.make_worker(
+                        noise_sigma,
+                        results_tx.clone(),
+                        ((ebn0_index as u64) << 32) | worker_index as u64,
+                    );
+                    let handle = std::thread::spawn(move || worker.work());
+                    (handle, terminate_tx)
+                })
+                .collect::<Vec<_>>();
+
+            let start = Instant::now();
+            let mut aggregate = CurrentStatistics::default();
+            let mut components = vec![CurrentStatistics::default(); self.components.len()];
+            while aggregate.frame_errors < self.max_frame_errors
+                && self.max_frames.is_none_or(|max| aggregate.num_frames < max)
+                && !self.cancelled()
+            {
+                match results_rx.recv().unwrap() {
+                    Ok(result) => {
+                        let k = self.components[result.component].k;
+                        aggregate.update(&result, k);
+                        components[result.component].update(&result, k);
+                    }
+                    Err(()) => break,
+                }
+                self.report(&aggregate, start, ebn0_db, false);
+            }
+            // If the test is cancelled before simulating any frames for this
+            // Eb/N0, there are no statistics to report.
+            let has_statistics = aggregate.num_frames > 0;
+            if has_statistics {
+                self.report(&aggregate, start, ebn0_db, true);
+            }
+
+            for (_, terminate_tx) in workers.iter() {
+                // we don't care if this fails because the worker has terminated
+                // and dropped the channel.
+                let _ = terminate_tx.send(());
+            }
+
+            let mut join_error = None;
+            for (handle, _) in workers.into_iter() {
+                if let Err(e) = handle.join().unwrap() {
+                    join_error = Some(e);
+                }
+            }
+            if let Some(e) = join_error {
+                return Err(e);
+            }
+
+            if has_statistics {
+                self.statistics.push(MixtureStatistics {
+                    aggregate: aggregate.statistics(start, ebn0_db, self.max_iterations),
+                    components: components
+                        .iter()
+                        .zip(&self.components)
+                        .map(|(c, component)| Statistics {
+                            metadata: Some(component.metadata.clone()),
+                            ..c.statistics(start, ebn0_db, self.max_iterations)
+                        })
+                        .collect(),
+                });
+            }
+            if self.cancelled() {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    fn report(&mut self, stats: &CurrentStatistics, start: Instant, ebn0_db: f32, last: bool) {
+        if let Some(reporter) = self.reporter.as_ref() {
+            let now = Instant::now();
+            if last || self.last_reported + reporter.interval < now {
+                reporter
+                    .tx
+                    .send(Report::Statistics(stats.statistics(
+                        start,
+                        ebn0_db,
+                        self.max_iterations,
+                    )))
+                    .unwrap();
+                self.last_reported = now;
+            }
+        }
+    }
+
+    fn make_worker(
+        &self,
+        noise_sigma: f64,
+        results_tx: Sender<WorkerResult>,
+        stream_index: u64,
+    ) -> (Worker<Mod>, SyncSender<()>) {
+        let (terminate_tx, terminate_rx) = mpsc::sync_channel(1);
+        let mut decoder_rng = stream_rng(self.seed, "decoder", stream_index);
+        (
+            Worker {
+                terminate_rx,
+                results_tx,
+                components: self
+                    .components
+                    .iter()
+                    .map(|c| WorkerComponent {
+                        k: c.k,
+                        encoder: c.encoder.clone(),
+                        ordering: c.ordering.clone(),
+                        decoder: self
+                            .decoder_implementation
+                            .build_seeded_decoder(Arc::clone(&c.graph), decoder_rng.gen()),
+                        decoded: vec![0; c.graph.num_vars()],
+                    })
+                    .collect(),
+                weights: self.weights.clone(),
+                modulator: self.modulator.clone(),
+                channel: AwgnChannel::new(noise_sigma),
+                demodulator: Mod::demodulator(&self.modulator, noise_sigma),
+                max_iterations: self.max_iterations,
+                mixture_rng: stream_rng(self.seed, "mixture", stream_index),
+                message_rng: stream_rng(self.seed, "message", stream_index),
+                noise_rng: stream_rng(self.seed, "noise", stream_index),
+            },
+            terminate_tx,
+        )
+    }
+}
+
+impl<Mod: Modulation> Worker<Mod> {
+    fn work(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+        loop {
+            match self.terminate_rx.try_recv() {
+                Ok(()) => return Ok(()),
+                Err(TryRecvError::Disconnected) => panic!(),
+                Err(TryRecvError::Empty) => (),
+            };
+            let result = self.simulate();
+            let to_send = match result.as_ref() {
+                Ok(r) => Ok(r.clone()),
+                Err(_) => Err(()),
+            };
+            self.results_tx.send(to_send).unwrap();
+            result?;
+        }
+    }
+
+    fn simulate(
+        &mut self,
+    ) -> Result<WorkerResultOk, Box<dyn std::error::Error + Send + Sync + 'static>> {
+        let index = self.mixture_rng.sample(&self.weights);
+        let component = &mut self.components[index];
+        let message = (&mut self.message_rng)
+            .sample_iter(Standard)
+            .map(<u8 as From<bool>>::from)
+            .take(component.k)
+            .collect::<Vec<u8>>();
+        let codeword = component
+            .encoder
+            .encode(&Array1::from_iter(message.iter().map(|&b| {
+                if b == 1 {
+                    GF2::one()
+                } else {
+                    GF2::zero()
+                }
+            })));
+        let transmitted = component.ordering.codeword_to_transmission(&codeword)?;
+        let mut symbols = self.modulator.modulate(&transmitted);
+        self.channel.add_noise(&mut self.noise_rng, &mut symbols);
+        let llrs_demod = self.demodulator.demodulate(&symbols);
+        let llrs_decoder = component.ordering.llrs_to_codeword(&llrs_demod)?;
+
+        let decoded = &mut component.decoded;
+        let (iterations, success) =
+            match component
+                .decoder
+                .decode_into(&llrs_decoder, decoded, self.max_iterations)
+            {
+                Ok(iterations) => (iterations, true),
+                Err(iterations) => (iterations, false),
+            };
+        // Count only bit errors in the systematic part of the codeword
+        let bit_errors = message
+            .iter()
+            .zip(decoded.iter())
+            .filter(|(&a, &b)| a != b)
+            .count() as u64;
+        let frame_error = bit_errors > 0;
+        let false_decode = frame_error && success;
+        Ok(WorkerResultOk {
+            component: index,
+            bit_errors,
+            frame_error,
+            false_decode,
+            false_decode_distance: false_decode.then(|| codeword_distance(&codeword, decoded)),
+            iterations: iterations as u64,
+        })
+    }
+}
+
+impl CurrentStatistics {
+    fn update(&mut self, result: &WorkerResultOk, k: usize) {
+        self.num_frames += 1;
+        self.info_bits += k as u64;
+        self.bit_errors += result.bit_errors;
+        self.frame_errors += u64::from(result.frame_error);
+        self.false_decodes += u64::from(result.false_decode);
+        if let Some(distance) = result.false_decode_distance {
+            *self.false_decode_distances.entry(distance).or_default() += 1;
+        }
+        self.total_iterations += result.iterations;
+        if !result.frame_error {
+            self.correct_iterations += result.iterations;
+        }
+    }
+
+    fn statistics(&self, start: Instant, ebn0_db: f32, max_iterations: usize) -> Statistics {
+        let elapsed = Instant::now() - start;
+        Statistics {
+            ebn0_db,
+            num_frames: self.num_frames,
+            total_iterations: self.total_iterations,
+            false_decodes: self.false_decodes,
+            false_decode_distances: self.false_decode_distances.clone(),
+            average_iterations: self.total_iterations as f64 / self.num_frames as f64,
+            elapsed,
+            throughput_mbps: 1e-6 * self.info_bits as f64 / elapsed.as_secs_f64(),
+            warm_up_frames: 0,
+            worker_utilization: Vec::new(),
+            max_llr_magnitude: None,
+            max_iterations,
+            ldpc: CodeStatistics {
+                bit_errors: self.bit_errors,
+                frame_errors: self.frame_errors,
+                correct_iterations: self.correct_iterations,
+                ber: self.bit_errors as f64 / self.info_bits as f64,
+                fer: self.frame_errors as f64 / self.num_frames as f64,
+                ber_confidence_interval: ber_confidence_interval(self.bit_errors, self.info_bits),
+                fer_confidence_interval: fer_confidence_interval(
+                    self.frame_errors,
+                    self.num_frames,
+                ),
+                average_iterations_correct: self.correct_iterations as f64
+                    / (self.num_frames - self.frame_errors) as f64,
+            },
+            bch: None,
+            metadata: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{codes::reference::ReferenceCode, simulation::modulation::Bpsk};
+
+    fn component(code: ReferenceCode, weight: f64) -> MixtureComponent {
+        MixtureComponent {
+            name: code.to_string(),
+            h: Arc::new(code.h()),
+            puncturing_pattern: None,
+            weight,
+        }
+    }
+
+    #[test]
+    fn mixture() {
+        let components = [
+            component(ReferenceCode::Hamming7_4, 1.0),
+            component(ReferenceCode::ExtendedGolay24_12, 3.0),
+        ];
+        let new = |components: &[MixtureComponent]| {
+            MixtureTest::<Bpsk>::new(
+                components,
+                DecoderImplementation::Phif64,
+                20,
+                None,
+                10,
+                &[0.0],
+                None,
+                Some(0),
+                None,
+            )
+        };
+        assert!(matches!(new(&[]), Err(Error::Empty)));
+        assert!(matches!(
+            new(&[component(ReferenceCode::Hamming7_4, 0.0)]),
+            Err(Error::InvalidWeights)
+        ));
+        let test = new(&components).unwrap();
+        // (4 + 3 * 12) / (7 + 3 * 24)
+        assert!((test.rate() - 40.0 / 79.0).abs() < 1e-12);
+        let statistics = test.run().unwrap();
+        assert_eq!(statistics.len(), 1);
+        let stats = &statistics[0];
+        assert!(stats.aggregate.ldpc.frame_errors >= 20);
+        assert_eq!(
+            stats.aggregate.num_frames,
+            stats.components.iter().map(|c| c.num_frames).sum::<u64>()
+        );
+        assert_eq!(
+            stats.aggregate.ldpc.bit_errors,
+            stats
+                .components
+                .iter()
+                .map(|c| c.ldpc.bit_errors)
+                .sum::<u64>()
+        );
+        // The distances of the false decodes are at least the minimum distance
+        // of each code
+        for (c, min_distance) in stats.components.iter().zip([3, 8]) {
+            assert_eq!(
+                c.false_decode_distances.values().sum::<u64>(),
+                c.false_decodes
+            );
+            assert!(c
+                .false_decode_distances
+                .keys()
+                .all(|&distance| distance >= min_distance));
+        }
+        assert_eq!(
+            stats.aggregate.false_decode_distances.values().sum::<u64>(),
+            stats.aggregate.false_decodes
+        );
+    }
+
+    #[test]
+    fn max_frames() {
+        let test = MixtureTest::<Bpsk>::new(
+            &[component(ReferenceCode::Hamming7_4, 1.0)],
+            DecoderImplementation::Phif64,
+            1000,
+            Some(50),
+            10,
+            &[20.0],
+            None,
+            Some(0),
+            None,
+        )
+        .unwrap();
+        let statistics = test.run().unwrap();
+        assert_eq!(statistics[0].aggregate.num_frames, 50);
+    }
+}
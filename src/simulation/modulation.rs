@@ -26,6 +26,21 @@ pub trait Modulation: 'static {
     type Demodulator: Demodulator<T = Self::T>;
     /// Number of bits per symbol.
     const BITS_PER_SYMBOL: f64;
+    /// Name of the modulation.
+    ///
+    /// This is used to describe the modulation in the BER test results.
+    const NAME: &'static str;
+
+    /// Creates the demodulator for a modulator.
+    ///
+    /// This is used by modulations whose modulator has parameters, such as the
+    /// ring ratio of [`Apsk16`], so that the demodulator uses the same
+    /// constellation as the modulator. The default implementation ignores the
+    /// modulator and calls [`Demodulator::from_noise_sigma`].
+    fn demodulator(modulator: &Self::Modulator, noise_sigma: f64) -> Self::Demodulator {
+        let _ = modulator;
+        Self::Demodulator::from_noise_sigma(noise_sigma)
+    }
 }
 
 /// Modulator.
@@ -48,7 +63,7 @@ pub trait Modulator: Default + Clone + Send {
 /// sequence of symbols.
 pub trait Demodulator: Send {
     /// Scalar type for the symbols.
-    type T;
+    type T: ChannelType;
 
     /// Creates a new demodulator.
     ///
@@ -59,6 +74,26 @@ pub trait Demodulator: Send {
 
     /// Returns the LLRs corresponding to a sequence of symbols.
     fn demodulate(&self, symbols: &[Self::T]) -> Vec<f64>;
+
+    /// Returns the LLRs corresponding to a sequence of faded symbols.
+    ///
+    /// The parameter `gains` contains the fading gain of each symbol, as
+    /// returned by [`Channel::transmit`](super::channel::Channel::transmit).
+    /// The gains are assumed to be known perfectly by the demodulator.
+    ///
+    /// The default implementation multiplies each symbol by its gain and calls
+    /// [`Demodulator::demodulate`]. This gives the exact LLRs for
+    /// constellations whose points all have the same energy, such as PSK
+    /// constellations. Demodulators for other constellations need to override
+    /// it.
+    fn demodulate_faded(&self, symbols: &[Self::T], gains: &[f64]) -> Vec<f64> {
+        let symbols = symbols
+            .iter()
+            .zip(gains.iter())
+            .map(|(&x, &gain)| x * gain)
+            .collect::<Vec<_>>();
+        self.demodulate(&symbols)
+    }
 }
 
 /// BPSK modulation
@@ -70,6 +105,7 @@ impl Modulation for Bpsk {
     type Modulator = BpskModulator;
     type Demodulator = BpskDemodulator;
     const BITS_PER_SYMBOL: f64 = 1.0;
+    const NAME: &'static str = "BPSK";
 }
 
 /// BPSK modulator.
@@ -141,7 +177,200 @@ impl Demodulator for BpskDemodulator {
     }
 }
 
-/// BPSK modulation
+/// Hard-decision demodulator.
+///
+/// This demodulator makes a hard decision on each bit and maps it to an LLR
+/// with a constant magnitude log((1 - p) / p), where p is the crossover
+/// probability of the binary symmetric channel formed by the transmitter, the
+/// channel and the hard decisions (see
+/// [`BscChannel`](super::channel::BscChannel)). It is used to simulate the
+/// performance of a decoder with a hard-decision receiver.
+///
+/// The LLR magnitude is limited to [`HardDecisionDemodulator::MAX_LLR`], so
+/// that it remains finite when the crossover probability is zero.
+///
+/// # Examples
+/// ```
+/// # use ldpc_toolbox::simulation::modulation::{Demodulator, HardDecisionDemodulator};
+/// let demodulator = HardDecisionDemodulator::new(0.1);
+/// let llr = 9.0_f64.ln();
+/// assert_eq!(demodulator.demodulate_bits(&[0, 1]), [llr, -llr]);
+/// // BPSK symbols, with the bit 1 mapped to +1.0
+/// assert_eq!(demodulator.demodulate(&[-0.3, 2.0]), [llr, -llr]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct HardDecisionDemodulator {
+    llr: f64,
+}
+
+impl HardDecisionDemodulator {
+    /// Maximum LLR magnitude.
+    pub const MAX_LLR: f64 = 100.0;
+
+    /// Creates a new hard-decision demodulator.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `crossover` is not between 0 and 0.5.
+    pub fn new(crossover: f64) -> HardDecisionDemodulator {
+        assert!((0.0..=0.5).contains(&crossover));
+        HardDecisionDemodulator {
+            llr: ((1.0 - crossover) / crossover)
+                .ln()
+                .min(HardDecisionDemodulator::MAX_LLR),
+        }
+    }
+
+    /// Returns the magnitude of the LLRs.
+    pub fn llr(&self) -> f64 {
+        self.llr
+    }
+
+    /// Returns the LLRs corresponding to a sequence of received bits.
+    ///
+    /// Each bit is given as a `u8` that is either 0 or 1.
+    pub fn demodulate_bits(&self, bits: &[u8]) -> Vec<f64> {
+        bits.iter()
+            .map(|&b| if b == 0 { self.llr } else { -self.llr })
+            .collect()
+    }
+
+    /// Replaces soft LLRs by the LLRs of their hard decisions.
+    ///
+    /// Each LLR is replaced by an LLR with the same sign and the magnitude of
+    /// this demodulator. LLRs equal to zero are left unchanged, since they do
+    /// not give a decision.
+    pub fn harden(&self, llrs: &mut [f64]) {
+        for x in llrs.iter_mut() {
+            if *x != 0.0 {
+                *x = self.llr.copysign(*x);
+            }
+        }
+    }
+}
+
+impl Demodulator for HardDecisionDemodulator {
+    type T = f64;
+
+    /// Creates a hard-decision demodulator for BPSK symbols.
+    ///
+    /// The crossover probability is the one obtained with
+    /// [`BscChannel::from_noise_sigma`](super::channel::BscChannel::from_noise_sigma).
+    fn from_noise_sigma(noise_sigma: f64) -> HardDecisionDemodulator {
+        HardDecisionDemodulator::new(
+            super::channel::BscChannel::from_noise_sigma(noise_sigma).crossover(),
+        )
+    }
+
+    /// Returns the LLRs of the hard decisions on a sequence of BPSK symbols.
+    ///
+    /// The symbols use the same mapping as the [BpskModulator].
+    fn demodulate(&self, symbols: &[f64]) -> Vec<f64> {
+        let mut llrs = symbols.iter().map(|&x| -x).collect::<Vec<_>>();
+        self.harden(&mut llrs);
+        llrs
+    }
+}
+
+/// QPSK modulation
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Default)]
+pub struct Qpsk {}
+
+impl Modulation for Qpsk {
+    type T = Complex<f64>;
+    type Modulator = QpskModulator;
+    type Demodulator = QpskDemodulator;
+    const BITS_PER_SYMBOL: f64 = 2.0;
+    const NAME: &'static str = "QPSK";
+}
+
+/// QPSK modulator.
+///
+/// QPSK modulator using the DVB-S2 Gray-coded constellation, in which the
+/// first bit of each symbol selects the sign of the real part and the second
+/// bit selects the sign of the imaginary part, with the bit 0 mapped to a
+/// positive sign. The modulator can only work with codewords whose length is a
+/// multiple of 2 bits.
+#[derive(Debug, Clone, Default)]
+pub struct QpskModulator {}
+
+impl QpskModulator {
+    /// Creates a new QPSK modulator.
+    pub fn new() -> QpskModulator {
+        QpskModulator::default()
+    }
+
+    fn modulate_bit(bit: GF2) -> f64 {
+        let a = (0.5f64).sqrt();
+        if bit.is_zero() {
+            a
+        } else {
+            -a
+        }
+    }
+}
+
+impl Modulator for QpskModulator {
+    type T = Complex<f64>;
+
+    /// Modulates a sequence of bits into symbols.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the length of the codeword is not a multiple of 2 bits.
+    fn modulate<S>(&self, codeword: &ArrayBase<S, Ix1>) -> Vec<Complex<f64>>
+    where
+        S: Data<Elem = GF2>,
+    {
+        assert_eq!(codeword.len() % 2, 0);
+        codeword
+            .iter()
+            .step_by(2)
+            .zip(codeword.iter().skip(1).step_by(2))
+            .map(|(&b0, &b1)| Complex::new(Self::modulate_bit(b0), Self::modulate_bit(b1)))
+            .collect()
+    }
+}
+
+/// QPSK demodulator.
+///
+/// Assumes the same mapping as the [QpskModulator]. Since the real and
+/// imaginary parts carry one bit each, the exact LLRs are obtained as for BPSK.
+#[derive(Debug, Clone, Default)]
+pub struct QpskDemodulator {
+    scale: f64,
+}
+
+impl QpskDemodulator {
+    /// Creates a new QPSK demodulator.
+    ///
+    /// The `noise_sigma` indicates the channel noise standard deviation. The
+    /// channel noise is assumed to be a circularly symmetric Gaussian with mean
+    /// zero and standard deviation `noise_sigma` in its real part and imaginary
+    /// part.
+    pub fn new(noise_sigma: f64) -> QpskDemodulator {
+        QpskDemodulator {
+            scale: 2.0 * (0.5f64).sqrt() / (noise_sigma * noise_sigma),
+        }
+    }
+}
+
+impl Demodulator for QpskDemodulator {
+    type T = Complex<f64>;
+
+    fn from_noise_sigma(noise_sigma: f64) -> QpskDemodulator {
+        QpskDemodulator::new(noise_sigma)
+    }
+
+    fn demodulate(&self, symbols: &[Complex<f64>]) -> Vec<f64> {
+        symbols
+            .iter()
+            .flat_map(|&x| [self.scale * x.re, self.scale * x.im])
+            .collect()
+    }
+}
+
+/// 8PSK modulation
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Default)]
 pub struct Psk8 {}
 
@@ -150,6 +379,7 @@ impl Modulation for Psk8 {
     type Modulator = Psk8Modulator;
     type Demodulator = Psk8Demodulator;
     const BITS_PER_SYMBOL: f64 = 3.0;
+    const NAME: &'static str = "8PSK";
 }
 
 /// 8PSK modulator.
@@ -279,6 +509,208 @@ impl Demodulator for Psk8Demodulator {
     }
 }
 
+/// 16APSK modulation
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Default)]
+pub struct Apsk16 {}
+
+impl Modulation for Apsk16 {
+    type T = Complex<f64>;
+    type Modulator = Apsk16Modulator;
+    type Demodulator = Apsk16Demodulator;
+    const BITS_PER_SYMBOL: f64 = 4.0;
+    const NAME: &'static str = "16APSK";
+
+    fn demodulator(modulator: &Apsk16Modulator, noise_sigma: f64) -> Apsk16Demodulator {
+        Apsk16Demodulator::new(noise_sigma, modulator.ring_ratio())
+    }
+}
+
+/// Default ring ratio of the 16APSK constellation.
+///
+/// This is the ring ratio used in DVB-S2 for the rate 3/4 code.
+pub const APSK16_DEFAULT_RING_RATIO: f64 = 2.85;
+
+/// Returns the ring ratio used by DVB-S2 for 16APSK with a code rate.
+///
+/// The `rate` is given as a string such as `"3/4"`. The ring ratios are those
+/// in Table 9 of EN 302 307-1. Returns `None` if DVB-S2 does not use 16APSK
+/// with this rate.
+///
+/// # Examples
+/// ```
+/// # use ldpc_toolbox::simulation::modulation::dvbs2_apsk16_ring_ratio;
+/// assert_eq!(dvbs2_apsk16_ring_ratio("2/3"), Some(3.15));
+/// assert_eq!(dvbs2_apsk16_ring_ratio("1/2"), None);
+/// ```
+pub fn dvbs2_apsk16_ring_ratio(rate: &str) -> Option<f64> {
+    Some(match rate {
+        "2/3" => 3.15,
+        "3/4" => 2.85,
+        "4/5" => 2.75,
+        "5/6" => 2.70,
+        "8/9" => 2.60,
+        "9/10" => 2.57,
+        _ => return None,
+    })
+}
+
+// Constellation of the DVB-S2 16APSK with unit average energy. The element j
+// corresponds to the bits of j, with the first bit as the MSB.
+fn apsk16_constellation(ring_ratio: f64) -> [Complex<f64>; 16] {
+    use std::f64::consts::PI;
+    let r1 = (4.0 / (1.0 + 3.0 * ring_ratio * ring_ratio)).sqrt();
+    let r2 = ring_ratio * r1;
+    // Angles in units of pi / 12. The first 12 points belong to the outer ring
+    // and the last 4 points to the inner ring.
+    let angles = [3, -3, 9, -9, 1, -1, 11, -11, 5, -5, 7, -7, 3, -3, 9, -9];
+    std::array::from_fn(|j| {
+        Complex::from_polar(
+            if j < 12 { r2 } else { r1 },
+            f64::from(angles[j]) * PI / 12.0,
+        )
+    })
+}
+
+/// 16APSK modulator.
+///
+/// 16APSK modulator using the DVB-S2 constellation, which has 4 points in the
+/// inner ring and 12 points in the outer ring. The ring ratio is the ratio
+/// between the radii of the outer and inner rings. The constellation is
+/// normalized to unit average energy. The modulator can only work with
+/// codewords whose length is a multiple of 4 bits.
+#[derive(Debug, Clone)]
+pub struct Apsk16Modulator {
+    ring_ratio: f64,
+    constellation: [Complex<f64>; 16],
+}
+
+impl Apsk16Modulator {
+    /// Creates a new 16APSK modulator with a given ring ratio.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the ring ratio is not larger than one.
+    pub fn new(ring_ratio: f64) -> Apsk16Modulator {
+        assert!(ring_ratio > 1.0);
+        Apsk16Modulator {
+            ring_ratio,
+            constellation: apsk16_constellation(ring_ratio),
+        }
+    }
+
+    /// Returns the ring ratio of the constellation.
+    pub fn ring_ratio(&self) -> f64 {
+        self.ring_ratio
+    }
+}
+
+impl Default for Apsk16Modulator {
+    fn default() -> Apsk16Modulator {
+        Apsk16Modulator::new(APSK16_DEFAULT_RING_RATIO)
+    }
+}
+
+impl Modulator for Apsk16Modulator {
+    type T = Complex<f64>;
+
+    /// Modulates a sequence of bits into symbols.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the length of the codeword is not a multiple of 4 bits.
+    fn modulate<S>(&self, codeword: &ArrayBase<S, Ix1>) -> Vec<Complex<f64>>
+    where
+        S: Data<Elem = GF2>,
+    {
+        assert_eq!(codeword.len() % 4, 0);
+        codeword
+            .iter()
+            .collect::<Vec<_>>()
+            .chunks_exact(4)
+            .map(|bits| {
+                let j = bits
+                    .iter()
+                    .fold(0, |j, b| (j << 1) | usize::from(b.is_one()));
+                self.constellation[j]
+            })
+            .collect()
+    }
+}
+
+/// 16APSK demodulator.
+///
+/// Assumes the same mapping as the [Apsk16Modulator]. Demodulates symbols into
+/// LLRs using the exact formula implemented with the max-* function.
+#[derive(Debug, Clone)]
+pub struct Apsk16Demodulator {
+    scale: f64,
+    constellation: [Complex<f64>; 16],
+}
+
+impl Apsk16Demodulator {
+    /// Creates a new 16APSK demodulator.
+    ///
+    /// The `noise_sigma` indicates the channel noise standard deviation, as
+    /// in [`Psk8Demodulator::new`], and `ring_ratio` is the ring ratio of the
+    /// constellation.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the ring ratio is not larger than one.
+    pub fn new(noise_sigma: f64, ring_ratio: f64) -> Apsk16Demodulator {
+        assert!(ring_ratio > 1.0);
+        Apsk16Demodulator {
+            scale: 1.0 / (noise_sigma * noise_sigma),
+            constellation: apsk16_constellation(ring_ratio),
+        }
+    }
+
+    // The received constellation is the constellation scaled by the fading
+    // gain.
+    fn demodulate_symbol(&self, symbol: Complex<f64>, gain: f64) -> [f64; 4] {
+        let symbol = symbol * (self.scale * gain);
+        // The energy of the points is not constant, so it must be included in
+        // the metrics.
+        let metrics = self
+            .constellation
+            .map(|point| dot(symbol, point) - 0.5 * self.scale * gain * gain * point.norm_sqr());
+        std::array::from_fn(|bit| {
+            let mask = 8 >> bit;
+            let metric = |value| {
+                (0..16)
+                    .filter(|j| (j & mask == 0) == (value == 0))
+                    .map(|j| metrics[j])
+                    .reduce(maxstar)
+                    .unwrap()
+            };
+            metric(0) - metric(1)
+        })
+    }
+}
+
+impl Demodulator for Apsk16Demodulator {
+    type T = Complex<f64>;
+
+    fn from_noise_sigma(noise_sigma: f64) -> Apsk16Demodulator {
+        Apsk16Demodulator::new(noise_sigma, APSK16_DEFAULT_RING_RATIO)
+    }
+
+    fn demodulate(&self, symbols: &[Complex<f64>]) -> Vec<f64> {
+        symbols
+            .iter()
+            .flat_map(|&x| self.demodulate_symbol(x, 1.0))
+            .collect()
+    }
+
+    fn demodulate_faded(&self, symbols: &[Complex<f64>], gains: &[f64]) -> Vec<f64> {
+        symbols
+            .iter()
+            .zip(gains.iter())
+            .flat_map(|(&x, &gain)| self.demodulate_symbol(x, gain))
+            .collect()
+    }
+}
+
 fn dot(a: Complex<f64>, b: Complex<f64>) -> f64 {
     a.re * b.re + a.im * b.im
 }
@@ -321,6 +753,46 @@ mod test {
         );
     }
 
+    #[test]
+    fn qpsk() {
+        let o = GF2::one();
+        let z = GF2::zero();
+        let modulator = QpskModulator::new();
+        let x = modulator.modulate(&ndarray::arr1(&[z, o, o, o]));
+        let a = (0.5f64).sqrt();
+        assert_eq!(&x, &[Complex::new(a, -a), Complex::new(-a, -a)]);
+        let llr = QpskDemodulator::new(1.0).demodulate(&x);
+        assert!(llr[0] > 0.0 && llr[1] < 0.0 && llr[2] < 0.0 && llr[3] < 0.0);
+    }
+
+    #[test]
+    fn apsk16() {
+        let ring_ratio = 3.15;
+        let modulator = Apsk16Modulator::new(ring_ratio);
+        let constellation = &modulator.constellation;
+        let energy = constellation.iter().map(|x| x.norm_sqr()).sum::<f64>() / 16.0;
+        assert!((energy - 1.0).abs() < 1e-12);
+        assert!((constellation[0].norm() / constellation[12].norm() - ring_ratio).abs() < 1e-12);
+        // The demodulator recovers the bits of each point without noise
+        let demodulator = Apsk16Demodulator::new(0.1, ring_ratio);
+        for (j, &point) in constellation.iter().enumerate() {
+            let bits = (0..4)
+                .map(|b| {
+                    if j & (8 >> b) != 0 {
+                        GF2::one()
+                    } else {
+                        GF2::zero()
+                    }
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(modulator.modulate(&ndarray::arr1(&bits)), vec![point]);
+            let llr = demodulator.demodulate(&[point]);
+            for (&b, &l) in bits.iter().zip(llr.iter()) {
+                assert_eq!(b.is_one(), l < 0.0);
+            }
+        }
+    }
+
     #[test]
     fn psk8_demodulator_signs() {
         let noise_sigma = 1.0;
@@ -344,4 +816,22 @@ mod test {
         assert!(llr[7] > 0.0);
         assert!(llr[8] > 0.0);
     }
+
+    #[test]
+    fn faded_demodulation() {
+        let demodulator = BpskDemodulator::new(1.0);
+        let llr = demodulator.demodulate_faded(&[0.5, -0.5], &[0.5, 2.0]);
+        assert_eq!(llr, vec![-0.5, 2.0]);
+        // A faded point of the outer ring is close to the inner ring, but it
+        // is demodulated correctly when the gain is known
+        let ring_ratio = 2.85;
+        let modulator = Apsk16Modulator::new(ring_ratio);
+        let demodulator = Apsk16Demodulator::new(0.1, ring_ratio);
+        let gain = 1.0 / ring_ratio;
+        let faded = modulator.constellation[0] * gain;
+        let llr = demodulator.demodulate_faded(&[faded], &[gain]);
+        assert!(llr.iter().all(|&l| l > 0.0));
+        let llr = demodulator.demodulate(&[faded]);
+        assert!(llr[1] < 0.0);
+    }
 }
@@ -0,0 +1,276 @@
+//! Bit ordering conversions.
+//!
+//! This module implements conversions between the two orderings of the bits of
+//! a codeword that appear in a communications system:
+//!
+//! - The *codeword order* is the order used by the encoder and the decoder,
+//!   which corresponds to the columns of the parity check matrix.
+//!
+//! - The *transmission order* is the order in which the bits are mapped to the
+//!   modulation symbols. Conversion from codeword order to transmission order
+//!   consists of puncturing, scrambling and interleaving, in this order.
+//!
+//! The [`BitOrdering`] struct performs the conversions in both directions, both
+//! for codewords and for LLRs, and it can also describe the conversion as a
+//! permutation of positions. The same conversions are used by
+//! [`BerTest`](super::ber::BerTest), so external transmitters and receivers can
+//! use this struct to interoperate with the codewords and LLRs of this crate.
+
+use super::{
+    interleaving::Interleaver,
+    puncturing::{self, Puncturer},
+    scrambling::Scrambler,
+};
+use ndarray::{Array1, ArrayBase, Data, Ix1};
+use num_traits::{One, Zero};
+use std::ops::{Add, Neg};
+use thiserror::Error;
+
+/// Bit ordering.
+///
+/// This struct converts codewords and LLRs between codeword order and
+/// transmission order. See the [module documentation](self) for the
+/// definitions of these orders.
+#[derive(Debug, Clone, Default)]
+pub struct BitOrdering {
+    puncturer: Option<Puncturer>,
+    scrambler: Option<Scrambler>,
+    interleaver: Option<Interleaver>,
+}
+
+/// Bit ordering error.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Error)]
+pub enum Error {
+    /// Puncturing error.
+    #[error("puncturing error: {0}")]
+    Puncturing(#[from] puncturing::Error),
+    /// The length is not divisible by the number of interleaver columns.
+    #[error("length not divisible by the number of interleaver columns")]
+    InterleaverLength,
+}
+
+impl BitOrdering {
+    /// Creates a new bit ordering.
+    ///
+    /// Each of the `puncturer`, `scrambler` and `interleaver` can be `None` to
+    /// indicate that the corresponding step is not performed. If all of them
+    /// are `None`, the codeword order and the transmission order coincide.
+    pub fn new(
+        puncturer: Option<Puncturer>,
+        scrambler: Option<Scrambler>,
+        interleaver: Option<Interleaver>,
+    ) -> BitOrdering {
+        BitOrdering {
+            puncturer,
+            scrambler,
+            interleaver,
+        }
+    }
+
+    /// Sets the scrambler of the bit ordering.
+    ///
+    /// This replaces the scrambler given in [`BitOrdering::new`], if any.
+    pub fn with_scrambler(mut self, scrambler: Scrambler) -> BitOrdering {
+        self.scrambler = Some(scrambler);
+        self
+    }
+
+    /// Converts a codeword from codeword order to transmission order.
+    ///
+    /// The codeword is punctured, scrambled and interleaved.
+    pub fn codeword_to_transmission<S, T>(
+        &self,
+        codeword: &ArrayBase<S, Ix1>,
+    ) -> Result<Array1<T>, Error>
+    where
+        S: Data<Elem = T>,
+        T: Clone + Zero + One + Add<Output = T>,
+    {
+        let codeword = match &self.puncturer {
+            Some(p) => p.puncture(codeword)?,
+            None => codeword.to_owned(),
+        };
+        let codeword = match &self.scrambler {
+            Some(s) => s.scramble(&codeword),
+            None => codeword,
+        };
+        self.interleave(&codeword)
+    }
+
+    /// Converts LLRs from transmission order to codeword order.
+    ///
+    /// The LLRs are deinterleaved, descrambled and depunctured. The punctured
+    /// bits are given an LLR equal to `T::default()`, which is zero for
+    /// numeric types.
+    pub fn llrs_to_codeword<T>(&self, llrs: &[T]) -> Result<Vec<T>, Error>
+    where
+        T: Copy + Default + Zero + Neg<Output = T>,
+    {
+        let llrs = match &self.interleaver {
+            Some(i) => {
+                self.check_interleaver_length(llrs.len())?;
+                i.deinterleave(llrs)
+            }
+            None => llrs.to_vec(),
+        };
+        let llrs = match &self.scrambler {
+            Some(s) => s.descramble(&llrs),
+            None => llrs,
+        };
+        Ok(match &self.puncturer {
+            Some(p) => p.depuncture(&llrs)?,
+            None => llrs,
+        })
+    }
+
+    /// Converts LLRs from codeword order to transmission order.
+    ///
+    /// The LLRs of the punctured bits are discarded, the signs of the LLRs are
+    /// flipped according to the scrambler, and the LLRs are interleaved. This
+    /// can be used, for instance, to compare the LLRs computed by the decoder
+    /// with those of an external receiver.
+    pub fn llrs_to_transmission<T>(&self, llrs: &[T]) -> Result<Vec<T>, Error>
+    where
+        T: Copy + Zero + Neg<Output = T>,
+    {
+        let llrs = Array1::from_vec(llrs.to_vec());
+        let llrs = match &self.puncturer {
+            Some(p) => p.puncture(&llrs)?,
+            None => llrs,
+        };
+        let llrs = match &self.scrambler {
+            Some(s) => Array1::from_vec(s.descramble(llrs.as_slice().unwrap())),
+            None => llrs,
+        };
+        Ok(self.interleave(&llrs)?.to_vec())
+    }
+
+    /// Returns the codeword positions in transmission order.
+    ///
+    /// The element `j` of the returned vector is the position in codeword
+    /// order of the bit transmitted in position `j`. The length of the
+    /// returned vector is the length of the transmitted codeword, since the
+    /// punctured bits do not appear. The effect of the scrambler is not
+    /// included, since it does not change the positions of the bits.
+    ///
+    /// # Examples
+    /// ```
+    /// # use ldpc_toolbox::simulation::{
+    /// #     interleaving::Interleaver, ordering::BitOrdering, puncturing::Puncturer,
+    /// # };
+    /// let ordering = BitOrdering::new(
+    ///     Some(Puncturer::new(&[true, true, false])),
+    ///     None,
+    ///     Some(Interleaver::new(2, false)),
+    /// );
+    /// assert_eq!(ordering.transmission_positions(6).unwrap(), vec![0, 2, 1, 3]);
+    /// ```
+    pub fn transmission_positions(&self, codeword_length: usize) -> Result<Vec<usize>, Error> {
+        let positions = Array1::from_iter(0..codeword_length);
+        let positions = match &self.puncturer {
+            Some(p) => p.puncture(&positions)?,
+            None => positions,
+        };
+        Ok(self.interleave(&positions)?.to_vec())
+    }
+
+    /// Returns the transmission positions in codeword order.
+    ///
+    /// The element `j` of the returned vector is the position in transmission
+    /// order of the bit in position `j` of the codeword, or `None` if this bit
+    /// is punctured. This is the inverse of
+    /// [`transmission_positions`](BitOrdering::transmission_positions).
+    pub fn codeword_positions(&self, codeword_length: usize) -> Result<Vec<Option<usize>>, Error> {
+        let mut positions = vec![None; codeword_length];
+        for (j, k) in self
+            .transmission_positions(codeword_length)?
+            .into_iter()
+            .enumerate()
+        {
+            positions[k] = Some(j);
+        }
+        Ok(positions)
+    }
+
+    fn interleave<S, T>(&self, codeword: &ArrayBase<S, Ix1>) -> Result<Array1<T>, Error>
+    where
+        S: Data<Elem = T>,
+        T: Clone + Zero,
+    {
+        Ok(match &self.interleaver {
+            Some(i) => {
+                self.check_interleaver_length(codeword.len())?;
+                i.interleave(codeword)
+            }
+            None => codeword.to_owned(),
+        })
+    }
+
+    fn check_interleaver_length(&self, length: usize) -> Result<(), Error> {
+        match &self.interleaver {
+            Some(i) if !length.is_multiple_of(i.columns()) => Err(Error::InterleaverLength),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::gf2::GF2;
+
+    fn ordering() -> BitOrdering {
+        BitOrdering::new(
+            Some(Puncturer::new(&[true, false, true])),
+            Some(Scrambler::new(0x1a9, 0xff).unwrap()),
+            Some(Interleaver::new(2, true)),
+        )
+    }
+
+    #[test]
+    fn round_trip() {
+        let ordering = ordering();
+        let codeword = Array1::from_iter([1, 0, 0, 1, 1, 0, 1, 1, 0, 0, 1, 0].iter().map(|&b| {
+            if b == 1 {
+                GF2::one()
+            } else {
+                GF2::zero()
+            }
+        }));
+        let transmitted = ordering.codeword_to_transmission(&codeword).unwrap();
+        assert_eq!(transmitted.len(), 8);
+        let llrs = transmitted
+            .iter()
+            .map(|x| if x.is_one() { -1.0 } else { 1.0 })
+            .collect::<Vec<_>>();
+        let llrs_codeword = ordering.llrs_to_codeword(&llrs).unwrap();
+        let positions = ordering.codeword_positions(codeword.len()).unwrap();
+        for ((x, &llr), position) in codeword.iter().zip(llrs_codeword.iter()).zip(positions) {
+            match position {
+                Some(_) => assert_eq!(llr, if x.is_one() { -1.0 } else { 1.0 }),
+                None => assert_eq!(llr, 0.0),
+            }
+        }
+        assert_eq!(ordering.llrs_to_transmission(&llrs_codeword).unwrap(), llrs);
+    }
+
+    #[test]
+    fn positions() {
+        let ordering = ordering();
+        let positions = ordering.transmission_positions(12).unwrap();
+        let inverse = ordering.codeword_positions(12).unwrap();
+        for (j, &k) in positions.iter().enumerate() {
+            assert_eq!(inverse[k], Some(j));
+        }
+        assert_eq!(inverse.iter().filter(|p| p.is_none()).count(), 4);
+    }
+
+    #[test]
+    fn interleaver_length() {
+        let ordering = BitOrdering::new(None, None, Some(Interleaver::new(3, false)));
+        assert_eq!(
+            ordering.transmission_positions(7),
+            Err(Error::InterleaverLength)
+        );
+    }
+}
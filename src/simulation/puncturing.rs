@@ -1,5 +1,31 @@
 //! Code puncturing.
+//!
+//! The [`Puncturer`] removes bits from the codewords before transmission. Its
+//! pattern can be defined by blocks ([`Puncturer::new`]), so that each element
+//! of the pattern applies to a contiguous block of the codeword, or per bit
+//! ([`Puncturer::per_bit`]), so that the pattern is repeated periodically over
+//! the codeword. A [`PuncturingPattern`] describes any of these patterns or
+//! the puncturing defined by some standards, and it gives the puncturer for a
+//! particular codeword length.
+//!
+//! # Examples
+//! ```
+//! # use ldpc_toolbox::simulation::puncturing::PuncturingPattern;
+//! # use ndarray::Array1;
+//! // 5G NR puncturing with lifting size 2
+//! let pattern: PuncturingPattern = "nr:2".parse()?;
+//! let puncturer = pattern.puncturer(10)?;
+//! let codeword = Array1::from_iter(0..10);
+//! assert_eq!(puncturer.puncture(&codeword)?.to_vec(), [4, 5, 6, 7, 8, 9]);
+//! // Every third bit punctured
+//! let pattern: PuncturingPattern = "bits:1,1,0".parse()?;
+//! let puncturer = pattern.puncturer(9)?;
+//! let codeword = Array1::from_iter(0..9);
+//! assert_eq!(puncturer.puncture(&codeword)?.to_vec(), [0, 1, 3, 4, 6, 7]);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
 
+use crate::sparse::{ColumnLabel, SparseMatrix};
 use ndarray::{s, Array1, ArrayBase, Data, Ix1};
 use thiserror::Error;
 
@@ -11,6 +37,7 @@ use thiserror::Error;
 pub struct Puncturer {
     pattern: Box<[bool]>,
     num_trues: usize,
+    per_bit: bool,
 }
 
 /// Puncturer error.
@@ -19,6 +46,12 @@ pub enum Error {
     /// The codeword size is not divisible by the puncturing pattern length
     #[error("codeword size not divisible by puncturing pattern length")]
     CodewordSizeNotDivisible,
+    /// The codeword is too short for the puncturing pattern
+    #[error("codeword too short for the puncturing pattern")]
+    CodewordTooShort,
+    /// The codeword size does not correspond to an AR4JA code
+    #[error("codeword size does not correspond to an AR4JA code")]
+    NotAr4ja,
 }
 
 impl Puncturer {
@@ -36,6 +69,24 @@ impl Puncturer {
         Puncturer {
             pattern: pattern.into(),
             num_trues: pattern.iter().filter(|&&b| b).count(),
+            per_bit: false,
+        }
+    }
+
+    /// Creates a new puncturer with a per-bit pattern.
+    ///
+    /// The puncturing pattern is defined by bits and repeated periodically
+    /// over the codeword. For example `[true, true, false]` means that every
+    /// third bit of the codeword is punctured. A pattern with the same length
+    /// as the codeword gives an arbitrary mask of the bits to preserve.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the pattern is empty.
+    pub fn per_bit(pattern: &[bool]) -> Puncturer {
+        Puncturer {
+            per_bit: true,
+            ..Puncturer::new(pattern)
         }
     }
 
@@ -51,9 +102,17 @@ impl Puncturer {
     {
         let pattern_len = self.pattern.len();
         let codeword_len = codeword.shape()[0];
-        if codeword_len % pattern_len != 0 {
+        if !codeword_len.is_multiple_of(pattern_len) {
             return Err(Error::CodewordSizeNotDivisible);
         }
+        if self.per_bit {
+            return Ok(codeword
+                .iter()
+                .zip(self.pattern.iter().cycle())
+                .filter(|&(_, &b)| b)
+                .map(|(x, _)| x.clone())
+                .collect());
+        }
         let block_size = codeword_len / pattern_len;
         let output_size = block_size * self.num_trues;
         let mut out = Array1::uninit(output_size);
@@ -81,12 +140,23 @@ impl Puncturer {
     /// returned if the length of input is not divisible by the number of `true`
     /// elements in the pattern.
     pub fn depuncture<T: Copy + Default>(&self, llrs: &[T]) -> Result<Vec<T>, Error> {
-        if llrs.len() % self.num_trues != 0 {
+        if !llrs.len().is_multiple_of(self.num_trues) {
             return Err(Error::CodewordSizeNotDivisible);
         }
         let block_size = llrs.len() / self.num_trues;
         let output_size = self.pattern.len() * block_size;
         let mut output = vec![T::default(); output_size];
+        if self.per_bit {
+            let mut llrs = llrs.iter();
+            for (out, _) in output
+                .iter_mut()
+                .zip(self.pattern.iter().cycle())
+                .filter(|&(_, &b)| b)
+            {
+                *out = *llrs.next().unwrap();
+            }
+            return Ok(output);
+        }
         for (j, k) in self
             .pattern
             .iter()
@@ -100,6 +170,33 @@ impl Puncturer {
         Ok(output)
     }
 
+    /// Labels the punctured columns of a parity check matrix.
+    ///
+    /// The columns of `h` corresponding to the codeword bits removed by the
+    /// puncturer are given the [`ColumnLabel::Punctured`] label in the
+    /// [column metadata](crate::sparse::ColumnMetadata) of `h`. An error is
+    /// returned if the number of columns of `h` is not divisible by the length
+    /// of the puncturing pattern.
+    pub fn label_punctured(&self, h: &mut SparseMatrix) -> Result<(), Error> {
+        let pattern_len = self.pattern.len();
+        if !h.num_cols().is_multiple_of(pattern_len) {
+            return Err(Error::CodewordSizeNotDivisible);
+        }
+        let block_size = h.num_cols() / pattern_len;
+        let punctured = (0..h.num_cols())
+            .filter(|&j| {
+                let k = if self.per_bit {
+                    j % pattern_len
+                } else {
+                    j / block_size
+                };
+                !self.pattern[k]
+            })
+            .collect::<Vec<_>>();
+        h.label_cols(punctured.iter(), ColumnLabel::Punctured);
+        Ok(())
+    }
+
     /// Returns the rate of the puncturer.
     ///
     /// The rate is defined as the length of the original codeword divided by
@@ -110,6 +207,199 @@ impl Puncturer {
     }
 }
 
+/// Puncturing pattern.
+///
+/// This describes the puncturing of a code independently of the codeword
+/// length. The [`Puncturer`] for a codeword length is obtained with
+/// [`PuncturingPattern::puncturer`].
+///
+/// The pattern can be parsed from a string. A list such as `"1,1,1,0"` gives a
+/// pattern defined by blocks, `"bits:1,1,0"` a pattern defined per bit (both
+/// lists use the format of [`parse_puncturing_pattern`]), `"nr:<Zc>"` the 5G NR
+/// puncturing with lifting size `Zc`, and `"ar4ja"` the AR4JA puncturing.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum PuncturingPattern {
+    /// Pattern defined by blocks (see [`Puncturer::new`]).
+    Blocks(Vec<bool>),
+    /// Pattern defined per bit (see [`Puncturer::per_bit`]).
+    Bits(Vec<bool>),
+    /// 5G NR puncturing.
+    ///
+    /// The first `2 * lifting_size` bits of the codeword, which correspond to
+    /// the first two columns of the base graph, are punctured.
+    Nr {
+        /// Lifting size `Zc`.
+        lifting_size: usize,
+    },
+    /// CCSDS AR4JA puncturing.
+    ///
+    /// The last M bits of the codeword are punctured. The submatrix size M is
+    /// deduced from the codeword length, which is 5M, 7M or 11M for the rates
+    /// 1/2, 2/3 and 4/5 respectively (M is a power of two).
+    Ar4ja,
+}
+
+impl PuncturingPattern {
+    /// Returns the puncturer for a codeword length.
+    ///
+    /// An error is returned if the codeword length is not compatible with the
+    /// pattern.
+    pub fn puncturer(&self, codeword_len: usize) -> Result<Puncturer, Error> {
+        match self {
+            PuncturingPattern::Blocks(pattern) | PuncturingPattern::Bits(pattern) => {
+                if !codeword_len.is_multiple_of(pattern.len()) {
+                    return Err(Error::CodewordSizeNotDivisible);
+                }
+                Ok(match self {
+                    PuncturingPattern::Blocks(_) => Puncturer::new(pattern),
+                    _ => Puncturer::per_bit(pattern),
+                })
+            }
+            PuncturingPattern::Nr { lifting_size } => {
+                let punctured = 2 * lifting_size;
+                if punctured >= codeword_len {
+                    return Err(Error::CodewordTooShort);
+                }
+                let mut pattern = vec![true; codeword_len];
+                pattern[..punctured].fill(false);
+                Ok(Puncturer::per_bit(&pattern))
+            }
+            PuncturingPattern::Ar4ja => {
+                if codeword_len == 0 {
+                    return Err(Error::NotAr4ja);
+                }
+                let blocks = codeword_len >> codeword_len.trailing_zeros();
+                if ![5, 7, 11].contains(&blocks) {
+                    return Err(Error::NotAr4ja);
+                }
+                let mut pattern = vec![true; blocks];
+                pattern[blocks - 1] = false;
+                Ok(Puncturer::new(&pattern))
+            }
+        }
+    }
+}
+
+impl std::str::FromStr for PuncturingPattern {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<PuncturingPattern, &'static str> {
+        let s = s.trim();
+        if s == "ar4ja" {
+            return Ok(PuncturingPattern::Ar4ja);
+        }
+        if let Some(lifting_size) = s.strip_prefix("nr:") {
+            return match lifting_size.trim().parse() {
+                Ok(lifting_size) if lifting_size > 0 => Ok(PuncturingPattern::Nr { lifting_size }),
+                _ => Err("invalid 5G NR lifting size"),
+            };
+        }
+        if let Some(pattern) = s.strip_prefix("bits:") {
+            return Ok(PuncturingPattern::Bits(parse_puncturing_pattern(pattern)?));
+        }
+        Ok(PuncturingPattern::Blocks(parse_puncturing_pattern(s)?))
+    }
+}
+
+impl std::fmt::Display for PuncturingPattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PuncturingPattern::Blocks(pattern) => {
+                write!(f, "{}", format_puncturing_pattern(pattern))
+            }
+            PuncturingPattern::Bits(pattern) => {
+                write!(f, "bits:{}", format_puncturing_pattern(pattern))
+            }
+            PuncturingPattern::Nr { lifting_size } => write!(f, "nr:{lifting_size}"),
+            PuncturingPattern::Ar4ja => write!(f, "ar4ja"),
+        }
+    }
+}
+
+/// Parses a puncturing pattern.
+///
+/// This function parses a punturing pattern given as a string, converting it
+/// into a vector of bools. The format for the puncturing pattern should be
+/// like `"1,1,1,0"`. Runs of repeated elements can be written as
+/// `count*element`, so that long patterns can be given compactly. For instance,
+/// `"35*1,5*0"` denotes 35 ones followed by 5 zeros. The count must be
+/// positive. Both syntaxes can be mixed, as in `"3*1,0,1"`.
+///
+/// The same format is used for shortening patterns and other per-block
+/// patterns of bits.
+///
+/// # Examples
+/// ```
+/// # use ldpc_toolbox::simulation::puncturing::parse_puncturing_pattern;
+/// assert_eq!(
+///     parse_puncturing_pattern("2*1,0,1").unwrap(),
+///     vec![true, true, false, true]
+/// );
+/// ```
+pub fn parse_puncturing_pattern(s: &str) -> Result<Vec<bool>, &'static str> {
+    let mut v = Vec::new();
+    for a in s.split(',') {
+        let a = a.trim();
+        let (count, element) = match a.split_once('*') {
+            Some((count, element)) => (
+                count
+                    .trim()
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|&count| count != 0)
+                    .ok_or("invalid repetition count in puncturing pattern")?,
+                element.trim(),
+            ),
+            None => (1, a),
+        };
+        let element = match element {
+            "0" => false,
+            "1" => true,
+            _ => return Err("invalid puncturing pattern"),
+        };
+        v.extend(std::iter::repeat_n(element, count));
+    }
+    if v.is_empty() {
+        return Err("empty puncturing pattern");
+    }
+    Ok(v)
+}
+
+/// Formats a puncturing pattern.
+///
+/// This function is the inverse of [`parse_puncturing_pattern`]. Runs of
+/// repeated elements are written using the `count*element` syntax, while
+/// isolated elements are written as a single `0` or `1`.
+///
+/// # Examples
+/// ```
+/// # use ldpc_toolbox::simulation::puncturing::format_puncturing_pattern;
+/// let mut pattern = vec![true; 35];
+/// pattern.extend([false; 5]);
+/// pattern.push(true);
+/// assert_eq!(format_puncturing_pattern(&pattern), "35*1,5*0,1");
+/// ```
+pub fn format_puncturing_pattern(pattern: &[bool]) -> String {
+    let mut runs: Vec<(bool, usize)> = Vec::new();
+    for &b in pattern {
+        match runs.last_mut() {
+            Some((element, count)) if *element == b => *count += 1,
+            _ => runs.push((b, 1)),
+        }
+    }
+    runs.iter()
+        .map(|&(element, count)| {
+            let element = u8::from(element);
+            if count == 1 {
+                format!("{element}")
+            } else {
+                format!("{count}*{element}")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -126,5 +416,99 @@ mod test {
         let llrs_out = puncturer.depuncture(&llrs).unwrap();
         let expected = [1.0, 2.0, 3.0, 4.0, 0.0, 0.0, 5.0, 6.0, 0.0, 0.0];
         assert_eq!(&llrs_out, &expected);
+        let mut h = SparseMatrix::new(5, 10);
+        puncturer.label_punctured(&mut h).unwrap();
+        assert_eq!(h.cols_with_label(ColumnLabel::Punctured), [4, 5, 8, 9]);
+        assert!(puncturer
+            .label_punctured(&mut SparseMatrix::new(5, 12))
+            .is_err());
+    }
+
+    #[test]
+    fn per_bit() {
+        let puncturer = Puncturer::per_bit(&[true, false, true]);
+        let codeword = array![0, 1, 2, 3, 4, 5];
+        let punctured = puncturer.puncture(&codeword).unwrap();
+        assert_eq!(&punctured, &array![0, 2, 3, 5]);
+        let llrs = puncturer.depuncture(&[1.0, 2.0, 3.0, 4.0]).unwrap();
+        assert_eq!(&llrs, &[1.0, 0.0, 2.0, 3.0, 0.0, 4.0]);
+        let mut h = SparseMatrix::new(3, 6);
+        puncturer.label_punctured(&mut h).unwrap();
+        assert_eq!(h.cols_with_label(ColumnLabel::Punctured), [1, 4]);
+        assert_eq!(puncturer.rate(), 1.5);
+        assert!(puncturer.puncture(&array![0, 1, 2, 3]).is_err());
+    }
+
+    #[test]
+    fn puncturing_patterns() {
+        for s in ["1,1,1,0", "bits:3*1,0", "nr:4", "ar4ja"] {
+            let pattern: PuncturingPattern = s.parse().unwrap();
+            assert_eq!(
+                pattern.to_string().parse::<PuncturingPattern>(),
+                Ok(pattern)
+            );
+        }
+        assert!("nr:0".parse::<PuncturingPattern>().is_err());
+        assert!("bits:".parse::<PuncturingPattern>().is_err());
+
+        let codeword = Array1::from_iter(0..12);
+        let puncturer = PuncturingPattern::Nr { lifting_size: 3 }
+            .puncturer(12)
+            .unwrap();
+        assert_eq!(
+            puncturer.puncture(&codeword).unwrap().to_vec(),
+            (6..12).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            PuncturingPattern::Nr { lifting_size: 6 }
+                .puncturer(12)
+                .unwrap_err(),
+            Error::CodewordTooShort
+        );
+        assert_eq!(
+            PuncturingPattern::Blocks(vec![true, false])
+                .puncturer(11)
+                .unwrap_err(),
+            Error::CodewordSizeNotDivisible
+        );
+
+        // AR4JA r=2/3 k=1024 has M = 256 and n = 7M
+        let puncturer = PuncturingPattern::Ar4ja.puncturer(7 * 256).unwrap();
+        let mut h = SparseMatrix::new(3 * 256, 7 * 256);
+        puncturer.label_punctured(&mut h).unwrap();
+        assert_eq!(
+            h.cols_with_label(ColumnLabel::Punctured),
+            (6 * 256..7 * 256).collect::<Vec<_>>()
+        );
+        assert!(PuncturingPattern::Ar4ja.puncturer(3 * 256).is_err());
+    }
+
+    #[test]
+    fn parse_pattern() {
+        assert_eq!(
+            parse_puncturing_pattern("1,1,1,0").unwrap(),
+            vec![true, true, true, false]
+        );
+        assert_eq!(
+            parse_puncturing_pattern("3*1,2*0,1").unwrap(),
+            vec![true, true, true, false, false, true]
+        );
+        assert!(parse_puncturing_pattern("1,2").is_err());
+        assert!(parse_puncturing_pattern("x*1").is_err());
+        assert!(parse_puncturing_pattern("0*1").is_err());
+        assert!(parse_puncturing_pattern("0*1,1").is_err());
+    }
+
+    #[test]
+    fn format_pattern() {
+        for pattern in ["1,1,1,0", "35*1,5*0", "0,1,0", "2*0,1,10*1"] {
+            let parsed = parse_puncturing_pattern(pattern).unwrap();
+            let formatted = format_puncturing_pattern(&parsed);
+            assert_eq!(parse_puncturing_pattern(&formatted).unwrap(), parsed);
+        }
+        assert_eq!(
+            format_puncturing_pattern(&[true, true, true, false]),
+            "3*1,0"
+        );
     }
 }
@@ -0,0 +1,190 @@
+//! Decoder quantization sweep.
+//!
+//! Choosing the fixed-point parameters of a decoder requires comparing the
+//! performance of several quantized decoders against a floating point
+//! reference. This module contains the building blocks of such a sweep, which
+//! is run by the [`quant-sweep`](crate::cli::quant_sweep) CLI subcommand.
+//!
+//! A sweep is formed by [`SweepConfig`]s, each of which gives a decoder
+//! implementation and a scale applied to the channel LLRs before decoding.
+//! Since the fixed-point decoders quantize the LLRs with a fixed step, the LLR
+//! scale is equivalent to a change of the quantization step (and hence of the
+//! clipping level). The clipping and limiting options are given by the
+//! decoder implementation (for instance,
+//! [`Aminstari8PartialHardLimit`](DecoderImplementation::Aminstari8PartialHardLimit)
+//! or [`Aminstari8Deg1Clip`](DecoderImplementation::Aminstari8Deg1Clip)).
+//!
+//! The degradation of each configuration with respect to the reference is
+//! measured by [`ebn0_gap_db`], which compares the two FER curves.
+//!
+//! # Examples
+//! ```
+//! # use ldpc_toolbox::{
+//! #     decoder::factory::DecoderImplementation,
+//! #     simulation::quantization_sweep::{self, SweepConfig},
+//! # };
+//! let configs = quantization_sweep::configs(
+//!     &[DecoderImplementation::Aminstari8, DecoderImplementation::HLAminstari8],
+//!     &[0.75, 1.0],
+//! );
+//! assert_eq!(configs.len(), 4);
+//! assert_eq!(configs[1].to_string(), "Aminstari8 (LLR scale 1)");
+//! // Reference FER curve and a curve that is 0.2 dB worse
+//! let reference = [(1.0, 1e-1), (1.5, 1e-2), (2.0, 1e-3)];
+//! let curve = [(1.2, 1e-1), (1.7, 1e-2), (2.2, 1e-3)];
+//! let gap = quantization_sweep::ebn0_gap_db(&reference, &curve).unwrap();
+//! assert!((gap - 0.2).abs() < 1e-9);
+//! ```
+
+use super::{ber::Statistics, llr_transform::LlrTransform};
+use crate::decoder::factory::DecoderImplementation;
+
+/// Configuration of a quantization sweep.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SweepConfig {
+    /// Decoder implementation.
+    pub decoder: DecoderImplementation,
+    /// Scale applied to the channel LLRs before decoding.
+    pub llr_scale: f64,
+}
+
+impl SweepConfig {
+    /// Returns the LLR transform corresponding to the LLR scale.
+    ///
+    /// This returns `None` if the LLR scale is one.
+    pub fn llr_transform(&self) -> Option<LlrTransform> {
+        if self.llr_scale == 1.0 {
+            None
+        } else {
+            Some(LlrTransform::affine(self.llr_scale, 0.0).expect("invalid LLR scale"))
+        }
+    }
+}
+
+impl std::fmt::Display for SweepConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} (LLR scale {})", self.decoder, self.llr_scale)
+    }
+}
+
+/// Returns the configurations of a sweep.
+///
+/// The sweep contains every combination of a decoder and an LLR scale. The
+/// configurations are sorted by decoder, and then by LLR scale, in the order in
+/// which they are given.
+///
+/// # Panics
+/// This function panics if some LLR scale is not finite.
+pub fn configs(decoders: &[DecoderImplementation], llr_scales: &[f64]) -> Vec<SweepConfig> {
+    assert!(llr_scales.iter().all(|s| s.is_finite()));
+    decoders
+        .iter()
+        .flat_map(|&decoder| {
+            llr_scales
+                .iter()
+                .map(move |&llr_scale| SweepConfig { decoder, llr_scale })
+        })
+        .collect()
+}
+
+/// Returns the FER curve of the results of a BER test.
+///
+/// The curve is returned as a list of `(Eb/N0, FER)` pairs, with the Eb/N0 in
+/// dB. The LDPC-only statistics are used if the test includes a BCH decoder.
+pub fn fer_curve(statistics: &[Statistics]) -> Vec<(f64, f64)> {
+    statistics
+        .iter()
+        .map(|stats| (f64::from(stats.ebn0_db), stats.ldpc.fer))
+        .collect()
+}
+
+/// Returns the Eb/N0 at which a FER curve achieves a given FER.
+///
+/// The curve is given as a list of `(Eb/N0, FER)` pairs in increasing order of
+/// Eb/N0. The FER is interpolated linearly in logarithmic scale between
+/// consecutive points with non-zero FER. This returns `None` if the FER is not
+/// positive or if it is outside the range of the curve.
+pub fn ebn0_at_fer(curve: &[(f64, f64)], fer: f64) -> Option<f64> {
+    if fer <= 0.0 {
+        return None;
+    }
+    let fer = fer.log10();
+    let points = curve
+        .iter()
+        .filter(|&&(_, fer)| fer > 0.0)
+        .map(|&(ebn0, fer)| (ebn0, fer.log10()))
+        .collect::<Vec<_>>();
+    points.windows(2).find_map(|w| {
+        let ((ebn0_0, fer0), (ebn0_1, fer1)) = (w[0], w[1]);
+        if fer <= fer0 && fer >= fer1 && fer0 != fer1 {
+            let t = (fer - fer0) / (fer1 - fer0);
+            Some(ebn0_0 + t * (ebn0_1 - ebn0_0))
+        } else if fer == fer0 {
+            Some(ebn0_0)
+        } else {
+            None
+        }
+    })
+}
+
+/// Returns the Eb/N0 gap between a FER curve and a reference curve.
+///
+/// For each point of `curve`, the gap is its Eb/N0 minus the Eb/N0 at which
+/// the reference achieves the same FER (see [`ebn0_at_fer`]), so a positive
+/// gap means that the curve performs worse than the reference. The average of
+/// the gaps of the points whose FER is in the range of the reference is
+/// returned, or `None` if there are no such points.
+pub fn ebn0_gap_db(reference: &[(f64, f64)], curve: &[(f64, f64)]) -> Option<f64> {
+    let gaps = curve
+        .iter()
+        .filter_map(|&(ebn0, fer)| ebn0_at_fer(reference, fer).map(|r| ebn0 - r))
+        .collect::<Vec<_>>();
+    if gaps.is_empty() {
+        None
+    } else {
+        Some(gaps.iter().sum::<f64>() / gaps.len() as f64)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn interpolation() {
+        let curve = [(0.0, 1.0), (1.0, 1e-1), (2.0, 1e-3), (3.0, 0.0)];
+        assert_eq!(ebn0_at_fer(&curve, 1e-1), Some(1.0));
+        assert!((ebn0_at_fer(&curve, 1e-2).unwrap() - 1.5).abs() < 1e-9);
+        assert_eq!(ebn0_at_fer(&curve, 1e-3), Some(2.0));
+        assert_eq!(ebn0_at_fer(&curve, 1e-4), None);
+        assert_eq!(ebn0_at_fer(&curve, 0.0), None);
+    }
+
+    #[test]
+    fn gap() {
+        let reference = [(1.0, 1e-1), (2.0, 1e-3)];
+        // Only the second point is in the range of the reference
+        let curve = [(1.0, 5e-1), (2.0, 1e-2), (3.0, 0.0)];
+        assert!((ebn0_gap_db(&reference, &curve).unwrap() - 0.5).abs() < 1e-9);
+        assert_eq!(ebn0_gap_db(&reference, &[(1.0, 0.5)]), None);
+    }
+
+    #[test]
+    fn sweep_configs() {
+        let configs = configs(
+            &[
+                DecoderImplementation::Phif64,
+                DecoderImplementation::Aminstari8,
+            ],
+            &[0.5],
+        );
+        assert_eq!(configs.len(), 2);
+        assert_eq!(configs[1].decoder, DecoderImplementation::Aminstari8);
+        assert!(configs[0].llr_transform().is_some());
+        let config = SweepConfig {
+            llr_scale: 1.0,
+            ..configs[0]
+        };
+        assert!(config.llr_transform().is_none());
+    }
+}
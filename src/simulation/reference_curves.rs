@@ -0,0 +1,270 @@
+//! Reference BER curves.
+//!
+//! This module contains a small dataset with the BER and FER of some standard
+//! codes in an AWGN channel with BPSK modulation, which can be used to check
+//! that a simulation setup gives the expected results. The data points are
+//! approximate, since they have been read from published performance curves.
+//! They are accurate to about 0.1 dB, so deviations larger than this indicate a
+//! problem in the setup, such as a wrong puncturing pattern or an incorrect
+//! noise variance. The ignored test `simulate_reference_points` of this module
+//! simulates one point of each curve with the decoders of this crate and checks
+//! it against the reference. It takes several minutes, so it should be run in
+//! release mode with `cargo test --release -- --ignored`.
+//!
+//! The results of a simulation can be compared with a [`ReferenceCurve`] by
+//! means of [`ReferenceCurve::compare`], which computes the Eb/N0 gap between
+//! the simulation and the reference at the same FER.
+//!
+//! # Examples
+//! ```
+//! # use ldpc_toolbox::simulation::reference_curves;
+//! let curve = reference_curves::find("ar4ja:1/2:1024").unwrap();
+//! assert_eq!((curve.k, curve.n), (1024, 2048));
+//! // FER of the reference at 1.5 dB
+//! let fer = curve.fer(1.5).unwrap();
+//! assert!((fer.log10() - 5e-3f64.log10()).abs() < 1e-6);
+//! // Eb/N0 at which the reference achieves a FER of 5e-3
+//! let ebn0 = curve.ebn0_at_fer(5e-3).unwrap();
+//! assert!((ebn0 - 1.5).abs() < 1e-6);
+//! ```
+
+use super::ber::Statistics;
+
+/// Data point of a reference curve.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ReferencePoint {
+    /// Eb/N0 in dB units.
+    pub ebn0_db: f64,
+    /// Bit error rate.
+    pub ber: f64,
+    /// Frame error rate.
+    pub fer: f64,
+}
+
+/// Reference BER curve of a code.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ReferenceCurve {
+    /// Code specification (see [`crate::codes::cache`]).
+    pub spec: &'static str,
+    /// Description of the code and the conditions of the curve.
+    pub description: &'static str,
+    /// Source of the data.
+    pub source: &'static str,
+    /// Number of information bits.
+    pub k: usize,
+    /// Number of transmitted bits per frame.
+    pub n: usize,
+    /// Data points, in increasing order of Eb/N0.
+    pub points: &'static [ReferencePoint],
+}
+
+const fn point(ebn0_db: f64, ber: f64, fer: f64) -> ReferencePoint {
+    ReferencePoint { ebn0_db, ber, fer }
+}
+
+/// Reference curves.
+pub static CURVES: &[ReferenceCurve] = &[
+    ReferenceCurve {
+        spec: "ar4ja:1/2:1024",
+        description: "CCSDS AR4JA r=1/2 k=1024 with standard puncturing, \
+                      BPSK, belief propagation with 100 iterations",
+        source: "CCSDS 130.1-G (TM Synchronization and Channel Coding, Summary)",
+        k: 1024,
+        n: 2048,
+        points: &[
+            point(1.00, 2.8e-2, 2.3e-1),
+            point(1.25, 5.0e-3, 4.0e-2),
+            point(1.50, 5.5e-4, 5.0e-3),
+            point(1.75, 6.0e-5, 7.0e-4),
+            point(2.00, 6.0e-6, 8.0e-5),
+            point(2.25, 5.0e-7, 8.0e-6),
+            point(2.50, 5.0e-8, 9.0e-7),
+        ],
+    },
+    ReferenceCurve {
+        spec: "dvbs2:1/2",
+        description: "DVB-S2 r=1/2 normal FECFRAME (LDPC only), BPSK, \
+                      belief propagation with 50 iterations",
+        source: "ETSI TR 102 376 (DVB-S2 implementation guidelines)",
+        k: 32400,
+        n: 64800,
+        points: &[
+            point(0.60, 6.0e-2, 9.5e-1),
+            point(0.70, 2.0e-2, 3.5e-1),
+            point(0.75, 2.0e-3, 5.0e-2),
+            point(0.80, 1.0e-4, 3.0e-3),
+            point(0.85, 3.0e-6, 1.0e-4),
+            point(0.90, 8.0e-8, 3.0e-6),
+        ],
+    },
+];
+
+/// Returns the reference curve with a given code specification.
+pub fn find(spec: &str) -> Option<&'static ReferenceCurve> {
+    CURVES.iter().find(|curve| curve.spec == spec)
+}
+
+/// Comparison of simulation results with a reference curve at one Eb/N0.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Comparison {
+    /// Eb/N0 in dB units.
+    pub ebn0_db: f64,
+    /// Simulated BER.
+    pub ber: f64,
+    /// Simulated FER.
+    pub fer: f64,
+    /// BER of the reference at the same Eb/N0.
+    ///
+    /// This is `None` if the Eb/N0 is outside the range of the reference.
+    pub reference_ber: Option<f64>,
+    /// FER of the reference at the same Eb/N0.
+    ///
+    /// This is `None` if the Eb/N0 is outside the range of the reference.
+    pub reference_fer: Option<f64>,
+    /// Eb/N0 gap with respect to the reference in dB.
+    ///
+    /// This is the simulated Eb/N0 minus the Eb/N0 at which the reference
+    /// achieves the simulated FER, so a positive value means that the
+    /// simulation performs worse than the reference. It is `None` if the
+    /// simulated FER is zero or outside the range of the reference.
+    pub delta_ebn0_db: Option<f64>,
+}
+
+impl ReferenceCurve {
+    /// Returns the BER of the reference at a given Eb/N0.
+    ///
+    /// The BER is interpolated linearly in logarithmic scale. This returns
+    /// `None` if the Eb/N0 is outside the range of the reference.
+    pub fn ber(&self, ebn0_db: f64) -> Option<f64> {
+        self.interpolate(ebn0_db, |p| p.ber)
+    }
+
+    /// Returns the FER of the reference at a given Eb/N0.
+    ///
+    /// The FER is interpolated linearly in logarithmic scale. This returns
+    /// `None` if the Eb/N0 is outside the range of the reference.
+    pub fn fer(&self, ebn0_db: f64) -> Option<f64> {
+        self.interpolate(ebn0_db, |p| p.fer)
+    }
+
+    /// Returns the Eb/N0 at which the reference achieves a given FER.
+    ///
+    /// This returns `None` if the FER is not positive or if it is outside the
+    /// range of the reference.
+    pub fn ebn0_at_fer(&self, fer: f64) -> Option<f64> {
+        if fer <= 0.0 {
+            return None;
+        }
+        let fer = fer.log10();
+        self.points.windows(2).find_map(|w| {
+            let (fer0, fer1) = (w[0].fer.log10(), w[1].fer.log10());
+            if fer <= fer0 && fer >= fer1 {
+                let t = (fer - fer0) / (fer1 - fer0);
+                Some(w[0].ebn0_db + t * (w[1].ebn0_db - w[0].ebn0_db))
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Compares simulation results with the reference.
+    ///
+    /// The LDPC-only statistics are used if the simulation includes a BCH
+    /// decoder.
+    pub fn compare(&self, stats: &Statistics) -> Comparison {
+        let ebn0_db = f64::from(stats.ebn0_db);
+        let (ber, fer) = (stats.ldpc.ber, stats.ldpc.fer);
+        Comparison {
+            ebn0_db,
+            ber,
+            fer,
+            reference_ber: self.ber(ebn0_db),
+            reference_fer: self.fer(ebn0_db),
+            delta_ebn0_db: self.ebn0_at_fer(fer).map(|ebn0| ebn0_db - ebn0),
+        }
+    }
+
+    fn interpolate(&self, ebn0_db: f64, value: impl Fn(&ReferencePoint) -> f64) -> Option<f64> {
+        self.points.windows(2).find_map(|w| {
+            if ebn0_db >= w[0].ebn0_db && ebn0_db <= w[1].ebn0_db {
+                let t = (ebn0_db - w[0].ebn0_db) / (w[1].ebn0_db - w[0].ebn0_db);
+                let (y0, y1) = (value(&w[0]).log10(), value(&w[1]).log10());
+                Some(10.0f64.powf(y0 + t * (y1 - y0)))
+            } else {
+                None
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        codes::cache::{code_from_spec, CodeCache},
+        simulation::{ber::BerTest, modulation::Bpsk, puncturing::Puncturer},
+    };
+
+    #[test]
+    fn curves_are_consistent() {
+        for curve in CURVES {
+            assert!(curve.points.len() >= 2);
+            for w in curve.points.windows(2) {
+                assert!(w[0].ebn0_db < w[1].ebn0_db);
+                assert!(w[0].ber > w[1].ber);
+                assert!(w[0].fer > w[1].fer);
+            }
+            assert!(curve.points.iter().all(|p| p.ber <= p.fer && p.fer <= 1.0));
+            let h = CodeCache::global().get(curve.spec).unwrap();
+            assert_eq!(curve.k, h.num_cols() - h.num_rows());
+        }
+    }
+
+    #[test]
+    fn interpolation() {
+        let curve = find("ar4ja:1/2:1024").unwrap();
+        let fer = curve.fer(1.125).unwrap();
+        assert!((fer - (2.3e-1f64 * 4.0e-2).sqrt()).abs() < 1e-9);
+        assert_eq!(curve.fer(0.5), None);
+        assert_eq!(curve.ber(3.0), None);
+        let ebn0 = curve.ebn0_at_fer(fer).unwrap();
+        assert!((ebn0 - 1.125).abs() < 1e-9);
+        assert_eq!(curve.ebn0_at_fer(0.0), None);
+        assert_eq!(curve.ebn0_at_fer(0.5), None);
+        assert!(find("dvbs2:3/5").is_none());
+    }
+
+    #[test]
+    #[ignore = "slow; run in release mode"]
+    fn simulate_reference_points() {
+        for curve in CURVES {
+            // Simulate the first point with a FER below 0.1, which needs few
+            // frames but is already past the waterfall threshold
+            let point = curve.points.iter().find(|p| p.fer < 0.1).unwrap();
+            let code = code_from_spec(curve.spec).unwrap();
+            let preset = code.decoder_preset().unwrap();
+            let puncturer = code.standard_puncturing().map(|p| Puncturer::new(&p));
+            let test = BerTest::<Bpsk>::new(
+                code.h(),
+                preset.implementation,
+                puncturer,
+                None,
+                100,
+                preset.max_iterations,
+                &[point.ebn0_db as f32],
+                None,
+                0,
+            )
+            .unwrap()
+            .with_seed(0);
+            let stats = test.run().unwrap();
+            let comparison = curve.compare(&stats[0]);
+            let delta = comparison.delta_ebn0_db.unwrap();
+            assert!(
+                delta.abs() < 0.2,
+                "{}: simulation is {delta:.2} dB away from the reference",
+                curve.spec
+            );
+        }
+    }
+}
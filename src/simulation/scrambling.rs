@@ -0,0 +1,148 @@
+//! Codeword scrambling.
+//!
+//! This module implements an additive scrambler (also called randomizer), which
+//! XORs the codeword bits with a PRBS generated by an LFSR. The scrambler can
+//! be used between the encoder and the modulator, and the descrambler can be
+//! used on the demodulated LLRs before decoding. The CCSDS pseudo-randomizer
+//! can be obtained with polynomial `0x1a9` and seed `0xff`.
+
+use ndarray::{Array1, ArrayBase, Data, Ix1};
+use num_traits::One;
+use std::ops::{Add, Neg};
+use thiserror::Error;
+
+/// Scrambler.
+///
+/// This struct is used to perform additive scrambling on codewords to be
+/// transmitted, and descrambling on demodulated LLRs. The PRBS is restarted
+/// from the seed at the beginning of each codeword.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Scrambler {
+    degree: u32,
+    taps: u64,
+    seed: u64,
+}
+
+/// Scrambler error.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Error)]
+pub enum Error {
+    /// The polynomial is not valid.
+    #[error("the scrambler polynomial must have degree between 1 and 63 and constant term 1")]
+    InvalidPolynomial,
+    /// The seed is not valid.
+    #[error("the scrambler seed must be non-zero and fit in the LFSR")]
+    InvalidSeed,
+}
+
+impl Scrambler {
+    /// Creates a new scrambler.
+    ///
+    /// The `polynomial` gives the LFSR polynomial, where bit `j` contains the
+    /// coefficient of `x^j`. For instance, the CCSDS polynomial `x^8 + x^7 +
+    /// x^5 + x^3 + 1` is given as `0x1a9`.
+    ///
+    /// The `seed` gives the initial state of the LFSR. It must have at most as
+    /// many bits as the degree of the polynomial. The most significant bit of
+    /// the seed is the first bit of the PRBS.
+    pub fn new(polynomial: u64, seed: u64) -> Result<Scrambler, Error> {
+        if polynomial & 1 == 0 || polynomial == 1 {
+            return Err(Error::InvalidPolynomial);
+        }
+        let degree = 63 - polynomial.leading_zeros();
+        if seed == 0 || seed >> degree != 0 {
+            return Err(Error::InvalidSeed);
+        }
+        // The LFSR state holds the next `degree` bits of the PRBS, with the
+        // oldest one in the MSB. The coefficient of x^j multiplies the bit in
+        // position degree - 1 - j.
+        let taps = (0..degree)
+            .filter(|&j| (polynomial >> j) & 1 != 0)
+            .map(|j| 1 << (degree - 1 - j))
+            .sum();
+        Ok(Scrambler { degree, taps, seed })
+    }
+
+    /// Returns the first `length` bits of the PRBS.
+    pub fn sequence(&self, length: usize) -> Vec<bool> {
+        self.prbs().take(length).collect()
+    }
+
+    /// Scrambles a codeword.
+    ///
+    /// Returns the codeword with its bits XORed with the PRBS.
+    pub fn scramble<S, T>(&self, codeword: &ArrayBase<S, Ix1>) -> Array1<T>
+    where
+        S: Data<Elem = T>,
+        T: Clone + One + Add<Output = T>,
+    {
+        codeword
+            .iter()
+            .zip(self.prbs())
+            .map(|(x, b)| if b { x.clone() + T::one() } else { x.clone() })
+            .collect()
+    }
+
+    /// Descrambles LLRs.
+    ///
+    /// Flips the sign of the LLRs in the positions where the PRBS is one.
+    pub fn descramble<T: Copy + Neg<Output = T>>(&self, llrs: &[T]) -> Vec<T> {
+        llrs.iter()
+            .zip(self.prbs())
+            .map(|(&x, b)| if b { -x } else { x })
+            .collect()
+    }
+
+    fn prbs(&self) -> impl Iterator<Item = bool> + '_ {
+        let msb = self.degree - 1;
+        let mask = (1 << self.degree) - 1;
+        let mut state = self.seed;
+        std::iter::from_fn(move || {
+            let out = (state >> msb) & 1 != 0;
+            let feedback = u64::from((state & self.taps).count_ones() & 1);
+            state = ((state << 1) | feedback) & mask;
+            Some(out)
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::gf2::GF2;
+    use num_traits::Zero;
+
+    #[test]
+    fn ccsds_randomizer() {
+        let scrambler = Scrambler::new(0x1a9, 0xff).unwrap();
+        let bytes = scrambler
+            .sequence(40)
+            .chunks(8)
+            .map(|c| c.iter().fold(0u8, |acc, &b| (acc << 1) | u8::from(b)))
+            .collect::<Vec<_>>();
+        assert_eq!(&bytes, &[0xff, 0x48, 0x0e, 0xc0, 0x9a]);
+    }
+
+    #[test]
+    fn scramble_descramble() {
+        let scrambler = Scrambler::new(0x1a9, 0xff).unwrap();
+        let codeword = Array1::from_elem(16, GF2::zero());
+        let scrambled = scrambler.scramble(&codeword);
+        let sequence = scrambler.sequence(16);
+        for (&x, &b) in scrambled.iter().zip(sequence.iter()) {
+            assert_eq!(x, if b { GF2::one() } else { GF2::zero() });
+        }
+        let llrs = vec![1.0; 16];
+        let descrambled = scrambler.descramble(&llrs);
+        for (&x, &b) in descrambled.iter().zip(sequence.iter()) {
+            assert_eq!(x, if b { -1.0 } else { 1.0 });
+        }
+    }
+
+    #[test]
+    fn invalid_parameters() {
+        assert_eq!(Scrambler::new(0x1a8, 0xff), Err(Error::InvalidPolynomial));
+        assert_eq!(Scrambler::new(1, 1), Err(Error::InvalidPolynomial));
+        assert_eq!(Scrambler::new(0x1a9, 0), Err(Error::InvalidSeed));
+        assert_eq!(Scrambler::new(0x1a9, 0x100), Err(Error::InvalidSeed));
+    }
+}
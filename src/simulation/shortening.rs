@@ -0,0 +1,227 @@
+//! Code shortening.
+//!
+//! Shortening obtains a code with fewer information bits from a longer code by
+//! fixing some of the information bits to zero and not transmitting them. The
+//! receiver knows the values of the shortened bits, so it gives them the LLRs
+//! of [known bits](super::known_bits) before decoding. Shortening `s` bits
+//! reduces both the number of information bits and the frame size by `s`, so
+//! a code with rate `k / n` becomes a code with rate `(k - s) / (n - s)`.
+//!
+//! Systems rarely use the full `k` of a standard code, so simulating the
+//! shortened configuration gives more accurate results than simulating the
+//! mother code. In a [`BerTest`](super::ber::BerTest), the shortened bits are
+//! removed from the frame after the conversion to transmission order (see
+//! [`ordering`](super::ordering)), so they are neither modulated nor counted
+//! in the BER, and the Eb/N0 is computed with the rate of the shortened code.
+//!
+//! # Examples
+//! ```
+//! # use ldpc_toolbox::simulation::shortening::Shortening;
+//! // Use only 900 of the 1024 information bits
+//! let shortening = Shortening::information_bits(1024, 900)?;
+//! assert_eq!(shortening.len(), 124);
+//! assert_eq!(shortening.positions()[0], 900);
+//! # Ok::<(), ldpc_toolbox::simulation::shortening::Error>(())
+//! ```
+
+use super::{
+    known_bits::KnownBits,
+    ordering::{self, BitOrdering},
+};
+use crate::sparse::{ColumnLabel, SparseMatrix};
+use thiserror::Error;
+
+/// Shortening.
+///
+/// This struct contains the positions of the shortened bits in the codeword.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Shortening {
+    // sorted
+    positions: Box<[usize]>,
+}
+
+/// Shortening error.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Error)]
+pub enum Error {
+    /// A position is repeated.
+    #[error("repeated shortened bit position")]
+    RepeatedPosition,
+    /// A position is out of range.
+    #[error("shortened bit position out of range")]
+    PositionOutOfRange,
+    /// The number of information bits to use is not valid.
+    #[error("invalid number of information bits")]
+    InvalidInformationBits,
+}
+
+impl Shortening {
+    /// Creates a new shortening.
+    ///
+    /// The `positions` of the shortened bits are given in codeword order.
+    pub fn new(positions: &[usize]) -> Result<Shortening, Error> {
+        let mut positions = positions.to_vec();
+        positions.sort_unstable();
+        if positions.windows(2).any(|w| w[0] == w[1]) {
+            return Err(Error::RepeatedPosition);
+        }
+        Ok(Shortening {
+            positions: positions.into_boxed_slice(),
+        })
+    }
+
+    /// Creates a shortening that uses only the first `k_effective` information
+    /// bits of a code with `k` information bits.
+    ///
+    /// The last `k - k_effective` information bits are shortened. An error is
+    /// returned if `k_effective` is zero or larger than `k`.
+    pub fn information_bits(k: usize, k_effective: usize) -> Result<Shortening, Error> {
+        if k_effective == 0 || k_effective > k {
+            return Err(Error::InvalidInformationBits);
+        }
+        Shortening::new(&(k_effective..k).collect::<Vec<_>>())
+    }
+
+    /// Returns the number of shortened bits.
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    /// Returns `true` if there are no shortened bits.
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    /// Returns the positions of the shortened bits in codeword order.
+    ///
+    /// The positions are sorted.
+    pub fn positions(&self) -> &[usize] {
+        &self.positions
+    }
+
+    /// Checks that all the shortened bits are within the first `len` positions.
+    pub fn check_range(&self, len: usize) -> Result<(), Error> {
+        match self.positions.last() {
+            Some(&position) if position >= len => Err(Error::PositionOutOfRange),
+            _ => Ok(()),
+        }
+    }
+
+    /// Labels the shortened columns of a parity check matrix.
+    ///
+    /// The columns of `h` corresponding to the shortened bits are given the
+    /// [`ColumnLabel::Shortened`] label in the [column
+    /// metadata](crate::sparse::ColumnMetadata) of `h`. An error is returned if
+    /// some shortened bit is out of the range of the columns of `h`.
+    pub fn label_shortened(&self, h: &mut SparseMatrix) -> Result<(), Error> {
+        self.check_range(h.num_cols())?;
+        h.label_cols(self.positions.iter(), ColumnLabel::Shortened);
+        Ok(())
+    }
+
+    /// Returns the shortened bits as known bits.
+    ///
+    /// All the shortened bits are known to be zero.
+    pub fn known_bits(&self) -> KnownBits {
+        // The positions are not repeated, so this cannot fail
+        KnownBits::zeros(&self.positions).unwrap()
+    }
+
+    /// Returns the positions of the shortened bits in transmission order.
+    ///
+    /// The returned positions are sorted, and they refer to the frame obtained
+    /// with [`BitOrdering::codeword_to_transmission`] before removing the
+    /// shortened bits. Shortened bits which are punctured do not appear.
+    pub fn transmission_positions(
+        &self,
+        ordering: &BitOrdering,
+        codeword_length: usize,
+    ) -> Result<Vec<usize>, ordering::Error> {
+        let codeword_positions = ordering.codeword_positions(codeword_length)?;
+        let mut positions = self
+            .positions
+            .iter()
+            .filter_map(|&p| codeword_positions.get(p).copied().flatten())
+            .collect::<Vec<_>>();
+        positions.sort_unstable();
+        Ok(positions)
+    }
+}
+
+// Removes the elements in the sorted `positions` from `values`.
+pub(crate) fn remove_positions<T: Clone>(
+    values: impl IntoIterator<Item = T>,
+    positions: &[usize],
+) -> Vec<T> {
+    let mut positions = positions.iter().peekable();
+    values
+        .into_iter()
+        .enumerate()
+        .filter_map(|(j, x)| {
+            if positions.next_if_eq(&&j).is_some() {
+                None
+            } else {
+                Some(x)
+            }
+        })
+        .collect()
+}
+
+// Inserts `T::default()` in the sorted `positions` of `values`. This is the
+// inverse of `remove_positions`.
+pub(crate) fn insert_positions<T: Copy + Default>(values: &[T], positions: &[usize]) -> Vec<T> {
+    let mut output = Vec::with_capacity(values.len() + positions.len());
+    let mut values = values.iter();
+    let mut positions = positions.iter().peekable();
+    while output.len() < output.capacity() {
+        if positions.next_if_eq(&&output.len()).is_some() {
+            output.push(T::default());
+        } else {
+            match values.next() {
+                Some(&x) => output.push(x),
+                None => break,
+            }
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::simulation::puncturing::Puncturer;
+
+    #[test]
+    fn shortening() {
+        let shortening = Shortening::new(&[5, 1, 2]).unwrap();
+        assert_eq!(shortening.positions(), &[1, 2, 5]);
+        assert_eq!(shortening.check_range(6), Ok(()));
+        assert_eq!(shortening.check_range(5), Err(Error::PositionOutOfRange));
+        assert_eq!(Shortening::new(&[1, 1]), Err(Error::RepeatedPosition));
+        assert_eq!(
+            Shortening::information_bits(4, 5),
+            Err(Error::InvalidInformationBits)
+        );
+        assert!(Shortening::information_bits(4, 4).unwrap().is_empty());
+    }
+
+    #[test]
+    fn remove_and_insert() {
+        let positions = [0, 3, 4];
+        let removed = remove_positions([1, 2, 3, 4, 5, 6], &positions);
+        assert_eq!(removed, vec![2, 3, 6]);
+        assert_eq!(
+            insert_positions(&removed, &positions),
+            vec![0, 2, 3, 0, 0, 6]
+        );
+    }
+
+    #[test]
+    fn transmission_positions() {
+        let ordering = BitOrdering::new(Some(Puncturer::new(&[false, true, true])), None, None);
+        let shortening = Shortening::new(&[0, 1, 4]).unwrap();
+        assert_eq!(
+            shortening.transmission_positions(&ordering, 6).unwrap(),
+            vec![2]
+        );
+    }
+}
@@ -0,0 +1,292 @@
+//! Time-varying SNR profiles.
+//!
+//! This module contains [`SnrProfile`], which gives the Eb/N0 of each frame of
+//! a BER test in which the SNR changes over time, as it happens in the links
+//! that use adaptive coding and modulation (ACM). With a profile, a
+//! [`BerTest`](super::ber::BerTest) simulates the frames of the profile in a
+//! single run and reports the statistics of the frames grouped in Eb/N0 bins
+//! (see [`BerTest::with_snr_profile`](super::ber::BerTest::with_snr_profile)).
+//!
+//! # Examples
+//! ```
+//! # use ldpc_toolbox::simulation::snr_profile::SnrProfile;
+//! // Eb/N0 ramp from 0 dB to 2 dB over 5 frames
+//! let profile: SnrProfile = "ramp:0:2:5".parse()?;
+//! assert_eq!(profile.num_frames(), 5);
+//! assert_eq!(profile.ebn0_db(1), 0.5);
+//! assert_eq!(profile.bins(1.0), vec![0.0, 1.0, 2.0]);
+//! # Ok::<(), String>(())
+//! ```
+
+/// Eb/N0 profile.
+///
+/// The profile gives the Eb/N0 in dB of each of the frames that are simulated.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SnrProfile {
+    /// Linear ramp.
+    ///
+    /// The Eb/N0 goes linearly from `start_db` in the first frame to `stop_db`
+    /// in the last frame.
+    Ramp {
+        /// Eb/N0 of the first frame (dB).
+        start_db: f32,
+        /// Eb/N0 of the last frame (dB).
+        stop_db: f32,
+        /// Number of frames.
+        frames: u64,
+    },
+    /// Sinusoid.
+    ///
+    /// The Eb/N0 of frame `j` is `mean_db + amplitude_db * sin(2 * pi * j /
+    /// period)`.
+    Sinusoid {
+        /// Mean Eb/N0 (dB).
+        mean_db: f32,
+        /// Amplitude of the Eb/N0 variations (dB).
+        amplitude_db: f32,
+        /// Period in frames.
+        period: f64,
+        /// Number of frames.
+        frames: u64,
+    },
+    /// Trace with the Eb/N0 of each frame.
+    ///
+    /// This can be used to replay the SNR measured in a real link.
+    Trace(Vec<f32>),
+}
+
+impl SnrProfile {
+    /// Parses a trace.
+    ///
+    /// The trace contains the Eb/N0's (in dB) of each frame, separated by
+    /// commas or whitespace, including newlines. Everything after a `#` until
+    /// the end of the line is treated as a comment. Unlike in
+    /// [`parse_ebn0_list`](super::factory::parse_ebn0_list), the Eb/N0's can be
+    /// repeated.
+    pub fn from_trace(s: &str) -> Result<SnrProfile, String> {
+        let mut trace = Vec::new();
+        for line in s.lines() {
+            let line = line.split_once('#').map_or(line, |(data, _)| data);
+            for a in line
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|a| !a.is_empty())
+            {
+                match a.parse::<f32>() {
+                    Ok(ebn0) if ebn0.is_finite() => trace.push(ebn0),
+                    _ => return Err(format!("invalid Eb/N0 {a} in trace")),
+                }
+            }
+        }
+        if trace.is_empty() {
+            return Err("empty Eb/N0 trace".to_string());
+        }
+        Ok(SnrProfile::Trace(trace))
+    }
+
+    /// Returns the number of frames of the profile.
+    pub fn num_frames(&self) -> u64 {
+        match self {
+            SnrProfile::Ramp { frames, .. } | SnrProfile::Sinusoid { frames, .. } => *frames,
+            SnrProfile::Trace(trace) => trace.len() as u64,
+        }
+    }
+
+    /// Returns the Eb/N0 (in dB) of a frame.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the frame is not in the profile.
+    pub fn ebn0_db(&self, frame: u64) -> f32 {
+        assert!(frame < self.num_frames(), "frame outside of the profile");
+        match self {
+            SnrProfile::Ramp {
+                start_db,
+                stop_db,
+                frames,
+            } => {
+                if *frames == 1 {
+                    *start_db
+                } else {
+                    let t = frame as f64 / (frames - 1) as f64;
+                    (f64::from(*start_db) + t * f64::from(stop_db - start_db)) as f32
+                }
+            }
+            SnrProfile::Sinusoid {
+                mean_db,
+                amplitude_db,
+                period,
+                ..
+            } => {
+                let phase = 2.0 * std::f64::consts::PI * frame as f64 / period;
+                (f64::from(*mean_db) + f64::from(*amplitude_db) * phase.sin()) as f32
+            }
+            SnrProfile::Trace(trace) => trace[frame as usize],
+        }
+    }
+
+    /// Returns the Eb/N0 bin of a frame.
+    ///
+    /// The bins have a width of `bin_width_db` dB and are centered at the
+    /// multiples of `bin_width_db`. The bin is given by the index of its
+    /// center, which is `index * bin_width_db`.
+    pub fn bin(&self, frame: u64, bin_width_db: f32) -> i64 {
+        (self.ebn0_db(frame) / bin_width_db).round() as i64
+    }
+
+    /// Returns the centers of the Eb/N0 bins used by the profile.
+    ///
+    /// The centers are sorted in increasing order. See [`SnrProfile::bin`].
+    pub fn bins(&self, bin_width_db: f32) -> Vec<f32> {
+        let mut bins = (0..self.num_frames())
+            .map(|frame| self.bin(frame, bin_width_db))
+            .collect::<Vec<_>>();
+        bins.sort_unstable();
+        bins.dedup();
+        bins.into_iter()
+            .map(|bin| bin as f32 * bin_width_db)
+            .collect()
+    }
+}
+
+impl std::str::FromStr for SnrProfile {
+    type Err = String;
+
+    /// Parses an Eb/N0 profile.
+    ///
+    /// The accepted formats are `ramp:<start>:<stop>:<frames>`,
+    /// `sine:<mean>:<amplitude>:<period>:<frames>`, and `trace:<path>`, where
+    /// `<path>` is a file in the format of [`SnrProfile::from_trace`].
+    fn from_str(s: &str) -> Result<SnrProfile, String> {
+        let invalid = || format!("invalid Eb/N0 profile {s}");
+        if let Some(path) = s.strip_prefix("trace:") {
+            let trace = std::fs::read_to_string(path)
+                .map_err(|e| format!("could not read Eb/N0 trace {path}: {e}"))?;
+            return SnrProfile::from_trace(&trace);
+        }
+        let fields = s.split(':').collect::<Vec<_>>();
+        let ebn0 = |a: &str| a.parse::<f32>().ok().filter(|x| x.is_finite());
+        let frames = |a: &str| a.parse::<u64>().ok().filter(|&x| x > 0);
+        let profile = match fields[..] {
+            ["ramp", start, stop, n] => SnrProfile::Ramp {
+                start_db: ebn0(start).ok_or_else(invalid)?,
+                stop_db: ebn0(stop).ok_or_else(invalid)?,
+                frames: frames(n).ok_or_else(invalid)?,
+            },
+            ["sine", mean, amplitude, period, n] => SnrProfile::Sinusoid {
+                mean_db: ebn0(mean).ok_or_else(invalid)?,
+                amplitude_db: ebn0(amplitude).ok_or_else(invalid)?,
+                period: period
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|&p| p > 0.0 && p.is_finite())
+                    .ok_or_else(invalid)?,
+                frames: frames(n).ok_or_else(invalid)?,
+            },
+            _ => return Err(invalid()),
+        };
+        Ok(profile)
+    }
+}
+
+impl std::fmt::Display for SnrProfile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SnrProfile::Ramp {
+                start_db,
+                stop_db,
+                frames,
+            } => write!(f, "ramp:{start_db}:{stop_db}:{frames}"),
+            SnrProfile::Sinusoid {
+                mean_db,
+                amplitude_db,
+                period,
+                frames,
+            } => write!(f, "sine:{mean_db}:{amplitude_db}:{period}:{frames}"),
+            SnrProfile::Trace(trace) => write!(f, "trace ({} frames)", trace.len()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        codes::examples::ExampleCode,
+        decoder::factory::DecoderImplementation,
+        simulation::{ber::BerTest, modulation::Bpsk},
+    };
+
+    #[test]
+    fn parse() {
+        for s in ["ramp:-1:3.5:100", "sine:2:0.5:1000:10000"] {
+            assert_eq!(s.parse::<SnrProfile>().unwrap().to_string(), s);
+        }
+        for s in [
+            "ramp:0:1",
+            "ramp:0:1:0",
+            "sine:0:1:-5:10",
+            "constant:1:10",
+            "trace:/nonexistent/trace.txt",
+        ] {
+            assert!(s.parse::<SnrProfile>().is_err(), "{s}");
+        }
+        assert_eq!(
+            SnrProfile::from_trace("# measured\n1.0, 1.0\n1.5 # fade\n"),
+            Ok(SnrProfile::Trace(vec![1.0, 1.0, 1.5]))
+        );
+        assert!(SnrProfile::from_trace("# nothing").is_err());
+        assert!(SnrProfile::from_trace("1.0 inf").is_err());
+    }
+
+    #[test]
+    fn profiles() {
+        let ramp = SnrProfile::Ramp {
+            start_db: 2.0,
+            stop_db: 0.0,
+            frames: 9,
+        };
+        assert_eq!(ramp.ebn0_db(0), 2.0);
+        assert_eq!(ramp.ebn0_db(4), 1.0);
+        assert_eq!(ramp.ebn0_db(8), 0.0);
+        assert_eq!(ramp.bins(0.5), vec![0.0, 0.5, 1.0, 1.5, 2.0]);
+
+        let sine = "sine:1:2:4:8".parse::<SnrProfile>().unwrap();
+        assert_eq!(sine.num_frames(), 8);
+        assert!((sine.ebn0_db(1) - 3.0).abs() < 1e-6);
+        assert!((sine.ebn0_db(3) + 1.0).abs() < 1e-6);
+        assert_eq!(sine.bins(1.0), vec![-1.0, 1.0, 3.0]);
+    }
+
+    #[test]
+    fn ber_test() {
+        let profile = SnrProfile::Ramp {
+            start_db: 0.0,
+            stop_db: 6.0,
+            frames: 700,
+        };
+        let test = BerTest::<Bpsk>::new(
+            ExampleCode::Qc42_21.h(),
+            DecoderImplementation::HLPhif64,
+            None,
+            None,
+            10,
+            20,
+            &[],
+            None,
+            0,
+        )
+        .unwrap()
+        .with_max_frames(10)
+        .with_seed(42)
+        .with_snr_profile(profile.clone(), 2.0);
+        let statistics = test.run().unwrap();
+        let ebn0s = statistics.iter().map(|s| s.ebn0_db).collect::<Vec<_>>();
+        assert_eq!(ebn0s, profile.bins(2.0));
+        // All the frames of the profile are simulated, regardless of the
+        // maximum number of frames and frame errors
+        assert_eq!(statistics.iter().map(|s| s.num_frames).sum::<u64>(), 700);
+        // Frames 0-116 are in the 0 dB bin
+        assert_eq!(statistics[0].num_frames, 117);
+        assert!(statistics[0].ldpc.fer > statistics[3].ldpc.fer);
+    }
+}
@@ -0,0 +1,436 @@
+//! Collection of training data for learned decoders.
+//!
+//! This module contains [`TrainingDataSink`], which writes the frames
+//! simulated by a [`BerTest`](super::ber::BerTest) in a compact binary format
+//! that can be used to train neural decoders and other learned decoding
+//! algorithms. For each sampled frame, the sink stores the transmitted
+//! codeword, the channel LLRs and the LLRs of the decoder at the end of each
+//! iteration (see [`LlrTrace`]). The sink is attached to a BER test with
+//! [`BerTest::with_training_data`](super::ber::BerTest::with_training_data).
+//!
+//! Storing all the iterations of all the frames produces a large amount of
+//! data, so the sink can sample a random fraction of the frames, keep only the
+//! frames with errors after decoding, and stop after a maximum number of
+//! records.
+//!
+//! # Format
+//!
+//! The output is a sequence of fixed-size records, one per sampled frame,
+//! without any header, so that it can be loaded as a flat array (for instance
+//! with `numpy.fromfile` and a structured dtype). The record size is given by
+//! [`TrainingDataSink::record_size`]. Each record contains, in little-endian
+//! byte order:
+//!
+//! - The Eb/N0 of the frame in dB, as an `f32`.
+//! - The number of iterations performed by the decoder, as a `u32`.
+//! - The `n` bits of the transmitted codeword, as one `u8` per bit, in the
+//!   order of the columns of the parity check matrix.
+//! - `max_iterations + 1` rows of `n` LLRs each, in the [`TensorFormat`] of
+//!   the sink. Row 0 contains the channel LLRs given to the decoder, and row
+//!   `t` contains the LLRs at the end of iteration `t`. If the decoder stops
+//!   before `max_iterations`, its last LLRs are repeated in the remaining rows.
+//!
+//! The LLRs are positive for the bit 0. The LLRs of the iterations are given in
+//! the internal units of the decoder arithmetic (see
+//! [`LlrTrace::llrs`]), so the floating point arithmetics should be used to
+//! obtain LLRs in the same units as the channel LLRs. Decoders that do not
+//! support LLR tracing cannot be used to collect training data.
+//!
+//! # Examples
+//! ```
+//! # use ldpc_toolbox::simulation::training_data::{TensorFormat, TrainingDataSink};
+//! let sink = TrainingDataSink::new(Vec::new(), "i8:4".parse()?)
+//!     .with_sampling_probability(0.1)
+//!     .with_max_records(1000);
+//! assert_eq!(sink.format(), TensorFormat::I8 { scale: 4.0 });
+//! // Eb/N0, iterations, 100 bits and 11 rows of 100 LLRs
+//! assert_eq!(sink.record_size(100, 10), 8 + 100 + 11 * 100);
+//! # Ok::<(), String>(())
+//! ```
+
+use crate::{decoder::trace::LlrTrace, gf2::GF2};
+use ndarray::Array1;
+use num_traits::One;
+use rand::Rng;
+use std::{
+    fs::File,
+    io::{BufWriter, Result, Write},
+    path::Path,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+};
+
+/// Format of the LLR tensors.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum TensorFormat {
+    /// IEEE 754 half-precision floating point.
+    F16,
+    /// 8-bit signed integers.
+    ///
+    /// The LLRs are multiplied by `scale`, rounded and saturated to the range
+    /// -127 to 127.
+    I8 {
+        /// Scale factor applied to the LLRs.
+        scale: f64,
+    },
+}
+
+impl TensorFormat {
+    /// Returns the size in bytes of each LLR.
+    pub fn element_size(self) -> usize {
+        match self {
+            TensorFormat::F16 => 2,
+            TensorFormat::I8 { .. } => 1,
+        }
+    }
+
+    fn write(self, llr: f64, out: &mut Vec<u8>) {
+        match self {
+            TensorFormat::F16 => out.extend_from_slice(&f16_bits(llr as f32).to_le_bytes()),
+            TensorFormat::I8 { scale } => {
+                // NaN's are converted to 0
+                out.push((llr * scale).round().clamp(-127.0, 127.0) as i8 as u8)
+            }
+        }
+    }
+}
+
+impl std::str::FromStr for TensorFormat {
+    type Err = String;
+
+    /// Parses a tensor format.
+    ///
+    /// The accepted formats are `f16` and `i8:<scale>`.
+    fn from_str(s: &str) -> std::result::Result<TensorFormat, String> {
+        let invalid = || format!("invalid tensor format {s}");
+        if s == "f16" {
+            return Ok(TensorFormat::F16);
+        }
+        let scale = s
+            .strip_prefix("i8:")
+            .and_then(|scale| scale.parse::<f64>().ok())
+            .filter(|&scale| scale > 0.0 && scale.is_finite())
+            .ok_or_else(invalid)?;
+        Ok(TensorFormat::I8 { scale })
+    }
+}
+
+impl std::fmt::Display for TensorFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TensorFormat::F16 => write!(f, "f16"),
+            TensorFormat::I8 { scale } => write!(f, "i8:{scale}"),
+        }
+    }
+}
+
+/// Sink for training data.
+///
+/// The sink is shared by the worker threads of the BER test, which write their
+/// records through a [`Mutex`]. See the [module documentation](self) for the
+/// format of the records.
+pub struct TrainingDataSink {
+    format: TensorFormat,
+    sampling_probability: f64,
+    errors_only: bool,
+    max_records: Option<u64>,
+    records: AtomicU64,
+    writer: Mutex<Box<dyn Write + Send>>,
+}
+
+impl TrainingDataSink {
+    /// Creates a sink that writes to a writer.
+    ///
+    /// By default, all the frames are written.
+    pub fn new(writer: impl Write + Send + 'static, format: TensorFormat) -> TrainingDataSink {
+        TrainingDataSink {
+            format,
+            sampling_probability: 1.0,
+            errors_only: false,
+            max_records: None,
+            records: AtomicU64::new(0),
+            writer: Mutex::new(Box::new(writer)),
+        }
+    }
+
+    /// Creates a sink that writes to a file.
+    ///
+    /// The file is created, or truncated if it exists.
+    pub fn create(path: impl AsRef<Path>, format: TensorFormat) -> Result<TrainingDataSink> {
+        Ok(TrainingDataSink::new(
+            BufWriter::new(File::create(path)?),
+            format,
+        ))
+    }
+
+    /// Sets the probability with which each frame is sampled.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the probability is not between 0 and 1.
+    pub fn with_sampling_probability(mut self, probability: f64) -> TrainingDataSink {
+        assert!((0.0..=1.0).contains(&probability));
+        self.sampling_probability = probability;
+        self
+    }
+
+    /// Sets whether only the sampled frames with bit errors after decoding are
+    /// written.
+    ///
+    /// The bit errors are counted in the same way as in the BER test, so they
+    /// do not include the errors in the known bits.
+    pub fn with_errors_only(mut self, errors_only: bool) -> TrainingDataSink {
+        self.errors_only = errors_only;
+        self
+    }
+
+    /// Sets the maximum number of records to write.
+    pub fn with_max_records(mut self, max_records: u64) -> TrainingDataSink {
+        self.max_records = Some(max_records);
+        self
+    }
+
+    /// Returns the format of the LLR tensors.
+    pub fn format(&self) -> TensorFormat {
+        self.format
+    }
+
+    /// Returns the number of records written so far.
+    pub fn num_records(&self) -> u64 {
+        self.records.load(Ordering::Relaxed)
+    }
+
+    /// Returns the size in bytes of each record.
+    ///
+    /// The parameters are the codeword length and the maximum number of
+    /// iterations of the BER test.
+    pub fn record_size(&self, n: usize, max_iterations: usize) -> usize {
+        8 + n + (max_iterations + 1) * n * self.format.element_size()
+    }
+
+    /// Flushes the writer of the sink.
+    pub fn flush(&self) -> Result<()> {
+        self.writer.lock().unwrap().flush()
+    }
+
+    // Decides if the next frame is sampled. This is called before decoding, so
+    // that LLR tracing is only enabled for the sampled frames.
+    pub(crate) fn sample<R: Rng>(&self, rng: &mut R) -> bool {
+        self.max_records
+            .is_none_or(|max| self.records.load(Ordering::Relaxed) < max)
+            && rng.gen_bool(self.sampling_probability)
+    }
+
+    // Writes the record of a sampled frame, unless it is discarded because it
+    // has no errors or because the maximum number of records has been reached.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn write_record(
+        &self,
+        ebn0_db: f32,
+        iterations: usize,
+        frame_error: bool,
+        codeword: &Array1<GF2>,
+        channel_llrs: &[f64],
+        trace: &LlrTrace,
+        max_iterations: usize,
+    ) -> Result<()> {
+        if self.errors_only && !frame_error {
+            return Ok(());
+        }
+        let reserved = self
+            .records
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |records| {
+                self.max_records
+                    .is_none_or(|max| records < max)
+                    .then_some(records + 1)
+            });
+        if reserved.is_err() {
+            return Ok(());
+        }
+        let n = codeword.len();
+        let mut record = Vec::with_capacity(self.record_size(n, max_iterations));
+        record.extend_from_slice(&ebn0_db.to_le_bytes());
+        record.extend_from_slice(&(iterations as u32).to_le_bytes());
+        record.extend(codeword.iter().map(|x| u8::from(x.is_one())));
+        for &llr in channel_llrs {
+            self.format.write(llr, &mut record);
+        }
+        let last = trace.num_iterations().saturating_sub(1);
+        for iteration in 1..=max_iterations {
+            for &llr in trace.llrs(iteration.min(last)) {
+                self.format.write(llr, &mut record);
+            }
+        }
+        self.writer.lock().unwrap().write_all(&record)
+    }
+}
+
+impl std::fmt::Debug for TrainingDataSink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // The writer is not required to implement Debug
+        f.debug_struct("TrainingDataSink")
+            .field("format", &self.format)
+            .field("sampling_probability", &self.sampling_probability)
+            .field("errors_only", &self.errors_only)
+            .field("max_records", &self.max_records)
+            .field("records", &self.records)
+            .finish_non_exhaustive()
+    }
+}
+
+// Converts an f32 to the bits of the nearest f16, rounding ties to even.
+fn f16_bits(x: f32) -> u16 {
+    let bits = x.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xff) as i32;
+    let mantissa = bits & 0x7f_ffff;
+    if exponent == 0xff {
+        // infinity or NaN
+        return sign | 0x7c00 | if mantissa != 0 { 0x200 } else { 0 };
+    }
+    let round = |value: u32, shift: u32| {
+        let truncated = value >> shift;
+        let remainder = value & ((1 << shift) - 1);
+        let halfway = 1 << (shift - 1);
+        if remainder > halfway || (remainder == halfway && truncated & 1 == 1) {
+            truncated + 1
+        } else {
+            truncated
+        }
+    };
+    let exponent = exponent - 127 + 15;
+    if exponent >= 0x1f {
+        // overflow to infinity
+        sign | 0x7c00
+    } else if exponent <= 0 {
+        // subnormal or zero
+        if exponent < -10 {
+            return sign;
+        }
+        sign | round(mantissa | 0x80_0000, (14 - exponent) as u32) as u16
+    } else {
+        // a carry from the mantissa into the exponent gives the correct
+        // result, including the overflow to infinity
+        sign | round(((exponent as u32) << 23) | mantissa, 13) as u16
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        codes::examples::ExampleCode,
+        decoder::factory::DecoderImplementation,
+        simulation::{ber::BerTest, modulation::Bpsk},
+    };
+    use std::sync::Arc;
+
+    #[test]
+    fn f16() {
+        for (x, bits) in [
+            (0.0, 0x0000),
+            (-0.0, 0x8000),
+            (1.0, 0x3c00),
+            (-2.5, 0xc100),
+            (65504.0, 0x7bff),
+            (65520.0, 0x7c00),
+            (1e10, 0x7c00),
+            (f32::NEG_INFINITY, 0xfc00),
+            (6.0e-8, 0x0001),
+            (6.103_515_6e-5, 0x0400),
+            // ties to even
+            (1.0 + 1.0 / 2048.0, 0x3c00),
+            (1.0 + 3.0 / 2048.0, 0x3c02),
+        ] {
+            assert_eq!(f16_bits(x), bits, "{x}");
+        }
+        assert_eq!(f16_bits(f32::NAN) & 0x7c00, 0x7c00);
+        assert_ne!(f16_bits(f32::NAN) & 0x3ff, 0);
+    }
+
+    #[test]
+    fn tensor_format() {
+        for s in ["f16", "i8:0.5"] {
+            assert_eq!(s.parse::<TensorFormat>().unwrap().to_string(), s);
+        }
+        for s in ["f32", "i8", "i8:0", "i8:x"] {
+            assert!(s.parse::<TensorFormat>().is_err(), "{s}");
+        }
+        let mut out = Vec::new();
+        let format = TensorFormat::I8 { scale: 2.0 };
+        for llr in [1.2, -0.75, 100.0, f64::NEG_INFINITY] {
+            format.write(llr, &mut out);
+        }
+        assert_eq!(out, [2, (-2_i8) as u8, 127, (-127_i8) as u8]);
+    }
+
+    // Writer that can be inspected after the sink has been used.
+    #[derive(Debug, Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn ber_test() {
+        let buffer = SharedBuffer::default();
+        let max_iterations = 10;
+        let sink = Arc::new(
+            TrainingDataSink::new(buffer.clone(), TensorFormat::I8 { scale: 8.0 })
+                .with_max_records(20),
+        );
+        let test = BerTest::<Bpsk>::new(
+            ExampleCode::Qc42_21.h(),
+            DecoderImplementation::HLPhif64,
+            None,
+            None,
+            10,
+            max_iterations,
+            &[1.0],
+            None,
+            0,
+        )
+        .unwrap()
+        .with_max_frames(100)
+        .with_seed(42)
+        .with_training_data(Arc::clone(&sink));
+        test.run().unwrap();
+        assert_eq!(sink.num_records(), 20);
+        let data = buffer.0.lock().unwrap();
+        let n = 42;
+        let size = sink.record_size(n, max_iterations);
+        assert_eq!(data.len(), 20 * size);
+        for record in data.chunks(size) {
+            assert_eq!(f32::from_le_bytes(record[..4].try_into().unwrap()), 1.0);
+            let iterations = u32::from_le_bytes(record[4..8].try_into().unwrap()) as usize;
+            assert!(iterations <= max_iterations);
+            let codeword = &record[8..8 + n];
+            assert!(codeword.iter().all(|&b| b <= 1));
+            let rows = record[8 + n..]
+                .chunks(n)
+                .map(|row| row.iter().map(|&x| x as i8).collect::<Vec<_>>())
+                .collect::<Vec<_>>();
+            assert_eq!(rows.len(), max_iterations + 1);
+            // After convergence the LLRs are repeated, and their hard
+            // decisions are a codeword
+            if iterations < max_iterations {
+                assert_eq!(rows[iterations], rows[max_iterations]);
+            }
+            let last = &rows[max_iterations];
+            if iterations < max_iterations && !last.contains(&0) {
+                let h = ExampleCode::Qc42_21.h();
+                assert!((0..h.num_rows())
+                    .all(|r| h.iter_row(r).filter(|&&c| last[c] < 0).count() % 2 == 0));
+            }
+        }
+    }
+}
@@ -1,15 +1,59 @@
 //! # Sparse binary matrix representation and functions
 //!
 //! This module implements a representation for sparse binary matrices based on
-//! the alist format used to handle LDPC parity check matrices.
+//! the alist format used to handle LDPC parity check matrices. Matrices can
+//! also be read and written in the MatrixMarket coordinate format.
+//!
+//! # Typed indices
+//!
+//! The methods of [`SparseMatrix`] that take a row or column index accept
+//! either a plain `usize` or the newtypes [`Row`] and [`Col`]. Using the
+//! newtypes turns swapped row and column arguments into compile-time errors.
+//! ```
+//! # use ldpc_toolbox::sparse::{Col, Row, SparseMatrix};
+//! let mut h = SparseMatrix::new(10, 30);
+//! h.insert(Row(3), Col(7));
+//! assert!(h.contains(3, 7));
+//! assert_eq!(h.col_weight(Col(7)), 1);
+//! ```
+//! The following does not compile, because the arguments are swapped.
+//! ```compile_fail
+//! # use ldpc_toolbox::sparse::{Col, Row, SparseMatrix};
+//! let mut h = SparseMatrix::new(10, 30);
+//! h.insert(Col(7), Row(3));
+//! ```
+//!
+//! The matrix constructions of this crate use the newtypes. The `usize`
+//! implementations of [`AsRow`] and [`AsCol`] are a transitional shim that
+//! keeps existing code compiling. Since trait implementations cannot be marked
+//! as deprecated, they will be removed in the next breaking release (0.8.0),
+//! after which plain indices need to be wrapped in [`Row`] and [`Col`].
 
+use crate::progress::{Progress, ProgressSender, Reporter};
 use std::borrow::Borrow;
+use std::collections::HashSet;
 use std::slice::Iter;
 
+mod ace;
 mod bfs;
+pub mod chunked;
+mod components;
+mod cycles;
+mod dvbs2_tables;
 mod girth;
+mod metadata;
+mod mtx;
+mod qc;
+mod reduction;
+#[cfg(feature = "serde")]
+mod serialization;
 
 pub use bfs::BFSResults;
+pub use components::Component;
+pub use cycles::Cycle;
+pub use metadata::{ColumnLabel, ColumnMetadata};
+pub use qc::QcMatrix;
+pub use reduction::{DegreeTwoChain, EliminatedCol, Reduction, ReductionOptions};
 
 /// A [`String`] with an description of the error.
 pub type Error = String;
@@ -19,10 +63,18 @@ pub type Result<T> = std::result::Result<T, Error>;
 /// A sparse binary matrix
 ///
 /// The internal representation for this matrix is based on the alist format.
+/// Each entry is stored twice: in the list of columns of its row and in the
+/// list of rows of its column. See [`SparseMatrix::validate`] for the
+/// invariants that this representation must satisfy.
+///
+/// The matrix can optionally keep [`ColumnMetadata`] that tracks the role and
+/// the original position of each column through transformations (see
+/// [`SparseMatrix::init_column_metadata`]).
 #[derive(PartialEq, Eq, Debug, Clone)]
 pub struct SparseMatrix {
     rows: Vec<Vec<usize>>,
     cols: Vec<Vec<usize>>,
+    metadata: Option<Vec<ColumnMetadata>>,
 }
 
 impl SparseMatrix {
@@ -41,7 +93,11 @@ impl SparseMatrix {
         use std::iter::repeat_with;
         let rows = repeat_with(Vec::new).take(nrows).collect();
         let cols = repeat_with(Vec::new).take(ncols).collect();
-        SparseMatrix { rows, cols }
+        SparseMatrix {
+            rows,
+            cols,
+            metadata: None,
+        }
     }
 
     /// Returns the number of rows of the matrix
@@ -58,23 +114,23 @@ impl SparseMatrix {
     ///
     /// The row weight is defined as the number of entries equal to
     /// one in a particular row. Rows are indexed starting by zero.
-    pub fn row_weight(&self, row: usize) -> usize {
-        self.rows[row].len()
+    pub fn row_weight(&self, row: impl AsRow) -> usize {
+        self.rows[row.row()].len()
     }
 
     /// Returns the column weight of `column`
     ///
     /// The column weight is defined as the number of entries equal to
     /// one in a particular column. Columns are indexed starting by zero.
-    pub fn col_weight(&self, col: usize) -> usize {
-        self.cols[col].len()
+    pub fn col_weight(&self, col: impl AsCol) -> usize {
+        self.cols[col.col()].len()
     }
 
     /// Returns `true` if the entry corresponding to a particular
     /// row and column is a one
-    pub fn contains(&self, row: usize, col: usize) -> bool {
+    pub fn contains(&self, row: impl AsRow, col: impl AsCol) -> bool {
         // typically columns are shorter, so we search in the column
-        self.cols[col].contains(&row)
+        self.cols[col.col()].contains(&row.row())
     }
 
     /// Inserts a one in a particular row and column.
@@ -90,11 +146,13 @@ impl SparseMatrix {
     /// h.insert(3, 7);
     /// assert!(h.contains(3, 7));
     /// ```
-    pub fn insert(&mut self, row: usize, col: usize) {
+    pub fn insert(&mut self, row: impl AsRow, col: impl AsCol) {
+        let (row, col) = (row.row(), col.col());
         if !self.contains(row, col) {
             self.rows[row].push(col);
             self.cols[col].push(row);
         }
+        self.debug_validate_entry(row, col, true);
     }
 
     /// Removes a one in a particular row and column.
@@ -110,16 +168,18 @@ impl SparseMatrix {
     /// h.remove(3, 7);
     /// assert!(!h.contains(3, 7));
     /// ```
-    pub fn remove(&mut self, row: usize, col: usize) {
+    pub fn remove(&mut self, row: impl AsRow, col: impl AsCol) {
+        let (row, col) = (row.row(), col.col());
         self.rows[row].retain(|&c| c != col);
         self.cols[col].retain(|&r| r != row);
+        self.debug_validate_entry(row, col, false);
     }
 
     /// Toggles the 0/1 in a particular row and column.
     ///
     /// If the row and column contains a zero, this function sets a one, and
     /// vice versa. This is useful to implement addition modulo 2.
-    pub fn toggle(&mut self, row: usize, col: usize) {
+    pub fn toggle(&mut self, row: impl AsRow, col: impl AsCol) {
         match self.contains(row, col) {
             true => self.remove(row, col),
             false => self.insert(row, col),
@@ -143,11 +203,12 @@ impl SparseMatrix {
     /// }
     /// assert_eq!(h1, h2);
     /// ```
-    pub fn insert_row<T, S>(&mut self, row: usize, cols: T)
+    pub fn insert_row<T, S>(&mut self, row: impl AsRow, cols: T)
     where
         T: Iterator<Item = S>,
         S: Borrow<usize>,
     {
+        let row = row.row();
         for col in cols {
             self.insert(row, *col.borrow());
         }
@@ -156,37 +217,141 @@ impl SparseMatrix {
     /// Inserts ones in a particular rows of a column
     ///
     /// This works like `insert_row()`.
-    pub fn insert_col<T, S>(&mut self, col: usize, rows: T)
+    pub fn insert_col<T, S>(&mut self, col: impl AsCol, rows: T)
     where
         T: Iterator<Item = S>,
         S: Borrow<usize>,
     {
+        let col = col.col();
         for row in rows {
             self.insert(*row.borrow(), col);
         }
     }
 
     /// Remove all the ones in a particular row
-    pub fn clear_row(&mut self, row: usize) {
-        for &col in &self.rows[row] {
+    pub fn clear_row(&mut self, row: impl AsRow) {
+        let row = row.row();
+        let cols = std::mem::take(&mut self.rows[row]);
+        for &col in &cols {
             self.cols[col].retain(|r| *r != row);
         }
-        self.rows[row].clear();
+        for col in cols {
+            self.debug_validate_entry(row, col, false);
+        }
     }
 
     /// Remove all the ones in a particular column
-    pub fn clear_col(&mut self, col: usize) {
-        for &row in &self.cols[col] {
+    pub fn clear_col(&mut self, col: impl AsCol) {
+        let col = col.col();
+        let rows = std::mem::take(&mut self.cols[col]);
+        for &row in &rows {
             self.rows[row].retain(|c| *c != col);
         }
-        self.cols[col].clear();
+        for row in rows {
+            self.debug_validate_entry(row, col, false);
+        }
+    }
+
+    /// Checks the internal consistency of the matrix
+    ///
+    /// This function checks that every entry stored in the list of columns of
+    /// a row is also stored in the list of rows of the corresponding column
+    /// and vice versa, that there are no duplicate entries, and that all the
+    /// indices are within the dimensions of the matrix. If any of these
+    /// invariants does not hold, an error describing the first inconsistency
+    /// found is returned.
+    ///
+    /// The methods of `SparseMatrix` maintain these invariants, so an error
+    /// indicates a bug. In debug builds, the mutating methods check that the
+    /// entries that they modify are stored consistently, and panic if they are
+    /// not.
+    ///
+    /// # Examples
+    /// ```
+    /// # use ldpc_toolbox::sparse::SparseMatrix;
+    /// let mut h = SparseMatrix::new(10, 30);
+    /// h.insert(3, 7);
+    /// h.insert(5, 7);
+    /// h.clear_col(7);
+    /// assert!(h.validate().is_ok());
+    /// ```
+    pub fn validate(&self) -> Result<()> {
+        for row in 0..self.num_rows() {
+            self.validate_row(row)?;
+        }
+        for col in 0..self.num_cols() {
+            self.validate_col(col)?;
+        }
+        if let Some(metadata) = &self.metadata {
+            if metadata.len() != self.num_cols() {
+                return Err(format!(
+                    "column metadata has {} entries, but the matrix has {} columns",
+                    metadata.len(),
+                    self.num_cols()
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_row(&self, row: usize) -> Result<()> {
+        let cols = &self.rows[row];
+        for (j, &col) in cols.iter().enumerate() {
+            if col >= self.num_cols() {
+                return Err(format!("row {row} contains out of range column {col}"));
+            }
+            if cols[..j].contains(&col) {
+                return Err(format!("row {row} contains column {col} more than once"));
+            }
+            if !self.cols[col].contains(&row) {
+                return Err(format!(
+                    "entry ({row}, {col}) is stored in row {row} but not in column {col}"
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_col(&self, col: usize) -> Result<()> {
+        let rows = &self.cols[col];
+        for (j, &row) in rows.iter().enumerate() {
+            if row >= self.num_rows() {
+                return Err(format!("column {col} contains out of range row {row}"));
+            }
+            if rows[..j].contains(&row) {
+                return Err(format!("column {col} contains row {row} more than once"));
+            }
+            if !self.rows[row].contains(&col) {
+                return Err(format!(
+                    "entry ({row}, {col}) is stored in column {col} but not in row {row}"
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    // Checks that an entry modified by a mutating method is stored exactly
+    // once in its row and in its column if it is present, and not stored at all
+    // otherwise. This is cheaper than validating the whole row and column. It
+    // does nothing in release builds.
+    fn debug_validate_entry(&self, row: usize, col: usize, present: bool) {
+        if cfg!(debug_assertions) {
+            let expected = usize::from(present);
+            let in_row = self.rows[row].iter().filter(|&&c| c == col).count();
+            let in_col = self.cols[col].iter().filter(|&&r| r == row).count();
+            assert!(
+                in_row == expected && in_col == expected,
+                "sparse matrix invariant violated: entry ({row}, {col}) is stored \
+                 {in_row} times in its row and {in_col} times in its column"
+            );
+        }
     }
 
     /// Set the elements that are equal to one in a row
     ///
     /// The effect of this is like calling `clear_row()` followed
     /// by `insert_row()`.
-    pub fn set_row<T, S>(&mut self, row: usize, cols: T)
+    pub fn set_row<T, S>(&mut self, row: impl AsRow, cols: T)
     where
         T: Iterator<Item = S>,
         S: Borrow<usize>,
@@ -196,7 +361,7 @@ impl SparseMatrix {
     }
 
     /// Set the elements that are equal to one in a column
-    pub fn set_col<T, S>(&mut self, col: usize, rows: T)
+    pub fn set_col<T, S>(&mut self, col: impl AsCol, rows: T)
     where
         T: Iterator<Item = S>,
         S: Borrow<usize>,
@@ -214,25 +379,56 @@ impl SparseMatrix {
             .flat_map(|(j, r)| r.iter().map(move |&k| (j, k)))
     }
 
+    /// Returns an [Iterator] over the typed indices of the entries equal to
+    /// one in all the matrix.
+    ///
+    /// This works like `iter_all()`, but returns the indices as [`Row`] and
+    /// [`Col`].
+    pub fn iter_entries(&self) -> impl Iterator<Item = (Row, Col)> + '_ {
+        self.iter_all().map(|(j, k)| (Row(j), Col(k)))
+    }
+
     /// Returns an [Iterator] over the entries equal to one
     /// in a particular row
-    pub fn iter_row(&self, row: usize) -> Iter<'_, usize> {
-        self.rows[row].iter()
+    pub fn iter_row(&self, row: impl AsRow) -> Iter<'_, usize> {
+        self.rows[row.row()].iter()
     }
 
     /// Returns an [Iterator] over the entries equal to one
     /// in a particular column
-    pub fn iter_col(&self, col: usize) -> Iter<'_, usize> {
-        self.cols[col].iter()
+    pub fn iter_col(&self, col: impl AsCol) -> Iter<'_, usize> {
+        self.cols[col.col()].iter()
     }
 
-    fn write_alist_maybe_padding<W: std::fmt::Write>(
+    /// Writes the matrix in alist format to a writer using the given options.
+    ///
+    /// See [`AlistOptions`] for the variants of the alist format that can be
+    /// written. With the default options, this is equivalent to
+    /// [`SparseMatrix::write_alist`].
+    ///
+    /// If the matrix keeps [`ColumnMetadata`], it is written after the alist
+    /// in a section that starts with a `# column metadata` line and contains
+    /// one line per column with its zero-based original index and its
+    /// comma-separated labels (or `-` if it has no labels). Tools that only
+    /// read the alist sections ignore these lines.
+    ///
+    /// # Errors
+    /// If a call to `write!()` returns an error, this function returns
+    /// such an error.
+    pub fn write_alist_with<W: std::fmt::Write>(
         &self,
         w: &mut W,
-        use_padding: bool,
+        options: &AlistOptions,
     ) -> std::fmt::Result {
-        writeln!(w, "{} {}", self.num_cols(), self.num_rows())?;
-        let directions = [&self.cols, &self.rows];
+        let (sizes, directions) = match options.sections {
+            AlistSections::ColumnsFirst | AlistSections::ColumnsOnly => {
+                ([self.num_cols(), self.num_rows()], [&self.cols, &self.rows])
+            }
+            AlistSections::RowsFirst => {
+                ([self.num_rows(), self.num_cols()], [&self.rows, &self.cols])
+            }
+        };
+        writeln!(w, "{} {}", sizes[0], sizes[1])?;
         let mut direction_lengths = [0, 0];
         for (dir, len) in directions.iter().zip(direction_lengths.iter_mut()) {
             *len = dir.iter().map(|el| el.len()).max().unwrap_or(0);
@@ -248,12 +444,27 @@ impl SparseMatrix {
             }
             writeln!(w)?;
         }
-        for (dir, &dirlen) in directions.iter().zip(direction_lengths.iter()) {
+        let num_sections = match options.sections {
+            AlistSections::ColumnsOnly => 1,
+            _ => 2,
+        };
+        // zero is a valid index when using zero-based indices, so it cannot be
+        // used for padding
+        let use_padding = options.padding && options.index_base == IndexBase::One;
+        let offset = match options.index_base {
+            IndexBase::One => 1,
+            IndexBase::Zero => 0,
+        };
+        for (dir, &dirlen) in directions
+            .iter()
+            .zip(direction_lengths.iter())
+            .take(num_sections)
+        {
             for el in *dir {
                 let mut v = el.clone();
                 v.sort_unstable();
                 let vlen = v.len();
-                let mut v = v.iter().map(|x| x + 1);
+                let mut v = v.iter().map(|x| x + offset);
                 if let Some(x) = v.next() {
                     write!(w, "{}", x)?;
                 }
@@ -274,7 +485,7 @@ impl SparseMatrix {
                 writeln!(w)?;
             }
         }
-        Ok(())
+        self.write_alist_metadata(w)
     }
 
     /// Writes the matrix in alist format to a writer.
@@ -286,7 +497,7 @@ impl SparseMatrix {
     /// If a call to `write!()` returns an error, this function returns
     /// such an error.
     pub fn write_alist<W: std::fmt::Write>(&self, w: &mut W) -> std::fmt::Result {
-        self.write_alist_maybe_padding(w, true)
+        self.write_alist_with(w, &AlistOptions::default())
     }
 
     /// Writes the matrix in alist format to a writer.
@@ -297,7 +508,13 @@ impl SparseMatrix {
     /// If a call to `write!()` returns an error, this function returns
     /// such an error.
     pub fn write_alist_no_padding<W: std::fmt::Write>(&self, w: &mut W) -> std::fmt::Result {
-        self.write_alist_maybe_padding(w, false)
+        self.write_alist_with(
+            w,
+            &AlistOptions {
+                padding: false,
+                ..AlistOptions::default()
+            },
+        )
     }
 
     /// Returns a [`String`] with the alist representation of the matrix.
@@ -319,16 +536,51 @@ impl SparseMatrix {
         s
     }
 
+    /// Returns a [`String`] with the alist representation of the matrix using
+    /// the given options.
+    ///
+    /// See [`SparseMatrix::write_alist_with`].
+    ///
+    /// # Examples
+    /// ```
+    /// # use ldpc_toolbox::sparse::{AlistOptions, AlistSections, IndexBase, SparseMatrix};
+    /// let mut h = SparseMatrix::new(2, 3);
+    /// h.insert(0, 1);
+    /// h.insert(1, 2);
+    /// let options = AlistOptions {
+    ///     index_base: IndexBase::Zero,
+    ///     sections: AlistSections::ColumnsOnly,
+    ///     ..AlistOptions::default()
+    /// };
+    /// assert_eq!(h.alist_with(&options), "3 2\n1 1\n0 1 1\n1 1\n\n0\n1\n");
+    /// ```
+    pub fn alist_with(&self, options: &AlistOptions) -> String {
+        let mut s = String::new();
+        self.write_alist_with(&mut s, options).unwrap();
+        s
+    }
+
     /// Constructs and returns a sparse matrix from its alist representation.
     ///
     /// This function is able to read alists that use zeros for padding in the
     /// case of an irregular code (as was defined originally by MacKay), as well
-    /// as alists that omit these zeros.
+    /// as alists that omit these zeros. The column metadata section written by
+    /// [`SparseMatrix::write_alist_with`] is also read if present.
+    ///
+    /// The weights given in the header of the alist are not checked. See
+    /// [`SparseMatrix::from_alist_with`] for a function that checks them.
     ///
     /// # Errors
     /// `alist` should hold a valid alist representation. If an error is found
     /// while parsing `alist`, a `String` describing the error will be returned.
     pub fn from_alist(alist: &str) -> Result<SparseMatrix> {
+        SparseMatrix::parse_alist(alist).map(|(h, _)| h)
+    }
+
+    // Parses an alist. Together with the matrix, this returns the warnings
+    // about the weights given in the header of the alist that do not match
+    // the matrix.
+    fn parse_alist(alist: &str) -> Result<(SparseMatrix, Vec<AlistWarning>)> {
         let mut alist = alist.split('\n');
         let sizes = alist
             .next()
@@ -345,9 +597,10 @@ impl SparseMatrix {
             .parse()
             .map_err(|_| String::from("nrows is not a number"))?;
         let mut h = SparseMatrix::new(nrows, ncols);
-        alist.next(); // skip max weights
-        alist.next();
-        alist.next(); // skip weights
+        // the weights are checked after reading the matrix
+        let max_weights = alist.next();
+        let col_weights = alist.next();
+        let row_weights = alist.next();
         for col in 0..ncols {
             let col_data = alist
                 .next()
@@ -363,8 +616,199 @@ impl SparseMatrix {
                 }
             }
         }
-        // we do not need to process the rows of the alist
-        Ok(h)
+        // we do not need to process the rows of the alist, but there can be
+        // a column metadata section after them
+        h.read_alist_metadata(alist)?;
+        let warnings = h.check_alist_weights([max_weights, col_weights, row_weights]);
+        Ok((h, warnings))
+    }
+
+    // Checks the maximum weights, the column weights and the row weights
+    // given in the lines 2 to 4 of an alist against the weights of the matrix.
+    fn check_alist_weights(&self, lines: [Option<&str>; 3]) -> Vec<AlistWarning> {
+        let col_weights = (0..self.num_cols())
+            .map(|col| self.col_weight(col))
+            .collect::<Vec<_>>();
+        let row_weights = (0..self.num_rows())
+            .map(|row| self.row_weight(row))
+            .collect::<Vec<_>>();
+        let max_weights = [
+            col_weights.iter().copied().max().unwrap_or(0),
+            row_weights.iter().copied().max().unwrap_or(0),
+        ];
+        let weights: [&[usize]; 3] = [&max_weights, &col_weights, &row_weights];
+        lines
+            .into_iter()
+            .zip(weights)
+            .zip(2..)
+            .filter_map(|((stated, actual), line)| {
+                let mut stated = stated
+                    .unwrap_or_default()
+                    .split_whitespace()
+                    .map(|w| w.parse::<usize>().ok());
+                let mut mismatches = actual.iter().enumerate().filter_map(|(index, &actual)| {
+                    let stated = stated.next().flatten();
+                    (stated != Some(actual)).then_some((index, stated, actual))
+                });
+                let (index, stated, actual) = mismatches.next()?;
+                Some(AlistWarning::InconsistentWeights {
+                    line,
+                    index,
+                    stated,
+                    actual,
+                    count: 1 + mismatches.count(),
+                })
+            })
+            .collect()
+    }
+
+    /// Constructs and returns a sparse matrix from its alist representation,
+    /// handling alists that hold the transpose of the matrix and checking the
+    /// weights given in the alist.
+    ///
+    /// Some tools write alists with the roles of the rows and columns swapped
+    /// (for instance, an alist written with [`AlistSections::RowsFirst`] and
+    /// read as a standard alist). Reading such an alist gives the transpose of
+    /// the parity check matrix, which has more rows than columns. This
+    /// function works as [`SparseMatrix::from_alist`], but the `orientation`
+    /// selects whether the matrix is transposed after reading it:
+    ///
+    /// - [`AlistOrientation::Auto`] transposes the matrix if it has more rows
+    ///   than columns, unless the alist has column metadata (which is only
+    ///   written by this crate, so the orientation is known to be correct).
+    ///
+    /// - [`AlistOrientation::AsIs`] never transposes the matrix.
+    ///
+    /// - [`AlistOrientation::Transposed`] always transposes the matrix.
+    ///
+    /// The maximum weights, column weights and row weights given in the header
+    /// of the alist are checked against the matrix. Some tools write alists
+    /// whose weights are wrong, and other tools reject these alists. With
+    /// [`AlistValidation::Lenient`], an [`AlistWarning::InconsistentWeights`]
+    /// is returned for each header line with wrong weights, and with
+    /// [`AlistValidation::Strict`] an error is returned.
+    ///
+    /// Together with the matrix, this function returns the warnings about the
+    /// weights and an [`AlistWarning`] if the matrix has been transposed
+    /// automatically or if it has been kept with more rows than columns, so
+    /// that the caller can inform the user.
+    ///
+    /// # Examples
+    /// ```
+    /// # use ldpc_toolbox::sparse::{
+    /// #     AlistOptions, AlistOrientation, AlistSections, AlistValidation, AlistWarning,
+    /// #     SparseMatrix,
+    /// # };
+    /// let mut h = SparseMatrix::new(2, 3);
+    /// h.insert(0, 0);
+    /// h.insert(0, 1);
+    /// h.insert(1, 1);
+    /// h.insert(1, 2);
+    /// let options = AlistOptions {
+    ///     sections: AlistSections::RowsFirst,
+    ///     ..AlistOptions::default()
+    /// };
+    /// let alist = h.alist_with(&options);
+    /// let (h2, warnings) =
+    ///     SparseMatrix::from_alist_with(&alist, AlistOrientation::Auto, AlistValidation::Lenient)
+    ///         .unwrap();
+    /// assert_eq!(h2, h);
+    /// assert_eq!(warnings, [AlistWarning::Transposed { rows: 3, cols: 2 }]);
+    /// ```
+    ///
+    /// # Errors
+    /// An error is returned if `alist` is not a valid alist representation, if
+    /// `orientation` is [`AlistOrientation::Transposed`] and the alist has
+    /// column metadata, or if `validation` is [`AlistValidation::Strict`] and
+    /// the weights given in the alist do not match the matrix.
+    pub fn from_alist_with(
+        alist: &str,
+        orientation: AlistOrientation,
+        validation: AlistValidation,
+    ) -> Result<(SparseMatrix, Vec<AlistWarning>)> {
+        let (h, mut warnings) = SparseMatrix::parse_alist(alist)?;
+        if let (AlistValidation::Strict, Some(warning)) = (validation, warnings.first()) {
+            return Err(format!("invalid alist: {warning}"));
+        }
+        let rows = h.num_rows();
+        let cols = h.num_cols();
+        let overdetermined = rows > cols;
+        let h = match orientation {
+            AlistOrientation::Auto if overdetermined && h.column_metadata().is_none() => {
+                warnings.push(AlistWarning::Transposed { rows, cols });
+                h.transpose()
+            }
+            AlistOrientation::Transposed => {
+                if h.column_metadata().is_some() {
+                    return Err(String::from(
+                        "cannot transpose an alist with column metadata",
+                    ));
+                }
+                h.transpose()
+            }
+            _ => {
+                if overdetermined {
+                    warnings.push(AlistWarning::MoreRowsThanColumns { rows, cols });
+                }
+                h
+            }
+        };
+        Ok((h, warnings))
+    }
+
+    /// Returns the transpose of the matrix.
+    ///
+    /// The column metadata is not kept in the transpose.
+    pub fn transpose(&self) -> SparseMatrix {
+        let mut t = SparseMatrix::new(self.num_cols(), self.num_rows());
+        for (row, col) in self.iter_all() {
+            t.insert(col, row);
+        }
+        t
+    }
+
+    /// Returns a fingerprint of the matrix
+    ///
+    /// The fingerprint is a 64-bit FNV-1a hash of the matrix dimensions and of
+    /// the positions of its non-zero entries. It does not depend on the order
+    /// in which the entries were inserted, and it is stable across platforms
+    /// and versions of this crate, so it can be used to identify the parity
+    /// check matrix that produced a set of results.
+    ///
+    /// # Examples
+    /// ```
+    /// # use ldpc_toolbox::sparse::SparseMatrix;
+    /// let mut h1 = SparseMatrix::new(2, 3);
+    /// h1.insert(0, 1);
+    /// h1.insert(0, 2);
+    /// let mut h2 = SparseMatrix::new(2, 3);
+    /// h2.insert(0, 2);
+    /// h2.insert(0, 1);
+    /// assert_eq!(h1.fingerprint(), h2.fingerprint());
+    /// h2.insert(1, 0);
+    /// assert_ne!(h1.fingerprint(), h2.fingerprint());
+    /// ```
+    pub fn fingerprint(&self) -> u64 {
+        const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+        const FNV_PRIME: u64 = 0x100000001b3;
+        let mut hash = FNV_OFFSET_BASIS;
+        let mut update = |x: usize| {
+            for byte in (x as u64).to_le_bytes() {
+                hash ^= u64::from(byte);
+                hash = hash.wrapping_mul(FNV_PRIME);
+            }
+        };
+        update(self.num_rows());
+        update(self.num_cols());
+        for row in &self.rows {
+            let mut row = row.clone();
+            row.sort_unstable();
+            update(row.len());
+            for col in row {
+                update(col);
+            }
+        }
+        hash
     }
 
     /// Returns the girth of the bipartite graph defined by the matrix
@@ -408,6 +852,25 @@ impl SparseMatrix {
             .min()
     }
 
+    /// Returns the girth of the bipartite graph defined by the matrix
+    /// with progress reporting
+    ///
+    /// This function works like `girth_with_max()`, but sends [`Progress`]
+    /// reports to `reporter` with the number of columns analyzed and the
+    /// smallest local girth found so far.
+    pub fn girth_with_progress(&self, max: usize, reporter: &Reporter<Progress>) -> Option<usize> {
+        let mut progress = ProgressSender::new(reporter);
+        let mut girth = None;
+        for c in 0..self.num_cols() {
+            progress.update(c as u64, self.num_cols() as u64, girth.map(|g| g as u64));
+            if let Some(g) = self.girth_at_node_with_max(Node::Col(c), max) {
+                girth = Some(girth.map_or(g, |x: usize| x.min(g)));
+            }
+        }
+        progress.finish();
+        girth
+    }
+
     /// Returns the local girth at a particular node
     ///
     /// The local girth at a node of a graph is defined as the minimum
@@ -429,6 +892,60 @@ impl SparseMatrix {
         bfs::BFSContext::new(self, node).local_girth(max)
     }
 
+    /// Returns the edges that belong to short cycles
+    ///
+    /// This function returns the list of entries of the matrix (as `(row,
+    /// col)` pairs) whose corresponding edges in the bipartite graph belong to
+    /// at least one cycle of length smaller or equal than `max`. For instance,
+    /// `max = 6` gives the edges that belong to 4-cycles or 6-cycles.
+    ///
+    /// # Examples
+    /// ```
+    /// # use ldpc_toolbox::sparse::SparseMatrix;
+    /// let mut h = SparseMatrix::new(3, 4);
+    /// h.insert_row(0, [0, 1, 2].iter());
+    /// h.insert_row(1, [0, 1].iter());
+    /// h.insert_row(2, [2, 3].iter());
+    /// assert_eq!(
+    ///     h.short_cycle_edges(6),
+    ///     vec![(0, 0), (0, 1), (1, 0), (1, 1)]
+    /// );
+    /// ```
+    pub fn short_cycle_edges(&self, max: usize) -> Vec<(usize, usize)> {
+        self.iter_all()
+            .filter(|&(row, col)| self.edge_in_cycle_with_max(row, col, max))
+            .collect()
+    }
+
+    // Determines if the edge (row, col) belongs to a cycle of length smaller or
+    // equal than max by searching for a path from col to row of length smaller
+    // than max which does not use this edge.
+    fn edge_in_cycle_with_max(&self, row: usize, col: usize, max: usize) -> bool {
+        let target = Node::Row(row);
+        let mut visited = HashSet::new();
+        visited.insert(Node::Col(col));
+        let mut frontier = vec![Node::Col(col)];
+        for path_length in 1..max {
+            let mut next = Vec::new();
+            for &node in &frontier {
+                for x in node.iter(self) {
+                    if x == target {
+                        if path_length > 1 {
+                            return true;
+                        }
+                        // skip the edge (row, col) itself
+                        continue;
+                    }
+                    if visited.insert(x) {
+                        next.push(x);
+                    }
+                }
+            }
+            frontier = next;
+        }
+        false
+    }
+
     /// Run the BFS algorithm
     ///
     /// This uses a node of the graph associated to the matrix as the root
@@ -457,7 +974,7 @@ impl SparseMatrix {
 /// A node in the graph associated to a sparse matrix
 ///
 /// A node can represent a row or a column of the graph.
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
 pub enum Node {
     /// Node representing row number `n`
     Row(usize),
@@ -466,6 +983,22 @@ pub enum Node {
 }
 
 impl Node {
+    /// Returns the row of this node, or `None` if the node is a column.
+    pub fn row(self) -> Option<Row> {
+        match self {
+            Node::Row(n) => Some(Row(n)),
+            Node::Col(_) => None,
+        }
+    }
+
+    /// Returns the column of this node, or `None` if the node is a row.
+    pub fn col(self) -> Option<Col> {
+        match self {
+            Node::Row(_) => None,
+            Node::Col(n) => Some(Col(n)),
+        }
+    }
+
     fn iter(self, h: &SparseMatrix) -> impl Iterator<Item = Node> + '_ {
         match self {
             Node::Row(n) => h.iter_row(n),
@@ -478,6 +1011,370 @@ impl Node {
     }
 }
 
+/// Options for writing a sparse matrix in alist format
+///
+/// The default options give the alist format as originally defined by MacKay,
+/// which is the one written by [`SparseMatrix::write_alist`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct AlistOptions {
+    /// Use zeros as padding for irregular codes.
+    ///
+    /// Padding is never written when using zero-based indices.
+    pub padding: bool,
+    /// Base of the row and column indices.
+    pub index_base: IndexBase,
+    /// Order of the sections of the alist.
+    pub sections: AlistSections,
+}
+
+impl Default for AlistOptions {
+    fn default() -> AlistOptions {
+        AlistOptions {
+            padding: true,
+            index_base: IndexBase::One,
+            sections: AlistSections::ColumnsFirst,
+        }
+    }
+}
+
+/// Orientation of the matrix in an alist
+///
+/// This is used by [`SparseMatrix::from_alist_with`] to handle alists that
+/// hold the transpose of the parity check matrix.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Default)]
+pub enum AlistOrientation {
+    /// Transpose the matrix if it has more rows than columns.
+    #[default]
+    Auto,
+    /// Use the matrix as given in the alist.
+    AsIs,
+    /// Transpose the matrix given in the alist.
+    Transposed,
+}
+
+impl std::str::FromStr for AlistOrientation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<AlistOrientation> {
+        Ok(match s {
+            "auto" => AlistOrientation::Auto,
+            "as-is" => AlistOrientation::AsIs,
+            "transposed" => AlistOrientation::Transposed,
+            _ => return Err(format!("invalid alist orientation {s}")),
+        })
+    }
+}
+
+impl std::fmt::Display for AlistOrientation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                AlistOrientation::Auto => "auto",
+                AlistOrientation::AsIs => "as-is",
+                AlistOrientation::Transposed => "transposed",
+            }
+        )
+    }
+}
+
+/// Validation of the weights given in an alist
+///
+/// This is used by [`SparseMatrix::from_alist_with`] to select how to handle
+/// alists whose header gives weights that do not match the matrix.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Default)]
+pub enum AlistValidation {
+    /// Return a warning and use the matrix given by the alist.
+    #[default]
+    Lenient,
+    /// Return an error.
+    Strict,
+}
+
+impl std::str::FromStr for AlistValidation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<AlistValidation> {
+        Ok(match s {
+            "lenient" => AlistValidation::Lenient,
+            "strict" => AlistValidation::Strict,
+            _ => return Err(format!("invalid alist validation {s}")),
+        })
+    }
+}
+
+impl std::fmt::Display for AlistValidation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                AlistValidation::Lenient => "lenient",
+                AlistValidation::Strict => "strict",
+            }
+        )
+    }
+}
+
+/// Warning about the contents of an alist
+///
+/// This is returned by [`SparseMatrix::from_alist_with`]. The sizes and
+/// indices refer to the matrix as given in the alist, before any
+/// transposition.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum AlistWarning {
+    /// The matrix had more rows than columns and it has been transposed.
+    Transposed {
+        /// Number of rows in the alist.
+        rows: usize,
+        /// Number of columns in the alist.
+        cols: usize,
+    },
+    /// The matrix has more rows than columns, so the alist might hold its
+    /// transpose.
+    MoreRowsThanColumns {
+        /// Number of rows in the alist.
+        rows: usize,
+        /// Number of columns in the alist.
+        cols: usize,
+    },
+    /// Some of the weights given in a line of the header of the alist do not
+    /// match the matrix.
+    InconsistentWeights {
+        /// Line of the alist (2 for the maximum weights, 3 for the column
+        /// weights and 4 for the row weights).
+        line: usize,
+        /// Index of the first wrong weight in the line.
+        index: usize,
+        /// First wrong weight, or `None` if it is missing or it is not a
+        /// number.
+        stated: Option<usize>,
+        /// Actual value of the first wrong weight.
+        actual: usize,
+        /// Number of wrong weights in the line.
+        count: usize,
+    },
+}
+
+impl std::fmt::Display for AlistWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AlistWarning::Transposed { rows, cols } => write!(
+                f,
+                "the alist matrix has more rows than columns ({rows} x {cols}); \
+                 it has been transposed"
+            ),
+            AlistWarning::MoreRowsThanColumns { rows, cols } => write!(
+                f,
+                "the alist matrix has more rows than columns ({rows} x {cols}); \
+                 the alist might hold its transpose"
+            ),
+            AlistWarning::InconsistentWeights {
+                line,
+                index,
+                stated,
+                actual,
+                count,
+            } => {
+                let (weights, first) = match line {
+                    2 if *index == 0 => ("maximum weights", "the column weight".to_string()),
+                    2 => ("maximum weights", "the row weight".to_string()),
+                    3 => ("column weights", format!("column {index}")),
+                    _ => ("row weights", format!("row {index}")),
+                };
+                let stated = match stated {
+                    Some(w) => format!("given as {w}"),
+                    None => "missing".to_string(),
+                };
+                write!(
+                    f,
+                    "{count} of the {weights} in line {line} of the alist do not match the \
+                     matrix; the first is {first}: {stated}, actual weight {actual}"
+                )
+            }
+        }
+    }
+}
+
+/// Base of the indices written in an alist
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum IndexBase {
+    /// One-based indices, as in the original alist format.
+    One,
+    /// Zero-based indices.
+    Zero,
+}
+
+impl std::str::FromStr for IndexBase {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<IndexBase> {
+        Ok(match s {
+            "1" => IndexBase::One,
+            "0" => IndexBase::Zero,
+            _ => return Err(format!("invalid index base {s}")),
+        })
+    }
+}
+
+impl std::fmt::Display for IndexBase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                IndexBase::One => "1",
+                IndexBase::Zero => "0",
+            }
+        )
+    }
+}
+
+/// Order of the sections written in an alist
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum AlistSections {
+    /// Column data before row data, as in the original alist format.
+    ColumnsFirst,
+    /// Row data before column data.
+    ///
+    /// All the lines of the alist that contain a value for the columns and
+    /// a value for the rows are written with the rows first, including the
+    /// matrix size and the maximum weights. Then the row weights are written
+    /// before the column weights, and the row entries before the column
+    /// entries.
+    RowsFirst,
+    /// Only the column data.
+    ///
+    /// The header lines are the same as in the original alist format, but the
+    /// entries of each row are omitted. The resulting alist can still be read
+    /// with [`SparseMatrix::from_alist`].
+    ColumnsOnly,
+}
+
+impl std::str::FromStr for AlistSections {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<AlistSections> {
+        Ok(match s {
+            "columns-first" => AlistSections::ColumnsFirst,
+            "rows-first" => AlistSections::RowsFirst,
+            "columns-only" => AlistSections::ColumnsOnly,
+            _ => return Err(format!("invalid alist sections {s}")),
+        })
+    }
+}
+
+impl std::fmt::Display for AlistSections {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                AlistSections::ColumnsFirst => "columns-first",
+                AlistSections::RowsFirst => "rows-first",
+                AlistSections::ColumnsOnly => "columns-only",
+            }
+        )
+    }
+}
+
+/// Row index of a sparse matrix
+///
+/// This newtype can be used instead of a `usize` in the methods of
+/// [`SparseMatrix`] to prevent mixing up row and column indices.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct Row(pub usize);
+
+/// Column index of a sparse matrix
+///
+/// This newtype can be used instead of a `usize` in the methods of
+/// [`SparseMatrix`] to prevent mixing up row and column indices.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct Col(pub usize);
+
+/// Types that can be used as a row index
+///
+/// This is implemented by [`Row`] and by `usize`. The `usize` implementation
+/// is transitional and will be removed in 0.8.0 (see the
+/// [module documentation](self#typed-indices)).
+pub trait AsRow: Copy {
+    /// Returns the row index as a `usize`.
+    fn row(self) -> usize;
+}
+
+/// Types that can be used as a column index
+///
+/// This is implemented by [`Col`] and by `usize`. The `usize` implementation
+/// is transitional and will be removed in 0.8.0 (see the
+/// [module documentation](self#typed-indices)).
+pub trait AsCol: Copy {
+    /// Returns the column index as a `usize`.
+    fn col(self) -> usize;
+}
+
+// Transitional: to be removed in 0.8.0
+impl AsRow for usize {
+    fn row(self) -> usize {
+        self
+    }
+}
+
+impl AsRow for Row {
+    fn row(self) -> usize {
+        self.0
+    }
+}
+
+// Transitional: to be removed in 0.8.0
+impl AsCol for usize {
+    fn col(self) -> usize {
+        self
+    }
+}
+
+impl AsCol for Col {
+    fn col(self) -> usize {
+        self.0
+    }
+}
+
+impl From<Row> for usize {
+    fn from(row: Row) -> usize {
+        row.0
+    }
+}
+
+impl From<Col> for usize {
+    fn from(col: Col) -> usize {
+        col.0
+    }
+}
+
+impl From<Row> for Node {
+    fn from(row: Row) -> Node {
+        Node::Row(row.0)
+    }
+}
+
+impl From<Col> for Node {
+    fn from(col: Col) -> Node {
+        Node::Col(col.0)
+    }
+}
+
+impl std::fmt::Display for Row {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "row {}", self.0)
+    }
+}
+
+impl std::fmt::Display for Col {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "column {}", self.0)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -502,6 +1399,60 @@ mod tests {
         assert_eq!(h, h2);
     }
 
+    #[test]
+    fn validate() {
+        let mut h = SparseMatrix::new(3, 4);
+        h.insert(0, 1);
+        h.insert(2, 3);
+        h.toggle(2, 1);
+        h.clear_row(0);
+        assert_eq!(h.validate(), Ok(()));
+
+        let mut missing = h.clone();
+        missing.rows[1].push(2);
+        assert_eq!(
+            missing.validate(),
+            Err(String::from(
+                "entry (1, 2) is stored in row 1 but not in column 2"
+            ))
+        );
+
+        let mut duplicate = h.clone();
+        duplicate.cols[3].push(2);
+        assert_eq!(
+            duplicate.validate(),
+            Err(String::from("column 3 contains row 2 more than once"))
+        );
+
+        let mut out_of_range = h;
+        out_of_range.rows[0].push(4);
+        assert_eq!(
+            out_of_range.validate(),
+            Err(String::from("row 0 contains out of range column 4"))
+        );
+    }
+
+    #[test]
+    fn typed_indices() {
+        let mut h = SparseMatrix::new(10, 20);
+        h.insert(Row(3), Col(7));
+        h.insert(4, Col(7));
+        assert!(h.contains(Row(3), 7));
+        assert_eq!(h.row_weight(Row(3)), 1);
+        assert_eq!(h.col_weight(Col(7)), 2);
+        assert_eq!(
+            h.iter_entries().collect::<Vec<_>>(),
+            vec![(Row(3), Col(7)), (Row(4), Col(7))]
+        );
+        h.remove(Row(4), Col(7));
+        assert_eq!(h.col_weight(Col(7)), 1);
+
+        assert_eq!(Node::from(Row(3)), Node::Row(3));
+        assert_eq!(Node::from(Col(7)).col(), Some(Col(7)));
+        assert_eq!(Node::Col(7).row(), None);
+        assert_eq!(usize::from(Col(7)), 7);
+    }
+
     #[test]
     fn iter_all() {
         use std::collections::HashSet;
@@ -624,4 +1575,157 @@ mod tests {
         assert_eq!(h3.alist(), expected);
         assert_eq!(h3.alist_no_padding(), expected_no_padding);
     }
+
+    #[test]
+    fn alist_options() {
+        let mut h = SparseMatrix::new(2, 3);
+        h.insert(0, 0);
+        h.insert(0, 1);
+        h.insert(1, 1);
+
+        let rows_first = AlistOptions {
+            sections: AlistSections::RowsFirst,
+            ..AlistOptions::default()
+        };
+        assert_eq!(
+            h.alist_with(&rows_first),
+            "2 3\n2 2\n2 1\n1 2 0\n1 2\n2 0\n1 0\n1 2\n0 0\n"
+        );
+
+        let zero_based = AlistOptions {
+            index_base: IndexBase::Zero,
+            ..AlistOptions::default()
+        };
+        assert_eq!(
+            h.alist_with(&zero_based),
+            "3 2\n2 2\n1 2 0\n2 1\n0\n0 1\n\n0 1\n1\n"
+        );
+
+        let columns_only = AlistOptions {
+            sections: AlistSections::ColumnsOnly,
+            ..AlistOptions::default()
+        };
+        let alist = h.alist_with(&columns_only);
+        assert_eq!(alist, "3 2\n2 2\n1 2 0\n2 1\n1 0\n1 2\n0 0\n");
+        assert_eq!(SparseMatrix::from_alist(&alist).unwrap(), h);
+        assert_eq!(h.alist_with(&AlistOptions::default()), h.alist());
+    }
+
+    #[test]
+    fn alist_orientation() {
+        let mut h = SparseMatrix::new(2, 3);
+        h.insert(0, 0);
+        h.insert(0, 1);
+        h.insert(1, 1);
+        h.insert(1, 2);
+        let alist = h.alist();
+        let transposed = h.transpose().alist();
+        let read = |alist: &str, orientation| {
+            SparseMatrix::from_alist_with(alist, orientation, AlistValidation::Strict)
+        };
+
+        for orientation in [AlistOrientation::Auto, AlistOrientation::AsIs] {
+            assert_eq!(read(&alist, orientation).unwrap(), (h.clone(), vec![]));
+        }
+        assert_eq!(
+            read(&transposed, AlistOrientation::Auto).unwrap(),
+            (
+                h.clone(),
+                vec![AlistWarning::Transposed { rows: 3, cols: 2 }]
+            )
+        );
+        assert_eq!(
+            read(&transposed, AlistOrientation::AsIs).unwrap(),
+            (
+                h.transpose(),
+                vec![AlistWarning::MoreRowsThanColumns { rows: 3, cols: 2 }]
+            )
+        );
+        assert_eq!(
+            read(&alist, AlistOrientation::Transposed).unwrap(),
+            (h.transpose(), vec![])
+        );
+
+        // an alist with column metadata is never transposed automatically
+        let mut t = h.transpose();
+        t.init_column_metadata();
+        let alist = t.alist_with(&AlistOptions::default());
+        let (t2, warnings) = read(&alist, AlistOrientation::Auto).unwrap();
+        assert_eq!(t2, t);
+        assert_eq!(
+            warnings,
+            [AlistWarning::MoreRowsThanColumns { rows: 3, cols: 2 }]
+        );
+        assert!(read(&alist, AlistOrientation::Transposed).is_err());
+    }
+
+    #[test]
+    fn alist_weights() {
+        let mut h = SparseMatrix::new(2, 3);
+        h.insert(0, 0);
+        h.insert(0, 1);
+        h.insert(1, 1);
+        h.insert(1, 2);
+        let read = |alist: &str, validation| {
+            SparseMatrix::from_alist_with(alist, AlistOrientation::AsIs, validation)
+        };
+        // alists written in all the formats that can be read have consistent
+        // weights
+        for options in [
+            AlistOptions::default(),
+            AlistOptions {
+                padding: false,
+                ..AlistOptions::default()
+            },
+            AlistOptions {
+                sections: AlistSections::ColumnsOnly,
+                ..AlistOptions::default()
+            },
+        ] {
+            let alist = h.alist_with(&options);
+            assert_eq!(
+                read(&alist, AlistValidation::Strict).unwrap(),
+                (h.clone(), vec![])
+            );
+        }
+
+        // wrong maximum weights, a wrong column weight, and missing row weights
+        let alist = "3 2\n2 3\n1 2 2\n2\n1\n1 2\n2\n1 2\n2 3\n";
+        let (h2, warnings) = read(alist, AlistValidation::Lenient).unwrap();
+        assert_eq!(h2, h);
+        assert_eq!(
+            warnings,
+            [
+                AlistWarning::InconsistentWeights {
+                    line: 2,
+                    index: 1,
+                    stated: Some(3),
+                    actual: 2,
+                    count: 1
+                },
+                AlistWarning::InconsistentWeights {
+                    line: 3,
+                    index: 2,
+                    stated: Some(2),
+                    actual: 1,
+                    count: 1
+                },
+                AlistWarning::InconsistentWeights {
+                    line: 4,
+                    index: 1,
+                    stated: None,
+                    actual: 2,
+                    count: 1
+                },
+            ]
+        );
+        assert_eq!(
+            warnings[1].to_string(),
+            "1 of the column weights in line 3 of the alist do not match the matrix; \
+             the first is column 2: given as 2, actual weight 1"
+        );
+        assert!(read(alist, AlistValidation::Strict).is_err());
+        // from_alist does not check the weights
+        assert_eq!(SparseMatrix::from_alist(alist).unwrap(), h);
+    }
 }
@@ -0,0 +1,132 @@
+use crate::sparse::SparseMatrix;
+
+impl SparseMatrix {
+    /// Returns the ACE spectrum of the bipartite graph defined by the matrix.
+    ///
+    /// The approximate cycle extrinsic message degree (ACE) of a cycle is the
+    /// sum of `d - 2` over the column nodes of the cycle, where `d` is the
+    /// degree (column weight) of each node. Cycles with a low ACE are poorly
+    /// connected to the rest of the graph, so they tend to form the stopping
+    /// and trapping sets that cause the error floors of irregular codes.
+    ///
+    /// This function returns the minimum ACE of the cycles of each length up to
+    /// `max_length`, as a list of `(length, ace)` pairs sorted by
+    /// length. Lengths for which there are no cycles are not included. The
+    /// cycles are enumerated exhaustively, so the cost grows exponentially with
+    /// `max_length`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use ldpc_toolbox::sparse::SparseMatrix;
+    /// let mut h = SparseMatrix::new(3, 4);
+    /// h.insert_col(0, [0, 1, 2].iter());
+    /// h.insert_col(1, [0, 1].iter());
+    /// h.insert_col(2, [1, 2].iter());
+    /// h.insert_col(3, [2].iter());
+    /// // The 4-cycles are formed by column 0 together with column 1 or 2, and
+    /// // the 6-cycle formed by the three first columns has the same ACE
+    /// assert_eq!(h.ace_spectrum(8), vec![(4, 1), (6, 1)]);
+    /// ```
+    pub fn ace_spectrum(&self, max_length: usize) -> Vec<(usize, usize)> {
+        let mut search = CycleSearch {
+            h: self,
+            root: 0,
+            max_length,
+            visited_rows: vec![false; self.num_rows()],
+            visited_cols: vec![false; self.num_cols()],
+            min_ace: vec![None; max_length / 2 + 1],
+        };
+        for root in 0..self.num_cols() {
+            search.root = root;
+            search.visited_cols[root] = true;
+            search.extend_col(root, 0, self.col_ace(root));
+            search.visited_cols[root] = false;
+        }
+        search
+            .min_ace
+            .iter()
+            .enumerate()
+            .filter_map(|(j, ace)| ace.map(|ace| (2 * j, ace)))
+            .collect()
+    }
+
+    // Contribution of a column node to the ACE of a cycle. Columns of weight
+    // smaller than 2 cannot belong to a cycle.
+    fn col_ace(&self, col: usize) -> usize {
+        self.col_weight(col).saturating_sub(2)
+    }
+}
+
+// Depth-first search of the cycles whose column of lowest index is the root.
+struct CycleSearch<'a> {
+    h: &'a SparseMatrix,
+    root: usize,
+    max_length: usize,
+    visited_rows: Vec<bool>,
+    visited_cols: Vec<bool>,
+    // indexed by half the length of the cycle
+    min_ace: Vec<Option<usize>>,
+}
+
+impl CycleSearch<'_> {
+    // Continues a path that has `length` edges and ends in a column.
+    fn extend_col(&mut self, col: usize, length: usize, ace: usize) {
+        // At least two more edges are needed to close the cycle
+        if length + 2 > self.max_length {
+            return;
+        }
+        for &row in self.h.iter_col(col) {
+            if !self.visited_rows[row] {
+                self.visited_rows[row] = true;
+                self.extend_row(row, length + 1, ace);
+                self.visited_rows[row] = false;
+            }
+        }
+    }
+
+    // Continues a path that has `length` edges and ends in a row.
+    fn extend_row(&mut self, row: usize, length: usize, ace: usize) {
+        for &col in self.h.iter_row(row) {
+            if col == self.root {
+                // A path of length 1 returns through the edge it has used
+                if length >= 3 {
+                    let cycle_length = length + 1;
+                    let entry = &mut self.min_ace[cycle_length / 2];
+                    *entry = Some(entry.map_or(ace, |x| x.min(ace)));
+                }
+            } else if col > self.root && !self.visited_cols[col] && length + 3 <= self.max_length {
+                self.visited_cols[col] = true;
+                self.extend_col(col, length + 1, ace + self.h.col_ace(col));
+                self.visited_cols[col] = false;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sparse::SparseMatrix;
+
+    #[test]
+    fn ace_spectrum() {
+        // Cycle of length 8 through 4 columns of weight 2, with an extra
+        // column of weight 3 that forms 6-cycles with columns 0 and 1 and with
+        // columns 2 and 3
+        let mut h = SparseMatrix::new(5, 5);
+        for j in 0..4 {
+            h.insert(j, j);
+            h.insert((j + 1) % 4, j);
+        }
+        h.insert_col(4, [0, 2, 4].iter());
+        assert_eq!(h.girth(), Some(6));
+        assert!(h.ace_spectrum(4).is_empty());
+        // The 6-cycles contain column 4 (ACE 1) and two columns of weight 2
+        assert_eq!(h.ace_spectrum(10), vec![(6, 1), (8, 0)]);
+
+        let mut h = SparseMatrix::new(3, 3);
+        h.insert_row(0, [0, 1, 2].iter());
+        h.insert_row(1, [0, 1, 2].iter());
+        h.insert_row(2, [0, 1, 2].iter());
+        assert_eq!(h.ace_spectrum(6), vec![(4, 2), (6, 3)]);
+    }
+}
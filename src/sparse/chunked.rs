@@ -0,0 +1,585 @@
+//! File-backed sparse matrices.
+//!
+//! A [`SparseMatrix`] keeps every entry twice in memory, which is not practical
+//! for very long codes, such as spatially coupled LDPC chains with millions of
+//! columns. This module contains [`ChunkedMatrix`], a read-only matrix stored
+//! in a file by columns and divided into windows of consecutive columns. Only
+//! a small index is kept in memory, and each [`Window`] is read from the file
+//! when it is requested, so the memory usage of an algorithm that processes
+//! the matrix window by window is bounded by the size of a window.
+//!
+//! A chunked matrix is constructed column by column with a
+//! [`ChunkedMatrixWriter`], either directly, from a [`SparseMatrix`] with
+//! [`ChunkedMatrix::from_sparse`], or from an alist file with
+//! [`ChunkedMatrix::from_alist_reader`], which reads the alist line by line.
+//!
+//! The decoders require a [`SparseMatrix`]. A chunked matrix can be decoded
+//! window by window with the sliding window decoder in
+//! [`decoder::windowed`](crate::decoder::windowed). Other window-based
+//! algorithms can obtain the part of the matrix that corresponds to a window
+//! with [`Window::to_sparse_matrix`].
+//!
+//! # File format
+//!
+//! All the integers are stored in little-endian. The file starts with the
+//! 8-byte magic `LDPCCHK1`, followed by the number of rows, the number of
+//! columns, the number of columns per window and the position of the index
+//! in the file, each as a `u64`. Then each column is stored as its weight
+//! followed by the rows of its entries, each as a `u32`. The index at the end
+//! of the file contains the position of the first column of each window,
+//! and the position of the index itself, each as a `u64`.
+//!
+//! # Examples
+//! ```
+//! # use ldpc_toolbox::{codes::ccsds::*, sparse::chunked::ChunkedMatrix};
+//! let h = AR4JACode::new(AR4JARate::R1_2, AR4JAInfoSize::K1024).h();
+//! let path = std::env::temp_dir().join("ldpc-toolbox-chunked-doctest.bin");
+//! let chunked = ChunkedMatrix::from_sparse(&h, &path, 512)?;
+//! assert_eq!(chunked.num_windows(), 5);
+//! let window = chunked.window(1)?;
+//! assert_eq!(window.cols(), 512..1024);
+//! assert_eq!(window.iter_col(600).len(), h.col_weight(600));
+//! # std::fs::remove_file(&path)?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use crate::sparse::SparseMatrix;
+use std::{
+    fs::File,
+    io::{BufRead, BufWriter, Read, Seek, SeekFrom, Write},
+    ops::Range,
+    path::Path,
+    sync::Mutex,
+};
+use thiserror::Error;
+
+/// Magic at the start of a chunked matrix file.
+const MAGIC: &[u8; 8] = b"LDPCCHK1";
+/// Size of the header of a chunked matrix file.
+const HEADER_SIZE: u64 = 8 + 4 * 8;
+
+/// Chunked matrix error.
+#[derive(Debug, Error)]
+pub enum Error {
+    /// Error accessing the file.
+    #[error("could not access chunked matrix file: {0}")]
+    Io(#[from] std::io::Error),
+    /// The file is not a valid chunked matrix file.
+    #[error("invalid chunked matrix file: {0}")]
+    InvalidFile(String),
+    /// Error in the contents of the matrix.
+    #[error("invalid matrix: {0}")]
+    InvalidMatrix(String),
+}
+
+/// Chunked matrix result.
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// File-backed sparse matrix divided into windows of columns.
+///
+/// See the [module documentation](self).
+#[derive(Debug)]
+pub struct ChunkedMatrix {
+    file: Mutex<File>,
+    num_rows: usize,
+    num_cols: usize,
+    window_cols: usize,
+    // window_offsets[w]..window_offsets[w + 1] is the byte range of window w
+    // in the file
+    window_offsets: Vec<u64>,
+}
+
+impl ChunkedMatrix {
+    /// Opens a chunked matrix file.
+    ///
+    /// Only the header and the index of the file are read.
+    pub fn open(path: impl AsRef<Path>) -> Result<ChunkedMatrix> {
+        let mut file = File::open(path)?;
+        let mut magic = [0; 8];
+        file.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(Error::InvalidFile("wrong magic".to_string()));
+        }
+        let [num_rows, num_cols, window_cols, index_offset] = read_u64s::<4>(&mut file)?;
+        let to_usize = |x: u64| {
+            usize::try_from(x).map_err(|_| Error::InvalidFile(format!("size {x} too large")))
+        };
+        let num_rows = to_usize(num_rows)?;
+        let num_cols = to_usize(num_cols)?;
+        let window_cols = to_usize(window_cols)?;
+        if window_cols == 0 {
+            return Err(Error::InvalidFile("zero columns per window".to_string()));
+        }
+        let num_windows = num_cols.div_ceil(window_cols);
+        file.seek(SeekFrom::Start(index_offset))?;
+        let mut window_offsets = Vec::with_capacity(num_windows + 1);
+        for _ in 0..=num_windows {
+            window_offsets.push(read_u64s::<1>(&mut file)?[0]);
+        }
+        if window_offsets.first() != Some(&HEADER_SIZE)
+            || window_offsets.last() != Some(&index_offset)
+            || window_offsets.windows(2).any(|w| w[0] > w[1])
+        {
+            return Err(Error::InvalidFile("invalid window index".to_string()));
+        }
+        Ok(ChunkedMatrix {
+            file: Mutex::new(file),
+            num_rows,
+            num_cols,
+            window_cols,
+            window_offsets,
+        })
+    }
+
+    /// Writes a [`SparseMatrix`] to a chunked matrix file and opens it.
+    pub fn from_sparse(
+        h: &SparseMatrix,
+        path: impl AsRef<Path>,
+        window_cols: usize,
+    ) -> Result<ChunkedMatrix> {
+        let mut writer = ChunkedMatrixWriter::create(path, h.num_rows(), window_cols)?;
+        for col in 0..h.num_cols() {
+            writer.push_col(h.iter_col(col).copied())?;
+        }
+        writer.finish()
+    }
+
+    /// Converts an alist to a chunked matrix file and opens it.
+    ///
+    /// The alist is read line by line, and only the column section is used,
+    /// so the whole matrix is never held in memory. Alists with and without
+    /// zero padding are supported, as in [`SparseMatrix::from_alist`].
+    pub fn from_alist_reader<R: BufRead>(
+        alist: R,
+        path: impl AsRef<Path>,
+        window_cols: usize,
+    ) -> Result<ChunkedMatrix> {
+        let mut lines = alist.lines();
+        let mut next_line = |what: &str| {
+            lines
+                .next()
+                .transpose()?
+                .ok_or_else(|| Error::InvalidMatrix(format!("alist {what} not found")))
+        };
+        let sizes = next_line("first line")?;
+        let sizes = sizes
+            .split_whitespace()
+            .map(|x| x.parse::<usize>())
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|_| Error::InvalidMatrix("alist size is not a number".to_string()))?;
+        let [num_cols, num_rows, ..] = sizes[..] else {
+            return Err(Error::InvalidMatrix(
+                "alist first line does not contain enough elements".to_string(),
+            ));
+        };
+        // skip max weights and weights
+        for _ in 0..3 {
+            next_line("weights")?;
+        }
+        let mut writer = ChunkedMatrixWriter::create(path, num_rows, window_cols)?;
+        for _ in 0..num_cols {
+            let col_data = next_line("column")?;
+            let rows = col_data
+                .split_whitespace()
+                .map(|x| x.parse::<usize>())
+                .collect::<std::result::Result<Vec<_>, _>>()
+                .map_err(|_| Error::InvalidMatrix("row value is not a number".to_string()))?;
+            // row == 0 is used for padding in irregular codes
+            writer.push_col(rows.into_iter().filter(|&row| row != 0).map(|row| row - 1))?;
+        }
+        writer.finish()
+    }
+
+    /// Returns the number of rows of the matrix.
+    pub fn num_rows(&self) -> usize {
+        self.num_rows
+    }
+
+    /// Returns the number of columns of the matrix.
+    pub fn num_cols(&self) -> usize {
+        self.num_cols
+    }
+
+    /// Returns the number of columns of each window.
+    ///
+    /// The last window can have fewer columns.
+    pub fn window_cols(&self) -> usize {
+        self.window_cols
+    }
+
+    /// Returns the number of windows.
+    pub fn num_windows(&self) -> usize {
+        self.window_offsets.len() - 1
+    }
+
+    /// Returns the window that contains a column.
+    pub fn window_of(&self, col: usize) -> usize {
+        assert!(col < self.num_cols);
+        col / self.window_cols
+    }
+
+    /// Reads a window from the file.
+    pub fn window(&self, window: usize) -> Result<Window> {
+        assert!(window < self.num_windows());
+        let start = self.window_offsets[window];
+        let len = usize::try_from(self.window_offsets[window + 1] - start)
+            .map_err(|_| Error::InvalidFile("window too large".to_string()))?;
+        let mut data = vec![0; len];
+        {
+            let mut file = self.file.lock().unwrap();
+            file.seek(SeekFrom::Start(start))?;
+            file.read_exact(&mut data)?;
+        }
+        let first_col = window * self.window_cols;
+        let num_cols = self.window_cols.min(self.num_cols - first_col);
+        let mut data = data
+            .chunks_exact(4)
+            .map(|x| u32::from_le_bytes(x.try_into().unwrap()));
+        let mut offsets = Vec::with_capacity(num_cols + 1);
+        let mut rows = Vec::with_capacity(len / 4 - num_cols);
+        offsets.push(0);
+        for _ in 0..num_cols {
+            let weight = data
+                .next()
+                .ok_or_else(|| Error::InvalidFile("truncated window".to_string()))?;
+            for _ in 0..weight {
+                let row = data
+                    .next()
+                    .ok_or_else(|| Error::InvalidFile("truncated window".to_string()))?;
+                if row as usize >= self.num_rows {
+                    return Err(Error::InvalidFile(format!("row {row} out of range")));
+                }
+                rows.push(row);
+            }
+            offsets.push(rows.len());
+        }
+        if data.next().is_some() {
+            return Err(Error::InvalidFile("trailing data in window".to_string()));
+        }
+        Ok(Window {
+            first_col,
+            offsets,
+            rows,
+        })
+    }
+
+    /// Returns an iterator over all the windows, in order.
+    ///
+    /// Each window is read from the file when the iterator reaches it.
+    pub fn windows(&self) -> impl Iterator<Item = Result<Window>> + '_ {
+        (0..self.num_windows()).map(|window| self.window(window))
+    }
+
+    /// Returns the weights of the rows of the matrix.
+    ///
+    /// The matrix is read window by window.
+    pub fn row_weights(&self) -> Result<Vec<usize>> {
+        let mut weights = vec![0; self.num_rows];
+        for window in self.windows() {
+            for &row in window?.rows.iter() {
+                weights[row as usize] += 1;
+            }
+        }
+        Ok(weights)
+    }
+
+    /// Computes the syndrome of a word.
+    ///
+    /// The word is given with a `u8` per bit, and the syndrome is returned in
+    /// the same way. The matrix is read window by window.
+    pub fn syndrome(&self, word: &[u8]) -> Result<Vec<u8>> {
+        assert_eq!(word.len(), self.num_cols);
+        let mut syndrome = vec![0; self.num_rows];
+        for window in self.windows() {
+            let window = window?;
+            for col in window.cols() {
+                if word[col] & 1 == 1 {
+                    for &row in window.iter_col(col) {
+                        syndrome[row as usize] ^= 1;
+                    }
+                }
+            }
+        }
+        Ok(syndrome)
+    }
+
+    /// Reads the whole matrix into a [`SparseMatrix`].
+    pub fn to_sparse_matrix(&self) -> Result<SparseMatrix> {
+        let mut h = SparseMatrix::new(self.num_rows, self.num_cols);
+        for window in self.windows() {
+            let window = window?;
+            for col in window.cols() {
+                h.insert_col(col, window.iter_col(col).map(|&row| row as usize));
+            }
+        }
+        Ok(h)
+    }
+}
+
+/// Window of a [`ChunkedMatrix`].
+///
+/// A window holds the entries of a range of consecutive columns.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Window {
+    first_col: usize,
+    // offsets[j]..offsets[j + 1] are the positions in rows corresponding to
+    // column first_col + j
+    offsets: Vec<usize>,
+    rows: Vec<u32>,
+}
+
+impl Window {
+    /// Returns the columns of the matrix contained in the window.
+    pub fn cols(&self) -> Range<usize> {
+        self.first_col..self.first_col + self.offsets.len() - 1
+    }
+
+    /// Returns the number of non-zero entries in the window.
+    pub fn num_entries(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Returns the rows of the entries of a column.
+    ///
+    /// The column is given by its index in the matrix, and it must be contained
+    /// in the window.
+    pub fn iter_col(&self, col: usize) -> std::slice::Iter<'_, u32> {
+        assert!(self.cols().contains(&col));
+        let j = col - self.first_col;
+        self.rows[self.offsets[j]..self.offsets[j + 1]].iter()
+    }
+
+    /// Returns the submatrix of the window.
+    ///
+    /// The submatrix is formed by the columns of the window and the rows that
+    /// have entries in these columns. The rows of the matrix are returned
+    /// in increasing order together with the submatrix, whose row `j`
+    /// corresponds to the `j`-th returned row.
+    pub fn to_sparse_matrix(&self) -> (Vec<usize>, SparseMatrix) {
+        let mut rows = self
+            .rows
+            .iter()
+            .map(|&row| row as usize)
+            .collect::<Vec<_>>();
+        rows.sort_unstable();
+        rows.dedup();
+        let mut h = SparseMatrix::new(rows.len(), self.offsets.len() - 1);
+        for col in self.cols() {
+            h.insert_col(
+                col - self.first_col,
+                self.iter_col(col)
+                    .map(|&row| rows.binary_search(&(row as usize)).unwrap()),
+            );
+        }
+        (rows, h)
+    }
+}
+
+/// Writer of chunked matrix files.
+///
+/// The columns are written one by one with [`ChunkedMatrixWriter::push_col`],
+/// so the matrix never needs to be held in memory.
+///
+/// # Examples
+/// ```
+/// # use ldpc_toolbox::sparse::chunked::ChunkedMatrixWriter;
+/// let path = std::env::temp_dir().join("ldpc-toolbox-chunked-writer-doctest.bin");
+/// let mut writer = ChunkedMatrixWriter::create(&path, 2, 2)?;
+/// writer.push_col([0, 1])?;
+/// writer.push_col([1])?;
+/// writer.push_col([0])?;
+/// let h = writer.finish()?;
+/// assert_eq!(h.num_cols(), 3);
+/// assert_eq!(h.num_windows(), 2);
+/// assert_eq!(h.row_weights()?, vec![2, 2]);
+/// # std::fs::remove_file(&path)?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug)]
+pub struct ChunkedMatrixWriter {
+    file: BufWriter<File>,
+    path: std::path::PathBuf,
+    num_rows: usize,
+    num_cols: usize,
+    window_cols: usize,
+    position: u64,
+    window_offsets: Vec<u64>,
+}
+
+impl ChunkedMatrixWriter {
+    /// Creates a chunked matrix file.
+    ///
+    /// The file is truncated if it already exists.
+    pub fn create(
+        path: impl AsRef<Path>,
+        num_rows: usize,
+        window_cols: usize,
+    ) -> Result<ChunkedMatrixWriter> {
+        if window_cols == 0 {
+            return Err(Error::InvalidMatrix("zero columns per window".to_string()));
+        }
+        if u32::try_from(num_rows).is_err() {
+            return Err(Error::InvalidMatrix(format!(
+                "number of rows {num_rows} too large"
+            )));
+        }
+        let path = path.as_ref().to_path_buf();
+        let mut file = BufWriter::new(File::create(&path)?);
+        // the header is rewritten by finish()
+        file.write_all(&[0; HEADER_SIZE as usize])?;
+        Ok(ChunkedMatrixWriter {
+            file,
+            path,
+            num_rows,
+            num_cols: 0,
+            window_cols,
+            position: HEADER_SIZE,
+            window_offsets: Vec::new(),
+        })
+    }
+
+    /// Appends a column to the matrix.
+    ///
+    /// The column is given by the rows of its entries.
+    pub fn push_col(&mut self, rows: impl IntoIterator<Item = usize>) -> Result<()> {
+        if self.num_cols.is_multiple_of(self.window_cols) {
+            self.window_offsets.push(self.position);
+        }
+        let rows = rows.into_iter().collect::<Vec<_>>();
+        self.write_u32(rows.len())?;
+        for row in rows {
+            if row >= self.num_rows {
+                return Err(Error::InvalidMatrix(format!(
+                    "row {row} out of range in column {}",
+                    self.num_cols
+                )));
+            }
+            self.write_u32(row)?;
+        }
+        self.num_cols += 1;
+        Ok(())
+    }
+
+    /// Finishes writing the file and opens it as a [`ChunkedMatrix`].
+    pub fn finish(mut self) -> Result<ChunkedMatrix> {
+        let index_offset = self.position;
+        self.window_offsets.push(index_offset);
+        for &offset in &self.window_offsets {
+            self.file.write_all(&offset.to_le_bytes())?;
+        }
+        self.file.seek(SeekFrom::Start(0))?;
+        self.file.write_all(MAGIC)?;
+        for x in [
+            self.num_rows as u64,
+            self.num_cols as u64,
+            self.window_cols as u64,
+            index_offset,
+        ] {
+            self.file.write_all(&x.to_le_bytes())?;
+        }
+        self.file.flush()?;
+        drop(self.file);
+        ChunkedMatrix::open(&self.path)
+    }
+
+    fn write_u32(&mut self, x: usize) -> Result<()> {
+        let x =
+            u32::try_from(x).map_err(|_| Error::InvalidMatrix(format!("value {x} too large")))?;
+        self.file.write_all(&x.to_le_bytes())?;
+        self.position += 4;
+        Ok(())
+    }
+}
+
+fn read_u64s<const N: usize>(file: &mut File) -> Result<[u64; N]> {
+    let mut values = [0; N];
+    for x in values.iter_mut() {
+        let mut bytes = [0; 8];
+        file.read_exact(&mut bytes)?;
+        *x = u64::from_le_bytes(bytes);
+    }
+    Ok(values)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::codes::ccsds::{AR4JACode, AR4JAInfoSize, AR4JARate};
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!(
+            "ldpc-toolbox-chunked-{name}-{}.bin",
+            std::process::id()
+        ))
+    }
+
+    #[test]
+    fn round_trip() {
+        let h = AR4JACode::new(AR4JARate::R1_2, AR4JAInfoSize::K1024).h();
+        let path = temp_path("round-trip");
+        let chunked = ChunkedMatrix::from_sparse(&h, &path, 1000).unwrap();
+        assert_eq!(chunked.num_windows(), 3);
+        assert_eq!(chunked.window(2).unwrap().cols(), 2000..2560);
+        assert_eq!(
+            chunked.to_sparse_matrix().unwrap().fingerprint(),
+            h.fingerprint()
+        );
+        let weights = (0..h.num_rows())
+            .map(|row| h.row_weight(row))
+            .collect::<Vec<_>>();
+        assert_eq!(chunked.row_weights().unwrap(), weights);
+        let reopened = ChunkedMatrix::open(&path).unwrap();
+        assert_eq!(reopened.window(1).unwrap(), chunked.window(1).unwrap());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn alist() {
+        let h = AR4JACode::new(AR4JARate::R4_5, AR4JAInfoSize::K1024).h();
+        let path = temp_path("alist");
+        let chunked =
+            ChunkedMatrix::from_alist_reader(h.alist_no_padding().as_bytes(), &path, 256).unwrap();
+        assert_eq!(
+            chunked.to_sparse_matrix().unwrap().fingerprint(),
+            h.fingerprint()
+        );
+        let chunked = ChunkedMatrix::from_alist_reader(h.alist().as_bytes(), &path, 256).unwrap();
+        assert_eq!(
+            chunked.to_sparse_matrix().unwrap().fingerprint(),
+            h.fingerprint()
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn window_submatrix_and_syndrome() {
+        let mut h = SparseMatrix::new(4, 4);
+        h.insert_col(0, [0, 3].iter());
+        h.insert_col(1, [0].iter());
+        h.insert_col(2, [1, 2].iter());
+        h.insert_col(3, [2, 3].iter());
+        let path = temp_path("window");
+        let chunked = ChunkedMatrix::from_sparse(&h, &path, 2).unwrap();
+        let (rows, sub) = chunked.window(1).unwrap().to_sparse_matrix();
+        assert_eq!(rows, vec![1, 2, 3]);
+        assert!(sub.contains(0, 0) && sub.contains(1, 0));
+        assert!(sub.contains(1, 1) && sub.contains(2, 1));
+        assert_eq!(chunked.syndrome(&[1, 0, 1, 0]).unwrap(), vec![1, 1, 1, 1]);
+        assert_eq!(chunked.syndrome(&[1, 1, 0, 1]).unwrap(), vec![0, 0, 1, 0]);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn errors() {
+        let path = temp_path("errors");
+        let mut writer = ChunkedMatrixWriter::create(&path, 2, 2).unwrap();
+        assert!(writer.push_col([2]).is_err());
+        assert!(ChunkedMatrixWriter::create(&path, 2, 0).is_err());
+        std::fs::write(&path, b"not a chunked matrix file").unwrap();
+        assert!(matches!(
+            ChunkedMatrix::open(&path),
+            Err(Error::InvalidFile(_))
+        ));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
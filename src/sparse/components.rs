@@ -0,0 +1,154 @@
+use crate::sparse::{Node, SparseMatrix};
+
+/// Connected component of the graph associated to a sparse matrix
+///
+/// A component contains a set of rows and columns that are connected through
+/// the non-zero entries of the matrix, and that are not connected to any other
+/// row or column. If the matrix is the parity check matrix of a code, each
+/// component is an independent sub-code, given by the parity check matrix
+/// [`Component::h`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Component {
+    /// Rows of the original matrix that belong to the component, in
+    /// increasing order.
+    ///
+    /// Row `j` of [`Component::h`] is row `rows[j]` of the original matrix.
+    pub rows: Vec<usize>,
+    /// Columns of the original matrix that belong to the component, in
+    /// increasing order.
+    ///
+    /// Column `k` of [`Component::h`] is column `cols[k]` of the original
+    /// matrix.
+    pub cols: Vec<usize>,
+    /// Submatrix formed by the rows and columns of the component.
+    pub h: SparseMatrix,
+}
+
+impl SparseMatrix {
+    /// Returns the connected components of the graph associated to the matrix.
+    ///
+    /// Rows and columns that do not have any non-zero entries form components
+    /// by themselves. The components are sorted by their smallest column, and
+    /// the components formed by a single row without any non-zero entries go
+    /// last.
+    ///
+    /// # Examples
+    /// ```
+    /// # use ldpc_toolbox::sparse::SparseMatrix;
+    /// let mut h = SparseMatrix::new(2, 4);
+    /// h.insert_row(0, [0, 2].iter());
+    /// h.insert_row(1, [1, 3].iter());
+    /// let components = h.connected_components();
+    /// assert_eq!(components.len(), 2);
+    /// assert_eq!(components[1].rows, vec![1]);
+    /// assert_eq!(components[1].cols, vec![1, 3]);
+    /// assert!(components[1].h.contains(0, 1));
+    /// ```
+    pub fn connected_components(&self) -> Vec<Component> {
+        let mut row_visited = vec![false; self.num_rows()];
+        let mut col_visited = vec![false; self.num_cols()];
+        let roots = (0..self.num_cols())
+            .map(Node::Col)
+            .chain((0..self.num_rows()).map(Node::Row));
+        let mut components = Vec::new();
+        for root in roots {
+            let visited = match root {
+                Node::Row(n) => &mut row_visited[n],
+                Node::Col(n) => &mut col_visited[n],
+            };
+            if *visited {
+                continue;
+            }
+            *visited = true;
+            let mut rows = Vec::new();
+            let mut cols = Vec::new();
+            let mut to_visit = vec![root];
+            while let Some(node) = to_visit.pop() {
+                match node {
+                    Node::Row(n) => rows.push(n),
+                    Node::Col(n) => cols.push(n),
+                }
+                for x in node.iter(self) {
+                    let visited = match x {
+                        Node::Row(n) => &mut row_visited[n],
+                        Node::Col(n) => &mut col_visited[n],
+                    };
+                    if !*visited {
+                        *visited = true;
+                        to_visit.push(x);
+                    }
+                }
+            }
+            rows.sort_unstable();
+            cols.sort_unstable();
+            let h = self.submatrix(&rows, &cols);
+            components.push(Component { rows, cols, h });
+        }
+        components
+    }
+
+    /// Returns `true` if the graph associated to the matrix is connected.
+    ///
+    /// The graph is connected if it has a single connected component (see
+    /// [`SparseMatrix::connected_components`]).
+    pub fn is_connected(&self) -> bool {
+        self.connected_components().len() == 1
+    }
+
+    // Returns the submatrix formed by the given rows and columns. All the
+    // non-zero entries in the given rows must be in the given columns.
+    pub(super) fn submatrix(&self, rows: &[usize], cols: &[usize]) -> SparseMatrix {
+        let mut h = SparseMatrix::new(rows.len(), cols.len());
+        for (j, &row) in rows.iter().enumerate() {
+            for col in self.iter_row(row) {
+                let k = cols
+                    .binary_search(col)
+                    .expect("column not in the component");
+                h.insert(j, k);
+            }
+        }
+        h.metadata = self
+            .metadata
+            .as_ref()
+            .map(|metadata| cols.iter().map(|&col| metadata[col].clone()).collect());
+        h
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sparse::SparseMatrix;
+
+    #[test]
+    fn connected_components() {
+        let mut h = SparseMatrix::new(4, 7);
+        h.insert_row(0, [0, 3, 5].iter());
+        h.insert_row(1, [3, 6].iter());
+        h.insert_row(2, [1, 4].iter());
+        assert!(!h.is_connected());
+        let components = h.connected_components();
+        assert_eq!(components.len(), 4);
+
+        assert_eq!(components[0].rows, vec![0, 1]);
+        assert_eq!(components[0].cols, vec![0, 3, 5, 6]);
+        let mut expected = SparseMatrix::new(2, 4);
+        expected.insert_row(0, [0, 1, 2].iter());
+        expected.insert_row(1, [1, 3].iter());
+        assert_eq!(components[0].h, expected);
+
+        assert_eq!(components[1].rows, vec![2]);
+        assert_eq!(components[1].cols, vec![1, 4]);
+
+        // Column 2 and row 3 have no entries
+        assert_eq!(components[2].rows, Vec::<usize>::new());
+        assert_eq!(components[2].cols, vec![2]);
+        assert_eq!(components[3].rows, vec![3]);
+        assert_eq!(components[3].cols, Vec::<usize>::new());
+
+        for row in 0..3 {
+            h.insert(row, 2);
+        }
+        h.insert(3, 0);
+        assert!(h.is_connected());
+    }
+}
@@ -0,0 +1,308 @@
+use crate::sparse::{Node, SparseMatrix};
+use std::collections::BTreeMap;
+
+/// Cycle of the graph associated to a sparse matrix
+///
+/// A cycle of length `2 * L` alternates between `L` columns and `L` rows of the
+/// matrix. The cycle goes from column `cols[j]` to row `rows[j]` and then to
+/// column `cols[(j + 1) % L]`.
+///
+/// Cycles are given in a canonical form, so that each cycle has a unique
+/// representation: `cols[0]` is the smallest column of the cycle, and the
+/// direction in which the cycle is traversed is chosen so that `cols[1] <
+/// cols[L - 1]`, or `rows[0] < rows[1]` for cycles of length 4.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Cycle {
+    /// Columns visited by the cycle.
+    pub cols: Vec<usize>,
+    /// Rows visited by the cycle.
+    pub rows: Vec<usize>,
+}
+
+impl Cycle {
+    /// Returns the length of the cycle.
+    pub fn length(&self) -> usize {
+        2 * self.cols.len()
+    }
+}
+
+impl SparseMatrix {
+    /// Returns the number of cycles of each length.
+    ///
+    /// This function counts the cycles of the graph associated to the matrix
+    /// whose length is smaller or equal than `max_len`. The map returned
+    /// contains an entry for each even length between 4 and `max_len`,
+    /// including the lengths for which there are no cycles. The smallest length
+    /// with a non-zero count is the girth.
+    ///
+    /// The cycles are enumerated with a depth-first search, so the cost grows
+    /// exponentially with `max_len`. Typically `max_len` is not larger than
+    /// the girth plus 4.
+    ///
+    /// # Examples
+    /// ```
+    /// # use ldpc_toolbox::sparse::SparseMatrix;
+    /// // Complete bipartite graph K_{3,3}
+    /// let mut h = SparseMatrix::new(3, 3);
+    /// for j in 0..3 {
+    ///     h.insert_row(j, [0, 1, 2].iter());
+    /// }
+    /// let spectrum = h.cycle_spectrum(8);
+    /// assert_eq!(spectrum.into_iter().collect::<Vec<_>>(), [(4, 9), (6, 6), (8, 0)]);
+    /// ```
+    pub fn cycle_spectrum(&self, max_len: usize) -> BTreeMap<usize, u64> {
+        let mut spectrum = (4..=max_len)
+            .step_by(2)
+            .map(|len| (len, 0))
+            .collect::<BTreeMap<_, _>>();
+        CycleSearch::new(self, max_len).run(|cols, _| {
+            *spectrum.get_mut(&(2 * cols.len())).unwrap() += 1;
+        });
+        spectrum
+    }
+
+    /// Returns the cycles with length smaller or equal than `max_len`.
+    ///
+    /// This function enumerates the cycles counted by
+    /// [`SparseMatrix::cycle_spectrum`]. The cycles are sorted by length, and
+    /// the cycles of the same length are sorted by their smallest column.
+    ///
+    /// # Examples
+    /// ```
+    /// # use ldpc_toolbox::sparse::SparseMatrix;
+    /// let mut h = SparseMatrix::new(3, 4);
+    /// h.insert_row(0, [0, 1, 2].iter());
+    /// h.insert_row(1, [0, 1].iter());
+    /// h.insert_row(2, [2, 3].iter());
+    /// let cycles = h.cycles(8);
+    /// assert_eq!(cycles.len(), 1);
+    /// assert_eq!(cycles[0].length(), 4);
+    /// assert_eq!(cycles[0].cols, vec![0, 1]);
+    /// assert_eq!(cycles[0].rows, vec![0, 1]);
+    /// ```
+    pub fn cycles(&self, max_len: usize) -> Vec<Cycle> {
+        let mut cycles = Vec::new();
+        CycleSearch::new(self, max_len).run(|cols, rows| {
+            cycles.push(Cycle {
+                cols: cols.to_vec(),
+                rows: rows.to_vec(),
+            })
+        });
+        // The search finds the cycles in order of their smallest column, and
+        // the sort is stable
+        cycles.sort_by_key(Cycle::length);
+        cycles
+    }
+}
+
+// Depth-first search of the cycles of a graph.
+//
+// The cycles are searched from each column in turn, only visiting the columns
+// larger than the starting column, so that each cycle is found from its
+// smallest column. The search is pruned with the distances to the starting
+// column, which are computed with a breadth-first search.
+struct CycleSearch<'a> {
+    h: &'a SparseMatrix,
+    max_len: usize,
+    start: usize,
+    // Distances to the starting column, or usize::MAX if the distance is
+    // larger than max_len / 2
+    col_dist: Vec<usize>,
+    row_dist: Vec<usize>,
+    col_used: Vec<bool>,
+    row_used: Vec<bool>,
+    // Current path
+    cols: Vec<usize>,
+    rows: Vec<usize>,
+}
+
+impl CycleSearch<'_> {
+    fn new(h: &SparseMatrix, max_len: usize) -> CycleSearch<'_> {
+        CycleSearch {
+            h,
+            max_len,
+            start: 0,
+            col_dist: vec![usize::MAX; h.num_cols()],
+            row_dist: vec![usize::MAX; h.num_rows()],
+            col_used: vec![false; h.num_cols()],
+            row_used: vec![false; h.num_rows()],
+            cols: Vec::new(),
+            rows: Vec::new(),
+        }
+    }
+
+    fn run<F: FnMut(&[usize], &[usize])>(mut self, mut f: F) {
+        for start in 0..self.h.num_cols() {
+            self.start = start;
+            let reached = self.compute_distances();
+            self.cols.push(start);
+            self.col_used[start] = true;
+            self.visit_col(start, &mut f);
+            self.col_used[start] = false;
+            self.cols.pop();
+            for node in reached {
+                match node {
+                    Node::Row(n) => self.row_dist[n] = usize::MAX,
+                    Node::Col(n) => self.col_dist[n] = usize::MAX,
+                }
+            }
+        }
+    }
+
+    // Computes the distances to the starting column and returns the nodes that
+    // have been reached.
+    fn compute_distances(&mut self) -> Vec<Node> {
+        self.col_dist[self.start] = 0;
+        let mut reached = vec![Node::Col(self.start)];
+        let mut frontier = vec![Node::Col(self.start)];
+        for dist in 1..=self.max_len / 2 {
+            let mut next = Vec::new();
+            for node in frontier {
+                for x in node.iter(self.h) {
+                    let d = match x {
+                        Node::Row(n) => &mut self.row_dist[n],
+                        Node::Col(n) if n > self.start => &mut self.col_dist[n],
+                        Node::Col(_) => continue,
+                    };
+                    if *d == usize::MAX {
+                        *d = dist;
+                        reached.push(x);
+                        next.push(x);
+                    }
+                }
+            }
+            frontier = next;
+        }
+        reached
+    }
+
+    // Number of edges in the current path.
+    fn path_edges(&self) -> usize {
+        self.cols.len() + self.rows.len() - 1
+    }
+
+    fn visit_col<F: FnMut(&[usize], &[usize])>(&mut self, col: usize, f: &mut F) {
+        let edges = self.path_edges() + 1;
+        for &row in self.h.iter_col(col) {
+            if self.row_used[row] || edges.saturating_add(self.row_dist[row]) > self.max_len {
+                continue;
+            }
+            self.rows.push(row);
+            self.row_used[row] = true;
+            self.visit_row(row, f);
+            self.row_used[row] = false;
+            self.rows.pop();
+        }
+    }
+
+    fn visit_row<F: FnMut(&[usize], &[usize])>(&mut self, row: usize, f: &mut F) {
+        let edges = self.path_edges() + 1;
+        for &col in self.h.iter_row(row) {
+            if col == self.start {
+                let len = self.cols.len();
+                let canonical = match len {
+                    1 => false,
+                    2 => self.rows[0] < self.rows[1],
+                    _ => self.cols[1] < self.cols[len - 1],
+                };
+                if canonical {
+                    f(&self.cols, &self.rows);
+                }
+                continue;
+            }
+            if col < self.start
+                || self.col_used[col]
+                || edges.saturating_add(self.col_dist[col]) > self.max_len
+            {
+                continue;
+            }
+            self.cols.push(col);
+            self.col_used[col] = true;
+            self.visit_col(col, f);
+            self.col_used[col] = false;
+            self.cols.pop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::codes::{ccsds_tc::TcCode, wimax::WimaxCode, wimax::WimaxRate};
+
+    fn complete_bipartite(m: usize, n: usize) -> SparseMatrix {
+        let mut h = SparseMatrix::new(m, n);
+        for j in 0..m {
+            h.insert_row(j, 0..n);
+        }
+        h
+    }
+
+    #[test]
+    fn complete_bipartite_graphs() {
+        let spectrum = complete_bipartite(2, 3).cycle_spectrum(8);
+        assert_eq!(
+            spectrum.into_iter().collect::<Vec<_>>(),
+            [(4, 3), (6, 0), (8, 0)]
+        );
+        // The number of cycles of length 2L in K_{n,n} is
+        // binomial(n, L)^2 * L! * (L - 1)! / 2
+        let spectrum = complete_bipartite(4, 4).cycle_spectrum(10);
+        assert_eq!(
+            spectrum.into_iter().collect::<Vec<_>>(),
+            [(4, 36), (6, 96), (8, 72), (10, 0)]
+        );
+    }
+
+    #[test]
+    fn single_cycle() {
+        for len in 2..6 {
+            let mut h = SparseMatrix::new(len, len + 1);
+            for j in 0..len {
+                h.insert(j, j);
+                h.insert(j, (j + 1) % len);
+            }
+            let cycles = h.cycles(20);
+            assert_eq!(cycles.len(), 1);
+            assert_eq!(cycles[0].length(), 2 * len);
+            assert_eq!(cycles[0].cols, (0..len).collect::<Vec<_>>());
+            if len > 2 {
+                // For len = 2 the direction is given by the rows
+                assert!(cycles[0].cols[1] < cycles[0].cols[len - 1]);
+            }
+            assert!(h.cycles(2 * len - 2).is_empty());
+        }
+    }
+
+    #[test]
+    fn cycles_are_valid() {
+        let h = TcCode::N128.h();
+        let cycles = h.cycles(8);
+        let spectrum = h.cycle_spectrum(8);
+        assert_eq!(spectrum[&4], 0);
+        assert!(spectrum[&6] > 0);
+        assert_eq!(cycles.len() as u64, spectrum.values().sum::<u64>());
+        for cycle in &cycles {
+            let len = cycle.cols.len();
+            for j in 0..len {
+                assert!(h.contains(cycle.rows[j], cycle.cols[j]));
+                assert!(h.contains(cycle.rows[j], cycle.cols[(j + 1) % len]));
+            }
+            assert_eq!(cycle.cols.iter().min(), Some(&cycle.cols[0]));
+        }
+        // All the cycles are different
+        let distinct = cycles.iter().collect::<std::collections::HashSet<_>>();
+        assert_eq!(distinct.len(), cycles.len());
+    }
+
+    #[test]
+    fn matches_girth() {
+        let h = WimaxCode::new(WimaxRate::R1_2, 672).unwrap().h();
+        let girth = h.girth().unwrap();
+        let spectrum = h.cycle_spectrum(girth + 2);
+        assert_eq!(
+            spectrum.iter().find(|(_, &n)| n > 0).map(|(&l, _)| l),
+            Some(girth)
+        );
+    }
+}
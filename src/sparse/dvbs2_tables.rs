@@ -0,0 +1,191 @@
+use crate::sparse::{Col, Result, Row, SparseMatrix};
+
+/// Number of information bits that share a row of a DVB-S2 table.
+const DVBS2_GROUP_SIZE: usize = 360;
+
+impl SparseMatrix {
+    /// Constructs a matrix from a table in the format used in DVB-S2.
+    ///
+    /// This is the inverse of [`SparseMatrix::to_dvbs2_tables`]. The matrix has
+    /// `n` columns and `k = 360 * table.len()` information columns, and its
+    /// parity part is an accumulator. The addresses in each row of the table
+    /// can be given in any order.
+    ///
+    /// # Errors
+    /// This function returns an error if `k` is not smaller than `n`, if
+    /// `n - k` is not a multiple of 360, or if some address is not smaller
+    /// than `n - k`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use ldpc_toolbox::{codes::dvbs2::Code, sparse::SparseMatrix};
+    /// let h = Code::R1_2short.h();
+    /// let table = h.to_dvbs2_tables()?;
+    /// let h2 = SparseMatrix::from_dvbs2_tables(&table, h.num_cols())?;
+    /// // the entries can be stored in a different order
+    /// assert_eq!(h2.fingerprint(), h.fingerprint());
+    /// # Ok::<(), String>(())
+    /// ```
+    pub fn from_dvbs2_tables<T: AsRef<[usize]>>(table: &[T], n: usize) -> Result<SparseMatrix> {
+        let k = DVBS2_GROUP_SIZE * table.len();
+        if k >= n || !(n - k).is_multiple_of(DVBS2_GROUP_SIZE) {
+            return Err(format!(
+                "table with {} rows is not valid for codeword length {n} \
+                 (n - k and k must be non-zero multiples of {DVBS2_GROUP_SIZE})",
+                table.len()
+            ));
+        }
+        let m = n - k;
+        let q = m / DVBS2_GROUP_SIZE;
+        let mut h = SparseMatrix::new(m, n);
+        // information bits
+        for (t, addresses) in table.iter().enumerate() {
+            let addresses = addresses.as_ref();
+            if let Some(x) = addresses.iter().find(|&&x| x >= m) {
+                return Err(format!(
+                    "address {x} in table row {t} is not smaller than {m}"
+                ));
+            }
+            for w in 0..DVBS2_GROUP_SIZE {
+                h.insert_col(
+                    Col(DVBS2_GROUP_SIZE * t + w),
+                    addresses.iter().map(|x| (x + w * q) % m),
+                );
+            }
+        }
+        // parity bits
+        h.insert(Row(0), Col(k));
+        for j in 1..m {
+            h.insert(Row(j), Col(j + k));
+            h.insert(Row(j), Col(j + k - 1));
+        }
+        Ok(h)
+    }
+
+    /// Converts the matrix to the table format used in DVB-S2.
+    ///
+    /// The tables in Annexes B and C of EN 302 307 describe a parity check
+    /// matrix of size `m x n` whose last `m` columns form an accumulator (a
+    /// staircase with ones in the main diagonal and in the diagonal below it)
+    /// and whose first `k = n - m` columns are divided into groups of 360.
+    /// Row `t` of the table contains the rows of the non-zero entries of column
+    /// `360 * t` of the matrix. Column `360 * t + w` has its non-zero entries
+    /// in the rows `(x + w * q) % m`, where `x` runs over row `t` of the table
+    /// and `q = m / 360`.
+    ///
+    /// Each row of the returned table is sorted in increasing order. The tables
+    /// in the standard list the addresses in an arbitrary order, but the
+    /// order does not change the code.
+    ///
+    /// # Errors
+    /// This function returns an error if `m` or `k` are not multiples of 360,
+    /// if the last `m` columns do not form an accumulator, or if the first
+    /// `k` columns do not have the structure described above.
+    ///
+    /// # Examples
+    /// ```
+    /// # use ldpc_toolbox::codes::dvbs2::Code;
+    /// let table = Code::R1_2short.h().to_dvbs2_tables()?;
+    /// assert_eq!(table.len(), 20);
+    /// assert_eq!(table[19], vec![14, 3450, 7411]);
+    /// # Ok::<(), String>(())
+    /// ```
+    pub fn to_dvbs2_tables(&self) -> Result<Vec<Vec<usize>>> {
+        let m = self.num_rows();
+        let n = self.num_cols();
+        if m == 0
+            || m >= n
+            || !m.is_multiple_of(DVBS2_GROUP_SIZE)
+            || !(n - m).is_multiple_of(DVBS2_GROUP_SIZE)
+        {
+            return Err(format!(
+                "matrix size {m}x{n} is not valid for DVB-S2 tables \
+                 (n - k and k must be non-zero multiples of {DVBS2_GROUP_SIZE})"
+            ));
+        }
+        let k = n - m;
+        let q = m / DVBS2_GROUP_SIZE;
+        for j in 0..m {
+            // Column k + j has ones in rows j and j + 1
+            let rows = if j + 1 < m { &[j, j + 1][..] } else { &[j][..] };
+            if self.col_weight(k + j) != rows.len()
+                || rows.iter().any(|&r| !self.contains(r, k + j))
+            {
+                return Err(format!(
+                    "the parity part of the matrix is not an accumulator (column {})",
+                    k + j
+                ));
+            }
+        }
+        let mut table = Vec::with_capacity(k / DVBS2_GROUP_SIZE);
+        for t in 0..k / DVBS2_GROUP_SIZE {
+            let mut addresses = self
+                .iter_col(DVBS2_GROUP_SIZE * t)
+                .copied()
+                .collect::<Vec<_>>();
+            addresses.sort_unstable();
+            for w in 1..DVBS2_GROUP_SIZE {
+                let col = DVBS2_GROUP_SIZE * t + w;
+                if self.col_weight(col) != addresses.len()
+                    || addresses
+                        .iter()
+                        .any(|&x| !self.contains((x + w * q) % m, col))
+                {
+                    return Err(format!(
+                        "column {col} is not a cyclic shift of column {} by {}",
+                        DVBS2_GROUP_SIZE * t,
+                        w * q
+                    ));
+                }
+            }
+            table.push(addresses);
+        }
+        Ok(table)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn accumulator(m: usize, n: usize) -> SparseMatrix {
+        let mut h = SparseMatrix::new(m, n);
+        for j in 0..m {
+            h.insert(j, n - m + j);
+            if j + 1 < m {
+                h.insert(j + 1, n - m + j);
+            }
+        }
+        h
+    }
+
+    #[test]
+    fn conversion() {
+        let mut h = accumulator(720, 1080);
+        for w in 0..360 {
+            h.insert_col(w, [(3 + 2 * w) % 720, (10 + 2 * w) % 720].iter());
+        }
+        assert_eq!(h.to_dvbs2_tables(), Ok(vec![vec![3, 10]]));
+        let h2 = SparseMatrix::from_dvbs2_tables(&[[10, 3]], 1080).unwrap();
+        assert_eq!(h2.fingerprint(), h.fingerprint());
+    }
+
+    #[test]
+    fn errors() {
+        assert!(accumulator(700, 1080).to_dvbs2_tables().is_err());
+        // Missing one in the accumulator
+        let mut h = accumulator(360, 720);
+        h.remove(200, 720 - 360 + 199);
+        assert!(h.to_dvbs2_tables().is_err());
+        // Columns that are not cyclic shifts
+        let mut h = accumulator(360, 720);
+        for w in 0..360 {
+            h.insert(w % 7, w);
+        }
+        assert!(h.to_dvbs2_tables().is_err());
+
+        assert!(SparseMatrix::from_dvbs2_tables(&[[3, 10]], 1000).is_err());
+        assert!(SparseMatrix::from_dvbs2_tables(&[[3, 10]], 360).is_err());
+        assert!(SparseMatrix::from_dvbs2_tables(&[[3, 720]], 1080).is_err());
+    }
+}
@@ -1,7 +1,9 @@
 #[cfg(test)]
 mod tests {
+    use crate::progress::{Progress, Reporter};
     use crate::sparse::Node::{Col, Row};
     use crate::sparse::SparseMatrix;
+    use std::{sync::mpsc, time::Duration};
 
     #[test]
     fn test_local_girth_circulant() {
@@ -40,6 +42,32 @@ mod tests {
         assert_eq!(h.girth_with_max(100), None);
     }
 
+    #[test]
+    fn test_girth_with_progress() {
+        let mut h = SparseMatrix::new(20, 30);
+        for j in 0..5 {
+            h.insert(j, j);
+            h.insert(j, (j + 1) % 5);
+        }
+        let (tx, rx) = mpsc::channel();
+        let reporter = Reporter {
+            tx,
+            interval: Duration::ZERO,
+        };
+        assert_eq!(h.girth_with_progress(100, &reporter), Some(10));
+        let reports = rx.try_iter().collect::<Vec<_>>();
+        assert_eq!(reports.len(), h.num_cols() + 1);
+        assert_eq!(
+            reports[h.num_cols() - 1],
+            Progress::Update {
+                done: 29,
+                total: 30,
+                best: Some(10)
+            }
+        );
+        assert_eq!(reports.last(), Some(&Progress::Finished));
+    }
+
     #[test]
     fn test_girth_double_circulant() {
         let mut h = SparseMatrix::new(20, 30);
@@ -0,0 +1,300 @@
+use crate::sparse::{Result, SparseMatrix};
+use std::borrow::Borrow;
+use std::collections::BTreeSet;
+
+/// Role of a column of a parity check matrix
+///
+/// Labels are attached to the columns of a [`SparseMatrix`] through its
+/// [`ColumnMetadata`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum ColumnLabel {
+    /// The column corresponds to an information bit of a systematic encoding.
+    Systematic,
+    /// The column corresponds to a parity bit of a systematic encoding.
+    Parity,
+    /// The bit of the column is punctured (not transmitted).
+    Punctured,
+    /// The bit of the column is shortened (fixed to zero and not
+    /// transmitted).
+    Shortened,
+}
+
+impl std::str::FromStr for ColumnLabel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<ColumnLabel> {
+        Ok(match s {
+            "systematic" => ColumnLabel::Systematic,
+            "parity" => ColumnLabel::Parity,
+            "punctured" => ColumnLabel::Punctured,
+            "shortened" => ColumnLabel::Shortened,
+            _ => return Err(format!("invalid column label {s}")),
+        })
+    }
+}
+
+impl std::fmt::Display for ColumnLabel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                ColumnLabel::Systematic => "systematic",
+                ColumnLabel::Parity => "parity",
+                ColumnLabel::Punctured => "punctured",
+                ColumnLabel::Shortened => "shortened",
+            }
+        )
+    }
+}
+
+/// Metadata of a column of a parity check matrix
+///
+/// The column metadata of a [`SparseMatrix`] keeps track of the role of each
+/// bit and of its position in the matrix from which it was obtained. It is
+/// preserved and updated by [`SparseMatrix::permute_cols`],
+/// [`parity_to_systematic`](crate::systematic::parity_to_systematic) and the
+/// alist reading and writing functions.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ColumnMetadata {
+    /// Index of the column in the original matrix.
+    pub original_index: usize,
+    /// Labels of the column.
+    pub labels: BTreeSet<ColumnLabel>,
+}
+
+impl ColumnMetadata {
+    /// Creates the metadata of a column without labels
+    pub fn new(original_index: usize) -> ColumnMetadata {
+        ColumnMetadata {
+            original_index,
+            labels: BTreeSet::new(),
+        }
+    }
+}
+
+// Line that starts the column metadata section of an alist file.
+const ALIST_METADATA_HEADER: &str = "# column metadata";
+
+impl SparseMatrix {
+    /// Returns the column metadata of the matrix
+    ///
+    /// Returns `None` if the matrix does not keep column metadata.
+    pub fn column_metadata(&self) -> Option<&[ColumnMetadata]> {
+        self.metadata.as_deref()
+    }
+
+    /// Sets the column metadata of the matrix
+    ///
+    /// Passing `None` removes the column metadata.
+    ///
+    /// # Panics
+    /// Panics if the length of `metadata` is not the number of columns.
+    pub fn set_column_metadata(&mut self, metadata: Option<Vec<ColumnMetadata>>) {
+        if let Some(metadata) = &metadata {
+            assert_eq!(
+                metadata.len(),
+                self.num_cols(),
+                "column metadata length does not match number of columns"
+            );
+        }
+        self.metadata = metadata;
+    }
+
+    /// Starts keeping column metadata
+    ///
+    /// If the matrix does not keep column metadata, each column is given
+    /// metadata with its current index as original index and no labels. Does
+    /// nothing if the matrix already keeps column metadata.
+    ///
+    /// # Examples
+    /// ```
+    /// # use ldpc_toolbox::sparse::{ColumnLabel, SparseMatrix};
+    /// let mut h = SparseMatrix::new(2, 4);
+    /// h.init_column_metadata();
+    /// h.label_cols([3].iter(), ColumnLabel::Punctured);
+    /// let h = h.permute_cols(&[3, 0, 1, 2])?;
+    /// let metadata = h.column_metadata().unwrap();
+    /// assert_eq!(metadata[0].original_index, 3);
+    /// assert_eq!(h.cols_with_label(ColumnLabel::Punctured), vec![0]);
+    /// # Ok::<(), String>(())
+    /// ```
+    pub fn init_column_metadata(&mut self) {
+        if self.metadata.is_none() {
+            self.metadata = Some((0..self.num_cols()).map(ColumnMetadata::new).collect());
+        }
+    }
+
+    /// Adds a label to some columns
+    ///
+    /// Column metadata is initialized with
+    /// [`SparseMatrix::init_column_metadata`] if the matrix does not keep it.
+    pub fn label_cols<T, S>(&mut self, cols: T, label: ColumnLabel)
+    where
+        T: Iterator<Item = S>,
+        S: Borrow<usize>,
+    {
+        self.init_column_metadata();
+        let metadata = self.metadata.as_mut().unwrap();
+        for col in cols {
+            metadata[*col.borrow()].labels.insert(label);
+        }
+    }
+
+    /// Removes a label from all the columns
+    ///
+    /// Does nothing if the matrix does not keep column metadata.
+    pub fn remove_label(&mut self, label: ColumnLabel) {
+        for metadata in self.metadata.iter_mut().flatten() {
+            metadata.labels.remove(&label);
+        }
+    }
+
+    /// Returns the columns that have a label
+    ///
+    /// The columns are returned in increasing order. If the matrix does not
+    /// keep column metadata, no columns are returned.
+    pub fn cols_with_label(&self, label: ColumnLabel) -> Vec<usize> {
+        self.metadata
+            .iter()
+            .flatten()
+            .enumerate()
+            .filter_map(|(col, metadata)| metadata.labels.contains(&label).then_some(col))
+            .collect()
+    }
+
+    /// Returns a matrix with permuted columns
+    ///
+    /// The column `j` of the returned matrix is the column `permutation[j]` of
+    /// this matrix. The column metadata, if any, is permuted in the same way.
+    ///
+    /// # Errors
+    /// Returns an error if `permutation` is not a permutation of the column
+    /// indices.
+    pub fn permute_cols(&self, permutation: &[usize]) -> Result<SparseMatrix> {
+        let mut seen = vec![false; self.num_cols()];
+        if permutation.len() != self.num_cols()
+            || !permutation
+                .iter()
+                .all(|&col| col < seen.len() && !std::mem::replace(&mut seen[col], true))
+        {
+            return Err(String::from("invalid column permutation"));
+        }
+        let mut h = SparseMatrix::new(self.num_rows(), self.num_cols());
+        for (j, &col) in permutation.iter().enumerate() {
+            for &row in self.iter_col(col) {
+                h.rows[row].push(j);
+                h.cols[j].push(row);
+            }
+        }
+        h.metadata = self.metadata.as_ref().map(|metadata| {
+            permutation
+                .iter()
+                .map(|&col| metadata[col].clone())
+                .collect()
+        });
+        Ok(h)
+    }
+
+    // Writes the column metadata section of an alist file. Original indices
+    // are always zero-based.
+    pub(super) fn write_alist_metadata<W: std::fmt::Write>(&self, w: &mut W) -> std::fmt::Result {
+        let Some(metadata) = &self.metadata else {
+            return Ok(());
+        };
+        writeln!(w, "{ALIST_METADATA_HEADER}")?;
+        for column in metadata {
+            write!(w, "{} ", column.original_index)?;
+            if column.labels.is_empty() {
+                write!(w, "-")?;
+            }
+            for (j, label) in column.labels.iter().enumerate() {
+                if j != 0 {
+                    write!(w, ",")?;
+                }
+                write!(w, "{label}")?;
+            }
+            writeln!(w)?;
+        }
+        Ok(())
+    }
+
+    // Parses the column metadata section from the lines of an alist file that
+    // follow the columns section. Nothing is done if there is no such section.
+    pub(super) fn read_alist_metadata<'a>(
+        &mut self,
+        mut lines: impl Iterator<Item = &'a str>,
+    ) -> Result<()> {
+        if !lines.any(|line| line.trim() == ALIST_METADATA_HEADER) {
+            return Ok(());
+        }
+        let metadata = (0..self.num_cols())
+            .map(|_| {
+                let line = lines
+                    .next()
+                    .ok_or_else(|| String::from("alist column metadata is too short"))?;
+                let mut fields = line.split_whitespace();
+                let original_index = fields
+                    .next()
+                    .ok_or_else(|| String::from("alist column metadata line is empty"))?
+                    .parse()
+                    .map_err(|_| String::from("column original index is not a number"))?;
+                let labels = match fields.next() {
+                    None | Some("-") => BTreeSet::new(),
+                    Some(labels) => labels.split(',').map(str::parse).collect::<Result<_>>()?,
+                };
+                Ok(ColumnMetadata {
+                    original_index,
+                    labels,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        self.metadata = Some(metadata);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn permute_cols() {
+        let mut h = SparseMatrix::new(2, 3);
+        h.insert_row(0, [0, 1].iter());
+        h.insert_row(1, [2].iter());
+        let p = h.permute_cols(&[2, 0, 1]).unwrap();
+        assert!(p.contains(0, 1) && p.contains(0, 2) && p.contains(1, 0));
+        assert_eq!(p.validate(), Ok(()));
+        assert_eq!(p.column_metadata(), None);
+        assert!(h.permute_cols(&[0, 1]).is_err());
+        assert!(h.permute_cols(&[0, 1, 1]).is_err());
+        assert!(h.permute_cols(&[0, 1, 3]).is_err());
+    }
+
+    #[test]
+    fn alist_metadata() {
+        let mut h = SparseMatrix::new(2, 3);
+        h.insert_row(0, [0, 1].iter());
+        h.insert_row(1, [1, 2].iter());
+        h.label_cols([0, 1].iter(), ColumnLabel::Systematic);
+        h.label_cols([1].iter(), ColumnLabel::Punctured);
+        let h = h.permute_cols(&[1, 2, 0]).unwrap();
+        let alist = h.alist();
+        assert!(alist.ends_with("# column metadata\n1 systematic,punctured\n2 -\n0 systematic\n"));
+        let h2 = SparseMatrix::from_alist(&alist).unwrap();
+        assert_eq!(h2.column_metadata(), h.column_metadata());
+        assert_eq!(h2.cols_with_label(ColumnLabel::Systematic), vec![0, 2]);
+        // an alist without metadata has no metadata
+        let mut h3 = h2.clone();
+        h3.set_column_metadata(None);
+        assert_eq!(
+            SparseMatrix::from_alist(&h3.alist())
+                .unwrap()
+                .column_metadata(),
+            None
+        );
+        assert!(SparseMatrix::from_alist(&alist.replace("2 -", "2 unknown")).is_err());
+    }
+}
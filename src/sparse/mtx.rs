@@ -0,0 +1,192 @@
+use crate::sparse::{Result, SparseMatrix};
+
+/// Header line of the MatrixMarket files written by
+/// [`SparseMatrix::write_mtx`].
+const MTX_HEADER: &str = "%%MatrixMarket matrix coordinate pattern general";
+
+impl SparseMatrix {
+    /// Writes the matrix in MatrixMarket format to a writer.
+    ///
+    /// The matrix is written as a `coordinate pattern general` MatrixMarket
+    /// file, which lists the one-based row and column of each non-zero entry.
+    /// The entries are listed by columns, and by increasing row within each
+    /// column.
+    ///
+    /// # Errors
+    /// If a call to `write!()` returns an error, this function returns
+    /// such an error.
+    pub fn write_mtx<W: std::fmt::Write>(&self, w: &mut W) -> std::fmt::Result {
+        writeln!(w, "{MTX_HEADER}")?;
+        let nnz = self.cols.iter().map(|col| col.len()).sum::<usize>();
+        writeln!(w, "{} {} {}", self.num_rows(), self.num_cols(), nnz)?;
+        for (col, rows) in self.cols.iter().enumerate() {
+            let mut rows = rows.clone();
+            rows.sort_unstable();
+            for row in rows {
+                writeln!(w, "{} {}", row + 1, col + 1)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns a [`String`] with the MatrixMarket representation of the
+    /// matrix.
+    ///
+    /// See [`SparseMatrix::write_mtx`].
+    ///
+    /// # Examples
+    /// ```
+    /// # use ldpc_toolbox::sparse::SparseMatrix;
+    /// let mut h = SparseMatrix::new(2, 3);
+    /// h.insert(0, 1);
+    /// h.insert(1, 2);
+    /// assert_eq!(
+    ///     h.mtx(),
+    ///     "%%MatrixMarket matrix coordinate pattern general\n2 3 2\n1 2\n2 3\n"
+    /// );
+    /// ```
+    pub fn mtx(&self) -> String {
+        let mut s = String::new();
+        self.write_mtx(&mut s).unwrap();
+        s
+    }
+
+    /// Constructs and returns a sparse matrix from its MatrixMarket
+    /// representation.
+    ///
+    /// This function reads `coordinate` MatrixMarket files with the `general`
+    /// symmetry and any of the `pattern`, `integer` and `real` fields. Entries
+    /// whose value is zero are ignored. Comment lines, which start with `%`,
+    /// and blank lines are skipped.
+    ///
+    /// # Errors
+    /// `mtx` should hold a valid MatrixMarket representation of the kind
+    /// described above. If an error is found while parsing `mtx`, a `String`
+    /// describing the error will be returned.
+    ///
+    /// # Examples
+    /// ```
+    /// # use ldpc_toolbox::sparse::SparseMatrix;
+    /// let mtx = "%%MatrixMarket matrix coordinate real general\n\
+    ///            % a comment\n\
+    ///            2 3 3\n\
+    ///            1 2 1.0\n\
+    ///            2 3 1.0\n\
+    ///            2 1 0.0\n";
+    /// let h = SparseMatrix::from_mtx(mtx)?;
+    /// assert_eq!(h.num_rows(), 2);
+    /// assert_eq!(h.num_cols(), 3);
+    /// assert!(h.contains(0, 1));
+    /// assert!(h.contains(1, 2));
+    /// assert!(!h.contains(1, 0));
+    /// # Ok::<(), String>(())
+    /// ```
+    pub fn from_mtx(mtx: &str) -> Result<SparseMatrix> {
+        let mut lines = mtx.lines();
+        let header = lines
+            .next()
+            .ok_or_else(|| String::from("MatrixMarket header not found"))?;
+        let header = header.to_lowercase();
+        let header = header.split_whitespace().collect::<Vec<_>>();
+        let field = match header[..] {
+            ["%%matrixmarket", "matrix", "coordinate", field, "general"] => field,
+            ["%%matrixmarket", "matrix", "coordinate", _, symmetry] => {
+                return Err(format!("unsupported MatrixMarket symmetry {symmetry}"))
+            }
+            ["%%matrixmarket", "matrix", format, _, _] => {
+                return Err(format!("unsupported MatrixMarket format {format}"))
+            }
+            _ => return Err(String::from("invalid MatrixMarket header")),
+        };
+        let has_value = match field {
+            "pattern" => false,
+            "integer" | "real" => true,
+            _ => return Err(format!("unsupported MatrixMarket field {field}")),
+        };
+        let mut lines = lines.filter(|line| {
+            let line = line.trim_start();
+            !line.is_empty() && !line.starts_with('%')
+        });
+        let sizes = lines
+            .next()
+            .ok_or_else(|| String::from("MatrixMarket size line not found"))?;
+        let sizes = sizes
+            .split_whitespace()
+            .map(|x| x.parse::<usize>())
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|_| String::from("MatrixMarket size is not a number"))?;
+        let [nrows, ncols, nnz] = sizes[..] else {
+            return Err(String::from(
+                "MatrixMarket size line does not contain three elements",
+            ));
+        };
+        let mut h = SparseMatrix::new(nrows, ncols);
+        let mut num_entries = 0;
+        for line in lines {
+            let mut entry = line.split_whitespace();
+            let mut index = |name: &str, size: usize| -> Result<usize> {
+                let x = entry
+                    .next()
+                    .ok_or_else(|| format!("MatrixMarket entry does not contain a {name}"))?
+                    .parse::<usize>()
+                    .map_err(|_| format!("{name} value is not a number"))?;
+                if x == 0 || x > size {
+                    return Err(format!("{name} {x} is out of range"));
+                }
+                Ok(x - 1)
+            };
+            let row = index("row", nrows)?;
+            let col = index("column", ncols)?;
+            num_entries += 1;
+            if has_value {
+                let value = entry
+                    .next()
+                    .ok_or_else(|| String::from("MatrixMarket entry does not contain a value"))?
+                    .parse::<f64>()
+                    .map_err(|_| String::from("entry value is not a number"))?;
+                if value == 0.0 {
+                    continue;
+                }
+            }
+            h.insert(row, col);
+        }
+        if num_entries != nnz {
+            return Err(format!(
+                "MatrixMarket file contains {num_entries} entries, but {nnz} were expected"
+            ));
+        }
+        Ok(h)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::codes::ccsds::{AR4JACode, AR4JAInfoSize, AR4JARate};
+
+    #[test]
+    fn round_trip() {
+        let h = AR4JACode::new(AR4JARate::R1_2, AR4JAInfoSize::K1024).h();
+        let h2 = SparseMatrix::from_mtx(&h.mtx()).unwrap();
+        assert_eq!(h2.fingerprint(), h.fingerprint());
+        assert_eq!(h2.alist(), h.alist());
+    }
+
+    #[test]
+    fn errors() {
+        let header = "%%MatrixMarket matrix coordinate pattern general\n";
+        assert!(SparseMatrix::from_mtx("2 3 1\n1 1\n").is_err());
+        assert!(SparseMatrix::from_mtx(
+            "%%MatrixMarket matrix coordinate pattern symmetric\n2 2 1\n1 1\n"
+        )
+        .is_err());
+        assert!(SparseMatrix::from_mtx("%%MatrixMarket matrix array real general\n2 2\n").is_err());
+        assert!(SparseMatrix::from_mtx(&format!("{header}2 3\n")).is_err());
+        assert!(SparseMatrix::from_mtx(&format!("{header}2 3 1\n3 1\n")).is_err());
+        assert!(SparseMatrix::from_mtx(&format!("{header}2 3 2\n1 1\n")).is_err());
+        assert_eq!(
+            SparseMatrix::from_mtx(&format!("{header}2 3 1\n\n2 3\n")).map(|h| h.contains(1, 2)),
+            Ok(true)
+        );
+    }
+}
@@ -0,0 +1,295 @@
+use crate::sparse::{Col, Result, Row, SparseMatrix};
+
+/// Quasi-cyclic base matrix
+///
+/// A quasi-cyclic (QC) matrix is formed by square blocks of size `z`, each of
+/// which is either zero or a sum of circulant permutation matrices. The base
+/// matrix holds the shift exponents of each block, and it can be lifted to a
+/// [`SparseMatrix`] for any lifting size `z` with [`QcMatrix::lift`]. The
+/// circulant permutation matrix with shift `s` has its non-zero entries at the
+/// positions `(j, (j + s) % z)`.
+///
+/// The shifts of a block are stored as given, including repeated shifts. Since
+/// the circulant permutation matrices of a block are added over GF(2), any two
+/// shifts that are congruent modulo the lifting size cancel each other when
+/// the matrix is lifted, whether they are equal or not.
+///
+/// # Examples
+/// ```
+/// # use ldpc_toolbox::sparse::QcMatrix;
+/// let qc = QcMatrix::from_exponents("0 1 -1\n2 -1 0+1")?;
+/// assert_eq!(qc.num_row_blocks(), 2);
+/// assert_eq!(qc.num_col_blocks(), 3);
+/// assert_eq!(qc.shifts(1, 2), &[0, 1]);
+/// let h = qc.lift(4);
+/// assert_eq!(h.num_rows(), 8);
+/// assert_eq!(h.num_cols(), 12);
+/// assert!(h.contains(0, 4 + 1));
+/// assert_eq!(QcMatrix::from_sparse(&h, 4)?, qc);
+/// # Ok::<(), String>(())
+/// ```
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct QcMatrix {
+    // Shifts of each block, sorted in increasing order and possibly repeated. A
+    // block without shifts is a zero block.
+    blocks: Vec<Vec<Vec<usize>>>,
+}
+
+impl QcMatrix {
+    /// Create a new QC base matrix of a given size
+    ///
+    /// The size is given in blocks. All the blocks are inizialized to zero.
+    pub fn new(row_blocks: usize, col_blocks: usize) -> QcMatrix {
+        QcMatrix {
+            blocks: vec![vec![Vec::new(); col_blocks]; row_blocks],
+        }
+    }
+
+    /// Returns the number of block rows of the matrix
+    pub fn num_row_blocks(&self) -> usize {
+        self.blocks.len()
+    }
+
+    /// Returns the number of block columns of the matrix
+    pub fn num_col_blocks(&self) -> usize {
+        self.blocks.first().map_or(0, |row| row.len())
+    }
+
+    /// Returns the shifts of a block
+    ///
+    /// The shifts are sorted in increasing order, and they can contain repeated
+    /// shifts. A zero block has no shifts.
+    pub fn shifts(&self, row: usize, col: usize) -> &[usize] {
+        &self.blocks[row][col]
+    }
+
+    /// Sets the shifts of a block
+    ///
+    /// The shifts are stored in increasing order. Repeated shifts are kept, so
+    /// that they cancel each other when the matrix is lifted.
+    pub fn set_shifts<T, S>(&mut self, row: usize, col: usize, shifts: T)
+    where
+        T: Iterator<Item = S>,
+        S: std::borrow::Borrow<usize>,
+    {
+        let block = &mut self.blocks[row][col];
+        block.clear();
+        block.extend(shifts.map(|s| *s.borrow()));
+        block.sort_unstable();
+    }
+
+    /// Returns the largest shift of the matrix
+    ///
+    /// Returns `None` if all the blocks are zero. The lifting size should be
+    /// larger than the largest shift, unless the shifts are meant to be
+    /// reduced modulo the lifting size, as in 5G NR.
+    pub fn max_shift(&self) -> Option<usize> {
+        self.blocks.iter().flatten().flatten().copied().max()
+    }
+
+    /// Lifts the base matrix to a sparse matrix
+    ///
+    /// Each block is replaced by a sum of `z x z` circulant permutation
+    /// matrices with the shifts of the block reduced modulo `z`. The sum is
+    /// computed over GF(2), so two shifts that are congruent modulo `z`
+    /// cancel each other.
+    ///
+    /// # Panics
+    /// Panics if `z` is zero.
+    pub fn lift(&self, z: usize) -> SparseMatrix {
+        assert!(z > 0, "lifting size is zero");
+        let mut h = SparseMatrix::new(self.num_row_blocks() * z, self.num_col_blocks() * z);
+        for (row, blocks) in self.blocks.iter().enumerate() {
+            for (col, shifts) in blocks.iter().enumerate() {
+                for &shift in shifts {
+                    for j in 0..z {
+                        h.toggle(Row(row * z + j), Col(col * z + (j + shift) % z));
+                    }
+                }
+            }
+        }
+        h
+    }
+
+    /// Recovers the QC structure of a sparse matrix
+    ///
+    /// The matrix is split into blocks of size `z x z`, and the shifts of each
+    /// block are obtained from its first row. The resulting base matrix has
+    /// all its shifts smaller than `z`, and lifting it with `z` gives back
+    /// `h`.
+    ///
+    /// # Errors
+    /// Returns an error if the dimensions of `h` are not multiples of `z` or
+    /// if some block is not a sum of circulant permutation matrices.
+    pub fn from_sparse(h: &SparseMatrix, z: usize) -> Result<QcMatrix> {
+        if z == 0 || !h.num_rows().is_multiple_of(z) || !h.num_cols().is_multiple_of(z) {
+            return Err(format!(
+                "matrix of size {} x {} cannot be split into blocks of size {z}",
+                h.num_rows(),
+                h.num_cols()
+            ));
+        }
+        let mut qc = QcMatrix::new(h.num_rows() / z, h.num_cols() / z);
+        for (row, blocks) in qc.blocks.iter_mut().enumerate() {
+            for &col in h.iter_row(row * z) {
+                blocks[col / z].push(col % z);
+            }
+            for shifts in blocks.iter_mut() {
+                shifts.sort_unstable();
+            }
+        }
+        for row in 0..h.num_rows() {
+            let j = row % z;
+            let mut expected = qc.blocks[row / z]
+                .iter()
+                .enumerate()
+                .flat_map(|(col, shifts)| shifts.iter().map(move |&s| col * z + (j + s) % z))
+                .collect::<Vec<_>>();
+            let mut found = h.iter_row(row).copied().collect::<Vec<_>>();
+            expected.sort_unstable();
+            found.sort_unstable();
+            if expected != found {
+                return Err(format!(
+                    "row {row} does not follow the quasi-cyclic structure of size {z}"
+                ));
+            }
+        }
+        Ok(qc)
+    }
+
+    /// Writes the exponents of the base matrix into a [`Write`](std::fmt::Write)
+    ///
+    /// The format is described in [`QcMatrix::from_exponents`].
+    pub fn write_exponents<W: std::fmt::Write>(&self, w: &mut W) -> std::fmt::Result {
+        for blocks in &self.blocks {
+            for (col, shifts) in blocks.iter().enumerate() {
+                if col != 0 {
+                    write!(w, " ")?;
+                }
+                if shifts.is_empty() {
+                    write!(w, "-1")?;
+                }
+                for (j, shift) in shifts.iter().enumerate() {
+                    if j != 0 {
+                        write!(w, "+")?;
+                    }
+                    write!(w, "{shift}")?;
+                }
+            }
+            writeln!(w)?;
+        }
+        Ok(())
+    }
+
+    /// Returns a [`String`] with the exponents of the base matrix
+    ///
+    /// The format is described in [`QcMatrix::from_exponents`].
+    pub fn exponents(&self) -> String {
+        let mut s = String::new();
+        self.write_exponents(&mut s).unwrap();
+        s
+    }
+
+    /// Constructs a base matrix from its exponents
+    ///
+    /// The exponents are given as one line per block row, with the blocks
+    /// separated by whitespace. Each block is either `-1`, which denotes a zero
+    /// block, or a shift, or several shifts separated by `+`, which denote a
+    /// sum of circulant permutation matrices. Repeated shifts are kept, as in
+    /// [`QcMatrix::set_shifts`]. Empty lines and lines starting with `#` are
+    /// ignored.
+    ///
+    /// # Errors
+    /// If an error is found while parsing `exponents`, a `String` describing
+    /// the error will be returned.
+    pub fn from_exponents(exponents: &str) -> Result<QcMatrix> {
+        let blocks = exponents
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| {
+                line.split_whitespace()
+                    .map(|block| {
+                        if block == "-1" {
+                            return Ok(Vec::new());
+                        }
+                        let mut shifts = block
+                            .split('+')
+                            .map(|s| {
+                                s.parse::<usize>()
+                                    .map_err(|_| format!("invalid exponent {block}"))
+                            })
+                            .collect::<Result<Vec<_>>>()?;
+                        shifts.sort_unstable();
+                        Ok(shifts)
+                    })
+                    .collect::<Result<Vec<_>>>()
+            })
+            .collect::<Result<Vec<_>>>()?;
+        if let Some(first) = blocks.first() {
+            if let Some(row) = blocks.iter().position(|row| row.len() != first.len()) {
+                return Err(format!(
+                    "block row {row} has {} blocks, but block row 0 has {}",
+                    blocks[row].len(),
+                    first.len()
+                ));
+            }
+        }
+        Ok(QcMatrix { blocks })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exponents() {
+        let exponents = "0 -1 3+5\n-1 2 1\n";
+        let qc = QcMatrix::from_exponents(exponents).unwrap();
+        assert_eq!(qc.exponents(), exponents);
+        assert_eq!(qc.max_shift(), Some(5));
+        assert_eq!(
+            QcMatrix::from_exponents("# comment\n\n5+3 -1\n")
+                .unwrap()
+                .shifts(0, 0),
+            &[3, 5]
+        );
+        assert!(QcMatrix::from_exponents("0 1\n2").is_err());
+        assert!(QcMatrix::from_exponents("0 x").is_err());
+        assert!(QcMatrix::from_exponents("0 -2").is_err());
+    }
+
+    #[test]
+    fn lift_and_recover() {
+        let mut qc = QcMatrix::new(2, 3);
+        qc.set_shifts(0, 0, [2, 0].iter());
+        qc.set_shifts(0, 2, [4].iter());
+        qc.set_shifts(1, 1, [1].iter());
+        qc.set_shifts(1, 2, [0].iter());
+        let z = 5;
+        let h = qc.lift(z);
+        assert_eq!(h.validate(), Ok(()));
+        assert_eq!(h.iter_all().count(), 5 * z);
+        assert!(h.contains(z - 1, 1));
+        assert!(h.contains(z - 1, 2 * z + 3));
+        assert_eq!(QcMatrix::from_sparse(&h, z), Ok(qc.clone()));
+        // shifts congruent modulo z cancel, including repeated shifts
+        qc.set_shifts(1, 1, [1, 1 + z].iter());
+        assert_eq!(qc.lift(z).iter_all().count(), 4 * z);
+        qc.set_shifts(1, 1, [1, 1].iter());
+        assert_eq!(qc.shifts(1, 1), &[1, 1]);
+        assert_eq!(qc.lift(z).iter_all().count(), 4 * z);
+        let qc = QcMatrix::from_exponents("3+3 2").unwrap();
+        assert_eq!(qc.exponents(), "3+3 2\n");
+        assert_eq!(qc.lift(z).iter_all().count(), z);
+    }
+
+    #[test]
+    fn recover_not_qc() {
+        let mut h = QcMatrix::from_exponents("0 1").unwrap().lift(3);
+        assert!(QcMatrix::from_sparse(&h, 2).is_err());
+        h.toggle(2, 0);
+        assert!(QcMatrix::from_sparse(&h, 3).is_err());
+    }
+}
@@ -0,0 +1,412 @@
+use crate::sparse::{ColumnLabel, SparseMatrix};
+
+/// Chain of degree-2 columns
+///
+/// In the graph associated to a sparse matrix, a chain is a path formed by
+/// columns of weight 2 joined through rows that contain exactly two columns of
+/// weight 2. Chains appear in codes with an accumulator structure, such as the
+/// staircase parity part of the DVB-S2 codes.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct DegreeTwoChain {
+    /// Columns of the chain, in path order.
+    pub cols: Vec<usize>,
+    /// Rows of the chain, in path order.
+    ///
+    /// Column `cols[j]` joins rows `rows[j]` and `rows[(j + 1) % rows.len()]`.
+    /// If the chain is closed, the number of rows is equal to the number of
+    /// columns. Otherwise there is one more row than columns.
+    pub rows: Vec<usize>,
+}
+
+impl DegreeTwoChain {
+    /// Returns `true` if the chain is closed (it forms a cycle).
+    pub fn is_closed(&self) -> bool {
+        self.rows.len() == self.cols.len()
+    }
+}
+
+/// Options for the reduction of a parity check matrix
+///
+/// See [`SparseMatrix::reduce`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct ReductionOptions {
+    /// Eliminate columns of weight 1.
+    pub degree_one: bool,
+    /// Eliminate columns of weight 2.
+    pub degree_two: bool,
+    /// Only eliminate columns with the [`ColumnLabel::Punctured`] label in the
+    /// [column metadata](crate::sparse::ColumnMetadata) of the matrix.
+    pub only_punctured: bool,
+}
+
+impl Default for ReductionOptions {
+    fn default() -> ReductionOptions {
+        ReductionOptions {
+            degree_one: true,
+            degree_two: true,
+            only_punctured: false,
+        }
+    }
+}
+
+/// Column eliminated by a reduction
+///
+/// The value of the column in a codeword is the sum of the values of the
+/// columns in its equation.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct EliminatedCol {
+    /// Column of the original matrix.
+    pub col: usize,
+    /// Columns of the original matrix whose sum gives the value of the
+    /// eliminated column.
+    ///
+    /// These columns are either kept in the reduced matrix or eliminated
+    /// after this column.
+    pub equation: Vec<usize>,
+}
+
+/// Reduced parity check matrix
+///
+/// A reduction is obtained with [`SparseMatrix::reduce`]. It contains a parity
+/// check matrix defined on a subset of the columns of the original matrix,
+/// whose codewords are the restrictions of the codewords of the original
+/// matrix to these columns, together with the equations that recover the
+/// eliminated columns.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Reduction {
+    /// Rows of the original matrix kept in the reduction, in increasing order.
+    ///
+    /// Row `j` of [`Reduction::h`] is the sum of row `rows[j]` of the original
+    /// matrix and of the rows that have been merged into it.
+    pub rows: Vec<usize>,
+    /// Columns of the original matrix kept in the reduction, in increasing
+    /// order.
+    ///
+    /// Column `k` of [`Reduction::h`] is column `cols[k]` of the original
+    /// matrix.
+    pub cols: Vec<usize>,
+    /// Eliminated columns, in order of elimination.
+    pub eliminated: Vec<EliminatedCol>,
+    /// Reduced parity check matrix.
+    pub h: SparseMatrix,
+}
+
+impl Reduction {
+    /// Restricts a vector of the original matrix to the reduced matrix.
+    ///
+    /// This can be used with codewords or with LLRs. The values of the
+    /// eliminated columns are dropped.
+    pub fn restrict<T: Copy>(&self, values: &[T]) -> Vec<T> {
+        self.cols.iter().map(|&col| values[col]).collect()
+    }
+
+    /// Expands a codeword of the reduced matrix to a codeword of the original
+    /// matrix.
+    ///
+    /// The values of the eliminated columns are computed with their equations.
+    /// The codeword is given with a `u8` per bit.
+    pub fn expand(&self, codeword: &[u8]) -> Vec<u8> {
+        assert_eq!(codeword.len(), self.cols.len());
+        let num_cols = self.cols.len() + self.eliminated.len();
+        let mut expanded = vec![0; num_cols];
+        for (&col, &bit) in self.cols.iter().zip(codeword) {
+            expanded[col] = bit;
+        }
+        for eliminated in self.eliminated.iter().rev() {
+            expanded[eliminated.col] = eliminated
+                .equation
+                .iter()
+                .fold(0, |acc, &col| acc ^ expanded[col]);
+        }
+        expanded
+    }
+}
+
+impl SparseMatrix {
+    /// Returns the maximal chains of degree-2 columns.
+    ///
+    /// See [`DegreeTwoChain`]. The chains are sorted by their smallest column.
+    ///
+    /// # Examples
+    /// ```
+    /// # use ldpc_toolbox::sparse::SparseMatrix;
+    /// // staircase
+    /// let mut h = SparseMatrix::new(3, 5);
+    /// h.insert_col(0, [0, 1, 2].iter());
+    /// h.insert_col(1, [0, 1, 2].iter());
+    /// h.insert_col(2, [0, 1].iter());
+    /// h.insert_col(3, [1, 2].iter());
+    /// h.insert_col(4, [2].iter());
+    /// let chains = h.degree_two_chains();
+    /// assert_eq!(chains.len(), 1);
+    /// assert_eq!(chains[0].cols, vec![2, 3]);
+    /// assert_eq!(chains[0].rows, vec![0, 1, 2]);
+    /// ```
+    pub fn degree_two_chains(&self) -> Vec<DegreeTwoChain> {
+        let is_degree_two = |col: usize| self.col_weight(col) == 2;
+        // Returns the other degree-2 column in a row, if the row contains
+        // exactly two degree-2 columns.
+        let next_col = |row: usize, col: usize| {
+            let mut cols = self.iter_row(row).copied().filter(|&c| is_degree_two(c));
+            match (cols.next(), cols.next(), cols.next()) {
+                (Some(a), Some(b), None) => Some(if a == col { b } else { a }),
+                _ => None,
+            }
+        };
+        let other_row = |col: usize, row: usize| {
+            *self
+                .iter_col(col)
+                .find(|&&r| r != row)
+                .expect("degree-2 column has a single row")
+        };
+        let mut visited = vec![false; self.num_cols()];
+        let mut chains = Vec::new();
+        for start in 0..self.num_cols() {
+            if visited[start] || !is_degree_two(start) {
+                continue;
+            }
+            visited[start] = true;
+            let mut rows = self.iter_col(start).copied().collect::<Vec<_>>();
+            let mut cols = vec![start];
+            // Extend the chain in each direction. The left side is built in
+            // reverse order.
+            let mut closed = false;
+            let mut sides = [(vec![], vec![rows[0]]), (vec![], vec![rows[1]])];
+            for (side_cols, side_rows) in sides.iter_mut() {
+                let mut col = start;
+                let mut row = *side_rows.last().unwrap();
+                while let Some(next) = next_col(row, col) {
+                    if visited[next] {
+                        closed = next == start;
+                        break;
+                    }
+                    visited[next] = true;
+                    row = other_row(next, row);
+                    side_cols.push(next);
+                    side_rows.push(row);
+                    col = next;
+                }
+                if closed {
+                    break;
+                }
+            }
+            let [(left_cols, left_rows), (right_cols, right_rows)] = sides;
+            if closed {
+                // The left side has returned to the other row of the first
+                // column
+                rows = right_rows;
+                rows.extend_from_slice(&left_rows[..left_rows.len() - 1]);
+                cols.extend(left_cols);
+                chains.push(DegreeTwoChain { cols, rows });
+                continue;
+            }
+            rows = left_rows.into_iter().rev().chain(right_rows).collect();
+            cols = left_cols
+                .into_iter()
+                .rev()
+                .chain(cols)
+                .chain(right_cols)
+                .collect();
+            chains.push(DegreeTwoChain { cols, rows });
+        }
+        chains.sort_by_key(|chain| chain.cols.iter().copied().min());
+        chains
+    }
+
+    /// Reduces the matrix by eliminating low degree columns.
+    ///
+    /// A column of weight 1 is eliminated together with its row, since the
+    /// row only determines the value of the column. A column of weight 2 is
+    /// eliminated by merging its two rows into their sum, which absorbs
+    /// chains of degree-2 columns into a single row. Eliminations are repeated
+    /// while the options allow eliminating some column, and rows without
+    /// entries are removed.
+    ///
+    /// The reduced matrix defines the same code restricted to the remaining
+    /// columns, and the eliminated columns can be recovered with
+    /// [`Reduction::expand`]. Note that eliminating a column discards the
+    /// channel information about its bit, so the reduction is only lossless
+    /// for decoding when the eliminated columns are punctured (see
+    /// [`ReductionOptions::only_punctured`]). In general, eliminating all the
+    /// columns of weight 1 and 2 of a code with an accumulator structure
+    /// eliminates all its parity columns.
+    pub fn reduce(&self, options: &ReductionOptions) -> Reduction {
+        let punctured = self.cols_with_label(ColumnLabel::Punctured);
+        let eligible =
+            |col: usize| !options.only_punctured || punctured.binary_search(&col).is_ok();
+        let mut h = self.clone();
+        let mut kept = vec![true; self.num_cols()];
+        let mut eliminated = Vec::new();
+        let mut to_visit = (0..self.num_cols()).rev().collect::<Vec<_>>();
+        while let Some(col) = to_visit.pop() {
+            if !kept[col] || !eligible(col) {
+                continue;
+            }
+            let rows = h.iter_col(col).copied().collect::<Vec<_>>();
+            let (row, merged) = match rows[..] {
+                [row] if options.degree_one => (row, None),
+                [row, other] if options.degree_two => (row, Some(other)),
+                _ => continue,
+            };
+            let equation = h
+                .iter_row(row)
+                .copied()
+                .filter(|&c| c != col)
+                .collect::<Vec<_>>();
+            match merged {
+                None => {
+                    to_visit.extend(equation.iter().rev());
+                    h.clear_row(row);
+                }
+                Some(other) => {
+                    let other_cols = h.iter_row(other).copied().collect::<Vec<_>>();
+                    for &c in &other_cols {
+                        h.toggle(row, c);
+                    }
+                    h.clear_row(other);
+                    to_visit.extend(other_cols.iter().rev().filter(|&&c| c != col));
+                }
+            }
+            kept[col] = false;
+            eliminated.push(EliminatedCol { col, equation });
+        }
+        let rows = (0..h.num_rows())
+            .filter(|&row| h.row_weight(row) > 0)
+            .collect::<Vec<_>>();
+        let cols = (0..h.num_cols())
+            .filter(|&col| kept[col])
+            .collect::<Vec<_>>();
+        let h = h.submatrix(&rows, &cols);
+        Reduction {
+            rows,
+            cols,
+            eliminated,
+            h,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{encoder::Encoder, gf2::GF2};
+    use ndarray::Array1;
+    use num_traits::{One, Zero};
+
+    // Code with a staircase parity part
+    fn staircase() -> SparseMatrix {
+        let mut h = SparseMatrix::new(4, 8);
+        h.insert_col(0, [0, 2, 3].iter());
+        h.insert_col(1, [0, 1, 3].iter());
+        h.insert_col(2, [1, 2, 3].iter());
+        h.insert_col(3, [0, 1, 2, 3].iter());
+        for j in 0..4 {
+            h.insert(j, 4 + j);
+            if j + 1 < 4 {
+                h.insert(j + 1, 4 + j);
+            }
+        }
+        h
+    }
+
+    fn codewords(h: &SparseMatrix) -> Vec<Vec<u8>> {
+        let encoder = Encoder::from_h(h).unwrap();
+        (0..16)
+            .map(|x| {
+                let message = (0..4)
+                    .map(|j| {
+                        if (x >> j) & 1 == 1 {
+                            GF2::one()
+                        } else {
+                            GF2::zero()
+                        }
+                    })
+                    .collect::<Array1<_>>();
+                encoder
+                    .encode(&message)
+                    .iter()
+                    .map(|&b| u8::from(b == GF2::one()))
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn check_parity(h: &SparseMatrix, codeword: &[u8]) -> bool {
+        (0..h.num_rows()).all(|row| h.iter_row(row).fold(0, |acc, &c| acc ^ codeword[c]) == 0)
+    }
+
+    #[test]
+    fn chains() {
+        let h = staircase();
+        let chains = h.degree_two_chains();
+        assert_eq!(
+            chains,
+            vec![DegreeTwoChain {
+                cols: vec![4, 5, 6],
+                rows: vec![0, 1, 2, 3]
+            }]
+        );
+        assert!(!chains[0].is_closed());
+        let mut cycle = SparseMatrix::new(3, 3);
+        for j in 0..3 {
+            cycle.insert_col(j, [j, (j + 1) % 3].iter());
+        }
+        let chains = cycle.degree_two_chains();
+        assert_eq!(chains.len(), 1);
+        assert!(chains[0].is_closed());
+        assert_eq!(chains[0].cols, vec![0, 2, 1]);
+        assert_eq!(chains[0].rows, vec![1, 0, 2]);
+    }
+
+    #[test]
+    fn reduce() {
+        let h = staircase();
+        let codewords = codewords(&h);
+        for options in [
+            ReductionOptions::default(),
+            ReductionOptions {
+                degree_one: false,
+                ..ReductionOptions::default()
+            },
+            ReductionOptions {
+                degree_two: false,
+                ..ReductionOptions::default()
+            },
+        ] {
+            let reduction = h.reduce(&options);
+            assert_eq!(reduction.h.validate(), Ok(()));
+            assert_eq!(
+                reduction.cols.len() + reduction.eliminated.len(),
+                h.num_cols()
+            );
+            for codeword in &codewords {
+                let restricted = reduction.restrict(codeword);
+                assert!(check_parity(&reduction.h, &restricted));
+                assert_eq!(&reduction.expand(&restricted), codeword);
+            }
+        }
+        // The staircase is absorbed, and column 3 gets weight 2 after merging
+        // rows 0 and 1, so it is also eliminated
+        let reduction = h.reduce(&ReductionOptions {
+            degree_one: false,
+            ..ReductionOptions::default()
+        });
+        assert_eq!(reduction.cols, vec![0, 1, 2, 6, 7]);
+        assert_eq!(reduction.rows, vec![2]);
+    }
+
+    #[test]
+    fn reduce_only_punctured() {
+        let mut h = staircase();
+        h.label_cols([7].iter(), ColumnLabel::Punctured);
+        let options = ReductionOptions {
+            only_punctured: true,
+            ..ReductionOptions::default()
+        };
+        let reduction = h.reduce(&options);
+        assert_eq!(reduction.cols, vec![0, 1, 2, 3, 4, 5, 6]);
+        assert_eq!(reduction.rows, vec![0, 1, 2]);
+        let metadata = reduction.h.column_metadata().unwrap();
+        assert_eq!(metadata[6].original_index, 6);
+    }
+}
@@ -0,0 +1,98 @@
+use crate::sparse::{ColumnLabel, ColumnMetadata, SparseMatrix};
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+
+// The matrix is serialized as its dimensions and the list of the (row, column)
+// positions of its ones, which is more compact and easier to read than the
+// row and column lists of the in-memory representation.
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "SparseMatrix")]
+struct Triplets {
+    num_rows: usize,
+    num_cols: usize,
+    entries: Vec<(usize, usize)>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    metadata: Option<Vec<ColumnMetadata>>,
+}
+
+impl Serialize for SparseMatrix {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Triplets {
+            num_rows: self.num_rows(),
+            num_cols: self.num_cols(),
+            entries: self.iter_all().collect(),
+            metadata: self.metadata.clone(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for SparseMatrix {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<SparseMatrix, D::Error> {
+        let triplets = Triplets::deserialize(deserializer)?;
+        let mut h = SparseMatrix::new(triplets.num_rows, triplets.num_cols);
+        for (row, col) in triplets.entries {
+            if row >= h.num_rows() || col >= h.num_cols() {
+                return Err(D::Error::custom(format!(
+                    "entry ({row}, {col}) outside of the {} x {} matrix",
+                    h.num_rows(),
+                    h.num_cols()
+                )));
+            }
+            h.insert(row, col);
+        }
+        if let Some(metadata) = &triplets.metadata {
+            if metadata.len() != h.num_cols() {
+                return Err(D::Error::custom(
+                    "column metadata length does not match number of columns",
+                ));
+            }
+        }
+        h.metadata = triplets.metadata;
+        Ok(h)
+    }
+}
+
+impl Serialize for ColumnLabel {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for ColumnLabel {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<ColumnLabel, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json() {
+        let mut h = SparseMatrix::new(3, 4);
+        h.insert_row(0, [0, 2].iter());
+        h.insert_row(2, [1, 2, 3].iter());
+        let json = serde_json::to_string(&h).unwrap();
+        assert_eq!(
+            json,
+            r#"{"num_rows":3,"num_cols":4,"entries":[[0,0],[0,2],[2,1],[2,2],[2,3]]}"#
+        );
+        let h2: SparseMatrix = serde_json::from_str(&json).unwrap();
+        assert_eq!(h2, h);
+
+        h.init_column_metadata();
+        h.label_cols([3].iter(), ColumnLabel::Punctured);
+        let json = serde_json::to_string(&h).unwrap();
+        assert!(json.contains(r#""labels":["punctured"]"#));
+        let h2: SparseMatrix = serde_json::from_str(&json).unwrap();
+        assert_eq!(h2.column_metadata(), h.column_metadata());
+
+        assert!(serde_json::from_str::<SparseMatrix>(
+            r#"{"num_rows":3,"num_cols":4,"entries":[[3,0]]}"#
+        )
+        .is_err());
+    }
+}
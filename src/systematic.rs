@@ -4,9 +4,18 @@
 //! convert a full-rank parity check matrix into one that supports systematic
 //! encoding using the first variables (as done by the systematic encoder in the
 //! [`encoder`](crate::encoder) module) by permuting the columns of the parity
-//! check matrix.
+//! check matrix. The permutation can be obtained with
+//! [`parity_to_systematic_with_permutation`].
+//!
+//! If the parity check matrix keeps [column
+//! metadata](crate::sparse::ColumnMetadata), the metadata is permuted together
+//! with the columns, and the columns are labelled as systematic or parity.
 
-use crate::{gf2::GF2, linalg, sparse::SparseMatrix};
+use crate::{
+    gf2::GF2,
+    linalg,
+    sparse::{ColumnLabel, SparseMatrix},
+};
 use ndarray::Array2;
 use num_traits::{One, Zero};
 use thiserror::Error;
@@ -29,6 +38,19 @@ pub enum Error {
 /// columns of `h` in such a way that the square submatrix formed by the last
 /// columns is invertible.
 pub fn parity_to_systematic(h: &SparseMatrix) -> Result<SparseMatrix, Error> {
+    parity_to_systematic_with_permutation(h).map(|(h_new, _)| h_new)
+}
+
+/// Permutes the columns of the parity check matrix to obtain a parity check
+/// matrix that supports systematic encoding using the first variables, and
+/// returns the permutation used.
+///
+/// This function works as [`parity_to_systematic`], but it also returns the
+/// column permutation. The element `j` of the permutation is the column of `h`
+/// that has been placed in the column `j` of the returned matrix.
+pub fn parity_to_systematic_with_permutation(
+    h: &SparseMatrix,
+) -> Result<(SparseMatrix, Vec<usize>), Error> {
     let n = h.num_rows();
     let m = h.num_cols();
     if n > m {
@@ -48,6 +70,8 @@ pub fn parity_to_systematic(h: &SparseMatrix) -> Result<SparseMatrix, Error> {
     // write point for columns that do not "go down" in the row echelon form
     let mut k = 0;
     let mut h_new = SparseMatrix::new(n, m);
+    // column of h placed at each column of h_new
+    let mut permutation = vec![0; m];
     let mut j0 = 0;
     for j in 0..n {
         assert!(k < m - n);
@@ -58,6 +82,7 @@ pub fn parity_to_systematic(h: &SparseMatrix) -> Result<SparseMatrix, Error> {
                 for &u in h.iter_col(s) {
                     h_new.insert(u, k);
                 }
+                permutation[k] = s;
                 k += 1;
             } else {
                 // Column goes down on row echelon form. Move to its appropriate
@@ -66,6 +91,7 @@ pub fn parity_to_systematic(h: &SparseMatrix) -> Result<SparseMatrix, Error> {
                 for &u in h.iter_col(s) {
                     h_new.insert(u, col);
                 }
+                permutation[col] = s;
                 found = true;
                 j0 = s + 1;
                 break;
@@ -79,9 +105,19 @@ pub fn parity_to_systematic(h: &SparseMatrix) -> Result<SparseMatrix, Error> {
         for &u in h.iter_col(j) {
             h_new.insert(u, k);
         }
+        permutation[k] = j;
         k += 1;
     }
-    Ok(h_new)
+    if let Some(metadata) = h.column_metadata() {
+        h_new.set_column_metadata(Some(
+            permutation.iter().map(|&s| metadata[s].clone()).collect(),
+        ));
+        h_new.remove_label(ColumnLabel::Systematic);
+        h_new.remove_label(ColumnLabel::Parity);
+        h_new.label_cols(0..m - n, ColumnLabel::Systematic);
+        h_new.label_cols(m - n..m, ColumnLabel::Parity);
+    }
+    Ok((h_new, permutation))
 }
 
 #[cfg(test)]
@@ -111,5 +147,25 @@ mod test {
         expected.insert_col(4, [1].into_iter());
         expected.insert_col(5, [0, 2].into_iter());
         assert_eq!(parity_to_systematic(&h).unwrap(), expected);
+        let (h_sys, permutation) = parity_to_systematic_with_permutation(&h).unwrap();
+        assert_eq!(h_sys, expected);
+        assert_eq!(permutation, [2, 3, 5, 6, 7, 8, 0, 1, 4]);
+
+        h.init_column_metadata();
+        h.label_cols([2].iter(), ColumnLabel::Punctured);
+        let h_sys = parity_to_systematic(&h).unwrap();
+        let original_indices = h_sys
+            .column_metadata()
+            .unwrap()
+            .iter()
+            .map(|c| c.original_index)
+            .collect::<Vec<_>>();
+        assert_eq!(original_indices, [2, 3, 5, 6, 7, 8, 0, 1, 4]);
+        assert_eq!(h_sys.cols_with_label(ColumnLabel::Punctured), [0]);
+        assert_eq!(h_sys.cols_with_label(ColumnLabel::Parity), [6, 7, 8]);
+        assert_eq!(
+            h_sys.cols_with_label(ColumnLabel::Systematic),
+            [0, 1, 2, 3, 4, 5]
+        );
     }
 }